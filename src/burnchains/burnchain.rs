@@ -71,7 +71,6 @@ use chainstate::burn::operations::{
 };
 use chainstate::burn::db::sortdb::{
     SortitionDB, SortitionHandleTx, SortitionHandleConn,
-    PoxId, PoxDB
 };
 
 use chainstate::stacks::StacksAddress;
@@ -321,10 +320,46 @@ impl Burnchain {
             consensus_hash_lifetime: params.consensus_hash_lifetime,
             stable_confirmations: params.stable_confirmations,
             first_block_height: params.first_block_height,
-            first_block_hash: params.first_block_hash.clone()
+            first_block_hash: params.first_block_hash.clone(),
+            pox_constants: params.pox_constants.clone(),
         })
     }
 
+    /// Is the given burnchain block height the first block of a reward cycle?
+    /// The first burnchain block always starts reward cycle 0.
+    pub fn is_reward_cycle_start(&self, burn_height: u64) -> bool {
+        if burn_height < self.first_block_height {
+            return false;
+        }
+        (burn_height - self.first_block_height) % (self.pox_constants.reward_cycle_length as u64) == 0
+    }
+
+    /// Which reward cycle does the given burnchain block height fall into?
+    /// Returns None if the height is before the first burnchain block.
+    pub fn block_height_to_reward_cycle(&self, burn_height: u64) -> Option<u64> {
+        if burn_height < self.first_block_height {
+            return None;
+        }
+        Some((burn_height - self.first_block_height) / (self.pox_constants.reward_cycle_length as u64))
+    }
+
+    /// Get the burnchain block height of the first block in the given reward cycle.
+    pub fn reward_cycle_to_block_height(&self, reward_cycle: u64) -> u64 {
+        self.first_block_height + reward_cycle * (self.pox_constants.reward_cycle_length as u64)
+    }
+
+    /// Is the given burnchain block height in the prepare phase of the _next_ reward cycle?
+    /// The prepare phase is the last `prepare_length` blocks of each reward cycle.
+    pub fn is_in_prepare_phase(&self, burn_height: u64) -> bool {
+        if burn_height < self.first_block_height {
+            return false;
+        }
+        let cycle_length = self.pox_constants.reward_cycle_length as u64;
+        let prepare_length = self.pox_constants.prepare_length as u64;
+        let effective_height = burn_height - self.first_block_height;
+        effective_height % cycle_length >= cycle_length - prepare_length
+    }
+
     #[cfg(test)]
     pub fn default_unittest(first_block_height: u64, first_block_hash: &BurnchainHeaderHash) -> Burnchain {
         let mut ret = Burnchain::new(&"/unit-tests".to_string(), &"bitcoin".to_string(), &"mainnet".to_string()).unwrap();
@@ -592,10 +627,58 @@ impl Burnchain {
         let header = block.header();
         let blockstack_txs = burnchain_db.store_new_burnchain_block(&block)?;
 
-        let pox_id = PoxId::stubbed();
-        let pox_db = PoxDB::stubbed();
+        // if a missing anchor block has since arrived, re-evaluate the PoX fork it starts.
+        Burnchain::process_pox_anchors(db, burnchain_db, burnchain)?;
+
+        // the parent may have been evaluated on a PoX fork that is no longer valid
+        Burnchain::process_missing_ancestors(db, burnchain_db, burnchain, &header.parent_block_hash)?;
+
+        db.evaluate_sortition(&header, blockstack_txs, burnchain)
+    }
+
+    /// Re-evaluate the canonical burnchain fork from the start of each reward cycle whose anchor
+    /// block was not known when the reward cycle began, but has been accepted since.
+    /// Returns the new canonical burn chain tip, if any sortitions were re-evaluated.
+    pub fn process_pox_anchors(db: &mut SortitionDB, burnchain_db: &BurnchainDB, burnchain: &Burnchain) -> Result<Option<BlockSnapshot>, burnchain_error> {
+        let mut new_tip = None;
+        while let Some(cycle_start) = db.find_newly_known_pox_anchor(burnchain)? {
+            let canonical_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn())?;
+            info!("Anchor block for the reward cycle starting at {} ({}) is now known; re-evaluating burnchain blocks {}-{}",
+                  cycle_start.block_height, &cycle_start.burn_header_hash, cycle_start.block_height, canonical_tip.block_height);
+
+            db.invalidate_pox_fork(&cycle_start)?;
+            new_tip = Burnchain::process_missing_ancestors(db, burnchain_db, burnchain, &canonical_tip.burn_header_hash)?;
+        }
+        Ok(new_tip)
+    }
+
+    /// Evaluate the given burnchain block, and each of its ancestors that does not have a
+    /// PoX-valid sortition, in order by block height.  These blocks must already be stored in the
+    /// burnchain DB.
+    /// Returns the snapshot for the given block, if it had to be evaluated.
+    fn process_missing_ancestors(db: &mut SortitionDB, burnchain_db: &BurnchainDB, burnchain: &Burnchain, burn_header_hash: &BurnchainHeaderHash) -> Result<Option<BlockSnapshot>, burnchain_error> {
+        let mut missing_blocks = vec![];
+        let mut cursor = burn_header_hash.clone();
+        while !db.is_sortition_processed(&cursor)? {
+            let (header, blockstack_txs) = burnchain_db.get_burnchain_block(&cursor)
+                .map_err(|e| match e {
+                    burnchain_error::UnknownBlock(_) => {
+                        warn!("Unknown block {:?}", &cursor);
+                        burnchain_error::MissingParentBlock
+                    },
+                    e => e
+                })?;
+            cursor = header.parent_block_hash.clone();
+            missing_blocks.push((header, blockstack_txs));
+        }
 
-        db.evaluate_sortition(&header, blockstack_txs, burnchain, &pox_id, &pox_db)
+        let mut last_snapshot = None;
+        for (header, blockstack_txs) in missing_blocks.into_iter().rev() {
+            debug!("Re-evaluate block {} {}", header.block_height, &header.block_hash);
+            let (snapshot, _) = db.evaluate_sortition(&header, blockstack_txs, burnchain)?;
+            last_snapshot = Some(snapshot);
+        }
+        Ok(last_snapshot)
     }
 
     /// Determine if there has been a chain reorg, given our current canonical burnchain tip.
@@ -651,16 +734,7 @@ impl Burnchain {
                 e
             })?;
 
-        let pox_id = PoxId::stubbed();
-        let pox_db = PoxDB::stubbed();
-
-        let last_snapshot_processed = match SortitionDB::get_last_snapshot(&sortdb.conn, &pox_id, &pox_db)? {
-            Some(snapshot) => snapshot,
-            None => {
-                warn!("No snapshot processed yet");
-                SortitionDB::get_first_block_snapshot(&sortdb.conn)?
-            }
-        };
+        let last_snapshot_processed = SortitionDB::get_canonical_burn_chain_tip(&sortdb.conn)?;
 
         // does the bunchain db have more blocks than the sortition db has processed?
         // PoX TODO: this check shouldn't happen here. instead, this "sync_with_indexer" function
@@ -826,6 +900,7 @@ pub mod tests {
     use burnchains::Address;
     use burnchains::PublicKey;
     use burnchains::Burnchain;
+    use burnchains::PoxConstants;
     use burnchains::BurnchainSigner;
     use burnchains::BurnchainBlock;
     use burnchains::bitcoin::keys::BitcoinPublicKey;
@@ -890,7 +965,8 @@ pub mod tests {
             consensus_hash_lifetime: 24,
             stable_confirmations: 7,
             first_block_height: first_block_height,
-            first_block_hash: first_burn_hash.clone()
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000123").unwrap();        
         let block_121_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000012").unwrap();
@@ -1036,6 +1112,8 @@ pub mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -1060,6 +1138,8 @@ pub mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -1084,6 +1164,8 @@ pub mod tests {
             memo: vec![0x80],
 
             burn_fee: 23456,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("0283d603abdd2392646dbdd0dc80beb39c25bfab96a8a921ea5e7517ce533f8cd5").unwrap(),
@@ -1106,14 +1188,14 @@ pub mod tests {
             ConsensusHash::from_hex("0000000000000000000000000000000000000000").unwrap(),
         ];
         let mut block_121_snapshot = BlockSnapshot {
-            pox_id: PoxId::stubbed(),
+            pox_id: PoxId::initial(),
             block_height: 121,
             burn_header_hash: block_121_hash.clone(),
             sortition_id: SortitionId(block_121_hash.0.clone()),
             burn_header_timestamp: 121,
             parent_burn_header_hash: first_burn_hash.clone(),
             ops_hash: block_opshash_121.clone(),
-            consensus_hash: ConsensusHash::from_ops(&block_121_hash, &block_opshash_121, 0, &block_prev_chs_121),
+            consensus_hash: ConsensusHash::from_ops(&block_121_hash, &block_opshash_121, &PoxId::initial(), 0, &block_prev_chs_121),
             total_burn: 0,
             sortition: false,
             sortition_hash: SortitionHash::initial()
@@ -1139,14 +1221,14 @@ pub mod tests {
             ConsensusHash::from_hex("0000000000000000000000000000000000000000").unwrap(),
         ];
         let mut block_122_snapshot = BlockSnapshot {
-            pox_id: PoxId::stubbed(),
+            pox_id: PoxId::initial(),
             block_height: 122,
             burn_header_hash: block_122_hash.clone(),
             sortition_id: SortitionId(block_122_hash.0.clone()),
             burn_header_timestamp: 122,
             parent_burn_header_hash: block_121_hash.clone(),
             ops_hash: block_opshash_122.clone(),
-            consensus_hash: ConsensusHash::from_ops(&block_122_hash, &block_opshash_122, 0, &block_prev_chs_122),
+            consensus_hash: ConsensusHash::from_ops(&block_122_hash, &block_opshash_122, &PoxId::initial(), 0, &block_prev_chs_122),
             total_burn: 0,
             sortition: false,
             sortition_hash: SortitionHash::initial()
@@ -1178,14 +1260,14 @@ pub mod tests {
             block_121_snapshot.consensus_hash.clone(),
         ];
         let mut block_123_snapshot = BlockSnapshot {
-            pox_id: PoxId::stubbed(),
+            pox_id: PoxId::initial(),
             block_height: 123,
             burn_header_hash: block_123_hash.clone(),
             sortition_id: SortitionId(block_123_hash.0.clone()),
             burn_header_timestamp: 123,
             parent_burn_header_hash: block_122_hash.clone(),
             ops_hash: block_opshash_123.clone(),
-            consensus_hash: ConsensusHash::from_ops(&block_123_hash, &block_opshash_123, 0, &block_prev_chs_123),        // user burns not included, so zero burns this block
+            consensus_hash: ConsensusHash::from_ops(&block_123_hash, &block_opshash_123, &PoxId::initial(), 0, &block_prev_chs_123),        // user burns not included, so zero burns this block
             total_burn: 0,
             sortition: false,
             sortition_hash: SortitionHash::initial()
@@ -1326,14 +1408,14 @@ pub mod tests {
             let next_sortition = block_ops_124.len() > 0 && burn_total > 0;
             
             let mut block_124_snapshot = BlockSnapshot {
-                pox_id: PoxId::stubbed(),
+                pox_id: PoxId::initial(),
                 block_height: 124,
                 burn_header_hash: block_124_hash.clone(),
                 sortition_id: SortitionId(block_124_hash.0.clone()),
                 burn_header_timestamp: 124,
                 parent_burn_header_hash: block_123_snapshot.burn_header_hash.clone(),
                 ops_hash: block_opshash_124.clone(),
-                consensus_hash: ConsensusHash::from_ops(&block_124_hash, &block_opshash_124, burn_total, &block_prev_chs_124),
+                consensus_hash: ConsensusHash::from_ops(&block_124_hash, &block_opshash_124, &PoxId::initial(), burn_total, &block_prev_chs_124),
                 total_burn: burn_total,
                 sortition: next_sortition,
                 sortition_hash: SortitionHash::initial()
@@ -1411,6 +1493,8 @@ pub mod tests {
                 memo: vec![i],
 
                 burn_fee: (i + 1) as u64,
+                commit_outs: vec![],
+
                 input: BurnchainSigner {
                     public_keys: vec![
                         StacksPublicKey::from_hex("02113c274c05ed0b7f9d08f41ca674b22e42188408caaff82a350b024442de353c").unwrap(),
@@ -1437,6 +1521,8 @@ pub mod tests {
             memo: vec![0x00],
 
             burn_fee: 256,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02113c274c05ed0b7f9d08f41ca674b22e42188408caaff82a350b024442de353c").unwrap(),
@@ -1524,7 +1610,8 @@ pub mod tests {
             consensus_hash_lifetime: 24,
             stable_confirmations: 7,
             first_block_height: first_block_height,
-            first_block_hash: first_burn_hash.clone()
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut leader_private_keys = vec![];
@@ -1582,6 +1669,8 @@ pub mod tests {
                     memo: vec![i],
 
                     burn_fee: i as u64,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex(&leader_bitcoin_public_keys[(i-1) as usize].clone()).unwrap(),
@@ -1654,6 +1743,31 @@ pub mod tests {
     // TODO; test that all but the first of the block commits committing to the same key are
     // dropped
     // TODO: test that we can get the histories of all Stacks block headers from different fork segments
+    #[test]
+    fn test_reward_cycle_boundaries() {
+        let first_block_hash = BurnchainHeaderHash([0u8; 32]);
+        let mut burnchain = Burnchain::default_unittest(100, &first_block_hash);
+        burnchain.pox_constants = PoxConstants::new(10, 3, 2);
+
+        assert_eq!(burnchain.block_height_to_reward_cycle(99), None);
+        assert_eq!(burnchain.block_height_to_reward_cycle(100), Some(0));
+        assert_eq!(burnchain.block_height_to_reward_cycle(109), Some(0));
+        assert_eq!(burnchain.block_height_to_reward_cycle(110), Some(1));
+        assert_eq!(burnchain.reward_cycle_to_block_height(0), 100);
+        assert_eq!(burnchain.reward_cycle_to_block_height(2), 120);
+
+        assert!(!burnchain.is_reward_cycle_start(99));
+        assert!(burnchain.is_reward_cycle_start(100));
+        assert!(!burnchain.is_reward_cycle_start(105));
+        assert!(burnchain.is_reward_cycle_start(120));
+
+        assert!(!burnchain.is_in_prepare_phase(99));
+        assert!(!burnchain.is_in_prepare_phase(106));
+        assert!(burnchain.is_in_prepare_phase(107));
+        assert!(burnchain.is_in_prepare_phase(109));
+        assert!(!burnchain.is_in_prepare_phase(110));
+    }
+
     // TODO: test top-level sync with a burn chain reorg
     // -- make sure the chain can switch from fork A to fork B back to fork A safely.
     // TODO: test that only relevant user burns get stored in a burn distribution, and that they're
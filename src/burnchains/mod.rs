@@ -63,7 +63,6 @@ use chainstate::burn::ConsensusHash;
 use chainstate::burn::operations::Error as op_error;
 use chainstate::burn::operations::BlockstackOperationType;
 use chainstate::burn::operations::LeaderKeyRegisterOp;
-use chainstate::burn::db::sortdb::PoxId;
 use chainstate::burn::distribution::BurnSamplePoint;
 
 use address::AddressHashMode;
@@ -113,6 +112,44 @@ pub struct BurnchainParameters {
     first_block_hash: BurnchainHeaderHash,
     stable_confirmations: u32,
     consensus_hash_lifetime: u32,
+    pox_constants: PoxConstants,
+}
+
+/// Proof-of-Transfer reward cycle parameters.
+/// A reward cycle is `reward_cycle_length` burnchain blocks long, and its
+/// last `prepare_length` blocks make up the prepare phase for the _next_
+/// reward cycle, during which miners confirm the next cycle's anchor block.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PoxConstants {
+    /// number of burnchain blocks in a reward cycle
+    pub reward_cycle_length: u32,
+    /// number of burnchain blocks at the end of a reward cycle used to choose the next anchor block
+    pub prepare_length: u32,
+    /// number of prepare-phase sortitions that must descend from a block for it to be the anchor block
+    pub anchor_threshold: u32,
+}
+
+impl PoxConstants {
+    pub fn new(reward_cycle_length: u32, prepare_length: u32, anchor_threshold: u32) -> PoxConstants {
+        assert!(prepare_length > 0);
+        assert!(prepare_length < reward_cycle_length);
+        assert!(anchor_threshold > prepare_length / 2, "BUG: anchor threshold must be a majority of the prepare phase");
+        assert!(anchor_threshold <= prepare_length);
+
+        PoxConstants {
+            reward_cycle_length,
+            prepare_length,
+            anchor_threshold
+        }
+    }
+
+    pub fn mainnet_default() -> PoxConstants {
+        PoxConstants::new(2100, 100, 80)
+    }
+
+    pub fn testnet_default() -> PoxConstants {
+        PoxConstants::new(150, 25, 20)
+    }
 }
 
 impl BurnchainParameters {
@@ -125,6 +162,7 @@ impl BurnchainParameters {
             first_block_hash: FIRST_BURNCHAIN_BLOCK_HASH.clone(),
             stable_confirmations: 7,
             consensus_hash_lifetime: 24,
+            pox_constants: PoxConstants::mainnet_default(),
         }
     }
 
@@ -137,6 +175,7 @@ impl BurnchainParameters {
             first_block_hash: FIRST_BURNCHAIN_BLOCK_HASH_TESTNET.clone(),
             stable_confirmations: 7,
             consensus_hash_lifetime: 24,
+            pox_constants: PoxConstants::testnet_default(),
         }
     }
 
@@ -148,7 +187,8 @@ impl BurnchainParameters {
             first_block_height: BITCOIN_FIRST_BLOCK_REGTEST,
             first_block_hash: FIRST_BURNCHAIN_BLOCK_HASH_REGTEST.clone(),
             stable_confirmations: 1,
-            consensus_hash_lifetime: 24
+            consensus_hash_lifetime: 24,
+            pox_constants: PoxConstants::testnet_default(),
         }
     }
 
@@ -268,7 +308,8 @@ pub struct Burnchain {
     pub consensus_hash_lifetime: u32,
     pub stable_confirmations: u32,
     pub first_block_height: u64,
-    pub first_block_hash: BurnchainHeaderHash
+    pub first_block_hash: BurnchainHeaderHash,
+    pub pox_constants: PoxConstants,
 }
 
 /// Structure for encoding our view of the network 
@@ -319,7 +360,6 @@ pub enum Error {
     /// Try again error
     TrySyncAgain,
     UnknownBlock(BurnchainHeaderHash),
-    NonCanonicalPoxId(PoxId, PoxId),
 }
 
 impl fmt::Display for Error {
@@ -338,8 +378,6 @@ impl fmt::Display for Error {
             Error::OpError(ref e) => fmt::Display::fmt(e, f),
             Error::TrySyncAgain => write!(f, "Try synchronizing again"),
             Error::UnknownBlock(block) => write!(f, "Unknown burnchain block {}", block),
-            Error::NonCanonicalPoxId(parent, child) => write!(f, "{} is not a descendant of the canonical parent PoXId: {}",
-                                                              parent, child),
         }
     }
 }
@@ -360,7 +398,6 @@ impl error::Error for Error {
             Error::OpError(ref e) => Some(e),
             Error::TrySyncAgain => None,
             Error::UnknownBlock(_) => None,
-            Error::NonCanonicalPoxId(_, _) => None,
         }
     }
 }
@@ -427,6 +464,7 @@ pub mod test {
 
     use chainstate::burn::*;
     use chainstate::burn::operations::*;
+    use chainstate::burn::operations::leader_block_commit::OUTPUTS_PER_COMMIT;
     use chainstate::stacks::*;
 
     use burnchains::*;
@@ -756,7 +794,7 @@ pub mod test {
             
             let last_snapshot = match fork_snapshot {
                 Some(sn) => sn.clone(),
-                None => SortitionDB::get_canonical_burn_chain_tip_stubbed(ic).unwrap()
+                None => SortitionDB::get_canonical_burn_chain_tip(ic).unwrap()
             };
 
             let last_snapshot_with_sortition = match parent_block_snapshot {
@@ -828,7 +866,10 @@ pub mod test {
             test_debug!("Process block {} {}", block.block_height(), &block.block_hash());
 
             let header = block.header();
-            let sort_id = SortitionId::stubbed(&header.parent_block_hash);
+            let sort_id = SortitionDB::get_block_snapshot_by_burn_header_hash(db.conn(), &header.parent_block_hash)
+                .unwrap()
+                .expect("FATAL: no PoX-valid sortition for parent block")
+                .sortition_id;
             let mut sortition_db_handle = SortitionHandleTx::begin(db, &sort_id).unwrap();
 
             let parent_snapshot = sortition_db_handle.as_conn().get_block_snapshot(&header.parent_block_hash)
@@ -870,7 +911,7 @@ pub mod test {
 
         pub fn get_tip(&mut self, ic: &SortitionDBConn) -> BlockSnapshot {
             test_debug!("Get tip snapshot at {}", &self.tip_header_hash);
-            SortitionDB::get_block_snapshot(ic, &SortitionId(self.tip_header_hash.0.clone()))
+            SortitionDB::get_block_snapshot_by_burn_header_hash(ic, &self.tip_header_hash)
                 .unwrap().unwrap()
         }
 
@@ -1203,4 +1244,186 @@ pub mod test {
             prev_keys_2.append(&mut next_prev_keys_2);
         }
     }
+
+    #[test]
+    fn mine_pox_anchor_block() {
+        let mut node = TestBurnchainNode::new();
+        node.burnchain.pox_constants = PoxConstants::new(10, 3, 2);
+
+        let mut miner_factory = TestMinerFactory::new();
+        let mut miner = miner_factory.next_miner(&node.burnchain, 1, 1, AddressHashMode::SerializeP2PKH);
+
+        let first_snapshot = SortitionDB::get_first_block_snapshot(node.sortdb.conn()).unwrap();
+        let mut fork = TestBurnchainFork::new(first_snapshot.block_height, &first_snapshot.burn_header_hash, &first_snapshot.index_root, 0);
+
+        // mine one chain of Stacks blocks across the first reward cycle boundary (height 110)
+        let mut prev_key : Option<LeaderKeyRegisterOp> = None;
+        let mut last_sortition : Option<BlockSnapshot> = None;
+        for i in 0..15 {
+            let mut block = {
+                let ic = node.sortdb.index_conn();
+                fork.next_block(&ic)
+            };
+
+            if let Some(ref key) = prev_key {
+                let ic = node.sortdb.index_conn();
+                let hash = BlockHeaderHash([(i + 1) as u8; 32]);
+                block.add_leader_block_commit(&ic, &mut miner, &hash, 1000, key, None, last_sortition.as_ref());
+            }
+            prev_key = Some(block.add_leader_key_register(&mut miner));
+
+            fork.append_block(block);
+            let tip = node.mine_fork(&mut fork);
+            if tip.sortition {
+                last_sortition = Some(tip);
+            }
+        }
+
+        let tip = SortitionDB::get_canonical_burn_chain_tip(node.sortdb.conn()).unwrap();
+        assert_eq!(tip.block_height, 115);
+
+        // the prepare phase (107-109) confirmed the block committed at height 106, but nobody has it
+        assert_eq!(tip.pox_id, PoxId::from_bit_string("10").unwrap());
+
+        let (anchor_snapshot, cycle_start) = {
+            let ic = node.sortdb.index_handle(&tip.sortition_id);
+            (ic.get_block_snapshot_by_height(106).unwrap().unwrap(),
+             ic.get_block_snapshot_by_height(110).unwrap().unwrap())
+        };
+        assert_eq!(anchor_snapshot.winning_stacks_block_hash, BlockHeaderHash([6u8; 32]));
+        assert_eq!(SortitionDB::get_pox_anchor(node.sortdb.conn(), &cycle_start.sortition_id).unwrap(),
+                   Some((anchor_snapshot.burn_header_hash.clone(), anchor_snapshot.winning_stacks_block_hash.clone())));
+        assert!(node.sortdb.find_newly_known_pox_anchor(&node.burnchain).unwrap().is_none());
+
+        // the anchor block arrives
+        {
+            let mut tx = node.sortdb.tx_begin().unwrap();
+            tx.set_stacks_block_accepted(&anchor_snapshot.burn_header_hash, &BlockHeaderHash([5u8; 32]), &anchor_snapshot.winning_stacks_block_hash, 5).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let newly_known = node.sortdb.find_newly_known_pox_anchor(&node.burnchain).unwrap().unwrap();
+        assert_eq!(newly_known, cycle_start);
+
+        node.sortdb.invalidate_pox_fork(&newly_known).unwrap();
+
+        let parent = SortitionDB::get_canonical_burn_chain_tip(node.sortdb.conn()).unwrap();
+        assert_eq!(parent.block_height, 109);
+        assert!(SortitionDB::get_block_snapshot_by_burn_header_hash(node.sortdb.conn(), &cycle_start.burn_header_hash).unwrap().is_none());
+
+        // re-evaluate the burnchain blocks in the reward cycle
+        let mut parent = parent;
+        for block in fork.blocks[9..].iter() {
+            let mut block = block.clone();
+            block.patch_from_chain_tip(&parent);
+            parent = block.mine(&mut node.sortdb, &node.burnchain);
+        }
+
+        let new_tip = SortitionDB::get_canonical_burn_chain_tip(node.sortdb.conn()).unwrap();
+        assert_eq!(new_tip, parent);
+        assert_eq!(new_tip.burn_header_hash, tip.burn_header_hash);
+        assert_eq!(new_tip.winning_stacks_block_hash, tip.winning_stacks_block_hash);
+        assert_eq!(new_tip.pox_id, PoxId::from_bit_string("11").unwrap());
+        assert!(new_tip.sortition_id != tip.sortition_id);
+        assert!(new_tip.consensus_hash != tip.consensus_hash);
+
+        // the old PoX fork is still stored, but no longer canonical
+        assert_eq!(SortitionDB::get_block_snapshot(node.sortdb.conn(), &tip.sortition_id).unwrap().unwrap().pox_id, tip.pox_id);
+        assert!(node.sortdb.find_newly_known_pox_anchor(&node.burnchain).unwrap().is_none());
+    }
+
+    #[test]
+    fn mine_block_commits_to_pox_reward_set() {
+        let mut node = TestBurnchainNode::new();
+        node.burnchain.pox_constants = PoxConstants::new(10, 3, 2);
+
+        let mut miner_factory = TestMinerFactory::new();
+        let mut miner = miner_factory.next_miner(&node.burnchain, 1, 1, AddressHashMode::SerializeP2PKH);
+
+        let first_snapshot = SortitionDB::get_first_block_snapshot(node.sortdb.conn()).unwrap();
+        let mut fork = TestBurnchainFork::new(first_snapshot.block_height, &first_snapshot.burn_header_hash, &first_snapshot.index_root, 0);
+
+        // burning commits across the first reward cycle boundary (height 110)
+        let mut prev_key : Option<LeaderKeyRegisterOp> = None;
+        let mut last_sortition : Option<BlockSnapshot> = None;
+        for i in 0..15 {
+            let mut block = {
+                let ic = node.sortdb.index_conn();
+                fork.next_block(&ic)
+            };
+
+            if let Some(ref key) = prev_key {
+                let ic = node.sortdb.index_conn();
+                let hash = BlockHeaderHash([(i + 1) as u8; 32]);
+                block.add_leader_block_commit(&ic, &mut miner, &hash, 1000, key, None, last_sortition.as_ref());
+            }
+            prev_key = Some(block.add_leader_key_register(&mut miner));
+
+            fork.append_block(block);
+            let tip = node.mine_fork(&mut fork);
+            if tip.sortition {
+                last_sortition = Some(tip);
+            }
+        }
+
+        let tip = SortitionDB::get_canonical_burn_chain_tip(node.sortdb.conn()).unwrap();
+        let anchor_snapshot = node.sortdb.index_handle(&tip.sortition_id).get_block_snapshot_by_height(106).unwrap().unwrap();
+
+        // no reward set is known, so everyone burns
+        assert!(node.sortdb.index_handle(&tip.sortition_id).get_pox_recipients(&node.burnchain, 116).unwrap().is_none());
+
+        // the anchor block arrives, and it stacked STX for reward cycle 1
+        let reward_set = vec![
+            StacksAddress { version: 26, bytes: Hash160([0x01; 20]) },
+            StacksAddress { version: 26, bytes: Hash160([0x02; 20]) },
+            StacksAddress { version: 21, bytes: Hash160([0x03; 20]) },
+        ];
+        {
+            let mut tx = node.sortdb.tx_begin().unwrap();
+            tx.set_stacks_block_accepted(&anchor_snapshot.burn_header_hash, &BlockHeaderHash([5u8; 32]), &anchor_snapshot.winning_stacks_block_hash, 5).unwrap();
+            tx.set_pox_reward_set(&anchor_snapshot.burn_header_hash, &anchor_snapshot.winning_stacks_block_hash, 1, &reward_set).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let newly_known = node.sortdb.find_newly_known_pox_anchor(&node.burnchain).unwrap().unwrap();
+        node.sortdb.invalidate_pox_fork(&newly_known).unwrap();
+
+        // re-evaluate the reward cycle -- the burning commits are now invalid
+        let mut parent = SortitionDB::get_canonical_burn_chain_tip(node.sortdb.conn()).unwrap();
+        let last_valid_sortition = parent.clone();
+        assert_eq!(last_valid_sortition.block_height, 109);
+        assert!(last_valid_sortition.sortition);
+
+        for block in fork.blocks[9..].iter() {
+            let mut block = block.clone();
+            block.patch_from_chain_tip(&parent);
+            parent = block.mine(&mut node.sortdb, &node.burnchain);
+            assert!(!parent.sortition);
+        }
+        assert_eq!(parent.pox_id, PoxId::from_bit_string("11").unwrap());
+
+        let recipients = node.sortdb.index_handle(&parent.sortition_id).get_pox_recipients(&node.burnchain, parent.block_height + 1).unwrap().unwrap();
+        assert_eq!(recipients.len(), OUTPUTS_PER_COMMIT);
+        for addr in recipients.iter() {
+            assert!(reward_set.contains(addr));
+        }
+        assert_eq!(recipients, SortitionDB::choose_pox_recipients(&reward_set, &parent.sortition_hash));
+
+        // a commit that pays the chosen reward addresses wins
+        let mut block = TestBurnchainBlock::new(&parent, 0);
+        {
+            let ic = node.sortdb.index_conn();
+            block.add_leader_block_commit(&ic, &mut miner, &BlockHeaderHash([0x11; 32]), 1000, prev_key.as_ref().unwrap(), Some(&parent), Some(&last_valid_sortition));
+        }
+        match block.txs[0] {
+            BlockstackOperationType::LeaderBlockCommit(ref mut op) => {
+                op.commit_outs = recipients.clone();
+            },
+            _ => unreachable!()
+        }
+
+        let new_tip = block.mine(&mut node.sortdb, &node.burnchain);
+        assert!(new_tip.sortition);
+        assert_eq!(new_tip.winning_stacks_block_hash, BlockHeaderHash([0x11; 32]));
+    }
 }
@@ -22,7 +22,7 @@ use chainstate::burn::{
 };
 
 use chainstate::burn::db::sortdb::{
-    SortitionId, SortitionHandleTx
+    SortitionDB, SortitionId, SortitionHandleTx
 };
 
 use chainstate::burn::operations::{
@@ -113,11 +113,24 @@ impl <'a> SortitionHandleTx <'a> {
 
         let txids = state_transition.accepted_ops.iter().map(|ref op| op.txid()).collect();
 
-        let next_pox_id = &parent_snapshot.pox_id;
+        // if this block starts a reward cycle, choose its anchor block and extend the PoX identifier
+        let (next_pox_id, pox_anchor) = self.get_next_pox_id(burnchain, parent_snapshot, this_block_height)
+            .map_err(|e| {
+                error!("TRANSACTION ABORTED when choosing the PoX anchor block at block {} ({}): {:?}", this_block_height, &this_block_hash, e);
+                BurnchainError::DBError(e)
+            })?;
         let next_sortition_id = SortitionId::new(&this_block_hash, &next_pox_id);
 
+        // this block may have been evaluated in this PoX fork before, if the fork was
+        // invalidated and later became valid again.
+        if let Some(existing_snapshot) = SortitionDB::get_block_snapshot(self, &next_sortition_id)? {
+            debug!("Re-validate sortition {} for block {} ({})", &next_sortition_id, this_block_height, &this_block_hash);
+            self.revalidate_snapshot(&next_sortition_id)?;
+            return Ok((existing_snapshot, state_transition));
+        }
+
         // do the cryptographic sortition and pick the next winning block.
-        let mut snapshot = BlockSnapshot::make_snapshot(&self.as_conn(), burnchain, &next_sortition_id, next_pox_id,
+        let mut snapshot = BlockSnapshot::make_snapshot(&self.as_conn(), burnchain, &next_sortition_id, &next_pox_id,
                                                         parent_snapshot, block_header, &state_transition.burn_dist, &txids)
             .map_err(|e| {
                 error!("TRANSACTION ABORTED when taking snapshot at block {} ({}): {:?}", this_block_height, &this_block_hash, e);
@@ -130,10 +143,17 @@ impl <'a> SortitionHandleTx <'a> {
 
         snapshot.index_root = index_root;
 
+        if let Some((anchor_burn_header_hash, anchor_block_hash)) = pox_anchor {
+            let reward_cycle = burnchain.block_height_to_reward_cycle(this_block_height)
+                .expect("BUG: reward cycle start is before the first burnchain block");
+            self.insert_pox_anchor(&next_sortition_id, reward_cycle, &anchor_burn_header_hash, &anchor_block_hash)?;
+        }
+
         debug!("OPS-HASH({}): {}", this_block_height, &snapshot.ops_hash);
         debug!("INDEX-ROOT({}): {}", this_block_height, &snapshot.index_root);
         debug!("SORTITION-HASH({}): {}", this_block_height, &snapshot.sortition_hash);
         debug!("CONSENSUS({}): {}", this_block_height, &snapshot.consensus_hash);
+        debug!("POX-ID({}): {}", this_block_height, &snapshot.pox_id);
         Ok((snapshot, state_transition))
     }
 
@@ -18,7 +18,7 @@
 */
 
 use rusqlite::{Connection, OpenFlags, NO_PARAMS, OptionalExtension};
-use rusqlite::types::ToSql;
+use rusqlite::types::{ToSql, ToSqlOutput, FromSql, FromSqlResult, FromSqlError, ValueRef};
 use rusqlite::Row;
 use rusqlite::Transaction;
 use rusqlite::TransactionBehavior;
@@ -26,7 +26,7 @@ use rusqlite::TransactionBehavior;
 use rand;
 use rand::RngCore;
 
-use std::{io, fs, cmp};
+use std::{io, fs, cmp, fmt};
 use std::convert::From;
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -51,6 +51,7 @@ use chainstate::burn::operations::{
     BlockstackOperation,
     BlockstackOperationType
 };
+use chainstate::burn::operations::leader_block_commit::OUTPUTS_PER_COMMIT;
 
 use burnchains::{Txid, BurnchainHeaderHash, PublicKey, Address};
 use burnchains::BurnchainView;
@@ -80,7 +81,7 @@ use address::AddressHashMode;
 use util::log;
 use util::vrf::*;
 use util::secp256k1::MessageSignature;
-use util::hash::{to_hex, hex_bytes, Hash160, Sha512Trunc256Sum};
+use util::hash::{to_hex, hex_bytes, Hash160, Sha256Sum, Sha512Trunc256Sum};
 use sha2::{Sha512Trunc256, Digest};
 
 use util::strings::StacksString;
@@ -229,6 +230,7 @@ impl FromRow<LeaderBlockCommitOp> for LeaderBlockCommitOp {
         let memo_hex : String = row.get("memo");
         let burn_fee_str : String = row.get("burn_fee");
         let input_json : String = row.get("input");
+        let commit_outs_json : String = row.get("commit_outs");
         
         let memo_bytes = hex_bytes(&memo_hex)
             .map_err(|_e| db_error::ParseError)?;
@@ -238,6 +240,9 @@ impl FromRow<LeaderBlockCommitOp> for LeaderBlockCommitOp {
         let input = serde_json::from_str::<BurnchainSigner>(&input_json)
             .map_err(|e| db_error::SerializationError(e))?;
 
+        let commit_outs = serde_json::from_str::<Vec<StacksAddress>>(&commit_outs_json)
+            .map_err(|e| db_error::SerializationError(e))?;
+
         let burn_fee = burn_fee_str.parse::<u64>()
             .map_err(|_e| db_error::ParseError)?;
 
@@ -252,6 +257,7 @@ impl FromRow<LeaderBlockCommitOp> for LeaderBlockCommitOp {
 
             burn_fee: burn_fee,
             input: input,
+            commit_outs: commit_outs,
 
             txid: txid,
             vtxindex: vtxindex,
@@ -326,43 +332,7 @@ const BURNDB_SETUP : &'static [&'static str]= &[
     r#"
     PRAGMA foreign_keys = ON;
     "#,
-    r#"
-    -- sortition snapshots -- snapshot of all transactions processed in a burn block
-    -- organizes the set of forks in the burn chain as well.
-    CREATE TABLE snapshots(
-        block_height INTEGER NOT NULL,
-        burn_header_hash TEXT UNIQUE NOT NULL,
-        sortition_id TEXT UNIQUE NOT NULL,
-        pox_id TEXT NOT NULL,
-        burn_header_timestamp INT NOT NULL,
-        parent_burn_header_hash TEXT NOT NULL,
-        consensus_hash TEXT NOT NULL,
-        ops_hash TEXT NOT NULL,
-        total_burn TEXT NOT NULL,
-        sortition INTEGER NOT NULL,
-        sortition_hash TEXT NOT NULL,
-        winning_block_txid TEXT NOT NULL,
-        winning_stacks_block_hash TEXT NOT NULL,
-        index_root TEXT UNIQUE NOT NULL,
-
-        num_sortitions INTEGER NOT NULL,
-
-        stacks_block_accepted INTEGER NOT NULL,        -- set to 1 if we fetched and processed this Stacks block
-        stacks_block_height INTEGER NOT NULL,           -- set to the height of the stacks block, once it's processed
-        arrival_index INTEGER NOT NULL,                 -- (global) order in which this Stacks block was processed
-
-        canonical_stacks_tip_height INTEGER NOT NULL,   -- height of highest known Stacks fork in this burn chain fork
-        canonical_stacks_tip_hash TEXT NOT NULL,        -- hash of highest known Stacks fork's tip block in this burn chain fork
-        canonical_stacks_tip_burn_hash TEXT NOT NULL,   -- burn hash of highest known Stacks fork's tip block in this burn chain fork
-
-        PRIMARY KEY(sortition_id)
-    );"#,
-    r#"
-    CREATE UNIQUE INDEX snapshots_block_hashes ON snapshots(block_height,index_root,winning_stacks_block_hash);
-    CREATE UNIQUE INDEX snapshots_block_stacks_hashes ON snapshots(num_sortitions,index_root,winning_stacks_block_hash);
-    CREATE INDEX block_arrivals ON snapshots(arrival_index,burn_header_hash);
-    CREATE INDEX arrival_indexes ON snapshots(arrival_index);
-    "#,
+    BURNDB_SNAPSHOTS_TABLE,
     r#"
     -- all leader keys registered in the blockchain.
     -- contains pointers to the burn block and fork in which they occur
@@ -399,6 +369,7 @@ const BURNDB_SETUP : &'static [&'static str]= &[
         
         burn_fee TEXT NOT NULL,     -- use text to encode really big numbers
         input TEXT NOT NULL,        -- must match `address` in leader_keys
+        commit_outs TEXT NOT NULL,  -- PoX reward addresses paid, as a JSON list
 
         PRIMARY KEY(txid,sortition_id),
         FOREIGN KEY(sortition_id) REFERENCES snapshots(sortition_id)
@@ -433,6 +404,8 @@ const BURNDB_SETUP : &'static [&'static str]= &[
     );
     CREATE INDEX canonical_stacks_blocks ON canonical_accepted_stacks_blocks(tip_burn_block_hash,stacks_block_hash);
     "#,
+    BURNDB_POX_ANCHORS_TABLE,
+    BURNDB_POX_REWARD_SETS_TABLE,
     r#"
     CREATE TABLE db_config(
         version TEXT NOT NULL
@@ -440,6 +413,105 @@ const BURNDB_SETUP : &'static [&'static str]= &[
     "#
 ];
 
+// Each burnchain block has one snapshot for each PoX fork it was evaluated in, but only one of
+// them is PoX-valid.
+const BURNDB_SNAPSHOTS_TABLE : &'static str = r#"
+    -- sortition snapshots -- snapshot of all transactions processed in a burn block
+    -- organizes the set of forks in the burn chain as well.
+    CREATE TABLE snapshots(
+        block_height INTEGER NOT NULL,
+        burn_header_hash TEXT NOT NULL,
+        sortition_id TEXT UNIQUE NOT NULL,
+        pox_id TEXT NOT NULL,
+        pox_valid INTEGER NOT NULL,                     -- set to 0 if this sortition's PoX fork was superseded by a newly-known anchor block
+        burn_header_timestamp INT NOT NULL,
+        parent_burn_header_hash TEXT NOT NULL,
+        consensus_hash TEXT NOT NULL,
+        ops_hash TEXT NOT NULL,
+        total_burn TEXT NOT NULL,
+        sortition INTEGER NOT NULL,
+        sortition_hash TEXT NOT NULL,
+        winning_block_txid TEXT NOT NULL,
+        winning_stacks_block_hash TEXT NOT NULL,
+        index_root TEXT UNIQUE NOT NULL,
+
+        num_sortitions INTEGER NOT NULL,
+
+        stacks_block_accepted INTEGER NOT NULL,        -- set to 1 if we fetched and processed this Stacks block
+        stacks_block_height INTEGER NOT NULL,           -- set to the height of the stacks block, once it's processed
+        arrival_index INTEGER NOT NULL,                 -- (global) order in which this Stacks block was processed
+
+        canonical_stacks_tip_height INTEGER NOT NULL,   -- height of highest known Stacks fork in this burn chain fork
+        canonical_stacks_tip_hash TEXT NOT NULL,        -- hash of highest known Stacks fork's tip block in this burn chain fork
+        canonical_stacks_tip_burn_hash TEXT NOT NULL,   -- burn hash of highest known Stacks fork's tip block in this burn chain fork
+
+        PRIMARY KEY(sortition_id)
+    );"#;
+
+const BURNDB_SNAPSHOTS_INDEXES : &'static [&'static str] = &[
+    "CREATE UNIQUE INDEX snapshots_block_hashes ON snapshots(block_height,index_root,winning_stacks_block_hash);",
+    "CREATE UNIQUE INDEX snapshots_block_stacks_hashes ON snapshots(num_sortitions,index_root,winning_stacks_block_hash);",
+    "CREATE INDEX block_arrivals ON snapshots(arrival_index,burn_header_hash);",
+    "CREATE INDEX arrival_indexes ON snapshots(arrival_index);",
+    "CREATE INDEX snapshots_burn_hashes ON snapshots(burn_header_hash,pox_valid);",
+    "CREATE UNIQUE INDEX snapshots_valid_burn_hashes ON snapshots(burn_header_hash) WHERE pox_valid = 1;",
+    "CREATE INDEX snapshots_pox_valid ON snapshots(pox_valid,block_height);",
+    "CREATE INDEX snapshots_pox_ids ON snapshots(pox_id,block_height);",
+];
+
+const BURNDB_POX_ANCHORS_TABLE : &'static str = r#"
+    -- the anchor block chosen for each reward cycle, in each sortition that starts a reward cycle.
+    -- a reward cycle has no row here if no anchor block was chosen for it.
+    CREATE TABLE pox_anchors(
+        sortition_id TEXT NOT NULL,
+        reward_cycle INTEGER NOT NULL,
+        anchor_burn_header_hash TEXT NOT NULL,
+        anchor_block_hash TEXT NOT NULL,
+
+        PRIMARY KEY(sortition_id),
+        FOREIGN KEY(sortition_id) REFERENCES snapshots(sortition_id)
+    );
+    "#;
+
+const BURNDB_POX_REWARD_SETS_TABLE : &'static str = r#"
+    -- the PoX reward addresses for the reward cycle after the one each accepted Stacks block was
+    -- mined in, as registered in that block's chain state.  Used if the block becomes an anchor block.
+    -- a block has no rows here if its reward set is empty.
+    CREATE TABLE pox_reward_sets(
+        burn_header_hash TEXT NOT NULL,
+        block_hash TEXT NOT NULL,
+        reward_cycle INTEGER NOT NULL,
+        idx INTEGER NOT NULL,
+        address TEXT NOT NULL,
+
+        PRIMARY KEY(burn_header_hash, block_hash, reward_cycle, idx)
+    );
+    "#;
+
+// Migrate a database from before PoX reward cycles were tracked.  Snapshots gain a PoX-validity
+// flag, and a burnchain block may now have more than one snapshot, so the table is rebuilt
+// without the old UNIQUE constraint on burn_header_hash (see BURNDB_SNAPSHOTS_INDEXES).  Every
+// snapshot but the first is invalidated, so that its burnchain block gets re-evaluated in its
+// PoX fork.  Blocks in the first reward cycle re-validate their existing snapshots.
+const BURNDB_MIGRATE_23_0_0_0 : &'static [&'static str] = &[
+    r#"
+    INSERT INTO snapshots_migrated
+        (block_height, burn_header_hash, sortition_id, pox_id, pox_valid, burn_header_timestamp, parent_burn_header_hash, consensus_hash,
+         ops_hash, total_burn, sortition, sortition_hash, winning_block_txid, winning_stacks_block_hash, index_root, num_sortitions,
+         stacks_block_accepted, stacks_block_height, arrival_index, canonical_stacks_tip_height, canonical_stacks_tip_hash, canonical_stacks_tip_burn_hash)
+    SELECT block_height, burn_header_hash, sortition_id, '1', (CASE WHEN block_height = (SELECT MIN(block_height) FROM snapshots) THEN 1 ELSE 0 END), burn_header_timestamp, parent_burn_header_hash, consensus_hash,
+         ops_hash, total_burn, sortition, sortition_hash, winning_block_txid, winning_stacks_block_hash, index_root, num_sortitions,
+         stacks_block_accepted, stacks_block_height, arrival_index, canonical_stacks_tip_height, canonical_stacks_tip_hash, canonical_stacks_tip_burn_hash
+    FROM snapshots;"#,
+    "DROP TABLE snapshots;",
+    "ALTER TABLE snapshots_migrated RENAME TO snapshots;",
+];
+
+// Migrate a database from before block-commits could pay PoX reward addresses.
+const BURNDB_MIGRATE_23_0_0_2 : &'static [&'static str] = &[
+    "ALTER TABLE block_commits ADD COLUMN commit_outs TEXT NOT NULL DEFAULT '[]';",
+];
+
 pub struct SortitionDB {
     pub conn: Connection,
     pub readwrite: bool,
@@ -514,16 +586,43 @@ impl_byte_array_from_column!(SortitionId);
 impl_byte_array_message_codec!(SortitionId, 32);
 
 /// Identifier used to identify Proof-of-Transfer forks
-///  (or Rewards Cycle forks). These identifiers are opaque
-///  outside of the PoX DB, however, they are sufficient
-///  to uniquely identify a "sortition" when paired with
-///  a burn header hash
-pub struct PoxId(pub [u8; 32]);
-impl_array_newtype!(PoxId, u8, 32);
-impl_array_hexstring_fmt!(PoxId);
-impl_byte_array_newtype!(PoxId, u8, 32);
-impl_byte_array_from_column!(PoxId);
-impl_byte_array_message_codec!(PoxId, 32);
+///  (or Rewards Cycle forks). A PoX identifier is a bit-vector
+///  with one bit per reward cycle: the ith bit is set if the
+///  anchor block for the ith reward cycle is known (or if no
+///  anchor block was chosen for it), and unset otherwise.
+///  Paired with a burn header hash, it uniquely identifies
+///  a "sortition".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PoxId(Vec<bool>);
+
+impl fmt::Display for PoxId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bit in self.0.iter() {
+            write!(f, "{}", if *bit { 1 } else { 0 })?;
+        }
+        Ok(())
+    }
+}
+
+impl FromSql for PoxId {
+    fn column_result(value: ValueRef) -> FromSqlResult<PoxId> {
+        let bit_str = value.as_str()?;
+        PoxId::from_bit_string(bit_str)
+            .ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl FromColumn<PoxId> for PoxId {
+    fn from_column<'a>(row: &'a Row, column_name: &str) -> Result<PoxId, db_error> {
+        Ok(row.get::<_, PoxId>(column_name))
+    }
+}
+
+impl ToSql for PoxId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput> {
+        Ok(self.to_string().into())
+    }
+}
 
 struct db_keys;
 impl db_keys {
@@ -576,10 +675,25 @@ impl <'a> SortitionHandleTx <'a> {
 }
 
 impl <'a> SortitionDBTx <'a> { 
-    // PoX todo: this acceptance tracking will need to change once the StacksChainView is integrated
-    pub fn set_stacks_block_accepted_stubbed(&mut self, burn_header_hash: &BurnchainHeaderHash, parent_stacks_block_hash: &BlockHeaderHash,
+    /// Record the PoX reward set that an accepted Stacks block's chain state registered for the
+    /// given reward cycle.  It gets used if the block becomes that reward cycle's anchor block.
+    /// Replaces any reward set previously recorded for this block and reward cycle.
+    pub fn set_pox_reward_set(&mut self, burn_header_hash: &BurnchainHeaderHash, block_hash: &BlockHeaderHash,
+                              reward_cycle: u64, reward_set: &Vec<StacksAddress>) -> Result<(), db_error> {
+        let args : &[&dyn ToSql] = &[burn_header_hash, block_hash, &u64_to_sql(reward_cycle)?];
+        self.execute("DELETE FROM pox_reward_sets WHERE burn_header_hash = ?1 AND block_hash = ?2 AND reward_cycle = ?3", args)?;
+
+        for (i, address) in reward_set.iter().enumerate() {
+            let args : &[&dyn ToSql] = &[burn_header_hash, block_hash, &u64_to_sql(reward_cycle)?, &u64_to_sql(i as u64)?, &address.to_string()];
+            self.execute("INSERT INTO pox_reward_sets (burn_header_hash, block_hash, reward_cycle, idx, address) VALUES (?1, ?2, ?3, ?4, ?5)", args)?;
+        }
+        Ok(())
+    }
+
+    /// Mark a Stacks block as accepted, relative to the canonical burn chain tip.
+    pub fn set_stacks_block_accepted(&mut self, burn_header_hash: &BurnchainHeaderHash, parent_stacks_block_hash: &BlockHeaderHash,
                                              stacks_block_hash: &BlockHeaderHash, stacks_block_height: u64) -> Result<(), db_error> {
-        let chain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(self)?;
+        let chain_tip = SortitionDB::get_canonical_burn_chain_tip(self)?;
         self.set_stacks_block_accepted_at_tip(&chain_tip, burn_header_hash, parent_stacks_block_hash, stacks_block_hash, stacks_block_height)
     }
 
@@ -591,7 +705,7 @@ impl <'a> SortitionDBTx <'a> {
         let arrival_index = SortitionDB::get_max_arrival_index(self)?;
         let args : &[&dyn ToSql] = &[&u64_to_sql(stacks_block_height)?, &u64_to_sql(arrival_index + 1)?, burn_header_hash, stacks_block_hash];
 
-        self.execute("UPDATE snapshots SET stacks_block_accepted = 1, stacks_block_height = ?1, arrival_index = ?2 WHERE burn_header_hash = ?3 AND winning_stacks_block_hash = ?4", args)?;
+        self.execute("UPDATE snapshots SET stacks_block_accepted = 1, stacks_block_height = ?1, arrival_index = ?2 WHERE burn_header_hash = ?3 AND winning_stacks_block_hash = ?4 AND pox_valid = 1", args)?;
 
         let parent_key = db_keys::stacks_block_index(parent_stacks_block_hash);
 
@@ -640,7 +754,7 @@ impl <'a> SortitionDBTx <'a> {
                         debug!("Accepted Stacks block {}/{} builds on a previous canonical Stacks tip on this burnchain fork ({})", burn_header_hash, stacks_block_hash, &burn_tip.burn_header_hash);
                         let args : &[&dyn ToSql] = &[burn_header_hash, stacks_block_hash, &u64_to_sql(stacks_block_height)?, &burn_tip.burn_header_hash];
                         self.execute("UPDATE snapshots SET canonical_stacks_tip_burn_hash = ?1, canonical_stacks_tip_hash = ?2, canonical_stacks_tip_height = ?3
-                                    WHERE burn_header_hash = ?4", args)
+                                    WHERE burn_header_hash = ?4 AND pox_valid = 1", args)
                             .map_err(db_error::SqliteError)?;
                     }
                     else {
@@ -663,9 +777,12 @@ impl <'a> SortitionDBTx <'a> {
 }
 
 impl <'a> SortitionHandleConn <'a> {
-    /// open a reader handle
-    pub fn open_reader_stubbed(connection: &'a SortitionDBConn<'a>, chain_tip: &BurnchainHeaderHash) -> Result<SortitionHandleConn<'a>, db_error> {
-        let chain_tip = SortitionId(chain_tip.0.clone());
+    /// open a reader handle at the PoX-valid sortition of the given burnchain block.
+    /// Returns NotFoundError if the burnchain block has not been evaluated.
+    pub fn open_reader_at_burn_header_hash(connection: &'a SortitionDBConn<'a>, chain_tip: &BurnchainHeaderHash) -> Result<SortitionHandleConn<'a>, db_error> {
+        let chain_tip = SortitionDB::get_block_snapshot_by_burn_header_hash(connection.conn, chain_tip)?
+            .ok_or(db_error::NotFoundError)?
+            .sortition_id;
         SortitionHandleConn::open_reader(connection, &chain_tip)
     }
 
@@ -889,17 +1006,134 @@ impl <'a> SortitionHandleConn <'a> {
         SortitionDB::get_block_commit_parent(self, block_height, vtxindex, &self.context.chain_tip)
     }
 
-    /// Get a block commit by its content-addressed location.  Note that burn_header_hash is enough
-    /// to identify the fork we're on, since block hashes are globally-unique (w.h.p.) by
-    /// construction.
+    /// Get a block commit by its content-addressed location in this handle's fork.  A burnchain
+    /// block may have been evaluated in more than one PoX fork, so the burn header hash is mapped
+    /// to this fork's sortition first.
     pub fn get_block_commit(&self, txid: &Txid, burn_header_hash: &BurnchainHeaderHash) -> Result<Option<LeaderBlockCommitOp>, db_error> {
-        // PoX TODO: note -- block_commits table will index on burn_header_hash: if a block_commit is reprocessed due to a PoX fork,
-        //                   it should be allowed to either overwrite the previous entry OR skip insertion (i.e., UNIQUE constraints
-        //                   should not be allowed to cause a panic)
-        let qry = "SELECT * FROM block_commits WHERE txid = ?1 AND burn_header_hash = ?2";
-        let args: [&dyn ToSql; 2] = [&txid, &burn_header_hash];
-        query_row_panic(&self.conn, qry, &args,
-                        || format!("FATAL: multiple block commits for {},{}", &txid, &burn_header_hash))
+        let sortition_id = match self.get_block_snapshot(burn_header_hash)? {
+            Some(sn) => sn.sortition_id,
+            None => return Ok(None)
+        };
+        SortitionDB::get_block_commit_in_sortition(&self.conn, txid, &sortition_id)
+    }
+
+    /// Choose the anchor block for the reward cycle that begins at `cycle_start_height`, as seen
+    /// from this handle's chain tip (the parent of the reward cycle's first block).
+    /// Each winning block-commit in the prepare phase confirms the Stacks block it descends from
+    /// that was committed to before the prepare phase began.  A block confirmed by at least
+    /// `anchor_threshold` of them is the anchor block.
+    /// Returns the anchor's (burn header hash, stacks block hash), or None if no block was
+    /// confirmed often enough.
+    pub fn get_chosen_pox_anchor(&self, burnchain: &Burnchain, cycle_start_height: u64) -> Result<Option<(BurnchainHeaderHash, BlockHeaderHash)>, db_error> {
+        let prepare_length = burnchain.pox_constants.prepare_length as u64;
+        if cycle_start_height < self.context.first_block_height + prepare_length + 1 {
+            // no complete prepare phase
+            return Ok(None);
+        }
+        let prepare_start = cycle_start_height - prepare_length;
+
+        let mut confirmations : HashMap<(BurnchainHeaderHash, BlockHeaderHash), u32> = HashMap::new();
+        for height in prepare_start..cycle_start_height {
+            let snapshot = self.get_block_snapshot_by_height(height)?
+                .ok_or_else(|| {
+                    error!("Corrupt sortition DB: no snapshot at prepare phase height {} in {}", height, &self.context.chain_tip);
+                    db_error::Corruption
+                })?;
+            if !snapshot.sortition {
+                continue;
+            }
+
+            let mut cursor = SortitionDB::get_block_commit_in_sortition(self, &snapshot.winning_block_txid, &snapshot.sortition_id)?
+                .ok_or_else(|| {
+                    error!("Corrupt sortition DB: no winning block commit {} in {}", &snapshot.winning_block_txid, &snapshot.sortition_id);
+                    db_error::Corruption
+                })?;
+
+            // find the ancestor this commit confirms
+            let confirmed = loop {
+                if cursor.block_height < prepare_start {
+                    break Some(cursor);
+                }
+                if cursor.parent_block_ptr == 0 && cursor.parent_vtxindex == 0 {
+                    // builds on the genesis block
+                    break None;
+                }
+                match self.get_block_commit_parent(cursor.parent_block_ptr.into(), cursor.parent_vtxindex.into())? {
+                    Some(parent) => cursor = parent,
+                    None => break None
+                }
+            };
+
+            if let Some(commit) = confirmed {
+                let count = confirmations.entry((commit.burn_header_hash, commit.block_header_hash)).or_insert(0);
+                *count += 1;
+            }
+        }
+
+        // at most one block can be confirmed by a majority of the prepare phase
+        let anchor = confirmations.into_iter()
+            .find(|(_, count)| *count >= burnchain.pox_constants.anchor_threshold)
+            .map(|(anchor, _)| anchor);
+
+        Ok(anchor)
+    }
+
+    /// Get the PoX reward addresses that block-commits in the burnchain block at `block_height`
+    /// must pay, given that this handle's chain tip is that block's parent.
+    /// Returns None if block-commits must burn instead -- i.e. the block is in a prepare phase,
+    /// or its reward cycle has no known anchor block, or the anchor block's reward set is empty.
+    pub fn get_pox_recipients(&self, burnchain: &Burnchain, block_height: u64) -> Result<Option<Vec<StacksAddress>>, db_error> {
+        if block_height <= self.context.first_block_height || burnchain.is_in_prepare_phase(block_height) {
+            return Ok(None);
+        }
+
+        let reward_cycle = burnchain.block_height_to_reward_cycle(block_height)
+            .expect("BUG: block height is before the first burnchain block");
+        let cycle_start_height = burnchain.reward_cycle_to_block_height(reward_cycle);
+
+        let anchor = if block_height == cycle_start_height {
+            // this block starts the reward cycle, so its anchor block is being chosen from this fork
+            match self.get_chosen_pox_anchor(burnchain, cycle_start_height)? {
+                Some((anchor_burn_header_hash, anchor_block_hash)) => {
+                    if !SortitionDB::is_stacks_block_accepted(self, &anchor_burn_header_hash, &anchor_block_hash)? {
+                        return Ok(None);
+                    }
+                    (anchor_burn_header_hash, anchor_block_hash)
+                },
+                None => {
+                    return Ok(None);
+                }
+            }
+        }
+        else {
+            let cycle_start = self.get_block_snapshot_by_height(cycle_start_height)?
+                .ok_or_else(|| {
+                    error!("Corrupt sortition DB: no snapshot at reward cycle start {} in {}", cycle_start_height, &self.context.chain_tip);
+                    db_error::Corruption
+                })?;
+            if !cycle_start.pox_id.has_ith_anchor_block(reward_cycle as usize) {
+                return Ok(None);
+            }
+            match SortitionDB::get_pox_anchor(self, &cycle_start.sortition_id)? {
+                Some(anchor) => anchor,
+                None => {
+                    return Ok(None);
+                }
+            }
+        };
+
+        let reward_set = SortitionDB::get_pox_reward_set(self, &anchor.0, &anchor.1, reward_cycle)?;
+        if reward_set.len() == 0 {
+            return Ok(None);
+        }
+
+        let parent_snapshot = SortitionDB::get_block_snapshot(self, &self.context.chain_tip)?
+            .ok_or_else(|| {
+                error!("Corrupt sortition DB: no snapshot for chain tip {}", &self.context.chain_tip);
+                db_error::Corruption
+            })?;
+
+        Ok(Some(SortitionDB::choose_pox_recipients(&reward_set, &parent_snapshot.sortition_hash)))
     }
 
     /// Determine whether or not a leader key has been consumed by a subsequent block commitment in
@@ -934,31 +1168,58 @@ impl <'a> SortitionHandleConn <'a> {
 }
 
 impl PoxId {
-    const BASE_FORK: PoxId = PoxId([0; 32]);
+    /// The PoX identifier of the first reward cycle, which has no anchor block.
+    pub fn initial() -> PoxId {
+        PoxId(vec![true])
+    }
+
+    /// Parse a PoX identifier from its bit-string representation (e.g. "1101")
+    pub fn from_bit_string(bit_str: &str) -> Option<PoxId> {
+        if bit_str.len() == 0 {
+            return None;
+        }
+        let mut bits = Vec::with_capacity(bit_str.len());
+        for c in bit_str.chars() {
+            match c {
+                '1' => bits.push(true),
+                '0' => bits.push(false),
+                _ => return None
+            }
+        }
+        Some(PoxId(bits))
+    }
 
-    pub fn stubbed() -> PoxId {
-        PoxId::BASE_FORK.clone()
+    /// Append a reward cycle whose anchor block is known (or who has no anchor block)
+    pub fn extend_with_present_block(&mut self) {
+        self.0.push(true);
     }
 
-    pub fn base_fork() -> &'static PoxId {
-        &PoxId::BASE_FORK
+    /// Append a reward cycle whose anchor block is not yet known
+    pub fn extend_with_not_present_block(&mut self) {
+        self.0.push(false);
     }
-}
 
-impl SortitionId {
-    /// PoX Todo: any caller of this would need to instead
-    ///  construct a sortition ID with a burn header hash + pox fork identifier
-    pub fn stubbed(from: &BurnchainHeaderHash) -> SortitionId {
-        SortitionId::new(from, PoxId::base_fork())
+    /// Is the anchor block for the given reward cycle known in this PoX fork?
+    pub fn has_ith_anchor_block(&self, reward_cycle: usize) -> bool {
+        self.0.get(reward_cycle).cloned().unwrap_or(false)
     }
 
+    /// Number of reward cycles this identifier covers
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl SortitionId {
+    /// Sortitions in the initial PoX fork are identified by their burn header hash alone,
+    ///   so that chains that never cross a reward cycle are indexed just as before.
     pub fn new(bhh: &BurnchainHeaderHash, pox: &PoxId) -> SortitionId {
-        if pox == PoxId::base_fork() {
+        if *pox == PoxId::initial() {
             SortitionId(bhh.0.clone())
         } else {
             let mut hasher = Sha512Trunc256::new();
             hasher.input(bhh);
-            hasher.input(pox);
+            hasher.input(pox.to_string().as_bytes());
             let h = Sha512Trunc256Sum::from_hasher(hasher);
             SortitionId(h.0)
         }
@@ -1001,6 +1262,64 @@ impl SortitionDB {
         MARF::from_path(index_path).map_err(|_e| db_error::Corruption)
     }
 
+    /// Bring an existing database's schema up to this node's version, one version at a time.
+    /// Fails if the database's version is unknown, or if it needs migrating but was opened
+    /// read-only.
+    fn migrate_schema(conn: &mut Connection, readwrite: bool) -> Result<(), db_error> {
+        let version : Option<String> = conn.query_row("SELECT version FROM db_config LIMIT 1", NO_PARAMS, |row| row.get(0))
+            .optional()?;
+
+        let mut version = match version {
+            Some(version) => version,
+            None => {
+                error!("Invalid sortition database: no version");
+                return Err(db_error::Corruption);
+            }
+        };
+        if version == CHAINSTATE_VERSION {
+            return Ok(());
+        }
+        if !readwrite {
+            error!("Sortition database version {} must be opened read/write to migrate it to version {}", &version, CHAINSTATE_VERSION);
+            return Err(db_error::ReadOnly);
+        }
+
+        info!("Migrate sortition database from version {} to version {}", &version, CHAINSTATE_VERSION);
+        let tx = tx_begin_immediate(conn)?;
+        if version == "23.0.0.0" {
+            tx.execute(&BURNDB_SNAPSHOTS_TABLE.replace("CREATE TABLE snapshots(", "CREATE TABLE snapshots_migrated("), NO_PARAMS)?;
+            for row_text in BURNDB_MIGRATE_23_0_0_0 {
+                // ALTER TABLE ... RENAME reports rows, which execute() rejects
+                tx.execute_batch(row_text)?;
+            }
+            for row_text in BURNDB_SNAPSHOTS_INDEXES {
+                tx.execute(row_text, NO_PARAMS)?;
+            }
+            tx.execute(BURNDB_POX_ANCHORS_TABLE, NO_PARAMS)?;
+            version = "23.0.0.1".to_string();
+        }
+        if version == "23.0.0.1" {
+            // only the chain state changed
+            version = "23.0.0.2".to_string();
+        }
+        if version == "23.0.0.2" {
+            for row_text in BURNDB_MIGRATE_23_0_0_2 {
+                tx.execute(row_text, NO_PARAMS)?;
+            }
+            tx.execute(BURNDB_POX_REWARD_SETS_TABLE, NO_PARAMS)?;
+            version = "23.0.0.3".to_string();
+        }
+
+        if version != CHAINSTATE_VERSION {
+            error!("Invalid sortition database: expected version = {}, got {}", CHAINSTATE_VERSION, &version);
+            return Err(db_error::Other(format!("Unsupported sortition DB version {}", &version)));
+        }
+
+        tx.execute("UPDATE db_config SET version = ?1", &[&CHAINSTATE_VERSION])?;
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Open the database on disk.  It must already exist and be instantiated.
     /// It's best not to call this if you are able to call connect().  If you must call this, do so
     /// after you call connect() somewhere else, since connect() performs additional validations.
@@ -1017,9 +1336,11 @@ impl SortitionDB {
         debug!("Open sortdb '{}' as '{}', with index as '{}'",
                db_path, if readwrite { "readwrite" } else { "readonly" }, index_path);
         
-        let conn = Connection::open_with_flags(&db_path, open_flags)?;
+        let mut conn = Connection::open_with_flags(&db_path, open_flags)?;
         conn.busy_handler(Some(tx_busy_handler))?;
 
+        SortitionDB::migrate_schema(&mut conn, readwrite)?;
+
         let marf = SortitionDB::open_index(&index_path)?;
         let first_snapshot = SortitionDB::get_first_block_snapshot(&conn)?;
 
@@ -1081,6 +1402,8 @@ impl SortitionDB {
             db.instantiate(first_block_height, first_burn_hash, first_burn_header_timestamp)?;
         }
         else {
+            SortitionDB::migrate_schema(&mut db.conn, readwrite)?;
+
             // validate -- must contain the given first block and first block hash 
             let snapshot = SortitionDB::get_first_block_snapshot(&db.conn)?;
             if !snapshot.is_initial() || snapshot.block_height != first_block_height || snapshot.burn_header_hash != *first_burn_hash {
@@ -1116,6 +1439,9 @@ impl SortitionDB {
         for row_text in BURNDB_SETUP {
             db_tx.execute(row_text, NO_PARAMS)?;
         }
+        for row_text in BURNDB_SNAPSHOTS_INDEXES {
+            db_tx.execute(row_text, NO_PARAMS)?;
+        }
 
         db_tx.execute("INSERT INTO db_config (version) VALUES (?1)", &[&CHAINSTATE_VERSION])?;
 
@@ -1132,9 +1458,9 @@ impl SortitionDB {
         Ok(())
     }
 
-    /// Load up all snapshots, in ascending order by block height.  Great for testing!
+    /// Load up all PoX-valid snapshots, in ascending order by block height.  Great for testing!
     pub fn get_all_snapshots(&self) -> Result<Vec<BlockSnapshot>, db_error> {
-        let qry = "SELECT * FROM snapshots ORDER BY block_height ASC";
+        let qry = "SELECT * FROM snapshots WHERE pox_valid = 1 ORDER BY block_height ASC";
         query_rows(self.conn(), qry, NO_PARAMS)
     }
 }
@@ -1265,61 +1591,31 @@ impl <'a> SortitionDBConn <'a> {
     }
 }
 
-pub struct PoxDB;
-
-impl PoxDB {
-    /// Get the canonical PoX identifier for a given burnchain header hash.
-    ///   this result may change if a previously unknown PoX anchor is processed.
-    pub fn get_canonical_pox_id(&self, _burnchain_header_hash: &BurnchainHeaderHash) -> Result<PoxId, ()> {
-        Ok(PoxId::stubbed())
-    }
-    /// Get the parent PoX identifier for a given identifier.
-    ///  If the PoX identifier does not have a parent (should only be true for the "base" PoX identifier)
-    ///    return an error.
-    pub fn get_parent_pox_id(&self, _pox_id: &PoxId) -> Result<PoxId, ()> {
-        Err(())
-    }
-    /// does the given pox identifier describe a child fork of the parent pox identifier? this returns true if they
-    ///   are equal or child is a descendant.
-    pub fn is_pox_id_descendant(&self, parent: &PoxId, child: &PoxId) -> bool {
-        return parent == child || self.get_parent_pox_id(child).as_ref() == Ok(parent)
-    }
-    pub fn stubbed() -> PoxDB {
-        PoxDB
-    }
-}
-
 // High-level functions used by ChainsCoordinator
 impl SortitionDB {
-    pub fn get_sortition_id(&self, burnchain_header_hash: &BurnchainHeaderHash, pox_id: &PoxId) -> Result<SortitionId, ()> {
-        Ok(SortitionId::new(burnchain_header_hash, pox_id))
+    /// Get the sortition identifier of the PoX-valid sortition for a burnchain block, if it has
+    /// been processed.
+    pub fn get_sortition_id(&self, burnchain_header_hash: &BurnchainHeaderHash) -> Result<Option<SortitionId>, BurnchainError> {
+        SortitionDB::get_block_snapshot_by_burn_header_hash(&self.conn, burnchain_header_hash)
+            .map(|sn_opt| sn_opt.map(|sn| sn.sortition_id))
+            .map_err(|e| BurnchainError::from(e))
     }
-    pub fn is_sortition_processed(&self, burnchain_header_hash: &BurnchainHeaderHash, pox_id: &PoxId) -> Result<bool, BurnchainError> {
-        let sort_id = SortitionId::new(burnchain_header_hash, pox_id);
 
-        match SortitionDB::get_block_snapshot(&self.conn, &sort_id) {
-            Ok(opt_sn) => Ok(opt_sn.is_some()),
-            Err(e) => Err(BurnchainError::from(e))
-        }
+    pub fn is_sortition_processed(&self, burnchain_header_hash: &BurnchainHeaderHash) -> Result<bool, BurnchainError> {
+        self.get_sortition_id(burnchain_header_hash)
+            .map(|sort_id_opt| sort_id_opt.is_some())
     }
 
+    /// Evaluate a burnchain block on top of the PoX-valid sortition of its parent.
     pub fn evaluate_sortition(&mut self, burn_header: &BurnchainBlockHeader, ops: Vec<BlockstackOperationType>,
-                              burnchain: &Burnchain, pox_id: &PoxId, pox_db: &PoxDB) -> Result<(BlockSnapshot, BurnchainStateTransition), BurnchainError> {
-        let parent_pox = pox_db.get_canonical_pox_id(&burn_header.parent_block_hash)
-            .map_err(|_e| BurnchainError::MissingParentBlock)?;
-        let parent_sort_id = SortitionId::new(&burn_header.parent_block_hash, &parent_pox);
-
-        if !pox_db.is_pox_id_descendant(&parent_pox, pox_id) {
-            return Err(BurnchainError::NonCanonicalPoxId(parent_pox, pox_id.clone()));
-        }
-
-        let mut sortition_db_handle = SortitionHandleTx::begin(self, &parent_sort_id)?;
-        let parent_snapshot = sortition_db_handle.as_conn().get_block_snapshot(&burn_header.parent_block_hash)?
+                              burnchain: &Burnchain) -> Result<(BlockSnapshot, BurnchainStateTransition), BurnchainError> {
+        let parent_snapshot = SortitionDB::get_block_snapshot_by_burn_header_hash(&self.conn, &burn_header.parent_block_hash)?
             .ok_or_else(|| {
                 warn!("Unknown block {:?}", burn_header.parent_block_hash);
                 BurnchainError::MissingParentBlock
             })?;
 
+        let mut sortition_db_handle = SortitionHandleTx::begin(self, &parent_snapshot.sortition_id)?;
         let new_snapshot = sortition_db_handle.process_block_txs(
             &parent_snapshot, burn_header, burnchain, ops)?;
 
@@ -1339,44 +1635,128 @@ impl SortitionDB {
         SortitionDB::get_max_arrival_index(&db_handle)
             .map_err(|e| BurnchainError::from(e))
     }
-}
 
-// Querying methods
-impl SortitionDB {
-    /// Get the last snapshot processed, in the provided PoX fork
-    pub fn get_last_snapshot(conn: &Connection, pox_id: &PoxId, pox_db: &PoxDB) -> Result<Option<BlockSnapshot>, db_error> {
-        let qry = "SELECT * FROM snapshots WHERE pox_id = ?1 ORDER BY block_height DESC, burn_header_hash ASC LIMIT 1";
-        let opt_result = query_row(conn, qry, &[pox_id])?;
-        if let None = opt_result {
-            let parent_pox_id = match pox_db.get_parent_pox_id(pox_id).ok() {
-                None => return Ok(None),
-                Some(x) => x
-            };
-            query_row(conn, qry, &[&parent_pox_id])
-        } else {
-            Ok(opt_result)
+    /// Find the earliest reward cycle in the canonical burn chain tip's PoX fork whose anchor
+    /// block was unknown when the reward cycle began, but has since been accepted.
+    /// Returns the snapshot that started that reward cycle, if there is one.
+    pub fn find_newly_known_pox_anchor(&self, burnchain: &Burnchain) -> Result<Option<BlockSnapshot>, db_error> {
+        let tip = SortitionDB::get_canonical_burn_chain_tip(&self.conn)?;
+        let db_handle = self.index_handle(&tip.sortition_id);
+
+        for reward_cycle in 0..tip.pox_id.len() {
+            if tip.pox_id.has_ith_anchor_block(reward_cycle) {
+                continue;
+            }
+
+            let cycle_start_height = burnchain.reward_cycle_to_block_height(reward_cycle as u64);
+            let cycle_start = db_handle.get_block_snapshot_by_height(cycle_start_height)?
+                .ok_or_else(|| {
+                    error!("Corrupt sortition DB: no snapshot at reward cycle start {} in {}", cycle_start_height, &tip.sortition_id);
+                    db_error::Corruption
+                })?;
+            let (anchor_burn_header_hash, anchor_block_hash) = SortitionDB::get_pox_anchor(&self.conn, &cycle_start.sortition_id)?
+                .ok_or_else(|| {
+                    error!("Corrupt sortition DB: no anchor block recorded for unknown anchor at {}", &cycle_start.sortition_id);
+                    db_error::Corruption
+                })?;
+
+            if SortitionDB::is_stacks_block_accepted(&self.conn, &anchor_burn_header_hash, &anchor_block_hash)? {
+                debug!("Anchor block {}/{} for reward cycle {} is now known", &anchor_burn_header_hash, &anchor_block_hash, reward_cycle);
+                return Ok(Some(cycle_start));
+            }
         }
+        Ok(None)
     }
 
-    /// Get the canonical burn chain tip -- the tip of the longest burn chain we know about.
+    /// Mark the sortition that started a reward cycle, and every sortition that descends from it
+    /// in its PoX fork, as no longer valid.  Their burnchain blocks must be re-evaluated.
+    /// Note that sibling burnchain forks which made the same anchor block choice are invalidated
+    /// as well; they get re-evaluated if they ever become the canonical burnchain fork.
+    pub fn invalidate_pox_fork(&mut self, cycle_start: &BlockSnapshot) -> Result<(), db_error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        // PoX IDs are strings of '0's and '1's, so the PoX IDs that start with this one are exactly
+        // those in [pox_id, pox_id || '2').
+        let pox_id_start = cycle_start.pox_id.to_string();
+        let pox_id_end = format!("{}2", &pox_id_start);
+        let args : &[&dyn ToSql] = &[&u64_to_sql(cycle_start.block_height)?, &pox_id_start, &pox_id_end];
+        tx.execute("UPDATE snapshots SET pox_valid = 0 WHERE pox_valid = 1 AND pox_id >= ?2 AND pox_id < ?3 AND block_height >= ?1", args)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+// Querying methods
+impl SortitionDB {
+    /// Get the canonical burn chain tip -- the tip of the longest burn chain we know about, among
+    /// all sortitions that are valid in the PoX fork they were evaluated in.
     /// Break ties deterministically by ordering on burnchain block hash.
-    // PoX TODO: this method should go away -- callers will need to call `get_last_snapshot` with a PoX identifier
-    //            to obtain the last snapshot
-    pub fn get_canonical_burn_chain_tip_stubbed(conn: &Connection) -> Result<BlockSnapshot, db_error> {
-        let pox_db = PoxDB::stubbed();
-        SortitionDB::get_last_snapshot(conn, &PoxId::stubbed(), &pox_db)
+    pub fn get_canonical_burn_chain_tip(conn: &Connection) -> Result<BlockSnapshot, db_error> {
+        let qry = "SELECT * FROM snapshots WHERE pox_valid = 1 ORDER BY block_height DESC, burn_header_hash ASC LIMIT 1";
+        query_row(conn, qry, NO_PARAMS)
             .map(|opt| opt.expect("CORRUPTION: No canonical burnchain tip"))
     }
 
     /// Get the canonical Stacks chain tip -- this gets memoized on the canonical burn chain tip.
-    pub fn get_canonical_stacks_chain_tip_hash_stubbed(conn: &Connection) -> Result<(BurnchainHeaderHash, BlockHeaderHash), db_error> {
-        let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(conn)?;
+    pub fn get_canonical_stacks_chain_tip_hash(conn: &Connection) -> Result<(BurnchainHeaderHash, BlockHeaderHash), db_error> {
+        let sn = SortitionDB::get_canonical_burn_chain_tip(conn)?;
 
         let stacks_block_hash = sn.canonical_stacks_tip_hash;
         let burn_block_hash = sn.canonical_stacks_tip_burn_hash;
         Ok((burn_block_hash, stacks_block_hash))
     }
 
+    /// Get the PoX-valid snapshot for a burnchain block.
+    pub fn get_block_snapshot_by_burn_header_hash(conn: &Connection, burn_header_hash: &BurnchainHeaderHash) -> Result<Option<BlockSnapshot>, db_error> {
+        let qry = "SELECT * FROM snapshots WHERE burn_header_hash = ?1 AND pox_valid = 1";
+        query_row_panic(conn, qry, &[burn_header_hash],
+                        || format!("FATAL: multiple PoX-valid snapshots for burn block {}", burn_header_hash))
+    }
+
+    /// Get the anchor block chosen for the reward cycle started by the given sortition.
+    /// Returns the anchor's (burn header hash, stacks block hash), or None if no anchor block was chosen
+    /// (or if the sortition did not start a reward cycle).
+    pub fn get_pox_anchor(conn: &Connection, sortition_id: &SortitionId) -> Result<Option<(BurnchainHeaderHash, BlockHeaderHash)>, db_error> {
+        let qry = "SELECT anchor_burn_header_hash, anchor_block_hash FROM pox_anchors WHERE sortition_id = ?1";
+        conn.query_row(qry, &[sortition_id], |row| (BurnchainHeaderHash::from_column(row, "anchor_burn_header_hash"),
+                                                    BlockHeaderHash::from_column(row, "anchor_block_hash")))
+            .optional()?
+            .map(|(bhh, block_hash)| Ok((bhh?, block_hash?)))
+            .transpose()
+    }
+
+    /// Get the PoX reward set registered for the given reward cycle in the given Stacks block's chain state.
+    /// The reward set is empty if the block has not been accepted, or if no one Stacked in it for this reward cycle.
+    pub fn get_pox_reward_set(conn: &Connection, burn_header_hash: &BurnchainHeaderHash, block_hash: &BlockHeaderHash, reward_cycle: u64) -> Result<Vec<StacksAddress>, db_error> {
+        let qry = "SELECT address FROM pox_reward_sets WHERE burn_header_hash = ?1 AND block_hash = ?2 AND reward_cycle = ?3 ORDER BY idx ASC";
+        let args : &[&dyn ToSql] = &[burn_header_hash, block_hash, &u64_to_sql(reward_cycle)?];
+        query_row_columns(conn, &qry.to_string(), args, "address")
+    }
+
+    /// Choose the `OUTPUTS_PER_COMMIT` reward addresses that block-commits must pay, using the
+    /// parent sortition's hash as the source of randomness.  An address may be chosen more than once.
+    pub fn choose_pox_recipients(reward_set: &Vec<StacksAddress>, sortition_hash: &SortitionHash) -> Vec<StacksAddress> {
+        assert!(reward_set.len() > 0);
+        let mut recipients = Vec::with_capacity(OUTPUTS_PER_COMMIT);
+        for i in 0..OUTPUTS_PER_COMMIT {
+            let mut data = sortition_hash.as_bytes().to_vec();
+            data.extend_from_slice(&(i as u64).to_be_bytes());
+            let digest = Sha256Sum::from_data(&data);
+
+            let mut index_bytes = [0u8; 8];
+            index_bytes.copy_from_slice(&digest.as_bytes()[0..8]);
+            let index = u64::from_be_bytes(index_bytes) % (reward_set.len() as u64);
+            recipients.push(reward_set[index as usize].clone());
+        }
+        recipients
+    }
+
+    /// Has the given Stacks block been accepted in any sortition?
+    pub fn is_stacks_block_accepted(conn: &Connection, burn_header_hash: &BurnchainHeaderHash, block_hash: &BlockHeaderHash) -> Result<bool, db_error> {
+        let qry = "SELECT COUNT(*) FROM snapshots WHERE burn_header_hash = ?1 AND winning_stacks_block_hash = ?2 AND stacks_block_accepted = 1".to_string();
+        let args : &[&dyn ToSql] = &[burn_header_hash, block_hash];
+        query_count(conn, &qry, args)
+            .map(|count| count > 0)
+    }
 
     /// Get an accepted stacks block header in a fork whose chain tip has not yet committed
     /// to it.
@@ -1411,7 +1791,7 @@ impl SortitionDB {
 
     /// Get a snapshot with an arrived block (i.e. a block that was marked as processed)
     fn get_snapshot_by_arrival_index(conn: &Connection, arrival_index: u64) -> Result<Option<BlockSnapshot>, db_error> {
-        query_row_panic(conn, "SELECT * FROM snapshots WHERE arrival_index = ?1 AND stacks_block_accepted > 0",
+        query_row_panic(conn, "SELECT * FROM snapshots WHERE arrival_index = ?1 AND stacks_block_accepted > 0 AND pox_valid = 1",
                         &[&u64_to_sql(arrival_index)?],
                         || "BUG: multiple snapshots have the same non-zero arrival index".to_string())
     }
@@ -1562,11 +1942,8 @@ impl SortitionDB {
             }
         };
 
-        // PoX TODO: note -- leader_keys table will index on burn_header_hash: if a leader key is reprocessed due to a PoX fork,
-        //                   it should be allowed to either overwrite the previous entry OR skip insertion (i.e., UNIQUE constraints
-        //                   should not be allowed to cause a panic)
-        let qry = "SELECT * FROM leader_keys WHERE burn_header_hash = ?1 AND block_height = ?2 AND vtxindex = ?3 LIMIT 2";
-        let args : &[&dyn ToSql] = &[&ancestor_snapshot.burn_header_hash, &u64_to_sql(key_block_height)?, &key_vtxindex];
+        let qry = "SELECT * FROM leader_keys WHERE sortition_id = ?1 AND block_height = ?2 AND vtxindex = ?3 LIMIT 2";
+        let args : &[&dyn ToSql] = &[&ancestor_snapshot.sortition_id, &u64_to_sql(key_block_height)?, &key_vtxindex];
         query_row_panic(ic, qry, args,
                         || format!("Multiple keys at {},{} in {}", key_block_height, key_vtxindex, tip))
     }
@@ -1589,9 +1966,18 @@ impl SortitionDB {
         Ok(leader_keys)
     }
 
-    /// Get a block commit by its committed block
+    /// Get a block commit by its txid, as evaluated in a particular sortition
+    fn get_block_commit_in_sortition(conn: &Connection, txid: &Txid, sortition_id: &SortitionId) -> Result<Option<LeaderBlockCommitOp>, db_error> {
+        let qry = "SELECT * FROM block_commits WHERE txid = ?1 AND sortition_id = ?2";
+        let args: [&dyn ToSql; 2] = [&txid, &sortition_id];
+        query_row_panic(conn, qry, &args,
+                        || format!("FATAL: multiple block commits for {} in {}", &txid, &sortition_id))
+    }
+
+    /// Get a block commit by its committed block, as evaluated in the burnchain block's PoX-valid sortition
     pub fn get_block_commit_for_stacks_block(conn: &Connection, burn_header_hash: &BurnchainHeaderHash, block_hash: &BlockHeaderHash) -> Result<Option<LeaderBlockCommitOp>, db_error> {
-        let qry = "SELECT * FROM block_commits WHERE burn_header_hash = ?1 AND block_header_hash = ?2";
+        let qry = "SELECT * FROM block_commits WHERE burn_header_hash = ?1 AND block_header_hash = ?2 AND \
+                   sortition_id IN (SELECT sortition_id FROM snapshots WHERE burn_header_hash = ?1 AND pox_valid = 1)";
         let args: [&dyn ToSql; 2] = [&burn_header_hash, &block_hash];
         query_row_panic(conn, qry, &args,
                         || format!("FATAL: multiple block commits for {}", &block_hash))
@@ -1669,6 +2055,72 @@ impl SortitionDB {
 }
 
 impl <'a> SortitionHandleTx <'a> {
+    /// Calculate the PoX identifier for the sortition of the burnchain block at `block_height`,
+    /// whose parent sortition is `parent_snapshot`.  If this block starts a reward cycle, then the
+    /// cycle's anchor block is chosen from the parent's fork and is returned alongside it.
+    /// The new reward cycle's bit is set unless an anchor block was chosen and we do not have it.
+    pub fn get_next_pox_id(&self, burnchain: &Burnchain, parent_snapshot: &BlockSnapshot, block_height: u64) -> Result<(PoxId, Option<(BurnchainHeaderHash, BlockHeaderHash)>), db_error> {
+        let mut pox_id = parent_snapshot.pox_id.clone();
+        if block_height <= burnchain.first_block_height || !burnchain.is_reward_cycle_start(block_height) {
+            return Ok((pox_id, None));
+        }
+
+        let anchor = self.conn_view(&parent_snapshot.sortition_id)
+            .get_chosen_pox_anchor(burnchain, block_height)?;
+
+        match anchor {
+            Some((ref anchor_burn_header_hash, ref anchor_block_hash)) => {
+                if SortitionDB::is_stacks_block_accepted(self, anchor_burn_header_hash, anchor_block_hash)? {
+                    pox_id.extend_with_present_block();
+                }
+                else {
+                    info!("Anchor block {}/{} for the reward cycle starting at {} is not known", anchor_burn_header_hash, anchor_block_hash, block_height);
+                    pox_id.extend_with_not_present_block();
+                }
+            },
+            None => {
+                debug!("No anchor block chosen for the reward cycle starting at {}", block_height);
+                pox_id.extend_with_present_block();
+            }
+        }
+
+        Ok((pox_id, anchor))
+    }
+
+    /// Record the anchor block chosen for the reward cycle started by the given sortition.
+    pub fn insert_pox_anchor(&mut self, sortition_id: &SortitionId, reward_cycle: u64,
+                             anchor_burn_header_hash: &BurnchainHeaderHash, anchor_block_hash: &BlockHeaderHash) -> Result<(), db_error> {
+        let args : &[&dyn ToSql] = &[sortition_id, &u64_to_sql(reward_cycle)?, anchor_burn_header_hash, anchor_block_hash];
+        self.execute("INSERT INTO pox_anchors (sortition_id, reward_cycle, anchor_burn_header_hash, anchor_block_hash) VALUES (?1, ?2, ?3, ?4)", args)?;
+        Ok(())
+    }
+
+    /// Mark a previously-invalidated sortition as valid again.
+    pub fn revalidate_snapshot(&mut self, sortition_id: &SortitionId) -> Result<(), db_error> {
+        self.execute("UPDATE snapshots SET pox_valid = 1 WHERE sortition_id = ?1", &[sortition_id])?;
+        Ok(())
+    }
+
+    /// If this snapshot's burnchain block was evaluated before in a PoX fork that has since been
+    /// invalidated, and the Stacks block it selected was accepted there, then it is accepted in
+    /// this PoX fork as well.  It gets a new arrival index so that descendant sortitions in this
+    /// fork will find it.
+    fn inherit_stacks_block_acceptance(&mut self, snapshot: &BlockSnapshot) -> Result<(), db_error> {
+        if !snapshot.sortition {
+            return Ok(());
+        }
+
+        let qry = "SELECT * FROM snapshots WHERE burn_header_hash = ?1 AND winning_stacks_block_hash = ?2 AND stacks_block_accepted = 1 AND pox_valid = 0 LIMIT 1";
+        let args : &[&dyn ToSql] = &[&snapshot.burn_header_hash, &snapshot.winning_stacks_block_hash];
+        let accepted : Option<BlockSnapshot> = query_row(self, qry, args)?;
+        if let Some(accepted) = accepted {
+            let arrival_index = SortitionDB::get_max_arrival_index(self)?;
+            let args : &[&dyn ToSql] = &[&u64_to_sql(accepted.stacks_block_height)?, &u64_to_sql(arrival_index + 1)?, &snapshot.sortition_id];
+            self.execute("UPDATE snapshots SET stacks_block_accepted = 1, stacks_block_height = ?1, arrival_index = ?2 WHERE sortition_id = ?3", args)?;
+        }
+        Ok(())
+    }
+
     /// Append a snapshot to a chain tip, and update various chain tip statistics.
     /// Returns the new state root of this fork.
     pub fn append_chain_tip_snapshot(&mut self, parent_snapshot: &BlockSnapshot, snapshot: &BlockSnapshot, block_ops: &Vec<BlockstackOperationType>, consumed_leader_keys: &Vec<LeaderKeyRegisterOp>) -> Result<TrieHash, db_error> {
@@ -1693,6 +2145,7 @@ impl <'a> SortitionHandleTx <'a> {
         sn.canonical_stacks_tip_burn_hash = parent_sn.canonical_stacks_tip_burn_hash;
 
         self.insert_block_snapshot(&sn)?;
+        self.inherit_stacks_block_acceptance(&sn)?;
 
         for block_op in block_ops {
             self.store_burnchain_transaction(block_op, &sn.sortition_id)?;
@@ -1754,6 +2207,9 @@ impl <'a> SortitionHandleTx <'a> {
         let tx_input_str = serde_json::to_string(&block_commit.input)
             .map_err(|e| db_error::SerializationError(e))?;
 
+        let commit_outs_str = serde_json::to_string(&block_commit.commit_outs)
+            .map_err(|e| db_error::SerializationError(e))?;
+
         // represent burn fee as TEXT 
         let burn_fee_str = format!("{}", block_commit.burn_fee);
 
@@ -1771,11 +2227,12 @@ impl <'a> SortitionHandleTx <'a> {
             &to_hex(&block_commit.memo[..]),
             &burn_fee_str,
             &tx_input_str,
+            &commit_outs_str,
             sort_id
         ];
 
-        self.execute("INSERT INTO block_commits (txid, vtxindex, block_height, burn_header_hash, block_header_hash, new_seed, parent_block_ptr, parent_vtxindex, key_block_ptr, key_vtxindex, memo, burn_fee, input, sortition_id) \
-                      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)", args)?;
+        self.execute("INSERT INTO block_commits (txid, vtxindex, block_height, burn_header_hash, block_header_hash, new_seed, parent_block_ptr, parent_vtxindex, key_block_ptr, key_vtxindex, memo, burn_fee, input, commit_outs, sortition_id) \
+                      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)", args)?;
 
         Ok(())
     }
@@ -1844,13 +2301,14 @@ impl <'a> SortitionHandleTx <'a> {
             &snapshot.canonical_stacks_tip_hash,
             &snapshot.canonical_stacks_tip_burn_hash,
             &snapshot.sortition_id,
-            &snapshot.pox_id
+            &snapshot.pox_id,
+            &true
         ];
 
         self.execute("INSERT INTO snapshots \
                       (block_height, burn_header_hash, burn_header_timestamp, parent_burn_header_hash, consensus_hash, ops_hash, total_burn, sortition, sortition_hash, winning_block_txid, winning_stacks_block_hash, index_root, num_sortitions, \
-                      stacks_block_accepted, stacks_block_height, arrival_index, canonical_stacks_tip_height, canonical_stacks_tip_hash, canonical_stacks_tip_burn_hash, sortition_id, pox_id) \
-                      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)", args)
+                      stacks_block_accepted, stacks_block_height, arrival_index, canonical_stacks_tip_height, canonical_stacks_tip_hash, canonical_stacks_tip_burn_hash, sortition_id, pox_id, pox_valid) \
+                      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)", args)
             .map_err(db_error::SqliteError)?;
 
         Ok(())
@@ -2030,6 +2488,171 @@ mod tests {
         let _db = SortitionDB::connect_test(123, &first_burn_hash).unwrap();
     }
 
+    /// Column names of a table, minus the given column.
+    fn table_columns_except(conn: &Connection, table: &str, except: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+        let mut rows = stmt.query(NO_PARAMS).unwrap();
+        let mut columns = vec![];
+        while let Some(row_res) = rows.next() {
+            let name : String = row_res.unwrap().get("name");
+            if name != except {
+                columns.push(name);
+            }
+        }
+        columns
+    }
+
+    #[test]
+    fn test_migrate_old_schema_version() {
+        let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+        let path = "/tmp/test-blockstack-sortdb-migrate-old-schema-version";
+        if fs::metadata(path).is_ok() {
+            fs::remove_dir_all(path).unwrap();
+        }
+
+        let snapshots = {
+            let mut db = SortitionDB::connect(path, 123, &first_burn_hash, get_epoch_time_secs(), true).unwrap();
+            let sn_1 = test_append_snapshot(&mut db, BurnchainHeaderHash([0x01; 32]), &vec![], &vec![]);
+            let sn_2 = test_append_snapshot(&mut db, BurnchainHeaderHash([0x02; 32]), &vec![], &vec![]);
+
+            // give the appended snapshots their own consensus hashes, and turn the DB back into a
+            // version 23.0.0.0 database
+            let snapshot_columns = table_columns_except(&db.conn, "snapshots", "pox_valid").join(",");
+            let commit_columns = table_columns_except(&db.conn, "block_commits", "commit_outs").join(",");
+            db.conn.execute_batch(&format!(
+                "UPDATE snapshots SET consensus_hash = substr(burn_header_hash, 1, 40) WHERE block_height > 123;
+                 CREATE TABLE snapshots_old AS SELECT {} FROM snapshots;
+                 DROP TABLE snapshots;
+                 ALTER TABLE snapshots_old RENAME TO snapshots;
+                 CREATE TABLE block_commits_old AS SELECT {} FROM block_commits;
+                 DROP TABLE block_commits;
+                 ALTER TABLE block_commits_old RENAME TO block_commits;
+                 DROP TABLE pox_anchors;
+                 DROP TABLE pox_reward_sets;
+                 UPDATE db_config SET version = '23.0.0.0';", &snapshot_columns, &commit_columns)).unwrap();
+            vec![sn_1, sn_2]
+        };
+
+        // can't migrate a read-only database
+        match SortitionDB::open(path, false) {
+            Err(db_error::ReadOnly) => {},
+            Err(e) => panic!("Unexpected error {:?}", &e),
+            Ok(_) => panic!("Opened a read-only sortition DB with an old schema"),
+        }
+
+        let db = SortitionDB::connect(path, 123, &first_burn_hash, get_epoch_time_secs(), true).unwrap();
+        let version : String = db.conn.query_row("SELECT version FROM db_config", NO_PARAMS, |row| row.get(0)).unwrap();
+        assert_eq!(version, CHAINSTATE_VERSION);
+
+        // only the initial snapshot is still valid; the rest get re-evaluated
+        let first_snapshot = SortitionDB::get_first_block_snapshot(&db.conn).unwrap();
+        assert_eq!(first_snapshot.burn_header_hash, first_burn_hash);
+        for sn in snapshots.iter() {
+            let migrated = SortitionDB::get_block_snapshot(&db.conn, &sn.sortition_id).unwrap().unwrap();
+            assert_eq!(migrated.burn_header_hash, sn.burn_header_hash);
+            let pox_valid : i64 = db.conn.query_row("SELECT pox_valid FROM snapshots WHERE sortition_id = ?1", &[&sn.sortition_id], |row| row.get(0)).unwrap();
+            assert_eq!(pox_valid, 0);
+        }
+        assert_eq!(SortitionDB::get_canonical_burn_chain_tip(&db.conn).unwrap().sortition_id, first_snapshot.sortition_id);
+
+        // new tables and columns exist
+        assert!(SortitionDB::get_pox_anchor(&db.conn, &first_snapshot.sortition_id).unwrap().is_none());
+        assert_eq!(SortitionDB::get_pox_reward_set(&db.conn, &first_burn_hash, &BlockHeaderHash([0x02; 32]), 1).unwrap(), vec![]);
+        let commit_outs : i64 = db.conn.query_row("SELECT COUNT(commit_outs) FROM block_commits", NO_PARAMS, |row| row.get(0)).unwrap();
+        assert_eq!(commit_outs, 0);
+
+        // reopening is a no-op
+        drop(db);
+        SortitionDB::open(path, false).unwrap();
+    }
+
+    #[test]
+    fn test_valid_burn_header_hash_unique() {
+        let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+        let mut db = SortitionDB::connect_test(123, &first_burn_hash).unwrap();
+        let sn = test_append_snapshot(&mut db, BurnchainHeaderHash([0x01; 32]), &vec![], &vec![]);
+
+        // copy the snapshot into another PoX fork
+        let columns = table_columns_except(&db.conn, "snapshots", "sortition_id");
+        let copy_columns = columns.iter()
+            .map(|c| if c == "index_root" { "'new-index-root'".to_string() } else { c.clone() })
+            .collect::<Vec<_>>()
+            .join(",");
+        let copy_snapshot = format!("INSERT INTO snapshots (sortition_id,{}) SELECT ?1,{} FROM snapshots WHERE sortition_id = ?2",
+                                    columns.join(","), copy_columns);
+        let forked_sortition_id = SortitionId::new(&sn.burn_header_hash, &PoxId::from_bit_string("11").unwrap());
+        let args : &[&dyn ToSql] = &[&forked_sortition_id, &sn.sortition_id];
+
+        // two valid snapshots can't share a burnchain block
+        assert!(db.conn.execute(&copy_snapshot, args).is_err());
+
+        // but an invalidated one can
+        db.conn.execute("UPDATE snapshots SET pox_valid = 0 WHERE sortition_id = ?1", &[&sn.sortition_id]).unwrap();
+        db.conn.execute(&copy_snapshot, args).unwrap();
+        db.conn.execute("UPDATE snapshots SET pox_valid = 1 WHERE sortition_id = ?1", &[&forked_sortition_id]).unwrap();
+        assert!(db.conn.execute("UPDATE snapshots SET pox_valid = 1 WHERE sortition_id = ?1", &[&sn.sortition_id]).is_err());
+    }
+
+    #[test]
+    fn test_set_pox_reward_set() {
+        let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+        let mut db = SortitionDB::connect_test(123, &first_burn_hash).unwrap();
+
+        let burn_header_hash = BurnchainHeaderHash([0x01; 32]);
+        let block_hash = BlockHeaderHash([0x02; 32]);
+        let addrs : Vec<StacksAddress> = (0..3).map(|i| StacksAddress { version: 26, bytes: Hash160([i as u8; 20]) }).collect();
+
+        {
+            let mut tx = db.tx_begin().unwrap();
+            tx.set_pox_reward_set(&burn_header_hash, &block_hash, 1, &addrs).unwrap();
+            tx.set_pox_reward_set(&burn_header_hash, &block_hash, 2, &vec![addrs[2].clone()]).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(SortitionDB::get_pox_reward_set(db.conn(), &burn_header_hash, &block_hash, 1).unwrap(), addrs);
+        assert_eq!(SortitionDB::get_pox_reward_set(db.conn(), &burn_header_hash, &block_hash, 2).unwrap(), vec![addrs[2].clone()]);
+
+        // a recomputed, smaller reward set replaces the old one entirely
+        {
+            let mut tx = db.tx_begin().unwrap();
+            tx.set_pox_reward_set(&burn_header_hash, &block_hash, 1, &vec![addrs[1].clone()]).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(SortitionDB::get_pox_reward_set(db.conn(), &burn_header_hash, &block_hash, 1).unwrap(), vec![addrs[1].clone()]);
+        assert_eq!(SortitionDB::get_pox_reward_set(db.conn(), &burn_header_hash, &block_hash, 2).unwrap(), vec![addrs[2].clone()]);
+
+        {
+            let mut tx = db.tx_begin().unwrap();
+            tx.set_pox_reward_set(&burn_header_hash, &block_hash, 1, &vec![]).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(SortitionDB::get_pox_reward_set(db.conn(), &burn_header_hash, &block_hash, 1).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_pox_id() {
+        let mut pox_id = PoxId::initial();
+        assert_eq!(pox_id.len(), 1);
+        assert_eq!(pox_id.to_string(), "1");
+        assert!(pox_id.has_ith_anchor_block(0));
+        assert!(!pox_id.has_ith_anchor_block(1));
+
+        pox_id.extend_with_not_present_block();
+        pox_id.extend_with_present_block();
+        assert_eq!(pox_id.to_string(), "101");
+        assert!(!pox_id.has_ith_anchor_block(1));
+        assert!(pox_id.has_ith_anchor_block(2));
+        assert_eq!(PoxId::from_bit_string("101").unwrap(), pox_id);
+
+        assert!(PoxId::from_bit_string("").is_none());
+        assert!(PoxId::from_bit_string("10a").is_none());
+
+        // only sortitions outside of the initial PoX fork get a distinct ID
+        let bhh = BurnchainHeaderHash([0x11; 32]);
+        assert_eq!(SortitionId::new(&bhh, &PoxId::initial()), SortitionId(bhh.0.clone()));
+        assert!(SortitionId::new(&bhh, &pox_id) != SortitionId(bhh.0.clone()));
+        assert!(SortitionId::new(&bhh, &pox_id) != SortitionId::new(&bhh, &PoxId::from_bit_string("11").unwrap()));
+    }
+
     #[test]
     fn test_tx_begin_end() {
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
@@ -2039,7 +2662,7 @@ mod tests {
     }
 
     fn test_append_snapshot(db: &mut SortitionDB, next_hash: BurnchainHeaderHash, block_ops: &Vec<BlockstackOperationType>, consumed_leader_keys: &Vec<LeaderKeyRegisterOp>) -> BlockSnapshot {
-        let mut sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let mut sn = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         let mut tx = SortitionHandleTx::begin(db, &sn.sortition_id).unwrap();
 
         let sn_parent = sn.clone();
@@ -2047,7 +2670,7 @@ mod tests {
         sn.burn_header_hash = next_hash;
         sn.block_height += 1;
         sn.num_sortitions += 1;
-        sn.sortition_id = SortitionId::stubbed(&sn.burn_header_hash);
+        sn.sortition_id = SortitionId::new(&sn.burn_header_hash, &PoxId::initial());
 
         let index_root = tx.append_chain_tip_snapshot(&sn_parent, &sn, block_ops, consumed_leader_keys).unwrap();
         sn.index_root = index_root;
@@ -2129,6 +2752,8 @@ mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -2379,7 +3004,7 @@ mod tests {
                     burn_header_timestamp: get_epoch_time_secs(),
                     burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
                     sortition_id,
-                    pox_id: PoxId::stubbed(),
+                    pox_id: PoxId::initial(),
                     parent_burn_header_hash: BurnchainHeaderHash::from_bytes(&[(if i == 0 { 0x10 } else { 0 }) as u8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(if i == 0 { 0xff } else { i - 1 }) as u8]).unwrap(),
                     consensus_hash: ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(i+1) as u8]).unwrap(),
                     ops_hash: OpsHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
@@ -2405,7 +3030,7 @@ mod tests {
             }
         }
 
-        let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
 
         let ch_fresh = ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,255]).unwrap();
         let ch_oldest_fresh = ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(255 - consensus_hash_lifetime) as u8]).unwrap();
@@ -2451,7 +3076,7 @@ mod tests {
                     burn_header_timestamp: get_epoch_time_secs(),
                     burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
                     sortition_id,
-                    pox_id: PoxId::stubbed(),
+                    pox_id: PoxId::initial(),
                     parent_burn_header_hash: BurnchainHeaderHash::from_bytes(&[(if i == 0 { 0x10 } else { 0 }) as u8,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(if i == 0 { 0xff } else { i - 1 }) as u8]).unwrap(),
                     consensus_hash: ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(i+1) as u8]).unwrap(),
                     ops_hash: OpsHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
@@ -2481,7 +3106,7 @@ mod tests {
             }
         }
 
-        let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
 
         for i in 0..256 {
             // should succeed within the conn
@@ -2520,6 +3145,8 @@ mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -2591,7 +3218,7 @@ mod tests {
             stacks_block_accepted: false,
             stacks_block_height: 0,
             arrival_index: 0,
-            pox_id: PoxId::stubbed(),
+            pox_id: PoxId::initial(),
             canonical_stacks_tip_height: 0,
             canonical_stacks_tip_hash: BlockHeaderHash([0u8; 32]),
             canonical_stacks_tip_burn_hash: BurnchainHeaderHash([0u8; 32])
@@ -2615,7 +3242,7 @@ mod tests {
             stacks_block_accepted: false,
             stacks_block_height: 0,
             arrival_index: 0,
-            pox_id: PoxId::stubbed(),
+            pox_id: PoxId::initial(),
             canonical_stacks_tip_height: 0,
             canonical_stacks_tip_hash: BlockHeaderHash([0u8; 32]),
             canonical_stacks_tip_burn_hash: BurnchainHeaderHash([0u8; 32])
@@ -2639,7 +3266,7 @@ mod tests {
             stacks_block_accepted: false,
             stacks_block_height: 0,
             arrival_index: 0,
-            pox_id: PoxId::stubbed(),
+            pox_id: PoxId::initial(),
             canonical_stacks_tip_height: 0,
             canonical_stacks_tip_hash: BlockHeaderHash([0u8; 32]),
             canonical_stacks_tip_burn_hash: BurnchainHeaderHash([0u8; 32])
@@ -2647,7 +3274,7 @@ mod tests {
 
         let mut db = SortitionDB::connect_test(block_height - 2, &first_burn_hash).unwrap();
 
-        let chain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let chain_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
 
         let initial_snapshot = {
             let ic = db.index_handle(&chain_tip.sortition_id);
@@ -2659,14 +3286,14 @@ mod tests {
         assert_eq!(initial_snapshot, first_snapshot);
 
         {
-            let chain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+            let chain_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
             let mut tx = SortitionHandleTx::begin(&mut db, &chain_tip.sortition_id).unwrap();
 
             tx.append_chain_tip_snapshot(&chain_tip, &snapshot_without_sortition, &vec![], &vec![]).unwrap();
             tx.commit().unwrap();
         }
         
-        let chain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let chain_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
 
         let mut next_snapshot = {
             let ic = db.index_handle(&chain_tip.sortition_id);
@@ -2678,14 +3305,14 @@ mod tests {
         assert_eq!(initial_snapshot, next_snapshot);
 
         {
-            let chain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+            let chain_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
             let mut tx = SortitionHandleTx::begin(&mut db, &chain_tip.sortition_id).unwrap();
 
             tx.append_chain_tip_snapshot(&chain_tip, &snapshot_with_sortition, &vec![], &vec![]).unwrap();
             tx.commit().unwrap();
         }
         
-        let chain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let chain_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
 
         let next_snapshot_2 = {
             let ic = db.index_handle(&chain_tip.sortition_id);
@@ -2887,7 +3514,7 @@ mod tests {
             let mut expected_tip = next_snapshot.clone();
             expected_tip.index_root = next_index_root;
 
-            let canonical_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+            let canonical_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
             assert_eq!(canonical_tip, expected_tip);
 
             verify_fork_integrity(&mut db, &canonical_tip.sortition_id);
@@ -2911,7 +3538,7 @@ mod tests {
                 let snapshot_row = 
                     if i % 3 == 0 {
                         BlockSnapshot {
-                            pox_id: PoxId::stubbed(),
+                            pox_id: PoxId::initial(),
                             block_height: i+1,
                             burn_header_timestamp: get_epoch_time_secs(),
                             burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
@@ -2938,7 +3565,7 @@ mod tests {
                         total_burn += 1;
                         total_sortitions += 1;
                         BlockSnapshot {
-                            pox_id: PoxId::stubbed(),
+                            pox_id: PoxId::initial(),
                             block_height: i+1,
                             burn_header_timestamp: get_epoch_time_secs(),
                             burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
@@ -2978,7 +3605,7 @@ mod tests {
             }
         }
         
-        let canonical_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        let canonical_tip = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         let mut cache = BlockHeaderCache::new();
 
         {
@@ -3119,7 +3746,7 @@ mod tests {
         let mut last_snapshot = start_snapshot.clone();
         for i in last_snapshot.block_height..(last_snapshot.block_height + length) {
             let snapshot = BlockSnapshot {
-                pox_id: PoxId::stubbed(),
+                pox_id: PoxId::initial(),
                 block_height: last_snapshot.block_height + 1,
                 burn_header_timestamp: get_epoch_time_secs(),
                 burn_header_hash: BurnchainHeaderHash([(i as u8) | bit_pattern; 32]),
@@ -3176,13 +3803,13 @@ mod tests {
 
             {
                 let mut tx = db.tx_begin().unwrap();
-                tx.set_stacks_block_accepted_stubbed(
+                tx.set_stacks_block_accepted(
                     &burn_header_hash, &parent_stacks_block_hash, &stacks_block_hash, height).unwrap();
                 tx.commit().unwrap();
             }
 
             // chain tip is memoized to the current burn chain tip
-            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(db.conn()).unwrap();
+            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash(db.conn()).unwrap();
             assert_eq!(burn_bhh, burn_header_hash);
             assert_eq!(block_bhh, stacks_block_hash);
         }
@@ -3192,7 +3819,7 @@ mod tests {
         make_fork_run(&mut db, &last_snapshot, 1, 0);
 
         // verify that all Stacks block in this fork can be looked up from this chain tip
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         {
             let ic = db.index_conn();
             for i in 0..5 {
@@ -3207,11 +3834,11 @@ mod tests {
         }
 
         // make a burn fork off of the 5th block
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         make_fork_run(&mut db, &last_snapshot, 5, 0x80);
 
         // chain tip is _still_ memoized to the last materialized chain tip
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x8a; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x04; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x04; 32]));
@@ -3226,13 +3853,13 @@ mod tests {
                 
             {
                 let mut tx = db.tx_begin().unwrap();
-                tx.set_stacks_block_accepted_stubbed(
+                tx.set_stacks_block_accepted(
                     &burn_header_hash, &parent_stacks_block_hash, &stacks_block_hash, *height).unwrap();
                 tx.commit().unwrap();
             }
 
             // chain tip is memoized to the current burn chain tip, since it's the longest stacks fork
-            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(db.conn()).unwrap();
+            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash(db.conn()).unwrap();
             assert_eq!(burn_bhh, burn_header_hash);
             assert_eq!(block_bhh, stacks_block_hash);
 
@@ -3240,7 +3867,7 @@ mod tests {
         }
 
         // chain tip is _still_ memoized to the last materialized chain tip (i.e. stacks block 7)
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x8a; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x87; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x87; 32]));
@@ -3257,13 +3884,13 @@ mod tests {
                 
             {
                 let mut tx = db.tx_begin().unwrap();
-                tx.set_stacks_block_accepted_stubbed(
+                tx.set_stacks_block_accepted(
                     &burn_header_hash, &parent_stacks_block_hash, &stacks_block_hash, *height).unwrap();
                 tx.commit().unwrap();
             }
 
             // chain tip is memoized to the current burn chain tip, since it's the longest stacks fork
-            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(db.conn()).unwrap();
+            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash(db.conn()).unwrap();
             assert_eq!(burn_bhh, last_snapshot.canonical_stacks_tip_burn_hash);
             assert_eq!(block_bhh, last_snapshot.canonical_stacks_tip_hash);
             
@@ -3278,19 +3905,19 @@ mod tests {
                 
             {
                 let mut tx = db.tx_begin().unwrap();
-                tx.set_stacks_block_accepted_stubbed(
+                tx.set_stacks_block_accepted(
                     &burn_header_hash, &parent_stacks_block_hash, &stacks_block_hash, *height).unwrap();
                 tx.commit().unwrap();
             }
 
             // we've overtaken the longest fork with a different longest fork on this burn chain fork
-            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(db.conn()).unwrap();
+            let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash(db.conn()).unwrap();
             assert_eq!(burn_bhh, burn_header_hash);
             assert_eq!(block_bhh, stacks_block_hash);
         }
         
         // canonical stacks chain tip is now stacks block 9
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x8a; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x89; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x89; 32]));
@@ -3309,7 +3936,7 @@ mod tests {
 
         // canonical stacks chain tip is now stacks block 4, since the burn chain fork ending on
         // 0x4b has overtaken the burn chain fork ending on 0x8a
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x04; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x04; 32]));
@@ -3318,12 +3945,12 @@ mod tests {
         // set the stacks block at 0x4b as accepted as the 5th block
         {
             let mut tx = db.tx_begin().unwrap();
-            tx.set_stacks_block_accepted_stubbed(
+            tx.set_stacks_block_accepted(
                 &BurnchainHeaderHash([0x4b; 32]), &BlockHeaderHash([0x04; 32]), &BlockHeaderHash([0x4b; 32]), 5).unwrap();
             tx.commit().unwrap();
         }
         
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x4b; 32]));
@@ -3350,7 +3977,7 @@ mod tests {
         assert_eq!(last_snapshot.canonical_stacks_tip_height, 4);
         
         // doesn't affect canonical chain tip
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x4b; 32]));
@@ -3387,7 +4014,7 @@ mod tests {
         assert_eq!(last_snapshot.canonical_stacks_tip_height, 6);
 
         // insertion on the non-canonical tip doesn't affect canonical chain tip
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x4b; 32]));
@@ -3397,13 +4024,13 @@ mod tests {
         // canonical burn chain tip), on blocks 0x45, 0x46, and 0x47
         {
             let mut tx = db.tx_begin().unwrap();
-            tx.set_stacks_block_accepted_stubbed(
+            tx.set_stacks_block_accepted(
                 &BurnchainHeaderHash([0x45; 32]), &BlockHeaderHash([0x04; 32]), &BlockHeaderHash([0x45; 32]), 5).unwrap();
-            tx.set_stacks_block_accepted_stubbed(
+            tx.set_stacks_block_accepted(
                 &BurnchainHeaderHash([0x46; 32]), &BlockHeaderHash([0x45; 32]), &BlockHeaderHash([0x46; 32]), 6).unwrap();
-            tx.set_stacks_block_accepted_stubbed(
+            tx.set_stacks_block_accepted(
                 &BurnchainHeaderHash([0x47; 32]), &BlockHeaderHash([0x46; 32]), &BlockHeaderHash([0x47; 32]), 7).unwrap();
-            tx.set_stacks_block_accepted_stubbed(
+            tx.set_stacks_block_accepted(
                 &BurnchainHeaderHash([0x48; 32]), &BlockHeaderHash([0x47; 32]), &BlockHeaderHash([0x48; 32]), 8).unwrap();
             tx.commit().unwrap();
         }
@@ -3422,7 +4049,7 @@ mod tests {
         // burn:  0x01, 0x02, 0x03, 0x04, 0x45, 0x46, 0x47, 0x48, 0x29, 0x2a
 
         // new stacks tip is the 9th block added on burn chain tipped by 0x4b
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x4b; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x48; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x48; 32]));
@@ -3454,7 +4081,7 @@ mod tests {
         // stx:      1,    2,    3,    4,                            5,    6
         // burn:  0x01, 0x02, 0x03, 0x04, 0x45, 0x46, 0x47, 0x48, 0x29, 0x2a, 0x2b, 0x2c
 
-        last_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn()).unwrap();
+        last_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn()).unwrap();
         assert_eq!(last_snapshot.burn_header_hash, BurnchainHeaderHash([0x2c; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_burn_hash, BurnchainHeaderHash([0x48; 32]));
         assert_eq!(last_snapshot.canonical_stacks_tip_hash, BlockHeaderHash([0x48; 32]));
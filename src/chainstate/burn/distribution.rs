@@ -457,6 +457,8 @@ mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -481,6 +483,8 @@ mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -505,6 +509,8 @@ mod tests {
             memo: vec![0x80],
 
             burn_fee: 23456,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
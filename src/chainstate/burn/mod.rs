@@ -226,7 +226,7 @@ impl ConsensusHash {
     /// for the resulting consensus hash, and the geometric series of previous consensus
     /// hashes.  Note that prev_consensus_hashes should be in order from most-recent to
     /// least-recent.
-    pub fn from_ops(burn_header_hash: &BurnchainHeaderHash, opshash: &OpsHash, total_burn: u64, prev_consensus_hashes: &Vec<ConsensusHash>) -> ConsensusHash {
+    pub fn from_ops(burn_header_hash: &BurnchainHeaderHash, opshash: &OpsHash, pox_id: &PoxId, total_burn: u64, prev_consensus_hashes: &Vec<ConsensusHash>) -> ConsensusHash {
         // NOTE: unlike stacks v1, we calculate the next consensus hash
         // simply as a hash-chain of the new ops hash, the sequence of 
         // previous consensus hashes, and the total burn that went into this
@@ -234,6 +234,8 @@ impl ConsensusHash {
         // We also make it so the consensus hash commits to both the transactions and the block
         // that contains them (so two different blocks with the same Blockstack-relevant transactions
         // in the same order will have two different consensus hashes, as they should).
+        // It also commits to the PoX fork the block was evaluated in, so nodes that disagree on
+        // which anchor blocks are known will have different consensus hashes.
 
         let burn_bytes = total_burn.to_be_bytes();
         let result;
@@ -249,6 +251,9 @@ impl ConsensusHash {
 
             // ops hash...
             hasher.input(opshash.as_bytes());
+
+            // PoX fork...
+            hasher.input(pox_id.to_string().as_bytes());
             
             // total burn amount on this fork...
             hasher.input(&burn_bytes);
@@ -298,9 +303,9 @@ impl ConsensusHash {
     }
 
     /// Make a new consensus hash, given the ops hash and parent block data
-    pub fn from_parent_block_data(ic: &SortitionHandleConn, opshash: &OpsHash, parent_block_height: u64, first_block_height: u64, this_block_hash: &BurnchainHeaderHash, total_burn: u64) -> Result<ConsensusHash, db_error> {
+    pub fn from_parent_block_data(ic: &SortitionHandleConn, opshash: &OpsHash, parent_block_height: u64, first_block_height: u64, this_block_hash: &BurnchainHeaderHash, pox_id: &PoxId, total_burn: u64) -> Result<ConsensusHash, db_error> {
         let prev_consensus_hashes = ConsensusHash::get_prev_consensus_hashes(ic, parent_block_height, first_block_height)?;
-        Ok(ConsensusHash::from_ops(this_block_hash, opshash, total_burn, &prev_consensus_hashes))
+        Ok(ConsensusHash::from_ops(this_block_hash, opshash, pox_id, total_burn, &prev_consensus_hashes))
     }
 
     /// raw consensus hash
@@ -357,7 +362,7 @@ mod tests {
                     burn_header_timestamp: get_epoch_time_secs(),
                     burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
                     sortition_id: SortitionId([0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]),
-                    pox_id: PoxId::stubbed(),
+                    pox_id: PoxId::initial(),
                     parent_burn_header_hash: BurnchainHeaderHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(if i == 0 { 0xff } else { i-1 }) as u8]).unwrap(),
                     consensus_hash: ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
                     ops_hash: OpsHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
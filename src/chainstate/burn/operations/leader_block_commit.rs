@@ -64,6 +64,9 @@ use util::vrf::VRFPrivateKey;
 
 use chainstate::stacks::index::storage::TrieFileStorage;
 
+/// How many PoX reward addresses a block-commit pays during a reward phase
+pub const OUTPUTS_PER_COMMIT: usize = 2;
+
 // return type from parse_data below
 struct ParsedData {
     block_header_hash: BlockHeaderHash,
@@ -87,6 +90,7 @@ impl LeaderBlockCommitOp {
             memo: vec![0x00],
             burn_fee: burn_fee,
             input: input.clone(),
+            commit_outs: vec![],
             block_header_hash: block_header_hash.clone(),
 
             // to be filled in 
@@ -107,6 +111,7 @@ impl LeaderBlockCommitOp {
             memo: vec![],
             burn_fee: burn_fee,
             input: input.clone(),
+            commit_outs: vec![],
             block_header_hash: block_header_hash.clone(),
 
             // to be filled in
@@ -177,14 +182,35 @@ impl LeaderBlockCommitOp {
             return Err(op_error::InvalidInput);
         }
 
-        // outputs[0] should be the burn output
-        if !outputs[0].address.is_burn() {
-            // wrong burn output
-            warn!("Invalid tx: burn output missing (got {:?})", outputs[0]);
-            return Err(op_error::ParseError);
-        }
+        // outputs[0] should be the burn output, or the first of OUTPUTS_PER_COMMIT outputs that each
+        // pay the same amount to a PoX reward address.
+        let (commit_outs, burn_fee) =
+            if outputs[0].address.is_burn() {
+                (vec![], outputs[0].amount)
+            }
+            else {
+                if outputs.len() < OUTPUTS_PER_COMMIT {
+                    warn!("Invalid tx: burn output missing (got {:?}), and too few PoX outputs", outputs[0]);
+                    return Err(op_error::ParseError);
+                }
+
+                let pox_outputs = &outputs[0..OUTPUTS_PER_COMMIT];
+                if pox_outputs.iter().any(|o| o.address.is_burn() || o.amount != outputs[0].amount) {
+                    warn!("Invalid tx: PoX outputs must pay the same amount to {} reward addresses (got {:?})", OUTPUTS_PER_COMMIT, pox_outputs);
+                    return Err(op_error::ParseError);
+                }
+
+                let commit_outs = pox_outputs.iter().map(|o| o.address.clone()).collect();
+                let total = match outputs[0].amount.checked_mul(OUTPUTS_PER_COMMIT as u64) {
+                    Some(total) => total,
+                    None => {
+                        warn!("Invalid tx: PoX outputs overflow");
+                        return Err(op_error::ParseError);
+                    }
+                };
+                (commit_outs, total)
+            };
 
-        let burn_fee = outputs[0].amount;
         if burn_fee == 0 {
             // didn't burn
             warn!("Invalid tx: no burn quantity");
@@ -235,6 +261,7 @@ impl LeaderBlockCommitOp {
 
             burn_fee: burn_fee,
             input: inputs[0].clone(),
+            commit_outs: commit_outs,
 
             txid: tx.txid(),
             vtxindex: tx.vtxindex(),
@@ -282,7 +309,7 @@ impl BlockstackOperation for LeaderBlockCommitOp {
         LeaderBlockCommitOp::parse_from_tx(block_header.block_height, &block_header.block_hash, tx)
     }
         
-    fn check(&self, _burnchain: &Burnchain, tx: &SortitionHandleConn) -> Result<(), op_error> {
+    fn check(&self, burnchain: &Burnchain, tx: &SortitionHandleConn) -> Result<(), op_error> {
         let leader_key_block_height = self.key_block_ptr as u64;
         let parent_block_height = self.parent_block_ptr as u64;
         
@@ -306,6 +333,26 @@ impl BlockstackOperation for LeaderBlockCommitOp {
             return Err(op_error::BlockCommitPredatesGenesis);
         }
 
+        /////////////////////////////////////////////////////////////////////////////////////
+        // During a reward phase with a known reward set, this tx must pay the PoX reward
+        // addresses chosen for this block.  Otherwise, it must burn.
+        /////////////////////////////////////////////////////////////////////////////////////
+
+        match tx.get_pox_recipients(burnchain, self.block_height)? {
+            Some(recipients) => {
+                if self.commit_outs != recipients {
+                    warn!("Invalid block commit: expected PoX outputs to {:?}, got {:?}", &recipients, &self.commit_outs);
+                    return Err(op_error::BlockCommitBadOutputs);
+                }
+            },
+            None => {
+                if self.commit_outs.len() > 0 {
+                    warn!("Invalid block commit: must burn, but pays PoX reward addresses {:?}", &self.commit_outs);
+                    return Err(op_error::BlockCommitBadOutputs);
+                }
+            }
+        }
+
         /////////////////////////////////////////////////////////////////////////////////////
         // Block must be unique in this burnchain fork
         /////////////////////////////////////////////////////////////////////////////////////
@@ -394,9 +441,11 @@ mod tests {
     use burnchains::bitcoin::blocks::BitcoinBlockParser;
     use burnchains::Txid;
     use burnchains::BLOCKSTACK_MAGIC_MAINNET;
+    use burnchains::PoxConstants;
     use burnchains::BurnchainBlockHeader;
 
-    use burnchains::bitcoin::BitcoinNetworkType;
+    use burnchains::bitcoin::{BitcoinNetworkType, BitcoinTransaction, BitcoinTxInput, BitcoinTxOutput, BitcoinInputType};
+    use burnchains::bitcoin::address::BitcoinAddressType;
 
     use address::AddressHashMode;
 
@@ -465,6 +514,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -553,6 +604,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_pox_outputs() {
+        let burn_header_hash = BurnchainHeaderHash([0u8; 32]);
+        let header = BurnchainBlockHeader {
+            block_height: 0x71706363,
+            block_hash: burn_header_hash.clone(),
+            parent_block_hash: burn_header_hash.clone(),
+            num_txs: 1,
+            timestamp: get_epoch_time_secs()
+        };
+
+        let data = hex_bytes("2222222222222222222222222222222222222222222222222222222222222222333333333333333333333333333333333333333333333333333333333333333340414243505160616263707180").unwrap();
+        let input = BitcoinTxInput {
+            keys: vec![BitcoinPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap()],
+            num_required: 1,
+            in_type: BitcoinInputType::Standard
+        };
+        let reward_addr_1 = BitcoinAddress::from_bytes(BitcoinNetworkType::Testnet, BitcoinAddressType::PublicKeyHash, &vec![0x01; 20]).unwrap();
+        let reward_addr_2 = BitcoinAddress::from_bytes(BitcoinNetworkType::Testnet, BitcoinAddressType::ScriptHash, &vec![0x02; 20]).unwrap();
+        let change_addr = BitcoinAddress::from_bytes(BitcoinNetworkType::Testnet, BitcoinAddressType::PublicKeyHash, &vec![0x03; 20]).unwrap();
+
+        let make_tx = |outputs: Vec<(&BitcoinAddress, u64)>| {
+            BurnchainTransaction::Bitcoin(BitcoinTransaction {
+                txid: Txid([0x01; 32]),
+                vtxindex: 1,
+                opcode: Opcodes::LeaderBlockCommit as u8,
+                data: data.clone(),
+                inputs: vec![input.clone()],
+                outputs: outputs.into_iter().map(|(addr, units)| BitcoinTxOutput { address: addr.clone(), units: units }).collect()
+            })
+        };
+
+        // pays two reward addresses the same amount
+        let op = LeaderBlockCommitOp::from_tx(&header, &make_tx(vec![(&reward_addr_1, 5000), (&reward_addr_2, 5000), (&change_addr, 12345)])).unwrap();
+        assert_eq!(op.burn_fee, 10000);
+        assert_eq!(op.commit_outs, vec![StacksAddress::from_bitcoin_address(&reward_addr_1), StacksAddress::from_bitcoin_address(&reward_addr_2)]);
+
+        // unequal reward outputs
+        assert!(LeaderBlockCommitOp::from_tx(&header, &make_tx(vec![(&reward_addr_1, 5000), (&reward_addr_2, 5001), (&change_addr, 12345)])).is_err());
+
+        // too few reward outputs
+        assert!(LeaderBlockCommitOp::from_tx(&header, &make_tx(vec![(&reward_addr_1, 5000)])).is_err());
+    }
+
     #[test]
     fn test_check() {
         let first_block_height = 121;
@@ -581,7 +676,8 @@ mod tests {
             consensus_hash_lifetime: 24,
             stable_confirmations: 7,
             first_block_height: first_block_height,
-            first_block_hash: first_burn_hash.clone()
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
         
         let leader_key_1 = LeaderKeyRegisterOp { 
@@ -619,6 +715,8 @@ mod tests {
             memo: vec![0x80],
 
             burn_fee: 12345,
+            commit_outs: vec![],
+
             input: BurnchainSigner {
                 public_keys: vec![
                     StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -675,7 +773,7 @@ mod tests {
                     burn_header_timestamp: get_epoch_time_secs(),
                     burn_header_hash: block_header_hashes[i].clone(),
                     sortition_id: SortitionId(block_header_hashes[i as usize].0.clone()),
-                    pox_id: PoxId::stubbed(),
+                    pox_id: PoxId::initial(),
                     parent_burn_header_hash: prev_snapshot.burn_header_hash.clone(),
                     consensus_hash: ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(i+1) as u8]).unwrap(),
                     ops_hash: OpsHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
@@ -720,6 +818,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -747,6 +847,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -774,6 +876,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -801,6 +905,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -828,6 +934,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -855,6 +963,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("03984286096373539ae529bd997c92792d4e5b5967be72979a42f587a625394116").unwrap(),
@@ -882,6 +992,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 0,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -909,6 +1021,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -936,6 +1050,8 @@ mod tests {
                     memo: vec![0x80],
 
                     burn_fee: 12345,
+                    commit_outs: vec![],
+
                     input: BurnchainSigner {
                         public_keys: vec![
                             StacksPublicKey::from_hex("02d8015134d9db8178ac93acbc43170a2f20febba5087a5b0437058765ad5133d0").unwrap(),
@@ -961,7 +1077,7 @@ mod tests {
                 num_txs: 1,
                 timestamp: get_epoch_time_secs()
             };
-            let ic = db.index_handle(&SortitionId::stubbed(&fixture.op.burn_header_hash));
+            let ic = db.index_handle(&SortitionId::new(&fixture.op.burn_header_hash, &PoxId::initial()));
             assert_eq!(format!("{:?}", &fixture.res), format!("{:?}", &fixture.op.check(&burnchain, &ic)));
         }
     }
@@ -256,6 +256,7 @@ pub mod tests {
     use burnchains::Txid;
     use burnchains::BurnchainBlockHeader;
     use burnchains::BLOCKSTACK_MAGIC_MAINNET;
+    use burnchains::PoxConstants;
 
     use deps::bitcoin::network::serialize::deserialize;
     use deps::bitcoin::blockdata::transaction::Transaction;
@@ -454,7 +455,8 @@ pub mod tests {
             consensus_hash_lifetime: 24,
             stable_confirmations: 7,
             first_block_height: first_block_height,
-            first_block_hash: first_burn_hash.clone()
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
         
         let mut db = SortitionDB::connect_test(first_block_height, &first_burn_hash).unwrap();
@@ -505,7 +507,7 @@ pub mod tests {
                     burn_header_timestamp: get_epoch_time_secs(),
                     burn_header_hash: block_header_hashes[i as usize].clone(),
                     sortition_id: SortitionId(block_header_hashes[i as usize].0.clone()),
-                    pox_id: PoxId::stubbed(),
+                    pox_id: PoxId::initial(),
                     parent_burn_header_hash: prev_snapshot.burn_header_hash.clone(),
                     consensus_hash: ConsensusHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,(i+1) as u8]).unwrap(),
                     ops_hash: OpsHash::from_bytes(&[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,i as u8]).unwrap(),
@@ -590,7 +592,7 @@ pub mod tests {
                 num_txs: 1,
                 timestamp: get_epoch_time_secs()
             };
-            let ic = db.index_handle(&SortitionId::stubbed(&fixture.op.burn_header_hash));
+            let ic = db.index_handle(&SortitionId::new(&fixture.op.burn_header_hash, &PoxId::initial()));
             assert_eq!(format!("{:?}", &fixture.res), format!("{:?}", &fixture.op.check(&burnchain, &ic)));
         }
     }
@@ -74,6 +74,7 @@ pub enum Error {
     BlockCommitLeaderKeyAlreadyUsed,
     BlockCommitNoParent,
     BlockCommitBadInput,
+    BlockCommitBadOutputs,
     
     // all the things that can go wrong with leader key register
     LeaderKeyAlreadyRegistered,
@@ -97,6 +98,7 @@ impl fmt::Display for Error {
             Error::BlockCommitLeaderKeyAlreadyUsed => write!(f, "Block commit register key already used"),
             Error::BlockCommitNoParent => write!(f, "Block commit parent does not exist"),
             Error::BlockCommitBadInput => write!(f, "Block commit tx input does not match register key tx output"),
+            Error::BlockCommitBadOutputs => write!(f, "Block commit tx outputs do not pay the expected PoX reward addresses"),
 
             Error::LeaderKeyAlreadyRegistered => write!(f, "Leader key has already been registered"),
             Error::LeaderKeyBadConsensusHash => write!(f, "Leader key has an invalid consensus hash"),
@@ -120,6 +122,7 @@ impl error::Error for Error {
             Error::BlockCommitLeaderKeyAlreadyUsed => None,
             Error::BlockCommitNoParent => None,
             Error::BlockCommitBadInput => None,
+            Error::BlockCommitBadOutputs => None,
 
             Error::LeaderKeyAlreadyRegistered => None,
             Error::LeaderKeyBadConsensusHash => None,
@@ -147,8 +150,9 @@ pub struct LeaderBlockCommitOp {
     pub key_vtxindex: u16,                  // offset in the block where the leader key can be found
     pub memo: Vec<u8>,                      // extra unused byte
 
-    pub burn_fee: u64,                      // how many burn tokens (e.g. satoshis) were destroyed to produce this block
+    pub burn_fee: u64,                      // how many burn tokens (e.g. satoshis) were spent to produce this block (burnt or paid to reward addresses)
     pub input: BurnchainSigner,             // burn chain keys that must match the key registration
+    pub commit_outs: Vec<StacksAddress>,    // PoX reward addresses paid by this commit (empty if it burns)
 
     // common to all transactions
     pub txid: Txid,                         // transaction ID
@@ -89,8 +89,8 @@ impl BlockSnapshot {
             canonical_stacks_tip_height: 0,
             canonical_stacks_tip_hash: FIRST_STACKS_BLOCK_HASH.clone(),
             canonical_stacks_tip_burn_hash: FIRST_BURNCHAIN_BLOCK_HASH.clone(),
-            sortition_id: SortitionId::stubbed(first_burn_header_hash),
-            pox_id: PoxId([0; 32]),
+            sortition_id: SortitionId::new(first_burn_header_hash, &PoxId::initial()),
+            pox_id: PoxId::initial(),
         }
     }
 
@@ -166,7 +166,7 @@ impl BlockSnapshot {
     }
 
     /// Make the snapshot struct for the case where _no sortition_ takes place
-    fn make_snapshot_no_sortition(ic: &SortitionHandleConn, sortition_id: &SortitionId, parent_snapshot: &BlockSnapshot, block_header: &BurnchainBlockHeader, first_block_height: u64, burn_total: u64, sortition_hash: &SortitionHash, txids: &Vec<Txid>) -> Result<BlockSnapshot, db_error> {
+    fn make_snapshot_no_sortition(ic: &SortitionHandleConn, sortition_id: &SortitionId, pox_id: &PoxId, parent_snapshot: &BlockSnapshot, block_header: &BurnchainBlockHeader, first_block_height: u64, burn_total: u64, sortition_hash: &SortitionHash, txids: &Vec<Txid>) -> Result<BlockSnapshot, db_error> {
         let block_height = block_header.block_height;
         let block_hash = block_header.block_hash.clone();
         let parent_block_hash = block_header.parent_block_hash.clone();
@@ -176,7 +176,7 @@ impl BlockSnapshot {
 
         let ops_hash = OpsHash::from_txids(txids);
         let ch = ConsensusHash::from_parent_block_data(
-            ic, &ops_hash, block_height - 1, first_block_height, &block_hash, burn_total)?;
+            ic, &ops_hash, block_height - 1, first_block_height, &block_hash, pox_id, burn_total)?;
 
         debug!("SORTITION({}): NO BLOCK CHOSEN", block_height);
 
@@ -186,7 +186,7 @@ impl BlockSnapshot {
             burn_header_timestamp: block_header.timestamp,
             parent_burn_header_hash: parent_block_hash,
             consensus_hash: ch,
-            pox_id: parent_snapshot.pox_id,
+            pox_id: pox_id.clone(),
             ops_hash: ops_hash,
             total_burn: burn_total,
             sortition: false,
@@ -237,7 +237,7 @@ impl BlockSnapshot {
         if burn_dist.len() == 0 {
             // no burns happened
             debug!("No burns happened in block {} {:?}", block_height, &block_hash);
-            return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
+            return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, my_pox_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
         }
 
         // NOTE: this only counts burns from leader block commits and user burns that match them.
@@ -247,7 +247,7 @@ impl BlockSnapshot {
                 if total == 0 {
                     // no one burned, so no sortition
                     debug!("No transactions submitted burns in block {} {:?}", block_height, &block_hash);
-                    return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
+                    return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, my_pox_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
                 }
                 else {
                     total
@@ -256,7 +256,7 @@ impl BlockSnapshot {
             None => {
                 // overflow -- treat as 0 (no sortition)
                 warn!("Burn count exceeds maximum threshold");
-                return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
+                return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, my_pox_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
             }
         };
 
@@ -270,7 +270,7 @@ impl BlockSnapshot {
             None => {
                 // overflow.  Deny future sortitions
                 warn!("Cumulative sortition burn has overflown.  Subsequent sortitions will be denied.");
-                return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
+                return BlockSnapshot::make_snapshot_no_sortition(ic, my_sortition_id, my_pox_id, parent_snapshot, block_header, first_block_height, last_burn_total, &next_sortition_hash, &txids);
             }
         };
 
@@ -283,7 +283,7 @@ impl BlockSnapshot {
         let final_sortition_hash = next_sortition_hash.mix_VRF_seed(&winning_block.new_seed);
         let next_ops_hash = OpsHash::from_txids(&txids);
         let next_ch = ConsensusHash::from_parent_block_data(
-            ic, &next_ops_hash, block_height - 1, first_block_height, &block_hash, next_burn_total)?;
+            ic, &next_ops_hash, block_height - 1, first_block_height, &block_hash, my_pox_id, next_burn_total)?;
 
         debug!("SORTITION({}): WINNER IS {:?} (from {:?})", block_height, &winning_block.block_header_hash, &winning_block.txid);
 
@@ -347,7 +347,8 @@ mod test {
             consensus_hash_lifetime: 24,
             stable_confirmations: 7,
             first_block_height: first_block_height,
-            first_block_hash: first_burn_hash.clone()
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let db = SortitionDB::connect_test(first_block_height, &first_burn_hash).unwrap();
@@ -363,8 +364,8 @@ mod test {
         let initial_snapshot = SortitionDB::get_first_block_snapshot(db.conn()).unwrap();
 
         let snapshot_no_transactions = {
-            let pox_id = PoxId::stubbed();
-            let sort_id = SortitionId::stubbed(&empty_block_header.block_hash);
+            let pox_id = PoxId::initial();
+            let sort_id = SortitionId::new(&empty_block_header.block_hash, &PoxId::initial());
             let ic = db.index_handle(&sort_id);
             let sn = BlockSnapshot::make_snapshot(&ic, &burnchain, &sort_id, &pox_id, &initial_snapshot,
                                                   &empty_block_header, &vec![], &vec![]).unwrap();
@@ -386,8 +387,8 @@ mod test {
         };
 
         let snapshot_no_burns = {
-            let sort_id = SortitionId::stubbed(&empty_block_header.block_hash);
-            let pox_id = PoxId::stubbed();
+            let sort_id = SortitionId::new(&empty_block_header.block_hash, &PoxId::initial());
+            let pox_id = PoxId::initial();
             let ic = db.index_handle(&sort_id);
             let sn = BlockSnapshot::make_snapshot(&ic, &burnchain, &sort_id, &pox_id, &initial_snapshot, &empty_block_header,
                                                   &vec![empty_burn_point.clone()], &vec![key.txid.clone()]).unwrap();
@@ -0,0 +1,462 @@
+/*
+ copyright: (c) 2013-2020 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::convert::TryFrom;
+
+use address::address_serialization_flag;
+
+use burnchains::{Address, Burnchain};
+
+use chainstate::stacks::Error;
+use chainstate::stacks::StacksAddress;
+use chainstate::stacks::events::StacksTransactionEvent;
+use chainstate::stacks::db::{StacksChainState, ClarityTx, STACKS_BOOT_CODE_CONTRACT_ADDRESS};
+
+use util::hash::Hash160;
+
+use vm::clarity::{ClarityConnection, ClarityTransactionConnection};
+use vm::clarity::Error as clarity_error;
+use vm::costs::LimitedCostTracker;
+use vm::representations::ContractName;
+use vm::types::{Value, PrincipalData, StandardPrincipalData, QualifiedContractIdentifier};
+
+pub const BOOT_CODE_POX_CONTRACT_NAME : &'static str = "pox-stacking";
+pub const BOOT_CODE_POX : &'static str = include_str!("pox.clar");
+
+/// Identifier of a boot code contract
+pub fn boot_code_id(name: &str) -> QualifiedContractIdentifier {
+    let boot_code_address = StacksAddress::from_string(&STACKS_BOOT_CODE_CONTRACT_ADDRESS.to_string()).unwrap();
+    QualifiedContractIdentifier::new(StandardPrincipalData::from(boot_code_address),
+                                     ContractName::try_from(name.to_string()).expect("FATAL: invalid boot-code contract name"))
+}
+
+impl StacksChainState {
+    /// Tell the PoX contract how the burnchain is divided into reward cycles.  Only works in
+    /// the boot block, so call this from the `in_boot_block` closure given to `open_and_exec()`.
+    pub fn set_pox_burnchain_parameters(clarity_tx: &mut ClarityTx, burnchain: &Burnchain) -> Result<(), Error> {
+        let pox_contract = boot_code_id(BOOT_CODE_POX_CONTRACT_NAME);
+        let boot_code_principal = PrincipalData::Standard(pox_contract.issuer.clone());
+        let args = vec![
+            Value::UInt(burnchain.first_block_height as u128),
+            Value::UInt(burnchain.pox_constants.reward_cycle_length as u128),
+            Value::UInt(burnchain.pox_constants.prepare_length as u128)
+        ];
+
+        let (result, _, _) = clarity_tx.connection().as_transaction(|clarity| {
+            clarity.run_contract_call(&boot_code_principal, &pox_contract, "set-burnchain-parameters", &args, |_, _| false)
+        }).map_err(Error::ClarityError)?;
+
+        match result {
+            Value::Response(ref data) if data.committed => Ok(()),
+            _ => Err(Error::ClarityError(clarity_error::BadTransaction(format!("Failed to set PoX burnchain parameters: {:?}", &result))))
+        }
+    }
+
+    /// Evaluate a read-only expression against the PoX contract
+    fn eval_pox_read_only(clarity_tx: &mut ClarityTx, code: &str) -> Result<Value, Error> {
+        let pox_contract = boot_code_id(BOOT_CODE_POX_CONTRACT_NAME);
+        let sender = PrincipalData::Standard(pox_contract.issuer.clone());
+        clarity_tx.connection().with_readonly_clarity_env(sender, LimitedCostTracker::new_max_limit(), |env| env.eval_read_only(&pox_contract, code))
+            .map_err(|e| Error::ClarityError(clarity_error::Interpreter(e)))
+    }
+
+    /// Get the PoX reward addresses registered in this chain state for the reward cycle after the
+    /// one that contains the given burnchain block height, in the order they were registered.
+    /// Returns the reward cycle and its addresses.
+    pub fn get_next_reward_cycle_addresses(clarity_tx: &mut ClarityTx, mainnet: bool, burn_block_height: u64) -> Result<(u64, Vec<StacksAddress>), Error> {
+        let reward_cycle = match StacksChainState::eval_pox_read_only(clarity_tx, &format!("(+ u1 (burn-height-to-reward-cycle u{}))", burn_block_height))? {
+            Value::UInt(reward_cycle) => reward_cycle,
+            v => {
+                error!("PoX contract returned a malformed reward cycle: {:?}", &v);
+                return Err(Error::InvalidChainstateDB);
+            }
+        };
+
+        let num_addresses = match StacksChainState::eval_pox_read_only(clarity_tx, &format!("(get-reward-set-size u{})", reward_cycle))? {
+            Value::UInt(num_addresses) => num_addresses,
+            v => {
+                error!("PoX contract returned a malformed reward set size: {:?}", &v);
+                return Err(Error::InvalidChainstateDB);
+            }
+        };
+
+        let mut addresses = vec![];
+        for i in 0..num_addresses {
+            let pox_addr = StacksChainState::eval_pox_read_only(clarity_tx, &format!("(get pox-addr (get-reward-set-pox-address u{} u{}))", reward_cycle, i))?;
+            let address = match pox_addr {
+                Value::Optional(ref data) => match data.data {
+                    Some(ref pox_addr_tuple) => StacksChainState::pox_addr_to_stacks_address(pox_addr_tuple, mainnet),
+                    None => None
+                },
+                _ => None
+            }.ok_or_else(|| {
+                error!("PoX contract returned a malformed reward address: {:?}", &pox_addr);
+                Error::InvalidChainstateDB
+            })?;
+
+            addresses.push(address);
+        }
+
+        Ok((reward_cycle as u64, addresses))
+    }
+
+    /// Convert a PoX contract reward address -- a tuple of an address hash mode and the
+    /// address's hash bytes -- into a Stacks address.
+    fn pox_addr_to_stacks_address(pox_addr: &Value, mainnet: bool) -> Option<StacksAddress> {
+        let tuple = match *pox_addr {
+            Value::Tuple(ref tuple) => tuple,
+            _ => {
+                return None;
+            }
+        };
+
+        match (tuple.get("version"), tuple.get("hashbytes")) {
+            (Ok(Value::Buffer(version)), Ok(Value::Buffer(hashbytes))) => {
+                if version.data.len() != 1 || hashbytes.data.len() != 20 {
+                    return None;
+                }
+
+                let hash_mode = address_serialization_flag(version.data[0]);
+                let version = if mainnet { hash_mode.to_version_mainnet() } else { hash_mode.to_version_testnet() };
+                Hash160::from_bytes(&hashbytes.data)
+                    .map(|bytes| StacksAddress { version, bytes })
+            },
+            _ => None
+        }
+    }
+}
+
+/// Called after a transaction's contract code has run successfully.  Every successful
+/// `stack-stx` call to the PoX contract -- whether the transaction made it, or another contract
+/// did -- emits its lock-up as a `print` event.  Lock up each Stacker's uSTX until the unlock
+/// height in its event.  The contract has already checked that the Stackers can afford their
+/// lock-ups; if one can't be applied anyway, the transaction is invalid.
+pub fn handle_pox_lockup_events(clarity_tx: &mut ClarityTransactionConnection, events: &[StacksTransactionEvent]) -> Result<(), Error> {
+    let pox_contract = boot_code_id(BOOT_CODE_POX_CONTRACT_NAME);
+    for event in events.iter() {
+        let lockup = match event {
+            StacksTransactionEvent::SmartContractEvent(ref data) if data.key.0 == pox_contract && data.key.1 == "print" => &data.value,
+            _ => continue
+        };
+
+        let (stacker, amount, unlock_height) = match *lockup {
+            Value::Tuple(ref tuple) => {
+                match (tuple.get("stacker"), tuple.get("lock-amount"), tuple.get("unlock-burn-height")) {
+                    (Ok(Value::Principal(stacker)), Ok(Value::UInt(amount)), Ok(Value::UInt(unlock_height))) if *unlock_height <= u64::max_value() as u128 => {
+                        (stacker.clone(), *amount, *unlock_height as u64)
+                    },
+                    _ => {
+                        return Err(Error::InvalidStacksTransaction(format!("PoX contract emitted a malformed lock-up: {:?}", lockup), false));
+                    }
+                }
+            },
+            _ => {
+                return Err(Error::InvalidStacksTransaction(format!("PoX contract emitted a malformed lock-up: {:?}", lockup), false));
+            }
+        };
+
+        debug!("Lock {} uSTX of {} until burnchain block {}", amount, &stacker, unlock_height);
+        clarity_tx.with_clarity_db(|db| db.lock_account_stx(&stacker, amount, unlock_height).map_err(clarity_error::Interpreter))
+            .map_err(|e| Error::InvalidStacksTransaction(format!("Failed to lock {} uSTX of {} until burnchain block {}: {:?}", amount, &stacker, unlock_height, &e), false))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+
+    use burnchains::{BurnchainHeaderHash, PoxConstants};
+    use chainstate::burn::BlockHeaderHash;
+    use chainstate::stacks::*;
+    use chainstate::stacks::db::*;
+    use chainstate::stacks::db::test::chainstate_path;
+    use chainstate::stacks::index::TrieHash;
+    use core::*;
+    use util::hash::Hash160;
+    use vm::costs::ExecutionCost;
+    use vm::database::STXBalance;
+    use vm::types::TupleData;
+
+    fn instantiate_pox_chainstate(test_name: &str, balances: Vec<(StacksAddress, u64)>) -> StacksChainState {
+        let path = chainstate_path(test_name);
+        match fs::metadata(&path) {
+            Ok(_) => {
+                fs::remove_dir_all(&path).unwrap();
+            },
+            Err(_) => {}
+        };
+
+        // 5-block reward cycles, starting at burnchain block 0
+        let mut burnchain = Burnchain::default_unittest(0, &FIRST_BURNCHAIN_BLOCK_HASH);
+        burnchain.pox_constants = PoxConstants::new(5, 1, 1);
+
+        let initial_balances = Some(balances.into_iter().map(|(addr, balance)| (PrincipalData::from(addr), balance)).collect());
+        StacksChainState::open_and_exec(false, 0x80000000, &path, initial_balances,
+                                        |clarity_tx| StacksChainState::set_pox_burnchain_parameters(clarity_tx, &burnchain).unwrap(),
+                                        ExecutionCost::max_value()).unwrap()
+    }
+
+    fn make_pox_addr(addr: &StacksAddress) -> Value {
+        Value::Tuple(TupleData::from_data(vec![
+            ("version".into(), Value::buff_from(vec![0x00]).unwrap()),
+            ("hashbytes".into(), Value::buff_from(addr.bytes.as_bytes().to_vec()).unwrap())
+        ]).unwrap())
+    }
+
+    fn make_signed_tx(privk: &StacksPrivateKey, nonce: u64, payload: TransactionPayload) -> StacksTransaction {
+        let auth = TransactionAuth::from_p2pkh(privk).unwrap();
+        let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+        tx.chain_id = 0x80000000;
+        tx.post_condition_mode = TransactionPostConditionMode::Allow;
+        tx.set_fee_rate(0);
+        tx.set_origin_nonce(nonce);
+
+        let mut signer = StacksTransactionSigner::new(&tx);
+        signer.sign_origin(privk).unwrap();
+        signer.get_tx().unwrap()
+    }
+
+    fn make_stack_stx(privk: &StacksPrivateKey, nonce: u64, amount: u128, pox_addr: &StacksAddress, lock_period: u128) -> StacksTransaction {
+        let payload = TransactionPayload::ContractCall(TransactionContractCall {
+            address: StacksAddress::from_string(&STACKS_BOOT_CODE_CONTRACT_ADDRESS.to_string()).unwrap(),
+            contract_name: ContractName::try_from(BOOT_CODE_POX_CONTRACT_NAME.to_string()).unwrap(),
+            function_name: "stack-stx".into(),
+            function_args: vec![Value::UInt(amount), make_pox_addr(pox_addr), Value::UInt(lock_period)]
+        });
+        make_signed_tx(privk, nonce, payload)
+    }
+
+    fn make_token_transfer(privk: &StacksPrivateKey, nonce: u64, recipient: &StacksAddress, amount: u64) -> StacksTransaction {
+        let payload = TransactionPayload::TokenTransfer(recipient.clone().into(), amount, TokenTransferMemo([0u8; 34]));
+        make_signed_tx(privk, nonce, payload)
+    }
+
+    /// Pretend that the given Stacks block was mined in the given burnchain block
+    fn set_burn_header_height(chainstate: &mut StacksChainState, parent_block_height: u64, burn_hash: &BurnchainHeaderHash, burn_height: u32) -> BlockHeaderHash {
+        let mut header = StacksBlockHeader::genesis_block_header();
+        header.total_work.work = parent_block_height + 1;
+
+        let tip_info = StacksHeaderInfo {
+            anchored_header: header.clone(),
+            microblock_tail: None,
+            block_height: parent_block_height + 1,
+            index_root: TrieHash([0u8; 32]),
+            burn_header_hash: burn_hash.clone(),
+            burn_header_height: burn_height,
+            burn_header_timestamp: 0
+        };
+
+        let mut tx = chainstate.headers_tx_begin().unwrap();
+        StacksChainState::insert_stacks_block_header(&mut tx, &tip_info).unwrap();
+        tx.commit().unwrap();
+
+        header.block_hash()
+    }
+
+    fn eval_pox(conn: &mut ClarityTx, code: &str) -> Value {
+        conn.connection().as_transaction(|tx| tx.eval_read_only(&boot_code_id(BOOT_CODE_POX_CONTRACT_NAME), code)).unwrap()
+    }
+
+    #[test]
+    fn test_pox_lockup_and_unlock() {
+        let privk = StacksPrivateKey::from_hex("6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001").unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recv_addr = StacksAddress { version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG, bytes: Hash160([0xfe; 20]) };
+        let principal = PrincipalData::from(addr.clone());
+
+        let mut chainstate = instantiate_pox_chainstate("pox-lockup-and-unlock", vec![(addr.clone(), 1000)]);
+
+        // first block is in burnchain block 1, which is in reward cycle 0
+        let burn_hash_1 = BurnchainHeaderHash([1u8; 32]);
+        let block_hash_1 = {
+            let mut header = StacksBlockHeader::genesis_block_header();
+            header.total_work.work = 1;
+            header.block_hash()
+        };
+        {
+            let mut conn = chainstate.block_begin_at_burn_height(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &burn_hash_1, 1, &block_hash_1);
+            assert_eq!(eval_pox(&mut conn, "(current-pox-reward-cycle)"), Value::UInt(0));
+
+            // lock 600 uSTX for two reward cycles (1 and 2), so they unlock at the start of cycle 3
+            let (_, receipt) = StacksChainState::process_transaction(&mut conn, &make_stack_stx(&privk, 0, 600, &recv_addr, 2), false).unwrap();
+            match receipt.result {
+                Value::Response(ref data) => assert!(data.committed),
+                _ => panic!("not a response")
+            };
+
+            let balance = conn.with_clarity_db_readonly(|db| db.get_account_stx_balance_info(&principal));
+            assert_eq!(balance.amount_unlocked, 400);
+            assert_eq!(balance.amount_locked, 600);
+            assert_eq!(balance.unlock_height, 15);
+
+            let account = StacksChainState::get_account(&mut conn, &principal);
+            assert_eq!(account.stx_balance, 400);
+            assert_eq!(account.nonce, 1);
+
+            // can't stack twice
+            let (_, receipt) = StacksChainState::process_transaction(&mut conn, &make_stack_stx(&privk, 1, 100, &recv_addr, 1), false).unwrap();
+            assert_eq!(receipt.result, Value::error(Value::Int(3)).unwrap());
+
+            // locked tokens can't be spent
+            assert!(StacksChainState::process_transaction(&mut conn, &make_token_transfer(&privk, 2, &recv_addr, 500), false).is_err());
+            StacksChainState::process_transaction(&mut conn, &make_token_transfer(&privk, 2, &recv_addr, 100), false).unwrap();
+            assert_eq!(StacksChainState::get_account(&mut conn, &principal).stx_balance, 300);
+
+            // and the reward sets of the next two cycles both have the PoX address
+            for cycle in 1..3 {
+                assert_eq!(eval_pox(&mut conn, &format!("(get-reward-set-size u{})", cycle)), Value::UInt(1));
+                assert_eq!(eval_pox(&mut conn, &format!("(get-total-ustx-stacked u{})", cycle)), Value::UInt(600));
+                assert_eq!(eval_pox(&mut conn, &format!("(get pox-addr (get-reward-set-pox-address u{} u0))", cycle)),
+                           Value::some(make_pox_addr(&recv_addr)).unwrap());
+            }
+            assert_eq!(eval_pox(&mut conn, "(get-reward-set-size u3)"), Value::UInt(0));
+            assert_eq!(eval_pox(&mut conn, "(get-total-ustx-stacked u3)"), Value::UInt(0));
+            assert!(eval_pox(&mut conn, &format!("(get-stacker-info '{})", &addr)) != Value::none());
+
+            conn.commit_block();
+        }
+
+        set_burn_header_height(&mut chainstate, 0, &burn_hash_1, 1);
+
+        // the next block is mined after a run of burnchain blocks without sortitions.  At the
+        // end of reward cycle 2, the uSTX are still locked...
+        let burn_hash_2 = BurnchainHeaderHash([2u8; 32]);
+        let mut conn = chainstate.block_begin_at_burn_height(&burn_hash_1, &block_hash_1, &burn_hash_2, 14, &BlockHeaderHash([2u8; 32]));
+        assert_eq!(eval_pox(&mut conn, "burn-block-height"), Value::UInt(14));
+        assert_eq!(eval_pox(&mut conn, "(current-pox-reward-cycle)"), Value::UInt(2));
+        assert!(eval_pox(&mut conn, &format!("(get-stacker-info '{})", &addr)) != Value::none());
+        assert_eq!(StacksChainState::get_account(&mut conn, &principal).stx_balance, 300);
+        assert!(StacksChainState::process_transaction(&mut conn, &make_token_transfer(&privk, 3, &recv_addr, 800), false).is_err());
+        conn.rollback_block();
+
+        // if the burnchain block height isn't known, it can't be made up
+        let mut conn = chainstate.block_begin(&burn_hash_1, &block_hash_1, &burn_hash_2, &BlockHeaderHash([2u8; 32]));
+        assert!(conn.connection().as_transaction(|tx| tx.eval_read_only(&boot_code_id(BOOT_CODE_POX_CONTRACT_NAME), "burn-block-height")).is_err());
+        assert!(StacksChainState::process_transaction(&mut conn, &make_token_transfer(&privk, 3, &recv_addr, 100), false).is_err());
+        conn.rollback_block();
+
+        // ...and at the start of reward cycle 3, the lock-up has expired
+        let mut conn = chainstate.block_begin_at_burn_height(&burn_hash_1, &block_hash_1, &burn_hash_2, 15, &BlockHeaderHash([2u8; 32]));
+        assert_eq!(eval_pox(&mut conn, "burn-block-height"), Value::UInt(15));
+        assert_eq!(eval_pox(&mut conn, "(current-pox-reward-cycle)"), Value::UInt(3));
+        assert_eq!(eval_pox(&mut conn, &format!("(get-stacker-info '{})", &addr)), Value::none());
+        assert_eq!(StacksChainState::get_account(&mut conn, &principal).stx_balance, 900);
+
+        StacksChainState::process_transaction(&mut conn, &make_token_transfer(&privk, 3, &recv_addr, 800), false).unwrap();
+        let balance = conn.with_clarity_db_readonly(|db| db.get_account_stx_balance_info(&principal));
+        assert_eq!(balance, STXBalance::initial(100));
+
+        conn.commit_block();
+    }
+
+    #[test]
+    fn test_pox_stack_stx_from_contract() {
+        let privk = StacksPrivateKey::from_hex("6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001").unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recv_addr = StacksAddress { version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG, bytes: Hash160([0xfe; 20]) };
+        let stacker_id = QualifiedContractIdentifier::new(StandardPrincipalData::from(addr.clone()), ContractName::try_from("stacker".to_string()).unwrap());
+        let stacker = PrincipalData::Contract(stacker_id.clone());
+
+        // a contract that stacks its own uSTX, and one that stacks them and then aborts
+        let stacker_code = format!("
+            (define-public (stack (amount-ustx uint) (pox-addr (tuple (version (buff 1)) (hashbytes (buff 20)))) (lock-period uint))
+                (as-contract (contract-call? '{}.{} stack-stx amount-ustx pox-addr lock-period)))
+            (define-public (stack-and-abort (amount-ustx uint) (pox-addr (tuple (version (buff 1)) (hashbytes (buff 20)))) (lock-period uint))
+                (begin
+                    (unwrap-panic (stack amount-ustx pox-addr lock-period))
+                    (asserts! false (err 99))
+                    (ok true)))", STACKS_BOOT_CODE_CONTRACT_ADDRESS, BOOT_CODE_POX_CONTRACT_NAME);
+
+        let mut chainstate = instantiate_pox_chainstate("pox-stack-stx-from-contract", vec![(addr.clone(), 2000)]);
+        let mut conn = chainstate.block_begin_at_burn_height(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &BurnchainHeaderHash([1u8; 32]), 1, &BlockHeaderHash([1u8; 32]));
+
+        let deploy = TransactionPayload::SmartContract(TransactionSmartContract {
+            name: stacker_id.name.clone(),
+            code_body: StacksString::from_str(&stacker_code).unwrap()
+        });
+        StacksChainState::process_transaction(&mut conn, &make_signed_tx(&privk, 0, deploy), false).unwrap();
+
+        let fund = TransactionPayload::TokenTransfer(stacker.clone(), 1000, TokenTransferMemo([0u8; 34]));
+        StacksChainState::process_transaction(&mut conn, &make_signed_tx(&privk, 1, fund), false).unwrap();
+
+        let make_call = |nonce: u64, function_name: &str| {
+            let payload = TransactionPayload::ContractCall(TransactionContractCall {
+                address: addr.clone(),
+                contract_name: stacker_id.name.clone(),
+                function_name: function_name.into(),
+                function_args: vec![Value::UInt(600), make_pox_addr(&recv_addr), Value::UInt(2)]
+            });
+            make_signed_tx(&privk, nonce, payload)
+        };
+
+        // an aborted lock-up leaves the reward set and the contract's balance alone
+        let (_, receipt) = StacksChainState::process_transaction(&mut conn, &make_call(2, "stack-and-abort"), false).unwrap();
+        assert_eq!(receipt.result, Value::error(Value::Int(99)).unwrap());
+        let balance = conn.with_clarity_db_readonly(|db| db.get_account_stx_balance_info(&stacker));
+        assert_eq!(balance, STXBalance::initial(1000));
+        assert_eq!(eval_pox(&mut conn, "(get-reward-set-size u1)"), Value::UInt(0));
+
+        // stacking through as-contract locks the contract's uSTX, just like a direct call would
+        let (_, receipt) = StacksChainState::process_transaction(&mut conn, &make_call(3, "stack"), false).unwrap();
+        match receipt.result {
+            Value::Response(ref data) => assert!(data.committed),
+            _ => panic!("not a response")
+        };
+        let balance = conn.with_clarity_db_readonly(|db| db.get_account_stx_balance_info(&stacker));
+        assert_eq!(balance.amount_unlocked, 400);
+        assert_eq!(balance.amount_locked, 600);
+        assert_eq!(balance.unlock_height, 15);
+        assert_eq!(eval_pox(&mut conn, "(get-reward-set-size u1)"), Value::UInt(1));
+        assert_eq!(eval_pox(&mut conn, "(get-total-ustx-stacked u1)"), Value::UInt(600));
+
+        conn.commit_block();
+    }
+
+    #[test]
+    fn test_pox_stack_stx_rejects_bad_lockups() {
+        let privk = StacksPrivateKey::from_hex("6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001").unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recv_addr = StacksAddress { version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG, bytes: Hash160([0xfe; 20]) };
+        let principal = PrincipalData::from(addr.clone());
+
+        let mut chainstate = instantiate_pox_chainstate("pox-stack-stx-rejects-bad-lockups", vec![(addr.clone(), 1000)]);
+        let mut conn = chainstate.block_begin_at_burn_height(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &BurnchainHeaderHash([1u8; 32]), 1, &BlockHeaderHash([1u8; 32]));
+
+        // insufficient funds, zero amount, and lock periods out of range
+        let bad_lockups = vec![(1001, 1, 1), (0, 1, 18), (100, 0, 2), (100, 13, 2)];
+        for (i, (amount, lock_period, err_code)) in bad_lockups.into_iter().enumerate() {
+            let (_, receipt) = StacksChainState::process_transaction(&mut conn, &make_stack_stx(&privk, i as u64, amount, &recv_addr, lock_period), false).unwrap();
+            assert_eq!(receipt.result, Value::error(Value::Int(err_code)).unwrap());
+        }
+
+        let balance = conn.with_clarity_db_readonly(|db| db.get_account_stx_balance_info(&principal));
+        assert_eq!(balance, STXBalance::initial(1000));
+        assert_eq!(eval_pox(&mut conn, "(get-reward-set-size u1)"), Value::UInt(0));
+
+        // can only configure the burnchain in the boot block
+        let burnchain = Burnchain::default_unittest(0, &FIRST_BURNCHAIN_BLOCK_HASH);
+        assert!(StacksChainState::set_pox_burnchain_parameters(&mut conn, &burnchain).is_err());
+        assert_eq!(eval_pox(&mut conn, "(get reward-cycle-length (unwrap-panic (get-pox-info)))"), Value::UInt(5));
+
+        conn.commit_block();
+    }
+}
@@ -128,7 +128,12 @@ impl MinerReward {
 impl StacksChainState {
     pub fn get_account<T: ClarityConnection>(clarity_tx: &mut T, principal: &PrincipalData) -> StacksAccount {
         clarity_tx.with_clarity_db_readonly(|ref mut db| {
-            let stx_balance = db.get_account_stx_balance(principal);
+            // if the burnchain block height isn't known, then tokens that are still locked
+            // can't be told apart from unlocked ones, so only count the ones known to be spendable.
+            let stx_balance = match db.get_account_stx_balance(principal) {
+                Ok(stx_balance) => stx_balance,
+                Err(_) => db.get_account_stx_balance_info(principal).amount_unlocked
+            };
             let nonce = db.get_account_nonce(principal);
             StacksAccount {
                 principal: principal.clone(),
@@ -157,9 +162,10 @@ impl StacksChainState {
     /// Called each time a transaction is invoked from this principal, to e.g.
     /// debit the STX-denominated tx fee or transfer/burn STX.
     /// DOES NOT UPDATE THE NONCE
-    pub fn account_debit(clarity_tx: &mut ClarityTransactionConnection, principal: &PrincipalData, amount: u64) {
+    /// Fails if the account's spendable balance can't be determined.
+    pub fn account_debit(clarity_tx: &mut ClarityTransactionConnection, principal: &PrincipalData, amount: u64) -> Result<(), Error> {
         clarity_tx.with_clarity_db(|ref mut db| {
-            let cur_balance = db.get_account_stx_balance(principal);
+            let cur_balance = db.get_account_stx_balance(principal)?;
             
             // last line of defense: if we don't have sufficient funds, panic.
             // This should be checked by the block validation logic.
@@ -168,18 +174,18 @@ impl StacksChainState {
             }
 
             let final_balance = cur_balance - (amount as u128);
-            db.set_account_stx_balance(principal, final_balance);
+            db.set_account_stx_balance(principal, final_balance)?;
             Ok(())
-        }).expect("FATAL: failed to debit account")
+        }).map_err(Error::ClarityError)
     }
 
     /// Called each time a transaction sends STX to this principal.
     /// No nonce update is needed, since the transfer action is not taken by the principal.
     pub fn account_credit(clarity_tx: &mut ClarityTransactionConnection, principal: &PrincipalData, amount: u64) {
         clarity_tx.with_clarity_db(|ref mut db| {
-            let cur_balance = db.get_account_stx_balance(principal);
+            let cur_balance = db.get_account_stx_balance(principal)?;
             let final_balance = cur_balance.checked_add(amount as u128).expect("FATAL: account balance overflow");
-            db.set_account_stx_balance(principal, final_balance as u128);
+            db.set_account_stx_balance(principal, final_balance as u128)?;
             info!("{} credited: {} uSTX", principal, final_balance);
             Ok(())
         }).expect("FATAL: failed to credit account")
@@ -583,6 +589,7 @@ mod test {
         new_tip.microblock_tail = None;
         new_tip.block_height = parent_header_info.block_height + 1;
         new_tip.burn_header_hash = BurnchainHeaderHash(Sha512Trunc256Sum::from_data(&parent_header_info.burn_header_hash.0).0);
+        new_tip.burn_header_height = parent_header_info.burn_header_height + 1;
 
         block_reward.parent_burn_header_hash = parent_header_info.burn_header_hash.clone();
         block_reward.parent_block_hash = parent_header_info.anchored_header.block_hash().clone();
@@ -600,6 +607,7 @@ mod test {
                                                 &parent_header_info.burn_header_hash, 
                                                 &new_tip.anchored_header, 
                                                 &new_tip.burn_header_hash, 
+                                                new_tip.burn_header_height, 
                                                 new_tip.burn_header_timestamp, 
                                                 new_tip.microblock_tail.clone(), 
                                                 &block_reward, 
@@ -122,6 +122,7 @@ pub struct StagingMicroblock {
 #[derive(Debug, Clone, PartialEq)]
 pub struct StagingBlock {
     pub burn_header_hash: BurnchainHeaderHash,
+    pub burn_header_height: u32,
     pub burn_header_timestamp: u64,
     pub anchored_block_hash: BlockHeaderHash,
     pub parent_burn_header_hash: BurnchainHeaderHash,
@@ -165,7 +166,9 @@ pub enum MemPoolRejection {
     BadAddressVersionByte,
     NoCoinbaseViaMempool,
     NoSuchChainTip(BurnchainHeaderHash,BlockHeaderHash),
-    ConflictingNonceInMempool,
+    ReplaceByFeeTooLow(u64, u64),
+    MemPoolFull(u64, u64),
+    DoesNotFitInMemPool(u64, u64),
     TooMuchChaining,
     DBError(db_error),
     Other(String),
@@ -205,7 +208,18 @@ impl MemPoolRejection {
             NoSuchPublicFunction => ("NoSuchPublicFunction", None),
             BadFunctionArgument(e) => ("BadFunctionArgument",
                                        Some(json!({"message": e.to_string()}))),
-            ConflictingNonceInMempool => ("ConflictingNonceInMempool", None),
+            ReplaceByFeeTooLow(actual, expected) => ("ReplaceByFeeTooLow",
+                                                     Some(json!({
+                                                         "expected": expected,
+                                                         "actual": actual}))),
+            MemPoolFull(actual, expected) => ("MemPoolFull",
+                                              Some(json!({
+                                                  "expected": expected,
+                                                  "actual": actual}))),
+            DoesNotFitInMemPool(max_txs, max_bytes) => ("DoesNotFitInMemPool",
+                                                        Some(json!({
+                                                            "max_txs": max_txs,
+                                                            "max_bytes": max_bytes}))),
             ContractAlreadyExists(id) => ("ContractAlreadyExists",
                                           Some(json!({ "contract_identifier": id.to_string() }))),
             PoisonMicroblocksDoNotConflict => ("PoisonMicroblocksDoNotConflict", None),
@@ -281,6 +295,7 @@ impl FromRow<StagingBlock> for StagingBlock {
         let anchored_block_hash : BlockHeaderHash = BlockHeaderHash::from_column(row, "anchored_block_hash")?;
         let parent_anchored_block_hash : BlockHeaderHash = BlockHeaderHash::from_column(row, "parent_anchored_block_hash")?;
         let burn_header_hash : BurnchainHeaderHash = BurnchainHeaderHash::from_column(row, "burn_header_hash")?;
+        let burn_header_height = u64::from_column(row, "burn_header_height")? as u32;
         let burn_header_timestamp = u64::from_column(row, "burn_header_timestamp")?;
         let parent_burn_header_hash: BurnchainHeaderHash = BurnchainHeaderHash::from_column(row, "parent_burn_header_hash")?;
         let parent_microblock_hash : BlockHeaderHash = BlockHeaderHash::from_column(row, "parent_microblock_hash")?;
@@ -302,6 +317,7 @@ impl FromRow<StagingBlock> for StagingBlock {
             anchored_block_hash,
             parent_anchored_block_hash,
             burn_header_hash,
+            burn_header_height,
             burn_header_timestamp,
             parent_burn_header_hash,
             parent_microblock_hash,
@@ -421,6 +437,7 @@ const STACKS_BLOCK_INDEX_SQL : &'static [&'static str]= &[
     CREATE TABLE staging_blocks(anchored_block_hash TEXT NOT NULL,
                                 parent_anchored_block_hash TEXT NOT NULL,
                                 burn_header_hash TEXT NOT NULL,
+                                burn_header_height INT NOT NULL,
                                 burn_header_timestamp INT NOT NULL,
                                 parent_burn_header_hash TEXT NOT NULL,
                                 parent_microblock_hash TEXT NOT NULL,
@@ -1098,7 +1115,11 @@ impl StacksChainState {
 
     pub fn get_parent_burn_header_hash(sort_ic: &SortitionDBConn, parent_block_hash: &BlockHeaderHash,
                                        my_burn_header_hash: &BurnchainHeaderHash) -> Result<Option<BurnchainHeaderHash>, Error> {
-        let sort_handle = SortitionHandleConn::open_reader_stubbed(sort_ic, my_burn_header_hash)?;
+        let sort_handle = match SortitionHandleConn::open_reader_at_burn_header_hash(sort_ic, my_burn_header_hash) {
+            Ok(handle) => handle,
+            Err(db_error::NotFoundError) => return Ok(None),
+            Err(e) => return Err(Error::DBError(e))
+        };
 
         // find all blocks that we have that could be this block's parent
         let sql = "SELECT * FROM snapshots WHERE winning_stacks_block_hash = ?1";
@@ -1124,7 +1145,11 @@ impl StacksChainState {
             }
         };
         
-        let sort_handle = SortitionHandleConn::open_reader_stubbed(sort_ic, burn_header_hash)?;
+        let sort_handle = match SortitionHandleConn::open_reader_at_burn_header_hash(sort_ic, burn_header_hash) {
+            Ok(handle) => handle,
+            Err(db_error::NotFoundError) => return Ok(None),
+            Err(e) => return Err(Error::DBError(e))
+        };
 
         // find all blocks that we have that could be this block's parent
         let sql = "SELECT * FROM snapshots WHERE winning_stacks_block_hash = ?1";
@@ -1146,7 +1171,7 @@ impl StacksChainState {
     /// Store a preprocessed block, queuing it up for subsequent processing.
     /// The caller should at least verify that the block is attached to some fork in the burn
     /// chain.
-    fn store_staging_block<'a>(tx: &mut BlocksDBTx<'a>, burn_hash: &BurnchainHeaderHash, burn_header_timestamp: u64, block: &StacksBlock, parent_burn_header_hash: &BurnchainHeaderHash, commit_burn: u64, sortition_burn: u64) -> Result<(), Error> {
+    fn store_staging_block<'a>(tx: &mut BlocksDBTx<'a>, burn_hash: &BurnchainHeaderHash, burn_header_height: u32, burn_header_timestamp: u64, block: &StacksBlock, parent_burn_header_hash: &BurnchainHeaderHash, commit_burn: u64, sortition_burn: u64) -> Result<(), Error> {
         debug!("Store anchored block {}/{}, parent in {}", burn_hash, block.block_hash(), parent_burn_header_hash);
         assert!(commit_burn < i64::max_value() as u64);
         assert!(sortition_burn < i64::max_value() as u64);
@@ -1176,6 +1201,7 @@ impl StacksChainState {
                    (anchored_block_hash, \
                    parent_anchored_block_hash, \
                    burn_header_hash, \
+                   burn_header_height, \
                    burn_header_timestamp, \
                    parent_burn_header_hash, \
                    parent_microblock_hash, \
@@ -1188,11 +1214,12 @@ impl StacksChainState {
                    commit_burn, \
                    sortition_burn, \
                    index_block_hash) \
-                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)";
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)";
         let args: &[&dyn ToSql] = &[
             &block_hash,
             &block.header.parent_block,
             &burn_hash,
+            &burn_header_height,
             &u64_to_sql(burn_header_timestamp)?,
             &parent_burn_header_hash,
             &block.header.parent_microblock,
@@ -1629,7 +1656,7 @@ impl StacksChainState {
             // mark this block as processed in the burn db too
             match sort_tx_opt {
                 Some(ref mut sort_tx) => {
-                    sort_tx.set_stacks_block_accepted_stubbed(burn_hash, &block.parent_anchored_block_hash, &block.anchored_block_hash, block.height)?;
+                    sort_tx.set_stacks_block_accepted(burn_hash, &block.parent_anchored_block_hash, &block.anchored_block_hash, block.height)?;
                 }
                 None => {
                     if !cfg!(test) {
@@ -2269,7 +2296,10 @@ impl StacksChainState {
     pub fn preprocess_anchored_block(&mut self, sort_ic: &SortitionDBConn, burn_header_hash: &BurnchainHeaderHash, burn_header_timestamp: u64, block: &StacksBlock, parent_burn_header_hash: &BurnchainHeaderHash) -> Result<bool, Error> {
         debug!("preprocess anchored block {}/{}", burn_header_hash, block.block_hash());
 
-        let sort_handle = SortitionHandleConn::open_reader_stubbed(sort_ic, burn_header_hash)?;
+        let sort_handle = SortitionHandleConn::open_reader_at_burn_header_hash(sort_ic, burn_header_hash)?;
+        let burn_header_height = SortitionDB::get_block_snapshot_by_burn_header_hash(sort_ic, burn_header_hash)?
+            .ok_or(db_error::NotFoundError)?
+            .block_height as u32;
 
         // already in queue or already processed?
         let index_block_hash = StacksBlockHeader::make_index_block_hash(burn_header_hash, &block.block_hash());
@@ -2313,7 +2343,7 @@ impl StacksChainState {
         debug!("Storing staging block");
 
         // queue block up for processing
-        StacksChainState::store_staging_block(&mut block_tx, burn_header_hash, burn_header_timestamp, &block, parent_burn_header_hash, commit_burn, sortition_burn)?;
+        StacksChainState::store_staging_block(&mut block_tx, burn_header_hash, burn_header_height, burn_header_timestamp, &block, parent_burn_header_hash, commit_burn, sortition_burn)?;
 
         // store users who burned for this block so they'll get rewarded if we process it
         StacksChainState::store_staging_block_user_burn_supports(&mut block_tx, burn_header_hash, &block.block_hash(), &user_burns)?;
@@ -2763,6 +2793,7 @@ impl StacksChainState {
                         clarity_instance: &'a mut ClarityInstance,
                         parent_chain_tip: &StacksHeaderInfo, 
                         chain_tip_burn_header_hash: &BurnchainHeaderHash,
+                        chain_tip_burn_header_height: u32,
                         chain_tip_burn_header_timestamp: u64,
                         block: &StacksBlock, 
                         microblocks: &Vec<StacksMicroblock>,  // parent microblocks 
@@ -2781,7 +2812,7 @@ impl StacksChainState {
             StacksChainState::find_mature_miner_rewards(&mut chainstate_tx.headers_tx, parent_chain_tip, Some(chainstate_tx.miner_payment_cache))?
         };
 
-        let (scheduled_miner_reward, txs_receipts, microblock_execution_cost, block_execution_cost) = {
+        let (scheduled_miner_reward, txs_receipts, microblock_execution_cost, block_execution_cost, (pox_reward_cycle, pox_reward_set)) = {
             let (parent_burn_header_hash, parent_block_hash) = 
                 if block.is_first_mined() {
                     // has to be the sentinal hashes if this block has no parent
@@ -2811,7 +2842,7 @@ impl StacksChainState {
                        last_microblock_hash, last_microblock_seq, block.block_hash(), block.header.parent_microblock, block.header.parent_microblock_sequence);
             }
             
-            let mut clarity_tx = StacksChainState::chainstate_block_begin(chainstate_tx, clarity_instance, &parent_burn_header_hash, &parent_block_hash, &MINER_BLOCK_BURN_HEADER_HASH, &MINER_BLOCK_HEADER_HASH);
+            let mut clarity_tx = StacksChainState::chainstate_block_begin(chainstate_tx, clarity_instance, &parent_burn_header_hash, &parent_block_hash, &MINER_BLOCK_BURN_HEADER_HASH, chain_tip_burn_header_height, &MINER_BLOCK_HEADER_HASH);
 
             // process microblock stream
             let (microblock_fees, microblock_burns, mut microblock_txs_receipts) = match StacksChainState::process_microblocks_transactions(&mut clarity_tx, &microblocks) {
@@ -2865,6 +2896,17 @@ impl StacksChainState {
             }
 
             debug!("Reached state root {}", root_hash);
+
+            // remember the next reward cycle's PoX reward set, in case this block becomes its anchor block
+            let next_reward_set = match StacksChainState::get_next_reward_cycle_addresses(&mut clarity_tx, mainnet, chain_tip_burn_header_height as u64) {
+                Ok(next_reward_set) => next_reward_set,
+                Err(e) => {
+                    warn!("Failed to read the PoX reward set of block {}: {:?}", block.block_hash(), &e);
+
+                    clarity_tx.rollback_block();
+                    return Err(e);
+                }
+            };
             
             // good to go!
             clarity_tx.commit_to_block(chain_tip_burn_header_hash, &block.block_hash());
@@ -2886,7 +2928,7 @@ impl StacksChainState {
 
             txs_receipts.append(&mut microblock_txs_receipts);
 
-            (scheduled_miner_reward, txs_receipts, microblock_cost, block_cost)
+            (scheduled_miner_reward, txs_receipts, microblock_cost, block_cost, next_reward_set)
         };
 
         let microblock_tail_opt = match microblocks.len() {
@@ -2899,6 +2941,7 @@ impl StacksChainState {
                                                     &parent_chain_tip.burn_header_hash,
                                                     &block.header,
                                                     chain_tip_burn_header_hash,
+                                                    chain_tip_burn_header_height,
                                                     chain_tip_burn_header_timestamp,
                                                     microblock_tail_opt,
                                                     &scheduled_miner_reward,
@@ -2909,7 +2952,9 @@ impl StacksChainState {
             header: new_tip, 
             tx_receipts: txs_receipts,
             parent_microblocks_cost: microblock_execution_cost,
-            anchored_block_cost: block_execution_cost
+            anchored_block_cost: block_execution_cost,
+            pox_reward_cycle: pox_reward_cycle,
+            pox_reward_set: pox_reward_set
         };
 
         Ok(epoch_receipt)
@@ -3083,6 +3128,7 @@ impl StacksChainState {
                                                  clarity_instance, 
                                                  &parent_block_header_info, 
                                                  &next_staging_block.burn_header_hash, 
+                                                 next_staging_block.burn_header_height,
                                                  next_staging_block.burn_header_timestamp,
                                                  &block,
                                                  &next_microblocks,
@@ -3133,6 +3179,9 @@ impl StacksChainState {
         }
 
         StacksChainState::set_block_processed(&mut chainstate_tx.blocks_tx, Some(sort_tx), &epoch_receipt.header.burn_header_hash, &epoch_receipt.header.anchored_header.block_hash(), true)?;
+        sort_tx.set_pox_reward_set(&epoch_receipt.header.burn_header_hash, &epoch_receipt.header.anchored_header.block_hash(),
+                                   epoch_receipt.pox_reward_cycle, &epoch_receipt.pox_reward_set)
+            .map_err(Error::DBError)?;
        
         chainstate_tx.commit()
             .map_err(Error::DBError)?;
@@ -3225,7 +3274,7 @@ impl StacksChainState {
     /// (i.e. arbitrarily).  The staging block will be returned, but no block data will be filled
     /// in.
     pub fn get_stacks_chain_tip(&self, sortdb: &SortitionDB) -> Result<Option<StagingBlock>, Error> {
-        let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(&sortdb.conn)?;
+        let (burn_bhh, block_bhh) = SortitionDB::get_canonical_stacks_chain_tip_hash(&sortdb.conn)?;
         let sql = "SELECT * FROM staging_blocks WHERE processed = 1 AND orphaned = 0 AND burn_header_hash = ?1 AND anchored_block_hash = ?2";
         let args : &[&dyn ToSql] = &[&burn_bhh, &block_bhh];
         query_row(&self.blocks_db, sql, args).map_err(Error::DBError)
@@ -3276,6 +3325,15 @@ impl StacksChainState {
         })
     }
 
+    /// Get an account's state as of the given chain tip.  Used by the mempool to check whether
+    /// chained transactions can still be paid for.
+    pub fn get_account_at_tip(&mut self, current_burn: &BurnchainHeaderHash, current_block: &BlockHeaderHash, principal: &PrincipalData) -> StacksAccount {
+        let current_tip = StacksChainState::get_parent_index_block(current_burn, current_block);
+        self.with_read_only_clarity_tx(&current_tip, |conn| {
+            StacksChainState::get_account(conn, principal)
+        })
+    }
+
     /// Given an outstanding clarity connection, can we append the tx to the chain state?
     /// Used when mining transactions.
     pub fn can_include_tx<T: ClarityConnection>(mempool: &DBConn, clarity_connection: &mut T,
@@ -3620,7 +3678,9 @@ pub mod test {
 
     pub fn store_staging_block(chainstate: &mut StacksChainState, burn_header: &BurnchainHeaderHash, burn_header_timestamp: u64, block: &StacksBlock, parent_burn_header: &BurnchainHeaderHash, commit_burn: u64, sortition_burn: u64) {
         let mut tx = chainstate.blocks_tx_begin().unwrap();
-        StacksChainState::store_staging_block(&mut tx, burn_header, burn_header_timestamp, block, parent_burn_header, commit_burn, sortition_burn).unwrap();
+        // these tests mine one Stacks block per burnchain block
+        let burn_header_height = block.header.total_work.work as u32;
+        StacksChainState::store_staging_block(&mut tx, burn_header, burn_header_height, burn_header_timestamp, block, parent_burn_header, commit_burn, sortition_burn).unwrap();
         tx.commit().unwrap();
         
         let index_block_hash = StacksBlockHeader::make_index_block_hash(burn_header, &block.block_hash());
@@ -46,6 +46,7 @@ use chainstate::stacks::*;
 use chainstate::stacks::events::*;
 use chainstate::stacks::db::accounts::*;
 use chainstate::stacks::db::blocks::*;
+use chainstate::stacks::boot::{BOOT_CODE_POX, BOOT_CODE_POX_CONTRACT_NAME};
 use chainstate::stacks::index::{
     TrieHash,
     MARFValue
@@ -159,6 +160,7 @@ pub struct StacksHeaderInfo {
     pub block_height: u64,
     pub index_root: TrieHash,
     pub burn_header_hash: BurnchainHeaderHash,
+    pub burn_header_height: u32,
     pub burn_header_timestamp: u64
 }
 
@@ -167,7 +169,9 @@ pub struct StacksEpochReceipt {
     pub header: StacksHeaderInfo, 
     pub tx_receipts: Vec<StacksTransactionReceipt>,
     pub parent_microblocks_cost: ExecutionCost,
-    pub anchored_block_cost: ExecutionCost
+    pub anchored_block_cost: ExecutionCost,
+    pub pox_reward_cycle: u64,                  // reward cycle after the one this block was mined in
+    pub pox_reward_set: Vec<StacksAddress>      // reward addresses registered for it in this block's chain state
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -188,6 +192,7 @@ impl StacksHeaderInfo {
             block_height: 0,
             index_root: root_hash,
             burn_header_hash: FIRST_BURNCHAIN_BLOCK_HASH.clone(),
+            burn_header_height: 0,
             burn_header_timestamp: FIRST_BURNCHAIN_BLOCK_TIMESTAMP
         }
     }
@@ -218,6 +223,7 @@ impl FromRow<StacksHeaderInfo> for StacksHeaderInfo {
         let block_height = u64::from_column(row, "block_height")?;
         let index_root = TrieHash::from_column(row, "index_root")?;
         let burn_header_hash = BurnchainHeaderHash::from_column(row, "burn_header_hash")?;
+        let burn_header_height = u64::from_column(row, "burn_header_height")? as u32;
         let burn_header_timestamp = u64::from_column(row, "burn_header_timestamp")?;
         let stacks_header = StacksBlockHeader::from_row(row)?;
 
@@ -231,6 +237,7 @@ impl FromRow<StacksHeaderInfo> for StacksHeaderInfo {
             block_height: block_height,
             index_root: index_root,
             burn_header_hash: burn_header_hash,
+            burn_header_height: burn_header_height,
             burn_header_timestamp: burn_header_timestamp
         })
     }
@@ -288,6 +295,10 @@ impl ClarityConnection for ClarityTx<'_> {
     where F: FnOnce(&mut AnalysisDatabase) -> R {
         self.block.with_analysis_db_readonly(to_do)
     }
+
+    fn is_mainnet(&self) -> bool {
+        self.block.is_mainnet()
+    }
 }
 
 impl<'a> ClarityTx<'a> {
@@ -394,6 +405,7 @@ const STACKS_CHAIN_STATE_SQL : &'static [&'static str]= &[
         block_height INTEGER NOT NULL,
         index_root TEXT NOT NULL,                    -- root hash of the internal, not-consensus-critical MARF that allows us to track chainstate fork metadata
         burn_header_hash TEXT UNIQUE NOT NULL,       -- all burn header hashes are guaranteed to be unique
+        burn_header_height INT NOT NULL,             -- height of the burnchain block header
         burn_header_timestamp INT NOT NULL,          -- timestamp from burnchain block header
 
         PRIMARY KEY(burn_header_hash,block_hash)
@@ -497,13 +509,15 @@ const STACKS_BOOT_CODE : &'static [&'static str] = &[
 
     ;; TODO: authorize STX withdrawals
     ;; TODO: withdraw STX
-    "#
+    "#,
+    BOOT_CODE_POX
 ];
 
 pub const STACKS_BOOT_CODE_CONTRACT_ADDRESS : &'static str = "ST000000000000000000002AMW42H";
 
 const STACKS_BOOT_CODE_CONTRACT_NAMES : &'static [&'static str] = &[
-    "miner-rewards"
+    "miner-rewards",
+    BOOT_CODE_POX_CONTRACT_NAME
 ];
 
 pub const BOOT_CODE_MINER_CONTRACT_NAME : &'static str = "miner-rewards";
@@ -818,7 +832,8 @@ impl StacksChainState {
         let vm_state = MarfedKV::open(&clarity_state_index_root, Some(&StacksBlockHeader::make_index_block_hash(&MINER_BLOCK_BURN_HEADER_HASH, &MINER_BLOCK_HEADER_HASH)))
             .map_err(|e| Error::ClarityError(e.into()))?;
 
-        let clarity_state = ClarityInstance::new(vm_state, block_limit.clone());
+        let mut clarity_state = ClarityInstance::new(vm_state, block_limit.clone());
+        clarity_state.set_mainnet(mainnet);
 
         let mut chainstate = StacksChainState {
             mainnet: mainnet,
@@ -896,6 +911,12 @@ impl StacksChainState {
         Ok((chainstate_tx, clarity_instance))
     }
 
+    /// Attach a profile of each transaction's execution costs to its receipt, for the blocks
+    /// processed from now on.
+    pub fn set_cost_profiling(&mut self, enabled: bool) {
+        self.clarity_state.set_cost_profiling(enabled);
+    }
+
     pub fn clarity_eval_read_only(&mut self, parent_id_bhh: &StacksBlockId,
                                   contract: &QualifiedContractIdentifier, code: &str) -> Value {
         let result = self.clarity_state.eval_read_only(parent_id_bhh, &self.headers_db, contract, code);
@@ -908,19 +929,30 @@ impl StacksChainState {
                                       parent_burn_hash: &BurnchainHeaderHash,
                                       parent_block: &BlockHeaderHash,
                                       new_burn_hash: &BurnchainHeaderHash,
+                                      new_burn_height: u32,
                                       new_block: &BlockHeaderHash) -> ClarityTx<'a> {
 
         let conf = chainstate_tx.config.clone();
         StacksChainState::inner_clarity_tx_begin(conf, chainstate_tx.headers_tx.deref().deref(),
-                                                 clarity_instance, parent_burn_hash, parent_block, new_burn_hash, new_block)
+                                                 clarity_instance, parent_burn_hash, parent_block, new_burn_hash, Some(new_burn_height), new_block)
     }
     
     /// Begin a transaction against the Clarity VM, _outside of_ the context of a chainstate
-    /// transaction.  Used by the miner for producing blocks.
+    /// transaction.  The burnchain block height is unknown, so evaluating anything that depends
+    /// on it (`burn-block-height`, or the balance of an account with locked uSTX) fails.
     pub fn block_begin<'a>(&'a mut self, parent_burn_hash: &BurnchainHeaderHash, parent_block: &BlockHeaderHash, new_burn_hash: &BurnchainHeaderHash, new_block: &BlockHeaderHash) -> ClarityTx<'a> {
         let conf = self.config();
         StacksChainState::inner_clarity_tx_begin(conf, &self.headers_db, &mut self.clarity_state,
-                                                 parent_burn_hash, parent_block, new_burn_hash, new_block)
+                                                 parent_burn_hash, parent_block, new_burn_hash, None, new_block)
+    }
+
+    /// Begin a transaction against the Clarity VM for a block mined in a known burnchain block,
+    /// _outside of_ the context of a chainstate transaction.
+    pub fn block_begin_at_burn_height<'a>(&'a mut self, parent_burn_hash: &BurnchainHeaderHash, parent_block: &BlockHeaderHash,
+                                          new_burn_hash: &BurnchainHeaderHash, new_burn_height: u32, new_block: &BlockHeaderHash) -> ClarityTx<'a> {
+        let conf = self.config();
+        StacksChainState::inner_clarity_tx_begin(conf, &self.headers_db, &mut self.clarity_state,
+                                                 parent_burn_hash, parent_block, new_burn_hash, Some(new_burn_height), new_block)
     }
 
     pub fn with_clarity_marf<F, R>(&mut self, f: F) -> R
@@ -1011,6 +1043,7 @@ impl StacksChainState {
                                   parent_burn_hash: &BurnchainHeaderHash,
                                   parent_block: &BlockHeaderHash,
                                   new_burn_hash: &BurnchainHeaderHash,
+                                  new_burn_height: Option<u32>,
                                   new_block: &BlockHeaderHash) -> ClarityTx<'a> {
 
         // mix burn header hash and stacks block header hash together, since the stacks block hash
@@ -1023,7 +1056,10 @@ impl StacksChainState {
         test_debug!("Child MARF index root:  {} = {} + {}", new_index_block, new_burn_hash, new_block);
         test_debug!("Parent MARF index root: {} = {} + {}", parent_index_block, parent_burn_hash, parent_block);
 
-        let inner_clarity_tx = clarity_instance.begin_block(&parent_index_block, &new_index_block, headers_db);
+        let mut inner_clarity_tx = clarity_instance.begin_block(&parent_index_block, &new_index_block, headers_db);
+        if let Some(new_burn_height) = new_burn_height {
+            inner_clarity_tx.set_burn_block_height(new_burn_height);
+        }
 
         test_debug!("Got clarity TX!");
         ClarityTx {
@@ -1068,6 +1104,7 @@ impl StacksChainState {
                            parent_burn_block: &BurnchainHeaderHash, 
                            new_tip: &StacksBlockHeader, 
                            new_burn_block: &BurnchainHeaderHash, 
+                           new_burn_block_height: u32,
                            new_burn_block_timestamp: u64,
                            microblock_tail_opt: Option<StacksMicroblockHeader>,
                            block_reward: &MinerPaymentSchedule,
@@ -1106,6 +1143,7 @@ impl StacksChainState {
             index_root: root_hash,
             block_height: new_tip.total_work.work,
             burn_header_hash: new_burn_block.clone(),
+            burn_header_height: new_burn_block_height,
             burn_header_timestamp: new_burn_block_timestamp
         };
 
@@ -1173,4 +1211,20 @@ pub mod test {
             assert!(contract_res.is_some());
         }
     }
+
+    #[test]
+    fn test_reject_old_chainstate_version() {
+        let path = chainstate_path("reject-old-chainstate-version");
+        {
+            let chainstate = instantiate_chainstate(false, 0x80000000, "reject-old-chainstate-version");
+            chainstate.headers_db.execute("UPDATE db_config SET version = ?1", &["23.0.0.1"]).unwrap();
+        }
+
+        // staging blocks written by an older node lack the burnchain block height
+        match StacksChainState::open(false, 0x80000000, &path) {
+            Err(Error::InvalidChainstateDB) => {},
+            Err(e) => panic!("Unexpected error {:?}", &e),
+            Ok(_) => panic!("Opened a chainstate with an old schema"),
+        }
+    }
 }
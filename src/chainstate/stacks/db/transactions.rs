@@ -28,6 +28,7 @@ use std::collections::{HashSet, HashMap};
 use chainstate::stacks::Error;
 use chainstate::stacks::*;
 use chainstate::stacks::db::*;
+use chainstate::stacks::boot::handle_pox_lockup_events;
 
 use std::path::{Path, PathBuf};
 
@@ -103,7 +104,8 @@ impl StacksTransactionReceipt {
             post_condition_aborted: false,
             contract_analysis: None,
             transaction: tx,
-            execution_cost: cost
+            execution_cost: cost,
+            cost_profile: None,
         }
     }
 
@@ -115,7 +117,8 @@ impl StacksTransactionReceipt {
             result,
             stx_burned: burned,
             contract_analysis: None,
-            execution_cost: cost
+            execution_cost: cost,
+            cost_profile: None,
         }
     }
 
@@ -127,7 +130,8 @@ impl StacksTransactionReceipt {
             result,
             stx_burned: burned,
             contract_analysis: None,
-            execution_cost: cost
+            execution_cost: cost,
+            cost_profile: None,
         }
     }
 
@@ -139,7 +143,8 @@ impl StacksTransactionReceipt {
             result: Value::okay_true(),
             stx_burned: burned,
             contract_analysis: Some(analysis),
-            execution_cost: cost
+            execution_cost: cost,
+            cost_profile: None,
         }
     }
 
@@ -151,7 +156,8 @@ impl StacksTransactionReceipt {
             result: Value::okay_true(),
             stx_burned: burned,
             contract_analysis: Some(analysis),
-            execution_cost: cost
+            execution_cost: cost,
+            cost_profile: None,
         }
     }
 
@@ -163,7 +169,8 @@ impl StacksTransactionReceipt {
             result: Value::okay_true(),
             stx_burned: 0,
             contract_analysis: None,
-            execution_cost: ExecutionCost::zero()
+            execution_cost: ExecutionCost::zero(),
+            cost_profile: None,
         }
     }
 
@@ -176,6 +183,7 @@ impl StacksTransactionReceipt {
             stx_burned: 0,
             contract_analysis: None,
             execution_cost: analysis_cost,
+            cost_profile: None,
         }
     }
 }
@@ -281,7 +289,7 @@ impl StacksChainState {
         if payer_account.stx_balance < fee as u128 {
             return Err(Error::InvalidFee);
         }
-        StacksChainState::account_debit(clarity_tx, &payer_account.principal, fee);
+        StacksChainState::account_debit(clarity_tx, &payer_account.principal, fee)?;
         Ok(fee)
     }
 
@@ -471,6 +479,132 @@ impl StacksChainState {
         return true;
     }
 
+    /// Propose the tightest post-conditions that the given asset movements pass in Deny mode:
+    /// one condition on exactly how much of each fungible asset (and STX) each principal sent, and
+    /// one on each non-fungible asset each principal sent.  Conditions on the origin come first.
+    pub fn post_conditions_from_asset_map(origin: &PrincipalData, asset_map: &AssetMap) -> Vec<TransactionPostCondition> {
+        let to_condition_principal = |principal: &PrincipalData| {
+            if principal == origin {
+                return PostConditionPrincipal::Origin;
+            }
+            match principal {
+                PrincipalData::Standard(ref p) => PostConditionPrincipal::Standard(StacksAddress::from(p.clone())),
+                PrincipalData::Contract(ref contract_id) =>
+                    PostConditionPrincipal::Contract(StacksAddress::from(contract_id.issuer.clone()), contract_id.name.clone())
+            }
+        };
+        let to_asset_info = |asset_id: &AssetIdentifier| AssetInfo {
+            contract_address: StacksAddress::from(asset_id.contract_identifier.issuer.clone()),
+            contract_name: asset_id.contract_identifier.name.clone(),
+            asset_name: asset_id.asset_name.clone()
+        };
+        // amounts which don't fit in a post-condition can only be bounded from below
+        let sent_condition = |amount: u128| {
+            if amount > u64::MAX as u128 {
+                (FungibleConditionCode::SentGe, u64::MAX)
+            } else {
+                (FungibleConditionCode::SentEq, amount as u64)
+            }
+        };
+
+        let mut principals: Vec<_> = asset_map.clone().to_table().into_iter().collect();
+        principals.sort_by_key(|(principal, _)| (principal != origin, principal.to_string()));
+
+        let mut post_conditions = vec![];
+        for (principal, assets) in principals.into_iter() {
+            let condition_principal = to_condition_principal(&principal);
+
+            // the STX post-condition covers both the STX sent and the STX burned
+            let stx_sent = asset_map.get_stx(&principal).unwrap_or(0)
+                .saturating_add(asset_map.get_stx_burned(&principal).unwrap_or(0));
+            if stx_sent > 0 {
+                let (condition_code, amount) = sent_condition(stx_sent);
+                post_conditions.push(TransactionPostCondition::STX(condition_principal.clone(), condition_code, amount));
+            }
+
+            let mut assets: Vec<_> = assets.into_iter().collect();
+            assets.sort_by_key(|(asset_id, _)| asset_id.to_string());
+            for (asset_id, entry) in assets.into_iter() {
+                match entry {
+                    AssetMapEntry::STX(_) | AssetMapEntry::Burn(_) => {},
+                    AssetMapEntry::Token(amount) => {
+                        let (condition_code, amount) = sent_condition(amount);
+                        post_conditions.push(TransactionPostCondition::Fungible(
+                            condition_principal.clone(), to_asset_info(&asset_id), condition_code, amount));
+                    },
+                    AssetMapEntry::Asset(mut values) => {
+                        values.sort_by_key(|value| value.to_string());
+                        values.dedup();
+                        for value in values.into_iter() {
+                            post_conditions.push(TransactionPostCondition::Nonfungible(
+                                condition_principal.clone(), to_asset_info(&asset_id), value, NonfungibleConditionCode::Sent));
+                        }
+                    }
+                }
+            }
+        }
+        post_conditions
+    }
+
+    /// Dry-run a transaction payload from the given origin, and propose the tightest post-conditions
+    /// that would let exactly that execution succeed in Deny mode.  Nothing the payload does is
+    /// kept.  Fails if the payload would not execute, e.g. because of a runtime error.
+    pub fn infer_post_conditions(clarity_tx: &mut ClarityTransactionConnection, origin: &PrincipalData,
+                                 payload: &TransactionPayload) -> Result<Vec<TransactionPostCondition>, Error> {
+        // every execution is aborted once it has reported the assets it moved
+        let asset_map = match payload {
+            TransactionPayload::TokenTransfer(..) | TransactionPayload::PoisonMicroblock(..) => {
+                let msg = format!("Invalid Stacks transaction: {} transactions do not support post-conditions", payload.name());
+                return Err(Error::InvalidStacksTransaction(msg, false));
+            },
+            TransactionPayload::Coinbase(_) => {
+                return Ok(vec![]);
+            },
+            TransactionPayload::ContractCall(ref contract_call) => {
+                let contract_id = contract_call.to_clarity_contract_id();
+                match clarity_tx.run_contract_call(origin, &contract_id, &contract_call.function_name, &contract_call.function_args, |_, _| true) {
+                    Err(clarity_error::AbortedByCallback(_, asset_map, _)) => asset_map,
+                    Err(e) => return Err(Error::ClarityError(e)),
+                    Ok(_) => unreachable!("BUG: contract-call was not aborted")
+                }
+            },
+            TransactionPayload::SmartContract(ref smart_contract) => {
+                let issuer_principal = match origin {
+                    PrincipalData::Standard(ref p) => p.clone(),
+                    _ => {
+                        let msg = format!("Invalid Stacks transaction: smart contracts must be published by a standard principal");
+                        return Err(Error::InvalidStacksTransaction(msg, false));
+                    }
+                };
+                let contract_id = QualifiedContractIdentifier::new(issuer_principal, smart_contract.name.clone());
+                let contract_code_str = smart_contract.code_body.to_string();
+
+                let (contract_ast, _) = clarity_tx.analyze_smart_contract(&contract_id, &contract_code_str)
+                    .map_err(Error::ClarityError)?;
+                match clarity_tx.initialize_smart_contract(&contract_id, &contract_ast, &contract_code_str, |_, _| true) {
+                    Err(clarity_error::AbortedByCallback(_, asset_map, _)) => asset_map,
+                    Err(e) => return Err(Error::ClarityError(e)),
+                    Ok(_) => unreachable!("BUG: contract instantiation was not aborted")
+                }
+            }
+        };
+        Ok(StacksChainState::post_conditions_from_asset_map(origin, &asset_map))
+    }
+
+    /// Infer post-conditions for a transaction payload, as `infer_post_conditions` does, by
+    /// dry-running it on top of the given chain tip.
+    pub fn infer_post_conditions_at_tip(&mut self, parent_burn_hash: &BurnchainHeaderHash, parent_block: &BlockHeaderHash,
+                                        origin: &PrincipalData, payload: &TransactionPayload) -> Result<Vec<TransactionPostCondition>, Error> {
+        let parent_burn_height = StacksChainState::get_anchored_block_header_info(&self.headers_db, parent_burn_hash, parent_block)?
+            .ok_or(Error::NoSuchBlockError)?
+            .burn_header_height;
+        let mut clarity_tx = self.block_begin_at_burn_height(parent_burn_hash, parent_block, &MINER_BLOCK_BURN_HEADER_HASH,
+                                                             parent_burn_height, &MINER_BLOCK_HEADER_HASH);
+        let result = clarity_tx.connection().as_transaction(|tx| StacksChainState::infer_post_conditions(tx, origin, payload));
+        clarity_tx.rollback_block();
+        result
+    }
+
     /// Process a token transfer payload (but pass the transaction that wraps it, in order to do
     /// post-condition checks).
     fn process_transaction_token_transfer(clarity_tx: &mut ClarityTransactionConnection, txid: &Txid,
@@ -485,8 +619,8 @@ impl StacksChainState {
 
         clarity_tx.with_clarity_db(|ref mut db| {
             // does the sender have ths amount?
-            let cur_balance = db.get_account_stx_balance(&origin_account.principal);
-            let recipient_balance = db.get_account_stx_balance(&recipient_principal);
+            let cur_balance = db.get_account_stx_balance(&origin_account.principal)?;
+            let recipient_balance = db.get_account_stx_balance(&recipient_principal)?;
 
             let new_balance = cur_balance.checked_sub(amount as u128)
                 .ok_or(clarity_error::BadTransaction(format!("Address {} has {} microSTX; needed at least {}", &origin_account.principal, cur_balance, amount)))?;
@@ -494,8 +628,8 @@ impl StacksChainState {
             let new_recipient_balance = recipient_balance.checked_add(amount as u128)
                 .ok_or(clarity_error::BadTransaction(format!("Address {} has {} microSTX; cannot add {}", &recipient_principal, recipient_balance, amount)))?;
 
-            db.set_account_stx_balance(&origin_account.principal, new_balance);
-            db.set_account_stx_balance(&recipient_principal, new_recipient_balance);
+            db.set_account_stx_balance(&origin_account.principal, new_balance)?;
+            db.set_account_stx_balance(&recipient_principal, new_recipient_balance)?;
 
             Ok(())
         })
@@ -507,6 +641,11 @@ impl StacksChainState {
 
                     Error::InvalidStacksTransaction(msg, false)
                 },
+                clarity_error::Interpreter(_) => {
+                    // e.g. the balance depends on a lock-up, but the burnchain block height isn't known
+                    warn!("Failed to process STX-transfer transaction {:?}: {:?}", txid, &e);
+                    Error::ClarityError(e)
+                },
                 _ => {
                     // any other clarity error shouldn't happen
                     panic!("BUG: clarity VM error {:?} when processint token transfer", &e);
@@ -596,6 +735,9 @@ impl StacksChainState {
                     }
                 })?;
 
+                // successful stack-stx calls to the PoX contract lock the Stackers' tokens
+                handle_pox_lockup_events(clarity_tx, &events)?;
+
                 let receipt = StacksTransactionReceipt::from_contract_call(tx.clone(), events, result, asset_map.get_stx_burned_total(), total_cost);
                 Ok(receipt)
             },
@@ -693,6 +835,9 @@ impl StacksChainState {
                         _ => Error::ClarityError(e)
                     }
                 })?;
+
+                // successful stack-stx calls to the PoX contract lock the Stackers' tokens
+                handle_pox_lockup_events(clarity_tx, &events)?;
                 
                 // store analysis -- if this fails, then the have some pretty bad problems
                 clarity_tx.save_analysis(&contract_id, &contract_analysis)
@@ -732,7 +877,10 @@ impl StacksChainState {
         let mut transaction = clarity_block.connection().start_transaction_processing();
         let (origin_account, payer_account) = StacksChainState::check_transaction_nonces(&mut transaction, tx, quiet)?;
 
-        let tx_receipt = StacksChainState::process_transaction_payload(&mut transaction, tx, &origin_account)?;
+        // only profile the costs of the payload itself
+        transaction.take_cost_profile();
+        let mut tx_receipt = StacksChainState::process_transaction_payload(&mut transaction, tx, &origin_account)?;
+        tx_receipt.cost_profile = transaction.take_cost_profile();
 
         // pay fee borne by runtime costs.
         // NOTE: the fee must be paid _after_ we run the payload, because we will (eventually) be
@@ -1404,6 +1552,68 @@ pub mod test {
         assert_eq!(var_res, Some(Value::Int(3)));
     }
 
+    #[test]
+    fn process_smart_contract_contract_call_cost_profile() {
+        let contract = "
+        (define-data-var bar int 0)
+        (define-private (div (x int) (y int)) (/ x y))
+        (define-public (set-bar (x int) (y int))
+          (begin (var-set bar (div x y)) (ok (var-get bar))))";
+
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "process-contract-cc-cost-profile");
+        chainstate.set_cost_profiling(true);
+
+        let privk = StacksPrivateKey::from_hex("6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001").unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr = auth.origin().address_testnet();
+
+        let mut tx_contract = StacksTransaction::new(TransactionVersion::Testnet,
+                                                     auth.clone(),
+                                                     TransactionPayload::new_smart_contract(&"hello-world".to_string(), &contract.to_string()).unwrap());
+        tx_contract.chain_id = 0x80000000;
+        tx_contract.set_fee_rate(0);
+
+        let mut signer = StacksTransactionSigner::new(&tx_contract);
+        signer.sign_origin(&privk).unwrap();
+        let signed_tx = signer.get_tx().unwrap();
+
+        let privk_2 = StacksPrivateKey::from_hex("d2c340ebcc0794b6fabdd8ac8b1c983e363b05dc8adcdf7e30db205a3fa54c1601").unwrap();
+        let auth_2 = TransactionAuth::from_p2pkh(&privk_2).unwrap();
+
+        let mut tx_contract_call = StacksTransaction::new(TransactionVersion::Testnet,
+                                                          auth_2.clone(),
+                                                          TransactionPayload::new_contract_call(addr.clone(), "hello-world", "set-bar", vec![Value::Int(6), Value::Int(2)]).unwrap());
+        tx_contract_call.chain_id = 0x80000000;
+        tx_contract_call.set_fee_rate(0);
+
+        let mut signer_2 = StacksTransactionSigner::new(&tx_contract_call);
+        signer_2.sign_origin(&privk_2).unwrap();
+        let signed_tx_2 = signer_2.get_tx().unwrap();
+
+        let mut conn = chainstate.block_begin(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &BurnchainHeaderHash([1u8; 32]), &BlockHeaderHash([1u8; 32]));
+
+        let (_, receipt) = StacksChainState::process_transaction(&mut conn, &signed_tx, false).unwrap();
+        assert!(receipt.cost_profile.is_some());
+
+        let (_, receipt_2) = StacksChainState::process_transaction(&mut conn, &signed_tx_2, false).unwrap();
+        conn.commit_block();
+
+        // the contract-call's profile covers exactly its own execution cost
+        let profile = receipt_2.cost_profile.unwrap();
+        assert_eq!(profile.root.total_cost, receipt_2.execution_cost);
+
+        let set_bar_name = format!("{}.hello-world:set-bar", &addr);
+        let set_bar = profile.root.children.iter().find(|node| node.name == set_bar_name).unwrap();
+        assert_eq!(set_bar.calls, 1);
+
+        let begin = set_bar.children.iter().find(|node| node.name == "begin").unwrap();
+        let var_set = begin.children.iter().find(|node| node.name == "var-set").unwrap();
+        let div_name = format!("{}.hello-world:div", &addr);
+        let div = var_set.children.iter().find(|node| node.name == div_name).unwrap();
+        assert!(div.children.iter().any(|node| node.name == "/"));
+        assert!(var_set.total_cost.runtime > div.total_cost.runtime);
+    }
+
     #[test]
     fn process_smart_contract_contract_call_runtime_error() {
         let contract = "
@@ -2534,6 +2744,96 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn process_post_conditions_burns() {
+        let contract = "
+        (define-fungible-token stackaroos)
+        (define-non-fungible-token names uint)
+        (define-public (burn-stackaroos (amount uint))
+          (ft-burn? stackaroos amount tx-sender))
+        (define-public (burn-name (name uint))
+          (nft-burn? names name tx-sender))
+        (define-public (mint)
+          (begin
+            (unwrap-panic (ft-mint? stackaroos u100 tx-sender))
+            (nft-mint? names u1 tx-sender)))";
+
+        let privk_origin = StacksPrivateKey::from_hex("027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01").unwrap();
+        let auth_origin = TransactionAuth::from_p2pkh(&privk_origin).unwrap();
+        let addr_publisher = auth_origin.origin().address_testnet();
+        let addr_principal = addr_publisher.to_account_principal();
+
+        let contract_name = ContractName::try_from("hello-world").unwrap();
+        let contract_id = QualifiedContractIdentifier::new(StandardPrincipalData::from(addr_publisher.clone()), contract_name.clone());
+
+        let asset_info = AssetInfo {
+            contract_address: addr_publisher.clone(),
+            contract_name: contract_name.clone(),
+            asset_name: ClarityName::try_from("stackaroos").unwrap(),
+        };
+
+        let name_asset_info = AssetInfo {
+            contract_address: addr_publisher.clone(),
+            contract_name: contract_name.clone(),
+            asset_name: ClarityName::try_from("names").unwrap(),
+        };
+
+        let sign_tx = |payload: TransactionPayload, nonce: u64, post_condition: Option<TransactionPostCondition>| {
+            let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth_origin.clone(), payload);
+            tx.chain_id = 0x80000000;
+            tx.set_fee_rate(0);
+            tx.set_origin_nonce(nonce);
+            if let Some(post_condition) = post_condition {
+                tx.add_post_condition(post_condition);
+            }
+
+            let mut signer = StacksTransactionSigner::new(&tx);
+            signer.sign_origin(&privk_origin).unwrap();
+            signer.get_tx().unwrap()
+        };
+
+        let burn_stackaroos = TransactionPayload::new_contract_call(addr_publisher.clone(), "hello-world", "burn-stackaroos", vec![Value::UInt(10)]).unwrap();
+        let burn_name = TransactionPayload::new_contract_call(addr_publisher.clone(), "hello-world", "burn-name", vec![Value::UInt(1)]).unwrap();
+
+        let signed_contract_tx = sign_tx(TransactionPayload::new_smart_contract(&"hello-world".to_string(), &contract.to_string()).unwrap(), 0, None);
+        let tx_mint = sign_tx(TransactionPayload::new_contract_call(addr_publisher.clone(), "hello-world", "mint", vec![]).unwrap(), 1, None);
+
+        // burns are outflows, so in deny mode they must be declared
+        let tx_burn_undeclared = sign_tx(burn_stackaroos.clone(), 2, None);
+        let tx_burn_declared = sign_tx(burn_stackaroos.clone(), 3,
+            Some(TransactionPostCondition::Fungible(PostConditionPrincipal::Origin, asset_info.clone(), FungibleConditionCode::SentEq, 10)));
+        let tx_burn_name_undeclared = sign_tx(burn_name.clone(), 4, None);
+        let tx_burn_name_declared = sign_tx(burn_name.clone(), 5,
+            Some(TransactionPostCondition::Nonfungible(PostConditionPrincipal::Origin, name_asset_info.clone(), Value::UInt(1), NonfungibleConditionCode::Sent)));
+
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "process-post-conditions-burns");
+        let mut conn = chainstate.block_begin(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &BurnchainHeaderHash([1u8; 32]), &BlockHeaderHash([1u8; 32]));
+
+        let (_fee, receipt) = StacksChainState::process_transaction(&mut conn, &signed_contract_tx, false).unwrap();
+        assert!(!receipt.post_condition_aborted);
+
+        let (_fee, receipt) = StacksChainState::process_transaction(&mut conn, &tx_mint, false).unwrap();
+        assert!(!receipt.post_condition_aborted);
+
+        let (_fee, receipt) = StacksChainState::process_transaction(&mut conn, &tx_burn_undeclared, false).unwrap();
+        assert!(receipt.post_condition_aborted);
+        assert_eq!(StacksChainState::get_account_ft(&mut conn, &contract_id, "stackaroos", &addr_principal).unwrap(), 100);
+
+        let (_fee, receipt) = StacksChainState::process_transaction(&mut conn, &tx_burn_declared, false).unwrap();
+        assert!(!receipt.post_condition_aborted);
+        assert_eq!(StacksChainState::get_account_ft(&mut conn, &contract_id, "stackaroos", &addr_principal).unwrap(), 90);
+
+        let (_fee, receipt) = StacksChainState::process_transaction(&mut conn, &tx_burn_name_undeclared, false).unwrap();
+        assert!(receipt.post_condition_aborted);
+        assert_eq!(StacksChainState::get_account_nft(&mut conn, &contract_id, "names", &Value::UInt(1)).unwrap(), addr_principal);
+
+        let (_fee, receipt) = StacksChainState::process_transaction(&mut conn, &tx_burn_name_declared, false).unwrap();
+        assert!(!receipt.post_condition_aborted);
+        assert!(StacksChainState::get_account_nft(&mut conn, &contract_id, "names", &Value::UInt(1)).is_err());
+
+        conn.commit_block();
+    }
+
     #[test]
     fn test_check_postconditions_multiple_fts() {
         let privk = StacksPrivateKey::from_hex("6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001").unwrap();
@@ -3295,5 +3595,101 @@ pub mod test {
         assert_eq!(fee, 0);
         if let Error::InvalidFee = err {} else { assert!(false) };
     }
+
+    #[test]
+    fn infer_post_conditions() {
+        let contract = "
+        (define-fungible-token stackaroos)
+        (define-non-fungible-token names (buff 50))
+        (define-public (pay-and-mint (name (buff 50)) (recipient principal))
+          (begin
+            (unwrap-panic (stx-transfer? u10 tx-sender recipient))
+            (unwrap-panic (stx-burn? u5 tx-sender))
+            (as-contract
+              (begin (unwrap-panic (ft-mint? stackaroos u100 tx-sender))
+                     (unwrap-panic (ft-transfer? stackaroos u100 tx-sender recipient))
+                     (unwrap-panic (nft-mint? names name tx-sender))
+                     (unwrap-panic (nft-transfer? names name tx-sender recipient))))
+            (ok true)))
+        (define-public (refuse) (err u1))
+        (define-public (divide (x int)) (ok (/ 1 x)))";
+
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "infer-post-conditions");
+
+        let privk = StacksPrivateKey::from_hex("027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01").unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr_publisher = auth.origin().address_testnet();
+        let origin = addr_publisher.to_account_principal();
+        let recv_addr = StacksAddress { version: 1, bytes: Hash160([0xff; 20]) };
+        let contract_name = ContractName::try_from("hello-world").unwrap();
+        let contract_id = QualifiedContractIdentifier::new(StandardPrincipalData::from(addr_publisher.clone()), contract_name.clone());
+
+        let publish_payload = TransactionPayload::new_smart_contract(&"hello-world".to_string(), &contract.to_string()).unwrap();
+        let call_payload = |function: &str, args: Vec<Value>| {
+            TransactionPayload::new_contract_call(addr_publisher.clone(), "hello-world", function, args).unwrap()
+        };
+        let name = Value::buff_from(vec![1, 2, 3]).unwrap();
+        let pay_and_mint = call_payload("pay-and-mint", vec![name.clone(), Value::Principal(recv_addr.to_account_principal())]);
+
+        // dry-running against the chain tip leaves nothing behind
+        assert_eq!(chainstate.infer_post_conditions_at_tip(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &origin, &publish_payload).unwrap(), vec![]);
+        assert!(chainstate.infer_post_conditions_at_tip(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &origin, &pay_and_mint).is_err());
+
+        let mut conn = chainstate.block_begin(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &BurnchainHeaderHash([1u8; 32]), &BlockHeaderHash([1u8; 32]));
+        conn.connection().as_transaction(|tx| StacksChainState::account_credit(tx, &origin, 100));
+
+        let mut tx_contract = StacksTransaction::new(TransactionVersion::Testnet, auth.clone(), publish_payload);
+        tx_contract.chain_id = 0x80000000;
+        tx_contract.set_fee_rate(0);
+        let mut signer = StacksTransactionSigner::new(&tx_contract);
+        signer.sign_origin(&privk).unwrap();
+        StacksChainState::process_transaction(&mut conn, &signer.get_tx().unwrap(), false).unwrap();
+
+        let post_conditions = conn.connection().as_transaction(|tx| StacksChainState::infer_post_conditions(tx, &origin, &pay_and_mint)).unwrap();
+        let contract_principal = PostConditionPrincipal::Contract(addr_publisher.clone(), contract_name.clone());
+        let asset_info = |asset_name: &str| AssetInfo {
+            contract_address: addr_publisher.clone(),
+            contract_name: contract_name.clone(),
+            asset_name: ClarityName::try_from(asset_name).unwrap()
+        };
+        assert_eq!(post_conditions, vec![
+            TransactionPostCondition::STX(PostConditionPrincipal::Origin, FungibleConditionCode::SentEq, 15),
+            TransactionPostCondition::Nonfungible(contract_principal.clone(), asset_info("names"), name.clone(), NonfungibleConditionCode::Sent),
+            TransactionPostCondition::Fungible(contract_principal.clone(), asset_info("stackaroos"), FungibleConditionCode::SentEq, 100),
+        ]);
+
+        // the dry run moved nothing
+        assert_eq!(StacksChainState::get_account(&mut conn, &origin).stx_balance, 100);
+        assert_eq!(StacksChainState::get_account(&mut conn, &recv_addr.to_account_principal()).stx_balance, 0);
+
+        // the proposed post-conditions let the call succeed in Deny mode
+        let mut tx_call = StacksTransaction::new(TransactionVersion::Testnet, auth.clone(), pay_and_mint);
+        tx_call.chain_id = 0x80000000;
+        tx_call.set_fee_rate(0);
+        tx_call.set_origin_nonce(1);
+        tx_call.post_condition_mode = TransactionPostConditionMode::Deny;
+        for post_condition in post_conditions.into_iter() {
+            tx_call.add_post_condition(post_condition);
+        }
+        let mut signer = StacksTransactionSigner::new(&tx_call);
+        signer.sign_origin(&privk).unwrap();
+        let (_, receipt) = StacksChainState::process_transaction(&mut conn, &signer.get_tx().unwrap(), false).unwrap();
+        assert!(!receipt.post_condition_aborted);
+        assert_eq!(receipt.result, Value::okay_true());
+        assert_eq!(StacksChainState::get_account(&mut conn, &recv_addr.to_account_principal()).stx_balance, 10);
+
+        // calls which move nothing need no post-conditions, and those which fail get none
+        let post_conditions = conn.connection().as_transaction(|tx| StacksChainState::infer_post_conditions(tx, &origin, &call_payload("refuse", vec![]))).unwrap();
+        assert_eq!(post_conditions, vec![]);
+        let result = conn.connection().as_transaction(|tx| StacksChainState::infer_post_conditions(tx, &origin, &call_payload("divide", vec![Value::Int(0)])));
+        assert!(result.is_err());
+
+        let transfer = TransactionPayload::TokenTransfer(recv_addr.to_account_principal(), 1, TokenTransferMemo([0u8; 34]));
+        let result = conn.connection().as_transaction(|tx| StacksChainState::infer_post_conditions(tx, &origin, &transfer));
+        assert!(result.is_err());
+
+        conn.commit_block();
+    }
+
     // TODO: test poison microblock
 }
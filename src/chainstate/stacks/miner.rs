@@ -18,9 +18,13 @@
 */
 
 use std::fs;
+use std::fmt;
 use std::mem;
 use std::collections::HashSet;
 use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::BinaryHeap;
+use std::cmp;
 use chainstate::stacks::Error;
 use chainstate::stacks::*;
 use chainstate::stacks::db::{
@@ -36,6 +40,8 @@ use net::StacksMessageCodec;
 use net::Error as net_error;
 use net::codec::{read_next, write_next};
 use vm::clarity::ClarityConnection;
+use vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
+use vm::representations::ClarityName;
 
 use util::hash::MerkleTree;
 use util::hash::Sha512Trunc256Sum;
@@ -47,6 +53,7 @@ use chainstate::burn::*;
 use chainstate::burn::operations::*;
 
 use burnchains::BurnchainHeaderHash;
+use burnchains::Txid;
 use burnchains::PrivateKey;
 use burnchains::PublicKey;
 
@@ -79,12 +86,15 @@ impl <'a> StacksMicroblockBuilder <'a> {
     pub fn new(anchor_block: BlockHeaderHash, anchor_block_bhh: BurnchainHeaderHash,
                chainstate: &'a mut StacksChainState, initial_cost: ExecutionCost, bytes_so_far: u64) -> Result<StacksMicroblockBuilder<'a>, Error> {
         let header_reader = chainstate.reopen()?;
-        let mut clarity_tx = chainstate.block_begin(&anchor_block_bhh, &anchor_block,
-                                                    &MINER_BLOCK_BURN_HEADER_HASH, &MINER_BLOCK_HEADER_HASH);
-        let anchor_block_height = 
+        let anchor_header_info =
             StacksChainState::get_anchored_block_header_info(&header_reader.headers_db, &anchor_block_bhh, &anchor_block)?
-            .ok_or(Error::NoSuchBlockError)?
-            .block_height;
+            .ok_or(Error::NoSuchBlockError)?;
+        let anchor_block_height = anchor_header_info.block_height;
+
+        // the microblock stream is mined while its anchored block is the burnchain tip
+        let mut clarity_tx = chainstate.block_begin_at_burn_height(&anchor_block_bhh, &anchor_block,
+                                                                   &MINER_BLOCK_BURN_HEADER_HASH, anchor_header_info.burn_header_height,
+                                                                   &MINER_BLOCK_HEADER_HASH);
 
         clarity_tx.reset_cost(initial_cost);
         Ok(StacksMicroblockBuilder {
@@ -276,8 +286,95 @@ impl <'a> Drop for StacksMicroblockBuilder<'a> {
     }
 }
 
+/// Why a mempool transaction was left out of a block the miner built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionDropReason {
+    /// Including it would exceed the block's size or execution budget
+    BudgetExceeded,
+    /// It could not be applied to the chain state
+    Invalid(String),
+    /// Another transaction with the same origin or sponsor nonce was mined instead
+    NonceConflict,
+    /// Its origin and sponsor nonces could not both be reached by mining the other candidates
+    NonceUnreachable,
+}
+
+impl fmt::Display for TransactionDropReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransactionDropReason::BudgetExceeded => write!(f, "BudgetExceeded"),
+            TransactionDropReason::Invalid(_) => write!(f, "Invalid"),
+            TransactionDropReason::NonceConflict => write!(f, "NonceConflict"),
+            TransactionDropReason::NonceUnreachable => write!(f, "NonceUnreachable"),
+        }
+    }
+}
+
+/// Most mempool transactions the miner ranks against each other at once.  The mempool is read a
+/// window of this many candidates at a time, so building a block never holds all of it in memory.
+pub const MINER_CANDIDATE_WINDOW : usize = 1024;
+
+/// A mempool transaction that is a candidate for inclusion in the block being built, keyed by
+/// every (account, nonce) pair it consumes.
+struct MinerCandidate {
+    txinfo: MemPoolTxInfo,
+    nonce_keys: Vec<(StacksAddress, u64)>,
+}
+
+impl MinerCandidate {
+    fn new(txinfo: MemPoolTxInfo) -> MinerCandidate {
+        let mut nonce_keys = vec![(txinfo.tx.origin_address(), txinfo.tx.get_origin_nonce())];
+        if let (Some(sponsor_addr), Some(sponsor_nonce)) = (txinfo.tx.sponsor_address(), txinfo.tx.get_sponsor_nonce()) {
+            nonce_keys.push((sponsor_addr, sponsor_nonce));
+        }
+        MinerCandidate {
+            txinfo,
+            nonce_keys
+        }
+    }
+
+    /// What this transaction's execution is expected to cost, based on what the same kind of
+    /// transaction cost earlier in this block.  Transactions we know nothing about are only
+    /// charged for their length.
+    fn estimated_cost(&self, observed_costs: &HashMap<(QualifiedContractIdentifier, ClarityName), ExecutionCost>) -> ExecutionCost {
+        match self.txinfo.tx.payload {
+            TransactionPayload::ContractCall(ref cc) => {
+                let key = (QualifiedContractIdentifier::new(StandardPrincipalData::from(cc.address.clone()), cc.contract_name.clone()), cc.function_name.clone());
+                observed_costs.get(&key).cloned().unwrap_or(ExecutionCost::zero())
+            },
+            _ => ExecutionCost::zero()
+        }
+    }
+
+    /// Fee paid per millionth of the block this transaction is expected to consume.  The
+    /// transaction's share of the block is the largest share it takes of any one resource -- its
+    /// length, or any dimension of the execution budget.
+    fn fee_rate(&self, observed_costs: &HashMap<(QualifiedContractIdentifier, ClarityName), ExecutionCost>, budget: &ExecutionCost) -> u128 {
+        let cost = self.estimated_cost(observed_costs);
+        let share = |used: u64, limit: u64| -> u128 {
+            if limit == 0 {
+                0
+            }
+            else {
+                (used as u128) * 1_000_000 / (limit as u128)
+            }
+        };
+
+        let block_share = vec![
+            share(self.txinfo.metadata.len, MAX_EPOCH_SIZE as u64),
+            share(cost.write_length, budget.write_length),
+            share(cost.write_count, budget.write_count),
+            share(cost.read_length, budget.read_length),
+            share(cost.read_count, budget.read_count),
+            share(cost.runtime, budget.runtime),
+        ].into_iter().max().unwrap_or(0);
+
+        (self.txinfo.tx.get_fee_rate() as u128) * 1_000_000 / cmp::max(block_share, 1)
+    }
+}
+
 impl StacksBlockBuilder {
-    fn from_parent_pubkey_hash(miner_id: usize, parent_chain_tip: &StacksHeaderInfo, total_work: &StacksWorkScore, proof: &VRFProof, pubkh: Hash160) -> StacksBlockBuilder {
+    fn from_parent_pubkey_hash(miner_id: usize, parent_chain_tip: &StacksHeaderInfo, total_work: &StacksWorkScore, proof: &VRFProof, pubkh: Hash160, burn_block_height: u32) -> StacksBlockBuilder {
         let header = StacksBlockHeader::from_parent_empty(&parent_chain_tip.anchored_header, parent_chain_tip.microblock_tail.as_ref(), total_work, proof, &pubkh);
 
         let mut header_bytes = vec![];
@@ -292,6 +389,7 @@ impl StacksBlockBuilder {
             total_anchored_fees: 0,
             total_confirmed_streamed_fees: 0,
             total_streamed_fees: 0,
+            burn_block_height: burn_block_height,
             bytes_so_far: bytes_so_far,
             anchored_done: false,
             prev_microblock_header: StacksMicroblockHeader::first_unsigned(&EMPTY_MICROBLOCK_PARENT_HASH, &Sha512Trunc256Sum([0u8; 32])),       // will be updated
@@ -301,37 +399,38 @@ impl StacksBlockBuilder {
         }
     }
 
-    pub fn from_parent(miner_id: usize, parent_chain_tip: &StacksHeaderInfo, total_work: &StacksWorkScore, proof: &VRFProof, microblock_privkey: &StacksPrivateKey) -> StacksBlockBuilder {
+    pub fn from_parent(miner_id: usize, parent_chain_tip: &StacksHeaderInfo, total_work: &StacksWorkScore, proof: &VRFProof, microblock_privkey: &StacksPrivateKey, burn_block_height: u32) -> StacksBlockBuilder {
         let mut pubk = StacksPublicKey::from_private(microblock_privkey);
         pubk.set_compressed(true);
         let pubkh = Hash160::from_data(&pubk.to_bytes());
 
-        let mut builder = StacksBlockBuilder::from_parent_pubkey_hash(miner_id, parent_chain_tip, total_work, proof, pubkh);
+        let mut builder = StacksBlockBuilder::from_parent_pubkey_hash(miner_id, parent_chain_tip, total_work, proof, pubkh, burn_block_height);
         builder.miner_privkey = microblock_privkey.clone();
         builder
     }
 
-    fn first_pubkey_hash(miner_id: usize, genesis_burn_header_hash: &BurnchainHeaderHash, genesis_burn_header_timestamp: u64, proof: &VRFProof, pubkh: Hash160) -> StacksBlockBuilder {
+    fn first_pubkey_hash(miner_id: usize, genesis_burn_header_hash: &BurnchainHeaderHash, genesis_burn_header_timestamp: u64, proof: &VRFProof, pubkh: Hash160, burn_block_height: u32) -> StacksBlockBuilder {
         let genesis_chain_tip = StacksHeaderInfo {
             anchored_header: StacksBlockHeader::genesis_block_header(),
             microblock_tail: None,
             block_height: 0,
             index_root: TrieHash([0u8; 32]),
             burn_header_hash: genesis_burn_header_hash.clone(),
+            burn_header_height: 0,
             burn_header_timestamp: genesis_burn_header_timestamp
         };
 
-        let mut builder = StacksBlockBuilder::from_parent_pubkey_hash(miner_id, &genesis_chain_tip, &StacksWorkScore::initial(), proof, pubkh);
+        let mut builder = StacksBlockBuilder::from_parent_pubkey_hash(miner_id, &genesis_chain_tip, &StacksWorkScore::initial(), proof, pubkh, burn_block_height);
         builder.header.parent_block = EMPTY_MICROBLOCK_PARENT_HASH.clone();
         builder
     }
     
-    pub fn first(miner_id: usize, genesis_burn_header_hash: &BurnchainHeaderHash, genesis_burn_header_timestamp: u64, proof: &VRFProof, microblock_privkey: &StacksPrivateKey) -> StacksBlockBuilder {
+    pub fn first(miner_id: usize, genesis_burn_header_hash: &BurnchainHeaderHash, genesis_burn_header_timestamp: u64, proof: &VRFProof, microblock_privkey: &StacksPrivateKey, burn_block_height: u32) -> StacksBlockBuilder {
         let mut pubk = StacksPublicKey::from_private(microblock_privkey);
         pubk.set_compressed(true);
         let pubkh = Hash160::from_data(&pubk.to_bytes());
 
-        let mut builder = StacksBlockBuilder::first_pubkey_hash(miner_id, genesis_burn_header_hash, genesis_burn_header_timestamp, proof, pubkh);
+        let mut builder = StacksBlockBuilder::first_pubkey_hash(miner_id, genesis_burn_header_hash, genesis_burn_header_timestamp, proof, pubkh, burn_block_height);
         builder.miner_privkey = microblock_privkey.clone();
         builder
     }
@@ -571,7 +670,9 @@ impl StacksBlockBuilder {
             None => vec![]
         };
 
-        let mut tx = chainstate.block_begin(&parent_burn_header_hash, &parent_header_hash, &new_burn_hash, &new_block_hash);
+        // evaluate the block at the height of the burnchain block it will be mined in, so the
+        // state root matches the one validators compute
+        let mut tx = chainstate.block_begin_at_burn_height(&parent_burn_header_hash, &parent_header_hash, &new_burn_hash, self.burn_block_height, &new_block_hash);
 
         test_debug!("Miner {}: Apply {} parent microblocks", self.miner_id, parent_microblocks.len());
 
@@ -633,15 +734,16 @@ impl StacksBlockBuilder {
         Ok((block, size, cost))
     }
 
-    /// Create a block builder for mining
+    /// Create a block builder for mining a block in the burnchain block at the given height
     pub fn make_block_builder(stacks_parent_header: &StacksHeaderInfo,
                               proof: VRFProof,
                               total_burn: u64,
-                              pubkey_hash: Hash160) -> Result<StacksBlockBuilder, Error> {
+                              pubkey_hash: Hash160,
+                              burn_block_height: u32) -> Result<StacksBlockBuilder, Error> {
 
         let builder = 
             if stacks_parent_header.burn_header_hash == FIRST_BURNCHAIN_BLOCK_HASH {
-                StacksBlockBuilder::first_pubkey_hash(0, &FIRST_BURNCHAIN_BLOCK_HASH, FIRST_BURNCHAIN_BLOCK_TIMESTAMP, &proof, pubkey_hash)
+                StacksBlockBuilder::first_pubkey_hash(0, &FIRST_BURNCHAIN_BLOCK_HASH, FIRST_BURNCHAIN_BLOCK_TIMESTAMP, &proof, pubkey_hash, burn_block_height)
             }
             else {
                 // building off an existing stacks block
@@ -650,14 +752,171 @@ impl StacksBlockBuilder {
                     work: stacks_parent_header.block_height.checked_add(1).expect("FATAL: block height overflow")
                 };
 
-                StacksBlockBuilder::from_parent_pubkey_hash(0, stacks_parent_header, &new_work, &proof, pubkey_hash)
+                StacksBlockBuilder::from_parent_pubkey_hash(0, stacks_parent_header, &new_work, &proof, pubkey_hash, burn_block_height)
             };
 
         Ok(builder)
     }
 
+    /// Is this candidate the next transaction for each account whose nonce it consumes?
+    fn is_candidate_ready(candidate: &MinerCandidate, pending: &HashMap<StacksAddress, BTreeMap<u64, Vec<usize>>>) -> bool {
+        candidate.nonce_keys.iter().all(|(addr, nonce)| {
+            match pending.get(addr).and_then(|nonces| nonces.keys().next()) {
+                Some(next_nonce) => *next_nonce == *nonce,
+                None => false
+            }
+        })
+    }
+
+    /// Mine as many of the given candidate transactions as will fit into the block, taking the
+    /// highest fee-rate transactions first.  A transaction is only considered once every
+    /// lower-nonce candidate from its origin and sponsor accounts has been mined or dropped, and
+    /// a transaction that would blow the block budget is skipped in favor of the remaining ones.
+    /// Returns the candidates that were not mined, and why.
+    pub fn mine_candidates(&mut self, clarity_tx: &mut ClarityTx, candidates: Vec<MemPoolTxInfo>, budget: &ExecutionCost) -> Vec<(Txid, TransactionDropReason)> {
+        let mut observed_costs = HashMap::new();
+        let mut dropped = vec![];
+        let unresolved = self.mine_candidate_window(clarity_tx, candidates, budget, &mut observed_costs, &mut dropped);
+        for txinfo in unresolved.into_iter() {
+            dropped.push((txinfo.tx.txid(), TransactionDropReason::NonceUnreachable));
+        }
+        dropped
+    }
+
+    /// Mine one window of candidate transactions, as in `mine_candidates()`.  The execution costs
+    /// observed so far are used to estimate the fee-rates of contract calls, and are updated with
+    /// the costs of the calls mined here.  Dropped candidates are appended to `dropped`.
+    /// Returns the candidates whose nonces could not be reached yet, so they can be tried again
+    /// alongside the next window.
+    fn mine_candidate_window(&mut self, clarity_tx: &mut ClarityTx, candidates: Vec<MemPoolTxInfo>, budget: &ExecutionCost,
+                             observed_costs: &mut HashMap<(QualifiedContractIdentifier, ClarityName), ExecutionCost>,
+                             dropped: &mut Vec<(Txid, TransactionDropReason)>) -> Vec<MemPoolTxInfo> {
+        let mut candidates : Vec<MinerCandidate> = candidates.into_iter().map(MinerCandidate::new).collect();
+        candidates.sort_by(|c1, c2| (c1.txinfo.metadata.accept_time, c1.txinfo.tx.txid()).cmp(&(c2.txinfo.metadata.accept_time, c2.txinfo.tx.txid())));
+
+        // pending nonces for each account, and the candidates that would consume them
+        let mut pending : HashMap<StacksAddress, BTreeMap<u64, Vec<usize>>> = HashMap::new();
+        for (i, candidate) in candidates.iter().enumerate() {
+            for (addr, nonce) in candidate.nonce_keys.iter() {
+                pending.entry(addr.clone()).or_insert_with(BTreeMap::new)
+                    .entry(*nonce).or_insert_with(Vec::new)
+                    .push(i);
+            }
+        }
+
+        let mut resolved = vec![false; candidates.len()];
+        let mut queued = vec![false; candidates.len()];
+
+        // highest fee-rate first; ties go to the earliest arrival
+        let mut ready = BinaryHeap::new();
+        for (i, candidate) in candidates.iter().enumerate() {
+            if StacksBlockBuilder::is_candidate_ready(candidate, &pending) {
+                queued[i] = true;
+                ready.push((candidate.fee_rate(observed_costs, budget), cmp::Reverse(i)));
+            }
+        }
+
+        while let Some((fee_rate, cmp::Reverse(i))) = ready.pop() {
+            if resolved[i] {
+                continue;
+            }
+
+            // costs observed since this candidate was queued may have changed its estimated
+            // fee-rate, and thus its rank
+            let current_fee_rate = candidates[i].fee_rate(observed_costs, budget);
+            if current_fee_rate != fee_rate {
+                ready.push((current_fee_rate, cmp::Reverse(i)));
+                continue;
+            }
+
+            let txinfo = &candidates[i].txinfo;
+            let cost_before = clarity_tx.cost_so_far();
+            let mined = match self.try_mine_tx_with_len(clarity_tx, &txinfo.tx, txinfo.metadata.len) {
+                Ok(_) => {
+                    if let TransactionPayload::ContractCall(ref cc) = txinfo.tx.payload {
+                        let mut cost = clarity_tx.cost_so_far();
+                        if cost.sub(&cost_before).is_ok() {
+                            let key = (QualifiedContractIdentifier::new(StandardPrincipalData::from(cc.address.clone()), cc.contract_name.clone()), cc.function_name.clone());
+                            observed_costs.insert(key, cost);
+                        }
+                    }
+                    true
+                },
+                Err(Error::BlockTooBigError) => {
+                    // keep going -- a smaller transaction may still fit
+                    debug!("Block budget exceeded on tx {}", &txinfo.tx.txid());
+                    dropped.push((txinfo.tx.txid(), TransactionDropReason::BudgetExceeded));
+                    false
+                },
+                Err(Error::InvalidStacksTransaction(msg, quiet)) => {
+                    // if we have an invalid transaction that was quietly ignored, don't warn here either
+                    if !quiet {
+                        warn!("Failed to apply tx {}: {}", &txinfo.tx.txid(), &msg);
+                    }
+                    dropped.push((txinfo.tx.txid(), TransactionDropReason::Invalid(msg)));
+                    false
+                },
+                Err(e) => {
+                    warn!("Failed to apply tx {}: {:?}", &txinfo.tx.txid(), &e);
+                    dropped.push((txinfo.tx.txid(), TransactionDropReason::Invalid(format!("{}", &e))));
+                    false
+                }
+            };
+
+            // retire this candidate's nonces.  If it was mined, then every other candidate that
+            // consumes one of the same nonces can no longer be mined, so retire them too.
+            let mut retire = vec![i];
+            let mut touched = vec![];
+            while let Some(j) = retire.pop() {
+                if resolved[j] {
+                    continue;
+                }
+                resolved[j] = true;
+                if j != i {
+                    dropped.push((candidates[j].txinfo.tx.txid(), TransactionDropReason::NonceConflict));
+                }
+
+                for (addr, nonce) in candidates[j].nonce_keys.iter() {
+                    if let Some(nonces) = pending.get_mut(addr) {
+                        let mut conflicts = nonces.remove(nonce).unwrap_or(vec![]);
+                        conflicts.retain(|k| *k != j);
+                        if j == i && mined {
+                            retire.append(&mut conflicts);
+                        }
+                        else if conflicts.len() > 0 {
+                            nonces.insert(*nonce, conflicts);
+                        }
+                    }
+                    touched.push(addr.clone());
+                }
+            }
+
+            // candidates at the front of each touched account's nonce queue may now be ready
+            for addr in touched.iter() {
+                let next = match pending.get(addr).and_then(|nonces| nonces.values().next()) {
+                    Some(next) => next.clone(),
+                    None => {
+                        continue;
+                    }
+                };
+                for j in next.into_iter() {
+                    if !resolved[j] && !queued[j] && StacksBlockBuilder::is_candidate_ready(&candidates[j], &pending) {
+                        queued[j] = true;
+                        ready.push((candidates[j].fee_rate(observed_costs, budget), cmp::Reverse(j)));
+                    }
+                }
+            }
+        }
+
+        candidates.into_iter()
+            .zip(resolved.into_iter())
+            .filter_map(|(candidate, resolved)| if resolved { None } else { Some(candidate.txinfo) })
+            .collect()
+    }
+
     /// Given access to the mempool, mine an anchored block with no more than the given execution cost.
-    ///   returns the assembled block, and the consumed execution budget.
+    ///   returns the assembled block, the consumed execution budget, the block's size, and the
+    ///   candidate transactions that were left out of the block (and why).
     pub fn build_anchored_block(chainstate_handle: &StacksChainState,       // not directly used; used as a handle to open other chainstates
                                 mempool: &MemPoolDB,
                                 parent_stacks_header: &StacksHeaderInfo,    // Stacks header we're building off of
@@ -665,7 +924,8 @@ impl StacksBlockBuilder {
                                 proof: VRFProof,                            // proof over the burnchain's last seed
                                 pubkey_hash: Hash160,
                                 coinbase_tx: &StacksTransaction,
-                                execution_budget: ExecutionCost) -> Result<(StacksBlock, ExecutionCost, u64), Error> {
+                                execution_budget: ExecutionCost,
+                                burn_block_height: u32) -> Result<(StacksBlock, ExecutionCost, u64, Vec<(Txid, TransactionDropReason)>), Error> {
 
         if let TransactionPayload::Coinbase(..) = coinbase_tx.payload {} else {
             return Err(Error::MemPoolError("Not a coinbase transaction".to_string()));
@@ -676,61 +936,34 @@ impl StacksBlockBuilder {
         debug!("Build anchored block off of {}/{} height {}", &tip_burn_header_hash, &tip_block_hash, tip_height); 
         
         let mut header_reader_chainstate = chainstate_handle.reopen()?;            // used for reading block headers during an epoch
-        let mut chainstate = chainstate_handle.reopen_limited(execution_budget)?;  // used for processing a block up to the given limit
+        let mut chainstate = chainstate_handle.reopen_limited(execution_budget.clone())?;  // used for processing a block up to the given limit
 
-        let mut builder = StacksBlockBuilder::make_block_builder(parent_stacks_header, proof, total_burn, pubkey_hash)?;
+        let mut builder = StacksBlockBuilder::make_block_builder(parent_stacks_header, proof, total_burn, pubkey_hash, burn_block_height)?;
 
         let mut epoch_tx = builder.epoch_begin(&mut chainstate)?;
         builder.try_mine_tx(&mut epoch_tx, coinbase_tx)?;
 
+        // rank candidate transactions by fee-rate, one window at a time.  Candidates whose
+        // nonces can't be reached yet are carried over into the next window, unless they would
+        // crowd out the new candidates.
+        let mut window = vec![];
         let mut considered = HashSet::new();        // txids of all transactions we looked at
-        let mut mined_origin_nonces : HashMap<StacksAddress, u64> = HashMap::new();     // map addrs of mined transaction origins to the nonces we used
-        let mut mined_sponsor_nonces : HashMap<StacksAddress, u64> = HashMap::new();    // map addrs of mined transaction sponsors to the nonces we used
-
+        let mut observed_costs = HashMap::new();
+        let mut dropped = vec![];
         let result = mempool.iterate_candidates(&tip_burn_header_hash, &tip_block_hash, tip_height, &mut header_reader_chainstate, |available_txs| {
             for txinfo in available_txs.into_iter() {
-                // skip transactions early if we can
-                if considered.contains(&txinfo.tx.txid()) {
-                    continue;
+                if considered.insert(txinfo.tx.txid()) {
+                    window.push(txinfo);
                 }
-                if let Some(nonce) = mined_origin_nonces.get(&txinfo.tx.origin_address()) {
-                    if *nonce >= txinfo.tx.get_origin_nonce() {
-                        continue;
-                    }
-                }
-                if let Some(sponsor_addr) = txinfo.tx.sponsor_address() {
-                    if let Some(nonce) = mined_sponsor_nonces.get(&sponsor_addr) {
-                        if let Some(sponsor_nonce) = txinfo.tx.get_sponsor_nonce() {
-                            if *nonce >= sponsor_nonce {
-                                continue;
-                            }
+                if window.len() >= MINER_CANDIDATE_WINDOW {
+                    let candidates = mem::replace(&mut window, vec![]);
+                    window = builder.mine_candidate_window(&mut epoch_tx, candidates, &execution_budget, &mut observed_costs, &mut dropped);
+                    if window.len() >= MINER_CANDIDATE_WINDOW / 2 {
+                        for txinfo in window.drain(..) {
+                            dropped.push((txinfo.tx.txid(), TransactionDropReason::NonceUnreachable));
                         }
                     }
                 }
-
-                considered.insert(txinfo.tx.txid());
-
-                match builder.try_mine_tx_with_len(&mut epoch_tx, &txinfo.tx, txinfo.metadata.len) {
-                    Ok(_) => {},
-                    Err(Error::BlockTooBigError) => {
-                        // done mining -- our execution budget is exceeded.
-                        // Make the block from the transactions we did manage to get
-                        debug!("Block budget exceeded on tx {}", &txinfo.tx.txid());
-                    },
-                    Err(Error::InvalidStacksTransaction(_, true)) => {
-                        // if we have an invalid transaction that was quietly ignored, don't warn here either
-                        continue;
-                    },
-                    Err(e) => {
-                        warn!("Failed to apply tx {}: {:?}", &txinfo.tx.txid(), &e);
-                        continue;
-                    }
-                }
-
-                mined_origin_nonces.insert(txinfo.tx.origin_address(), txinfo.tx.get_origin_nonce());
-                if let (Some(sponsor_addr), Some(sponsor_nonce)) = (txinfo.tx.sponsor_address(), txinfo.tx.get_sponsor_nonce()) {
-                    mined_sponsor_nonces.insert(sponsor_addr, sponsor_nonce);
-                }
             }
             Ok(())
         });
@@ -746,12 +979,16 @@ impl StacksBlockBuilder {
 
         // the prior do_rebuild logic wasn't necessary
         // a transaction that caused a budget exception is rolled back in process_transaction
+        let unresolved = builder.mine_candidate_window(&mut epoch_tx, window, &execution_budget, &mut observed_costs, &mut dropped);
+        for txinfo in unresolved.into_iter() {
+            dropped.push((txinfo.tx.txid(), TransactionDropReason::NonceUnreachable));
+        }
 
         // save the block so we can build microblocks off of it
         let block = builder.mine_anchored_block(&mut epoch_tx);
         let size = builder.bytes_so_far;
         let consumed = builder.epoch_finish(epoch_tx);
-        Ok((block, consumed, size))
+        Ok((block, consumed, size, dropped))
     }
 }
 
@@ -1254,7 +1491,7 @@ pub mod test {
             let (builder, parent_block_snapshot_opt) = match parent_stacks_block {
                 None => {
                     // first stacks block
-                    let builder = StacksBlockBuilder::first(miner.id, &burn_block.parent_snapshot.burn_header_hash, burn_block.parent_snapshot.burn_header_timestamp, &proof, &miner.next_microblock_privkey());
+                    let builder = StacksBlockBuilder::first(miner.id, &burn_block.parent_snapshot.burn_header_hash, burn_block.parent_snapshot.burn_header_timestamp, &proof, &miner.next_microblock_privkey(), burn_block.block_height as u32);
                     (builder, None)
                 },
                 Some(parent_stacks_block) => {
@@ -1274,7 +1511,7 @@ pub mod test {
                     };
 
                     test_debug!("Work in {} {}: {},{}", burn_block.block_height, burn_block.parent_snapshot.burn_header_hash, new_work.burn, new_work.work);
-                    let builder = StacksBlockBuilder::from_parent(miner.id, &parent_chain_tip, &new_work, &proof, &miner.next_microblock_privkey());
+                    let builder = StacksBlockBuilder::from_parent(miner.id, &parent_chain_tip, &new_work, &proof, &miner.next_microblock_privkey(), burn_block.block_height as u32);
                     (builder, Some(parent_stacks_block_snapshot))
                 }
             };
@@ -2997,6 +3234,34 @@ pub mod test {
         (stacks_block, vec![])
     }
     
+    /// mine a smart contract whose state depends on the burnchain block height the block is
+    /// evaluated at
+    pub fn mine_burn_block_height_contract_block<'a>(clarity_tx: &mut ClarityTx<'a>, builder: &mut StacksBlockBuilder, miner: &mut TestMiner, burnchain_height: usize, parent_microblock_header: Option<&StacksMicroblockHeader>) -> (StacksBlock, Vec<StacksMicroblock>) {
+        let miner_account = StacksChainState::get_account(clarity_tx, &miner.origin_address().unwrap().to_account_principal());
+        miner.set_nonce(miner_account.nonce);
+
+        // make a coinbase for this miner
+        let tx_coinbase_signed = make_coinbase(miner, burnchain_height);
+        builder.try_mine_tx(clarity_tx, &tx_coinbase_signed).unwrap();
+
+        let mut tx_contract = StacksTransaction::new(TransactionVersion::Testnet,
+                                                     miner.as_transaction_auth().unwrap(),
+                                                     TransactionPayload::new_smart_contract(&format!("mined-at-{}", burnchain_height), &"(define-data-var mined-at uint burn-block-height)".to_string()).unwrap());
+
+        tx_contract.chain_id = 0x80000000;
+        tx_contract.auth.set_origin_nonce(miner.get_nonce());
+        tx_contract.set_fee_rate(0);
+
+        let mut tx_signer = StacksTransactionSigner::new(&tx_contract);
+        miner.sign_as_origin(&mut tx_signer);
+        builder.try_mine_tx(clarity_tx, &tx_signer.get_tx().unwrap()).unwrap();
+
+        let stacks_block = builder.mine_anchored_block(clarity_tx);
+
+        test_debug!("Produce anchored stacks block {} with a burn-block-height contract at burnchain height {} stacks height {}", stacks_block.block_hash(), burnchain_height, stacks_block.header.total_work.work);
+        (stacks_block, vec![])
+    }
+
     /// mine a smart contract in an anchored block, and mine some contract-calls to it in a microblock tail
     pub fn mine_smart_contract_block_contract_call_microblock<'a>(clarity_tx: &mut ClarityTx<'a>, builder: &mut StacksBlockBuilder, miner: &mut TestMiner, burnchain_height: usize, parent_microblock_header: Option<&StacksMicroblockHeader>) -> (StacksBlock, Vec<StacksMicroblock>) {
         if burnchain_height > 0 && builder.chain_tip.anchored_header.total_work.work > 0 {
@@ -3216,6 +3481,69 @@ pub mod test {
         miner_trace_replay_randomized(&mut miner_trace);
     }
     
+    #[test]
+    fn mine_anchored_blocks_after_burnchain_blocks_without_sortitions() {
+        // the miner and the validator must evaluate each block at the height of the burnchain
+        // block it was mined in, even if some burnchain blocks went by without a sortition.
+        let test_name = "mine_anchored_blocks_after_burnchain_blocks_without_sortitions";
+        let mut node = TestStacksNode::new(false, 0x80000000, test_name);
+        let mut burn_node = TestBurnchainNode::new();
+        let mut miner_factory = TestMinerFactory::new();
+        let mut miner = miner_factory.next_miner(&burn_node.burnchain, 1, 1, AddressHashMode::SerializeP2PKH);
+
+        let first_snapshot = SortitionDB::get_first_block_snapshot(burn_node.sortdb.conn()).unwrap();
+        let mut fork = TestBurnchainFork::new(first_snapshot.block_height, &first_snapshot.burn_header_hash, &first_snapshot.index_root, 0);
+
+        let mut first_burn_block = TestStacksNode::next_burn_block(&mut burn_node.sortdb, &mut fork);
+        node.add_key_register(&mut first_burn_block, &mut miner);
+        fork.append_block(first_burn_block);
+        burn_node.mine_fork(&mut fork);
+
+        for i in 0..3 {
+            let mut burn_block = {
+                let ic = burn_node.sortdb.index_conn();
+                fork.next_block(&ic)
+            };
+
+            let last_key = node.get_last_key(&miner);
+            let parent_block_opt = node.get_last_accepted_anchored_block(&miner);
+            node.add_key_register(&mut burn_block, &mut miner);
+
+            let (stacks_block, microblocks, block_commit_op) = node.mine_stacks_block(&mut burn_node.sortdb, &mut miner, &mut burn_block, &last_key, parent_block_opt.as_ref(), 1000, |mut builder, ref mut miner| {
+                let mut miner_chainstate = open_chainstate(false, 0x80000000, test_name);
+                let mut epoch = builder.epoch_begin(&mut miner_chainstate).unwrap();
+                let (stacks_block, microblocks) = mine_burn_block_height_contract_block(&mut epoch, &mut builder, miner, i, None);
+                builder.epoch_finish(epoch);
+                (stacks_block, microblocks)
+            });
+
+            fork.append_block(burn_block);
+            let fork_snapshot = burn_node.mine_fork(&mut fork);
+
+            preprocess_stacks_block_data(&mut node, &mut burn_node, &fork_snapshot, &stacks_block, &microblocks, &block_commit_op);
+            let tip_info_list = node.chainstate.process_blocks(&mut burn_node.sortdb, 1).unwrap();
+
+            // the validator accepted the block, so it computed the miner's state root
+            assert_eq!(tip_info_list.len(), 1);
+            let chain_tip = tip_info_list[0].0.clone().unwrap().header;
+            assert_eq!(chain_tip.anchored_header.block_hash(), stacks_block.block_hash());
+            assert_eq!(chain_tip.anchored_header.state_index_root, stacks_block.header.state_index_root);
+            assert!(check_block_state_index_root(&mut node.chainstate, &fork_snapshot.burn_header_hash, &chain_tip.anchored_header));
+
+            let index_block_hash = StacksBlockHeader::make_index_block_hash(&fork_snapshot.burn_header_hash, &stacks_block.block_hash());
+            let contract_id = QualifiedContractIdentifier::new(StandardPrincipalData::from(miner.origin_address().unwrap()), ContractName::try_from(format!("mined-at-{}", i).as_str()).unwrap());
+            assert_eq!(node.chainstate.clarity_eval_read_only(&index_block_hash, &contract_id, "(var-get mined-at)"), Value::UInt(fork_snapshot.block_height as u128));
+
+            // a burnchain block goes by without a sortition
+            let empty_burn_block = {
+                let ic = burn_node.sortdb.index_conn();
+                fork.next_block(&ic)
+            };
+            fork.append_block(empty_burn_block);
+            burn_node.mine_fork(&mut fork);
+        }
+    }
+
     #[test]
     fn mine_anchored_smart_contract_contract_call_blocks_single() {
         mine_stacks_blocks_1_fork_1_miner_1_burnchain(&"smart-contract-contract-call-anchored-blocks".to_string(), 10, mine_smart_contract_contract_call_block, |_, _| true);
@@ -3463,14 +3791,14 @@ pub mod test {
 
         let num_blocks = 10;
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
         let mut last_block : Option<StacksBlock> = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             assert_eq!(tip.block_height, first_stacks_block_height + (tenure_id as u64));
             if let Some(block) = last_block {
@@ -3496,7 +3824,7 @@ pub mod test {
 
                 let coinbase_tx = make_coinbase(miner, tenure_id);
 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
                 (anchored_block.0, vec![])
             });
 
@@ -3523,7 +3851,7 @@ pub mod test {
 
         let num_blocks = 10;
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -3534,7 +3862,7 @@ pub mod test {
         let mut last_block = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -3564,7 +3892,7 @@ pub mod test {
 
                     mempool.submit(&parent_tip_bhh, &parent_header_hash, stx_transfer).unwrap();
                 } 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
                 (anchored_block.0, vec![])
             });
             
@@ -3609,7 +3937,7 @@ pub mod test {
         let chainstate_path = peer.chainstate_path.clone();
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -3620,7 +3948,7 @@ pub mod test {
         let mut last_block = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -3661,7 +3989,7 @@ pub mod test {
                     sender_nonce += 1;
                 }
 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
                 (anchored_block.0, vec![])
             });
             
@@ -3716,7 +4044,7 @@ pub mod test {
         let chainstate_path = peer.chainstate_path.clone();
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -3727,7 +4055,7 @@ pub mod test {
         let mut last_block = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -3786,7 +4114,7 @@ pub mod test {
                     runtime: 3350
                 };
 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, execution_cost).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, execution_cost, (tip.block_height + 1) as u32).unwrap();
                 (anchored_block.0, vec![])
             });
             
@@ -3811,6 +4139,206 @@ pub mod test {
         }
     }
     
+    #[test]
+    fn test_mine_candidates_fee_rate_order() {
+        let privks : Vec<StacksPrivateKey> = (0..4).map(|_| StacksPrivateKey::new()).collect();
+        let addrs : Vec<StacksAddress> = privks.iter().map(|pk| StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(pk)]).unwrap()).collect();
+
+        // the last account has no STX
+        let balances = addrs[0..3].iter().map(|addr| (addr.clone(), 1000000)).collect();
+        let mut chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "test_mine_candidates_fee_rate_order", balances);
+        let parent_tip = StacksChainState::get_genesis_header_info(&chainstate.headers_db).unwrap();
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let low_fee = make_user_stacks_transfer(&privks[0], 0, 200, &recipient, 1);
+        let chained = make_user_stacks_transfer(&privks[0], 1, 2000, &recipient, 1);
+        let mid_fee = make_user_stacks_transfer(&privks[1], 0, 500, &recipient, 1);
+        let mid_fee_conflict = make_user_stacks_transfer(&privks[1], 0, 400, &recipient, 2);
+        let high_fee = make_user_stacks_transfer(&privks[2], 0, 1000, &recipient, 1);
+        let unfunded = make_user_stacks_transfer(&privks[3], 0, 3000, &recipient, 1);
+        let nonce_gap = make_user_stacks_transfer(&privks[2], 2, 5000, &recipient, 1);
+
+        let candidates = vec![&low_fee, &chained, &mid_fee, &mid_fee_conflict, &high_fee, &unfunded, &nonce_gap].into_iter()
+            .map(|tx| MemPoolTxInfo::from_tx(tx.clone(), tx.get_fee_rate(), parent_tip.burn_header_hash.clone(), parent_tip.anchored_header.block_hash(), parent_tip.block_height))
+            .collect();
+
+        let mut builder = StacksBlockBuilder::make_block_builder(&parent_tip, VRFProof::empty(), 0, Hash160([0u8; 20]), 1).unwrap();
+        let mut epoch_tx = builder.epoch_begin(&mut chainstate).unwrap();
+        let dropped = builder.mine_candidates(&mut epoch_tx, candidates, &ExecutionCost::max_value());
+        epoch_tx.rollback_block();
+
+        // highest fee-rate first, but never ahead of a lower nonce from the same account
+        let mined : Vec<Txid> = builder.txs.iter().map(|tx| tx.txid()).collect();
+        assert_eq!(mined, vec![high_fee.txid(), mid_fee.txid(), low_fee.txid(), chained.txid()]);
+
+        assert_eq!(dropped.len(), 3);
+        match dropped[0] {
+            (ref txid, TransactionDropReason::Invalid(_)) => assert_eq!(*txid, unfunded.txid()),
+            _ => panic!("expected the unfunded transaction to be dropped first")
+        }
+        assert!(dropped.contains(&(mid_fee_conflict.txid(), TransactionDropReason::NonceConflict)));
+        assert!(dropped.iter().any(|(txid, reason)| *txid == nonce_gap.txid() && reason != &TransactionDropReason::NonceConflict));
+    }
+
+    #[test]
+    fn test_mine_candidates_budget() {
+        let privks : Vec<StacksPrivateKey> = (0..4).map(|_| StacksPrivateKey::new()).collect();
+        let addrs : Vec<StacksAddress> = privks.iter().map(|pk| StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(pk)]).unwrap()).collect();
+        let balances = addrs.iter().map(|addr| (addr.clone(), 1000000)).collect();
+        let mut chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "test_mine_candidates_budget", balances);
+        let parent_tip = StacksChainState::get_genesis_header_info(&chainstate.headers_db).unwrap();
+
+        let small_contract = "(define-data-var bar int 0) (define-public (get-bar) (ok (var-get bar)))";
+        let mut big_contract = small_contract.to_string();
+        for i in 0..50 {
+            big_contract.push_str(&format!(" (define-data-var var-{} int 0)", i));
+        }
+
+        let to_candidates = |txs: Vec<&StacksTransaction>| -> Vec<MemPoolTxInfo> {
+            txs.into_iter()
+                .map(|tx| MemPoolTxInfo::from_tx(tx.clone(), tx.get_fee_rate(), parent_tip.burn_header_hash.clone(), parent_tip.anchored_header.block_hash(), parent_tip.block_height))
+                .collect()
+        };
+
+        let big = make_user_contract_publish(&privks[0], 0, 100000, "big-contract", &big_contract);
+        let small_1 = make_user_contract_publish(&privks[1], 0, 2000, "small-contract", small_contract);
+        let small_2 = make_user_contract_publish(&privks[2], 0, 1000, "small-contract", small_contract);
+        let transfer = make_user_stacks_transfer(&privks[3], 0, 10, &addrs[0].to_account_principal(), 1);
+
+        // find out what one small contract costs
+        let small_cost = {
+            let mut builder = StacksBlockBuilder::make_block_builder(&parent_tip, VRFProof::empty(), 0, Hash160([0u8; 20]), 1).unwrap();
+            let mut epoch_tx = builder.epoch_begin(&mut chainstate).unwrap();
+            let dropped = builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&small_1]), &ExecutionCost::max_value());
+            assert_eq!(dropped, vec![]);
+            let cost = epoch_tx.cost_so_far();
+            epoch_tx.rollback_block();
+            cost
+        };
+
+        // room for one and a half small contracts
+        let budget = ExecutionCost {
+            write_length: small_cost.write_length * 3 / 2 + 1,
+            write_count: small_cost.write_count * 3 / 2 + 1,
+            read_length: small_cost.read_length * 3 / 2 + 1,
+            read_count: small_cost.read_count * 3 / 2 + 1,
+            runtime: small_cost.runtime * 3 / 2 + 1
+        };
+        let mut limited_chainstate = chainstate.reopen_limited(budget.clone()).unwrap();
+        let mut builder = StacksBlockBuilder::make_block_builder(&parent_tip, VRFProof::empty(), 0, Hash160([0u8; 20]), 1).unwrap();
+        let mut epoch_tx = builder.epoch_begin(&mut limited_chainstate).unwrap();
+        let dropped = builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&big, &small_1, &small_2, &transfer]), &budget);
+        epoch_tx.rollback_block();
+
+        // the big contract never fits, so the miner skips it and keeps going.  The first small
+        // contract fits, which leaves no room for the second, but the transfer still fits.
+        let mined : Vec<Txid> = builder.txs.iter().map(|tx| tx.txid()).collect();
+        assert_eq!(mined, vec![small_1.txid(), transfer.txid()]);
+        assert_eq!(dropped, vec![(big.txid(), TransactionDropReason::BudgetExceeded),
+                                 (small_2.txid(), TransactionDropReason::BudgetExceeded)]);
+    }
+
+    #[test]
+    fn test_mine_candidates_rerank() {
+        let privks : Vec<StacksPrivateKey> = (0..4).map(|_| StacksPrivateKey::new()).collect();
+        let addrs : Vec<StacksAddress> = privks.iter().map(|pk| StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(pk)]).unwrap()).collect();
+        let balances = addrs.iter().map(|addr| (addr.clone(), 1000000)).collect();
+        let mut chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "test_mine_candidates_rerank", balances);
+        let parent_tip = StacksChainState::get_genesis_header_info(&chainstate.headers_db).unwrap();
+
+        let to_candidates = |txs: Vec<&StacksTransaction>| -> Vec<MemPoolTxInfo> {
+            txs.into_iter()
+                .map(|tx| MemPoolTxInfo::from_tx(tx.clone(), tx.get_fee_rate(), parent_tip.burn_header_hash.clone(), parent_tip.anchored_header.block_hash(), parent_tip.block_height))
+                .collect()
+        };
+        let make_call = |privk: &StacksPrivateKey, nonce: u64, fee_rate: u64| -> StacksTransaction {
+            let payload = TransactionContractCall {
+                address: addrs[0].clone(),
+                contract_name: ContractName::try_from("counter").unwrap(),
+                function_name: ClarityName::try_from("incr").unwrap(),
+                function_args: vec![]
+            };
+            sign_standard_singlesig_tx(TransactionPayload::ContractCall(payload), privk, nonce, fee_rate)
+        };
+
+        let contract = "(define-data-var n int 0) (define-public (incr) (begin (var-set n (+ (var-get n) 1)) (ok (var-get n))))";
+        let publish = make_user_contract_publish(&privks[0], 0, 1000, "counter", contract);
+        let probe = make_call(&privks[0], 1, 1000);
+        let call_1 = make_call(&privks[1], 0, 1000);
+        let call_2 = make_call(&privks[2], 0, 900);
+        let transfer = make_user_stacks_transfer(&privks[3], 0, 100, &addrs[0].to_account_principal(), 1);
+
+        let mut builder = StacksBlockBuilder::make_block_builder(&parent_tip, VRFProof::empty(), 0, Hash160([0u8; 20]), 1).unwrap();
+        let mut epoch_tx = builder.epoch_begin(&mut chainstate).unwrap();
+        assert_eq!(builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&publish]), &ExecutionCost::max_value()), vec![]);
+
+        // find out what one call costs
+        let cost_before = epoch_tx.cost_so_far();
+        assert_eq!(builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&probe]), &ExecutionCost::max_value()), vec![]);
+        let mut call_cost = epoch_tx.cost_so_far();
+        call_cost.sub(&cost_before).unwrap();
+
+        // each call takes a tenth of the block
+        let budget = ExecutionCost {
+            write_length: call_cost.write_length * 10,
+            write_count: call_cost.write_count * 10,
+            read_length: call_cost.read_length * 10,
+            read_count: call_cost.read_count * 10,
+            runtime: call_cost.runtime * 10
+        };
+        let dropped = builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&call_1, &call_2, &transfer]), &budget);
+        epoch_tx.rollback_block();
+        assert_eq!(dropped, vec![]);
+
+        // nothing is known about the calls' costs until the first one is mined.  After that, the
+        // second call is expected to cost far more per unit of fee than the transfer.
+        let mined : Vec<Txid> = builder.txs.iter().map(|tx| tx.txid()).collect();
+        assert_eq!(mined, vec![publish.txid(), probe.txid(), call_1.txid(), transfer.txid(), call_2.txid()]);
+    }
+
+    #[test]
+    fn test_mine_candidates_bad_nonces() {
+        let privk = StacksPrivateKey::new();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let mut chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "test_mine_candidates_bad_nonces", vec![(addr.clone(), 1000000)]);
+        let parent_tip = StacksChainState::get_genesis_header_info(&chainstate.headers_db).unwrap();
+
+        let to_candidates = |txs: Vec<&StacksTransaction>| -> Vec<MemPoolTxInfo> {
+            txs.into_iter()
+                .map(|tx| MemPoolTxInfo::from_tx(tx.clone(), tx.get_fee_rate(), parent_tip.burn_header_hash.clone(), parent_tip.anchored_header.block_hash(), parent_tip.block_height))
+                .collect()
+        };
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let first = make_user_stacks_transfer(&privk, 0, 100, &recipient, 1);
+        let stale = make_user_stacks_transfer(&privk, 0, 200, &recipient, 2);
+        let next = make_user_stacks_transfer(&privk, 1, 100, &recipient, 1);
+        let gap = make_user_stacks_transfer(&privk, 3, 100, &recipient, 1);
+
+        let mut builder = StacksBlockBuilder::make_block_builder(&parent_tip, VRFProof::empty(), 0, Hash160([0u8; 20]), 1).unwrap();
+        let mut epoch_tx = builder.epoch_begin(&mut chainstate).unwrap();
+        let dropped = builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&first]), &ExecutionCost::max_value());
+        assert_eq!(dropped, vec![]);
+
+        // nonce 0 was already used, and nonce 3 skips nonce 2
+        let dropped = builder.mine_candidates(&mut epoch_tx, to_candidates(vec![&stale, &next, &gap]), &ExecutionCost::max_value());
+        epoch_tx.rollback_block();
+
+        let mined : Vec<Txid> = builder.txs.iter().map(|tx| tx.txid()).collect();
+        assert_eq!(mined, vec![first.txid(), next.txid()]);
+
+        assert_eq!(dropped.len(), 2);
+        for (dropped_tx, expected_tx) in dropped.iter().zip(vec![&stale, &gap]) {
+            match dropped_tx {
+                (ref txid, TransactionDropReason::Invalid(ref msg)) => {
+                    assert_eq!(*txid, expected_tx.txid());
+                    assert!(msg.contains("Bad nonce"));
+                },
+                _ => panic!("expected {} to be dropped for its nonce", expected_tx.txid())
+            }
+        }
+    }
+
     #[test]
     fn test_build_anchored_blocks_multiple_chaintips() {
         let mut privks = vec![];
@@ -3833,14 +4361,14 @@ pub mod test {
         let chainstate_path = peer.chainstate_path.clone();
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
         let mut last_block = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -3887,7 +4415,7 @@ pub mod test {
                         ExecutionCost::max_value()
                     };
                 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, execution_cost).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, execution_cost, (tip.block_height + 1) as u32).unwrap();
                 (anchored_block.0, vec![])
             });
             
@@ -3927,14 +4455,14 @@ pub mod test {
         let chainstate_path = peer.chainstate_path.clone();
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
         let mut last_block = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -3954,7 +4482,7 @@ pub mod test {
 
                 let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
 
                 // submit a transaction for the _next_ block to pick up
                 if tenure_id > 0 {
@@ -4010,14 +4538,14 @@ pub mod test {
         let chainstate_path = peer.chainstate_path.clone();
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
         let mut last_block = None;
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -4065,7 +4593,7 @@ pub mod test {
                     sleep_ms(2000);
                 }
 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
 
                 (anchored_block.0, vec![])
             });
@@ -4090,7 +4618,7 @@ pub mod test {
 
         let num_blocks = 10;
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -4114,7 +4642,7 @@ pub mod test {
 
         for tenure_id in 0..num_blocks {
             // send transactions to the mempool
-            let mut tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let mut tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             
             if tenure_id == bad_block_ancestor_tenure {
                 bad_block_tip = Some(tip.clone());
@@ -4217,7 +4745,7 @@ pub mod test {
 
                 let coinbase_tx = make_coinbase(miner, tenure_id as usize);
 
-                let mut anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let mut anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
 
                 if tenure_id == bad_block_tenure {
                     // corrupt the block
@@ -4297,7 +4825,7 @@ pub mod test {
         let chainstate_path = peer.chainstate_path.clone();
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -4305,7 +4833,7 @@ pub mod test {
         for tenure_id in 0..num_blocks {
             eprintln!("Start tenure {:?}", tenure_id);
             // send transactions to the mempool
-            let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
+            let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn()).unwrap();
 
             let (burn_ops, stacks_block, microblocks) = peer.make_tenure(|ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, ref parent_microblock_header_opt| {
                 let parent_tip = match parent_opt {
@@ -4385,7 +4913,7 @@ pub mod test {
                     sleep_ms(2000);
                 }
 
-                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value()).unwrap();
+                let anchored_block = StacksBlockBuilder::build_anchored_block(chainstate, &mempool, &parent_tip, tip.total_burn, vrf_proof, Hash160([tenure_id as u8; 20]), &coinbase_tx, ExecutionCost::max_value(), (tip.block_height + 1) as u32).unwrap();
 
                 (anchored_block.0, vec![])
             });
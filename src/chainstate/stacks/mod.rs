@@ -20,6 +20,7 @@
 pub mod address;
 pub mod auth;
 pub mod block;
+pub mod boot;
 pub mod db;
 pub mod events;
 pub mod index;
@@ -771,6 +772,7 @@ pub struct StacksBlockBuilder {
     pub total_anchored_fees: u64,
     pub total_confirmed_streamed_fees: u64,
     pub total_streamed_fees: u64,
+    burn_block_height: u32,
     anchored_done: bool,
     bytes_so_far: u64,
     prev_microblock_header: StacksMicroblockHeader,
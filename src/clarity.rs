@@ -5,13 +5,18 @@ use std::fs;
 use std::env;
 use std::process;
 use std::convert::TryInto;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::iter;
 
 use util::log;
 
 use chainstate::burn::BlockHeaderHash;
 use chainstate::stacks::index::{MarfTrieId, storage::{TrieFileStorage}};
 use chainstate::stacks::StacksBlockId;
+use chainstate::stacks::{TransactionPostCondition, PostConditionPrincipal, AssetInfo,
+                         FungibleConditionCode, NonfungibleConditionCode};
+use chainstate::stacks::db::StacksChainState;
 
 use rusqlite::{Connection, OpenFlags, NO_PARAMS};
 use rusqlite::types::ToSql;
@@ -19,21 +24,32 @@ use rusqlite::Row;
 use rusqlite::Transaction;
 
 use util::db::FromColumn;
+use util::hash::to_hex;
 
 use vm::ast::{build_ast};
+use vm::ast::formatter::format_source;
 use vm::contexts::OwnedEnvironment;
-use vm::database::{ClarityDatabase, SqliteConnection,
+use vm::database::{ClarityDatabase, SqliteConnection, ClarityBackingStore,
                    MarfedKV, MemoryBackingStore, NULL_HEADER_DB};
 use vm::errors::{InterpreterResult, RuntimeErrorType, Error};
 use vm::{SymbolicExpression, SymbolicExpressionType, Value, execute as vm_execute};
 use vm::analysis;
 use vm::analysis::{errors::CheckResult, AnalysisDatabase, ContractAnalysis};
 use vm::analysis::contract_interface_builder::build_contract_interface;
-use vm::types::{QualifiedContractIdentifier, PrincipalData};
-use vm::costs::LimitedCostTracker;
+use vm::analysis::linter::Linter;
+use vm::types::{QualifiedContractIdentifier, PrincipalData, StandardPrincipalData, FunctionType};
+use vm::costs::{LimitedCostTracker, ExecutionCost};
+use vm::coverage::CoverageReporter;
+use vm::debug::Debugger;
+use vm::fuzz;
+use vm::costs::profile::CostMetric;
+
+use chainstate::stacks::events::StacksTransactionEvent;
 
 use address::c32::c32_address;
 
+use net::StacksMessageCodec;
+
 use serde::Serialize;
 
 #[cfg(test)]
@@ -52,6 +68,8 @@ where command is one of:
 
   initialize         to initialize a local VM state database.
   check              to typecheck a potential contract definition.
+  fmt                to reformat contract source files into the canonical layout.
+  lint               to typecheck a contract and warn about likely mistakes in it.
   launch             to launch a initialize a new contract in the local state database.
   eval               to evaluate (in read-only mode) a program in a given contract context.
   eval_at_chaintip   like `eval`, but does not advance to a new block.
@@ -59,8 +77,18 @@ where command is one of:
                      must be passed eval string via stdin.
   eval_raw           to typecheck and evaluate an expression without a contract or database context.
   repl               to typecheck and evaluate expressions in a stdin/stdout loop.
+                     pass --debug to step through each expression in the debugger.
   execute            to execute a public function of a defined contract.
+                     pass --debug to step through it in the debugger, or --profile to write
+                     out its costs by function as folded stacks for flame graph tools.
   generate_address   to generate a random Stacks public address for testing purposes.
+  test               to run the test functions of a suite of test contracts.
+  project            to deploy a project's contracts into its local state database,
+                     and to save and roll back to snapshots of that state.
+  post_conditions    to dry-run a public function call, and propose the post-conditions it needs
+                     to succeed in Deny mode.
+  fuzz               to call a contract's public functions with random arguments, checking
+                     that its invariants hold after each call.
 ", invoked_by);
     panic_test!()
 }
@@ -145,46 +173,56 @@ fn get_cli_chain_tip(conn: &Connection) -> StacksBlockId {
     }
 }
 
-fn advance_cli_chain_tip(path: &String) -> (StacksBlockId, StacksBlockId) {
-    let mut conn = create_or_open_db(path);
-    let tx = friendly_expect(conn.transaction(), &format!("FATAL: failed to begin transaction on '{}'", path));
-
-    friendly_expect(tx.execute("CREATE TABLE IF NOT EXISTS cli_chain_tips(id INTEGER PRIMARY KEY AUTOINCREMENT, block_hash TEXT UNIQUE NOT NULL);", NO_PARAMS),
-                    &format!("FATAL: failed to create 'cli_chain_tips' table"));
-
-    let parent_block_hash = get_cli_chain_tip(&tx);
-
-    let random_bytes = rand::thread_rng().gen::<[u8; 32]>();
-    let next_block_hash  = friendly_expect_opt(StacksBlockId::from_bytes(&random_bytes),
-                                              "Failed to generate random block header.");
-
-    friendly_expect(tx.execute("INSERT INTO cli_chain_tips (block_hash) VALUES (?1)", &[&next_block_hash]), 
-                    &format!("FATAL: failed to store next block hash in '{}'", path));
-
-    friendly_expect(tx.commit(), &format!("FATAL: failed to commit new chain tip to '{}'", path));
-
-    (parent_block_hash, next_block_hash)
+/// Open the CLI's data, stored alongside the MARF database state
+fn open_cli_db(db_path: &str) -> Connection {
+    friendly_expect(fs::create_dir_all(db_path), &format!("FATAL: failed to create '{}'", db_path));
+    let mut cli_db_path = PathBuf::from(db_path);
+    cli_db_path.push("cli.sqlite");
+    let conn = create_or_open_db(&cli_db_path.to_string_lossy().to_string());
+    friendly_expect(conn.execute("CREATE TABLE IF NOT EXISTS cli_chain_tips(id INTEGER PRIMARY KEY AUTOINCREMENT, block_hash TEXT UNIQUE NOT NULL);", NO_PARAMS),
+                    "FATAL: failed to create 'cli_chain_tips' table");
+    friendly_expect(conn.execute("CREATE TABLE IF NOT EXISTS cli_snapshots(name TEXT PRIMARY KEY, block_hash TEXT NOT NULL);", NO_PARAMS),
+                    "FATAL: failed to create 'cli_snapshots' table");
+    conn
 }
 
 // This function is pretty weird! But it helps cut down on
 //   repeating a lot of block initialization for the simulation commands.
-fn in_block<F,R>(db_path: &str, mut marf_kv: MarfedKV, f: F) -> R
+fn in_block<F,R>(db_path: &str, marf_kv: MarfedKV, f: F) -> R
 where F: FnOnce(MarfedKV) -> (MarfedKV, R) {
+    // need to load the last block 
+    let parent = get_cli_chain_tip(&open_cli_db(db_path));
+    let result = in_block_from(db_path, marf_kv, &parent, |kv| {
+        let (kv, result) = f(kv);
+        (kv, Ok(result))
+    });
+    friendly_expect(result, "FATAL: failed to mine block")
+}
 
-    // store CLI data alongside the MARF database state
-    let mut cli_db_path_buf = PathBuf::from(db_path);
-    cli_db_path_buf.push("cli.sqlite");
-    let cli_db_path = cli_db_path_buf
-        .to_str()
-        .expect(&format!("FATAL: failed to convert '{}' to a string", db_path))
-        .to_string();
+// like in_block, but the new block's parent is `parent` rather than the chain tip, which forks
+//   the chain if `parent` is not the tip.  The block is only committed (and becomes the chain
+//   tip) if `f` succeeds.
+fn in_block_from<F,R>(db_path: &str, mut marf_kv: MarfedKV, parent: &StacksBlockId, f: F) -> Result<R, String>
+where F: FnOnce(MarfedKV) -> (MarfedKV, Result<R, String>) {
+    let random_bytes = rand::thread_rng().gen::<[u8; 32]>();
+    let next_block_hash = friendly_expect_opt(StacksBlockId::from_bytes(&random_bytes),
+                                              "Failed to generate random block header.");
 
-    // need to load the last block 
-    let (from, to) = advance_cli_chain_tip(&cli_db_path);
-    marf_kv.begin(&from, &to);
+    marf_kv.begin(parent, &next_block_hash);
     let (mut marf_return, result) = f(marf_kv);
-    marf_return.commit_to(&to);
-    result
+    match result {
+        Ok(result) => {
+            marf_return.commit_to(&next_block_hash);
+            let conn = open_cli_db(db_path);
+            friendly_expect(conn.execute("INSERT INTO cli_chain_tips (block_hash) VALUES (?1)", &[&next_block_hash]),
+                            &format!("FATAL: failed to store next block hash in '{}'", db_path));
+            Ok(result)
+        },
+        Err(e) => {
+            marf_return.rollback();
+            Err(e)
+        }
+    }
 }
 
 // like in_block, but does _not_ advance the chain tip.  Used for read-only queries against the
@@ -225,6 +263,18 @@ where F: FnOnce(MarfedKV) -> (MarfedKV, R) {
 }
 
 
+/// Make a debugger that reads its commands from stdin, one per line
+fn stdin_debugger() -> Debugger {
+    let commands = iter::from_fn(|| {
+        let mut buffer = String::new();
+        match io::stdin().read_line(&mut buffer) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(buffer)
+        }
+    });
+    Debugger::new(Box::new(commands), Box::new(io::stdout()))
+}
+
 fn get_eval_input(invoked_by: &str, args: &[String]) -> EvalInput {
     if args.len() < 3 || args.len() > 4 {
         eprintln!("Usage: {} {} [contract-identifier] (program.clar) [vm-state.db]", invoked_by, args[0]);
@@ -269,6 +319,502 @@ struct InitialAllocation {
     amount: u64
 }
 
+// test contracts are named `*_test.clar`, and their test functions are public functions named `test-*`
+const TEST_CONTRACT_SUFFIX: &str = "_test.clar";
+const TEST_FUNCTION_PREFIX: &str = "test-";
+// if a test contract defines this public function, it is called before each of its tests
+const TEST_SETUP_FUNCTION: &str = "setup";
+// deploys the contracts of test suites and projects which do not name a deployer
+const DEFAULT_DEPLOYER: &str = "S1G2081040G2081040G2081040G208105NK8PE5";
+
+/// Optional settings for the `test` command, read from a JSON file
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct TestConfig {
+    /// address that deploys every contract
+    deployer: Option<String>,
+    /// principal that calls the test functions, if not the deployer
+    sender: Option<String>,
+    /// STX balances to credit before each test
+    balances: Vec<InitialAllocation>,
+    /// contracts under test, in deployment order, relative to the tests directory.
+    /// If empty, every non-test contract in the tests directory is deployed, in file name order.
+    contracts: Vec<String>,
+    /// principals that call particular tests, keyed by `test-contract.test-function`
+    senders: HashMap<String, String>,
+}
+
+struct TestSuite {
+    deployer: StandardPrincipalData,
+    default_sender: PrincipalData,
+    balances: Vec<(PrincipalData, u64)>,
+    /// (contract name, source file, source code) of each contract under test, in deployment order
+    contracts: Vec<(String, String, String)>,
+    /// (contract name, source file, source code) of each test contract
+    test_contracts: Vec<(String, String, String)>,
+    senders: HashMap<String, PrincipalData>,
+}
+
+#[derive(Serialize)]
+struct TestResult {
+    contract: String,
+    name: String,
+    passed: bool,
+    result: String,
+    events: Vec<String>,
+    cost: ExecutionCost,
+}
+
+enum TestBackingStore {
+    Memory,
+    Marf(MarfedKV, StacksBlockId),
+}
+
+impl TestBackingStore {
+    /// Run `f` against a fresh copy of the backing store, and throw away whatever it writes.
+    fn with_fresh_store<F, R>(&mut self, f: F) -> R
+    where F: FnOnce(&mut dyn ClarityBackingStore) -> R {
+        match *self {
+            TestBackingStore::Memory => {
+                let mut store = MemoryBackingStore::new();
+                f(&mut store)
+            },
+            TestBackingStore::Marf(ref mut marf_kv, ref tip) => {
+                marf_kv.begin(tip, &StacksBlockId([2u8; 32]));
+                let result = f(marf_kv);
+                marf_kv.rollback();
+                result
+            }
+        }
+    }
+}
+
+fn read_contract(path: &Path) -> (String, String, String) {
+    let name = friendly_expect_opt(path.file_stem().and_then(|stem| stem.to_str()),
+                                   &format!("Invalid contract file name: {}", path.display()));
+    let content = friendly_expect(fs::read_to_string(path), &format!("Error reading file: {}", path.display()));
+    (name.to_string(), path.to_string_lossy().to_string(), content)
+}
+
+fn load_test_suite(tests_path: &str, config: TestConfig) -> TestSuite {
+    let tests_path = Path::new(tests_path);
+    let (tests_dir, mut test_contracts, mut contracts) =
+        if tests_path.is_dir() {
+            let mut paths: Vec<_> = friendly_expect(fs::read_dir(tests_path), &format!("Error reading directory: {}", tests_path.display()))
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().map(|ext| ext == "clar").unwrap_or(false))
+                .collect();
+            paths.sort();
+
+            let (test_paths, contract_paths): (Vec<_>, Vec<_>) = paths.into_iter()
+                .partition(|path| path.to_string_lossy().ends_with(TEST_CONTRACT_SUFFIX));
+
+            (tests_path.to_path_buf(),
+             test_paths.iter().map(|path| read_contract(path)).collect(),
+             contract_paths.iter().map(|path| read_contract(path)).collect())
+        } else {
+            let tests_dir = tests_path.parent().map(|dir| dir.to_path_buf()).unwrap_or(PathBuf::new());
+            (tests_dir, vec![read_contract(tests_path)], vec![])
+        };
+
+    if config.contracts.len() > 0 {
+        contracts = config.contracts.iter()
+            .map(|contract_path| read_contract(&tests_dir.join(contract_path)))
+            .collect();
+    }
+
+    if test_contracts.len() == 0 {
+        eprintln!("No test contracts (*{}) found in {}", TEST_CONTRACT_SUFFIX, tests_path.display());
+        panic_test!();
+    }
+    test_contracts.sort();
+
+    let deployer = friendly_expect(PrincipalData::parse_standard_principal(config.deployer.as_ref().map(|s| s.as_str()).unwrap_or(DEFAULT_DEPLOYER)),
+                                   "Failed to parse deployer address.");
+    let default_sender = match config.sender {
+        Some(ref sender) => friendly_expect(PrincipalData::parse(sender), "Failed to parse sender principal."),
+        None => PrincipalData::Standard(deployer.clone())
+    };
+    let balances = config.balances.iter().map(|a| {
+        (friendly_expect(PrincipalData::parse(&a.principal), "Failed to parse principal in balances"), a.amount)
+    }).collect();
+    let senders = config.senders.iter().map(|(test_name, sender)| {
+        (test_name.clone(), friendly_expect(PrincipalData::parse(sender), &format!("Failed to parse sender for {}", test_name)))
+    }).collect();
+
+    TestSuite { deployer, default_sender, balances, contracts, test_contracts, senders }
+}
+
+/// Typecheck and instantiate a contract in the given store.  Returns the contract's analysis.
+fn deploy_contract(store: &mut dyn ClarityBackingStore, contract_identifier: &QualifiedContractIdentifier, content: &str,
+                   coverage: &mut Option<CoverageReporter>) -> Result<ContractAnalysis, String> {
+    let mut ast = parse(contract_identifier, content).map_err(|e| e.to_string())?;
+    let analysis = {
+        let mut analysis_db = AnalysisDatabase::new(store);
+        run_analysis(contract_identifier, &mut ast, &mut analysis_db, true).map_err(|e| e.diagnostic.message)?
+    };
+
+    let db = ClarityDatabase::new(store, &NULL_HEADER_DB);
+    let mut vm_env = OwnedEnvironment::new_cost_limited(db, LimitedCostTracker::new_max_limit());
+    if let Some(reporter) = coverage.take() {
+        vm_env.set_coverage_reporter(reporter);
+    }
+    let result = vm_env.initialize_contract(contract_identifier.clone(), content);
+    *coverage = vm_env.take_coverage_reporter();
+
+    result.map_err(|e| e.to_string())?;
+    Ok(analysis)
+}
+
+fn credit_balances(store: &mut dyn ClarityBackingStore, balances: &[(PrincipalData, u64)]) -> Result<(), String> {
+    let mut db = ClarityDatabase::new(store, &NULL_HEADER_DB);
+    db.begin();
+    for (principal, amount) in balances.iter() {
+        let cur_balance = match db.get_account_stx_balance(principal) {
+            Ok(balance) => balance,
+            Err(e) => {
+                db.roll_back();
+                return Err(e.to_string());
+            }
+        };
+        let final_balance = match cur_balance.checked_add(*amount as u128) {
+            Some(balance) => balance,
+            None => {
+                db.roll_back();
+                return Err("Account balance overflow".to_string());
+            }
+        };
+        if let Err(e) = db.set_account_stx_balance(principal, final_balance) {
+            db.roll_back();
+            return Err(e.to_string());
+        }
+    }
+    db.commit();
+    Ok(())
+}
+
+/// Credit the suite's balances, and deploy the contracts under test, followed by the test contracts.
+/// Returns the analysis of each test contract.
+fn deploy_test_suite(store: &mut dyn ClarityBackingStore, suite: &TestSuite, coverage: &mut Option<CoverageReporter>) -> Result<Vec<ContractAnalysis>, String> {
+    credit_balances(store, &suite.balances)?;
+
+    for (name, _, content) in suite.contracts.iter() {
+        let contract_identifier = QualifiedContractIdentifier::new(suite.deployer.clone(), name.as_str().try_into()
+                                                                   .map_err(|_| format!("Invalid contract name: {}", name))?);
+        deploy_contract(store, &contract_identifier, content, coverage)
+            .map_err(|e| format!("Failed to deploy {}: {}", contract_identifier, e))?;
+    }
+
+    let mut analyses = vec![];
+    for (name, _, content) in suite.test_contracts.iter() {
+        let contract_identifier = QualifiedContractIdentifier::new(suite.deployer.clone(), name.as_str().try_into()
+                                                                   .map_err(|_| format!("Invalid contract name: {}", name))?);
+        let analysis = deploy_contract(store, &contract_identifier, content, coverage)
+            .map_err(|e| format!("Failed to deploy {}: {}", contract_identifier, e))?;
+        analyses.push(analysis);
+    }
+    Ok(analyses)
+}
+
+/// Call a public function with no arguments.  Returns whether it returned an `ok` response,
+/// what it returned (or why it failed), the values it printed, and what it cost.
+fn call_test_function(store: &mut dyn ClarityBackingStore, sender: &PrincipalData, contract_identifier: &QualifiedContractIdentifier,
+                      function: &str, coverage: &mut Option<CoverageReporter>) -> (bool, String, Vec<String>, ExecutionCost) {
+    let db = ClarityDatabase::new(store, &NULL_HEADER_DB);
+    let mut vm_env = OwnedEnvironment::new_cost_limited(db, LimitedCostTracker::new_max_limit());
+    if let Some(reporter) = coverage.take() {
+        vm_env.set_coverage_reporter(reporter);
+    }
+    let result = vm_env.execute_transaction(Value::Principal(sender.clone()), contract_identifier.clone(), function, &[]);
+    *coverage = vm_env.take_coverage_reporter();
+    let cost = match vm_env.destruct() {
+        Some((_, cost_tracker)) => cost_tracker.get_total(),
+        None => ExecutionCost::zero()
+    };
+
+    match result {
+        Ok((value, _, events)) => {
+            let printed = events.iter().filter_map(|event| match event {
+                StacksTransactionEvent::SmartContractEvent(data) if data.key.1 == "print" => Some(data.value.to_string()),
+                _ => None
+            }).collect();
+            let passed = match value {
+                Value::Response(ref data) => data.committed,
+                _ => false
+            };
+            (passed, value.to_string(), printed, cost)
+        },
+        Err(e) => (false, e.to_string(), vec![], cost)
+    }
+}
+
+/// Run every test in the suite.  If given a coverage reporter, record what each test evaluates
+/// (including deploying the contracts it runs against).
+fn run_test_suite(store: &mut TestBackingStore, suite: &TestSuite, coverage: &mut Option<CoverageReporter>) -> Result<Vec<TestResult>, String> {
+    let analyses = store.with_fresh_store(|store| deploy_test_suite(store, suite, &mut None))?;
+
+    let mut results = vec![];
+    for analysis in analyses.iter() {
+        let contract_identifier = &analysis.contract_identifier;
+        let contract_name = contract_identifier.name.to_string();
+        let has_setup = analysis.public_function_types.contains_key(TEST_SETUP_FUNCTION);
+
+        for (function_name, function_type) in analysis.public_function_types.iter() {
+            if !function_name.starts_with(TEST_FUNCTION_PREFIX) {
+                continue;
+            }
+
+            let test_name = format!("{}.{}", contract_name, function_name.as_str());
+            let sender = suite.senders.get(&test_name).unwrap_or(&suite.default_sender);
+            let takes_args = match function_type {
+                FunctionType::Fixed(function) => function.args.len() > 0,
+                _ => true
+            };
+
+            let result = if takes_args {
+                TestResult {
+                    contract: contract_name.clone(), name: function_name.to_string(), passed: false,
+                    result: "Test functions must not take arguments".to_string(),
+                    events: vec![], cost: ExecutionCost::zero()
+                }
+            } else {
+                store.with_fresh_store(|store| {
+                    deploy_test_suite(store, suite, coverage).expect("FATAL: test suite deployed once, but not twice");
+                    if has_setup {
+                        let (passed, result, events, cost) = call_test_function(store, sender, contract_identifier, TEST_SETUP_FUNCTION, coverage);
+                        if !passed {
+                            return TestResult {
+                                contract: contract_name.clone(), name: function_name.to_string(), passed,
+                                result: format!("{} failed: {}", TEST_SETUP_FUNCTION, result), events, cost
+                            };
+                        }
+                    }
+                    let (passed, result, events, cost) = call_test_function(store, sender, contract_identifier, function_name, coverage);
+                    TestResult { contract: contract_name.clone(), name: function_name.to_string(), passed, result, events, cost }
+                })
+            };
+            results.push(result);
+        }
+    }
+    Ok(results)
+}
+
+/// Write an LCOV record for every contract in the suite
+fn format_test_coverage_lcov(suite: &TestSuite, coverage: &CoverageReporter) -> String {
+    let mut out = String::new();
+    for (name, path, content) in suite.contracts.iter().chain(suite.test_contracts.iter()) {
+        let contract_identifier = QualifiedContractIdentifier::new(suite.deployer.clone(), friendly_expect(name.as_str().try_into(), "Invalid contract name"));
+        let ast = friendly_expect(parse(&contract_identifier, content), &format!("Failed to parse {}", path));
+        out.push_str(&coverage.to_lcov(&contract_identifier, path, &ast));
+    }
+    out
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;").replace("\"", "&quot;")
+}
+
+fn format_test_results_junit(results: &[TestResult]) -> String {
+    let num_failed = results.iter().filter(|r| !r.passed).count();
+    let mut out = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites tests=\"{}\" failures=\"{}\">\n", results.len(), num_failed);
+
+    let mut contracts: Vec<&str> = results.iter().map(|r| r.contract.as_str()).collect();
+    contracts.dedup();
+    for contract in contracts.into_iter() {
+        let suite_results: Vec<_> = results.iter().filter(|r| r.contract == contract).collect();
+        out.push_str(&format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+                              escape_xml(contract), suite_results.len(), suite_results.iter().filter(|r| !r.passed).count()));
+        for result in suite_results.into_iter() {
+            out.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\">\n", escape_xml(contract), escape_xml(&result.name)));
+            if !result.passed {
+                out.push_str(&format!("      <failure message=\"{}\"/>\n", escape_xml(&result.result)));
+            }
+            let mut system_out = format!("cost: {}", result.cost);
+            for event in result.events.iter() {
+                system_out.push_str(&format!("\nprint: {}", event));
+            }
+            out.push_str(&format!("      <system-out>{}</system-out>\n", escape_xml(&system_out)));
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>");
+    out
+}
+
+fn format_test_results_human(results: &[TestResult]) -> String {
+    let mut out = String::new();
+    for result in results.iter() {
+        if result.passed {
+            out.push_str(&format!("{}.{} ... ok\n", result.contract, result.name));
+        } else {
+            out.push_str(&format!("{}.{} ... FAILED: {}\n", result.contract, result.name, result.result));
+        }
+        for event in result.events.iter() {
+            out.push_str(&format!("    print: {}\n", event));
+        }
+        out.push_str(&format!("    cost: {}\n", result.cost));
+    }
+    let num_failed = results.iter().filter(|r| !r.passed).count();
+    out.push_str(&format!("\ntest result: {}. {} passed; {} failed",
+                          if num_failed == 0 { "ok" } else { "FAILED" }, results.len() - num_failed, num_failed));
+    out
+}
+
+/// A set of contracts to deploy together into a local VM state database, read from a JSON file
+#[derive(Serialize, Deserialize)]
+struct ProjectManifest {
+    /// VM state database directory, relative to the manifest
+    #[serde(default = "default_project_database")]
+    database: String,
+    /// address that deploys the contracts which do not name their own deployer
+    #[serde(default)]
+    deployer: Option<String>,
+    /// STX balances to credit when the project is deployed
+    #[serde(default)]
+    balances: Vec<InitialAllocation>,
+    contracts: Vec<ProjectContract>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProjectContract {
+    name: String,
+    /// contract source file, relative to the manifest
+    path: String,
+    #[serde(default)]
+    deployer: Option<String>,
+    /// names of the project's contracts which must be deployed before this one
+    #[serde(default)]
+    depends_on: Vec<String>,
+}
+
+fn default_project_database() -> String {
+    "vm-state.db".to_string()
+}
+
+struct Project {
+    database: String,
+    balances: Vec<(PrincipalData, u64)>,
+    /// (contract identifier, source file, source code) of each contract, in deployment order
+    contracts: Vec<(QualifiedContractIdentifier, String, String)>,
+}
+
+/// Order the contracts so that each one comes after the contracts it depends on, and otherwise
+///   keeps its place in the manifest.  Returns the indexes of the contracts in that order.
+fn project_deployment_order(contracts: &[ProjectContract]) -> Result<Vec<usize>, String> {
+    fn visit(index: usize, contracts: &[ProjectContract], indexes: &HashMap<&str, usize>,
+             visiting: &mut Vec<usize>, order: &mut Vec<usize>) -> Result<(), String> {
+        if order.contains(&index) {
+            return Ok(());
+        }
+        if let Some(position) = visiting.iter().position(|i| *i == index) {
+            let cycle: Vec<_> = visiting[position..].iter().chain(iter::once(&index))
+                .map(|i| contracts[*i].name.as_str())
+                .collect();
+            return Err(format!("Circular dependency between contracts: {}", cycle.join(" -> ")));
+        }
+
+        visiting.push(index);
+        for dependency in contracts[index].depends_on.iter() {
+            let dependency_index = *indexes.get(dependency.as_str())
+                .ok_or_else(|| format!("{} depends on {}, which is not in the project", contracts[index].name, dependency))?;
+            visit(dependency_index, contracts, indexes, visiting, order)?;
+        }
+        visiting.pop();
+
+        order.push(index);
+        Ok(())
+    }
+
+    let mut indexes = HashMap::new();
+    for (index, contract) in contracts.iter().enumerate() {
+        if indexes.insert(contract.name.as_str(), index).is_some() {
+            return Err(format!("{} is in the project more than once", contract.name));
+        }
+    }
+
+    let mut order = vec![];
+    for index in 0..contracts.len() {
+        visit(index, contracts, &indexes, &mut vec![], &mut order)?;
+    }
+    Ok(order)
+}
+
+fn load_project(manifest_path: &str) -> Project {
+    let json_in = friendly_expect(fs::read_to_string(manifest_path), &format!("Error reading file: {}", manifest_path));
+    let manifest: ProjectManifest = friendly_expect(serde_json::from_str(&json_in), "Failure parsing JSON");
+    let project_dir = Path::new(manifest_path).parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+
+    let order = friendly_expect(project_deployment_order(&manifest.contracts), "Failed to order the project's contracts.");
+    let default_deployer = manifest.deployer.as_deref().unwrap_or(DEFAULT_DEPLOYER);
+    let contracts = order.into_iter().map(|index| {
+        let contract = &manifest.contracts[index];
+        let deployer = friendly_expect(PrincipalData::parse_standard_principal(contract.deployer.as_deref().unwrap_or(default_deployer)),
+                                       &format!("Failed to parse deployer address of {}.", contract.name));
+        let name = friendly_expect_opt(contract.name.as_str().try_into().ok(), &format!("Invalid contract name: {}", contract.name));
+        let (_, path, content) = read_contract(&project_dir.join(&contract.path));
+        (QualifiedContractIdentifier::new(deployer, name), path, content)
+    }).collect();
+
+    let balances = manifest.balances.iter().map(|a| {
+        (friendly_expect(PrincipalData::parse(&a.principal), "Failed to parse principal in balances"), a.amount)
+    }).collect();
+
+    Project {
+        database: project_dir.join(&manifest.database).to_string_lossy().to_string(),
+        balances, contracts
+    }
+}
+
+/// Credit the project's balances, and deploy its contracts in order.
+fn deploy_project(store: &mut dyn ClarityBackingStore, project: &Project) -> Result<(), String> {
+    credit_balances(store, &project.balances)?;
+    for (contract_identifier, _, content) in project.contracts.iter() {
+        deploy_contract(store, contract_identifier, content, &mut None)
+            .map_err(|e| format!("Failed to deploy {}: {}", contract_identifier, e))?;
+    }
+    Ok(())
+}
+
+fn get_cli_snapshot(conn: &Connection, name: &str) -> Option<StacksBlockId> {
+    let mut stmt = friendly_expect(conn.prepare("SELECT block_hash FROM cli_snapshots WHERE name = ?1"), "FATAL: could not prepare query");
+    let mut rows = friendly_expect(stmt.query(&[&name]), "FATAL: could not fetch rows");
+    match rows.next() {
+        Some(Ok(row)) => Some(friendly_expect(StacksBlockId::from_column(&row, "block_hash"), "FATAL: could not parse block hash")),
+        Some(Err(e)) => panic!("FATAL: could not read block hash: {:?}", e),
+        None => None
+    }
+}
+
+/// A post-condition, in words
+fn describe_post_condition(post_condition: &TransactionPostCondition) -> String {
+    let describe_principal = |principal: &PostConditionPrincipal| match principal {
+        PostConditionPrincipal::Origin => "origin".to_string(),
+        PostConditionPrincipal::Standard(address) => format!("'{}", address),
+        PostConditionPrincipal::Contract(address, contract_name) => format!("'{}.{}", address, contract_name.as_str())
+    };
+    let describe_asset = |asset_info: &AssetInfo| {
+        format!("'{}.{}::{}", asset_info.contract_address, asset_info.contract_name.as_str(), asset_info.asset_name.as_str())
+    };
+    let describe_code = |condition_code: &FungibleConditionCode| match condition_code {
+        FungibleConditionCode::SentEq => "exactly",
+        FungibleConditionCode::SentGt => "more than",
+        FungibleConditionCode::SentGe => "at least",
+        FungibleConditionCode::SentLt => "less than",
+        FungibleConditionCode::SentLe => "at most"
+    };
+    match post_condition {
+        TransactionPostCondition::STX(principal, condition_code, amount) =>
+            format!("{} sends {} {} microSTX", describe_principal(principal), describe_code(condition_code), amount),
+        TransactionPostCondition::Fungible(principal, asset_info, condition_code, amount) =>
+            format!("{} sends {} {} {}", describe_principal(principal), describe_code(condition_code), amount, describe_asset(asset_info)),
+        TransactionPostCondition::Nonfungible(principal, asset_info, value, NonfungibleConditionCode::Sent) =>
+            format!("{} sends {} {}", describe_principal(principal), describe_asset(asset_info), value),
+        TransactionPostCondition::Nonfungible(principal, asset_info, value, NonfungibleConditionCode::NotSent) =>
+            format!("{} does not send {} {}", describe_principal(principal), describe_asset(asset_info), value),
+    }
+}
+
 pub fn invoke_command(invoked_by: &str, args: &[String]) {
     if args.len() < 1 {
         print_usage(invoked_by)
@@ -311,9 +857,9 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
                     db.initialize();
                     db.begin();
                     for (principal, amount) in allocations.iter() {
-                        let cur_balance = db.get_account_stx_balance(principal);
+                        let cur_balance = db.get_account_stx_balance(principal).expect("FATAL: failed to read account balance");
                         let final_balance = cur_balance.checked_add(*amount as u128).expect("FATAL: account balance overflow");
-                        db.set_account_stx_balance(principal, final_balance as u128);
+                        db.set_account_stx_balance(principal, final_balance as u128).expect("FATAL: failed to write account balance");
                         println!("{} credited: {} uSTX", principal, final_balance);
                     }
                     db.commit();
@@ -340,8 +886,9 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
             let content: String = friendly_expect(fs::read_to_string(&args[1]),
                                                   &format!("Error reading file: {}", args[1]));
 
-            let mut ast = friendly_expect(parse(&contract_id, &content), "Failed to parse program");
+            let ast = friendly_expect(parse(&contract_id, &content), "Failed to parse program");
 
+            // report every error, rather than stopping at the first like a deployment would
             let contract_analysis = {
                 if args.len() >= 3 {
                     // use a persisted marf
@@ -351,17 +898,19 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
                         marf_kv,
                         |mut marf| {
                             let result = { let mut db = AnalysisDatabase::new(&mut marf);
-                                           run_analysis(&contract_id, &mut ast, &mut db, false) };
+                                           analysis::run_analysis_all_errors(&contract_id, &ast, &mut db, LimitedCostTracker::new_max_limit()) };
                             (marf, result)
                         });
                     result
                 } else {
                     let mut analysis_marf = MemoryBackingStore::new();
                     let mut db = analysis_marf.as_analysis_db();
-                    run_analysis(&contract_id, &mut ast, &mut db, false)
+                    analysis::run_analysis_all_errors(&contract_id, &ast, &mut db, LimitedCostTracker::new_max_limit())
+                }
+            }.unwrap_or_else(|diagnostics| {
+                for diagnostic in diagnostics.iter() {
+                    println!("{}", diagnostic);
                 }
-            }.unwrap_or_else(|e| {
-                println!("{}", &e.diagnostic);
                 panic_test!();
             });
 
@@ -374,9 +923,101 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
                 }
             }
         },
+        "fmt" => {
+            let check = args.iter().any(|arg| arg == "--check");
+            let paths: Vec<_> = args[1..].iter().filter(|arg| *arg != "--check").collect();
+            if paths.len() < 1 {
+                eprintln!("Usage: {} {} [program-file.clar ...] (--check)", invoked_by, args[0]);
+                eprintln!("   files are reformatted in place; with --check, files that would change are listed instead.");
+                eprintln!("   if the provided filename is `-`, the program is read from stdin and written to stdout.");
+                panic_test!();
+            }
+
+            let mut unformatted = vec![];
+            for path in paths.into_iter() {
+                let content = if path == "-" {
+                    let mut buffer = String::new();
+                    friendly_expect(io::stdin().read_to_string(&mut buffer), "Error reading from stdin.");
+                    buffer
+                } else {
+                    friendly_expect(fs::read_to_string(path), &format!("Error reading file: {}", path))
+                };
+
+                let formatted = format_source(&content).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path, &e.diagnostic);
+                    panic_test!();
+                });
+
+                if check {
+                    if formatted != content {
+                        unformatted.push(path);
+                    }
+                } else if path == "-" {
+                    print!("{}", formatted);
+                } else if formatted != content {
+                    friendly_expect(fs::write(path, &formatted), &format!("Failed to write file: {}", path));
+                }
+            }
+
+            if unformatted.len() > 0 {
+                for path in unformatted.iter() {
+                    println!("{}", path);
+                }
+                panic_test!();
+            }
+        },
+        "lint" => {
+            if args.len() < 2 {
+                eprintln!("Usage: {} {} [program-file.clar] (vm-state.db)", invoked_by, args[0]);
+                panic_test!();
+            }
+
+            let contract_id = QualifiedContractIdentifier::transient();
+
+            let content: String = friendly_expect(fs::read_to_string(&args[1]),
+                                                  &format!("Error reading file: {}", args[1]));
+
+            let mut ast = friendly_expect(parse(&contract_id, &content), "Failed to parse program");
+
+            let contract_analysis = {
+                if args.len() >= 3 {
+                    // use a persisted marf
+                    let marf_kv = friendly_expect(MarfedKV::open(&args[2], None), "Failed to open VM database.");
+                    at_chaintip(
+                        &args[2],
+                        marf_kv,
+                        |mut marf| {
+                            let result = { let mut db = AnalysisDatabase::new(&mut marf);
+                                           run_analysis(&contract_id, &mut ast, &mut db, false) };
+                            (marf, result)
+                        })
+                } else {
+                    let mut analysis_marf = MemoryBackingStore::new();
+                    let mut db = analysis_marf.as_analysis_db();
+                    run_analysis(&contract_id, &mut ast, &mut db, false)
+                }
+            }.unwrap_or_else(|e| {
+                println!("{}", &e.diagnostic);
+                panic_test!();
+            });
+
+            let warnings = Linter::run(&contract_analysis);
+            if warnings.len() > 0 {
+                for warning in warnings.iter() {
+                    println!("{}:{}", args[1], warning);
+                }
+                panic_test!();
+            }
+            println!("No warnings.");
+        },
         "repl" => {
+            let debug = args.len() > 1 && args[1] == "--debug";
+
             let mut marf = MemoryBackingStore::new();
             let mut vm_env = OwnedEnvironment::new_cost_limited(marf.as_clarity_db(), LimitedCostTracker::new_max_limit());
+            if debug {
+                vm_env.set_debugger(stdin_debugger());
+            }
             let mut exec_env = vm_env.get_exec_environment(None);
 
             let mut analysis_marf = MemoryBackingStore::new();
@@ -420,6 +1061,10 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
                     } 
                 }
 
+                if let Some(ref mut debugger) = exec_env.global_context.debugger {
+                    debugger.pause();
+                }
+
                 let eval_result = match exec_env.eval_raw(&content) {
                     Ok(val) => val,
                     Err(error) => {
@@ -622,8 +1267,37 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
             }
         },
         "execute" => {
+            let mut coverage_path = None;
+            let mut profile_path = None;
+            let mut profile_metric = CostMetric::Runtime;
+            let mut debug = false;
+            let mut positional = vec![];
+            let mut i = 0;
+            while i < args.len() {
+                match args[i].as_str() {
+                    "--coverage" if i + 1 < args.len() => {
+                        coverage_path = Some(args[i + 1].clone());
+                        i += 1;
+                    },
+                    "--profile" if i + 1 < args.len() => {
+                        profile_path = Some(args[i + 1].clone());
+                        i += 1;
+                    },
+                    "--profile-metric" if i + 1 < args.len() => {
+                        profile_metric = friendly_expect_opt(CostMetric::lookup_by_name(&args[i + 1]),
+                                                             &format!("Unknown cost metric {}.  Expected one of: {}",
+                                                                      args[i + 1], CostMetric::ALL_NAMES.join(", ")));
+                        i += 1;
+                    },
+                    "--debug" => debug = true,
+                    _ => positional.push(args[i].clone())
+                }
+                i += 1;
+            }
+            let args = &positional[..];
+
             if args.len() < 5 {
-                eprintln!("Usage: {} {} [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, args[0]);
+                eprintln!("Usage: {} {} [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...] (--coverage coverage.lcov) (--profile profile.folded) (--profile-metric runtime|read_count|read_length|write_count|write_length) (--debug)", invoked_by, args[0]);
                 panic_test!();
             }
             let vm_filename = &args[1];
@@ -656,14 +1330,53 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
                 })
                 .collect();
 
+            let mut profile = None;
             let result = in_block(vm_filename, marf_kv, |mut marf| {
                 let result = {
                     let db = marf.as_clarity_db(&NULL_HEADER_DB);
                     let mut vm_env = OwnedEnvironment::new_cost_limited(db, LimitedCostTracker::new_max_limit());
-                    vm_env.execute_transaction(Value::Principal(sender), contract_identifier, &tx_name, &arguments) };
+                    if coverage_path.is_some() {
+                        vm_env.set_coverage_reporter(CoverageReporter::new());
+                    }
+                    if profile_path.is_some() {
+                        vm_env.begin_cost_profiling();
+                    }
+                    if debug {
+                        vm_env.set_debugger(stdin_debugger());
+                    }
+                    let result = vm_env.execute_transaction(Value::Principal(sender), contract_identifier.clone(), &tx_name, &arguments);
+                    let coverage = vm_env.take_coverage_reporter();
+                    profile = vm_env.take_cost_profile();
+                    result.map(|result| (result, coverage))
+                };
+                let result = result.map(|((x, asset_map, events), coverage)| {
+                    let source = coverage.map(|coverage| {
+                        let mut db = marf.as_clarity_db(&NULL_HEADER_DB);
+                        db.begin();
+                        let source = db.get_contract_src(&contract_identifier);
+                        db.roll_back();
+                        (coverage, source)
+                    });
+                    ((x, asset_map, events), source)
+                });
                 (marf, result)
             });
 
+            // write out the profile even if the transaction failed, since it may have run out of budget
+            if let (Some(path), Some(profile)) = (profile_path, profile) {
+                friendly_expect(fs::write(&path, profile.to_folded_stacks(&profile_metric)),
+                                &format!("Failed to write cost profile to {}", path));
+            }
+
+            let result = result.map(|(result, source)| {
+                if let (Some(path), Some((coverage, Some(source)))) = (coverage_path, source) {
+                    let ast = friendly_expect(parse(&contract_identifier, &source), "Failed to parse contract source.");
+                    let lcov = coverage.to_lcov(&contract_identifier, &contract_identifier.to_string(), &ast);
+                    friendly_expect(fs::write(&path, lcov), &format!("Failed to write coverage report to {}", path));
+                }
+                result
+            });
+
             match result {
                 Ok((x, _, events)) => {
                     if let Value::Response(data) = x {
@@ -682,6 +1395,289 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) {
                 }
             }
         },
+        "test" => {
+            let mut config_path = None;
+            let mut coverage_path = None;
+            let mut format = "human".to_string();
+            let mut positional = vec![];
+            let mut i = 1;
+            while i < args.len() {
+                match args[i].as_str() {
+                    "--config" if i + 1 < args.len() => {
+                        config_path = Some(args[i + 1].clone());
+                        i += 1;
+                    },
+                    "--format" if i + 1 < args.len() => {
+                        format = args[i + 1].clone();
+                        i += 1;
+                    },
+                    "--coverage" if i + 1 < args.len() => {
+                        coverage_path = Some(args[i + 1].clone());
+                        i += 1;
+                    },
+                    _ => {
+                        positional.push(args[i].clone());
+                    }
+                }
+                i += 1;
+            }
+
+            if positional.len() < 1 || positional.len() > 2 || !["human", "json", "junit"].contains(&format.as_str()) {
+                eprintln!("Usage: {} {} [tests-dir-or-file.clar] (vm-state.db) (--config test-config.json) (--format human|json|junit) (--coverage coverage.lcov)", invoked_by, args[0]);
+                eprintln!("   test contracts are named *{}, and their test functions are public functions named {}*.", TEST_CONTRACT_SUFFIX, TEST_FUNCTION_PREFIX);
+                eprintln!("   each test runs against freshly-deployed contracts, after the test contract's `{}` function if it has one.", TEST_SETUP_FUNCTION);
+                eprintln!("   test-config.json is an object with the optional fields `deployer`, `sender`, `balances`, `contracts` and `senders`.");
+                panic_test!();
+            }
+
+            let config = match config_path {
+                Some(ref path) => {
+                    let json_in = friendly_expect(fs::read_to_string(path), &format!("Error reading file: {}", path));
+                    friendly_expect(serde_json::from_str(&json_in), "Failure parsing JSON")
+                },
+                None => TestConfig::default()
+            };
+            let suite = load_test_suite(&positional[0], config);
+
+            let mut store = match positional.get(1) {
+                Some(vm_filename) => {
+                    let marf_kv = friendly_expect(MarfedKV::open(vm_filename, None), "Failed to open VM database.");
+                    let mut cli_db_path = PathBuf::from(vm_filename);
+                    cli_db_path.push("cli.sqlite");
+                    let tip = get_cli_chain_tip(&create_or_open_db(&cli_db_path.to_string_lossy().to_string()));
+                    TestBackingStore::Marf(marf_kv, tip)
+                },
+                None => TestBackingStore::Memory
+            };
+
+            let mut coverage = coverage_path.as_ref().map(|_| CoverageReporter::new());
+            let results = friendly_expect(run_test_suite(&mut store, &suite, &mut coverage), "Failed to deploy test suite.");
+
+            if let (Some(path), Some(coverage)) = (coverage_path, coverage) {
+                friendly_expect(fs::write(&path, format_test_coverage_lcov(&suite, &coverage)),
+                                &format!("Failed to write coverage report to {}", path));
+            }
+
+            match format.as_str() {
+                "json" => println!("{}", friendly_expect(serde_json::to_string_pretty(&results), "Failed to serialize test results")),
+                "junit" => println!("{}", format_test_results_junit(&results)),
+                _ => println!("{}", format_test_results_human(&results))
+            }
+
+            if results.iter().any(|r| !r.passed) {
+                panic_test!();
+            }
+        },
+        "post_conditions" => {
+            if args.len() < 5 {
+                eprintln!("Usage: {} {} [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, args[0]);
+                eprintln!("   dry-runs the call at the chain tip, and prints the tightest post-conditions that would let it succeed in Deny mode,");
+                eprintln!("   each with its serialized form.");
+                panic_test!();
+            }
+            let vm_filename = &args[1];
+            let marf_kv = friendly_expect(MarfedKV::open(vm_filename, None), "Failed to open VM database.");
+            let contract_identifier = friendly_expect(QualifiedContractIdentifier::parse(&args[2]), "Failed to parse contract identifier.");
+            let tx_name = &args[3];
+            let sender = PrincipalData::Standard(friendly_expect(PrincipalData::parse_standard_principal(&args[4]),
+                                                                 &format!("Unexpected result parsing sender: {}", args[4])));
+            let arguments: Vec<_> = args[5..]
+                .iter()
+                .map(|argument| {
+                    let argument_parsed = friendly_expect(
+                        vm_execute(argument),
+                        &format!("Error parsing argument \"{}\"", argument));
+                    let argument_value = friendly_expect_opt(
+                        argument_parsed,
+                        &format!("Failed to parse a value from the argument: {}", argument));
+                    SymbolicExpression::atom_value(argument_value)
+                })
+                .collect();
+
+            let result = at_chaintip(vm_filename, marf_kv, |mut marf| {
+                let result = {
+                    let db = marf.as_clarity_db(&NULL_HEADER_DB);
+                    let mut vm_env = OwnedEnvironment::new_cost_limited(db, LimitedCostTracker::new_max_limit());
+                    vm_env.execute_transaction(Value::Principal(sender.clone()), contract_identifier, tx_name, &arguments)
+                };
+                (marf, result)
+            });
+
+            let (value, asset_map, _) = friendly_expect(result, "Transaction execution error.");
+            println!("Transaction returned {}.", value);
+            let post_conditions = StacksChainState::post_conditions_from_asset_map(&sender, &asset_map);
+            if post_conditions.is_empty() {
+                println!("No assets were sent, so no post-conditions are needed.");
+            }
+            for post_condition in post_conditions.iter() {
+                println!("{}  0x{}", describe_post_condition(post_condition), to_hex(&post_condition.serialize_to_vec()));
+            }
+        },
+        "fuzz" => {
+            let mut runs = None;
+            let mut calls_per_run = None;
+            let mut seed = None;
+            let mut positional = vec![];
+            let mut i = 1;
+            while i < args.len() {
+                match args[i].as_str() {
+                    "--runs" if i + 1 < args.len() => {
+                        runs = Some(friendly_expect(args[i + 1].parse::<u32>(), "Failed to parse --runs"));
+                        i += 1;
+                    },
+                    "--calls" if i + 1 < args.len() => {
+                        calls_per_run = Some(friendly_expect(args[i + 1].parse::<usize>(), "Failed to parse --calls"));
+                        i += 1;
+                    },
+                    "--seed" if i + 1 < args.len() => {
+                        seed = Some(friendly_expect(args[i + 1].parse::<u64>(), "Failed to parse --seed"));
+                        i += 1;
+                    },
+                    _ => {
+                        positional.push(args[i].clone());
+                    }
+                }
+                i += 1;
+            }
+
+            let is_project = positional.first().map(|path| path.ends_with(".json")).unwrap_or(false);
+            if positional.len() != (if is_project { 2 } else { 1 }) {
+                eprintln!("Usage: {} {} [contract-definition.clar] (--runs 100) (--calls 20) (--seed seed)", invoked_by, args[0]);
+                eprintln!("       {} {} [project.json] [contract-name] (--runs 100) (--calls 20) (--seed seed)", invoked_by, args[0]);
+                eprintln!("   makes random sequences of calls to the contract's public functions, and checks its invariants after");
+                eprintln!("   each call.  invariants are read-only functions named {}*, which take no arguments and return true.", fuzz::INVARIANT_PREFIX);
+                eprintln!("   a project's contracts are all deployed, in dependency order, before each sequence of calls.");
+                panic_test!();
+            }
+
+            let project = if is_project {
+                load_project(&positional[0])
+            } else {
+                let (name, path, content) = read_contract(Path::new(&positional[0]));
+                let deployer = friendly_expect(PrincipalData::parse_standard_principal(DEFAULT_DEPLOYER), "Failed to parse deployer address.");
+                let name = friendly_expect_opt(name.clone().try_into().ok(), &format!("Invalid contract name: {}", name));
+                Project {
+                    database: String::new(), balances: vec![],
+                    contracts: vec![(QualifiedContractIdentifier::new(deployer, name), path, content)]
+                }
+            };
+            let contract_identifier = match positional.get(1) {
+                Some(name) => friendly_expect_opt(project.contracts.iter().map(|(contract_identifier, _, _)| contract_identifier)
+                                                  .find(|contract_identifier| contract_identifier.name.as_str() == name.as_str()),
+                                                  &format!("{} is not in the project", name)).clone(),
+                None => project.contracts[0].0.clone()
+            };
+
+            let mut store = MemoryBackingStore::new();
+            friendly_expect(deploy_project(&mut store, &project), "Failed to deploy contracts.");
+            let analysis = {
+                let mut analysis_db = store.as_analysis_db();
+                analysis_db.begin();
+                let analysis = analysis_db.load_contract(&contract_identifier);
+                analysis_db.roll_back();
+                friendly_expect_opt(analysis, "FATAL: deployed contract has no analysis")
+            };
+            let target = fuzz::FuzzTarget::from_interface(contract_identifier, &build_contract_interface(&analysis));
+            if target.invariants.is_empty() {
+                eprintln!("{} has no invariants: read-only functions named {}*, which take no arguments.",
+                          target.contract_identifier, fuzz::INVARIANT_PREFIX);
+                panic_test!();
+            }
+            for name in target.skipped.iter() {
+                eprintln!("Skipping {}: arguments cannot be generated for it.", name);
+            }
+
+            // calls are sent by the deployers, the principals the project credits, and a few others
+            let mut senders: Vec<PrincipalData> = vec![];
+            let candidates = project.contracts.iter().map(|(contract_identifier, _, _)| PrincipalData::from(contract_identifier.issuer.clone()))
+                .chain(project.balances.iter().map(|(principal, _)| principal.clone()))
+                .chain(fuzz::default_senders());
+            for principal in candidates {
+                if !senders.contains(&principal) {
+                    senders.push(principal);
+                }
+            }
+
+            let mut config = fuzz::FuzzConfig::new(senders);
+            config.runs = runs.unwrap_or(config.runs);
+            config.calls_per_run = calls_per_run.unwrap_or(config.calls_per_run);
+            config.seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+            let result = friendly_expect(fuzz::fuzz(&target, &config, |store| deploy_project(store, &project)),
+                                         "Failed to deploy contracts.");
+            match result {
+                Some(failure) => {
+                    println!("Run {} of seed {} broke an invariant.", failure.run + 1, config.seed);
+                    print!("{}", failure);
+                    panic_test!();
+                },
+                None => println!("All invariants held over {} runs of {} calls (seed {}).", config.runs, config.calls_per_run, config.seed)
+            }
+        },
+        "project" => {
+            if args.len() < 3 {
+                eprintln!("Usage: {} {} deploy [project.json]", invoked_by, args[0]);
+                eprintln!("       {} {} snapshot [project.json] [snapshot-name]", invoked_by, args[0]);
+                eprintln!("       {} {} rollback [project.json] [snapshot-name]", invoked_by, args[0]);
+                eprintln!("       {} {} snapshots [project.json]", invoked_by, args[0]);
+                eprintln!("   project.json is an object with the fields `contracts`, and optionally `database`, `deployer` and `balances`.");
+                eprintln!("   each of the contracts is an object with the fields `name` and `path`, and optionally `deployer` and `depends_on`.");
+                eprintln!("   `deploy` credits the balances and deploys the contracts, in dependency order, in a new block.");
+                eprintln!("   `rollback` forks the database's chain from the named snapshot, discarding everything since.");
+                panic_test!();
+            }
+            let project = load_project(&args[2]);
+
+            match (args[1].as_str(), args.get(3)) {
+                ("deploy", None) => {
+                    let marf_kv = friendly_expect(MarfedKV::open(&project.database, None), "Failed to open VM database.");
+                    let parent = get_cli_chain_tip(&open_cli_db(&project.database));
+                    friendly_expect(in_block_from(&project.database, marf_kv, &parent, |mut marf| {
+                                        let result = deploy_project(&mut marf, &project);
+                                        (marf, result)
+                                    }),
+                                    "Failed to deploy project.");
+                    for (contract_identifier, path, _) in project.contracts.iter() {
+                        println!("{} deployed from {}", contract_identifier, path);
+                    }
+                },
+                ("snapshot", Some(name)) => {
+                    let conn = open_cli_db(&project.database);
+                    let tip = get_cli_chain_tip(&conn);
+                    if tip == StacksBlockId::sentinel() {
+                        eprintln!("Nothing to snapshot: the project has not been deployed.");
+                        panic_test!();
+                    }
+                    friendly_expect(conn.execute("INSERT OR REPLACE INTO cli_snapshots (name, block_hash) VALUES (?1, ?2)",
+                                                 &[name as &dyn ToSql, &tip]),
+                                    "FATAL: failed to store snapshot");
+                    println!("Snapshot {} saved at block {}", name, tip);
+                },
+                ("rollback", Some(name)) => {
+                    let snapshot = friendly_expect_opt(get_cli_snapshot(&open_cli_db(&project.database), name),
+                                                       &format!("No snapshot named {}", name));
+                    let marf_kv = friendly_expect(MarfedKV::open(&project.database, None), "Failed to open VM database.");
+                    friendly_expect(in_block_from(&project.database, marf_kv, &snapshot, |marf| (marf, Ok(()))),
+                                    "Failed to roll back.");
+                    println!("Rolled back to snapshot {}", name);
+                },
+                ("snapshots", None) => {
+                    let conn = open_cli_db(&project.database);
+                    let mut stmt = friendly_expect(conn.prepare("SELECT name, block_hash FROM cli_snapshots ORDER BY name"), "FATAL: could not prepare query");
+                    let mut rows = friendly_expect(stmt.query(NO_PARAMS), "FATAL: could not fetch rows");
+                    while let Some(row_res) = rows.next() {
+                        let row = friendly_expect(row_res, "FATAL: could not read snapshot");
+                        let name: String = friendly_expect(row.get_checked("name"), "FATAL: could not read snapshot name");
+                        let block_hash = friendly_expect(StacksBlockId::from_column(&row, "block_hash"), "FATAL: could not parse block hash");
+                        println!("{} {}", name, block_hash);
+                    }
+                },
+                _ => {
+                    eprintln!("Unknown {} command: {}", args[0], args[1..].join(" "));
+                    panic_test!();
+                }
+            }
+        },
         _ => {
             print_usage(invoked_by)
         }
@@ -744,6 +1740,26 @@ mod test {
                                  "mint!".to_string(), "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
                                  "(+ u900 u100)".to_string()]);
 
+        eprintln!("execute tokens with a cost profile");
+        let profile_name = format!("/tmp/execute_{}.folded", rand::thread_rng().gen::<u32>());
+        invoke_command("test", &["execute".to_string(), db_name.clone(), "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                                 "mint!".to_string(), "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                                 "u100".to_string(), "--profile".to_string(), profile_name.clone(),
+                                 "--profile-metric".to_string(), "write_count".to_string()]);
+        let profile = fs::read_to_string(&profile_name).unwrap();
+        assert!(profile.lines().all(|line| line.starts_with("[top-level];S1G2081040G2081040G2081040G208105NK8PE5.tokens:mint!;")));
+        assert!(profile.contains(";S1G2081040G2081040G2081040G208105NK8PE5.tokens:token-credit!;"));
+
+        eprintln!("execute tokens with coverage");
+        let lcov_name = format!("/tmp/execute_{}.lcov", rand::thread_rng().gen::<u32>());
+        invoke_command("test", &["execute".to_string(), db_name.clone(), "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                                 "mint!".to_string(), "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                                 "u100".to_string(), "--coverage".to_string(), lcov_name.clone()]);
+        let lcov = fs::read_to_string(&lcov_name).unwrap();
+        assert!(lcov.starts_with("TN:\nSF:S1G2081040G2081040G2081040G208105NK8PE5.tokens\n"));
+        assert!(lcov.contains("FNDA:1,mint!\n"));
+        assert!(lcov.ends_with("end_of_record\n"));
+
         eprintln!("eval tokens");
         invoke_command("test", &["eval".to_string(), "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(), 
                                  "sample-contracts/tokens-mint.clar".to_string(),
@@ -756,4 +1772,231 @@ mod test {
                                  db_name.clone()
                                  ]);
     }
+
+    #[test]
+    fn test_fmt_and_lint() {
+        let clar_name = format!("/tmp/fmt_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-data-var counter int 0) ;; the count\n(define-public (bump) (begin (var-set counter (+ (var-get counter) 1)) (ok (var-get counter))))").unwrap();
+
+        invoke_command("test", &["fmt".to_string(), clar_name.clone()]);
+        assert_eq!(fs::read_to_string(&clar_name).unwrap(),
+                   "(define-data-var counter int 0) ;; the count\n(define-public (bump)\n  (begin (var-set counter (+ (var-get counter) 1)) (ok (var-get counter))))\n");
+        invoke_command("test", &["fmt".to_string(), clar_name.clone(), "--check".to_string()]);
+
+        invoke_command("test", &["lint".to_string(), clar_name.clone()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fmt_check_unformatted() {
+        let clar_name = format!("/tmp/fmt_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-data-var   counter int 0)").unwrap();
+        invoke_command("test", &["fmt".to_string(), clar_name, "--check".to_string()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lint_warnings() {
+        let clar_name = format!("/tmp/lint_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-private (unused) 1)").unwrap();
+        invoke_command("test", &["lint".to_string(), clar_name]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_check_errors() {
+        let clar_name = format!("/tmp/check_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-private (a) (+ 1 u1))\n(define-public (b) u1)").unwrap();
+        invoke_command("test", &["check".to_string(), clar_name]);
+    }
+
+    #[test]
+    fn test_project_deployment_order() {
+        let contract = |name: &str, depends_on: &[&str]| ProjectContract {
+            name: name.to_string(),
+            path: format!("{}.clar", name),
+            deployer: None,
+            depends_on: depends_on.iter().map(|s| s.to_string()).collect()
+        };
+
+        let contracts = vec![contract("names", &["tokens"]), contract("tokens", &[]), contract("market", &["names", "tokens"])];
+        assert_eq!(project_deployment_order(&contracts).unwrap(), vec![1, 0, 2]);
+
+        let contracts = vec![contract("names", &["tokens"])];
+        assert_eq!(project_deployment_order(&contracts).unwrap_err(), "names depends on tokens, which is not in the project");
+
+        let contracts = vec![contract("a", &["b"]), contract("b", &["c"]), contract("c", &["b"])];
+        assert_eq!(project_deployment_order(&contracts).unwrap_err(), "Circular dependency between contracts: b -> c -> b");
+    }
+
+    #[test]
+    fn test_project() {
+        let project_dir = format!("/tmp/project_{}", rand::thread_rng().gen::<u32>());
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::copy("sample-contracts/tokens.clar", format!("{}/tokens.clar", project_dir)).unwrap();
+        fs::copy("sample-contracts/names.clar", format!("{}/names.clar", project_dir)).unwrap();
+        let manifest = format!("{}/project.json", project_dir);
+        fs::write(&manifest, r#"{
+            "balances": [{ "principal": "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR", "amount": 1000 }],
+            "contracts": [
+                { "name": "names", "path": "names.clar", "depends_on": ["tokens"] },
+                { "name": "tokens", "path": "tokens.clar" }
+            ]
+        }"#).unwrap();
+
+        let db_name = format!("{}/vm-state.db", project_dir);
+        let eval = |program: &str| {
+            let marf_kv = MarfedKV::open(&db_name, None).unwrap();
+            at_chaintip(&db_name, marf_kv, |mut marf| {
+                let result = {
+                    let db = marf.as_clarity_db(&NULL_HEADER_DB);
+                    let mut vm_env = OwnedEnvironment::new_cost_limited(db, LimitedCostTracker::new_max_limit());
+                    let contract_identifier = QualifiedContractIdentifier::parse("S1G2081040G2081040G2081040G208105NK8PE5.tokens").unwrap();
+                    vm_env.get_exec_environment(None)
+                        .eval_read_only(&contract_identifier, program)
+                        .unwrap()
+                };
+                (marf, result)
+            })
+        };
+
+        invoke_command("test", &["project".to_string(), "deploy".to_string(), manifest.clone()]);
+        invoke_command("test", &["project".to_string(), "snapshot".to_string(), manifest.clone(), "deployed".to_string()]);
+        assert_eq!(eval("(stx-get-balance 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)"), Value::UInt(1000));
+
+        invoke_command("test", &["execute".to_string(), db_name.clone(), "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                                 "mint!".to_string(), "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(), "u100".to_string()]);
+        assert_eq!(eval("(get-balance 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)"), Value::UInt(10100));
+
+        invoke_command("test", &["project".to_string(), "rollback".to_string(), manifest.clone(), "deployed".to_string()]);
+        assert_eq!(eval("(get-balance 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)"), Value::UInt(10000));
+        invoke_command("test", &["project".to_string(), "snapshots".to_string(), manifest.clone()]);
+
+        // the rolled back state is still usable
+        invoke_command("test", &["execute".to_string(), db_name.clone(), "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                                 "mint!".to_string(), "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(), "u5".to_string()]);
+        assert_eq!(eval("(get-balance 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)"), Value::UInt(10005));
+    }
+
+    #[test]
+    fn test_post_conditions() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        let clar_name = format!("/tmp/post_conditions_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-fungible-token tokens)
+(define-non-fungible-token names int)
+(define-public (give (name int) (recipient principal))
+  (begin (try! (ft-mint? tokens u10 tx-sender))
+         (try! (ft-transfer? tokens u10 tx-sender recipient))
+         (try! (nft-mint? names name tx-sender))
+         (nft-transfer? names name tx-sender recipient)))").unwrap();
+
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+        invoke_command("test", &["launch".to_string(), "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                                 clar_name, db_name.clone()]);
+
+        let asset_map = {
+            let marf_kv = MarfedKV::open(&db_name, None).unwrap();
+            at_chaintip(&db_name, marf_kv, |mut marf| {
+                let result = {
+                    let db = marf.as_clarity_db(&NULL_HEADER_DB);
+                    let mut vm_env = OwnedEnvironment::new(db);
+                    let sender = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+                    let contract_identifier = QualifiedContractIdentifier::parse("S1G2081040G2081040G2081040G208105NK8PE5.tokens").unwrap();
+                    vm_env.execute_transaction(Value::Principal(sender), contract_identifier, "give",
+                                               &[SymbolicExpression::atom_value(Value::Int(1)),
+                                                 SymbolicExpression::atom_value(Value::Principal(PrincipalData::parse("SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR").unwrap()))])
+                        .unwrap().1
+                };
+                (marf, result)
+            })
+        };
+        let sender = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+        let descriptions: Vec<_> = StacksChainState::post_conditions_from_asset_map(&sender, &asset_map).iter()
+            .map(describe_post_condition).collect();
+        assert_eq!(descriptions, vec![
+            "origin sends 'S1G2081040G2081040G2081040G208105NK8PE5.tokens::names 1".to_string(),
+            "origin sends exactly 10 'S1G2081040G2081040G2081040G208105NK8PE5.tokens::tokens".to_string()]);
+
+        invoke_command("test", &["post_conditions".to_string(), db_name, "S1G2081040G2081040G2081040G208105NK8PE5.tokens".to_string(),
+                                 "give".to_string(), "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+                                 "1".to_string(), "'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string()]);
+    }
+
+    #[test]
+    fn test_fuzz() {
+        let clar_name = format!("/tmp/fuzz_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-data-var total uint u0)
+(define-public (add (amount uint)) (begin (asserts! (< amount u10) (err u1)) (var-set total (+ (var-get total) amount)) (ok true)))
+(define-read-only (invariant-total-bounded) (< (var-get total) u100))").unwrap();
+
+        invoke_command("test", &["fuzz".to_string(), clar_name, "--runs".to_string(), "3".to_string(),
+                                 "--calls".to_string(), "5".to_string(), "--seed".to_string(), "1".to_string()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fuzz_failure() {
+        let clar_name = format!("/tmp/fuzz_{}.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&clar_name, "(define-data-var total uint u0)
+(define-public (add (amount uint)) (begin (var-set total (+ (var-get total) amount)) (ok true)))
+(define-read-only (invariant-total-bounded) (< (var-get total) u100))").unwrap();
+
+        invoke_command("test", &["fuzz".to_string(), clar_name, "--runs".to_string(), "5".to_string(), "--seed".to_string(), "1".to_string()]);
+    }
+
+    #[test]
+    fn test_test_runner() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        
+        for format in ["human", "json", "junit"].iter() {
+            invoke_command("test", &["test".to_string(), "sample-contracts/tests".to_string(),
+                                     "--config".to_string(), "sample-contracts/tests/test-config.json".to_string(),
+                                     "--format".to_string(), format.to_string()]);
+        }
+
+        // run against an on-disk MARF as well
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+        invoke_command("test", &["test".to_string(), "sample-contracts/tests".to_string(), db_name.clone(),
+                                 "--config".to_string(), "sample-contracts/tests/test-config.json".to_string()]);
+    }
+
+    #[test]
+    fn test_test_runner_coverage() {
+        let lcov_name = format!("/tmp/tests_{}.lcov", rand::thread_rng().gen::<u32>());
+        invoke_command("test", &["test".to_string(), "sample-contracts/tests".to_string(),
+                                 "--config".to_string(), "sample-contracts/tests/test-config.json".to_string(),
+                                 "--coverage".to_string(), lcov_name.clone()]);
+
+        let lcov = fs::read_to_string(&lcov_name).unwrap();
+        assert!(lcov.contains("SF:sample-contracts/tests/../tokens.clar\n"));
+        assert!(lcov.contains("SF:sample-contracts/tests/tokens_test.clar\n"));
+        assert_eq!(lcov.matches("end_of_record\n").count(), 2);
+        // the test functions themselves were all run
+        assert!(!lcov.contains("FNDA:0,test-"));
+    }
+
+    #[test]
+    fn test_test_runner_failures() {
+        let tests_name = format!("/tmp/failing_{}_test.clar", rand::thread_rng().gen::<u32>());
+        fs::write(&tests_name, r#"
+(define-public (test-ok) (begin (print u1) (ok true)))
+(define-public (test-err) (err u2))
+(define-public (test-panic) (begin (unwrap-panic (if true none (some u1))) (ok true)))
+(define-public (test-with-args (x uint)) (ok x))
+(define-public (not-a-test) (err u3))
+"#).unwrap();
+
+        let suite = load_test_suite(&tests_name, TestConfig::default());
+        let results = run_test_suite(&mut TestBackingStore::Memory, &suite, &mut None).unwrap();
+        let summary: Vec<_> = results.iter().map(|r| (r.name.as_str(), r.passed)).collect();
+        assert_eq!(summary, vec![("test-err", false), ("test-ok", true), ("test-panic", false), ("test-with-args", false)]);
+
+        assert_eq!(results[0].result, "(err u2)");
+        assert_eq!(results[1].events, vec!["u1".to_string()]);
+        assert!(results[1].cost.runtime > 0);
+
+        let junit = format_test_results_junit(&results);
+        assert!(junit.contains("<testsuites tests=\"4\" failures=\"3\">"));
+        assert!(junit.contains("<failure message=\"(err u2)\"/>"));
+    }
 }
@@ -0,0 +1,39 @@
+/*
+ copyright: (c) 2013-2019 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+#![allow(unused_imports)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+extern crate blockstack_lib;
+
+use std::io;
+use blockstack_lib::{ util::log, lsp::LanguageServer };
+
+fn main() {
+    // stdout carries the protocol, so only warnings and errors are logged (to stderr)
+    log::set_loglevel(log::LOG_WARN).unwrap();
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    LanguageServer::new().run(&mut stdin.lock(), &mut stdout.lock())
+        .expect("Failed to communicate with the language client");
+}
@@ -28,6 +28,8 @@ use rusqlite::Row;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::cmp;
+use std::fmt;
+use std::str::FromStr;
 
 use burnchains::BurnchainHeaderHash;
 use burnchains::Txid;
@@ -41,6 +43,10 @@ use chainstate::stacks::{
     StacksAddress,
     StacksTransaction,
     StacksBlockHeader,
+    TransactionPayload,
+    TransactionPostCondition,
+    PostConditionPrincipal,
+    FungibleConditionCode,
     db::StacksChainState,
     db::blocks::MemPoolRejection
 };
@@ -53,6 +59,7 @@ use util::db::{DBConn, DBTx, FromRow};
 use util::db::FromColumn;
 use util::db::query_rows;
 use util::db::query_row;
+use util::db::query_row_columns;
 use util::db::Error as db_error;
 use util::get_epoch_time_secs;
 use util::db::tx_begin_immediate;
@@ -67,6 +74,105 @@ use rusqlite::Error as SqliteError;
 pub const MEMPOOL_MAX_TRANSACTION_AGE: u64 = 256;
 pub const MAXIMUM_MEMPOOL_TX_CHAINING: u64 = 5;
 
+// default replace-by-fee policy: a replacement must raise the estimated fee of the transaction(s) it
+// replaces by at least this many uSTX, and by at least this percentage.
+pub const MEMPOOL_MIN_REPLACE_FEE_BUMP: u64 = 1;
+pub const MEMPOOL_MIN_REPLACE_FEE_BUMP_PERCENT: u64 = 10;
+
+/// How much more a transaction must pay in order to replace a same-nonce transaction in the same fork
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemPoolReplacePolicy {
+    pub min_fee_bump: u64,
+    pub min_fee_bump_percent: u64,
+}
+
+impl std::default::Default for MemPoolReplacePolicy {
+    fn default() -> MemPoolReplacePolicy {
+        MemPoolReplacePolicy {
+            min_fee_bump: MEMPOOL_MIN_REPLACE_FEE_BUMP,
+            min_fee_bump_percent: MEMPOOL_MIN_REPLACE_FEE_BUMP_PERCENT
+        }
+    }
+}
+
+impl MemPoolReplacePolicy {
+    /// The smallest estimated fee a transaction must have to replace one with the given estimated fee.
+    pub fn min_replacement_fee(&self, prior_fee: u64) -> u64 {
+        let percent_bump = ((prior_fee as u128) * (self.min_fee_bump_percent as u128) + 99) / 100;
+        let bump = cmp::max(self.min_fee_bump as u128, percent_bump);
+        cmp::min((prior_fee as u128) + bump, u64::max_value() as u128) as u64
+    }
+}
+
+// default mempool size limits.  Once either is exceeded, the transactions paying the lowest fee per
+// byte are evicted.
+pub const MEMPOOL_MAX_TXS: u64 = 100_000;
+pub const MEMPOOL_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// How many transactions, and how many bytes of transactions, the mempool may hold
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemPoolLimits {
+    pub max_txs: u64,
+    pub max_bytes: u64,
+}
+
+impl std::default::Default for MemPoolLimits {
+    fn default() -> MemPoolLimits {
+        MemPoolLimits {
+            max_txs: MEMPOOL_MAX_TXS,
+            max_bytes: MEMPOOL_MAX_BYTES
+        }
+    }
+}
+
+impl MemPoolLimits {
+    pub fn is_exceeded(&self, num_txs: u64, num_bytes: u64) -> bool {
+        num_txs > self.max_txs || num_bytes > self.max_bytes
+    }
+}
+
+/// Why a transaction was removed from the mempool before it was mined
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemPoolDropReason {
+    /// replaced by a same-nonce transaction that pays a higher fee
+    ReplaceByFee,
+    /// replaced by a same-nonce transaction in a different fork
+    ReplaceAcrossFork,
+    /// a replaced ancestor now spends enough that the sender can no longer pay for this transaction
+    NotEnoughFunds,
+    /// an earlier-nonce transaction from the same origin or sponsor was evicted
+    NonceGap,
+    /// evicted to make room for a higher fee-rate transaction
+    MemPoolFull,
+}
+
+impl fmt::Display for MemPoolDropReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MemPoolDropReason::ReplaceByFee => write!(f, "ReplaceByFee"),
+            MemPoolDropReason::ReplaceAcrossFork => write!(f, "ReplaceAcrossFork"),
+            MemPoolDropReason::NotEnoughFunds => write!(f, "NotEnoughFunds"),
+            MemPoolDropReason::NonceGap => write!(f, "NonceGap"),
+            MemPoolDropReason::MemPoolFull => write!(f, "MemPoolFull"),
+        }
+    }
+}
+
+impl FromStr for MemPoolDropReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MemPoolDropReason, String> {
+        match s {
+            "ReplaceByFee" => Ok(MemPoolDropReason::ReplaceByFee),
+            "ReplaceAcrossFork" => Ok(MemPoolDropReason::ReplaceAcrossFork),
+            "NotEnoughFunds" => Ok(MemPoolDropReason::NotEnoughFunds),
+            "NonceGap" => Ok(MemPoolDropReason::NonceGap),
+            "MemPoolFull" => Ok(MemPoolDropReason::MemPoolFull),
+            _ => Err(format!("Unknown mempool drop reason: {}", s))
+        }
+    }
+}
+
 pub struct MemPoolAdmitter {
     // mempool admission should have its own chain state view.
     //   the mempool admitter interacts with the chain state
@@ -76,11 +182,13 @@ pub struct MemPoolAdmitter {
     chainstate: StacksChainState,
     cur_block: BlockHeaderHash,
     cur_burn_block: BurnchainHeaderHash,
+    replace_policy: MemPoolReplacePolicy,
+    limits: MemPoolLimits,
 }
 
 impl MemPoolAdmitter {
     pub fn new(chainstate: StacksChainState, cur_block: BlockHeaderHash, cur_burn_block: BurnchainHeaderHash) -> MemPoolAdmitter {
-        MemPoolAdmitter { chainstate, cur_block, cur_burn_block }
+        MemPoolAdmitter { chainstate, cur_block, cur_burn_block, replace_policy: MemPoolReplacePolicy::default(), limits: MemPoolLimits::default() }
     }
 
     pub fn set_block(&mut self, cur_block: &BlockHeaderHash, cur_burn_block: &BurnchainHeaderHash) {
@@ -182,21 +290,70 @@ const MEMPOOL_SQL : &'static [&'static str] = &[
         height INTEGER NOT NULL,    -- stacks block height
         accept_time INTEGER NOT NULL,
         tx BLOB NOT NULL,
+        fee_per_byte REAL NOT NULL,
         PRIMARY KEY (txid),
         UNIQUE (origin_address, origin_nonce),
         UNIQUE (sponsor_address,sponsor_nonce)
     );
     "#,
+    "CREATE INDEX by_txid ON mempool(txid);",
+    "CREATE INDEX by_sponsor ON mempool(sponsor_address, sponsor_nonce);",
+    "CREATE INDEX by_origin ON mempool(origin_address, origin_nonce);",
+    "CREATE INDEX by_timestamp ON mempool(accept_time);",
+    "CREATE INDEX by_chaintip ON mempool(burn_header_hash,block_header_hash);",
+    "CREATE INDEX by_estimated_fee ON mempool(estimated_fee);",
+];
+
+// added after the mempool schema was first deployed, so these are run on every open
+const MEMPOOL_UPGRADE_SQL : &'static [&'static str] = &[
+    "PRAGMA recursive_triggers = ON;",
+    "CREATE INDEX IF NOT EXISTS by_fee_per_byte ON mempool(fee_per_byte ASC, accept_time DESC);",
     r#"
-    CREATE INDEX by_txid ON mempool(txid);
-    CREATE INDEX by_sponsor ON mempool(sponsor_address, sponsor_nonce),
-    CREATE INDEX by_origin ON mempool(origin_address, origin_nonce),
-    CREATE INDEX by_timestamp ON mempool(accept_time);
-    CREATE INDEX by_chaintip ON mempool(burn_header_hash,block_header_hash);
-    CREATE INDEX by_estimated_fee ON mempool(estimated_fee);
-    "#
+    -- transactions that were replaced or evicted, and not yet reported to event observers
+    CREATE TABLE IF NOT EXISTS mempool_dropped(
+        txid TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        height INTEGER NOT NULL,    -- stacks block height of the dropped transaction
+        PRIMARY KEY (txid)
+    );
+    "#,
+    r#"
+    -- number of transactions in the mempool, and their total length.  Kept up to date by the
+    -- triggers below, so the size limits can be checked without scanning the mempool.
+    CREATE TABLE IF NOT EXISTS mempool_size(
+        id INTEGER NOT NULL,
+        num_txs INTEGER NOT NULL,
+        num_bytes INTEGER NOT NULL,
+        PRIMARY KEY (id)
+    );
+    "#,
+    "INSERT OR IGNORE INTO mempool_size (id, num_txs, num_bytes) SELECT 0, COUNT(*), IFNULL(SUM(length), 0) FROM mempool;",
+    r#"
+    CREATE TRIGGER IF NOT EXISTS mempool_size_insert AFTER INSERT ON mempool
+    BEGIN
+        UPDATE mempool_size SET num_txs = num_txs + 1, num_bytes = num_bytes + NEW.length WHERE id = 0;
+    END;
+    "#,
+    r#"
+    CREATE TRIGGER IF NOT EXISTS mempool_size_delete AFTER DELETE ON mempool
+    BEGIN
+        UPDATE mempool_size SET num_txs = num_txs - 1, num_bytes = num_bytes - OLD.length WHERE id = 0;
+    END;
+    "#,
 ];
 
+// Walks the by_fee_per_byte index, and checks each candidate for dependents with index seeks on
+// the UNIQUE (address, nonce) constraints.  An unsponsored transaction's sponsor columns repeat
+// its origin columns, so its sponsor is only checked if it really has one.
+const MEMPOOL_EVICTABLE_TX_SQL : &'static str = "
+    SELECT m.* FROM mempool AS m WHERE m.txid != ?1
+    AND NOT EXISTS (SELECT 1 FROM mempool AS c WHERE c.origin_address = m.origin_address AND c.origin_nonce > m.origin_nonce)
+    AND NOT EXISTS (SELECT 1 FROM mempool AS c WHERE c.sponsor_address = m.origin_address AND c.sponsor_nonce > m.origin_nonce)
+    AND ((m.sponsor_address = m.origin_address AND m.sponsor_nonce = m.origin_nonce)
+         OR (NOT EXISTS (SELECT 1 FROM mempool AS c WHERE c.origin_address = m.sponsor_address AND c.origin_nonce > m.sponsor_nonce)
+             AND NOT EXISTS (SELECT 1 FROM mempool AS c WHERE c.sponsor_address = m.sponsor_address AND c.sponsor_nonce > m.sponsor_nonce)))
+    ORDER BY m.fee_per_byte ASC, m.accept_time DESC LIMIT 1";
+
 pub struct MemPoolDB {
     db: DBConn,
     path: String,
@@ -306,6 +463,45 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Does the given table have the given column?
+    fn table_has_column(tx: &DBTx, table: &str, column: &str) -> Result<bool, db_error> {
+        let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table)).map_err(db_error::SqliteError)?;
+        let mut rows = stmt.query(NO_PARAMS).map_err(db_error::SqliteError)?;
+        while let Some(row_res) = rows.next() {
+            let row = row_res.map_err(db_error::SqliteError)?;
+            let name : String = row.get("name");
+            if name == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Bring a mempool db created by an earlier version of this code up to date.
+    fn upgrade_mempool_db(conn: &mut DBConn) -> Result<(), db_error> {
+        let tx = tx_begin_immediate(conn)?;
+
+        if !MemPoolDB::table_has_column(&tx, "mempool", "fee_per_byte")? {
+            tx.execute("ALTER TABLE mempool ADD COLUMN fee_per_byte REAL NOT NULL DEFAULT 0", NO_PARAMS)
+                .map_err(db_error::SqliteError)?;
+            tx.execute("UPDATE mempool SET fee_per_byte = CAST(fee_rate AS REAL) / length", NO_PARAMS)
+                .map_err(db_error::SqliteError)?;
+        }
+
+        for cmd in MEMPOOL_UPGRADE_SQL {
+            tx.execute(cmd, NO_PARAMS).map_err(db_error::SqliteError)?;
+        }
+
+        // dropped transactions recorded before they were garbage-collected go at the next collection
+        if !MemPoolDB::table_has_column(&tx, "mempool_dropped", "height")? {
+            tx.execute("ALTER TABLE mempool_dropped ADD COLUMN height INTEGER NOT NULL DEFAULT 0", NO_PARAMS)
+                .map_err(db_error::SqliteError)?;
+        }
+
+        tx.commit().map_err(db_error::SqliteError)?;
+        Ok(())
+    }
+
     /// Open the mempool db within the chainstate directory.
     /// The chainstate must be instantiated already.
     pub fn open(mainnet: bool, chain_id: u32, chainstate_path: &str) -> Result<MemPoolDB, db_error> {
@@ -349,7 +545,10 @@ impl MemPoolDB {
             // instantiate!
             MemPoolDB::instantiate_mempool_db(&mut conn)?;
         }
-        
+
+        MemPoolDB::upgrade_mempool_db(&mut conn)?;
+
+
         Ok(MemPoolDB {
             db: conn,
             path: db_path.to_string(),
@@ -572,6 +771,15 @@ impl MemPoolDB {
         Ok(rows)
     }
 
+    /// Get up to `count` txids that sort strictly after `last_txid`, across all chain tips.
+    /// Txids are ordered by their hex encoding, which is the same as ascending byte order.
+    /// Used to page through the mempool when a peer asks for a summary of it.
+    pub fn get_txid_page(conn: &DBConn, last_txid: &Txid, count: u64) -> Result<Vec<Txid>, db_error> {
+        let sql = "SELECT txid FROM mempool WHERE txid > ?1 ORDER BY txid ASC LIMIT ?2".to_string();
+        let args : &[&dyn ToSql] = &[last_txid, &u64_to_sql(count)?];
+        query_row_columns(conn, &sql, args, "txid")
+    }
+
     /// Get a transaction's metadata, given address and nonce, and whether the address is used as a sponsor or an origin.
     /// Faster than getting the MemPoolTxInfo, since no deserialization will be needed.
     /// Used to see if there exists a transaction with this info, so as to implement replace-by-fee
@@ -613,9 +821,11 @@ impl MemPoolDB {
         Ok(cmp::max(as_origin, as_sponsor))
     }
 
-    /// Add a transaction to the mempool.  If it already exists, then replace it if the given fee
-    /// is higher than the one that's already there.
+    /// Add a transaction to the mempool.  If there is already a transaction with the same origin
+    /// or sponsor nonce, then replace it if the given fee is high enough to satisfy the
+    /// replace-by-fee policy, or if the existing transaction is in a different fork.
     /// Carry out the mempool admission test before adding.
+    /// Returns the transactions that were replaced.
     /// Don't call directly; use submit()
     fn try_add_tx<'a>(tx: &mut MemPoolTx<'a>, 
                       burn_header_hash: &BurnchainHeaderHash, 
@@ -628,40 +838,43 @@ impl MemPoolDB {
                       origin_address: &StacksAddress,
                       origin_nonce: u64,
                       sponsor_address: &StacksAddress,
-                      sponsor_nonce: u64) -> Result<(), MemPoolRejection> {
+                      sponsor_nonce: u64) -> Result<Vec<(Txid, MemPoolDropReason)>, MemPoolRejection> {
 
         let length = tx_bytes.len() as u64;
 
         // do we already have txs with either the same origin nonce or sponsor nonce ?
-        let prior_tx = {
-            match MemPoolDB::get_tx_metadata_by_address(tx, true, origin_address, origin_nonce)? {
-                Some(prior_tx) => Some(prior_tx),
-                None => MemPoolDB::get_tx_metadata_by_address(tx, false, sponsor_address, sponsor_nonce)?
+        let mut prior_txs = vec![];
+        if let Some(prior_tx) = MemPoolDB::get_tx_metadata_by_address(tx, true, origin_address, origin_nonce)? {
+            prior_txs.push(prior_tx);
+        }
+        if let Some(prior_tx) = MemPoolDB::get_tx_metadata_by_address(tx, false, sponsor_address, sponsor_nonce)? {
+            if !prior_txs.iter().any(|p| p.txid == prior_tx.txid) {
+                prior_txs.push(prior_tx);
             }
-        };
+        }
 
         // if so, is this a replace-by-fee? or a replace-in-chain-tip?
-        let add_tx = if let Some(prior_tx) = prior_tx {
-            if estimated_fee > prior_tx.estimated_fee {
+        let mut replaced = vec![];
+        for prior_tx in prior_txs.into_iter() {
+            let min_replacement_fee = tx.admitter.replace_policy.min_replacement_fee(prior_tx.estimated_fee);
+            if estimated_fee >= min_replacement_fee {
                 // is this a replace-by-fee ?
-                true
+                replaced.push((prior_tx.txid, MemPoolDropReason::ReplaceByFee));
             } else if !tx.is_block_in_fork(&prior_tx.burn_header_hash, &prior_tx.block_header_hash,
                                            burn_header_hash, block_header_hash)? {
                 // is this a replace-across-fork ?
-                true
+                replaced.push((prior_tx.txid, MemPoolDropReason::ReplaceAcrossFork));
             } else {
-                // there's a >= fee tx in this fork, cannot add
-                info!("TX conflicts with sponsor/origin nonce in same fork with >= fee: new_txid={}, old_txid={}, origin_addr={}, origin_nonce={}, sponsor_addr={}, sponsor_nonce={}, new_fee={}, old_fee={}",
-                      txid, prior_tx.txid, origin_address, origin_nonce, sponsor_address, sponsor_nonce, estimated_fee, prior_tx.estimated_fee);
-                false
+                // there's a tx in this fork that this one doesn't pay enough to replace
+                info!("TX conflicts with sponsor/origin nonce in same fork and does not pay enough to replace it: new_txid={}, old_txid={}, origin_addr={}, origin_nonce={}, sponsor_addr={}, sponsor_nonce={}, new_fee={}, old_fee={}, min_replacement_fee={}",
+                      txid, prior_tx.txid, origin_address, origin_nonce, sponsor_address, sponsor_nonce, estimated_fee, prior_tx.estimated_fee, min_replacement_fee);
+                return Err(MemPoolRejection::ReplaceByFeeTooLow(estimated_fee, min_replacement_fee));
             }
-        } else {
-            // no conflicting TX with this origin/sponsor, go ahead and add
-           true
-        };
+        }
 
-        if !add_tx {
-            return Err(MemPoolRejection::ConflictingNonceInMempool);
+        // the insert below removes the replaced transactions
+        for (replaced_txid, reason) in replaced.iter() {
+            MemPoolDB::drop_tx(tx, replaced_txid, reason)?;
         }
 
         let sql = "INSERT OR REPLACE INTO mempool (
@@ -677,8 +890,12 @@ impl MemPoolDB {
             block_header_hash,
             height,
             accept_time,
-            tx)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)";
+            tx,
+            fee_per_byte)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)";
+
+        // only used to order eviction candidates; exact comparisons use fee_rate and length
+        let fee_per_byte = (fee_rate as f64) / (length as f64);
 
         let args : &[&dyn ToSql] = &[
             &txid, 
@@ -693,18 +910,242 @@ impl MemPoolDB {
             block_header_hash,
             &u64_to_sql(height)?,
             &u64_to_sql(get_epoch_time_secs())?,
-            &tx_bytes];
+            &tx_bytes,
+            &fee_per_byte];
 
         tx.execute(sql, args).map_err(|e| MemPoolRejection::DBError(db_error::SqliteError(e)))?;
+        Ok(replaced)
+    }
+
+    /// Remove a transaction from the mempool, and remember why so event observers can be told.
+    fn drop_tx<'a>(tx: &mut MemPoolTx<'a>, txid: &Txid, reason: &MemPoolDropReason) -> Result<(), db_error> {
+        debug!("Drop mempool tx {}: {}", txid, reason);
+        let args : &[&dyn ToSql] = &[txid, &reason.to_string()];
+        tx.execute("INSERT OR REPLACE INTO mempool_dropped (txid, reason, height) SELECT txid, ?2, height FROM mempool WHERE txid = ?1", args)
+            .map_err(db_error::SqliteError)?;
+
+        tx.execute("DELETE FROM mempool WHERE txid = ?1", &[txid as &dyn ToSql])
+            .map_err(db_error::SqliteError)?;
+        Ok(())
+    }
+
+    /// Get all transactions in which the given address is the origin or the sponsor, ordered by
+    /// the address's nonce.
+    fn get_txs_by_address(conn: &DBConn, addr: &StacksAddress) -> Result<Vec<MemPoolTxInfo>, db_error> {
+        let sql = "SELECT * FROM mempool WHERE origin_address = ?1 OR sponsor_address = ?1";
+        let args : &[&dyn ToSql] = &[&addr.to_string()];
+        let mut rows = query_rows::<MemPoolTxInfo, _>(conn, sql, args)?;
+        rows.sort_by_key(|txinfo| MemPoolDB::get_nonce_of(txinfo, addr));
+        Ok(rows)
+    }
+
+    /// Get the nonce a transaction consumes from the given address (as its origin or sponsor).
+    fn get_nonce_of(txinfo: &MemPoolTxInfo, addr: &StacksAddress) -> u64 {
+        if txinfo.metadata.origin_address == *addr {
+            txinfo.metadata.origin_nonce
+        }
+        else {
+            txinfo.metadata.sponsor_nonce
+        }
+    }
+
+    /// How many uSTX could the given address spend on this transaction, at most?  Fees and STX
+    /// transfers are known ahead of time.  What a contract call or contract publish spends isn't,
+    /// so only the upper bound its origin's STX post-conditions put on it is counted.  If there
+    /// isn't one, the amount is unknowable, and it isn't counted at all.
+    fn get_stx_spent_by(tx: &StacksTransaction, addr: &StacksAddress) -> u128 {
+        let mut spent : u128 = 0;
+        if tx.sponsor_address().unwrap_or(tx.origin_address()) == *addr {
+            spent += tx.get_fee_rate() as u128;
+        }
+        if tx.origin_address() == *addr {
+            match tx.payload {
+                TransactionPayload::TokenTransfer(_, amount, _) => {
+                    spent += amount as u128;
+                },
+                TransactionPayload::ContractCall(_) | TransactionPayload::SmartContract(_) => {
+                    spent += MemPoolDB::get_stx_spend_bound(tx, addr).unwrap_or(0) as u128;
+                },
+                _ => {}
+            }
+        }
+        spent
+    }
+
+    /// Get the tightest upper bound that a transaction's STX post-conditions put on how many uSTX
+    /// the given address (its origin) can send or burn, if they put one on it at all.
+    fn get_stx_spend_bound(tx: &StacksTransaction, addr: &StacksAddress) -> Option<u64> {
+        let mut bound = None;
+        for post_condition in tx.post_conditions.iter() {
+            let (principal, condition_code, amount) = match post_condition {
+                TransactionPostCondition::STX(ref principal, ref condition_code, amount) => (principal, condition_code, *amount),
+                _ => continue
+            };
+            let names_addr = match principal {
+                PostConditionPrincipal::Origin => tx.origin_address() == *addr,
+                PostConditionPrincipal::Standard(ref pc_addr) => pc_addr == addr,
+                PostConditionPrincipal::Contract(..) => false
+            };
+            if !names_addr {
+                continue;
+            }
+            let max_sent = match condition_code {
+                FungibleConditionCode::SentEq | FungibleConditionCode::SentLe => amount,
+                FungibleConditionCode::SentLt => amount.saturating_sub(1),
+                FungibleConditionCode::SentGt | FungibleConditionCode::SentGe => continue
+            };
+            bound = Some(cmp::min(max_sent, bound.unwrap_or(max_sent)));
+        }
+        bound
+    }
+
+    /// A replacement transaction can spend more than the transaction(s) it replaced.  Check that
+    /// its origin and sponsor can still pay for every transaction chained after it, as of the given
+    /// chain tip.  Evict the first one they can't pay for, and everything chained after that.
+    /// Transactions whose nonces were already used at the chain tip are already paid for by the
+    /// tip's balance, so they aren't counted.
+    fn evict_unfunded_descendants<'a>(mempool_tx: &mut MemPoolTx<'a>, burn_header_hash: &BurnchainHeaderHash, block_hash: &BlockHeaderHash, tx: &StacksTransaction) -> Result<(), db_error> {
+        let mut payers = vec![(tx.origin_address(), tx.get_origin_nonce())];
+        if let (Some(sponsor_addr), Some(sponsor_nonce)) = (tx.sponsor_address(), tx.get_sponsor_nonce()) {
+            payers.push((sponsor_addr, sponsor_nonce));
+        }
+
+        // (address, nonce) pairs after which every chained transaction must go
+        let mut gaps = vec![];
+        for (addr, nonce) in payers.into_iter() {
+            let account = mempool_tx.admitter.chainstate.get_account_at_tip(burn_header_hash, block_hash, &addr.clone().into());
+            let mut spent : u128 = 0;
+            for txinfo in MemPoolDB::get_txs_by_address(mempool_tx, &addr)?.into_iter() {
+                let tx_nonce = MemPoolDB::get_nonce_of(&txinfo, &addr);
+                if tx_nonce < account.nonce {
+                    continue;
+                }
+                spent = spent.saturating_add(MemPoolDB::get_stx_spent_by(&txinfo.tx, &addr));
+                if tx_nonce > nonce && spent > account.stx_balance {
+                    MemPoolDB::drop_tx(mempool_tx, &txinfo.metadata.txid, &MemPoolDropReason::NotEnoughFunds)?;
+                    gaps.push((txinfo.metadata.origin_address.clone(), txinfo.metadata.origin_nonce));
+                    gaps.push((txinfo.metadata.sponsor_address.clone(), txinfo.metadata.sponsor_nonce));
+                    break;
+                }
+            }
+        }
+
+        while let Some((addr, nonce)) = gaps.pop() {
+            for txinfo in MemPoolDB::get_txs_by_address(mempool_tx, &addr)?.into_iter() {
+                if MemPoolDB::get_nonce_of(&txinfo, &addr) > nonce {
+                    MemPoolDB::drop_tx(mempool_tx, &txinfo.metadata.txid, &MemPoolDropReason::NonceGap)?;
+                    gaps.push((txinfo.metadata.origin_address.clone(), txinfo.metadata.origin_nonce));
+                    gaps.push((txinfo.metadata.sponsor_address.clone(), txinfo.metadata.sponsor_nonce));
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Get and forget the transactions that were replaced or evicted since the last call.
+    pub fn drain_dropped_txs(&mut self) -> Result<Vec<(Txid, MemPoolDropReason)>, db_error> {
+        let tx = tx_begin_immediate(&mut self.db)?;
+        let dropped = {
+            let mut stmt = tx.prepare("SELECT txid, reason FROM mempool_dropped ORDER BY rowid ASC")
+                .map_err(db_error::SqliteError)?;
+
+            let mut rows = stmt.query(NO_PARAMS)
+                .map_err(db_error::SqliteError)?;
+
+            let mut dropped = vec![];
+            while let Some(row_res) = rows.next() {
+                match row_res {
+                    Ok(row) => {
+                        let txid = Txid::from_column(&row, "txid")?;
+                        let reason_str : String = row.get("reason");
+                        let reason = MemPoolDropReason::from_str(&reason_str).map_err(|_| db_error::ParseError)?;
+                        dropped.push((txid, reason));
+                    },
+                    Err(e) => {
+                        return Err(db_error::SqliteError(e));
+                    }
+                };
+            }
+            dropped
+        };
+
+        tx.execute("DELETE FROM mempool_dropped", NO_PARAMS).map_err(db_error::SqliteError)?;
+        tx.commit().map_err(db_error::SqliteError)?;
+        Ok(dropped)
+    }
+
+    /// Use the given replace-by-fee policy when admitting transactions
+    pub fn set_replace_policy(&mut self, policy: MemPoolReplacePolicy) -> () {
+        self.admitter.replace_policy = policy;
+    }
+
+    /// Limit how many transactions, and how many bytes of transactions, the mempool will hold
+    pub fn set_limits(&mut self, limits: MemPoolLimits) -> () {
+        self.admitter.limits = limits;
+    }
+
+    /// Get the number of transactions in the mempool, and their total size in bytes
+    pub fn get_size(conn: &DBConn) -> Result<(u64, u64), db_error> {
+        let sql = "SELECT num_txs, num_bytes FROM mempool_size WHERE id = 0";
+        conn.query_row_and_then(sql, NO_PARAMS, |row| -> Result<(u64, u64), db_error> {
+            let num_txs : i64 = row.get(0);
+            let num_bytes : i64 = row.get(1);
+            Ok((num_txs as u64, num_bytes as u64))
+        })
+    }
+
+    /// Find the transaction other than `exclude_txid` that pays the lowest fee per byte, and that no
+    /// other transaction depends on -- i.e. one that has the highest nonce of both its origin and its
+    /// sponsor.  Evicting it leaves no nonce gaps.  Among equal fees per byte, prefer the most
+    /// recently accepted.  Note that the `fee_rate` column holds the transaction's whole fee.
+    fn get_evictable_tx(conn: &DBConn, exclude_txid: &Txid) -> Result<Option<MemPoolTxMetadata>, db_error> {
+        query_row(conn, MEMPOOL_EVICTABLE_TX_SQL, &[exclude_txid as &dyn ToSql])
+    }
+
+    /// Evict the transactions that pay the lowest fee per byte until the mempool is within its
+    /// limits, in order to make room for the newly-added transaction `txid`, which pays `fee` for
+    /// `len` bytes.  Fails if that would evict a transaction that pays at least as much per byte, or
+    /// if there is nothing left to evict.  On failure, reports the fee this transaction would need
+    /// to pay to get in.
+    fn enforce_limits<'a>(mempool_tx: &mut MemPoolTx<'a>, txid: &Txid, fee: u64, len: u64) -> Result<(), MemPoolRejection> {
+        let limits = mempool_tx.admitter.limits.clone();
+        loop {
+            let (num_txs, num_bytes) = MemPoolDB::get_size(mempool_tx)?;
+            if !limits.is_exceeded(num_txs, num_bytes) {
+                return Ok(());
+            }
+
+            let evictable = match MemPoolDB::get_evictable_tx(mempool_tx, txid)? {
+                Some(md) => md,
+                None => {
+                    info!("TX does not fit in an empty mempool: txid={}, num_txs={}, num_bytes={}", txid, num_txs, num_bytes);
+                    return Err(MemPoolRejection::DoesNotFitInMemPool(limits.max_txs, limits.max_bytes));
+                }
+            };
+
+            // compare fee / len against evictable.fee_rate / evictable.len without rounding
+            let evictable_fee_for_len = (evictable.fee_rate as u128) * (len as u128);
+            if evictable_fee_for_len >= (fee as u128) * (evictable.len as u128) {
+                let min_fee = evictable_fee_for_len / (evictable.len as u128) + 1;
+                let min_fee = cmp::min(min_fee, u64::max_value() as u128) as u64;
+                info!("TX fee per byte too low to enter full mempool: txid={}, fee={}, len={}, min_fee={}, num_txs={}, num_bytes={}",
+                      txid, fee, len, min_fee, num_txs, num_bytes);
+                return Err(MemPoolRejection::MemPoolFull(fee, min_fee));
+            }
+
+            MemPoolDB::drop_tx(mempool_tx, &evictable.txid, &MemPoolDropReason::MemPoolFull)?;
+        }
+    }
+
     /// Garbage-collect the mempool.  Remove transactions that have a given number of
-    /// confirmations.
+    /// confirmations, and forget the ones that were dropped as long ago.
     pub fn garbage_collect<'a>(tx: &mut MemPoolTx<'a>, min_height: u64) -> Result<(), db_error> {
         let sql = "DELETE FROM mempool WHERE height < ?1";
         let args : &[&dyn ToSql] = &[&u64_to_sql(min_height)?];
 
+        tx.execute(sql, args).map_err(db_error::SqliteError)?;
+
+        let sql = "DELETE FROM mempool_dropped WHERE height < ?1";
         tx.execute(sql, args).map_err(db_error::SqliteError)?;
         Ok(())
     }
@@ -765,7 +1206,12 @@ impl MemPoolDB {
             mempool_tx.admitter.will_admit_tx(&mempool_tx.tx, &tx, len)?;
         }
         
-        MemPoolDB::try_add_tx(mempool_tx, &burn_header_hash, &block_hash, txid, tx_data, estimated_fee, fee_rate, height, &origin_address, origin_nonce, &sponsor_address, sponsor_nonce)?;
+        let replaced = MemPoolDB::try_add_tx(mempool_tx, &burn_header_hash, &block_hash, txid, tx_data, estimated_fee, fee_rate, height, &origin_address, origin_nonce, &sponsor_address, sponsor_nonce)?;
+        if replaced.len() > 0 {
+            MemPoolDB::evict_unfunded_descendants(mempool_tx, &burn_header_hash, &block_hash, &tx)?;
+        }
+
+        MemPoolDB::enforce_limits(mempool_tx, &txid, fee_rate, len)?;
 
         Ok(())
     }
@@ -827,20 +1273,44 @@ mod tests {
         StacksMicroblockHeader, StacksPrivateKey, TransactionSpendingCondition, TransactionAuth, TransactionVersion,
         StacksPublicKey, TransactionPayload, StacksTransactionSigner,
         TokenTransferMemo, CoinbasePayload, TransactionPostConditionMode, TransactionAnchorMode,
-        StacksTransaction, TransactionSmartContract, TransactionContractCall, StacksAddress };
+        StacksTransaction, TransactionSmartContract, TransactionContractCall, StacksAddress,
+        TransactionPostCondition, PostConditionPrincipal, FungibleConditionCode };
 
     use util::db::{DBConn, FromRow};
-    use super::MemPoolDB;
+    use rusqlite::NO_PARAMS;
+    use rusqlite::types::ToSql;
+    use super::{MemPoolDB, MemPoolTx, MemPoolReplacePolicy, MemPoolDropReason, MemPoolLimits, MEMPOOL_EVICTABLE_TX_SQL};
+    use core::{FIRST_BURNCHAIN_BLOCK_HASH, FIRST_STACKS_BLOCK_HASH};
 
-    use burnchains::BurnchainHeaderHash;
+    use burnchains::{BurnchainHeaderHash, Txid};
     use chainstate::stacks::test::codec_all_transactions;
     use chainstate::stacks::db::test::chainstate_path;
     use chainstate::stacks::db::test::instantiate_chainstate;
+    use chainstate::stacks::db::test::instantiate_chainstate_with_balances;
+    use chainstate::stacks::miner::test::{make_user_stacks_transfer, make_user_contract_publish};
 
     const FOO_CONTRACT: &'static str = "(define-public (foo) (ok 1))
                                         (define-public (bar (x uint)) (ok x))";
     const SK_1: &'static str = "a1289f6438855da7decf9b61b852c882c398cff1446b2a0f823538aa2ebef92e01";
     const SK_2: &'static str = "4ce9a8f7539ea93753a36405b16e8b57e15a552430410709c2b6d65dca5c02e201";
+
+    /// Make a contract publish whose origin may send or burn at most `max_ustx_sent` uSTX
+    fn make_bounded_contract_publish(privk: &StacksPrivateKey, nonce: u64, fee_rate: u64, contract_name: &str, contract_content: &str, max_ustx_sent: u64) -> StacksTransaction {
+        let payload = TransactionSmartContract {
+            name: ContractName::from(contract_name),
+            code_body: StacksString::from_string(&contract_content.to_string()).unwrap()
+        };
+        let mut tx = StacksTransaction::new(TransactionVersion::Testnet, TransactionAuth::from_p2pkh(privk).unwrap(), TransactionPayload::SmartContract(payload));
+        tx.chain_id = 0x80000000;
+        tx.post_condition_mode = TransactionPostConditionMode::Allow;
+        tx.post_conditions.push(TransactionPostCondition::STX(PostConditionPrincipal::Origin, FungibleConditionCode::SentLe, max_ustx_sent));
+        tx.set_origin_nonce(nonce);
+        tx.set_fee_rate(fee_rate);
+
+        let mut signer = StacksTransactionSigner::new(&tx);
+        signer.sign_origin(privk).unwrap();
+        signer.get_tx().unwrap()
+    }
     const SK_3: &'static str = "cb95ddd0fe18ec57f4f3533b95ae564b3f1ae063dbf75b46334bd86245aef78501";
 
     #[test]
@@ -850,6 +1320,571 @@ mod tests {
         let _mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
     }
 
+    #[test]
+    fn mempool_evict_unfunded_descendants() {
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+
+        let _chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "mempool_evict_unfunded_descendants", vec![(addr.clone(), 10000)]);
+        // the initial balances are only visible from the boot block
+        let b_1 = (FIRST_BURNCHAIN_BLOCK_HASH.clone(), FIRST_STACKS_BLOCK_HASH.clone());
+
+        let chainstate_path = chainstate_path("mempool_evict_unfunded_descendants");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let add_tx = |mempool_tx: &mut MemPoolTx, tx: &StacksTransaction| {
+            let tx_bytes = tx.serialize_to_vec();
+            let estimated_fee = tx.get_fee_rate() * (tx_bytes.len() as u64);
+            MemPoolDB::try_add_tx(mempool_tx, &b_1.0, &b_1.1, tx.txid(), tx_bytes, estimated_fee, tx.get_fee_rate(), 1, &addr, tx.get_origin_nonce(), &addr, tx.get_origin_nonce())
+        };
+
+        // a chain of three transfers that the account can pay for
+        let txs = vec![
+            make_user_stacks_transfer(&privk, 0, 100, &recipient, 1000),
+            make_user_stacks_transfer(&privk, 1, 100, &recipient, 5000),
+            make_user_stacks_transfer(&privk, 2, 100, &recipient, 1000),
+        ];
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for tx in txs.iter() {
+            assert_eq!(add_tx(&mut mempool_tx, tx).unwrap(), vec![]);
+        }
+
+        // replace the first transfer with one that leaves too little for the second
+        let replacement = make_user_stacks_transfer(&privk, 0, 200, &recipient, 5000);
+        assert_eq!(add_tx(&mut mempool_tx, &replacement).unwrap(), vec![(txs[0].txid(), MemPoolDropReason::ReplaceByFee)]);
+        MemPoolDB::evict_unfunded_descendants(&mut mempool_tx, &b_1.0, &b_1.1, &replacement).unwrap();
+        mempool_tx.commit().unwrap();
+
+        assert!(mempool.has_tx(&replacement.txid()));
+        for tx in txs.iter() {
+            assert!(!mempool.has_tx(&tx.txid()));
+        }
+
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![
+            (txs[0].txid(), MemPoolDropReason::ReplaceByFee),
+            (txs[1].txid(), MemPoolDropReason::NotEnoughFunds),
+            (txs[2].txid(), MemPoolDropReason::NonceGap),
+        ]);
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn mempool_evict_descendants_of_contract_txs() {
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+
+        let _chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "mempool_evict_descendants_of_contract_txs", vec![(addr.clone(), 10000)]);
+        // the initial balances are only visible from the boot block
+        let b_1 = (FIRST_BURNCHAIN_BLOCK_HASH.clone(), FIRST_STACKS_BLOCK_HASH.clone());
+
+        let chainstate_path = chainstate_path("mempool_evict_descendants_of_contract_txs");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let add_tx = |mempool_tx: &mut MemPoolTx, tx: &StacksTransaction| {
+            let tx_bytes = tx.serialize_to_vec();
+            let estimated_fee = tx.get_fee_rate() * (tx_bytes.len() as u64);
+            MemPoolDB::try_add_tx(mempool_tx, &b_1.0, &b_1.1, tx.txid(), tx_bytes, estimated_fee, tx.get_fee_rate(), 1, &addr, tx.get_origin_nonce(), &addr, tx.get_origin_nonce())
+        };
+
+        // the contract's origin can send at most 8000 uSTX, so the last transfer can be paid for...
+        let txs = vec![
+            make_user_stacks_transfer(&privk, 0, 100, &recipient, 1000),
+            make_bounded_contract_publish(&privk, 1, 100, "hello-world", FOO_CONTRACT, 8000),
+            make_user_stacks_transfer(&privk, 2, 100, &recipient, 500),
+        ];
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for tx in txs.iter() {
+            assert_eq!(add_tx(&mut mempool_tx, tx).unwrap(), vec![]);
+        }
+
+        // ...until the first transfer is replaced by one that sends more
+        let replacement = make_user_stacks_transfer(&privk, 0, 200, &recipient, 1200);
+        assert_eq!(add_tx(&mut mempool_tx, &replacement).unwrap(), vec![(txs[0].txid(), MemPoolDropReason::ReplaceByFee)]);
+        MemPoolDB::evict_unfunded_descendants(&mut mempool_tx, &b_1.0, &b_1.1, &replacement).unwrap();
+        mempool_tx.commit().unwrap();
+
+        assert!(mempool.has_tx(&replacement.txid()));
+        assert!(mempool.has_tx(&txs[1].txid()));
+        assert!(!mempool.has_tx(&txs[2].txid()));
+
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![
+            (txs[0].txid(), MemPoolDropReason::ReplaceByFee),
+            (txs[2].txid(), MemPoolDropReason::NotEnoughFunds),
+        ]);
+    }
+
+    #[test]
+    fn mempool_fee_bump_of_contract_tx_keeps_descendants() {
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+
+        let _chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "mempool_fee_bump_of_contract_tx_keeps_descendants", vec![(addr.clone(), 10000)]);
+        let b_1 = (FIRST_BURNCHAIN_BLOCK_HASH.clone(), FIRST_STACKS_BLOCK_HASH.clone());
+
+        let chainstate_path = chainstate_path("mempool_fee_bump_of_contract_tx_keeps_descendants");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let add_tx = |mempool_tx: &mut MemPoolTx, tx: &StacksTransaction| {
+            let tx_bytes = tx.serialize_to_vec();
+            let estimated_fee = tx.get_fee_rate() * (tx_bytes.len() as u64);
+            MemPoolDB::try_add_tx(mempool_tx, &b_1.0, &b_1.1, tx.txid(), tx_bytes, estimated_fee, tx.get_fee_rate(), 1, &addr, tx.get_origin_nonce(), &addr, tx.get_origin_nonce())
+        };
+
+        // nothing limits what the contract spends, so only its fee is counted
+        let txs = vec![
+            make_user_contract_publish(&privk, 0, 100, "hello-world", FOO_CONTRACT),
+            make_user_stacks_transfer(&privk, 1, 100, &recipient, 9000),
+        ];
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for tx in txs.iter() {
+            assert_eq!(add_tx(&mut mempool_tx, tx).unwrap(), vec![]);
+        }
+
+        let replacement = make_user_contract_publish(&privk, 0, 200, "hello-world", FOO_CONTRACT);
+        assert_eq!(add_tx(&mut mempool_tx, &replacement).unwrap(), vec![(txs[0].txid(), MemPoolDropReason::ReplaceByFee)]);
+        MemPoolDB::evict_unfunded_descendants(&mut mempool_tx, &b_1.0, &b_1.1, &replacement).unwrap();
+        mempool_tx.commit().unwrap();
+
+        assert!(mempool.has_tx(&replacement.txid()));
+        assert!(mempool.has_tx(&txs[1].txid()));
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![(txs[0].txid(), MemPoolDropReason::ReplaceByFee)]);
+    }
+
+    #[test]
+    fn mempool_evict_unfunded_descendants_skips_mined_txs() {
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+
+        let txs = vec![
+            make_user_stacks_transfer(&privk, 0, 100, &recipient, 5000),
+            make_user_stacks_transfer(&privk, 1, 100, &recipient, 1000),
+            make_user_stacks_transfer(&privk, 2, 100, &recipient, 3000),
+        ];
+
+        // the first transfer is mined in the chain tip, leaving 4900 uSTX
+        let mut chainstate = instantiate_chainstate_with_balances(false, 0x80000000, "mempool_evict_unfunded_descendants_skips_mined_txs", vec![(addr.clone(), 10000)]);
+        let b_2 = (BurnchainHeaderHash([0x2; 32]), BlockHeaderHash([0x2; 32]));
+        {
+            let mut conn = chainstate.block_begin(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, &b_2.0, &b_2.1);
+            StacksChainState::process_transaction(&mut conn, &txs[0], false).unwrap();
+            conn.commit_block();
+        }
+
+        let chainstate_path = chainstate_path("mempool_evict_unfunded_descendants_skips_mined_txs");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let add_tx = |mempool_tx: &mut MemPoolTx, tx: &StacksTransaction| {
+            let tx_bytes = tx.serialize_to_vec();
+            let estimated_fee = tx.get_fee_rate() * (tx_bytes.len() as u64);
+            MemPoolDB::try_add_tx(mempool_tx, &b_2.0, &b_2.1, tx.txid(), tx_bytes, estimated_fee, tx.get_fee_rate(), 1, &addr, tx.get_origin_nonce(), &addr, tx.get_origin_nonce())
+        };
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for tx in txs.iter() {
+            assert_eq!(add_tx(&mut mempool_tx, tx).unwrap(), vec![]);
+        }
+
+        // the mined transfer is already paid for, so it isn't counted again
+        let replacement = make_user_stacks_transfer(&privk, 1, 200, &recipient, 1000);
+        assert_eq!(add_tx(&mut mempool_tx, &replacement).unwrap(), vec![(txs[1].txid(), MemPoolDropReason::ReplaceByFee)]);
+        MemPoolDB::evict_unfunded_descendants(&mut mempool_tx, &b_2.0, &b_2.1, &replacement).unwrap();
+        mempool_tx.commit().unwrap();
+
+        assert!(mempool.has_tx(&replacement.txid()));
+        assert!(mempool.has_tx(&txs[2].txid()));
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![(txs[1].txid(), MemPoolDropReason::ReplaceByFee)]);
+    }
+
+    #[test]
+    fn mempool_replace_by_fee_threshold() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_replace_by_fee_threshold");
+        let chainstate_path = chainstate_path("mempool_replace_by_fee_threshold");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let tip = (BurnchainHeaderHash([0x1; 32]), BlockHeaderHash([0x2; 32]));
+
+        let add_tx = |mempool_tx: &mut MemPoolTx, tx: &StacksTransaction, estimated_fee: u64| {
+            MemPoolDB::try_add_tx(mempool_tx, &tip.0, &tip.1, tx.txid(), tx.serialize_to_vec(), estimated_fee, tx.get_fee_rate(), 1, &addr, 0, &addr, 0)
+        };
+
+        let prior = make_user_stacks_transfer(&privk, 0, 1, &recipient, 1);
+        let too_low = make_user_stacks_transfer(&privk, 0, 2, &recipient, 1);
+        let just_enough = make_user_stacks_transfer(&privk, 0, 3, &recipient, 1);
+
+        let prior_fee = 1000;
+        let min_replacement_fee = MemPoolReplacePolicy::default().min_replacement_fee(prior_fee);
+
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        assert_eq!(add_tx(&mut mempool_tx, &prior, prior_fee).unwrap(), vec![]);
+
+        // one uSTX short of the threshold
+        match add_tx(&mut mempool_tx, &too_low, min_replacement_fee - 1).unwrap_err() {
+            MemPoolRejection::ReplaceByFeeTooLow(actual, expected) => {
+                assert_eq!(actual, min_replacement_fee - 1);
+                assert_eq!(expected, min_replacement_fee);
+            },
+            e => panic!("Unexpected rejection: {:?}", &e)
+        }
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &prior.txid()).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &too_low.txid()).unwrap());
+
+        // exactly at the threshold
+        assert_eq!(add_tx(&mut mempool_tx, &just_enough, min_replacement_fee).unwrap(), vec![(prior.txid(), MemPoolDropReason::ReplaceByFee)]);
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &prior.txid()).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &just_enough.txid()).unwrap());
+        mempool_tx.commit().unwrap();
+
+        // only the replaced tx is reported, and only once
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![(prior.txid(), MemPoolDropReason::ReplaceByFee)]);
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn mempool_dropped_txs_survive_reopen() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_dropped_txs_survive_reopen");
+        let chainstate_path = chainstate_path("mempool_dropped_txs_survive_reopen");
+
+        // a mempool from before dropped transactions were tracked
+        {
+            let mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+            mempool.db.execute("DROP TABLE mempool_dropped", NO_PARAMS).unwrap();
+        }
+
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let prior = make_user_stacks_transfer(&privk, 0, 10, &recipient, 1);
+        let replacement = make_user_stacks_transfer(&privk, 0, 100, &recipient, 1);
+        {
+            let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+            assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![]);
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, prior.serialize_to_vec()).unwrap();
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, replacement.serialize_to_vec()).unwrap();
+        }
+
+        // not yet drained, so still reported after a restart
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![(prior.txid(), MemPoolDropReason::ReplaceByFee)]);
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn mempool_garbage_collect_dropped_txs() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_garbage_collect_dropped_txs");
+        let chainstate_path = chainstate_path("mempool_garbage_collect_dropped_txs");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let addr = StacksAddress::from_public_keys(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, &AddressHashMode::SerializeP2PKH, 1, &vec![StacksPublicKey::from_private(&privk)]).unwrap();
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let tip = (BurnchainHeaderHash([0x1; 32]), BlockHeaderHash([0x2; 32]));
+
+        let add_tx = |mempool_tx: &mut MemPoolTx, tx: &StacksTransaction, height: u64| {
+            MemPoolDB::try_add_tx(mempool_tx, &tip.0, &tip.1, tx.txid(), tx.serialize_to_vec(), 1000 * tx.get_fee_rate(), tx.get_fee_rate(), height, &addr, tx.get_origin_nonce(), &addr, tx.get_origin_nonce())
+        };
+
+        // a replacement at height 1 and another at height 2
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for (nonce, height) in [(0, 1), (1, 2)].iter() {
+            let prior = make_user_stacks_transfer(&privk, *nonce, 1, &recipient, 1);
+            let replacement = make_user_stacks_transfer(&privk, *nonce, 10, &recipient, 1);
+            add_tx(&mut mempool_tx, &prior, *height).unwrap();
+            add_tx(&mut mempool_tx, &replacement, *height).unwrap();
+        }
+
+        // dropped transactions are forgotten along with the mempool transactions at their height
+        MemPoolDB::garbage_collect(&mut mempool_tx, 2).unwrap();
+        mempool_tx.commit().unwrap();
+
+        let dropped = mempool.drain_dropped_txs().unwrap();
+        assert_eq!(dropped, vec![(make_user_stacks_transfer(&privk, 1, 1, &recipient, 1).txid(), MemPoolDropReason::ReplaceByFee)]);
+    }
+
+    #[test]
+    fn mempool_evict_lowest_fee_rate() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_evict_lowest_fee_rate");
+        let chainstate_path = chainstate_path("mempool_evict_lowest_fee_rate");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        mempool.set_limits(MemPoolLimits { max_txs: 3, max_bytes: u64::max_value() });
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privks : Vec<_> = [SK_1, SK_2, SK_3].iter().map(|sk| StacksPrivateKey::from_hex(sk).unwrap()).collect();
+        let privk_4 = StacksPrivateKey::new();
+
+        // a low fee-rate parent with a high fee-rate child, and a medium fee-rate tx
+        let parent = make_user_stacks_transfer(&privks[0], 0, 10, &recipient, 1);
+        let child = make_user_stacks_transfer(&privks[0], 1, 100, &recipient, 1);
+        let medium = make_user_stacks_transfer(&privks[1], 0, 20, &recipient, 1);
+        for tx in [&parent, &child, &medium].iter() {
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+        }
+
+        // doesn't pay more than any evictable tx
+        let cheap = make_user_stacks_transfer(&privks[2], 0, 5, &recipient, 1);
+        match mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, cheap.serialize_to_vec()) {
+            Err(MemPoolRejection::MemPoolFull(actual, expected)) => {
+                assert_eq!(actual, 5);
+                assert_eq!(expected, 21);
+            },
+            x => panic!("Expected MemPoolFull, got {:?}", &x)
+        }
+        assert!(!mempool.has_tx(&cheap.txid()));
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap().0, 3);
+
+        // evicts the medium tx, but not the parent, since its child pays more
+        let better = make_user_stacks_transfer(&privks[2], 0, 30, &recipient, 1);
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, better.serialize_to_vec()).unwrap();
+
+        // evicts the tx that just got in
+        let best = make_user_stacks_transfer(&privk_4, 0, 200, &recipient, 1);
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, best.serialize_to_vec()).unwrap();
+
+        for tx in [&parent, &child, &best].iter() {
+            assert!(mempool.has_tx(&tx.txid()));
+        }
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap().0, 3);
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![
+            (medium.txid(), MemPoolDropReason::MemPoolFull),
+            (better.txid(), MemPoolDropReason::MemPoolFull),
+        ]);
+    }
+
+    #[test]
+    fn mempool_evict_lowest_fee_per_byte() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_evict_lowest_fee_per_byte");
+        let chainstate_path = chainstate_path("mempool_evict_lowest_fee_per_byte");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        mempool.set_limits(MemPoolLimits { max_txs: 3, max_bytes: u64::max_value() });
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privks : Vec<_> = [SK_1, SK_2, SK_3].iter().map(|sk| StacksPrivateKey::from_hex(sk).unwrap()).collect();
+        let privk_4 = StacksPrivateKey::new();
+
+        // pays the highest fee, but the lowest fee per byte
+        let big_contract = format!("{}\n;; {}", FOO_CONTRACT, "x".repeat(2000));
+        let big = make_user_contract_publish(&privks[0], 0, 2000, "big-contract", &big_contract);
+        let small_1 = make_user_stacks_transfer(&privks[1], 0, 500, &recipient, 1);
+        let small_2 = make_user_stacks_transfer(&privks[2], 0, 600, &recipient, 1);
+        for tx in [&big, &small_1, &small_2].iter() {
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+        }
+
+        let big_len = big.serialize_to_vec().len() as u64;
+        let transfer_len = small_1.serialize_to_vec().len() as u64;
+        let min_fee = 2000 * transfer_len / big_len + 1;
+        assert!(min_fee < 500);
+
+        // the new tx would be the cheapest per byte, so it's the one that doesn't get in
+        let cheap = make_user_stacks_transfer(&privk_4, 0, min_fee - 1, &recipient, 1);
+        match mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, cheap.serialize_to_vec()) {
+            Err(MemPoolRejection::MemPoolFull(actual, expected)) => {
+                assert_eq!(actual, min_fee - 1);
+                assert_eq!(expected, min_fee);
+            },
+            x => panic!("Expected MemPoolFull, got {:?}", &x)
+        }
+        assert!(!mempool.has_tx(&cheap.txid()));
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![]);
+
+        // paying just enough per byte evicts the big contract
+        let enough = make_user_stacks_transfer(&privk_4, 0, min_fee, &recipient, 1);
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, enough.serialize_to_vec()).unwrap();
+        for tx in [&small_1, &small_2, &enough].iter() {
+            assert!(mempool.has_tx(&tx.txid()));
+        }
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![(big.txid(), MemPoolDropReason::MemPoolFull)]);
+    }
+
+    #[test]
+    fn mempool_evict_nonce_chain_tail() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_evict_nonce_chain_tail");
+        let chainstate_path = chainstate_path("mempool_evict_nonce_chain_tail");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        mempool.set_limits(MemPoolLimits { max_txs: 3, max_bytes: u64::max_value() });
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privks : Vec<_> = [SK_1, SK_2, SK_3].iter().map(|sk| StacksPrivateKey::from_hex(sk).unwrap()).collect();
+
+        // the head of the chain pays the least, but evicting it would strand the rest
+        let chain : Vec<_> = (0..3).map(|nonce| make_user_stacks_transfer(&privks[0], nonce, 10 * (nonce + 1), &recipient, 1)).collect();
+        for tx in chain.iter() {
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+        }
+
+        let other_1 = make_user_stacks_transfer(&privks[1], 0, 100, &recipient, 1);
+        let other_2 = make_user_stacks_transfer(&privks[2], 0, 100, &recipient, 1);
+        for tx in [&other_1, &other_2].iter() {
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+        }
+
+        for tx in [&chain[0], &other_1, &other_2].iter() {
+            assert!(mempool.has_tx(&tx.txid()));
+        }
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![
+            (chain[2].txid(), MemPoolDropReason::MemPoolFull),
+            (chain[1].txid(), MemPoolDropReason::MemPoolFull),
+        ]);
+    }
+
+    #[test]
+    fn mempool_evict_over_byte_limit() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_evict_over_byte_limit");
+        let chainstate_path = chainstate_path("mempool_evict_over_byte_limit");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privks : Vec<_> = [SK_1, SK_2, SK_3].iter().map(|sk| StacksPrivateKey::from_hex(sk).unwrap()).collect();
+        let privk_4 = StacksPrivateKey::new();
+
+        let txs : Vec<_> = privks.iter().enumerate().map(|(i, privk)| make_user_stacks_transfer(privk, 0, 10 * (i as u64 + 1), &recipient, 1)).collect();
+        let transfer_len = txs[0].serialize_to_vec().len() as u64;
+
+        // room for two and a half transfers
+        mempool.set_limits(MemPoolLimits { max_txs: u64::max_value(), max_bytes: 5 * transfer_len / 2 });
+
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, txs[0].serialize_to_vec()).unwrap();
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, txs[1].serialize_to_vec()).unwrap();
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap(), (2, 2 * transfer_len));
+
+        // the third one doesn't fit, so the cheapest one goes
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, txs[2].serialize_to_vec()).unwrap();
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap(), (2, 2 * transfer_len));
+        assert!(!mempool.has_tx(&txs[0].txid()));
+
+        // a tx bigger than the whole mempool never fits
+        let big_contract = format!("{}\n;; {}", FOO_CONTRACT, "x".repeat(3 * transfer_len as usize));
+        let big = make_user_contract_publish(&privk_4, 0, 1000000, "big-contract", &big_contract);
+        match mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, big.serialize_to_vec()) {
+            Err(MemPoolRejection::DoesNotFitInMemPool(max_txs, max_bytes)) => {
+                assert_eq!(max_txs, u64::max_value());
+                assert_eq!(max_bytes, 5 * transfer_len / 2);
+            },
+            x => panic!("Expected DoesNotFitInMemPool, got {:?}", &x)
+        }
+        assert!(!mempool.has_tx(&big.txid()));
+
+        assert_eq!(mempool.drain_dropped_txs().unwrap(), vec![(txs[0].txid(), MemPoolDropReason::MemPoolFull)]);
+    }
+
+    #[test]
+    fn mempool_evictable_tx_uses_indexes() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_evictable_tx_uses_indexes");
+        let chainstate_path = chainstate_path("mempool_evictable_tx_uses_indexes");
+        let mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let mut stmt = mempool.conn().prepare(&format!("EXPLAIN QUERY PLAN {}", MEMPOOL_EVICTABLE_TX_SQL)).unwrap();
+        let mut rows = stmt.query(&[&Txid([0; 32]) as &dyn ToSql]).unwrap();
+        let mut plan = vec![];
+        while let Some(row_res) = rows.next() {
+            let detail : String = row_res.unwrap().get(3);
+            plan.push(detail);
+        }
+
+        // the candidates come off the fee index in order, and each dependents check is an index
+        // seek rather than a table scan
+        assert!(plan.iter().any(|detail| detail.contains("USING INDEX by_fee_per_byte")), "{:?}", &plan);
+        let searches = plan.iter().filter(|detail| detail.starts_with("SEARCH")).count();
+        assert_eq!(searches, 4, "{:?}", &plan);
+        assert!(!plan.iter().any(|detail| detail.contains("TEMP B-TREE")), "{:?}", &plan);
+        assert_eq!(plan.iter().filter(|detail| detail.starts_with("SCAN")).count(), 1, "{:?}", &plan);
+    }
+
+    #[test]
+    fn mempool_size_tracks_inserts_and_deletes() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_size_tracks_inserts_and_deletes");
+        let chainstate_path = chainstate_path("mempool_size_tracks_inserts_and_deletes");
+
+        fn counted_size(mempool: &MemPoolDB) -> (u64, u64) {
+            mempool.conn().query_row("SELECT COUNT(*), IFNULL(SUM(length), 0) FROM mempool", NO_PARAMS, |row| {
+                let num_txs : i64 = row.get(0);
+                let num_bytes : i64 = row.get(1);
+                (num_txs as u64, num_bytes as u64)
+            }).unwrap()
+        }
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privks : Vec<_> = [SK_1, SK_2, SK_3].iter().map(|sk| StacksPrivateKey::from_hex(sk).unwrap()).collect();
+        let txs : Vec<_> = privks.iter().enumerate().map(|(i, privk)| make_user_stacks_transfer(privk, 0, 10 * (i as u64 + 1), &recipient, 1)).collect();
+        let transfer_len = txs[0].serialize_to_vec().len() as u64;
+
+        // a mempool from before the fee per byte and the size were stored
+        {
+            let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+            for tx in txs.iter() {
+                mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+            }
+            mempool.db.execute_batch("
+                DROP TRIGGER mempool_size_insert;
+                DROP TRIGGER mempool_size_delete;
+                DROP TABLE mempool_size;
+                DROP INDEX by_fee_per_byte;
+                CREATE TABLE old_mempool AS SELECT txid, origin_address, origin_nonce, sponsor_address, sponsor_nonce, estimated_fee,
+                    fee_rate, length, burn_header_hash, block_header_hash, height, accept_time, tx FROM mempool;
+                DROP TABLE mempool;
+                ALTER TABLE old_mempool RENAME TO mempool;").unwrap();
+        }
+
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap(), (3, 3 * transfer_len));
+        let min_fee_per_byte : f64 = mempool.conn().query_row("SELECT MIN(fee_per_byte) FROM mempool", NO_PARAMS, |row| row.get(0)).unwrap();
+        assert_eq!(min_fee_per_byte, 10.0 / (transfer_len as f64));
+
+        // replace-by-fee
+        let replacement = make_user_stacks_transfer(&privks[0], 0, 100, &recipient, 1);
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, replacement.serialize_to_vec()).unwrap();
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap(), counted_size(&mempool));
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap().0, 3);
+
+        // eviction
+        mempool.set_limits(MemPoolLimits { max_txs: 3, max_bytes: u64::max_value() });
+        let privk_4 = StacksPrivateKey::new();
+        let big_contract = format!("{}\n;; {}", FOO_CONTRACT, "x".repeat(500));
+        let big = make_user_contract_publish(&privk_4, 0, 100000, "big-contract", &big_contract);
+        mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, big.serialize_to_vec()).unwrap();
+        assert!(!mempool.has_tx(&txs[1].txid()));
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap(), counted_size(&mempool));
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap().0, 3);
+
+        // garbage collection
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::garbage_collect(&mut mempool_tx, u32::max_value() as u64).unwrap();
+        mempool_tx.commit().unwrap();
+        assert_eq!(MemPoolDB::get_size(mempool.conn()).unwrap(), (0, 0));
+        assert_eq!(counted_size(&mempool), (0, 0));
+    }
+
+    #[test]
+    fn mempool_get_txid_page() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_get_txid_page");
+        let chainstate_path = chainstate_path("mempool_get_txid_page");
+        let mut mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap();
+
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+        let mut txids = vec![];
+        for nonce in 0..5 {
+            let tx = make_user_stacks_transfer(&privk, nonce, 100, &recipient, 1);
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+            txids.push(tx.txid());
+        }
+        txids.sort_by(|t1, t2| t1.as_bytes().cmp(t2.as_bytes()));
+
+        let first_page = MemPoolDB::get_txid_page(mempool.conn(), &Txid([0u8; 32]), 3).unwrap();
+        assert_eq!(first_page, txids[0..3].to_vec());
+
+        let second_page = MemPoolDB::get_txid_page(mempool.conn(), &first_page[2], 3).unwrap();
+        assert_eq!(second_page, txids[3..5].to_vec());
+
+        let last_page = MemPoolDB::get_txid_page(mempool.conn(), &second_page[1], 3).unwrap();
+        assert_eq!(last_page, vec![]);
+    }
+
     #[test]
     fn mempool_do_not_replace_tx() {
         let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_do_not_replace_tx");
@@ -871,6 +1906,7 @@ mod tests {
                                                                 &BurnchainHeaderHash::sentinel(),
                                                                 &BlockHeaderHash::sentinel(),
                                                                 &b_1.0,
+                                                                1,
                                                                 &b_1.1);
             c_tx.commit_block();
         }
@@ -881,6 +1917,7 @@ mod tests {
                                                                 &BurnchainHeaderHash::sentinel(),
                                                                 &BlockHeaderHash::sentinel(),
                                                                 &b_3.0,
+                                                                1,
                                                                 &b_3.1);
             c_tx.commit_block();
         }
@@ -891,6 +1928,7 @@ mod tests {
                                                                 &b_1.0,
                                                                 &b_1.1,
                                                                 &b_2.0,
+                                                                2,
                                                                 &b_2.1);
             c_tx.commit_block();
         }
@@ -944,7 +1982,7 @@ mod tests {
             MemPoolDB::try_add_tx(&mut mempool_tx, &b_2.0, &b_2.1,
                                   txid, tx_bytes, estimated_fee, tx.get_fee_rate(), height, &origin_address, origin_nonce, &sponsor_address, sponsor_nonce).unwrap_err();
         assert!(match err_resp {
-            MemPoolRejection::ConflictingNonceInMempool => true,
+            MemPoolRejection::ReplaceByFeeTooLow(..) => true,
             _ => false,
         });
 
@@ -1009,12 +2047,35 @@ mod tests {
             assert_eq!(tx_info.metadata.block_header_hash, BlockHeaderHash([0x2; 32]));
             assert_eq!(tx_info.metadata.block_height, height);
 
-            // test replace-by-fee with a higher fee
+            // test replace-by-fee with a higher fee, but not high enough
             let old_txid = txid;
 
             tx.set_fee_rate(124);
             assert!(txid != tx.txid());
 
+            let txid = tx.txid();
+            let mut tx_bytes = vec![];
+            tx.consensus_serialize(&mut tx_bytes).unwrap();
+            let estimated_fee = tx.get_fee_rate() * len;        // TODO: use clarity analysis data to make this estimate
+            let min_replacement_fee = MemPoolReplacePolicy::default().min_replacement_fee(tx_info.metadata.estimated_fee);
+            assert!(estimated_fee < min_replacement_fee);
+
+            match MemPoolDB::try_add_tx(&mut mempool_tx, &BurnchainHeaderHash([0x1; 32]), &BlockHeaderHash([0x2; 32]), txid, tx_bytes, estimated_fee, tx.get_fee_rate(), height, &origin_address, origin_nonce, &sponsor_address, sponsor_nonce).unwrap_err() {
+                MemPoolRejection::ReplaceByFeeTooLow(actual, expected) => {
+                    assert_eq!(actual, estimated_fee);
+                    assert_eq!(expected, min_replacement_fee);
+                },
+                e => panic!("Unexpected rejection: {:?}", &e)
+            }
+
+            // was NOT replaced
+            assert!(MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+            assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+            // test replace-by-fee with a high enough fee
+            tx.set_fee_rate(136);
+            assert!(txid != tx.txid());
+
             let txid = tx.txid();
             let mut tx_bytes = vec![];
             tx.consensus_serialize(&mut tx_bytes).unwrap();
@@ -1045,7 +2106,7 @@ mod tests {
             assert_eq!(tx_info.tx, expected_tx);
             assert_eq!(tx_info.metadata.len, len);
             assert_eq!(tx_info.metadata.estimated_fee, estimated_fee);
-            assert_eq!(tx_info.metadata.fee_rate, 124);
+            assert_eq!(tx_info.metadata.fee_rate, 136);
             assert_eq!(tx_info.metadata.origin_address, origin_address);
             assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
             assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
@@ -1057,7 +2118,7 @@ mod tests {
             // test replace-by-fee with a lower fee
             let old_txid = txid;
             
-            tx.set_fee_rate(122);
+            tx.set_fee_rate(135);
             assert!(txid != tx.txid());
             
             let txid = tx.txid();
@@ -1068,7 +2129,7 @@ mod tests {
     
             assert!(
                 match MemPoolDB::try_add_tx(&mut mempool_tx, &BurnchainHeaderHash([0x1; 32]), &BlockHeaderHash([0x2; 32]), txid, tx_bytes, estimated_fee, tx.get_fee_rate(), height, &origin_address, origin_nonce, &sponsor_address, sponsor_nonce).unwrap_err() {
-                    MemPoolRejection::ConflictingNonceInMempool => true,
+                    MemPoolRejection::ReplaceByFeeTooLow(..) => true,
                     _ => false
                 });
 
@@ -35,6 +35,7 @@ extern crate ed25519_dalek;
 extern crate sha2;
 extern crate sha3;
 extern crate ripemd160;
+extern crate ring;
 extern crate regex;
 extern crate time;
 extern crate mio;
@@ -68,6 +69,9 @@ pub mod vm;
 
 pub mod clarity;
 
+#[cfg(feature = "developer-mode")]
+pub mod lsp;
+
 pub mod monitoring;
 
 // set via _compile-time_ envars
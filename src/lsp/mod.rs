@@ -0,0 +1,571 @@
+pub mod protocol;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as JsonValue;
+use url::Url;
+
+use vm::analysis;
+use vm::analysis::ContractAnalysis;
+use vm::analysis::linter::Linter;
+use vm::ast::build_ast;
+use vm::costs::LimitedCostTracker;
+use vm::database::MemoryBackingStore;
+use vm::diagnostic::Diagnostic;
+use vm::docs::{make_all_api_reference, ReferenceAPIs};
+use vm::functions::define::DefineFunctions;
+use vm::representations::{SymbolicExpression, SymbolicExpressionType};
+use vm::types::{QualifiedContractIdentifier, StandardPrincipalData, PrincipalData, Value};
+
+use self::protocol::*;
+
+/// Workspace directories that are never searched for contracts
+const SKIPPED_DIRECTORIES: &[&str] = &["target", "node_modules"];
+
+/// What the server learned about one document the last time it was analyzed
+struct DocumentAnalysis {
+    contract_identifier: QualifiedContractIdentifier,
+    /// empty if the document does not parse
+    expressions: Vec<SymbolicExpression>,
+    /// None if the document does not parse or type check
+    contract_analysis: Option<ContractAnalysis>,
+    diagnostics: Vec<JsonValue>,
+    /// every contract the document depends on, directly or not
+    dependencies: HashSet<QualifiedContractIdentifier>,
+}
+
+/// A Clarity language server, speaking LSP over stdio.  Every `.clar` file in the workspace
+///   is a contract named after its file, all published by the same (transient) principal, so
+///   that `.other-contract` references resolve to the other files in the workspace.
+pub struct LanguageServer {
+    root: Option<PathBuf>,
+    /// the text of every open document, by URI
+    documents: HashMap<String, String>,
+    /// the dependencies of every open document, as of its last analysis
+    dependencies: HashMap<String, HashSet<QualifiedContractIdentifier>>,
+    /// the URI of every contract saved in the workspace, by name
+    workspace_index: HashMap<String, String>,
+    reference: ReferenceAPIs,
+    exited: bool,
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic) -> JsonValue {
+    let range = match diagnostic.spans.first() {
+        Some(span) => span_to_range(span),
+        None => line_range(0)
+    };
+    let message = match diagnostic.suggestion {
+        Some(ref suggestion) => format!("{}\n{}", diagnostic.message, suggestion),
+        None => diagnostic.message.clone()
+    };
+    json!({ "range": range, "severity": SEVERITY_ERROR, "source": "clarity", "message": message })
+}
+
+/// Every contract that these expressions call, or use a trait from
+fn contract_dependencies(expressions: &[SymbolicExpression], dependencies: &mut Vec<QualifiedContractIdentifier>) {
+    for expression in expressions.iter() {
+        let dependency = match expression.expr {
+            SymbolicExpressionType::List(ref list) => {
+                contract_dependencies(list, dependencies);
+                continue;
+            },
+            SymbolicExpressionType::LiteralValue(Value::Principal(PrincipalData::Contract(ref contract_identifier))) |
+            SymbolicExpressionType::AtomValue(Value::Principal(PrincipalData::Contract(ref contract_identifier))) => contract_identifier,
+            SymbolicExpressionType::Field(ref trait_identifier) => &trait_identifier.contract_identifier,
+            _ => continue
+        };
+        if !dependencies.contains(dependency) {
+            dependencies.push(dependency.clone());
+        }
+    }
+}
+
+/// The expression that names what a `define-*` form defines
+fn defined_name(expression: &SymbolicExpression) -> Option<&SymbolicExpression> {
+    let (define_type, args) = DefineFunctions::try_parse(expression)?;
+    match define_type {
+        DefineFunctions::PrivateFunction | DefineFunctions::PublicFunction | DefineFunctions::ReadOnlyFunction =>
+            args.first()?.match_list()?.first(),
+        DefineFunctions::ImplTrait => None,
+        _ => args.first()
+    }
+}
+
+/// The expressions containing a position, outermost first
+fn expressions_at(expressions: &[SymbolicExpression], line: u32, character: u32) -> Vec<&SymbolicExpression> {
+    let mut path = vec![];
+    let mut candidates = expressions;
+    while let Some(expression) = candidates.iter().find(|expression| span_contains(&expression.span, line, character)) {
+        path.push(expression);
+        candidates = match expression.match_list() {
+            Some(list) => list,
+            None => break
+        };
+    }
+    path
+}
+
+fn path_to_uri(path: &Path) -> Option<String> {
+    Url::from_file_path(path).ok().map(|uri| uri.to_string())
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+fn find_contracts(directory: &Path, found: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_str()) {
+                find_contracts(&path, found);
+            }
+        } else if path.extension().map(|extension| extension == "clar").unwrap_or(false) {
+            found.push(path);
+        }
+    }
+}
+
+impl Default for LanguageServer {
+    fn default() -> LanguageServer {
+        LanguageServer::new()
+    }
+}
+
+impl LanguageServer {
+    pub fn new() -> LanguageServer {
+        LanguageServer {
+            root: None,
+            documents: HashMap::new(),
+            dependencies: HashMap::new(),
+            workspace_index: HashMap::new(),
+            reference: make_all_api_reference(),
+            exited: false,
+        }
+    }
+
+    /// Serve requests until the client sends `exit` or closes the input
+    pub fn run<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> io::Result<()> {
+        loop {
+            let outgoing = match read_message(input) {
+                Ok(Some(message)) => self.handle(message),
+                Ok(None) => break,
+                Err(ReadError::Malformed(message)) => vec![error_response(JsonValue::Null, PARSE_ERROR, &message)],
+                Err(ReadError::Io(e)) => return Err(e)
+            };
+            for outgoing in outgoing.iter() {
+                write_message(output, outgoing)?;
+            }
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one message from the client, returning the messages to send back
+    pub fn handle(&mut self, message: JsonValue) -> Vec<JsonValue> {
+        let method = message["method"].as_str().unwrap_or("").to_string();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method.as_str() {
+            "initialize" => {
+                self.root = params["rootUri"].as_str()
+                    .and_then(uri_to_path)
+                    .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+                self.index_workspace();
+                Some(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "hoverProvider": true,
+                        "completionProvider": {},
+                        "definitionProvider": true
+                    },
+                    "serverInfo": { "name": "clarity-lsp" }
+                }))
+            },
+            "shutdown" => Some(JsonValue::Null),
+            "exit" => {
+                self.exited = true;
+                return vec![];
+            },
+            "textDocument/didOpen" => {
+                return match (params["textDocument"]["uri"].as_str(), params["textDocument"]["text"].as_str()) {
+                    (Some(uri), Some(text)) => {
+                        self.documents.insert(uri.to_string(), text.to_string());
+                        self.publish_diagnostics(uri)
+                    },
+                    _ => vec![]
+                };
+            },
+            "textDocument/didChange" => {
+                let text = params["contentChanges"].as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                return match (params["textDocument"]["uri"].as_str(), text) {
+                    (Some(uri), Some(text)) => {
+                        self.documents.insert(uri.to_string(), text.to_string());
+                        self.publish_diagnostics(uri)
+                    },
+                    _ => vec![]
+                };
+            },
+            "textDocument/didSave" => {
+                let uri = match params["textDocument"]["uri"].as_str() {
+                    Some(uri) => uri,
+                    None => return vec![]
+                };
+                if let Some(text) = params["text"].as_str() {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                // the document may be a new file in the workspace
+                self.index_workspace();
+                return self.publish_diagnostics(uri);
+            },
+            "textDocument/didClose" => {
+                let uri = match params["textDocument"]["uri"].as_str() {
+                    Some(uri) => uri,
+                    None => return vec![]
+                };
+                self.documents.remove(uri);
+                self.dependencies.remove(uri);
+                let mut messages = vec![notification("textDocument/publishDiagnostics",
+                                                     json!({ "uri": uri, "diagnostics": [] }))];
+                // its dependents now see the saved contract instead
+                messages.extend(self.publish_diagnostics(uri));
+                return messages;
+            },
+            "workspace/didChangeWatchedFiles" => {
+                self.index_workspace();
+                return vec![];
+            },
+            "textDocument/hover" => self.text_document_position(params).map(|(uri, line, character)| self.hover(&uri, line, character)),
+            "textDocument/completion" => self.text_document_position(params).map(|(uri, _, _)| self.completion(&uri)),
+            "textDocument/definition" => self.text_document_position(params).map(|(uri, line, character)| self.definition(&uri, line, character)),
+            _ => {
+                return match id {
+                    Some(id) => vec![error_response(id, METHOD_NOT_FOUND, &format!("Unsupported method: {}", method))],
+                    // unhandled notifications are ignored
+                    None => vec![]
+                };
+            }
+        };
+
+        match (id, result) {
+            (Some(id), Some(result)) => vec![response(id, result)],
+            (Some(id), None) => vec![error_response(id, INVALID_PARAMS, "Expected a text document and position")],
+            (None, _) => vec![]
+        }
+    }
+
+    fn text_document_position(&self, params: &JsonValue) -> Option<(String, u32, u32)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let line = params["position"]["line"].as_u64()?;
+        let character = params["position"]["character"].as_u64()?;
+        Some((uri.to_string(), line as u32, character as u32))
+    }
+
+    /// Find the contracts saved in the workspace.  The index is rebuilt when a document is
+    ///   saved, or the client reports that files changed.
+    fn index_workspace(&mut self) {
+        self.workspace_index.clear();
+        if let Some(ref root) = self.root {
+            let mut paths = vec![];
+            find_contracts(root, &mut paths);
+            for path in paths.iter() {
+                if let (Some(uri), Some(stem)) = (path_to_uri(path), path.file_stem()) {
+                    self.workspace_index.insert(stem.to_string_lossy().to_string(), uri);
+                }
+            }
+        }
+    }
+
+    fn source(&self, uri: &str) -> Option<String> {
+        match self.documents.get(uri) {
+            Some(text) => Some(text.clone()),
+            None => fs::read_to_string(uri_to_path(uri)?).ok()
+        }
+    }
+
+    fn contract_identifier(uri: &str) -> QualifiedContractIdentifier {
+        uri_to_path(uri).as_ref()
+            .and_then(|path| path.file_stem())
+            .and_then(|stem| QualifiedContractIdentifier::local(&stem.to_string_lossy()).ok())
+            .unwrap_or_else(QualifiedContractIdentifier::transient)
+    }
+
+    fn contract_uri(&self, contract_identifier: &QualifiedContractIdentifier) -> Option<String> {
+        if contract_identifier.issuer != StandardPrincipalData::transient() {
+            return None;
+        }
+        // open documents are used even if they are not saved in the workspace
+        let open_document = self.documents.keys()
+            .find(|uri| LanguageServer::contract_identifier(uri) == *contract_identifier);
+        match open_document {
+            Some(uri) => Some(uri.clone()),
+            None => self.workspace_index.get(contract_identifier.name.as_str()).cloned()
+        }
+    }
+
+    /// Analyze a contract from the workspace and save its analysis, after the contracts it
+    ///   depends on.  Failures are left for the contract's own diagnostics to report.
+    fn analyze_dependency(&self, store: &mut MemoryBackingStore, contract_identifier: &QualifiedContractIdentifier,
+                          visited: &mut HashSet<QualifiedContractIdentifier>) {
+        if !visited.insert(contract_identifier.clone()) {
+            return;
+        }
+        let source = match self.contract_uri(contract_identifier).and_then(|uri| self.source(&uri)) {
+            Some(source) => source,
+            None => return
+        };
+        let mut expressions = match build_ast(contract_identifier, &source, &mut ()) {
+            Ok(ast) => ast.expressions,
+            Err(_) => return
+        };
+
+        let mut dependencies = vec![];
+        contract_dependencies(&expressions, &mut dependencies);
+        for dependency in dependencies.iter() {
+            self.analyze_dependency(store, dependency, visited);
+        }
+
+        {
+            let mut db = store.as_clarity_db();
+            db.begin();
+            if db.insert_contract_hash(contract_identifier, &source).is_err() {
+                db.roll_back();
+                return;
+            }
+            db.commit();
+        }
+        let mut db = store.as_analysis_db();
+        let _ = analysis::run_analysis(contract_identifier, &mut expressions, &mut db, true,
+                                       LimitedCostTracker::new_max_limit());
+    }
+
+    fn analyze(&self, uri: &str) -> DocumentAnalysis {
+        let contract_identifier = LanguageServer::contract_identifier(uri);
+        let source = self.source(uri).unwrap_or_default();
+
+        let expressions = match build_ast(&contract_identifier, &source, &mut ()) {
+            Ok(ast) => ast.expressions,
+            Err(e) => return DocumentAnalysis {
+                contract_identifier,
+                expressions: vec![],
+                contract_analysis: None,
+                diagnostics: vec![diagnostic_to_json(&e.diagnostic)],
+                dependencies: HashSet::new()
+            }
+        };
+
+        let mut store = MemoryBackingStore::new();
+        let mut dependencies = vec![];
+        contract_dependencies(&expressions, &mut dependencies);
+        let mut visited = HashSet::new();
+        visited.insert(contract_identifier.clone());
+        for dependency in dependencies.iter() {
+            self.analyze_dependency(&mut store, dependency, &mut visited);
+        }
+
+        let result = {
+            let mut db = store.as_analysis_db();
+            analysis::run_analysis_all_errors(&contract_identifier, &expressions, &mut db,
+                                              LimitedCostTracker::new_max_limit())
+        };
+
+        let (contract_analysis, diagnostics) = match result {
+            Ok(contract_analysis) => {
+                let warnings = Linter::run(&contract_analysis).iter()
+                    .map(|warning| json!({
+                        "range": line_range(warning.line.saturating_sub(1)),
+                        "severity": SEVERITY_WARNING,
+                        "source": "clarity",
+                        "code": warning.kind.get_name(),
+                        "message": warning.message
+                    }))
+                    .collect();
+                (Some(contract_analysis), warnings)
+            },
+            Err(errors) => (None, errors.iter().map(diagnostic_to_json).collect())
+        };
+
+        visited.remove(&contract_identifier);
+        DocumentAnalysis { contract_identifier, expressions, contract_analysis, diagnostics, dependencies: visited }
+    }
+
+    /// Re-analyze a changed document, and the open documents that depend on it
+    fn publish_diagnostics(&mut self, changed_uri: &str) -> Vec<JsonValue> {
+        let changed = LanguageServer::contract_identifier(changed_uri);
+        let mut uris: Vec<_> = self.documents.keys()
+            .filter(|uri| uri.as_str() == changed_uri ||
+                    self.dependencies.get(uri.as_str()).map(|dependencies| dependencies.contains(&changed)).unwrap_or(false))
+            .cloned()
+            .collect();
+        uris.sort();
+
+        let mut messages = vec![];
+        for uri in uris.into_iter() {
+            let analysis = self.analyze(&uri);
+            messages.push(notification("textDocument/publishDiagnostics",
+                                       json!({ "uri": uri, "diagnostics": analysis.diagnostics })));
+            self.dependencies.insert(uri, analysis.dependencies);
+        }
+        messages
+    }
+
+    fn hover(&self, uri: &str, line: u32, character: u32) -> JsonValue {
+        let analysis = self.analyze(uri);
+        let path = expressions_at(&analysis.expressions, line, character);
+        let expression = match path.last() {
+            Some(expression) => expression,
+            None => return JsonValue::Null
+        };
+
+        let mut sections = vec![];
+        if let Some(name) = expression.match_atom() {
+            if let Some(function) = self.reference.functions.iter().find(|function| function.name == name.as_str()) {
+                sections.push(format!("```clarity\n{}\n```\n{}", function.signature, function.description));
+            } else if let Some(keyword) = self.reference.keywords.iter().find(|keyword| keyword.name == name.as_str()) {
+                sections.push(format!("```clarity\n{}: {}\n```\n{}", keyword.name, keyword.output_type, keyword.description));
+            }
+        }
+        let inferred_type = analysis.contract_analysis.as_ref()
+            .and_then(|contract_analysis| contract_analysis.type_map.as_ref())
+            .and_then(|type_map| type_map.get_type(expression));
+        if let Some(inferred_type) = inferred_type {
+            sections.push(format!("```clarity\n{}\n```", inferred_type));
+        }
+
+        if sections.is_empty() {
+            return JsonValue::Null;
+        }
+        json!({
+            "contents": { "kind": "markdown", "value": sections.join("\n\n") },
+            "range": span_to_range(&expression.span)
+        })
+    }
+
+    fn completion(&self, uri: &str) -> JsonValue {
+        let mut items = vec![];
+        for function in self.reference.functions.iter() {
+            items.push(json!({
+                "label": function.name,
+                "kind": COMPLETION_FUNCTION,
+                "detail": function.signature,
+                "documentation": function.description
+            }));
+        }
+        for keyword in self.reference.keywords.iter() {
+            items.push(json!({
+                "label": keyword.name,
+                "kind": COMPLETION_KEYWORD,
+                "detail": keyword.output_type,
+                "documentation": keyword.description
+            }));
+        }
+
+        let contract_identifier = LanguageServer::contract_identifier(uri);
+        let expressions = self.source(uri)
+            .and_then(|source| build_ast(&contract_identifier, &source, &mut ()).ok())
+            .map(|ast| ast.expressions)
+            .unwrap_or_default();
+        for expression in expressions.iter() {
+            let (define_type, _) = match DefineFunctions::try_parse(expression) {
+                Some(parsed) => parsed,
+                None => continue
+            };
+            let name = match defined_name(expression).and_then(|name| name.match_atom()) {
+                Some(name) => name,
+                None => continue
+            };
+            let kind = match define_type {
+                DefineFunctions::PrivateFunction | DefineFunctions::PublicFunction | DefineFunctions::ReadOnlyFunction => COMPLETION_FUNCTION,
+                DefineFunctions::Constant => COMPLETION_CONSTANT,
+                DefineFunctions::PersistedVariable => COMPLETION_VARIABLE,
+                DefineFunctions::Map => COMPLETION_STRUCT,
+                DefineFunctions::FungibleToken | DefineFunctions::NonFungibleToken => COMPLETION_VALUE,
+                DefineFunctions::Trait | DefineFunctions::UseTrait | DefineFunctions::ImplTrait => COMPLETION_INTERFACE,
+            };
+            items.push(json!({ "label": name.as_str(), "kind": kind, "detail": define_type.get_name() }));
+        }
+
+        json!({ "isIncomplete": false, "items": items })
+    }
+
+    /// The location of the definition of `name` in a contract
+    fn find_definition(&self, uri: &str, contract_identifier: &QualifiedContractIdentifier, name: &str) -> Option<JsonValue> {
+        let source = self.source(uri)?;
+        let expressions = build_ast(contract_identifier, &source, &mut ()).ok()?.expressions;
+        let name_expression = expressions.iter()
+            .filter_map(defined_name)
+            .find(|defined| defined.match_atom().map(|defined| defined.as_str() == name).unwrap_or(false))?;
+        Some(json!({ "uri": uri, "range": span_to_range(&name_expression.span) }))
+    }
+
+    fn definition(&self, uri: &str, line: u32, character: u32) -> JsonValue {
+        let analysis = self.analyze(uri);
+        let path = expressions_at(&analysis.expressions, line, character);
+        let expression = match path.last() {
+            Some(expression) => expression,
+            None => return JsonValue::Null
+        };
+
+        // the contract a definition is in, and its URI
+        let in_contract = |contract_identifier: &QualifiedContractIdentifier| {
+            if contract_identifier == &analysis.contract_identifier {
+                Some(uri.to_string())
+            } else {
+                self.contract_uri(contract_identifier)
+            }
+        };
+
+        let location = match expression.expr {
+            SymbolicExpressionType::Atom(ref name) => {
+                // in (contract-call? .contract function ...), the function is in the other contract
+                let called_contract = path.iter().rev().nth(1)
+                    .and_then(|parent| parent.match_list())
+                    .and_then(|list| match list {
+                        [function, contract, called, ..] if function.match_atom().map(|f| f.as_str() == "contract-call?").unwrap_or(false)
+                            && called.id == expression.id => {
+                            match contract.expr {
+                                SymbolicExpressionType::LiteralValue(Value::Principal(PrincipalData::Contract(ref contract_identifier))) =>
+                                    Some(contract_identifier.clone()),
+                                _ => None
+                            }
+                        },
+                        _ => None
+                    });
+                let contract_identifier = called_contract.unwrap_or_else(|| analysis.contract_identifier.clone());
+                in_contract(&contract_identifier)
+                    .and_then(|contract_uri| self.find_definition(&contract_uri, &contract_identifier, name))
+            },
+            SymbolicExpressionType::TraitReference(ref name, _) => {
+                self.find_definition(uri, &analysis.contract_identifier, name)
+            },
+            SymbolicExpressionType::Field(ref trait_identifier) => {
+                in_contract(&trait_identifier.contract_identifier)
+                    .and_then(|contract_uri| self.find_definition(&contract_uri, &trait_identifier.contract_identifier, &trait_identifier.name))
+            },
+            SymbolicExpressionType::LiteralValue(Value::Principal(PrincipalData::Contract(ref contract_identifier))) => {
+                in_contract(contract_identifier)
+                    .map(|contract_uri| json!({ "uri": contract_uri, "range": line_range(0) }))
+            },
+            _ => None
+        };
+
+        location.unwrap_or(JsonValue::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,189 @@
+use std::io;
+use std::io::{BufRead, Write};
+
+use serde_json::Value as JsonValue;
+
+use vm::representations::Span;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+pub const SEVERITY_ERROR: u32 = 1;
+pub const SEVERITY_WARNING: u32 = 2;
+
+pub const COMPLETION_FUNCTION: u32 = 3;
+pub const COMPLETION_VARIABLE: u32 = 6;
+pub const COMPLETION_INTERFACE: u32 = 8;
+pub const COMPLETION_VALUE: u32 = 12;
+pub const COMPLETION_KEYWORD: u32 = 14;
+pub const COMPLETION_CONSTANT: u32 = 21;
+pub const COMPLETION_STRUCT: u32 = 22;
+
+/// The largest message body the server will read.  Larger messages are skipped.
+pub const MAX_CONTENT_LENGTH: usize = 32 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum ReadError {
+    /// The input could not be read, and the server cannot go on
+    Io(io::Error),
+    /// The message could not be parsed, but the input is still usable
+    Malformed(String),
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+/// Read one JSON-RPC message, framed by a `Content-Length` header.  Returns None once the
+///   input is exhausted.  A message that cannot be parsed is consumed and reported as
+///   `ReadError::Malformed`, so that the caller can answer it and keep reading.
+pub fn read_message<R: BufRead>(input: &mut R) -> Result<Option<JsonValue>, ReadError> {
+    let mut content_length = None;
+    let mut bad_header = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let mut header = header.trim_end();
+        // a body that was skipped runs into the next message's header
+        if let Some(ix) = header.to_ascii_lowercase().rfind("content-length:") {
+            header = &header[ix..];
+        }
+        if header.is_empty() {
+            if content_length.is_some() || bad_header.is_some() {
+                break;
+            }
+            continue;
+        }
+        let mut parts = header.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let value = parts.next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            match value.parse::<usize>() {
+                Ok(length) => content_length = Some(length),
+                Err(e) => bad_header = Some(format!("Invalid Content-Length '{}': {}", value, e))
+            }
+        }
+    }
+
+    if let Some(message) = bad_header {
+        // without a length, the body cannot be skipped.  It is read as a header, and the
+        //   next message is found by its Content-Length.
+        return Err(ReadError::Malformed(message));
+    }
+
+    let content_length = content_length.unwrap_or(0);
+    if content_length > MAX_CONTENT_LENGTH {
+        io::copy(&mut io::Read::take(&mut *input, content_length as u64), &mut io::sink())?;
+        return Err(ReadError::Malformed(format!("Message of {} bytes exceeds the limit of {} bytes", content_length, MAX_CONTENT_LENGTH)));
+    }
+
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| ReadError::Malformed(format!("Invalid JSON: {}", e)))
+}
+
+pub fn write_message<W: Write>(output: &mut W, message: &JsonValue) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+pub fn response(id: JsonValue, result: JsonValue) -> JsonValue {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: JsonValue, code: i64, message: &str) -> JsonValue {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub fn notification(method: &str, params: JsonValue) -> JsonValue {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// LSP positions are zero-based, and ranges exclude their end.  Clarity spans are one-based,
+///   and include their end.
+pub fn span_to_range(span: &Span) -> JsonValue {
+    json!({
+        "start": { "line": span.start_line.saturating_sub(1), "character": span.start_column.saturating_sub(1) },
+        "end": { "line": span.end_line.saturating_sub(1), "character": span.end_column }
+    })
+}
+
+pub fn span_contains(span: &Span, line: u32, character: u32) -> bool {
+    let position = (line + 1, character + 1);
+    position >= (span.start_line, span.start_column) && position <= (span.end_line, span.end_column)
+}
+
+/// The range of a whole line, for diagnostics that only know their line (zero-based)
+pub fn line_range(line: u32) -> JsonValue {
+    json!({
+        "start": { "line": line, "character": 0 },
+        "end": { "line": line + 1, "character": 0 }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_message_framing() {
+        let mut buffer = vec![];
+        write_message(&mut buffer, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+        write_message(&mut buffer, &response(json!(1), json!(null))).unwrap();
+        assert!(String::from_utf8(buffer.clone()).unwrap().starts_with("Content-Length: 33\r\n\r\n{"));
+
+        let mut input = Cursor::new(buffer);
+        assert_eq!(read_message(&mut input).unwrap().unwrap()["method"], "exit");
+        assert_eq!(read_message(&mut input).unwrap().unwrap()["id"], 1);
+        assert!(read_message(&mut input).unwrap().is_none());
+
+        // other headers are ignored
+        let mut input = Cursor::new(b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length: 2\r\n\r\n{}".to_vec());
+        assert_eq!(read_message(&mut input).unwrap().unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        let mut buffer = b"Content-Length: 5\r\n\r\n{oops".to_vec();
+        buffer.extend_from_slice(format!("Content-Length: {}\r\n\r\n", MAX_CONTENT_LENGTH + 1).as_bytes());
+        buffer.extend_from_slice(&vec![b' '; MAX_CONTENT_LENGTH + 1]);
+        buffer.extend_from_slice(b"Content-Length: lots\r\n\r\n{}");
+        write_message(&mut buffer, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+
+        let mut input = Cursor::new(buffer);
+        match read_message(&mut input) {
+            Err(ReadError::Malformed(message)) => assert!(message.starts_with("Invalid JSON")),
+            x => panic!("Expected a malformed message, got {:?}", x)
+        }
+        match read_message(&mut input) {
+            Err(ReadError::Malformed(message)) => assert!(message.contains("exceeds the limit")),
+            x => panic!("Expected a malformed message, got {:?}", x)
+        }
+        match read_message(&mut input) {
+            Err(ReadError::Malformed(message)) => assert!(message.starts_with("Invalid Content-Length 'lots'")),
+            x => panic!("Expected a malformed message, got {:?}", x)
+        }
+        // the unread body runs into the next message, which is still found
+        assert_eq!(read_message(&mut input).unwrap().unwrap()["method"], "exit");
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_spans() {
+        let span = Span { start_line: 2, start_column: 3, end_line: 2, end_column: 7 };
+        assert_eq!(span_to_range(&span), json!({ "start": { "line": 1, "character": 2 }, "end": { "line": 1, "character": 7 } }));
+        assert!(span_contains(&span, 1, 2));
+        assert!(span_contains(&span, 1, 6));
+        assert!(!span_contains(&span, 1, 7));
+        assert!(!span_contains(&span, 0, 4));
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use rand::Rng;
+use serde_json::Value as JsonValue;
+
+use lsp::{LanguageServer, path_to_uri};
+use lsp::protocol::{read_message, write_message};
+
+const TOKENS: &str = "(define-map balances ((owner principal)) ((balance uint)))
+(define-read-only (get-balance (owner principal))
+  (ok (default-to u0 (get balance (map-get? balances {owner: owner})))))
+";
+
+const CALLER: &str = "(define-data-var last-balance uint u0)
+(define-private (fetch (owner principal))
+  (unwrap-panic (contract-call? .tokens get-balance owner)))
+(define-public (remember (owner principal))
+  (begin
+    (var-set last-balance (fetch owner))
+    (ok (var-get last-balance))))
+";
+
+fn uri(path: &PathBuf) -> String {
+    path_to_uri(path).unwrap()
+}
+
+fn make_workspace() -> PathBuf {
+    let root = PathBuf::from(format!("/tmp/lsp_{}", rand::thread_rng().gen::<u32>()));
+    fs::create_dir_all(root.join("contracts")).unwrap();
+    fs::write(root.join("contracts/tokens.clar"), TOKENS).unwrap();
+    fs::write(root.join("contracts/caller.clar"), CALLER).unwrap();
+    root
+}
+
+/// The zero-based (line, character) of the first occurrence of `needle`
+fn position_of(text: &str, needle: &str) -> (u32, u32) {
+    let offset = text.find(needle).unwrap();
+    let line = text[..offset].matches('\n').count();
+    let line_start = text[..offset].rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+    (line as u32, (offset - line_start) as u32)
+}
+
+fn request(id: u64, method: &str, params: JsonValue) -> JsonValue {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn position_request(id: u64, method: &str, uri: &str, (line, character): (u32, u32)) -> JsonValue {
+    request(id, method, json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } }))
+}
+
+fn initialized_server(root: &PathBuf) -> LanguageServer {
+    let mut server = LanguageServer::new();
+    let responses = server.handle(request(1, "initialize", json!({ "rootUri": uri(root) })));
+    assert_eq!(responses[0]["result"]["capabilities"]["hoverProvider"], true);
+    server
+}
+
+#[test]
+fn test_diagnostics() {
+    let root = make_workspace();
+    let caller_uri = uri(&root.join("contracts/caller.clar"));
+    let mut server = initialized_server(&root);
+
+    // the call into .tokens type checks against the workspace's tokens.clar
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen",
+                                         "params": { "textDocument": { "uri": caller_uri, "text": CALLER } } }));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["method"], "textDocument/publishDiagnostics");
+    assert_eq!(messages[0]["params"]["uri"], caller_uri.as_str());
+    assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+
+    // every independent error is reported
+    let broken = format!("{}(define-public (broken) (ok (+ 1 u1)))\n(define-public (also-broken) u1)\n", CALLER);
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didChange",
+                                         "params": { "textDocument": { "uri": caller_uri },
+                                                     "contentChanges": [{ "text": broken }] } }));
+    let diagnostics = messages[0]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 7);
+    assert!(diagnostics[0]["message"].as_str().unwrap().contains("expecting expression of type 'int'"));
+    assert_eq!(diagnostics[1]["range"]["start"]["line"], 8);
+    assert!(diagnostics[1]["message"].as_str().unwrap().contains("must return an expression of type 'response'"));
+
+    // lint warnings are published too
+    let unused = format!("{}(define-private (unused) 1)\n", CALLER);
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didChange",
+                                         "params": { "textDocument": { "uri": caller_uri },
+                                                     "contentChanges": [{ "text": unused }] } }));
+    let diagnostics = messages[0]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 2);
+    assert_eq!(diagnostics[0]["code"], "unused-private-function");
+
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didChange",
+                                         "params": { "textDocument": { "uri": caller_uri },
+                                                     "contentChanges": [{ "text": "(define-public (broken)" }] } }));
+    let diagnostics = messages[0]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 1);
+
+    // closing a document clears its diagnostics
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didClose",
+                                         "params": { "textDocument": { "uri": caller_uri } } }));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+}
+
+#[test]
+fn test_hover_and_completion() {
+    let root = make_workspace();
+    let caller_uri = uri(&root.join("contracts/caller.clar"));
+    let mut server = initialized_server(&root);
+
+    let (line, character) = position_of(CALLER, "fetch owner");
+    let responses = server.handle(position_request(2, "textDocument/hover", &caller_uri, (line, character + 6)));
+    let hover = responses[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert_eq!(hover, "```clarity\nprincipal\n```");
+    assert_eq!(responses[0]["result"]["range"]["start"], json!({ "line": line, "character": character + 6 }));
+
+    // natives show their documentation
+    let responses = server.handle(position_request(3, "textDocument/hover", &caller_uri, position_of(CALLER, "var-set")));
+    let hover = responses[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(hover.starts_with("```clarity\n(var-set var-name expr1)\n```"));
+
+    let responses = server.handle(position_request(4, "textDocument/hover", &caller_uri, (100, 0)));
+    assert_eq!(responses[0]["result"], JsonValue::Null);
+
+    let responses = server.handle(position_request(5, "textDocument/completion", &caller_uri, (0, 0)));
+    let items = responses[0]["result"]["items"].as_array().unwrap();
+    let item = |label: &str| items.iter().find(|item| item["label"] == label).cloned();
+    assert_eq!(item("map-get?").unwrap()["kind"], 3);
+    assert_eq!(item("block-height").unwrap()["kind"], 14);
+    assert_eq!(item("fetch").unwrap()["kind"], 3);
+    assert_eq!(item("last-balance").unwrap()["kind"], 6);
+    assert!(item("balances").is_none());
+}
+
+#[test]
+fn test_definition() {
+    let root = make_workspace();
+    let caller_uri = uri(&root.join("contracts/caller.clar"));
+    let tokens_uri = uri(&root.join("contracts/tokens.clar"));
+    let mut server = initialized_server(&root);
+
+    // a function in another contract
+    let responses = server.handle(position_request(2, "textDocument/definition", &caller_uri, position_of(CALLER, "get-balance owner")));
+    let (line, character) = position_of(TOKENS, "get-balance");
+    assert_eq!(responses[0]["result"], json!({
+        "uri": tokens_uri,
+        "range": { "start": { "line": line, "character": character }, "end": { "line": line, "character": character + 11 } }
+    }));
+
+    // the other contract itself
+    let (line, character) = position_of(CALLER, ".tokens");
+    let responses = server.handle(position_request(3, "textDocument/definition", &caller_uri, (line, character + 1)));
+    assert_eq!(responses[0]["result"]["uri"], tokens_uri.as_str());
+
+    // a private function, and a data var
+    let responses = server.handle(position_request(4, "textDocument/definition", &caller_uri, position_of(CALLER, "fetch owner")));
+    assert_eq!(responses[0]["result"]["uri"], caller_uri.as_str());
+    assert_eq!(responses[0]["result"]["range"]["start"], json!({ "line": 1, "character": 17 }));
+
+    let (line, character) = position_of(CALLER, "last-balance)");
+    let responses = server.handle(position_request(5, "textDocument/definition", &caller_uri, (line, character)));
+    assert_eq!(responses[0]["result"]["range"]["start"], json!({ "line": 0, "character": 17 }));
+
+    // a map, from within the contract defining it
+    let (line, character) = position_of(TOKENS, "balances {");
+    let responses = server.handle(position_request(6, "textDocument/definition", &tokens_uri, (line, character)));
+    assert_eq!(responses[0]["result"]["range"]["start"], json!({ "line": 0, "character": 12 }));
+}
+
+#[test]
+fn test_run() {
+    let root = make_workspace();
+    let mut input = vec![];
+    write_message(&mut input, &request(1, "initialize", json!({ "rootUri": uri(&root) }))).unwrap();
+    write_message(&mut input, &json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} })).unwrap();
+    write_message(&mut input, &request(2, "workspace/symbol", json!({ "query": "" }))).unwrap();
+    write_message(&mut input, &request(3, "shutdown", JsonValue::Null)).unwrap();
+    write_message(&mut input, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+    // never read: the server stops at `exit`
+    write_message(&mut input, &request(4, "shutdown", JsonValue::Null)).unwrap();
+
+    let mut output = vec![];
+    LanguageServer::new().run(&mut Cursor::new(input), &mut output).unwrap();
+
+    let mut output = Cursor::new(output);
+    let mut responses = vec![];
+    while let Some(message) = read_message(&mut output).unwrap() {
+        responses.push(message);
+    }
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[1]["id"], 2);
+    assert_eq!(responses[1]["error"]["code"], -32601);
+    assert_eq!(responses[2]["id"], 3);
+    assert_eq!(responses[2]["result"], JsonValue::Null);
+}
+
+#[test]
+fn test_run_malformed() {
+    let mut input = b"Content-Length: 5\r\n\r\n{oops".to_vec();
+    write_message(&mut input, &request(1, "shutdown", JsonValue::Null)).unwrap();
+    write_message(&mut input, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+
+    // a bad message is answered, and the server keeps going
+    let mut output = vec![];
+    LanguageServer::new().run(&mut Cursor::new(input), &mut output).unwrap();
+
+    let mut output = Cursor::new(output);
+    let mut responses = vec![];
+    while let Some(message) = read_message(&mut output).unwrap() {
+        responses.push(message);
+    }
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], JsonValue::Null);
+    assert_eq!(responses[0]["error"]["code"], -32700);
+    assert_eq!(responses[1]["id"], 1);
+}
+
+#[test]
+fn test_diagnostics_for_dependents() {
+    let root = make_workspace();
+    let caller_uri = uri(&root.join("contracts/caller.clar"));
+    let tokens_uri = uri(&root.join("contracts/tokens.clar"));
+    let mut server = initialized_server(&root);
+
+    for (uri, text) in [(&caller_uri, CALLER), (&tokens_uri, TOKENS)].iter() {
+        server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen",
+                              "params": { "textDocument": { "uri": uri, "text": text } } }));
+    }
+
+    // changing the caller only re-analyzes the caller
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didChange",
+                                         "params": { "textDocument": { "uri": caller_uri },
+                                                     "contentChanges": [{ "text": CALLER }] } }));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["params"]["uri"], caller_uri.as_str());
+
+    // changing tokens re-analyzes the caller against the open, unsaved document
+    let renamed = TOKENS.replace("get-balance", "balance-of");
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didChange",
+                                         "params": { "textDocument": { "uri": tokens_uri },
+                                                     "contentChanges": [{ "text": renamed }] } }));
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0]["params"]["uri"], caller_uri.as_str());
+    assert_eq!(messages[0]["params"]["diagnostics"].as_array().unwrap().len(), 1);
+    assert_eq!(messages[1]["params"]["uri"], tokens_uri.as_str());
+    assert_eq!(messages[1]["params"]["diagnostics"], json!([]));
+
+    // closing tokens puts the caller back on the saved contract
+    let messages = server.handle(json!({ "jsonrpc": "2.0", "method": "textDocument/didClose",
+                                         "params": { "textDocument": { "uri": tokens_uri } } }));
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1]["params"]["uri"], caller_uri.as_str());
+    assert_eq!(messages[1]["params"]["diagnostics"], json!([]));
+}
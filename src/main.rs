@@ -317,7 +317,7 @@ fn main() {
         use chainstate::stacks::db::StacksChainState;
         use chainstate::stacks::db::blocks::StagingBlock;
         use chainstate::stacks::StacksBlockHeader;
-        use chainstate::burn::db::sortdb::{SortitionDB, PoxId, PoxDB};
+        use chainstate::burn::db::sortdb::SortitionDB;
         use chainstate::stacks::StacksAddress;
         use burnchains::Address;
         use burnchains::Burnchain;
@@ -412,7 +412,7 @@ fn main() {
             loop {
                 // simulate the p2p refreshing itself
                 // update p2p's read-only view of the unconfirmed state
-                let (canonical_burn_tip, canonical_block_tip) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(p2p_new_sortition_db.conn())
+                let (canonical_burn_tip, canonical_block_tip) = SortitionDB::get_canonical_stacks_chain_tip_hash(p2p_new_sortition_db.conn())
                     .expect("Failed to read canonical stacks chain tip");
                 let canonical_tip = StacksBlockHeader::make_index_block_hash(&canonical_burn_tip, &canonical_block_tip);
                 p2p_chainstate.refresh_unconfirmed_state_readonly(canonical_tip)
@@ -430,11 +430,7 @@ fn main() {
                 continue;
             }
             
-            let (new_snapshot, _) = {
-                let pox_id = PoxId::stubbed();
-                let pox_db = PoxDB::stubbed();
-                new_sortition_db.evaluate_sortition(&burn_block_header, blockstack_txs, &burnchain, &pox_id, &pox_db).unwrap()
-            };
+            let (new_snapshot, _) = new_sortition_db.evaluate_sortition(&burn_block_header, blockstack_txs, &burnchain).unwrap();
 
             // importantly, the burnchain linkage must all match
             assert_eq!(old_snapshot.burn_header_hash, new_snapshot.burn_header_hash);
@@ -46,6 +46,27 @@ pub fn increment_stx_blocks_received_counter() {
     prometheus::STX_BLOCKS_RECEIVED_COUNTER.inc();    
 }
 
+pub fn increment_p2p_msg_compact_block_received_counter() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::P2P_MSG_COMPACT_BLOCK_RECEIVED_COUNTER.inc();
+}
+
+pub fn increment_p2p_compact_blocks_reassembled_counter() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::P2P_COMPACT_BLOCKS_REASSEMBLED_COUNTER.inc();
+}
+
+pub fn increment_p2p_compact_block_fallbacks_counter() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::P2P_COMPACT_BLOCK_FALLBACKS_COUNTER.inc();
+}
+
+#[allow(unused_variables)]
+pub fn increment_p2p_compact_block_bytes_saved_counter(bytes: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::P2P_COMPACT_BLOCK_BYTES_SAVED_COUNTER.inc_by(bytes as i64);
+}
+
 pub fn increment_stx_micro_blocks_received_counter() {
     #[cfg(feature = "monitoring_prom")]
     prometheus::STX_MICRO_BLOCKS_RECEIVED_COUNTER.inc();    
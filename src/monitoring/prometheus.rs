@@ -55,6 +55,30 @@ lazy_static! {
         labels! {"handler" => "all",}
     )).unwrap();
 
+    pub static ref P2P_MSG_COMPACT_BLOCK_RECEIVED_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_p2p_msg_compact_block_received_total",
+        "Total number of CompactBlock messages received.",
+        labels! {"handler" => "all",}
+    )).unwrap();
+
+    pub static ref P2P_COMPACT_BLOCKS_REASSEMBLED_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_p2p_compact_blocks_reassembled_total",
+        "Total number of compact blocks reassembled into full blocks.",
+        labels! {"handler" => "all",}
+    )).unwrap();
+
+    pub static ref P2P_COMPACT_BLOCK_FALLBACKS_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_p2p_compact_block_fallbacks_total",
+        "Total number of compact blocks that had to be fetched in full.",
+        labels! {"handler" => "all",}
+    )).unwrap();
+
+    pub static ref P2P_COMPACT_BLOCK_BYTES_SAVED_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_p2p_compact_block_bytes_saved_total",
+        "Total number of bytes saved by receiving compact blocks instead of full blocks.",
+        labels! {"handler" => "all",}
+    )).unwrap();
+
     pub static ref STX_MICRO_BLOCKS_RECEIVED_COUNTER: IntCounter = register_int_counter!(opts!(
         "stacks_node_stx_micro_blocks_received_total",
         "Total number of Stacks micro blocks received.",
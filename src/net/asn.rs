@@ -20,6 +20,8 @@
 use std::io::BufReader;
 use std::io::BufRead;
 use std::fs::File;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
 
 use net::Error as net_error;
 use net::PeerAddress;
@@ -29,6 +31,10 @@ use regex::Captures;
 
 use util::log;
 
+// one row of an ASN file, by address family.  A row that matches neither is malformed.
+const ASN4_REGEX : &'static str = "^[ \t]*([0-9]+\\.[0-9]+\\.[0-9]+\\.[0-9]+)/([0-9]+)[ \t]+([0-9]+)[ \t]*$";
+const ASN6_REGEX : &'static str = "^[ \t]*([0-9a-fA-F:]+)/([0-9]+)[ \t]+([0-9]+)[ \t]*$";
+
 // IPv4 prefix to ASN/org map entry
 #[derive(Debug, Clone, PartialEq)]
 pub struct ASEntry4 {
@@ -40,7 +46,7 @@ pub struct ASEntry4 {
 
 impl ASEntry4 {
     pub fn from_file(asn_file: &String) -> Result<Vec<ASEntry4>, net_error> {
-        // each row in asn_file must be one of the following:
+        // rows with IPv6 prefixes are skipped; each other row in asn_file must be one of the following:
         // ^[:whitespace:]*([0-9]+.[0-9]+.[0-9]+.[0-9]+)/([0-9]+)[:whitespace:]+([0-9]+)[:whitespace:]*$
         // group 1 is the IP prefix
         // group 2 is the prefix length
@@ -56,13 +62,14 @@ impl ASEntry4 {
     fn read_asn4_sequence<R: BufRead>(fd: &mut R) -> Result<Vec<ASEntry4>, net_error> {
         let mut asn4 = vec![];
 
-        let asn4_regex = Regex::new("^[ \t]*([0-9]+\\.[0-9]+\\.[0-9]+\\.[0-9]+)/([0-9]+)[ \t]+([0-9]+)[ \t]*$").unwrap();
+        let asn4_regex = Regex::new(ASN4_REGEX).unwrap();
+        let asn6_regex = Regex::new(ASN6_REGEX).unwrap();
         let asn4_whitespace_regex = Regex::new("^[ \t]*$|^[ \t]*#.+$").unwrap();
         let mut line_count = 0;
         let mut parsed = true;
 
         loop {
-            let next_asn4_opt_res = ASEntry4::read_asn4(fd, &asn4_regex, &asn4_whitespace_regex);
+            let next_asn4_opt_res = ASEntry4::read_asn4(fd, &asn4_regex, &asn6_regex, &asn4_whitespace_regex);
 
             match next_asn4_opt_res {
                 Ok(next_asn4_opt) => {
@@ -97,9 +104,9 @@ impl ASEntry4 {
     }
 
     // read one ASEntry4 record
-    // Returns None on whitespace
+    // Returns None on whitespace, or on an IPv6 record
     // Returns PermanentlyDrained on EOF
-    fn read_asn4<R: BufRead>(fd: &mut R, asn4_regex: &Regex, asn4_whitespace_regex: &Regex) -> Result<Option<ASEntry4>, net_error> {
+    fn read_asn4<R: BufRead>(fd: &mut R, asn4_regex: &Regex, asn6_regex: &Regex, asn4_whitespace_regex: &Regex) -> Result<Option<ASEntry4>, net_error> {
         let mut buf_full = String::new();
         let num_bytes = fd.read_line(&mut buf_full)
             .map_err(|_e| net_error::FilesystemError)?;
@@ -116,6 +123,11 @@ impl ASEntry4 {
             return Ok(None);
         }
 
+        // IPv6 entry?  Those are read by ASEntry6.
+        if asn6_regex.is_match(&buf) {
+            return Ok(None);
+        }
+
         let caps = asn4_regex.captures(&buf)
             .ok_or(net_error::DeserializeError("Line does not match ANS4 regex".to_string()))
             .map_err(|e| {
@@ -196,6 +208,168 @@ impl ASEntry4 {
     }
 }
 
+// IPv6 prefix to ASN map entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct ASEntry6 {
+    pub prefix: PeerAddress,
+    pub mask: u8,
+    pub asn: u32
+}
+
+impl ASEntry6 {
+    pub fn from_file(asn_file: &String) -> Result<Vec<ASEntry6>, net_error> {
+        // same file as ASEntry4::from_file().  Rows with IPv4 prefixes are skipped; each
+        // other row must be of the form
+        // ^[:whitespace:]*([0-9a-fA-F:]+)/([0-9]+)[:whitespace:]+([0-9]+)[:whitespace:]*$
+        // group 1 is the IP prefix
+        // group 2 is the prefix length
+        // group 3 is the AS number
+        let file_handle = File::open(asn_file)
+            .map_err(|_e| net_error::FilesystemError)?;
+
+        let mut line_cursor = BufReader::new(file_handle);
+        ASEntry6::read_asn6_sequence(&mut line_cursor)
+    }
+
+    // read a sequence of ASEntry6 records
+    fn read_asn6_sequence<R: BufRead>(fd: &mut R) -> Result<Vec<ASEntry6>, net_error> {
+        let mut asn6 = vec![];
+
+        let asn4_regex = Regex::new(ASN4_REGEX).unwrap();
+        let asn6_regex = Regex::new(ASN6_REGEX).unwrap();
+        let asn6_whitespace_regex = Regex::new("^[ \t]*$|^[ \t]*#.+$").unwrap();
+        let mut line_count = 0;
+        let mut parsed = true;
+
+        loop {
+            let next_asn6_opt_res = ASEntry6::read_asn6(fd, &asn6_regex, &asn4_regex, &asn6_whitespace_regex);
+
+            match next_asn6_opt_res {
+                Ok(next_asn6_opt) => {
+                    match next_asn6_opt {
+                        None => {},
+                        Some(asn6_rec) => {
+                            asn6.push(asn6_rec);
+                        }
+                    }
+                },
+                Err(net_error::DeserializeError(msg)) => {
+                    warn!("ASN6 parse error on line {}: {}", line_count, msg);
+                    parsed = false;
+                },
+                Err(net_error::PermanentlyDrained) => {
+                    // EOF 
+                    break;
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+            
+            line_count += 1;
+        }
+        if !parsed {
+            return Err(net_error::DeserializeError(format!("Failed to parse ASN6 sequence on line {}", line_count)));
+        }
+
+        asn6.sort_by(|a1, a2| a1.prefix.0.cmp(&a2.prefix.0));
+        Ok(asn6)
+    }
+
+    // read one ASEntry6 record
+    // Returns None on whitespace, or on an IPv4 record
+    // Returns PermanentlyDrained on EOF
+    fn read_asn6<R: BufRead>(fd: &mut R, asn6_regex: &Regex, asn4_regex: &Regex, asn6_whitespace_regex: &Regex) -> Result<Option<ASEntry6>, net_error> {
+        let mut buf_full = String::new();
+        let num_bytes = fd.read_line(&mut buf_full)
+            .map_err(|_e| net_error::FilesystemError)?;
+
+        if num_bytes == 0 {
+            return Err(net_error::PermanentlyDrained);
+        }
+
+        // trim trailing newline
+        let buf = buf_full.trim().to_string();
+
+        // comment and/or whitespace?
+        if asn6_whitespace_regex.is_match(&buf) {
+            return Ok(None);
+        }
+
+        // IPv4 entry?  Those are read by ASEntry4.
+        if asn4_regex.is_match(&buf) {
+            return Ok(None);
+        }
+
+        let caps = asn6_regex.captures(&buf)
+            .ok_or(net_error::DeserializeError("Line does not match ASN6 regex".to_string()))
+            .map_err(|e| {
+                debug!("Failed to read line \"{}\"", &buf);
+                e
+            })?;
+
+        let prefix_str = caps.get(1)
+            .ok_or(net_error::DeserializeError("Failed to read ASN6 prefix".to_string()))
+            .map_err(|e| {
+                debug!("Failed to get prefix of \"{}\"", &buf);
+                e
+            })?
+            .as_str();
+
+        let prefix_mask_str = caps.get(2)
+            .ok_or(net_error::DeserializeError("Failed to read ASN6 prefix mask".to_string()))
+            .map_err(|e| {
+                debug!("Failed to get mask of \"{}\"", &buf);
+                e
+            })?
+            .as_str();
+
+        let asn_str = caps.get(3)
+            .ok_or(net_error::DeserializeError("Failed to read ASN ID".to_string()))
+            .map_err(|e| {
+                debug!("Failed to get ASN of \"{}\"", &buf);
+                e
+            })?
+            .as_str();
+
+        let prefix_addr = Ipv6Addr::from_str(prefix_str)
+            .map_err(|_e| {
+                debug!("Failed to parse IPv6 prefix \"{}\"", &prefix_str);
+                net_error::DeserializeError("Failed to parse IPv6 prefix".to_string())
+            })?;
+
+        let prefix = PeerAddress(prefix_addr.octets());
+        if prefix.is_ipv4() {
+            debug!("IPv4-mapped prefix \"{}\"", &prefix_str);
+            return Err(net_error::DeserializeError("IPv4-mapped ASN6 prefix".to_string()));
+        }
+
+        let mask_opt = prefix_mask_str.parse::<u8>();
+        if mask_opt.is_err() {
+            debug!("Failed to parse mask \"{}\"", &prefix_mask_str);
+            return Err(net_error::DeserializeError("Failed to parse ASN mask".to_string()));
+        }
+        let mask = mask_opt.unwrap();
+        if mask < 1 || mask > 128 {
+            debug!("Invalid mask \"{}\"", mask);
+            return Err(net_error::DeserializeError(format!("Invalid ASN mask {}", mask)));
+        }
+
+        let asn_opt = asn_str.parse::<u32>();
+        if asn_opt.is_err() {
+            debug!("Failed to parse ASN \"{}\"", asn_str);
+            return Err(net_error::DeserializeError("Failed to parse ASN".to_string()));
+        }
+        let asn = asn_opt.unwrap();
+
+        Ok(Some(ASEntry6 {
+            prefix: prefix,
+            mask: mask,
+            asn: asn
+        }))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -273,4 +447,100 @@ mod test {
             assert_eq!(res, test.result);
         }
     }
+
+    struct asn6_fixture {
+        text: String,
+        result: Result<Vec<ASEntry6>, net_error>
+    }
+
+    #[test]
+    fn test_parse_asn6() {
+        let tests = vec![
+            asn6_fixture {
+                text: "2001:db8::/32 1\n2a00:1450:4000::/36 2\n".to_string(),
+                result: Ok(vec![
+                    ASEntry6 {
+                        prefix: PeerAddress([0x20,0x01,0x0d,0xb8,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                        mask: 32,
+                        asn: 1,
+                    },
+                    ASEntry6 {
+                        prefix: PeerAddress([0x2a,0x00,0x14,0x50,0x40,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                        mask: 36,
+                        asn: 2
+                    },
+                ])
+            },
+            // mixed with IPv4 entries, comments, and whitespace
+            asn6_fixture {
+                text: "\n1.2.3.4/24 100\n  2a00:1450::/32 \t\t300\n  # this is a comment\n2.3.4.5/23 200\n".to_string(),
+                result: Ok(vec![
+                    ASEntry6 {
+                        prefix: PeerAddress([0x2a,0x00,0x14,0x50,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                        mask: 32,
+                        asn: 300
+                    },
+                ]),
+            },
+            // invalid prefix
+            asn6_fixture {
+                text: "2001:db8:::/32 100".to_string(),
+                result: Err(net_error::DeserializeError("Failed to parse ASN6 sequence on line 1".to_string())),
+            },
+            // IPv4-mapped prefix
+            asn6_fixture {
+                text: "::ffff:102:300/120 100".to_string(),
+                result: Err(net_error::DeserializeError("Failed to parse ASN6 sequence on line 1".to_string())),
+            },
+            // neither an IPv4 nor an IPv6 row
+            asn6_fixture {
+                text: "::ffff:1.2.3.0/24 100".to_string(),
+                result: Err(net_error::DeserializeError("Failed to parse ASN6 sequence on line 1".to_string())),
+            },
+            // invalid mask
+            asn6_fixture {
+                text: "2001:db8::/129 100".to_string(),
+                result: Err(net_error::DeserializeError("Failed to parse ASN6 sequence on line 1".to_string())),
+            },
+            asn6_fixture {
+                text: "::/0 100".to_string(),
+                result: Err(net_error::DeserializeError("Failed to parse ASN6 sequence on line 1".to_string())),
+            },
+            // invalid asn
+            asn6_fixture {
+                text: "2001:db8::/32 4294967296".to_string(),
+                result: Err(net_error::DeserializeError("Failed to parse ASN6 sequence on line 1".to_string())),
+            },
+        ];
+
+        for test in &tests {
+            let mut cur = io::Cursor::new(&test.text);
+            let res = ASEntry6::read_asn6_sequence(&mut cur);
+            assert_eq!(res, test.result);
+        }
+    }
+
+    #[test]
+    fn test_parse_asn4_skips_asn6() {
+        let text = "2001:db8::/32 1\n1.0.0.0/8 2\n2001:db8:1::/64 3\n".to_string();
+        let mut cur = io::Cursor::new(&text);
+        let res = ASEntry4::read_asn4_sequence(&mut cur);
+        assert_eq!(res, Ok(vec![
+            ASEntry4 {
+                prefix: 0x01000000,
+                mask: 8,
+                asn: 2,
+                org: 0
+            }
+        ]));
+    }
+
+    #[test]
+    fn test_parse_asn4_rejects_malformed_rows() {
+        // a row that is neither an IPv4 nor an IPv6 record is an error, even if it has a ':'
+        let text = "1.0.0.0/8 2\n1.2.3.4:80/24 100\n".to_string();
+        let mut cur = io::Cursor::new(&text);
+        let res = ASEntry4::read_asn4_sequence(&mut cur);
+        assert_eq!(res, Err(net_error::DeserializeError("Failed to parse ASN4 sequence on line 2".to_string())));
+    }
 }
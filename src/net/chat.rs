@@ -37,12 +37,14 @@ use net::BLOCKS_INV_DATA_MAX_BITLEN;
 use net::connection::ConnectionP2P;
 use net::connection::ReplyHandleP2P;
 use net::connection::ConnectionOptions;
+use net::session::P2PSession;
 
 use net::neighbors::MAX_NEIGHBOR_BLOCK_DELAY;
 
 use net::db::*;
 
 use util::db::Error as db_error;
+use chainstate::stacks::Error as chain_error;
 use util::db::DBConn;
 use util::secp256k1::Secp256k1PublicKey;
 use util::secp256k1::Secp256k1PrivateKey;
@@ -55,6 +57,7 @@ use chainstate::burn::db::sortdb::{
 };
 
 use chainstate::stacks::db::StacksChainState;
+use core::mempool::MemPoolDB;
 use chainstate::stacks::StacksBlockHeader;
 use chainstate::stacks::StacksPublicKey;
 use burnchains::Burnchain;
@@ -150,6 +153,7 @@ pub struct NeighborStats {
     pub block_push_rx_counts: VecDeque<(u64, u64)>,         // (count, num bytes)
     pub microblocks_push_rx_counts: VecDeque<(u64, u64)>,   // (count, num bytes)
     pub transaction_push_rx_counts: VecDeque<(u64, u64)>,   // (count, num bytes)
+    pub mempool_sync_tx_counts: VecDeque<(u64, u64)>,       // (count, num bytes)
     pub relayed_messages: HashMap<NeighborAddress, RelayStats>
 }
 
@@ -173,6 +177,7 @@ impl NeighborStats {
             block_push_rx_counts: VecDeque::new(),
             microblocks_push_rx_counts: VecDeque::new(),
             transaction_push_rx_counts: VecDeque::new(),
+            mempool_sync_tx_counts: VecDeque::new(),
             relayed_messages: HashMap::new(),
         }
     }
@@ -209,6 +214,13 @@ impl NeighborStats {
         }
     }
 
+    pub fn add_mempool_sync(&mut self, message_size: u64) -> () {
+        self.mempool_sync_tx_counts.push_back((get_epoch_time_secs(), message_size));
+        while self.mempool_sync_tx_counts.len() > NUM_BLOCK_POINTS {
+            self.mempool_sync_tx_counts.pop_front();
+        }
+    }
+
     pub fn add_relayer(&mut self, addr: NeighborAddress, num_bytes: u64) -> () {
         if let Some(stats) = self.relayed_messages.get_mut(&addr) {
             stats.num_messages += 1;
@@ -290,6 +302,11 @@ impl NeighborStats {
         NeighborStats::get_bandwidth(&self.transaction_push_rx_counts, BLOCK_POINT_LIFETIME)
     }
 
+    /// Get the bandwidth we've spent serving this peer's mempool sync requests
+    pub fn get_mempool_sync_bandwidth(&self) -> f64 {
+        NeighborStats::get_bandwidth(&self.mempool_sync_tx_counts, BLOCK_POINT_LIFETIME)
+    }
+
     /// Determine how many of a particular message this peer has received
     pub fn get_message_recv_count(&self, msg_id: StacksMessageID) -> u64 {
         *(self.msg_rx_counts.get(&msg_id).unwrap_or(&0))
@@ -329,7 +346,11 @@ pub struct ConversationP2P {
     pub stats: NeighborStats,
 
     // outbound replies
-    pub reply_handles: VecDeque<ReplyHandleP2P>
+    pub reply_handles: VecDeque<ReplyHandleP2P>,
+
+    // encrypted session state, if we negotiated one
+    session: Option<P2PSession>,
+    peer_sealed: bool,                          // has the remote peer started sealing its messages?
 }
 
 impl fmt::Display for ConversationP2P {
@@ -487,6 +508,9 @@ impl ConversationP2P {
 
             stats: NeighborStats::new(outbound),
             reply_handles: VecDeque::new(),
+
+            session: None,
+            peer_sealed: false,
         }
     }
 
@@ -560,6 +584,23 @@ impl ConversationP2P {
         self.connection.ref_public_key()
     }
 
+    /// Did we negotiate an encrypted session with this peer?
+    pub fn is_encrypted(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Do we and the remote peer both support encrypted sessions?
+    fn supports_encryption(&self, local_peer: &LocalPeer) -> bool {
+        let flag = ServiceFlags::ENCRYPTION as u16;
+        (local_peer.services & flag) != 0 && (self.peer_services & flag) != 0
+    }
+
+    /// Do we and the remote peer both support compact blocks?
+    pub fn supports_compact_blocks(&self, local_peer: &LocalPeer) -> bool {
+        let flag = ServiceFlags::COMPACT_BLOCKS as u16;
+        (local_peer.services & flag) != 0 && (self.peer_services & flag) != 0
+    }
+
     pub fn get_burnchain_tip_height(&self) -> u64 {
         self.burnchain_tip_height
     }
@@ -637,9 +678,58 @@ impl ConversationP2P {
         rng.gen::<u32>()
     }
 
+    /// Encrypt a message's payload if we have an encrypted session with this peer.
+    /// Handshakes are always sent in the clear.
+    /// Must be called before the message is signed.
+    fn seal_message(&mut self, msg: &mut StacksMessage) -> Result<(), net_error> {
+        if let Some(ref mut session) = self.session {
+            if P2PSession::can_seal(&msg.payload) {
+                let sealed = session.seal(&msg.payload)?;
+                msg.payload = StacksMessageType::Sealed(sealed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt an inbound message's payload if it was sealed.  Once the remote peer starts
+    /// sealing its messages, it must seal all of them (except handshakes).
+    /// The message signature covers the sealed payload, and will have already been checked.
+    fn open_message(&mut self, msg: &mut StacksMessage) -> Result<(), net_error> {
+        let payload = match msg.payload {
+            StacksMessageType::Sealed(ref sealed) => {
+                let open_res = match self.session {
+                    Some(ref mut session) => session.open(sealed),
+                    None => {
+                        debug!("{:?}: got a sealed message, but there is no encrypted session", &self);
+                        return Err(net_error::InvalidMessage);
+                    }
+                };
+                match open_res {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        debug!("{:?}: failed to open sealed message: {:?}", &self, &e);
+                        return Err(net_error::InvalidMessage);
+                    }
+                }
+            },
+            ref payload => {
+                if self.peer_sealed && P2PSession::can_seal(payload) {
+                    debug!("{:?}: got an unsealed {} message in an encrypted session", &self, payload.get_message_name());
+                    return Err(net_error::InvalidMessage);
+                }
+                return Ok(());
+            }
+        };
+
+        self.peer_sealed = true;
+        msg.payload = payload;
+        Ok(())
+    }
+
     /// Generate a signed message for this conversation 
     pub fn sign_message(&mut self, chain_view: &BurnchainView, private_key: &Secp256k1PrivateKey, payload: StacksMessageType) -> Result<StacksMessage, net_error> {
         let mut msg = StacksMessage::from_chain_view(self.version, self.network_id, chain_view, payload);
+        self.seal_message(&mut msg)?;
         msg.sign(self.next_seq(), private_key)?;
         Ok(msg)
     }
@@ -649,6 +739,7 @@ impl ConversationP2P {
     pub fn sign_relay_message(&mut self, local_peer: &LocalPeer, chain_view: &BurnchainView, mut relay_hints: Vec<RelayData>, payload: StacksMessageType) -> Result<StacksMessage, net_error> {
         let mut msg = StacksMessage::from_chain_view(self.version, self.network_id, chain_view, payload);
         msg.relayers.append(&mut relay_hints);
+        self.seal_message(&mut msg)?;
         msg.sign_relay(&local_peer.private_key, self.next_seq(), &local_peer.to_neighbor_addr())?;
         Ok(msg)
     }
@@ -656,6 +747,7 @@ impl ConversationP2P {
     /// Generate a signed reply for this conversation 
     pub fn sign_reply(&mut self, chain_view: &BurnchainView, private_key: &Secp256k1PrivateKey, payload: StacksMessageType, seq: u32) -> Result<StacksMessage, net_error> {
         let mut msg = StacksMessage::from_chain_view(self.version, self.network_id, chain_view, payload);
+        self.seal_message(&mut msg)?;
         msg.sign(seq, private_key)?;
         Ok(msg)
     }
@@ -868,7 +960,19 @@ impl ConversationP2P {
         }
 
         let accept_data = HandshakeAcceptData::new(local_peer, self.heartbeat);
-        let accept = StacksMessage::from_chain_view(self.version, self.network_id, chain_view, StacksMessageType::HandshakeAccept(accept_data));
+        let mut accept = StacksMessage::from_chain_view(self.version, self.network_id, chain_view, StacksMessageType::HandshakeAccept(accept_data));
+
+        if self.session.is_none() && self.supports_encryption(local_peer) {
+            // set up an encrypted session, bound to this handshake by a nonce of our choosing.
+            // The nonce goes back to the initiator in the accept's preamble; peers that don't
+            // support encryption ignore it.
+            let session_nonce = thread_rng().gen_range(1, u32::max_value());
+            let peer_pubkey = handshake_data.node_public_key.to_public_key()?;
+            self.session = Some(P2PSession::new(&local_peer.private_key, &peer_pubkey, message.preamble.seq, session_nonce, false));
+            accept.preamble.additional_data = session_nonce;
+
+            debug!("{:?}: set up encrypted session", &self);
+        }
 
         // update stats
         self.stats.last_contact_time = get_epoch_time_secs();
@@ -882,8 +986,16 @@ impl ConversationP2P {
     /// Handle an inbound handshake-accept
     /// Update conversation state based on a HandshakeAccept
     /// Called from the p2p network thread.
-    fn handle_handshake_accept(&mut self, preamble: &Preamble, handshake_accept: &HandshakeAcceptData) -> Result<(), net_error> {
+    fn handle_handshake_accept(&mut self, local_peer: &LocalPeer, preamble: &Preamble, handshake_accept: &HandshakeAcceptData) -> Result<(), net_error> {
         self.update_from_handshake_data(preamble, &handshake_accept.handshake)?;
+        if self.session.is_none() && self.supports_encryption(local_peer) && preamble.additional_data != 0 {
+            // the remote peer set up an encrypted session.  An accept is a reply, so its sequence
+            // number is that of our handshake.
+            let peer_pubkey = handshake_accept.handshake.node_public_key.to_public_key()?;
+            self.session = Some(P2PSession::new(&local_peer.private_key, &peer_pubkey, preamble.seq, preamble.additional_data, true));
+
+            debug!("{:?}: set up encrypted session", &self);
+        }
         self.peer_heartbeat = 
             if handshake_accept.heartbeat_interval > (MAX_PEER_HEARTBEAT_INTERVAL as u32) {
                 debug!("{:?}: heartbeat interval is too long; forcing default maximum", self);
@@ -976,6 +1088,91 @@ impl ConversationP2P {
         self.sign_and_reply(local_peer, burnchain_view, preamble, blocks_inv_payload)
     }
 
+    /// Handle an inbound GetMempoolTxids request.
+    /// Reply with the next page of txids in our mempool after the one the peer gave us.
+    fn handle_getmempooltxids(&mut self, local_peer: &LocalPeer, mempool: &MemPoolDB, burnchain_view: &BurnchainView, preamble: &Preamble, get_mempool_txids: &GetMempoolTxidsData) -> Result<ReplyHandleP2P, net_error> {
+        let max_txids = cmp::min(MEMPOOL_TXIDS_PAGE_MAX_LEN, get_mempool_txids.max_txids as u32);
+        let txids = MemPoolDB::get_txid_page(mempool.conn(), &get_mempool_txids.last_txid, max_txids as u64)
+            .map_err(net_error::DBError)?;
+
+        debug!("{:?}: Handle GetMempoolTxids from {:?}. Reply {} txids after {}", &local_peer, &self, txids.len(), &get_mempool_txids.last_txid);
+
+        let txids_payload = StacksMessageType::MempoolTxids(MempoolTxidsData { txids: txids });
+        self.sign_and_reply(local_peer, burnchain_view, preamble, txids_payload)
+    }
+
+    /// Handle an inbound GetMempoolTxs request.
+    /// Reply with whichever of the requested transactions we still have, up to the maximum
+    /// payload size.  Throttle peers that make us serve too many bytes per second.
+    fn handle_getmempooltxs(&mut self, local_peer: &LocalPeer, mempool: &MemPoolDB, burnchain_view: &BurnchainView, preamble: &Preamble, get_mempool_txs: &GetMempoolTxsData) -> Result<ReplyHandleP2P, net_error> {
+        if self.connection.options.max_mempool_sync_bandwidth > 0 && self.stats.get_mempool_sync_bandwidth() > (self.connection.options.max_mempool_sync_bandwidth as f64) {
+            debug!("Neighbor {:?} exceeded max mempool-sync bandwidth of {} bytes/sec (currently at {})", &self.to_neighbor_key(), self.connection.options.max_mempool_sync_bandwidth, self.stats.get_mempool_sync_bandwidth());
+            return self.reply_nack(local_peer, burnchain_view, preamble, NackErrorCodes::Throttled);
+        }
+
+        let mut txs = vec![];
+        let mut total_len : u64 = 5;       // 1-byte type prefix + 4-byte vector length
+        for txid in get_mempool_txs.txids.iter() {
+            let tx_info = match MemPoolDB::get_tx(mempool.conn(), txid).map_err(net_error::DBError)? {
+                Some(tx_info) => tx_info,
+                None => {
+                    // mined or evicted since the peer saw it
+                    continue;
+                }
+            };
+            if total_len + tx_info.metadata.len > (MAX_PAYLOAD_LEN as u64) {
+                break;
+            }
+            total_len += tx_info.metadata.len;
+            txs.push(tx_info.tx);
+        }
+
+        debug!("{:?}: Handle GetMempoolTxs from {:?}. Reply {} of {} requested transactions ({} bytes)", &local_peer, &self, txs.len(), get_mempool_txs.txids.len(), total_len);
+
+        self.stats.add_mempool_sync(total_len);
+
+        let txs_payload = StacksMessageType::MempoolTxs(MempoolTxsData { txs: txs });
+        self.sign_and_reply(local_peer, burnchain_view, preamble, txs_payload)
+    }
+
+    /// Handle an inbound GetBlockTxs request.
+    /// Reply with the requested transactions from a block we sent as a compact block, or NACK if
+    /// we don't have the block or the peer asked for transactions it doesn't have.
+    fn handle_getblocktxs(&mut self, local_peer: &LocalPeer, chainstate: &StacksChainState, burnchain_view: &BurnchainView, preamble: &Preamble, get_block_txs: &GetBlockTxsData) -> Result<ReplyHandleP2P, net_error> {
+        let block = match StacksChainState::load_block(&chainstate.blocks_path, &get_block_txs.burn_header_hash, &get_block_txs.block_hash) {
+            Ok(Some(block)) => block,
+            Ok(None) | Err(chain_error::DBError(db_error::NotFoundError)) => {
+                debug!("{:?}: Handle GetBlockTxs from {:?}: no such block {}/{}", &local_peer, &self, &get_block_txs.burn_header_hash, &get_block_txs.block_hash);
+                return self.reply_nack(local_peer, burnchain_view, preamble, NackErrorCodes::NoSuchBlock);
+            },
+            Err(e) => {
+                return Err(e.into());
+            }
+        };
+
+        let mut txs = Vec::with_capacity(get_block_txs.indexes.len());
+        for index in get_block_txs.indexes.iter() {
+            match block.txs.get(*index as usize) {
+                Some(tx) => {
+                    txs.push(tx.clone());
+                },
+                None => {
+                    debug!("{:?}: Handle GetBlockTxs from {:?}: block {} has no transaction {}", &local_peer, &self, &get_block_txs.block_hash, index);
+                    return self.reply_nack(local_peer, burnchain_view, preamble, NackErrorCodes::NoSuchBlock);
+                }
+            }
+        }
+
+        debug!("{:?}: Handle GetBlockTxs from {:?}. Reply {} of {} transactions in {}", &local_peer, &self, txs.len(), block.txs.len(), &get_block_txs.block_hash);
+
+        let txs_payload = StacksMessageType::BlockTxs(BlockTxsData {
+            burn_header_hash: get_block_txs.burn_header_hash.clone(),
+            block_hash: get_block_txs.block_hash.clone(),
+            txs: txs
+        });
+        self.sign_and_reply(local_peer, burnchain_view, preamble, txs_payload)
+    }
+
     /// Verify that there are no cycles in our relayers list.
     /// Identify relayers by public key hash
     fn check_relayer_cycles(relayers: &Vec<RelayData>) -> bool {
@@ -1085,10 +1282,13 @@ impl ConversationP2P {
     
     /// Handle an inbound authenticated p2p data-plane message.
     /// Return the message if not handled
-    fn handle_data_message(&mut self, local_peer: &LocalPeer, peerdb: &mut PeerDB, sortdb: &SortitionDB, chainstate: &mut StacksChainState, chain_view: &BurnchainView, msg: StacksMessage) -> Result<Option<StacksMessage>, net_error> {
+    fn handle_data_message(&mut self, local_peer: &LocalPeer, peerdb: &mut PeerDB, sortdb: &SortitionDB, chainstate: &mut StacksChainState, mempool: &MemPoolDB, chain_view: &BurnchainView, msg: StacksMessage) -> Result<Option<StacksMessage>, net_error> {
         let res = match msg.payload {
             StacksMessageType::GetNeighbors => self.handle_getneighbors(peerdb.conn(), local_peer, chain_view, &msg.preamble),
             StacksMessageType::GetBlocksInv(ref get_blocks_inv) => self.handle_getblocksinv(local_peer, sortdb, chainstate, chain_view, &msg.preamble, get_blocks_inv),
+            StacksMessageType::GetMempoolTxids(ref get_mempool_txids) => self.handle_getmempooltxids(local_peer, mempool, chain_view, &msg.preamble, get_mempool_txids),
+            StacksMessageType::GetMempoolTxs(ref get_mempool_txs) => self.handle_getmempooltxs(local_peer, mempool, chain_view, &msg.preamble, get_mempool_txs),
+            StacksMessageType::GetBlockTxs(ref get_block_txs) => self.handle_getblocktxs(local_peer, chainstate, chain_view, &msg.preamble, get_block_txs),
             StacksMessageType::Blocks(_) => {
                 monitoring::increment_stx_blocks_received_counter();
 
@@ -1102,6 +1302,18 @@ impl ConversationP2P {
                     }
                 }
             },
+            StacksMessageType::CompactBlock(_) => {
+                monitoring::increment_p2p_msg_compact_block_received_counter();
+
+                // not handled here, but counts against the peer's block-push bandwidth
+                match self.validate_blocks_push(local_peer, chain_view, &msg.preamble, msg.relayers.clone())? {
+                    Some(handle) => Ok(handle),
+                    None => {
+                        // will forward upstream
+                        return Ok(Some(msg))
+                    }
+                }
+            },
             StacksMessageType::Microblocks(_) => {
                 monitoring::increment_stx_micro_blocks_received_counter();
 
@@ -1290,7 +1502,7 @@ impl ConversationP2P {
             },
             StacksMessageType::HandshakeAccept(ref data) => {
                 test_debug!("{:?}: Got HandshakeAccept", &self);
-                self.handle_handshake_accept(&msg.preamble, data).and_then(|_| Ok(None))
+                self.handle_handshake_accept(local_peer, &msg.preamble, data).and_then(|_| Ok(None))
             },
             StacksMessageType::Ping(_) => {
                 test_debug!("{:?}: Got Ping", &self);
@@ -1350,7 +1562,7 @@ impl ConversationP2P {
             StacksMessageType::HandshakeAccept(ref data) => {
                 if solicited {
                     test_debug!("{:?}: Got unauthenticated HandshakeAccept", &self);
-                    self.handle_handshake_accept(&msg.preamble, data).and_then(|_| Ok(None))
+                    self.handle_handshake_accept(local_peer, &msg.preamble, data).and_then(|_| Ok(None))
                 }
                 else {
                     test_debug!("{:?}: Unsolicited unauthenticated HandshakeAccept", &self);
@@ -1412,7 +1624,7 @@ impl ConversationP2P {
     /// Attempts to fulfill requests in other threads as a result of processing a message.
     /// Returns the list of unfulfilled Stacks messages we received -- messages not destined for
     /// any other thread in this program (i.e. "unsolicited messages").
-    pub fn chat(&mut self, local_peer: &LocalPeer, peerdb: &mut PeerDB, sortdb: &SortitionDB, chainstate: &mut StacksChainState, burnchain_view: &BurnchainView) -> Result<Vec<StacksMessage>, net_error> {
+    pub fn chat(&mut self, local_peer: &LocalPeer, peerdb: &mut PeerDB, sortdb: &SortitionDB, chainstate: &mut StacksChainState, mempool: &MemPoolDB, burnchain_view: &BurnchainView) -> Result<Vec<StacksMessage>, net_error> {
         let num_inbound = self.connection.inbox_len();
         test_debug!("{:?}: {} messages pending", &self, num_inbound);
 
@@ -1426,6 +1638,8 @@ impl ConversationP2P {
                 Some(m) => m
             };
 
+            self.open_message(&mut msg)?;
+
             if !self.validate_inbound_message(&msg, burnchain_view)? {
                 continue;
             }
@@ -1451,6 +1665,7 @@ impl ConversationP2P {
                 Some(mut reply) => {
                     // send back this message to the remote peer
                     test_debug!("{:?}: Send control-plane reply type {}", &self, reply.payload.get_message_name());
+                    self.seal_message(&mut reply)?;
                     reply.sign(msg.preamble.seq, &local_peer.private_key)?;
                     let reply_handle = self.relay_signed_message(reply)?;
                     self.reply_handles.push_back(reply_handle);
@@ -1513,7 +1728,7 @@ impl ConversationP2P {
                     }
                     else {
                         test_debug!("{:?}: Try handling message (type {} seq {})", &self, _msgtype, _seq);
-                        let msg_opt = self.handle_data_message(local_peer, peerdb, sortdb, chainstate, burnchain_view, msg)?;
+                        let msg_opt = self.handle_data_message(local_peer, peerdb, sortdb, chainstate, mempool, burnchain_view, msg)?;
                         match msg_opt {
                             Some(msg) => {
                                 debug!("{:?}: Did not handle message (type {} seq {}); passing upstream", &self, _msgtype, _seq);
@@ -1578,10 +1793,11 @@ mod test {
     use std::fs;
 
     use net::test::*;
+    use net::compact::test::make_test_block;
 
     use core::{PEER_VERSION, NETWORK_P2P_PORT};
 
-    fn make_test_chain_dbs(testname: &str, burnchain: &Burnchain, network_id: u32, key_expires: u64, data_url: UrlString, asn4_entries: &Vec<ASEntry4>, initial_neighbors: &Vec<Neighbor>) -> (PeerDB, SortitionDB, StacksChainState) {
+    fn make_test_chain_dbs(testname: &str, burnchain: &Burnchain, network_id: u32, key_expires: u64, data_url: UrlString, asn4_entries: &Vec<ASEntry4>, initial_neighbors: &Vec<Neighbor>) -> (PeerDB, SortitionDB, StacksChainState, MemPoolDB) {
         let test_path = format!("/tmp/blockstack-test-databases-{}", testname);
         match fs::metadata(&test_path) {
             Ok(_) => {
@@ -1596,11 +1812,12 @@ mod test {
         let peerdb_path = format!("{}/peers.db", &test_path);
         let chainstate_path = format!("{}/chainstate", &test_path);
 
-        let peerdb = PeerDB::connect(&peerdb_path, true, network_id, burnchain.network_id, None, key_expires, PeerAddress::from_ipv4(127, 0, 0, 1), NETWORK_P2P_PORT, data_url.clone(), &asn4_entries, Some(&initial_neighbors)).unwrap();
+        let peerdb = PeerDB::connect(&peerdb_path, true, network_id, burnchain.network_id, None, key_expires, PeerAddress::from_ipv4(127, 0, 0, 1), NETWORK_P2P_PORT, data_url.clone(), &asn4_entries, &vec![], Some(&initial_neighbors)).unwrap();
         let sortdb = SortitionDB::connect(&sortdb_path, burnchain.first_block_height, &burnchain.first_block_hash, get_epoch_time_secs(), true).unwrap();
         let chainstate = StacksChainState::open(false, network_id, &chainstate_path).unwrap();
+        let mempool = MemPoolDB::open(false, network_id, &chainstate_path).unwrap();
 
-        (peerdb, sortdb, chainstate)
+        (peerdb, sortdb, chainstate, mempool)
     }
 
     fn convo_send_recv(sender: &mut ConversationP2P, mut sender_handles: Vec<&mut ReplyHandleP2P>, receiver: &mut ConversationP2P) -> () {
@@ -1703,6 +1920,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -1714,8 +1932,8 @@ mod test {
         };
         chain_view.make_test_data();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_handshake_accept_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_handshake_accept_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_handshake_accept_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_handshake_accept_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -1739,12 +1957,12 @@ mod test {
         // it along to the chat caller (us)
         test_debug!("send handshake");
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 has a handshakeaccept 
         test_debug!("send handshake-accept");
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_1 = rh_1.recv(0).unwrap();
 
@@ -1806,6 +2024,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -1817,8 +2036,8 @@ mod test {
         };
         chain_view.make_test_data();
         
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_handshake_reject_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_handshake_reject_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_handshake_reject_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_handshake_reject_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -1842,11 +2061,11 @@ mod test {
 
         // convo_2 receives it and automatically rejects it.
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 has a handshakreject
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_1 = rh_1.recv(0).unwrap();
 
@@ -1884,6 +2103,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -1897,8 +2117,8 @@ mod test {
         
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
         
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_handshake_badsignature_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_handshake_badsignature_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_handshake_badsignature_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_handshake_badsignature_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -1927,11 +2147,11 @@ mod test {
 
         // convo_2 receives it and processes it, and barfs
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2_err = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view);
+        let unhandled_2_err = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view);
 
         // convo_1 gets a nack and consumes it
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         // the waiting reply aborts on disconnect
         let reply_1_err = rh_1.recv(0);
@@ -1964,6 +2184,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -1977,8 +2198,8 @@ mod test {
         
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_handshake_self_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_handshake_self_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_handshake_self_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_handshake_self_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -2000,11 +2221,11 @@ mod test {
 
         // convo_2 receives it and processes it automatically (consuming it), and give back a handshake reject
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         // convo_1 gets a handshake reject and consumes it
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // get back handshake reject
         let reply_1 = rh_1.recv(0).unwrap();
@@ -2043,6 +2264,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -2056,8 +2278,8 @@ mod test {
         
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_ping_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_ping_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_ping_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_ping_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -2083,13 +2305,13 @@ mod test {
         test_debug!("send handshake {:?}", &handshake_1);
         test_debug!("send ping {:?}", &ping_1);
         convo_send_recv(&mut convo_1, vec![&mut rh_handshake_1, &mut rh_ping_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 has a handshakeaccept 
         test_debug!("reply handshake-accept");
         test_debug!("send pong");
         convo_send_recv(&mut convo_2, vec![&mut rh_handshake_1, &mut rh_ping_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_handshake_1 = rh_handshake_1.recv(0).unwrap();
         let reply_ping_1 = rh_ping_1.recv(0).unwrap();
@@ -2118,6 +2340,305 @@ mod test {
         }
     }
 
+    #[test]
+    fn convo_encrypted_ping() {
+        let conn_opts = ConnectionOptions::default();
+        let socketaddr_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+        let socketaddr_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8081);
+        
+        let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+
+        let burnchain = Burnchain {
+            peer_version: PEER_VERSION,
+            network_id: 0,
+            chain_name: "bitcoin".to_string(),
+            network_name: "testnet".to_string(),
+            working_dir: "/nope".to_string(),
+            consensus_hash_lifetime: 24,
+            stable_confirmations: 7,
+            first_block_height: 12300,
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
+        };
+
+        let mut chain_view = BurnchainView {
+            burn_block_height: 12348,
+            burn_consensus_hash: ConsensusHash::from_hex("1111111111111111111111111111111111111111").unwrap(),
+            burn_stable_block_height: 12341,
+            burn_stable_consensus_hash: ConsensusHash::from_hex("2222222222222222222222222222222222222222").unwrap(),
+            last_consensus_hashes: HashMap::new()
+        };
+        chain_view.make_test_data();
+
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_encrypted_ping_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_encrypted_ping_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+
+        db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
+        db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
+
+        // both peers support encryption
+        let mut local_peer_1 = PeerDB::get_local_peer(&peerdb_1.conn()).unwrap();
+        let mut local_peer_2 = PeerDB::get_local_peer(&peerdb_2.conn()).unwrap();
+        local_peer_1.services |= ServiceFlags::ENCRYPTION as u16;
+        local_peer_2.services |= ServiceFlags::ENCRYPTION as u16;
+
+        let mut convo_1 = ConversationP2P::new(123, 456, &burnchain, &socketaddr_2, &conn_opts, true, 0);
+        let mut convo_2 = ConversationP2P::new(123, 456, &burnchain, &socketaddr_1, &conn_opts, true, 0);
+
+        // convo_1 sends a handshake to convo_2, in the clear
+        let handshake_data_1 = HandshakeData::from_local_peer(&local_peer_1);
+        let handshake_1 = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::Handshake(handshake_data_1.clone())).unwrap();
+        let mut rh_handshake_1 = convo_1.send_signed_request(handshake_1.clone(), 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_handshake_1], &mut convo_2);
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
+        assert_eq!(unhandled_2.len(), 1);
+        assert!(convo_2.is_encrypted());
+        assert!(!convo_1.is_encrypted());
+
+        // convo_2 sends back a handshake-accept, in the clear, which carries its session nonce
+        convo_send_recv(&mut convo_2, vec![&mut rh_handshake_1], &mut convo_1);
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
+        assert_eq!(unhandled_1.len(), 0);
+
+        let reply_handshake_1 = rh_handshake_1.recv(0).unwrap();
+        match reply_handshake_1.payload {
+            StacksMessageType::HandshakeAccept(_) => {},
+            _ => panic!("Expected HandshakeAccept")
+        };
+        assert!(reply_handshake_1.preamble.additional_data != 0);
+        assert!(convo_1.is_encrypted());
+
+        // convo_1 sends a ping to convo_2, which gets sealed
+        let ping_data_1 = PingData::new();
+        let ping_1 = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::Ping(ping_data_1.clone())).unwrap();
+        match ping_1.payload {
+            StacksMessageType::Sealed(_) => {},
+            _ => panic!("Expected a sealed ping")
+        };
+        let mut rh_ping_1 = convo_1.send_signed_request(ping_1.clone(), 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_ping_1], &mut convo_2);
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
+        assert_eq!(unhandled_2.len(), 0);   // ping is consumed
+        assert!(convo_2.peer_sealed);
+
+        // convo_2 replies with a sealed pong
+        convo_send_recv(&mut convo_2, vec![&mut rh_ping_1], &mut convo_1);
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
+        assert_eq!(unhandled_1.len(), 0);
+        assert!(convo_1.peer_sealed);
+
+        let reply_ping_1 = rh_ping_1.recv(0).unwrap();
+        match reply_ping_1.payload {
+            StacksMessageType::Pong(ref data) => {
+                assert_eq!(data.nonce, ping_data_1.nonce);
+            },
+            _ => panic!("Expected Pong")
+        };
+
+        // convo_2 won't take an unsealed ping now that convo_1 seals its messages
+        let mut ping_2 = StacksMessage::from_chain_view(convo_1.version, convo_1.network_id, &chain_view, StacksMessageType::Ping(PingData::new()));
+        ping_2.sign(convo_1.next_seq(), &local_peer_1.private_key).unwrap();
+        let mut rh_ping_2 = convo_1.send_signed_request(ping_2, 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_ping_2], &mut convo_2);
+        let res = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view);
+        match res {
+            Err(net_error::InvalidMessage) => {},
+            _ => panic!("Expected InvalidMessage")
+        };
+    }
+
+    #[test]
+    fn convo_encrypted_fallback() {
+        let conn_opts = ConnectionOptions::default();
+        let socketaddr_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+        let socketaddr_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8081);
+        
+        let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+
+        let burnchain = Burnchain {
+            peer_version: PEER_VERSION,
+            network_id: 0,
+            chain_name: "bitcoin".to_string(),
+            network_name: "testnet".to_string(),
+            working_dir: "/nope".to_string(),
+            consensus_hash_lifetime: 24,
+            stable_confirmations: 7,
+            first_block_height: 12300,
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
+        };
+
+        let mut chain_view = BurnchainView {
+            burn_block_height: 12348,
+            burn_consensus_hash: ConsensusHash::from_hex("1111111111111111111111111111111111111111").unwrap(),
+            burn_stable_block_height: 12341,
+            burn_stable_consensus_hash: ConsensusHash::from_hex("2222222222222222222222222222222222222222").unwrap(),
+            last_consensus_hashes: HashMap::new()
+        };
+        chain_view.make_test_data();
+
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_encrypted_fallback_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_encrypted_fallback_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+
+        db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
+        db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
+
+        // only peer 1 supports encryption
+        let mut local_peer_1 = PeerDB::get_local_peer(&peerdb_1.conn()).unwrap();
+        let local_peer_2 = PeerDB::get_local_peer(&peerdb_2.conn()).unwrap();
+        local_peer_1.services |= ServiceFlags::ENCRYPTION as u16;
+
+        let mut convo_1 = ConversationP2P::new(123, 456, &burnchain, &socketaddr_2, &conn_opts, true, 0);
+        let mut convo_2 = ConversationP2P::new(123, 456, &burnchain, &socketaddr_1, &conn_opts, true, 0);
+
+        let handshake_data_1 = HandshakeData::from_local_peer(&local_peer_1);
+        let handshake_1 = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::Handshake(handshake_data_1.clone())).unwrap();
+        let mut rh_handshake_1 = convo_1.send_signed_request(handshake_1.clone(), 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_handshake_1], &mut convo_2);
+        convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
+
+        convo_send_recv(&mut convo_2, vec![&mut rh_handshake_1], &mut convo_1);
+        convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
+
+        let reply_handshake_1 = rh_handshake_1.recv(0).unwrap();
+        match reply_handshake_1.payload {
+            StacksMessageType::HandshakeAccept(_) => {},
+            _ => panic!("Expected HandshakeAccept")
+        };
+        assert_eq!(reply_handshake_1.preamble.additional_data, 0);
+
+        // no session on either side
+        assert!(!convo_1.is_encrypted());
+        assert!(!convo_2.is_encrypted());
+
+        // messages go in the clear
+        let ping_data_1 = PingData::new();
+        let ping_1 = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::Ping(ping_data_1.clone())).unwrap();
+        match ping_1.payload {
+            StacksMessageType::Ping(_) => {},
+            _ => panic!("Expected an unsealed ping")
+        };
+        let mut rh_ping_1 = convo_1.send_signed_request(ping_1.clone(), 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_ping_1], &mut convo_2);
+        convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
+
+        convo_send_recv(&mut convo_2, vec![&mut rh_ping_1], &mut convo_1);
+        convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
+
+        let reply_ping_1 = rh_ping_1.recv(0).unwrap();
+        match reply_ping_1.payload {
+            StacksMessageType::Pong(ref data) => {
+                assert_eq!(data.nonce, ping_data_1.nonce);
+            },
+            _ => panic!("Expected Pong")
+        };
+        assert!(!convo_1.peer_sealed);
+        assert!(!convo_2.peer_sealed);
+    }
+
+    #[test]
+    fn convo_getblocktxs() {
+        let conn_opts = ConnectionOptions::default();
+        let socketaddr_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+        let socketaddr_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8081);
+        
+        let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+
+        let burnchain = Burnchain {
+            peer_version: PEER_VERSION,
+            network_id: 0,
+            chain_name: "bitcoin".to_string(),
+            network_name: "testnet".to_string(),
+            working_dir: "/nope".to_string(),
+            consensus_hash_lifetime: 24,
+            stable_confirmations: 7,
+            first_block_height: 12300,
+            first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
+        };
+
+        let mut chain_view = BurnchainView {
+            burn_block_height: 12348,
+            burn_consensus_hash: ConsensusHash::from_hex("1111111111111111111111111111111111111111").unwrap(),
+            burn_stable_block_height: 12341,
+            burn_stable_consensus_hash: ConsensusHash::from_hex("2222222222222222222222222222222222222222").unwrap(),
+            last_consensus_hashes: HashMap::new()
+        };
+        chain_view.make_test_data();
+
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_getblocktxs_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_getblocktxs_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+
+        db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
+        db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
+
+        let local_peer_1 = PeerDB::get_local_peer(&peerdb_1.conn()).unwrap();
+        let local_peer_2 = PeerDB::get_local_peer(&peerdb_2.conn()).unwrap();
+
+        // peer 2 has this block
+        let burn_header_hash = BurnchainHeaderHash([0x11; 32]);
+        let block = make_test_block(3);
+        StacksChainState::store_block(&chainstate_2.blocks_path, &burn_header_hash, &block).unwrap();
+
+        let mut convo_1 = ConversationP2P::new(123, 456, &burnchain, &socketaddr_2, &conn_opts, true, 0);
+        let mut convo_2 = ConversationP2P::new(123, 456, &burnchain, &socketaddr_1, &conn_opts, true, 0);
+
+        let handshake_data_1 = HandshakeData::from_local_peer(&local_peer_1);
+        let handshake_1 = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::Handshake(handshake_data_1.clone())).unwrap();
+        let mut rh_handshake_1 = convo_1.send_signed_request(handshake_1.clone(), 1000000).unwrap();
+
+        convo_send_recv(&mut convo_1, vec![&mut rh_handshake_1], &mut convo_2);
+        convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
+
+        convo_send_recv(&mut convo_2, vec![&mut rh_handshake_1], &mut convo_1);
+        convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
+
+        let reply_handshake_1 = rh_handshake_1.recv(0).unwrap();
+        match reply_handshake_1.payload {
+            StacksMessageType::HandshakeAccept(_) => {},
+            _ => panic!("Expected HandshakeAccept")
+        };
+
+        let requests = vec![
+            // known block, valid indexes
+            GetBlockTxsData { burn_header_hash: burn_header_hash.clone(), block_hash: block.block_hash(), indexes: vec![1, 3] },
+            // known block, index out of range
+            GetBlockTxsData { burn_header_hash: burn_header_hash.clone(), block_hash: block.block_hash(), indexes: vec![1, 4] },
+            // unknown block
+            GetBlockTxsData { burn_header_hash: burn_header_hash.clone(), block_hash: BlockHeaderHash([0x22; 32]), indexes: vec![0] },
+        ];
+
+        for (i, request) in requests.into_iter().enumerate() {
+            let getblocktxs_1 = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::GetBlockTxs(request)).unwrap();
+            let mut rh_getblocktxs_1 = convo_1.send_signed_request(getblocktxs_1, 1000000).unwrap();
+
+            convo_send_recv(&mut convo_1, vec![&mut rh_getblocktxs_1], &mut convo_2);
+            convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
+
+            convo_send_recv(&mut convo_2, vec![&mut rh_getblocktxs_1], &mut convo_1);
+            convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
+
+            let reply_1 = rh_getblocktxs_1.recv(0).unwrap();
+            match (i, reply_1.payload) {
+                (0, StacksMessageType::BlockTxs(ref data)) => {
+                    assert_eq!(data.burn_header_hash, burn_header_hash);
+                    assert_eq!(data.block_hash, block.block_hash());
+                    assert_eq!(data.txs, vec![block.txs[1].clone(), block.txs[3].clone()]);
+                },
+                (1, StacksMessageType::Nack(ref data)) |
+                (2, StacksMessageType::Nack(ref data)) => {
+                    assert_eq!(data.error_code, NackErrorCodes::NoSuchBlock);
+                },
+                (_, payload) => panic!("Unexpected reply to request {}: {:?}", i, &payload)
+            };
+        }
+    }
+
     #[test]
     fn convo_handshake_ping_loop() {
         let conn_opts = ConnectionOptions::default();
@@ -2136,6 +2657,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -2149,8 +2671,8 @@ mod test {
         
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_handshake_ping_loop_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_handshake_ping_loop_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_handshake_ping_loop_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_handshake_ping_loop_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -2178,11 +2700,11 @@ mod test {
             // convo_2 receives the handshake and ping and processes both, and since no one is waiting for the handshake, will forward
             // it along to the chat caller (us)
             convo_send_recv(&mut convo_1, vec![&mut rh_handshake_1, &mut rh_ping_1], &mut convo_2);
-            let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+            let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
             // convo_1 has a handshakeaccept 
             convo_send_recv(&mut convo_2, vec![&mut rh_handshake_1, &mut rh_ping_1], &mut convo_1);
-            let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+            let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
             let reply_handshake_1 = rh_handshake_1.recv(0).unwrap();
             let reply_ping_1 = rh_ping_1.recv(0).unwrap();
@@ -2262,6 +2784,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -2275,8 +2798,8 @@ mod test {
         
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_nack_unsolicited_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_nack_unsolicited_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_nack_unsolicited_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_nack_unsolicited_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -2298,11 +2821,11 @@ mod test {
 
         // convo_2 will reply with a nack since peer_1 hasn't authenticated yet
         convo_send_recv(&mut convo_1, vec![&mut rh_ping_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 has a nack 
         convo_send_recv(&mut convo_2, vec![&mut rh_ping_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_1 = rh_ping_1.recv(0).unwrap();
        
@@ -2349,6 +2872,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -2360,8 +2884,8 @@ mod test {
         };
         chain_view.make_test_data();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_handshake_accept_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_handshake_accept_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_handshake_accept_1", &burnchain, 0x9abcdef0, 12350, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_handshake_accept_2", &burnchain, 0x9abcdef0, 12351, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -2385,12 +2909,12 @@ mod test {
         // it along to the chat caller (us)
         test_debug!("send handshake");
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 has a handshakeaccept 
         test_debug!("send handshake-accept");
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_1 = rh_1.recv(0).unwrap();
 
@@ -2424,7 +2948,7 @@ mod test {
         };
 
         // convo_1 sends a getblocksinv to convo_2 for all the blocks
-        let convo_1_chaintip = SortitionDB::get_canonical_burn_chain_tip_stubbed(sortdb_1.conn()).unwrap();
+        let convo_1_chaintip = SortitionDB::get_canonical_burn_chain_tip(sortdb_1.conn()).unwrap();
         let getblocksdata_1 = GetBlocksInv { consensus_hash: convo_1_chaintip.consensus_hash, num_blocks: BLOCKS_INV_DATA_MAX_BITLEN as u16 };
         let getblocksdata_1_msg = convo_1.sign_message(&chain_view, &local_peer_1.private_key, StacksMessageType::GetBlocksInv(getblocksdata_1.clone())).unwrap();
         let mut rh_1 = convo_1.send_signed_request(getblocksdata_1_msg, 10000000).unwrap();
@@ -2432,12 +2956,12 @@ mod test {
         // convo_2 receives it, and handles it
         test_debug!("send getblocksinv");
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 gets back a blocksinv message
         test_debug!("send blocksinv");
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_1 = rh_1.recv(0).unwrap();
 
@@ -2469,12 +2993,12 @@ mod test {
         // convo_2 receives it, and handles it
         test_debug!("send getblocksinv (diverged)");
         convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 gets back a nack message
         test_debug!("send nack (diverged)");
         convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let reply_1 = rh_1.recv(0).unwrap();
 
@@ -2511,6 +3035,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -2524,8 +3049,8 @@ mod test {
         
         let first_burn_hash = BurnchainHeaderHash::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
 
-        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs("convo_natpunch_1", &burnchain, 0x9abcdef0, 12352, "http://peer1.com".into(), &vec![], &vec![]);
-        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs("convo_natpunch_2", &burnchain, 0x9abcdef0, 12353, "http://peer2.com".into(), &vec![], &vec![]);
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1, mempool_1) = make_test_chain_dbs("convo_natpunch_1", &burnchain, 0x9abcdef0, 12352, "http://peer1.com".into(), &vec![], &vec![]);
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2, mempool_2) = make_test_chain_dbs("convo_natpunch_2", &burnchain, 0x9abcdef0, 12353, "http://peer2.com".into(), &vec![], &vec![]);
 
         db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
         db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
@@ -2543,12 +3068,12 @@ mod test {
         // convo_2 receives the natpunch request and processes it
         test_debug!("send natpunch {:?}", &natpunch_1);
         convo_send_recv(&mut convo_1, vec![&mut rh_natpunch_1], &mut convo_2);
-        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &chain_view).unwrap();
+        let unhandled_2 = convo_2.chat(&local_peer_2, &mut peerdb_2, &sortdb_2, &mut chainstate_2, &mempool_2, &chain_view).unwrap();
 
         // convo_1 gets back a natpunch reply
         test_debug!("reply natpunch-reply");
         convo_send_recv(&mut convo_2, vec![&mut rh_natpunch_1], &mut convo_1);
-        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &chain_view).unwrap();
+        let unhandled_1 = convo_1.chat(&local_peer_1, &mut peerdb_1, &sortdb_1, &mut chainstate_1, &mempool_1, &chain_view).unwrap();
 
         let natpunch_reply_1 = rh_natpunch_1.recv(0).unwrap();
 
@@ -2587,6 +3112,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut chain_view = BurnchainView {
@@ -2598,7 +3124,7 @@ mod test {
         };
         chain_view.make_test_data();
 
-        let mut peerdb_1 = PeerDB::connect_memory(0x9abcdef0, 0, 12350, "http://peer1.com".into(), &vec![], &vec![]).unwrap();
+        let mut peerdb_1 = PeerDB::connect_memory(0x9abcdef0, 0, 12350, "http://peer1.com".into(), &vec![], &vec![], &vec![]).unwrap();
         let mut sortdb_1 = SortitionDB::connect_test(12300, &first_burn_hash).unwrap();
         let mut sortdb_2 = SortitionDB::connect_test(12300, &first_burn_hash).unwrap();
         
@@ -2703,6 +3229,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 12300,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
         
         let mut chain_view = BurnchainView {
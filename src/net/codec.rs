@@ -24,6 +24,7 @@ use std::convert::TryFrom;
 use std::collections::HashSet;
 
 use burnchains::BurnchainHeaderHash;
+use burnchains::Txid;
 use burnchains::PrivateKey;
 use burnchains::PublicKey;
 use burnchains::BurnchainView;
@@ -760,6 +761,213 @@ impl StacksMessageCodec for NatPunchData {
     }
 }
 
+impl StacksMessageCodec for GetMempoolTxidsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.last_txid)?;
+        write_next(fd, &self.max_txids)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<GetMempoolTxidsData, net_error> {
+        let last_txid : Txid = read_next(fd)?;
+        let max_txids : u16 = read_next(fd)?;
+        if (max_txids as u32) > MEMPOOL_TXIDS_PAGE_MAX_LEN {
+            // requested too big of a page
+            return Err(net_error::DeserializeError(format!("Mempool txid page is too big ({})", max_txids)));
+        }
+
+        Ok(GetMempoolTxidsData {
+            last_txid,
+            max_txids
+        })
+    }
+}
+
+impl StacksMessageCodec for MempoolTxidsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.txids)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MempoolTxidsData, net_error> {
+        let txids : Vec<Txid> = read_next_at_most::<_, Txid>(fd, MEMPOOL_TXIDS_PAGE_MAX_LEN)?;
+
+        // only valid if sorted (by bytes, not by Txid's numeric ordering) and free of dups
+        for i in 1..txids.len() {
+            if txids[i-1].as_bytes() >= txids[i].as_bytes() {
+                return Err(net_error::DeserializeError("Invalid MempoolTxidsData: txids are not in ascending order".to_string()));
+            }
+        }
+
+        Ok(MempoolTxidsData {
+            txids
+        })
+    }
+}
+
+impl StacksMessageCodec for GetMempoolTxsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.txids)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<GetMempoolTxsData, net_error> {
+        let txids : Vec<Txid> = read_next_at_most::<_, Txid>(fd, MEMPOOL_TXS_REQUEST_MAX_LEN)?;
+        Ok(GetMempoolTxsData {
+            txids
+        })
+    }
+}
+
+impl StacksMessageCodec for MempoolTxsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.txs)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MempoolTxsData, net_error> {
+        let txs : Vec<StacksTransaction> = {
+            // loose upper-bound
+            let mut bound_read = BoundReader::from_reader(fd, MAX_MESSAGE_LEN as u64);
+            read_next_at_most::<_, StacksTransaction>(&mut bound_read, MEMPOOL_TXS_REQUEST_MAX_LEN)
+        }?;
+
+        Ok(MempoolTxsData {
+            txs
+        })
+    }
+}
+
+impl StacksMessageCodec for SealedData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.nonce)?;
+        write_next(fd, &self.ciphertext)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<SealedData, net_error> {
+        let nonce : u64 = read_next(fd)?;
+        let ciphertext : Vec<u8> = read_next_at_most::<_, u8>(fd, MAX_PAYLOAD_LEN)?;
+        Ok(SealedData {
+            nonce,
+            ciphertext
+        })
+    }
+}
+
+impl StacksMessageCodec for PrefilledTransaction {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.index)?;
+        write_next(fd, &self.tx)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<PrefilledTransaction, net_error> {
+        let index : u16 = read_next(fd)?;
+        let tx : StacksTransaction = read_next(fd)?;
+        Ok(PrefilledTransaction {
+            index,
+            tx
+        })
+    }
+}
+
+impl StacksMessageCodec for CompactBlockData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.burn_header_hash)?;
+        write_next(fd, &self.header)?;
+        write_next(fd, &self.short_txids)?;
+        write_next(fd, &self.prefilled_txs)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<CompactBlockData, net_error> {
+        let burn_header_hash : BurnchainHeaderHash = read_next(fd)?;
+        let header : StacksBlockHeader = read_next(fd)?;
+        let short_txids : Vec<ShortTxid> = read_next_at_most::<_, ShortTxid>(fd, COMPACT_BLOCK_MAX_TXS)?;
+        let prefilled_txs : Vec<PrefilledTransaction> = {
+            // loose upper-bound
+            let mut bound_read = BoundReader::from_reader(fd, MAX_MESSAGE_LEN as u64);
+            read_next_at_most::<_, PrefilledTransaction>(&mut bound_read, COMPACT_BLOCK_MAX_TXS)
+        }?;
+
+        let num_txs = (short_txids.len() as u64) + (prefilled_txs.len() as u64);
+        if num_txs > (COMPACT_BLOCK_MAX_TXS as u64) {
+            return Err(net_error::DeserializeError(format!("Invalid CompactBlockData: too many transactions ({})", num_txs)));
+        }
+
+        // prefilled transactions must be in ascending order, and must fall within the block
+        for i in 0..prefilled_txs.len() {
+            if (prefilled_txs[i].index as u64) >= num_txs {
+                return Err(net_error::DeserializeError("Invalid CompactBlockData: prefilled transaction index out of range".to_string()));
+            }
+            if i > 0 && prefilled_txs[i-1].index >= prefilled_txs[i].index {
+                return Err(net_error::DeserializeError("Invalid CompactBlockData: prefilled transactions are not in ascending order".to_string()));
+            }
+        }
+
+        Ok(CompactBlockData {
+            burn_header_hash,
+            header,
+            short_txids,
+            prefilled_txs
+        })
+    }
+}
+
+impl StacksMessageCodec for GetBlockTxsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.burn_header_hash)?;
+        write_next(fd, &self.block_hash)?;
+        write_next(fd, &self.indexes)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<GetBlockTxsData, net_error> {
+        let burn_header_hash : BurnchainHeaderHash = read_next(fd)?;
+        let block_hash : BlockHeaderHash = read_next(fd)?;
+        let indexes : Vec<u16> = read_next_at_most::<_, u16>(fd, COMPACT_BLOCK_MAX_TXS)?;
+
+        // only valid if sorted and free of dups
+        for i in 1..indexes.len() {
+            if indexes[i-1] >= indexes[i] {
+                return Err(net_error::DeserializeError("Invalid GetBlockTxsData: indexes are not in ascending order".to_string()));
+            }
+        }
+
+        Ok(GetBlockTxsData {
+            burn_header_hash,
+            block_hash,
+            indexes
+        })
+    }
+}
+
+impl StacksMessageCodec for BlockTxsData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
+        write_next(fd, &self.burn_header_hash)?;
+        write_next(fd, &self.block_hash)?;
+        write_next(fd, &self.txs)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<BlockTxsData, net_error> {
+        let burn_header_hash : BurnchainHeaderHash = read_next(fd)?;
+        let block_hash : BlockHeaderHash = read_next(fd)?;
+        let txs : Vec<StacksTransaction> = {
+            // loose upper-bound
+            let mut bound_read = BoundReader::from_reader(fd, MAX_MESSAGE_LEN as u64);
+            read_next_at_most::<_, StacksTransaction>(&mut bound_read, COMPACT_BLOCK_MAX_TXS)
+        }?;
+
+        Ok(BlockTxsData {
+            burn_header_hash,
+            block_hash,
+            txs
+        })
+    }
+}
+
 impl StacksMessageCodec for RelayData {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), net_error> {
         write_next(fd, &self.peer)?;
@@ -797,6 +1005,14 @@ impl StacksMessageType {
             StacksMessageType::Pong(ref _m) => StacksMessageID::Pong,
             StacksMessageType::NatPunchRequest(ref _m) => StacksMessageID::NatPunchRequest,
             StacksMessageType::NatPunchReply(ref _m) => StacksMessageID::NatPunchReply,
+            StacksMessageType::GetMempoolTxids(ref _m) => StacksMessageID::GetMempoolTxids,
+            StacksMessageType::MempoolTxids(ref _m) => StacksMessageID::MempoolTxids,
+            StacksMessageType::GetMempoolTxs(ref _m) => StacksMessageID::GetMempoolTxs,
+            StacksMessageType::MempoolTxs(ref _m) => StacksMessageID::MempoolTxs,
+            StacksMessageType::Sealed(ref _m) => StacksMessageID::Sealed,
+            StacksMessageType::CompactBlock(ref _m) => StacksMessageID::CompactBlock,
+            StacksMessageType::GetBlockTxs(ref _m) => StacksMessageID::GetBlockTxs,
+            StacksMessageType::BlockTxs(ref _m) => StacksMessageID::BlockTxs,
         }
     }
 
@@ -819,6 +1035,14 @@ impl StacksMessageType {
             StacksMessageType::Pong(ref _m) => "Pong",
             StacksMessageType::NatPunchRequest(ref _m) => "NatPunchRequest",
             StacksMessageType::NatPunchReply(ref _m) => "NatPunchReply",
+            StacksMessageType::GetMempoolTxids(ref _m) => "GetMempoolTxids",
+            StacksMessageType::MempoolTxids(ref _m) => "MempoolTxids",
+            StacksMessageType::GetMempoolTxs(ref _m) => "GetMempoolTxs",
+            StacksMessageType::MempoolTxs(ref _m) => "MempoolTxs",
+            StacksMessageType::Sealed(ref _m) => "Sealed",
+            StacksMessageType::CompactBlock(ref _m) => "CompactBlock",
+            StacksMessageType::GetBlockTxs(ref _m) => "GetBlockTxs",
+            StacksMessageType::BlockTxs(ref _m) => "BlockTxs",
         }
     }
 }
@@ -848,6 +1072,14 @@ impl StacksMessageCodec for StacksMessageID {
             x if x == StacksMessageID::Pong as u8 => StacksMessageID::Pong,
             x if x == StacksMessageID::NatPunchRequest as u8 => StacksMessageID::NatPunchRequest,
             x if x == StacksMessageID::NatPunchReply as u8 => StacksMessageID::NatPunchReply,
+            x if x == StacksMessageID::GetMempoolTxids as u8 => StacksMessageID::GetMempoolTxids,
+            x if x == StacksMessageID::MempoolTxids as u8 => StacksMessageID::MempoolTxids,
+            x if x == StacksMessageID::GetMempoolTxs as u8 => StacksMessageID::GetMempoolTxs,
+            x if x == StacksMessageID::MempoolTxs as u8 => StacksMessageID::MempoolTxs,
+            x if x == StacksMessageID::Sealed as u8 => StacksMessageID::Sealed,
+            x if x == StacksMessageID::CompactBlock as u8 => StacksMessageID::CompactBlock,
+            x if x == StacksMessageID::GetBlockTxs as u8 => StacksMessageID::GetBlockTxs,
+            x if x == StacksMessageID::BlockTxs as u8 => StacksMessageID::BlockTxs,
             _ => { return Err(net_error::DeserializeError("Unknown message ID".to_string())); }
         };
         Ok(id)
@@ -875,6 +1107,14 @@ impl StacksMessageCodec for StacksMessageType {
             StacksMessageType::Pong(ref m) => write_next(fd, m)?,
             StacksMessageType::NatPunchRequest(ref nonce) => write_next(fd, nonce)?,
             StacksMessageType::NatPunchReply(ref m) => write_next(fd, m)?,
+            StacksMessageType::GetMempoolTxids(ref m) => write_next(fd, m)?,
+            StacksMessageType::MempoolTxids(ref m) => write_next(fd, m)?,
+            StacksMessageType::GetMempoolTxs(ref m) => write_next(fd, m)?,
+            StacksMessageType::MempoolTxs(ref m) => write_next(fd, m)?,
+            StacksMessageType::Sealed(ref m) => write_next(fd, m)?,
+            StacksMessageType::CompactBlock(ref m) => write_next(fd, m)?,
+            StacksMessageType::GetBlockTxs(ref m) => write_next(fd, m)?,
+            StacksMessageType::BlockTxs(ref m) => write_next(fd, m)?,
         }
         Ok(())
     }
@@ -899,6 +1139,14 @@ impl StacksMessageCodec for StacksMessageType {
             StacksMessageID::Pong => { let m : PongData = read_next(fd)?; StacksMessageType::Pong(m) },
             StacksMessageID::NatPunchRequest => { let nonce : u32 = read_next(fd)?; StacksMessageType::NatPunchRequest(nonce) },
             StacksMessageID::NatPunchReply => { let m : NatPunchData = read_next(fd)?; StacksMessageType::NatPunchReply(m) },
+            StacksMessageID::GetMempoolTxids => { let m : GetMempoolTxidsData = read_next(fd)?; StacksMessageType::GetMempoolTxids(m) },
+            StacksMessageID::MempoolTxids => { let m : MempoolTxidsData = read_next(fd)?; StacksMessageType::MempoolTxids(m) },
+            StacksMessageID::GetMempoolTxs => { let m : GetMempoolTxsData = read_next(fd)?; StacksMessageType::GetMempoolTxs(m) },
+            StacksMessageID::MempoolTxs => { let m : MempoolTxsData = read_next(fd)?; StacksMessageType::MempoolTxs(m) },
+            StacksMessageID::Sealed => { let m : SealedData = read_next(fd)?; StacksMessageType::Sealed(m) },
+            StacksMessageID::CompactBlock => { let m : CompactBlockData = read_next(fd)?; StacksMessageType::CompactBlock(m) },
+            StacksMessageID::GetBlockTxs => { let m : GetBlockTxsData = read_next(fd)?; StacksMessageType::GetBlockTxs(m) },
+            StacksMessageID::BlockTxs => { let m : BlockTxsData = read_next(fd)?; StacksMessageType::BlockTxs(m) },
             StacksMessageID::Reserved => { return Err(net_error::DeserializeError("Unsupported message ID 'reserved'".to_string())); }
         };
         Ok(message)
@@ -1599,6 +1847,215 @@ pub mod test {
         check_codec_and_corruption::<NatPunchData>(&data, &bytes);
     }
 
+    #[test]
+    fn codec_GetMempoolTxids() {
+        let data = GetMempoolTxidsData {
+            last_txid: Txid([0x11; 32]),
+            max_txids: 8
+        };
+        let bytes = vec![
+            // last txid
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+            // max txids
+            0x00, 0x08
+        ];
+
+        check_codec_and_corruption::<GetMempoolTxidsData>(&data, &bytes);
+
+        // should fail to decode if the page is too big
+        let data_too_big = GetMempoolTxidsData {
+            last_txid: Txid([0x11; 32]),
+            max_txids: (MEMPOOL_TXIDS_PAGE_MAX_LEN + 1) as u16
+        };
+
+        assert!(check_deserialize_failure::<GetMempoolTxidsData>(&data_too_big));
+    }
+
+    #[test]
+    fn codec_MempoolTxids() {
+        let data = MempoolTxidsData {
+            txids: vec![Txid([0x11; 32]), Txid([0x22; 32])]
+        };
+        let bytes = vec![
+            // length
+            0x00, 0x00, 0x00, 0x02,
+            // txids
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+            0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+        ];
+
+        check_codec_and_corruption::<MempoolTxidsData>(&data, &bytes);
+
+        // txids must be sorted and unique
+        let unsorted = MempoolTxidsData {
+            txids: vec![Txid([0x22; 32]), Txid([0x11; 32])]
+        };
+        assert!(check_deserialize_failure::<MempoolTxidsData>(&unsorted));
+
+        let duplicated = MempoolTxidsData {
+            txids: vec![Txid([0x11; 32]), Txid([0x11; 32])]
+        };
+        assert!(check_deserialize_failure::<MempoolTxidsData>(&duplicated));
+
+        // should fail to decode if the page is too big
+        let too_many = MempoolTxidsData {
+            txids: (0..MEMPOOL_TXIDS_PAGE_MAX_LEN + 1).map(|i| Txid([i as u8; 32])).collect()
+        };
+        assert!(check_deserialize_failure::<MempoolTxidsData>(&too_many));
+    }
+
+    #[test]
+    fn codec_GetMempoolTxs() {
+        let data = GetMempoolTxsData {
+            txids: vec![Txid([0x11; 32])]
+        };
+        let bytes = vec![
+            // length
+            0x00, 0x00, 0x00, 0x01,
+            // txids
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+        ];
+
+        check_codec_and_corruption::<GetMempoolTxsData>(&data, &bytes);
+
+        // should fail to decode if we ask for too many
+        let too_many = GetMempoolTxsData {
+            txids: (0..MEMPOOL_TXS_REQUEST_MAX_LEN + 1).map(|i| Txid([i as u8; 32])).collect()
+        };
+        assert!(check_deserialize_failure::<GetMempoolTxsData>(&too_many));
+    }
+
+    #[test]
+    fn codec_SealedData() {
+        let data = SealedData {
+            nonce: 0x0102030405060708,
+            ciphertext: vec![0x11, 0x22, 0x33]
+        };
+        let bytes = vec![
+            // nonce
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+            // ciphertext length
+            0x00, 0x00, 0x00, 0x03,
+            // ciphertext
+            0x11, 0x22, 0x33
+        ];
+
+        check_codec_and_corruption::<SealedData>(&data, &bytes);
+    }
+
+    fn make_codec_test_transaction() -> StacksTransaction {
+        use chainstate::stacks::*;
+        use chainstate::stacks::test::codec_all_transactions;
+        let txs = codec_all_transactions(&TransactionVersion::Testnet, 0x80000000, &TransactionAnchorMode::OnChainOnly, &TransactionPostConditionMode::Allow);
+        txs[0].clone()
+    }
+
+    #[test]
+    fn codec_CompactBlockData() {
+        let header = StacksBlockHeader::genesis_block_header();
+        let tx = make_codec_test_transaction();
+        let data = CompactBlockData {
+            burn_header_hash: BurnchainHeaderHash([0x11; 32]),
+            header: header.clone(),
+            short_txids: vec![ShortTxid([0x22; 8]), ShortTxid([0x33; 8])],
+            prefilled_txs: vec![PrefilledTransaction { index: 1, tx: tx.clone() }]
+        };
+
+        let mut bytes = vec![
+            // burn header hash
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+        ];
+        // header
+        bytes.append(&mut header.serialize_to_vec());
+        bytes.append(&mut vec![
+            // short txids length
+            0x00, 0x00, 0x00, 0x02,
+            // short txids
+            0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+            0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33,
+            // prefilled txs length
+            0x00, 0x00, 0x00, 0x01,
+            // prefilled tx index
+            0x00, 0x01,
+        ]);
+        // prefilled tx
+        bytes.append(&mut tx.serialize_to_vec());
+
+        check_codec_and_corruption::<CompactBlockData>(&data, &bytes);
+
+        // prefilled transactions must fall within the block
+        let mut out_of_range = data.clone();
+        out_of_range.prefilled_txs[0].index = 3;
+        assert!(check_deserialize_failure::<CompactBlockData>(&out_of_range));
+
+        // prefilled transactions must be in ascending order
+        let mut unsorted = data.clone();
+        unsorted.prefilled_txs = vec![PrefilledTransaction { index: 2, tx: tx.clone() }, PrefilledTransaction { index: 1, tx: tx.clone() }];
+        unsorted.short_txids.pop();
+        assert!(check_deserialize_failure::<CompactBlockData>(&unsorted));
+
+        let mut duplicated = data.clone();
+        duplicated.prefilled_txs = vec![PrefilledTransaction { index: 1, tx: tx.clone() }, PrefilledTransaction { index: 1, tx: tx.clone() }];
+        duplicated.short_txids.pop();
+        assert!(check_deserialize_failure::<CompactBlockData>(&duplicated));
+    }
+
+    #[test]
+    fn codec_GetBlockTxs() {
+        let data = GetBlockTxsData {
+            burn_header_hash: BurnchainHeaderHash([0x11; 32]),
+            block_hash: BlockHeaderHash([0x22; 32]),
+            indexes: vec![1, 0x0203]
+        };
+        let bytes = vec![
+            // burn header hash
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+            // block hash
+            0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+            // indexes length
+            0x00, 0x00, 0x00, 0x02,
+            // indexes
+            0x00, 0x01, 0x02, 0x03
+        ];
+
+        check_codec_and_corruption::<GetBlockTxsData>(&data, &bytes);
+
+        // indexes must be sorted and unique
+        let unsorted = GetBlockTxsData {
+            indexes: vec![2, 1],
+            ..data.clone()
+        };
+        assert!(check_deserialize_failure::<GetBlockTxsData>(&unsorted));
+
+        let duplicated = GetBlockTxsData {
+            indexes: vec![1, 1],
+            ..data.clone()
+        };
+        assert!(check_deserialize_failure::<GetBlockTxsData>(&duplicated));
+    }
+
+    #[test]
+    fn codec_BlockTxs() {
+        let tx = make_codec_test_transaction();
+        let data = BlockTxsData {
+            burn_header_hash: BurnchainHeaderHash([0x11; 32]),
+            block_hash: BlockHeaderHash([0x22; 32]),
+            txs: vec![tx.clone()]
+        };
+        let mut bytes = vec![
+            // burn header hash
+            0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+            // block hash
+            0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+            // txs length
+            0x00, 0x00, 0x00, 0x01,
+        ];
+        // txs
+        bytes.append(&mut tx.serialize_to_vec());
+
+        check_codec_and_corruption::<BlockTxsData>(&data, &bytes);
+    }
+
     #[test]
     fn codec_StacksMessage() {
         let payloads: Vec<StacksMessageType> = vec![
@@ -1670,6 +2127,39 @@ pub mod test {
                 port: 12345,
                 nonce: 0x12345678
             }),
+            StacksMessageType::GetMempoolTxids(GetMempoolTxidsData {
+                last_txid: Txid([0x11; 32]),
+                max_txids: 8
+            }),
+            StacksMessageType::MempoolTxids(MempoolTxidsData {
+                txids: vec![Txid([0x11; 32]), Txid([0x22; 32])]
+            }),
+            StacksMessageType::GetMempoolTxs(GetMempoolTxsData {
+                txids: vec![Txid([0x11; 32]), Txid([0x22; 32])]
+            }),
+            StacksMessageType::MempoolTxs(MempoolTxsData {
+                txs: vec![]
+            }),
+            StacksMessageType::Sealed(SealedData {
+                nonce: 0x0102030405060708,
+                ciphertext: vec![0x11, 0x22, 0x33, 0x44]
+            }),
+            StacksMessageType::CompactBlock(CompactBlockData {
+                burn_header_hash: BurnchainHeaderHash([0x11; 32]),
+                header: StacksBlockHeader::genesis_block_header(),
+                short_txids: vec![ShortTxid([0x22; 8]), ShortTxid([0x33; 8])],
+                prefilled_txs: vec![]
+            }),
+            StacksMessageType::GetBlockTxs(GetBlockTxsData {
+                burn_header_hash: BurnchainHeaderHash([0x11; 32]),
+                block_hash: BlockHeaderHash([0x22; 32]),
+                indexes: vec![1, 2, 3]
+            }),
+            StacksMessageType::BlockTxs(BlockTxsData {
+                burn_header_hash: BurnchainHeaderHash([0x11; 32]),
+                block_hash: BlockHeaderHash([0x22; 32]),
+                txs: vec![]
+            }),
         ];
 
         let mut maximal_relayers : Vec<RelayData> = vec![];
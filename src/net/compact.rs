@@ -0,0 +1,915 @@
+/*
+ copyright: (c) 2013-2020 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use net::NeighborKey;
+use net::Error as net_error;
+
+use net::*;
+
+use net::connection::ReplyHandleP2P;
+
+use net::p2p::PeerNetwork;
+
+use burnchains::Txid;
+use burnchains::BurnchainHeaderHash;
+
+use chainstate::burn::BlockHeaderHash;
+
+use chainstate::stacks::StacksBlock;
+use chainstate::stacks::StacksBlockHeader;
+use chainstate::stacks::StacksBlockId;
+use chainstate::stacks::StacksTransaction;
+use chainstate::stacks::TransactionPayload;
+
+use core::mempool::MemPoolDB;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use util::log;
+use util::hash::MerkleTree;
+use util::hash::Sha512Trunc256Sum;
+
+use sha2::Sha512Trunc256;
+use sha2::Digest;
+
+use monitoring;
+
+/// This module implements compact block relay.  Instead of pushing a whole anchored block to a
+/// peer, we push its header and a short ID for each of its transactions.  The peer fills in the
+/// transactions it already has in its mempool, and asks us for the rest with a GetBlockTxs.  If
+/// the reassembled block doesn't match its header (i.e. a short ID matched the wrong
+/// transaction), the peer falls back to asking for every transaction in the block.  If we can't
+/// serve it, the peer will eventually fetch the block through its block downloader.
+
+/// Maximum number of compact blocks we'll reassemble at once
+pub const COMPACT_BLOCKS_MAX_PENDING : usize = BLOCKS_PUSHED_MAX as usize;
+
+impl ShortTxid {
+    /// Short ID of a transaction in a given block.  Salting with the block hash means that two
+    /// transactions that collide in one block almost certainly won't collide in the next.
+    ///
+    /// There is no per-connection salt, so a miner can grind its block until two of its
+    /// transactions (or one of them and a mempool transaction) share a short ID.  This is not
+    /// worth defending against: a reassembled block is only accepted if it matches the header's
+    /// transaction Merkle root, so a collision can't make a peer accept the wrong block.  At
+    /// worst, peers fall back to asking for every transaction in the block, which costs them no
+    /// more than having the whole block pushed to them -- and only slows down the relay of the
+    /// grinding miner's own block.  Anyone else would need to find a second preimage of a 64-bit
+    /// short ID for a block hash they only learn once the block is relayed.
+    pub fn from_txid(block_hash: &BlockHeaderHash, txid: &Txid) -> ShortTxid {
+        let mut sha2 = Sha512Trunc256::new();
+        sha2.input(block_hash.as_bytes());
+        sha2.input(txid.as_bytes());
+
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&sha2.result()[0..8]);
+        ShortTxid(bytes)
+    }
+}
+
+impl CompactBlockData {
+    /// Compact an anchored block.  The coinbase is sent in full, since no one else has it.
+    pub fn from_block(burn_header_hash: &BurnchainHeaderHash, block: &StacksBlock) -> CompactBlockData {
+        let block_hash = block.block_hash();
+        let mut short_txids = vec![];
+        let mut prefilled_txs = vec![];
+        for (i, tx) in block.txs.iter().enumerate() {
+            match tx.payload {
+                TransactionPayload::Coinbase(_) => {
+                    prefilled_txs.push(PrefilledTransaction {
+                        index: i as u16,
+                        tx: tx.clone()
+                    });
+                },
+                _ => {
+                    short_txids.push(ShortTxid::from_txid(&block_hash, &tx.txid()));
+                }
+            }
+        }
+
+        CompactBlockData {
+            burn_header_hash: burn_header_hash.clone(),
+            header: block.header.clone(),
+            short_txids: short_txids,
+            prefilled_txs: prefilled_txs
+        }
+    }
+
+    /// Convert a Blocks message into one CompactBlock message per block.
+    /// Returns None if the message isn't a Blocks message, or if it has a block with too many
+    /// transactions to index.
+    pub fn from_blocks_payload(payload: &StacksMessageType) -> Option<Vec<StacksMessageType>> {
+        match *payload {
+            StacksMessageType::Blocks(ref blocks_data) => {
+                let mut compact_payloads = vec![];
+                for (burn_header_hash, block) in blocks_data.blocks.iter() {
+                    if (block.txs.len() as u64) > (COMPACT_BLOCK_MAX_TXS as u64) {
+                        return None;
+                    }
+                    compact_payloads.push(StacksMessageType::CompactBlock(CompactBlockData::from_block(burn_header_hash, block)));
+                }
+                Some(compact_payloads)
+            },
+            _ => None
+        }
+    }
+
+    pub fn num_txs(&self) -> usize {
+        self.short_txids.len() + self.prefilled_txs.len()
+    }
+}
+
+/// Mempool transactions that have the short IDs we were looking for in a given block
+#[derive(Debug, Clone, PartialEq)]
+pub struct MempoolCandidates {
+    /// short IDs we looked for
+    short_txids: HashSet<ShortTxid>,
+    /// our transactions that have them
+    txids: HashMap<ShortTxid, Vec<Txid>>
+}
+
+impl MempoolCandidates {
+    /// Find the transactions in our mempool with the given short IDs.
+    /// This walks the whole mempool, which is bounded in size.
+    pub fn find(mempool: &MemPoolDB, block_hash: &BlockHeaderHash, short_txids: HashSet<ShortTxid>) -> Result<MempoolCandidates, net_error> {
+        let mut txids : HashMap<ShortTxid, Vec<Txid>> = HashMap::new();
+        let mut last_txid = Txid([0u8; 32]);
+        loop {
+            let page = MemPoolDB::get_txid_page(mempool.conn(), &last_txid, MEMPOOL_TXIDS_PAGE_MAX_LEN as u64)
+                .map_err(net_error::DBError)?;
+
+            for txid in page.iter() {
+                let short_txid = ShortTxid::from_txid(block_hash, txid);
+                if short_txids.contains(&short_txid) {
+                    txids.entry(short_txid).or_insert(vec![]).push(txid.clone());
+                }
+            }
+
+            match page.last() {
+                Some(txid) if (page.len() as u32) == MEMPOOL_TXIDS_PAGE_MAX_LEN => {
+                    last_txid = txid.clone();
+                },
+                _ => {
+                    break;
+                }
+            }
+        }
+
+        Ok(MempoolCandidates {
+            short_txids: short_txids,
+            txids: txids
+        })
+    }
+
+    /// Did we look for all of these short IDs?
+    pub fn covers(&self, short_txids: &[ShortTxid]) -> bool {
+        short_txids.iter().all(|short_txid| self.short_txids.contains(short_txid))
+    }
+}
+
+/// An anchored block being reassembled from a compact block
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialBlock {
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub header: StacksBlockHeader,
+    txs: Vec<Option<StacksTransaction>>
+}
+
+impl PartialBlock {
+    /// Fill in as much of a compact block as we can from our mempool.  Short IDs that match more
+    /// than one transaction -- either in the block or in our mempool -- are left for us to ask for.
+    pub fn from_compact(mempool: &MemPoolDB, compact: &CompactBlockData) -> Result<PartialBlock, net_error> {
+        let candidates = MempoolCandidates::find(mempool, &compact.header.block_hash(), compact.short_txids.iter().cloned().collect())?;
+        PartialBlock::from_compact_candidates(mempool, compact, &candidates)
+    }
+
+    /// Fill in as much of a compact block as we can, given the mempool transactions that have
+    /// its short IDs.
+    pub fn from_compact_candidates(mempool: &MemPoolDB, compact: &CompactBlockData, candidates: &MempoolCandidates) -> Result<PartialBlock, net_error> {
+        let num_txs = compact.num_txs();
+
+        let mut txs : Vec<Option<StacksTransaction>> = vec![None; num_txs];
+        for prefilled in compact.prefilled_txs.iter() {
+            match txs.get_mut(prefilled.index as usize) {
+                Some(slot) => {
+                    *slot = Some(prefilled.tx.clone());
+                },
+                None => {
+                    return Err(net_error::InvalidMessage);
+                }
+            }
+        }
+
+        // where does each short ID go?
+        let mut positions : HashMap<ShortTxid, Vec<usize>> = HashMap::new();
+        let mut short_txids = compact.short_txids.iter();
+        for i in 0..num_txs {
+            if txs[i].is_some() {
+                continue;
+            }
+            let short_txid = short_txids.next().ok_or(net_error::InvalidMessage)?;
+            positions.entry(short_txid.clone()).or_insert(vec![]).push(i);
+        }
+
+        for (short_txid, indexes) in positions.iter() {
+            let txids = match candidates.txids.get(short_txid) {
+                Some(txids) => txids,
+                None => {
+                    continue;
+                }
+            };
+            if indexes.len() != 1 || txids.len() != 1 {
+                // ambiguous
+                continue;
+            }
+            if let Some(tx_info) = MemPoolDB::get_tx(mempool.conn(), &txids[0]).map_err(net_error::DBError)? {
+                txs[indexes[0]] = Some(tx_info.tx);
+            }
+        }
+
+        Ok(PartialBlock {
+            burn_header_hash: compact.burn_header_hash.clone(),
+            header: compact.header.clone(),
+            txs: txs
+        })
+    }
+
+    pub fn block_hash(&self) -> BlockHeaderHash {
+        self.header.block_hash()
+    }
+
+    pub fn index_block_hash(&self) -> StacksBlockId {
+        self.header.index_block_hash(&self.burn_header_hash)
+    }
+
+    /// Positions of the transactions we don't have yet
+    pub fn missing_indexes(&self) -> Vec<u16> {
+        self.txs.iter().enumerate().filter(|(_, tx)| tx.is_none()).map(|(i, _)| i as u16).collect()
+    }
+
+    /// Positions of all transactions in the block
+    pub fn all_indexes(&self) -> Vec<u16> {
+        (0..self.txs.len()).map(|i| i as u16).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.txs.iter().all(|tx| tx.is_some())
+    }
+
+    /// Fill in the transactions at the given positions.
+    /// Returns false if we weren't given exactly one transaction per position.
+    pub fn fill(&mut self, indexes: &Vec<u16>, txs: Vec<StacksTransaction>) -> bool {
+        if indexes.len() != txs.len() {
+            return false;
+        }
+        for (index, tx) in indexes.iter().zip(txs.into_iter()) {
+            match self.txs.get_mut(*index as usize) {
+                Some(slot) => {
+                    *slot = Some(tx);
+                },
+                None => {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Assemble the block, if we have all of its transactions and they match its header.
+    /// Performs the same static checks on the transactions that decoding a block would.
+    pub fn try_assemble(&self) -> Option<StacksBlock> {
+        if !self.is_complete() {
+            return None;
+        }
+
+        let txs : Vec<StacksTransaction> = self.txs.iter().map(|tx| tx.clone().expect("BUG: incomplete block")).collect();
+        let txid_vecs = txs
+            .iter()
+            .map(|tx| tx.txid().as_bytes().to_vec())
+            .collect();
+
+        let merkle_tree = MerkleTree::<Sha512Trunc256Sum>::new(&txid_vecs);
+        if merkle_tree.root() != self.header.tx_merkle_root {
+            test_debug!("Reassembled block {} does not match its tx Merkle root", self.block_hash());
+            return None;
+        }
+
+        if !StacksBlock::validate_anchor_mode(&txs, true) || !StacksBlock::validate_transactions_unique(&txs) || !StacksBlock::validate_coinbase(&txs, true) {
+            test_debug!("Reassembled block {} has invalid transactions", self.block_hash());
+            return None;
+        }
+
+        Some(StacksBlock {
+            header: self.header.clone(),
+            txs: txs
+        })
+    }
+}
+
+/// A compact block we're asking a peer for transactions for
+struct PendingCompactBlock {
+    neighbor_key: NeighborKey,
+    partial: PartialBlock,
+    /// Transactions to ask for (or that we asked for)
+    indexes: Vec<u16>,
+    /// In-flight GetBlockTxs request
+    request: Option<ReplyHandleP2P>,
+    /// Did we give up on reassembly, and ask for the whole block?
+    full_block: bool,
+    /// Number of bytes of compact block and BlockTxs data we've received
+    num_bytes: u64
+}
+
+pub struct CompactBlockState {
+    /// Compact blocks we're waiting on transactions for
+    pending: HashMap<StacksBlockId, PendingCompactBlock>,
+
+    /// Blocks we reassembled, and who sent them
+    pub reassembled: Vec<(NeighborKey, BurnchainHeaderHash, StacksBlock)>,
+
+    /// Mempool candidates for the short IDs of recent compact blocks, so that a block relayed
+    /// again (e.g. after its first sender failed us) doesn't cost another walk of the mempool.
+    mempool_candidates: HashMap<BlockHeaderHash, MempoolCandidates>,
+    /// Order in which the candidates were found, oldest first
+    mempool_candidates_order: VecDeque<BlockHeaderHash>,
+
+    /// Peers that sent protocol-violating data
+    pub broken_peers: HashSet<NeighborKey>,
+    /// Peers that we couldn't contact
+    pub dead_peers: HashSet<NeighborKey>,
+
+    /// How long is a request allowed to take?
+    request_timeout: u64,
+
+    /// Running totals
+    pub num_blocks_received: u64,
+    pub num_blocks_reassembled: u64,
+    pub num_full_block_fallbacks: u64,
+    pub num_bytes_saved: u64,
+}
+
+impl CompactBlockState {
+    pub fn new(request_timeout: u64) -> CompactBlockState {
+        CompactBlockState {
+            pending: HashMap::new(),
+            reassembled: vec![],
+            mempool_candidates: HashMap::new(),
+            mempool_candidates_order: VecDeque::new(),
+            broken_peers: HashSet::new(),
+            dead_peers: HashSet::new(),
+            request_timeout: request_timeout,
+            num_blocks_received: 0,
+            num_blocks_reassembled: 0,
+            num_full_block_fallbacks: 0,
+            num_bytes_saved: 0,
+        }
+    }
+
+    /// How many compact blocks are we still reassembling?
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Record a reassembled block, and how many bytes we saved by not having it pushed to us
+    /// whole.  Never counts negative savings.
+    fn finish_block(&mut self, neighbor_key: NeighborKey, block: StacksBlock, burn_header_hash: BurnchainHeaderHash, num_bytes: u64) -> () {
+        let full_size = block.serialize_to_vec().len() as u64;
+        let saved = full_size.saturating_sub(num_bytes);
+
+        debug!("Reassembled block {}/{} from {:?}: received {} bytes instead of {}", &burn_header_hash, block.block_hash(), &neighbor_key, num_bytes, full_size);
+
+        self.num_blocks_reassembled += 1;
+        self.num_bytes_saved += saved;
+        monitoring::increment_p2p_compact_blocks_reassembled_counter();
+        monitoring::increment_p2p_compact_block_bytes_saved_counter(saved);
+
+        self.reassembled.push((neighbor_key, burn_header_hash, block));
+    }
+
+    /// Get the mempool candidates for a compact block's short IDs, walking the mempool only if we
+    /// haven't already looked for them.
+    fn get_mempool_candidates(&mut self, mempool: &MemPoolDB, compact: &CompactBlockData) -> Result<MempoolCandidates, net_error> {
+        let block_hash = compact.header.block_hash();
+        if let Some(candidates) = self.mempool_candidates.get(&block_hash) {
+            if candidates.covers(&compact.short_txids) {
+                return Ok(candidates.clone());
+            }
+        }
+
+        let candidates = MempoolCandidates::find(mempool, &block_hash, compact.short_txids.iter().cloned().collect())?;
+        if self.mempool_candidates.insert(block_hash.clone(), candidates.clone()).is_none() {
+            self.mempool_candidates_order.push_back(block_hash);
+            if self.mempool_candidates_order.len() > COMPACT_BLOCKS_MAX_PENDING {
+                if let Some(oldest) = self.mempool_candidates_order.pop_front() {
+                    self.mempool_candidates.remove(&oldest);
+                }
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Start reassembling a compact block from our mempool.  If we have all of its transactions,
+    /// the block is done; otherwise, we'll need to ask the peer for the rest.
+    pub fn add_compact_block(&mut self, mempool: &MemPoolDB, neighbor_key: &NeighborKey, compact: &CompactBlockData) -> Result<(), net_error> {
+        self.num_blocks_received += 1;
+
+        let index_block_hash = compact.header.index_block_hash(&compact.burn_header_hash);
+        if self.pending.contains_key(&index_block_hash) {
+            debug!("Already reassembling block {} (sent again by {:?})", &index_block_hash, neighbor_key);
+            return Ok(());
+        }
+        if self.reassembled.iter().any(|(_, burn_header_hash, block)| block.index_block_hash(burn_header_hash) == index_block_hash) {
+            debug!("Already reassembled block {} (sent again by {:?})", &index_block_hash, neighbor_key);
+            return Ok(());
+        }
+        if self.pending.len() >= COMPACT_BLOCKS_MAX_PENDING {
+            debug!("Too many compact blocks pending; dropping {} from {:?}", &index_block_hash, neighbor_key);
+            return Ok(());
+        }
+
+        let num_bytes = compact.serialize_to_vec().len() as u64;
+        let candidates = self.get_mempool_candidates(mempool, compact)?;
+        let partial = PartialBlock::from_compact_candidates(mempool, compact, &candidates)?;
+        if let Some(block) = partial.try_assemble() {
+            self.finish_block(neighbor_key.clone(), block, partial.burn_header_hash, num_bytes);
+            return Ok(());
+        }
+
+        let (indexes, full_block) =
+            if partial.is_complete() {
+                // we have a transaction for every short ID, but at least one is wrong
+                debug!("Compact block {} does not match our mempool; will ask {:?} for all of its transactions", &index_block_hash, neighbor_key);
+                self.num_full_block_fallbacks += 1;
+                monitoring::increment_p2p_compact_block_fallbacks_counter();
+                (partial.all_indexes(), true)
+            }
+            else {
+                (partial.missing_indexes(), false)
+            };
+
+        debug!("Compact block {} from {:?} is missing {} of {} transactions", &index_block_hash, neighbor_key, indexes.len(), compact.num_txs());
+
+        self.pending.insert(index_block_hash, PendingCompactBlock {
+            neighbor_key: neighbor_key.clone(),
+            partial: partial,
+            indexes: indexes,
+            request: None,
+            full_block: full_block,
+            num_bytes: num_bytes
+        });
+        Ok(())
+    }
+
+    /// Handle a peer's reply to our GetBlockTxs.
+    /// Returns the pending block back if we need to ask for more transactions.
+    fn handle_blocktxs_reply(&mut self, mut pending: PendingCompactBlock, message: StacksMessage) -> Option<PendingCompactBlock> {
+        let nk = pending.neighbor_key.clone();
+        match message.payload {
+            StacksMessageType::BlockTxs(block_txs) => {
+                if block_txs.burn_header_hash != pending.partial.burn_header_hash || block_txs.block_hash != pending.partial.block_hash() {
+                    debug!("Remote neighbor {:?} sent transactions for the wrong block", &nk);
+                    self.broken_peers.insert(nk);
+                    return None;
+                }
+
+                pending.num_bytes += block_txs.serialize_to_vec().len() as u64;
+                let indexes = pending.indexes.clone();
+                if !pending.partial.fill(&indexes, block_txs.txs) {
+                    debug!("Remote neighbor {:?} did not send the transactions we asked for", &nk);
+                    self.broken_peers.insert(nk);
+                    return None;
+                }
+
+                if let Some(block) = pending.partial.try_assemble() {
+                    self.finish_block(nk, block, pending.partial.burn_header_hash.clone(), pending.num_bytes);
+                    return None;
+                }
+
+                if pending.full_block {
+                    // the peer sent us every transaction, and they still don't match the header
+                    debug!("Remote neighbor {:?} sent transactions that do not match block {}", &nk, pending.partial.index_block_hash());
+                    self.broken_peers.insert(nk);
+                    return None;
+                }
+
+                // one of the transactions we took from our mempool was wrong
+                debug!("Block {} does not match our mempool; will ask {:?} for all of its transactions", pending.partial.index_block_hash(), &nk);
+                self.num_full_block_fallbacks += 1;
+                monitoring::increment_p2p_compact_block_fallbacks_counter();
+
+                pending.indexes = pending.partial.all_indexes();
+                pending.full_block = true;
+                pending.request = None;
+                Some(pending)
+            },
+            StacksMessageType::Nack(nack_data) => {
+                // the block downloader will get it
+                debug!("Remote neighbor {:?} nack'ed our GetBlockTxs: NACK code {}", &nk, nack_data.error_code);
+                None
+            },
+            _ => {
+                // unexpected reply
+                debug!("Remote neighbor {:?} sent an unexpected reply of '{}'", &nk, message.get_message_name());
+                self.broken_peers.insert(nk);
+                None
+            }
+        }
+    }
+
+    /// Send GetBlockTxs requests for the compact blocks we haven't asked about yet, and poll the
+    /// ones we have.
+    pub fn getblocktxs_run(&mut self, network: &mut PeerNetwork) -> Result<(), net_error> {
+        let mut still_pending = HashMap::new();
+        for (index_block_hash, mut pending) in self.pending.drain().collect::<Vec<_>>().into_iter() {
+            let nk = pending.neighbor_key.clone();
+            let mut rh = match pending.request.take() {
+                Some(rh) => rh,
+                None => {
+                    let request = GetBlockTxsData {
+                        burn_header_hash: pending.partial.burn_header_hash.clone(),
+                        block_hash: pending.partial.block_hash(),
+                        indexes: pending.indexes.clone()
+                    };
+
+                    debug!("{:?}: send getblocktxs request for {} transactions in {} to {:?}", &network.local_peer, request.indexes.len(), &index_block_hash, &nk);
+
+                    let message = match network.sign_for_peer(&nk, StacksMessageType::GetBlockTxs(request)) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            debug!("Failed to sign GetBlockTxs for {:?}: {:?}", &nk, &e);
+                            continue;
+                        }
+                    };
+                    match network.send_message(&nk, message, self.request_timeout) {
+                        Ok(rh) => rh,
+                        Err(e) => {
+                            debug!("Failed to send GetBlockTxs to {:?}: {:?}", &nk, &e);
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            if let Err(_e) = network.saturate_p2p_socket(rh.get_event_id(), &mut rh) {
+                self.dead_peers.insert(nk);
+                continue;
+            }
+            match rh.try_send_recv() {
+                Ok(message) => {
+                    if let Some(pending) = self.handle_blocktxs_reply(pending, message) {
+                        still_pending.insert(index_block_hash, pending);
+                    }
+                },
+                Err(Ok(same_req)) => {
+                    // try again
+                    pending.request = Some(same_req);
+                    still_pending.insert(index_block_hash, pending);
+                },
+                Err(Err(_e)) => {
+                    // connection broken, or timed out.  The block downloader will get it.
+                    debug!("Failed to get block transactions from {:?}: {:?}", &nk, &_e);
+                    self.dead_peers.insert(nk);
+                }
+            }
+        }
+
+        self.pending = still_pending;
+        Ok(())
+    }
+}
+
+impl PeerNetwork {
+    pub fn with_compact_block_state<F, R>(network: &mut PeerNetwork, handler: F) -> Result<R, net_error>
+    where
+        F: FnOnce(&mut PeerNetwork, &mut CompactBlockState) -> Result<R, net_error>
+    {
+        let mut compact_blocks = network.compact_blocks.take();
+        let res = match compact_blocks {
+            None => {
+                test_debug!("{:?}: compact block state not connected", &network.local_peer);
+                Err(net_error::NotConnected)
+            },
+            Some(ref mut cb) => handler(network, cb)
+        };
+        network.compact_blocks = compact_blocks;
+        res
+    }
+
+    /// Initialize compact block state
+    pub fn init_compact_blocks(&mut self) -> () {
+        debug!("{:?}: Initializing compact block state", &self.local_peer);
+        self.compact_blocks = Some(CompactBlockState::new(self.connection_opts.timeout));
+    }
+
+    /// Start reassembling a compact block a peer pushed to us
+    pub fn add_compact_block(&mut self, mempool: &MemPoolDB, neighbor_key: &NeighborKey, compact: &CompactBlockData) -> Result<(), net_error> {
+        if self.compact_blocks.is_none() {
+            self.init_compact_blocks();
+        }
+        PeerNetwork::with_compact_block_state(self, |ref mut _network, ref mut compact_state| {
+            compact_state.add_compact_block(mempool, neighbor_key, compact)
+        })
+    }
+
+    /// Drive fetching the transactions we're missing from compact blocks.
+    /// Returns the blocks we finished reassembling, and the list of dead and broken peers that
+    /// we should disconnect from.
+    pub fn sync_compact_blocks(&mut self) -> Result<(Vec<(NeighborKey, BurnchainHeaderHash, StacksBlock)>, Vec<NeighborKey>, Vec<NeighborKey>), net_error> {
+        if self.compact_blocks.is_none() {
+            return Ok((vec![], vec![], vec![]));
+        }
+
+        PeerNetwork::with_compact_block_state(self, |ref mut network, ref mut compact_state| {
+            compact_state.getblocktxs_run(network)?;
+
+            let blocks = compact_state.reassembled.split_off(0);
+            let dead = compact_state.dead_peers.drain().collect();
+            let broken = compact_state.broken_peers.drain().collect();
+            Ok((blocks, dead, broken))
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use net::*;
+    use chainstate::stacks::*;
+    use chainstate::stacks::index::TrieHash;
+    use chainstate::stacks::db::test::chainstate_path;
+    use chainstate::stacks::db::test::instantiate_chainstate;
+    use chainstate::stacks::miner::test::make_user_stacks_transfer;
+    use chainstate::stacks::miner::test::make_user_coinbase;
+    use burnchains::Address;
+    use core::{FIRST_BURNCHAIN_BLOCK_HASH, FIRST_STACKS_BLOCK_HASH};
+    use util::hash::Hash160;
+    use util::vrf::VRFProof;
+
+    pub fn make_test_block(num_txs: u64) -> StacksBlock {
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let miner_privk = StacksPrivateKey::new();
+        let privk = StacksPrivateKey::new();
+
+        let mut txs = vec![make_user_coinbase(&miner_privk, 0, 0)];
+        for nonce in 0..num_txs {
+            txs.push(make_user_stacks_transfer(&privk, nonce, 100, &recipient, 1));
+        }
+
+        let parent_header = StacksBlockHeader::genesis_block_header();
+        let parent_microblock_header = StacksMicroblockHeader::first_unsigned(&parent_header.block_hash(), &Sha512Trunc256Sum([0u8; 32]));
+        StacksBlock::from_parent(&parent_header, &parent_microblock_header, txs, &StacksWorkScore::initial(), &VRFProof::empty(), &TrieHash([0u8; 32]), &Hash160([0u8; 20]))
+    }
+
+    fn make_test_mempool(test_name: &str) -> MemPoolDB {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, test_name);
+        let chainstate_path = chainstate_path(test_name);
+        MemPoolDB::open(false, 0x80000000, &chainstate_path).unwrap()
+    }
+
+    fn submit_txs(mempool: &mut MemPoolDB, txs: &[StacksTransaction]) -> () {
+        for tx in txs.iter() {
+            mempool.submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compact_block_from_block() {
+        let block = make_test_block(3);
+        let compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+
+        // coinbase is prefilled; everything else is a short ID
+        assert_eq!(compact.num_txs(), 4);
+        assert_eq!(compact.prefilled_txs, vec![PrefilledTransaction { index: 0, tx: block.txs[0].clone() }]);
+        assert_eq!(compact.short_txids.len(), 3);
+        for (short_txid, tx) in compact.short_txids.iter().zip(block.txs[1..].iter()) {
+            assert_eq!(*short_txid, ShortTxid::from_txid(&block.block_hash(), &tx.txid()));
+        }
+
+        // short IDs depend on the block
+        assert!(ShortTxid::from_txid(&BlockHeaderHash([0x22; 32]), &block.txs[1].txid()) != compact.short_txids[0]);
+
+        // only Blocks messages get compacted
+        let blocks_payload = StacksMessageType::Blocks(BlocksData { blocks: vec![(BurnchainHeaderHash([0x11; 32]), block.clone())] });
+        assert_eq!(CompactBlockData::from_blocks_payload(&blocks_payload), Some(vec![StacksMessageType::CompactBlock(compact.clone())]));
+        assert_eq!(CompactBlockData::from_blocks_payload(&StacksMessageType::Ping(PingData { nonce: 1 })), None);
+
+        // compacted block is smaller
+        assert!(compact.serialize_to_vec().len() < block.serialize_to_vec().len());
+    }
+
+    #[test]
+    fn test_compact_block_reassemble_from_mempool() {
+        let mut mempool = make_test_mempool("test_compact_block_reassemble_from_mempool");
+
+        // more transactions than fit into one page of txids
+        let block = make_test_block((MEMPOOL_TXIDS_PAGE_MAX_LEN * 2 + 1) as u64);
+        submit_txs(&mut mempool, &block.txs[1..]);
+
+        let compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+        let partial = PartialBlock::from_compact(&mempool, &compact).unwrap();
+        assert!(partial.is_complete());
+        assert_eq!(partial.missing_indexes(), Vec::<u16>::new());
+        assert_eq!(partial.try_assemble(), Some(block.clone()));
+
+        let mut state = CompactBlockState::new(60);
+        let nk = NeighborKey::empty();
+        state.add_compact_block(&mempool, &nk, &compact).unwrap();
+        assert_eq!(state.num_pending(), 0);
+        assert_eq!(state.num_blocks_reassembled, 1);
+        assert_eq!(state.reassembled, vec![(nk.clone(), BurnchainHeaderHash([0x11; 32]), block.clone())]);
+        assert_eq!(state.num_bytes_saved, (block.serialize_to_vec().len() - compact.serialize_to_vec().len()) as u64);
+    }
+
+    #[test]
+    fn test_compact_block_missing_txs() {
+        let mut mempool = make_test_mempool("test_compact_block_missing_txs");
+
+        // we only have some of the transactions
+        let block = make_test_block(4);
+        submit_txs(&mut mempool, &block.txs[1..2]);
+        submit_txs(&mut mempool, &block.txs[3..4]);
+
+        let compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+        let mut partial = PartialBlock::from_compact(&mempool, &compact).unwrap();
+        assert!(!partial.is_complete());
+        assert_eq!(partial.try_assemble(), None);
+
+        let missing = partial.missing_indexes();
+        assert_eq!(missing, vec![2, 4]);
+
+        // peer must send exactly what we asked for
+        let mut bad_partial = partial.clone();
+        assert!(!bad_partial.fill(&missing, vec![block.txs[2].clone()]));
+        assert!(!bad_partial.fill(&vec![2, 5], vec![block.txs[2].clone(), block.txs[4].clone()]));
+
+        // wrong transactions don't assemble
+        let mut wrong_partial = partial.clone();
+        assert!(wrong_partial.fill(&missing, vec![block.txs[4].clone(), block.txs[2].clone()]));
+        assert!(wrong_partial.is_complete());
+        assert_eq!(wrong_partial.try_assemble(), None);
+
+        assert!(partial.fill(&missing, vec![block.txs[2].clone(), block.txs[4].clone()]));
+        assert_eq!(partial.try_assemble(), Some(block.clone()));
+
+        // state machine waits for the missing transactions
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        assert_eq!(state.num_pending(), 1);
+        assert_eq!(state.num_blocks_reassembled, 0);
+
+        let pending = state.pending.get(&block.index_block_hash(&compact.burn_header_hash)).unwrap();
+        assert_eq!(pending.indexes, vec![2, 4]);
+        assert!(!pending.full_block);
+
+        // a duplicate compact block doesn't start a second reassembly
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        assert_eq!(state.num_pending(), 1);
+        assert_eq!(state.num_blocks_received, 2);
+    }
+
+    #[test]
+    fn test_compact_block_duplicates() {
+        let mut mempool = make_test_mempool("test_compact_block_duplicates");
+
+        let block = make_test_block(4);
+        submit_txs(&mut mempool, &block.txs[1..]);
+        let compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+
+        // a block we already reassembled isn't reassembled again
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        assert_eq!(state.num_blocks_received, 2);
+        assert_eq!(state.num_blocks_reassembled, 1);
+        assert_eq!(state.reassembled.len(), 1);
+
+        // the mempool is only walked once per block hash
+        assert_eq!(state.mempool_candidates.len(), 1);
+        let candidates = state.mempool_candidates.get(&block.block_hash()).unwrap().clone();
+        assert!(candidates.covers(&compact.short_txids));
+        assert_eq!(candidates.txids.len(), 4);
+
+        state.reassembled.clear();
+        state.mempool_candidates.get_mut(&block.block_hash()).unwrap().txids.clear();
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        assert_eq!(state.num_pending(), 1);
+        assert_eq!(state.pending.get(&block.index_block_hash(&compact.burn_header_hash)).unwrap().indexes, vec![1, 2, 3, 4]);
+
+        // ...unless we're asked about short IDs we didn't look for
+        let mut other_compact = compact.clone();
+        other_compact.burn_header_hash = BurnchainHeaderHash([0x22; 32]);
+        other_compact.short_txids[0] = ShortTxid([0xff; 8]);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &other_compact).unwrap();
+        assert_eq!(state.mempool_candidates.len(), 1);
+        assert_eq!(state.mempool_candidates.get(&block.block_hash()).unwrap().txids.len(), 3);
+    }
+
+    #[test]
+    fn test_compact_block_blocktxs_reply() {
+        let mut mempool = make_test_mempool("test_compact_block_blocktxs_reply");
+
+        let block = make_test_block(4);
+        submit_txs(&mut mempool, &block.txs[1..2]);
+
+        let compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+        let index_block_hash = block.index_block_hash(&compact.burn_header_hash);
+
+        let make_reply = |txs: Vec<StacksTransaction>| {
+            let payload = StacksMessageType::BlockTxs(BlockTxsData {
+                burn_header_hash: compact.burn_header_hash.clone(),
+                block_hash: block.block_hash(),
+                txs: txs
+            });
+            StacksMessage::new(0, 0, 0, &ConsensusHash([0u8; 20]), 0, &ConsensusHash([0u8; 20]), payload)
+        };
+
+        // peer sends what we asked for
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        let pending = state.pending.remove(&index_block_hash).unwrap();
+        assert!(state.handle_blocktxs_reply(pending, make_reply(block.txs[2..].to_vec())).is_none());
+        assert_eq!(state.reassembled.len(), 1);
+        assert_eq!(state.reassembled[0].2, block);
+        assert_eq!(state.broken_peers.len(), 0);
+
+        // peer sends the wrong transactions, so we ask for all of them
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        let pending = state.pending.remove(&index_block_hash).unwrap();
+        let pending = state.handle_blocktxs_reply(pending, make_reply(vec![block.txs[4].clone(), block.txs[3].clone(), block.txs[2].clone()])).unwrap();
+        assert!(pending.full_block);
+        assert_eq!(pending.indexes, vec![0, 1, 2, 3, 4]);
+        assert_eq!(state.num_full_block_fallbacks, 1);
+        assert_eq!(state.broken_peers.len(), 0);
+
+        // ...and give up on the peer if it still can't send the block
+        assert!(state.handle_blocktxs_reply(pending, make_reply(vec![block.txs[0].clone(), block.txs[1].clone(), block.txs[4].clone(), block.txs[3].clone(), block.txs[2].clone()])).is_none());
+        assert_eq!(state.reassembled.len(), 0);
+        assert!(state.broken_peers.contains(&NeighborKey::empty()));
+
+        // peer sends too few transactions
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        let pending = state.pending.remove(&index_block_hash).unwrap();
+        assert!(state.handle_blocktxs_reply(pending, make_reply(block.txs[2..4].to_vec())).is_none());
+        assert!(state.broken_peers.contains(&NeighborKey::empty()));
+
+        // peer can't serve the block
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        let pending = state.pending.remove(&index_block_hash).unwrap();
+        let nack = StacksMessage::new(0, 0, 0, &ConsensusHash([0u8; 20]), 0, &ConsensusHash([0u8; 20]), StacksMessageType::Nack(NackData { error_code: NackErrorCodes::NoSuchBlock }));
+        assert!(state.handle_blocktxs_reply(pending, nack).is_none());
+        assert_eq!(state.reassembled.len(), 0);
+        assert_eq!(state.broken_peers.len(), 0);
+    }
+
+    #[test]
+    fn test_compact_block_short_txid_collision() {
+        let mut mempool = make_test_mempool("test_compact_block_short_txid_collision");
+
+        let block = make_test_block(3);
+        submit_txs(&mut mempool, &block.txs[1..]);
+
+        // two transactions in the block claim the same short ID, so neither can be filled in
+        let mut compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+        compact.short_txids[1] = compact.short_txids[0].clone();
+
+        let partial = PartialBlock::from_compact(&mempool, &compact).unwrap();
+        assert_eq!(partial.missing_indexes(), vec![1, 2]);
+
+        // a short ID that matches the wrong transaction in our mempool forces a full-block fallback
+        let mut compact = CompactBlockData::from_block(&BurnchainHeaderHash([0x11; 32]), &block);
+        compact.short_txids.swap(0, 1);
+
+        let partial = PartialBlock::from_compact(&mempool, &compact).unwrap();
+        assert!(partial.is_complete());
+        assert_eq!(partial.try_assemble(), None);
+
+        let mut state = CompactBlockState::new(60);
+        state.add_compact_block(&mempool, &NeighborKey::empty(), &compact).unwrap();
+        assert_eq!(state.num_full_block_fallbacks, 1);
+
+        let pending = state.pending.get(&block.index_block_hash(&compact.burn_header_hash)).unwrap();
+        assert_eq!(pending.indexes, vec![0, 1, 2, 3]);
+        assert!(pending.full_block);
+    }
+}
@@ -53,6 +53,7 @@ use net::MAX_MESSAGE_LEN;
 
 use net::inv::INV_SYNC_INTERVAL;
 use net::download::BLOCK_DOWNLOAD_INTERVAL;
+use net::mempool::{MEMPOOL_SYNC_INTERVAL, MEMPOOL_SYNC_MAX_BYTES};
 use net::neighbors::{NUM_INITIAL_WALKS, WALK_RETRY_COUNT, NEIGHBOR_WALK_INTERVAL, NEIGHBOR_REQUEST_TIMEOUT};
 
 use util::strings::UrlString;
@@ -340,6 +341,8 @@ pub struct ConnectionOptions {
     pub walk_inbound_ratio: u64,
     pub inv_sync_interval: u64,
     pub download_interval: u64,
+    pub mempool_sync_interval: u64,
+    pub max_mempool_sync_bytes: u64,
     pub pingback_timeout: u64,
     pub dns_timeout: u128,
     pub max_inflight_blocks: u64,
@@ -348,17 +351,22 @@ pub struct ConnectionOptions {
     pub max_block_push_bandwidth: u64,
     pub max_microblocks_push_bandwidth: u64,
     pub max_transaction_push_bandwidth: u64,
+    pub max_mempool_sync_bandwidth: u64,
     pub max_sockets: usize,
     pub public_ip_address: Option<(PeerAddress, u16)>,
     pub public_ip_request_timeout: u64,
     pub public_ip_timeout: u64,
     pub public_ip_max_retries: u64,
+    pub encrypt_p2p: bool,
+    pub compact_blocks: bool,
+    pub admin_rpc_token: Option<String>,
     
     // fault injection
     pub disable_neighbor_walk: bool,
     pub disable_chat_neighbors: bool,
     pub disable_inv_sync: bool,
     pub disable_block_download: bool,
+    pub disable_mempool_sync: bool,
     pub disable_network_prune: bool,
     pub disable_network_bans: bool,
     pub disable_block_advertisement: bool,
@@ -394,6 +402,8 @@ impl std::default::Default for ConnectionOptions {
             walk_inbound_ratio: 2,                              // walk inbound neighbors twice as often as outbound by default
             inv_sync_interval: INV_SYNC_INTERVAL,               // how often to synchronize block inventories
             download_interval: BLOCK_DOWNLOAD_INTERVAL,         // how often to scan for blocks to download
+            mempool_sync_interval: MEMPOOL_SYNC_INTERVAL,       // how often to synchronize mempools with our neighbors
+            max_mempool_sync_bytes: MEMPOOL_SYNC_MAX_BYTES,     // how many bytes of transactions to fetch in one mempool sync pass
             pingback_timeout: 60,
             dns_timeout: 15_000,            // DNS timeout, in millis
             max_inflight_blocks: 6,         // number of parallel block downloads
@@ -404,17 +414,22 @@ impl std::default::Default for ConnectionOptions {
             max_block_push_bandwidth: 0,    // infinite upload bandwidth allowed
             max_microblocks_push_bandwidth: 0,     // infinite upload bandwidth allowed
             max_transaction_push_bandwidth: 0,      // infinite upload bandwidth allowed
+            max_mempool_sync_bandwidth: 0,  // infinite upload bandwidth allowed
             max_sockets: 800,               // maximum number of client sockets we'll ever register
             public_ip_address: None,        // resolve it at runtime by default
             public_ip_request_timeout: 60,  // how often we can attempt to look up our public IP address
             public_ip_timeout: 3600,        // re-learn the public IP ever hour, if it's not given
             public_ip_max_retries: 3,       // maximum number of retries before self-throttling for $public_ip_timeout
+            encrypt_p2p: false,             // negotiate encrypted sessions with peers that support them
+            compact_blocks: true,           // send compact blocks to peers that support them
+            admin_rpc_token: None,          // bearer token for the /admin RPC endpoints (disabled if not set)
 
             // no faults on by default
             disable_neighbor_walk: false,
             disable_chat_neighbors: false,
             disable_inv_sync: false,
             disable_block_download: false,
+            disable_mempool_sync: false,
             disable_network_prune: false,
             disable_network_bans: false,
             disable_block_advertisement: false,
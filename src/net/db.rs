@@ -49,6 +49,7 @@ use rand::thread_rng;
 use rand::seq::SliceRandom;
 
 use net::asn::ASEntry4;
+use net::asn::ASEntry6;
 use net::PeerAddress;
 use net::Neighbor;
 use net::NeighborKey;
@@ -222,6 +223,20 @@ impl FromRow<ASEntry4> for ASEntry4 {
     }
 }
 
+impl FromRow<ASEntry6> for ASEntry6 {
+    fn from_row<'a>(row: &'a Row) -> Result<ASEntry6, db_error> {
+        let prefix = PeerAddress::from_column(row, "prefix")?;
+        let mask : u8 = row.get("mask");
+        let asn : u32 = row.get("asn");
+
+        Ok(ASEntry6 {
+            prefix,
+            mask,
+            asn
+        })
+    }
+}
+
 impl FromRow<Neighbor> for Neighbor {
     fn from_row<'a>(row: &'a Row) -> Result<Neighbor, db_error> {
         let peer_version : u32 = row.get("peer_version");
@@ -301,6 +316,8 @@ const PEERDB_SETUP : &'static [&'static str]= &[
 
         PRIMARY KEY(prefix,mask)
     );"#,
+    PEERDB_ASN6_SETUP,
+    PEERDB_DENIED_PEERS_SETUP,
     r#"
     CREATE TABLE db_version(version TEXT NOT NULL);
     "#,
@@ -328,13 +345,42 @@ const PEERDB_SETUP : &'static [&'static str]= &[
     );"#,
 ];
 
+// IPv6 prefixes are stored as 128-character binary strings, like frontier addresses, so they can
+// be matched with the same prefix comparison used for CIDR filters.  Created if missing when
+// opening a database that predates it.
+const PEERDB_ASN6_SETUP : &'static str = r#"
+    CREATE TABLE IF NOT EXISTS asn6(
+        prefix TEXT NOT NULL,
+        mask INTEGER NOT NULL,
+
+        asn INTEGER NOT NULL,
+
+        PRIMARY KEY(prefix,mask)
+    );"#;
+
+// Per-peer bans, kept apart from frontier.denied so that lifting a CIDR ban does not also lift
+// a ban that was placed on an individual peer inside it.  Created if missing when opening a
+// database that predates it.
+const PEERDB_DENIED_PEERS_SETUP : &'static str = r#"
+    CREATE TABLE IF NOT EXISTS denied_peers(
+        network_id INTEGER NOT NULL,
+        addrbytes TEXT NOT NULL,
+        port INTEGER NOT NULL,
+        deadline INTEGER NOT NULL,
+
+        PRIMARY KEY(network_id,addrbytes,port)
+    );"#;
+
+// The frontier.denied value a peer has once CIDR bans are disregarded
+const PEERDB_PEER_DENY_DEADLINE : &'static str = "COALESCE((SELECT deadline FROM denied_peers WHERE denied_peers.network_id = frontier.network_id AND denied_peers.addrbytes = frontier.addrbytes AND denied_peers.port = frontier.port), 0)";
+
 pub struct PeerDB {
     pub conn: Connection,
     pub readwrite: bool,
 }
 
 impl PeerDB {
-    fn instantiate(&mut self, network_id: u32, parent_network_id: u32, privkey_opt: Option<Secp256k1PrivateKey>, key_expires: u64, data_url: UrlString, p2p_addr: PeerAddress, p2p_port: u16, asn4_entries: &Vec<ASEntry4>, initial_neighbors: &Vec<Neighbor>) -> Result<(), db_error> {
+    fn instantiate(&mut self, network_id: u32, parent_network_id: u32, privkey_opt: Option<Secp256k1PrivateKey>, key_expires: u64, data_url: UrlString, p2p_addr: PeerAddress, p2p_port: u16, asn4_entries: &Vec<ASEntry4>, asn6_entries: &Vec<ASEntry6>, initial_neighbors: &Vec<Neighbor>) -> Result<(), db_error> {
         let localpeer = LocalPeer::new(network_id, parent_network_id, p2p_addr, p2p_port, privkey_opt, key_expires, data_url);
 
         let mut tx = self.tx_begin()?;
@@ -374,6 +420,10 @@ impl PeerDB {
             PeerDB::asn4_insert(&mut tx, &asn4)?;
         }
 
+        for asn6 in asn6_entries {
+            PeerDB::asn6_insert(&mut tx, &asn6)?;
+        }
+
         tx.commit()
             .map_err(db_error::SqliteError)?;
 
@@ -395,7 +445,7 @@ impl PeerDB {
     }
 
     fn reset_denies<'a>(tx: &mut Transaction<'a>) -> Result<(), db_error> {
-        tx.execute("UPDATE frontier SET denied = 0", NO_PARAMS).map_err(db_error::SqliteError)?;
+        tx.execute(&format!("UPDATE frontier SET denied = {}", PEERDB_PEER_DENY_DEADLINE), NO_PARAMS).map_err(db_error::SqliteError)?;
         Ok(())
     }
     
@@ -436,6 +486,7 @@ impl PeerDB {
                    p2p_port: u16, 
                    data_url: UrlString, 
                    asn4_recs: &Vec<ASEntry4>, 
+                   asn6_recs: &Vec<ASEntry6>, 
                    initial_neighbors: Option<&Vec<Neighbor>>) -> Result<PeerDB, db_error> {
 
         let mut create_flag = false;
@@ -472,10 +523,10 @@ impl PeerDB {
             // instantiate!
             match initial_neighbors {
                 Some(ref neighbors) => {
-                    db.instantiate(network_id, parent_network_id, privkey_opt, key_expires, data_url, p2p_addr, p2p_port, asn4_recs, neighbors)?;
+                    db.instantiate(network_id, parent_network_id, privkey_opt, key_expires, data_url, p2p_addr, p2p_port, asn4_recs, asn6_recs, neighbors)?;
                 },
                 None => {
-                    db.instantiate(network_id, parent_network_id, privkey_opt, key_expires, data_url, p2p_addr, p2p_port, asn4_recs, &vec![])?;
+                    db.instantiate(network_id, parent_network_id, privkey_opt, key_expires, data_url, p2p_addr, p2p_port, asn4_recs, asn6_recs, &vec![])?;
                 }
             }
         } else {
@@ -483,6 +534,9 @@ impl PeerDB {
             
             {
                 let mut tx = db.tx_begin()?;
+                PeerDB::upgrade_asn6(&mut tx, asn6_recs)?;
+                tx.execute(PEERDB_DENIED_PEERS_SETUP, NO_PARAMS)
+                    .map_err(db_error::SqliteError)?;
                 PeerDB::refresh_allows(&mut tx)?;
                 PeerDB::refresh_denies(&mut tx)?;
                 tx.commit()?;
@@ -493,7 +547,7 @@ impl PeerDB {
 
     /// Open a burn database in memory (used for testing)
     #[cfg(test)]
-    pub fn connect_memory(network_id: u32, parent_network_id: u32, key_expires: u64, data_url: UrlString, asn4_entries: &Vec<ASEntry4>, initial_neighbors: &Vec<Neighbor>) -> Result<PeerDB, db_error> {
+    pub fn connect_memory(network_id: u32, parent_network_id: u32, key_expires: u64, data_url: UrlString, asn4_entries: &Vec<ASEntry4>, asn6_entries: &Vec<ASEntry6>, initial_neighbors: &Vec<Neighbor>) -> Result<PeerDB, db_error> {
         let conn = Connection::open_in_memory()
             .map_err(|e| db_error::SqliteError(e))?;
 
@@ -502,7 +556,7 @@ impl PeerDB {
             readwrite: true,
         };
 
-        db.instantiate(network_id, parent_network_id, None, key_expires, data_url, PeerAddress::from_ipv4(127, 0, 0, 1), NETWORK_P2P_PORT, asn4_entries, initial_neighbors)?;
+        db.instantiate(network_id, parent_network_id, None, key_expires, data_url, PeerAddress::from_ipv4(127, 0, 0, 1), NETWORK_P2P_PORT, asn4_entries, asn6_entries, initial_neighbors)?;
         Ok(db)
     }
 
@@ -729,6 +783,15 @@ impl PeerDB {
         let num_updated = tx.execute("UPDATE frontier SET denied = ?1 WHERE network_id = ?2 AND addrbytes = ?3 AND port = ?4", args)
             .map_err(db_error::SqliteError)?;
 
+        if deny_deadline > 0 {
+            tx.execute("INSERT OR REPLACE INTO denied_peers (deadline, network_id, addrbytes, port) VALUES (?1, ?2, ?3, ?4)", args)
+                .map_err(db_error::SqliteError)?;
+        }
+        else {
+            tx.execute("DELETE FROM denied_peers WHERE network_id = ?1 AND addrbytes = ?2 AND port = ?3", &args[1..])
+                .map_err(db_error::SqliteError)?;
+        }
+
         if num_updated == 0 {
             // we're preemptively denying
             let nk = NeighborKey {
@@ -904,6 +967,26 @@ impl PeerDB {
         Ok(())
     }
 
+    /// Remove a denied CIDR prefix.
+    /// Peers in the prefix are no longer denied, unless another denied prefix still covers them
+    /// or they were banned individually.
+    pub fn remove_deny_cidr<'a>(tx: &mut Transaction<'a>, prefix: &PeerAddress, mask: u32) -> Result<(), db_error> {
+        assert!(mask > 0 && mask <= 128);
+        PeerDB::remove_cidr_prefix(tx, "denied_prefixes", prefix, mask)?;
+
+        debug!("Remove deny {}/{}", &prefix, mask);
+        let prefix_txt = PeerDB::cidr_prefix_to_string(prefix, mask);
+        let args : &[&dyn ToSql] = &[&mask, &prefix_txt];
+        tx.execute(&format!("UPDATE frontier SET denied = {} WHERE SUBSTR(addrbytes,1,?1) = SUBSTR(?2,1,?1)", PEERDB_PEER_DENY_DEADLINE), args)
+            .map_err(db_error::SqliteError)?;
+
+        let deny_cidrs = PeerDB::get_denied_cidrs(tx)?;
+        for (prefix, mask) in deny_cidrs.into_iter() {
+            PeerDB::apply_cidr_filter(tx, &prefix, mask, "denied", i64::max_value())?;
+        }
+        Ok(())
+    }
+
     /// Get random neighbors, optionally always including allowed neighbors
     pub fn get_random_neighbors(conn: &DBConn, network_id: u32, count: u32, block_height: u64, always_include_allowed: bool) -> Result<Vec<Neighbor>, db_error> {
         let mut ret = vec![];
@@ -989,14 +1072,55 @@ impl PeerDB {
         }
     }
 
+    /// Add an IPv6 <--> ASN mapping
+    /// Used during db instantiation
+    fn asn6_insert<'a>(tx: &mut Transaction<'a>, asn6: &ASEntry6) -> Result<(), db_error> {
+        tx.execute("INSERT OR REPLACE INTO asn6 (prefix, mask, asn) VALUES (?1, ?2, ?3)",
+                  &[&asn6.prefix.to_bin() as &dyn ToSql, &asn6.mask as &dyn ToSql, &asn6.asn as &dyn ToSql])
+            .map_err(db_error::SqliteError)?;
+
+        Ok(())
+    }
+
+    /// Create the asn6 table in a database that predates it, and load the given IPv6 <--> ASN
+    /// mappings into it if it is empty.
+    fn upgrade_asn6<'a>(tx: &mut Transaction<'a>, asn6_entries: &Vec<ASEntry6>) -> Result<(), db_error> {
+        tx.execute(PEERDB_ASN6_SETUP, NO_PARAMS)
+            .map_err(db_error::SqliteError)?;
+
+        let count = query_count(tx, &"SELECT COUNT(*) FROM asn6".to_string(), NO_PARAMS)?;
+        if count == 0 {
+            for asn6 in asn6_entries {
+                PeerDB::asn6_insert(tx, &asn6)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Classify an IPv6 address to its AS number.
+    /// The most-specific matching prefix wins.
+    pub fn asn6_lookup(conn: &DBConn, addrbits: &PeerAddress) -> Result<Option<u32>, db_error> {
+        // must not be an IPv4 address
+        if addrbits.is_ipv4() {
+            return Err(db_error::TypeError);
+        }
+
+        let qry = "SELECT * FROM asn6 WHERE SUBSTR(prefix,1,mask) = SUBSTR(?1,1,mask) ORDER BY mask DESC LIMIT 1".to_string();
+        let args = [&addrbits.to_bin() as &dyn ToSql];
+        let rows = query_rows::<ASEntry6, _>(conn, &qry, &args)?;
+        match rows.len() {
+            0 => Ok(None),
+            _ => Ok(Some(rows[0].asn))
+        }
+    }
+
     /// Classify an IP address to its AS number
     pub fn asn_lookup(conn: &DBConn, addrbits: &PeerAddress) -> Result<Option<u32>, db_error> {
         if addrbits.is_ipv4() {
             PeerDB::asn4_lookup(conn, addrbits)
         }
         else {
-            // TODO
-            Ok(None)
+            PeerDB::asn6_lookup(conn, addrbits)
         }
     }
 
@@ -1030,7 +1154,7 @@ mod test {
 
     #[test]
     fn test_local_peer() {
-        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![]).unwrap();
+        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
         let local_peer = PeerDB::get_local_peer(db.conn()).unwrap();
 
         assert_eq!(local_peer.network_id, 0x9abcdef0);
@@ -1062,7 +1186,7 @@ mod test {
             out_degree: 1
         };
         
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
         
         let neighbor_before_opt = PeerDB::get_peer(db.conn(), 0x9abcdef0, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x08,0x09,0x0a,0x0b,0x0c,0x0d,0x0e,0x0f]), 12345).unwrap();
         assert_eq!(neighbor_before_opt, None);
@@ -1112,7 +1236,7 @@ mod test {
             out_degree: 1
         };
 
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
         
         {
             let mut tx = db.tx_begin().unwrap();
@@ -1194,7 +1318,7 @@ mod test {
             return true;
         }
         
-        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &initial_neighbors).unwrap();
+        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &initial_neighbors).unwrap();
 
         let n5 = PeerDB::get_initial_neighbors(db.conn(), 0x9abcdef0, 5, 23455).unwrap();
         assert!(are_present(&n5, &initial_neighbors));
@@ -1242,7 +1366,7 @@ mod test {
             },
         ];
 
-        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &asn4_table, &vec![]).unwrap();
+        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &asn4_table, &vec![], &vec![]).unwrap();
     
         let asn1_addr = PeerAddress([0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0xff,0xff,0x01,0x02,0x02,0x04]);
         let asn2_addr = PeerAddress([0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0xff,0xff,0x01,0x02,0x03,0x10]);
@@ -1277,9 +1401,114 @@ mod test {
         assert_eq!(asn_missing_opt, None);
     }
 
+    #[test]
+    fn asn6_insert_lookup() {
+        let asn6_table = vec![
+            ASEntry6 {
+                prefix: PeerAddress([0x20,0x01,0x0d,0xb8,0x01,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                mask: 40,
+                asn: 1
+            },
+            ASEntry6 {
+                prefix: PeerAddress([0x20,0x01,0x0d,0xb8,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                mask: 32,
+                asn: 2
+            },
+            ASEntry6 {
+                prefix: PeerAddress([0x2a,0x00,0x14,0x50,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                mask: 32,
+                asn: 3
+            },
+        ];
+
+        let asn4_table = vec![
+            ASEntry4 {
+                prefix: 0x01020000,
+                mask: 16,
+                asn: 4,
+                org: 0
+            },
+        ];
+
+        let db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &asn4_table, &asn6_table, &vec![]).unwrap();
+
+        let asn1_addr = PeerAddress([0x20,0x01,0x0d,0xb8,0x01,0x02,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x01]);
+        let asn2_addr = PeerAddress([0x20,0x01,0x0d,0xb8,0x02,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x01]);
+        let asn3_addr = PeerAddress([0x2a,0x00,0x14,0x50,0xff,0xff,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x01]);
+        let asn4_addr = PeerAddress([0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0xff,0xff,0x01,0x02,0x13,0x10]);
+
+        // classify addresses -- most-specific prefix wins
+        let asn1_opt = PeerDB::asn6_lookup(db.conn(), &asn1_addr).unwrap();
+        assert_eq!(asn1_opt, Some(1));
+
+        let asn2_opt = PeerDB::asn6_lookup(db.conn(), &asn2_addr).unwrap();
+        assert_eq!(asn2_opt, Some(2));
+
+        let asn3_opt = PeerDB::asn6_lookup(db.conn(), &asn3_addr).unwrap();
+        assert_eq!(asn3_opt, Some(3));
+
+        // asn_lookup dispatches on address family
+        assert_eq!(PeerDB::asn_lookup(db.conn(), &asn1_addr).unwrap(), Some(1));
+        assert_eq!(PeerDB::asn_lookup(db.conn(), &asn3_addr).unwrap(), Some(3));
+        assert_eq!(PeerDB::asn_lookup(db.conn(), &asn4_addr).unwrap(), Some(4));
+
+        // invalid -- not an ipv6 address
+        let asn_invalid_opt = PeerDB::asn6_lookup(db.conn(), &asn4_addr);
+        match asn_invalid_opt {
+            Ok(_) => assert!(false),
+            Err(db_error::TypeError) => assert!(true),
+            Err(_) => assert!(false)
+        }
+
+        // not present
+        let asn6_missing_addr = PeerAddress([0x20,0x01,0x0d,0xb9,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x01]);
+        let asn_missing_opt = PeerDB::asn6_lookup(db.conn(), &asn6_missing_addr).unwrap();
+        assert_eq!(asn_missing_opt, None);
+        assert_eq!(PeerDB::asn_lookup(db.conn(), &asn6_missing_addr).unwrap(), None);
+    }
+
+    #[test]
+    fn asn6_upgrade() {
+        let asn6_table = vec![
+            ASEntry6 {
+                prefix: PeerAddress([0x20,0x01,0x0d,0xb8,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                mask: 32,
+                asn: 2
+            },
+        ];
+        let asn6_addr = PeerAddress([0x20,0x01,0x0d,0xb8,0x02,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x01]);
+
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
+
+        // simulate a database from before the asn6 table existed
+        db.conn.execute("DROP TABLE asn6", NO_PARAMS).unwrap();
+        assert!(PeerDB::asn6_lookup(db.conn(), &asn6_addr).is_err());
+
+        {
+            let mut tx = db.tx_begin().unwrap();
+            PeerDB::upgrade_asn6(&mut tx, &asn6_table).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(PeerDB::asn6_lookup(db.conn(), &asn6_addr).unwrap(), Some(2));
+
+        // entries are only loaded into an empty table
+        {
+            let mut tx = db.tx_begin().unwrap();
+            PeerDB::upgrade_asn6(&mut tx, &vec![
+                ASEntry6 {
+                    prefix: PeerAddress([0x20,0x01,0x0d,0xb8,0x02,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]),
+                    mask: 40,
+                    asn: 3
+                }
+            ]).unwrap();
+            tx.commit().unwrap();
+        }
+        assert_eq!(PeerDB::asn6_lookup(db.conn(), &asn6_addr).unwrap(), Some(2));
+    }
+
     #[test]
     fn test_peer_preemptive_deny_allow() {
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
         {
             let mut tx = db.tx_begin().unwrap();
             PeerDB::set_deny_peer(&mut tx, 0x9abcdef0, &PeerAddress([0x1; 16]), 12345, 10000000).unwrap();
@@ -1296,7 +1525,7 @@ mod test {
 
     #[test]
     fn test_peer_cidr_lists() {
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
         {
             let mut tx = db.tx_begin().unwrap();
             PeerDB::add_cidr_prefix(&mut tx, "denied_prefixes", &PeerAddress([0x1; 16]), 64).unwrap();
@@ -1313,7 +1542,7 @@ mod test {
     
     #[test]
     fn test_peer_is_denied() {
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![]).unwrap();
         {
             let mut tx = db.tx_begin().unwrap();
             PeerDB::add_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 64).unwrap();
@@ -1367,7 +1596,7 @@ mod test {
             out_degree: 1
         };
         
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![neighbor_1.clone(), neighbor_2.clone()]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![neighbor_1.clone(), neighbor_2.clone()]).unwrap();
 
         let n1 = PeerDB::get_peer(db.conn(), neighbor_1.addr.network_id, &neighbor_1.addr.addrbytes, neighbor_1.addr.port).unwrap().unwrap();
         assert_eq!(n1.allowed, 12345);
@@ -1402,7 +1631,95 @@ mod test {
         assert_eq!(n2.allowed, 12345);
         assert_eq!(n2.denied, 67890);
     }
-    
+
+    #[test]
+    fn test_peer_remove_deny_cidr() {
+        let neighbor_1 = Neighbor {
+            addr: NeighborKey {
+                peer_version: 0x12345678,
+                network_id: 0x9abcdef0,
+                addrbytes: PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x08,0x09,0x0a,0x0b,0x0c,0x0d,0x0e,0x0f]),
+                port: 12345,
+            },
+            public_key: Secp256k1PublicKey::from_hex("02fa66b66f8971a8cd4d20ffded09674e030f0f33883f337f34b95ad4935bac0e3").unwrap(),
+            expire_block: 23456,
+            last_contact_time: 1552509642,
+            allowed: 12345,
+            denied: 67890,
+            asn: 34567,
+            org: 45678,
+            in_degree: 1,
+            out_degree: 1
+        };
+
+        let neighbor_2 = Neighbor {
+            addr: NeighborKey {
+                peer_version: 0x12345678,
+                network_id: 0x9abcdef0,
+                addrbytes: PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x08,0x09,0x0a,0x0b,0x0c,0x0d,0x0e,0x10]),
+                port: 12346,
+            },
+            public_key: Secp256k1PublicKey::from_hex("02fa66b66f8971a8cd4d20ffded09674e030f0f33883f337f34b95ad4935bac0e3").unwrap(),
+            expire_block: 23456,
+            last_contact_time: 1552509642,
+            allowed: 12345,
+            denied: 0,
+            asn: 34567,
+            org: 45678,
+            in_degree: 1,
+            out_degree: 1
+        };
+
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![neighbor_1.clone(), neighbor_2.clone()]).unwrap();
+
+        {
+            // ban peer 2 on its own, and ban both peers by two overlapping prefixes
+            let mut tx = db.tx_begin().unwrap();
+            PeerDB::set_deny_peer(&mut tx, neighbor_2.addr.network_id, &neighbor_2.addr.addrbytes, neighbor_2.addr.port, 67890).unwrap();
+            PeerDB::add_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 64).unwrap();
+            PeerDB::add_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 48).unwrap();
+            tx.commit().unwrap();
+        }
+
+        {
+            // still banned by the /48
+            let mut tx = db.tx_begin().unwrap();
+            PeerDB::remove_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 64).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let n1 = PeerDB::get_peer(db.conn(), neighbor_1.addr.network_id, &neighbor_1.addr.addrbytes, neighbor_1.addr.port).unwrap().unwrap();
+        assert_eq!(n1.denied, i64::max_value());
+        assert!(PeerDB::is_address_denied(db.conn(), &neighbor_1.addr.addrbytes).unwrap());
+
+        {
+            let mut tx = db.tx_begin().unwrap();
+            PeerDB::remove_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 48).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let n1 = PeerDB::get_peer(db.conn(), neighbor_1.addr.network_id, &neighbor_1.addr.addrbytes, neighbor_1.addr.port).unwrap().unwrap();
+        assert_eq!(n1.denied, 0);
+        assert!(!PeerDB::is_address_denied(db.conn(), &neighbor_1.addr.addrbytes).unwrap());
+        assert_eq!(PeerDB::get_denied_cidrs(db.conn()).unwrap(), vec![]);
+
+        // peer 2 keeps its own ban
+        let n2 = PeerDB::get_peer(db.conn(), neighbor_2.addr.network_id, &neighbor_2.addr.addrbytes, neighbor_2.addr.port).unwrap().unwrap();
+        assert_eq!(n2.denied, 67890);
+
+        {
+            let mut tx = db.tx_begin().unwrap();
+            PeerDB::set_deny_peer(&mut tx, neighbor_2.addr.network_id, &neighbor_2.addr.addrbytes, neighbor_2.addr.port, 0).unwrap();
+            PeerDB::add_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 48).unwrap();
+            PeerDB::remove_deny_cidr(&mut tx, &PeerAddress([0x00,0x01,0x02,0x03,0x04,0x05,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00]), 48).unwrap();
+            tx.commit().unwrap();
+        }
+
+        // once unbanned, peer 2 is no longer denied after the prefix is lifted
+        let n2 = PeerDB::get_peer(db.conn(), neighbor_2.addr.network_id, &neighbor_2.addr.addrbytes, neighbor_2.addr.port).unwrap().unwrap();
+        assert_eq!(n2.denied, 0);
+    }
+
     #[test]
     fn test_peer_refresh_cidr() {
         let neighbor_1 = Neighbor {
@@ -1441,7 +1758,7 @@ mod test {
             out_degree: 1
         };
 
-        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![neighbor_1.clone(), neighbor_2.clone()]).unwrap();
+        let mut db = PeerDB::connect_memory(0x9abcdef0, 12345, 0, "http://foo.com".into(), &vec![], &vec![], &vec![neighbor_1.clone(), neighbor_2.clone()]).unwrap();
         {
             let mut tx = db.tx_begin().unwrap();
             PeerDB::add_cidr_prefix(&mut tx, "denied_prefixes", &PeerAddress([0x00; 16]), 8).unwrap();
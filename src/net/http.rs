@@ -21,6 +21,7 @@ use std::collections::{HashMap, HashSet};
 use std::str;
 use std::fmt;
 use std::net::SocketAddr;
+use std::net::IpAddr;
 use std::str::FromStr;
 use std::io::prelude::*;
 use std::io;
@@ -49,8 +50,11 @@ use net::HttpRequestMetadata;
 use net::HttpResponseMetadata;
 use net::NeighborAddress;
 use net::CallReadOnlyRequestBody;
+use net::RPCAdminRequestBody;
+use net::PeerAdminCommand;
 use net::HTTP_PREAMBLE_MAX_ENCODED_SIZE;
 use net::HTTP_PREAMBLE_MAX_NUM_HEADERS;
+use net::HTTP_ADMIN_REQUEST_MAX_LEN;
 use net::MAX_MESSAGE_LEN;
 use net::MAX_MICROBLOCKS_UNCONFIRMED;
 use net::HTTP_REQUEST_ID_RESERVED;
@@ -66,6 +70,7 @@ use chainstate::stacks::{
 use util::log;
 use util::hash::hex_bytes;
 use util::hash::to_hex;
+use util::secp256k1::Secp256k1PublicKey;
 use util::retry::RetryReader;
 use util::retry::BoundReader;
 
@@ -116,6 +121,13 @@ lazy_static! {
         *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX)).unwrap();
     static ref PATH_GET_TRANSFER_COST: Regex = Regex::new("^/v2/fees/transfer$").unwrap();
     static ref PATH_OPTIONS_WILDCARD: Regex = Regex::new("^/v2/.{0,4096}$").unwrap();
+    static ref PATH_ADMIN_GETNEIGHBORS : Regex = Regex::new(r#"^/admin/neighbors$"#).unwrap();
+    static ref PATH_ADMIN_BAN_PEER : Regex = Regex::new(r#"^/admin/peers/ban$"#).unwrap();
+    static ref PATH_ADMIN_UNBAN_PEER : Regex = Regex::new(r#"^/admin/peers/unban$"#).unwrap();
+    static ref PATH_ADMIN_DISCONNECT_PEER : Regex = Regex::new(r#"^/admin/peers/disconnect$"#).unwrap();
+    static ref PATH_ADMIN_ADD_BOOTSTRAP_PEER : Regex = Regex::new(r#"^/admin/peers/bootstrap$"#).unwrap();
+    static ref PATH_ADMIN_BAN_CIDR : Regex = Regex::new(r#"^/admin/cidrs/ban$"#).unwrap();
+    static ref PATH_ADMIN_UNBAN_CIDR : Regex = Regex::new(r#"^/admin/cidrs/unban$"#).unwrap();
 }
 
 /// HTTP headers that we really care about
@@ -737,6 +749,15 @@ fn empty_headers<W: Write>(_fd: &mut W) -> Result<(), net_error> {
     Ok(())
 }
 
+fn bearer_token_headers<W: Write>(fd: &mut W, token_opt: &Option<String>) -> Result<(), net_error> {
+    if let Some(ref token) = token_opt {
+        fd.write_all("Authorization: Bearer ".as_bytes()).map_err(net_error::WriteError)?;
+        fd.write_all(token.as_bytes()).map_err(net_error::WriteError)?;
+        fd.write_all("\r\n".as_bytes()).map_err(net_error::WriteError)?;
+    }
+    Ok(())
+}
+
 fn keep_alive_headers<W: Write>(fd: &mut W, md: &HttpResponseMetadata) -> Result<(), net_error> {
     match md.client_version {
         HttpVersion::Http10 => {
@@ -1178,6 +1199,13 @@ impl HttpRequestType {
             ("GET", &PATH_GET_CONTRACT_ABI, &HttpRequestType::parse_get_contract_abi),
             ("POST", &PATH_POST_CALL_READ_ONLY, &HttpRequestType::parse_call_read_only),
             ("OPTIONS", &PATH_OPTIONS_WILDCARD, &HttpRequestType::parse_options_preflight),
+            ("GET", &PATH_ADMIN_GETNEIGHBORS, &HttpRequestType::parse_admin_getneighbors),
+            ("POST", &PATH_ADMIN_BAN_PEER, &HttpRequestType::parse_admin_ban_peer),
+            ("POST", &PATH_ADMIN_UNBAN_PEER, &HttpRequestType::parse_admin_unban_peer),
+            ("POST", &PATH_ADMIN_DISCONNECT_PEER, &HttpRequestType::parse_admin_disconnect_peer),
+            ("POST", &PATH_ADMIN_ADD_BOOTSTRAP_PEER, &HttpRequestType::parse_admin_add_bootstrap_peer),
+            ("POST", &PATH_ADMIN_BAN_CIDR, &HttpRequestType::parse_admin_ban_cidr),
+            ("POST", &PATH_ADMIN_UNBAN_CIDR, &HttpRequestType::parse_admin_unban_cidr),
         ];
 
         // use url::Url to parse path and query string
@@ -1222,6 +1250,131 @@ impl HttpRequestType {
         Ok(HttpRequestType::GetNeighbors(HttpRequestMetadata::from_preamble(preamble)))
     }
 
+    /// Get the bearer token from a request's Authorization header, if there is one
+    fn get_bearer_token(preamble: &HttpRequestPreamble) -> Option<String> {
+        match preamble.headers.get("authorization") {
+            Some(value) => {
+                let mut parts = value.trim().splitn(2, ' ');
+                match (parts.next(), parts.next()) {
+                    (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => Some(token.trim().to_string()),
+                    _ => None
+                }
+            },
+            None => None
+        }
+    }
+
+    fn parse_admin_getneighbors<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, _fd: &mut R) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError("Invalid Http request: expected 0-length body for AdminGetNeighbors".to_string()));
+        }
+
+        Ok(HttpRequestType::AdminGetNeighbors(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble)))
+    }
+
+    /// Read the JSON body of an admin command
+    fn parse_admin_body<R: Read>(preamble: &HttpRequestPreamble, fd: &mut R) -> Result<RPCAdminRequestBody, net_error> {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < HTTP_ADMIN_REQUEST_MAX_LEN) {
+            return Err(net_error::DeserializeError(format!("Invalid Http request: invalid body length for AdminCommand ({})", content_len)));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError("Invalid content-type: expected application/json".to_string()));
+        }
+
+        serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))
+    }
+
+    /// Get the "ip:port" peer address from an admin command
+    fn parse_admin_addr(body: &RPCAdminRequestBody) -> Result<(PeerAddress, u16), net_error> {
+        let addr_str = body.addr.as_ref()
+            .ok_or_else(|| net_error::DeserializeError("Missing peer address".into()))?;
+        let addr = addr_str.parse::<SocketAddr>()
+            .map_err(|_e| net_error::DeserializeError("Failed to parse peer address".into()))?;
+        Ok((PeerAddress::from_socketaddr(&addr), addr.port()))
+    }
+
+    /// Get the "ip/mask" address range from an admin command.  The mask is converted to apply to
+    /// the 128-bit peer address.
+    fn parse_admin_cidr(body: &RPCAdminRequestBody) -> Result<(PeerAddress, u32), net_error> {
+        let cidr_str = body.cidr.as_ref()
+            .ok_or_else(|| net_error::DeserializeError("Missing CIDR prefix".into()))?;
+        let parts : Vec<&str> = cidr_str.split('/').collect();
+        if parts.len() != 2 {
+            return Err(net_error::DeserializeError("Failed to parse CIDR prefix".into()));
+        }
+
+        let ip = parts[0].parse::<IpAddr>()
+            .map_err(|_e| net_error::DeserializeError("Failed to parse CIDR prefix address".into()))?;
+        let mask = parts[1].parse::<u32>()
+            .map_err(|_e| net_error::DeserializeError("Failed to parse CIDR prefix mask".into()))?;
+
+        let mask = match ip {
+            IpAddr::V4(_) => {
+                if mask > 32 {
+                    return Err(net_error::DeserializeError("Invalid IPv4 CIDR prefix mask".into()));
+                }
+                mask + 96
+            },
+            IpAddr::V6(_) => {
+                if mask == 0 || mask > 128 {
+                    return Err(net_error::DeserializeError("Invalid IPv6 CIDR prefix mask".into()));
+                }
+                mask
+            }
+        };
+
+        Ok((PeerAddress::from_ip(&ip), mask))
+    }
+
+    fn parse_admin_ban_peer<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, fd: &mut R) -> Result<HttpRequestType, net_error> {
+        let body = HttpRequestType::parse_admin_body(preamble, fd)?;
+        let (addrbytes, port) = HttpRequestType::parse_admin_addr(&body)?;
+        Ok(HttpRequestType::AdminCommand(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble),
+                                         PeerAdminCommand::BanPeer(addrbytes, port, body.duration)))
+    }
+
+    fn parse_admin_unban_peer<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, fd: &mut R) -> Result<HttpRequestType, net_error> {
+        let body = HttpRequestType::parse_admin_body(preamble, fd)?;
+        let (addrbytes, port) = HttpRequestType::parse_admin_addr(&body)?;
+        Ok(HttpRequestType::AdminCommand(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble),
+                                         PeerAdminCommand::UnbanPeer(addrbytes, port)))
+    }
+
+    fn parse_admin_disconnect_peer<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, fd: &mut R) -> Result<HttpRequestType, net_error> {
+        let body = HttpRequestType::parse_admin_body(preamble, fd)?;
+        let (addrbytes, port) = HttpRequestType::parse_admin_addr(&body)?;
+        Ok(HttpRequestType::AdminCommand(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble),
+                                         PeerAdminCommand::Disconnect(addrbytes, port)))
+    }
+
+    fn parse_admin_add_bootstrap_peer<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, fd: &mut R) -> Result<HttpRequestType, net_error> {
+        let body = HttpRequestType::parse_admin_body(preamble, fd)?;
+        let (addrbytes, port) = HttpRequestType::parse_admin_addr(&body)?;
+        let public_key_hex = body.public_key.as_ref()
+            .ok_or_else(|| net_error::DeserializeError("Missing public key".into()))?;
+        let public_key = Secp256k1PublicKey::from_hex(public_key_hex)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse public key".into()))?;
+        Ok(HttpRequestType::AdminCommand(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble),
+                                         PeerAdminCommand::AddBootstrapPeer(addrbytes, port, public_key)))
+    }
+
+    fn parse_admin_ban_cidr<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, fd: &mut R) -> Result<HttpRequestType, net_error> {
+        let body = HttpRequestType::parse_admin_body(preamble, fd)?;
+        let (prefix, mask) = HttpRequestType::parse_admin_cidr(&body)?;
+        Ok(HttpRequestType::AdminCommand(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble),
+                                         PeerAdminCommand::BanCIDR(prefix, mask)))
+    }
+
+    fn parse_admin_unban_cidr<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, fd: &mut R) -> Result<HttpRequestType, net_error> {
+        let body = HttpRequestType::parse_admin_body(preamble, fd)?;
+        let (prefix, mask) = HttpRequestType::parse_admin_cidr(&body)?;
+        Ok(HttpRequestType::AdminCommand(HttpRequestMetadata::from_preamble(preamble), HttpRequestType::get_bearer_token(preamble),
+                                         PeerAdminCommand::UnbanCIDR(prefix, mask)))
+    }
+
     fn parse_get_transfer_cost<R: Read>(_protocol: &mut StacksHttp, preamble: &HttpRequestPreamble, _regex: &Captures, _query: Option<&str>, _fd: &mut R) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError("Invalid Http request: expected 0-length body for GetTransferCost".to_string()));
@@ -1516,6 +1669,8 @@ impl HttpRequestType {
             HttpRequestType::GetContractSrc(ref md, ..) => md,
             HttpRequestType::CallReadOnlyFunction(ref md, ..) => md,
             HttpRequestType::OptionsPreflight(ref md, ..) => md,
+            HttpRequestType::AdminGetNeighbors(ref md, ..) => md,
+            HttpRequestType::AdminCommand(ref md, ..) => md,
             HttpRequestType::ClientError(ref md, ..) => md,
         }
     }
@@ -1537,6 +1692,8 @@ impl HttpRequestType {
             HttpRequestType::GetContractSrc(ref mut md, ..) => md,
             HttpRequestType::CallReadOnlyFunction(ref mut md, ..) => md,
             HttpRequestType::OptionsPreflight(ref mut md, ..) => md,
+            HttpRequestType::AdminGetNeighbors(ref mut md, ..) => md,
+            HttpRequestType::AdminCommand(ref mut md, ..) => md,
             HttpRequestType::ClientError(ref mut md, ..) => md,
         }
     }
@@ -1553,6 +1710,41 @@ impl HttpRequestType {
         }
     }
 
+    /// Inverse of parse_admin_cidr
+    fn make_admin_cidr(prefix: &PeerAddress, mask: u32) -> String {
+        if prefix.is_ipv4() && mask >= 96 {
+            format!("{}/{}", prefix.to_socketaddr(0).ip(), mask - 96)
+        }
+        else {
+            format!("{}/{}", prefix.to_socketaddr(0).ip(), mask)
+        }
+    }
+
+    fn make_admin_body(command: &PeerAdminCommand) -> RPCAdminRequestBody {
+        match command {
+            PeerAdminCommand::BanPeer(addrbytes, port, duration) => RPCAdminRequestBody {
+                addr: Some(format!("{}", addrbytes.to_socketaddr(*port))),
+                duration: *duration,
+                ..RPCAdminRequestBody::default()
+            },
+            PeerAdminCommand::UnbanPeer(addrbytes, port) |
+            PeerAdminCommand::Disconnect(addrbytes, port) => RPCAdminRequestBody {
+                addr: Some(format!("{}", addrbytes.to_socketaddr(*port))),
+                ..RPCAdminRequestBody::default()
+            },
+            PeerAdminCommand::AddBootstrapPeer(addrbytes, port, public_key) => RPCAdminRequestBody {
+                addr: Some(format!("{}", addrbytes.to_socketaddr(*port))),
+                public_key: Some(to_hex(&public_key.to_bytes_compressed())),
+                ..RPCAdminRequestBody::default()
+            },
+            PeerAdminCommand::BanCIDR(prefix, mask) |
+            PeerAdminCommand::UnbanCIDR(prefix, mask) => RPCAdminRequestBody {
+                cidr: Some(HttpRequestType::make_admin_cidr(prefix, *mask)),
+                ..RPCAdminRequestBody::default()
+            },
+        }
+    }
+
     pub fn request_path(&self) -> String {
         match self {
             HttpRequestType::GetInfo(_md) => "/v2/info".to_string(),
@@ -1578,6 +1770,17 @@ impl HttpRequestType {
                 format!("/v2/contracts/call-read/{}/{}/{}{}", contract_addr, contract_name.as_str(), func_name.as_str(), HttpRequestType::make_query_string(tip_opt.as_ref(), true))
             },
             HttpRequestType::OptionsPreflight(_md, path) => path.to_string(),
+            HttpRequestType::AdminGetNeighbors(_md, _) => "/admin/neighbors".to_string(),
+            HttpRequestType::AdminCommand(_md, _, command) => {
+                match command {
+                    PeerAdminCommand::BanPeer(..) => "/admin/peers/ban".to_string(),
+                    PeerAdminCommand::UnbanPeer(..) => "/admin/peers/unban".to_string(),
+                    PeerAdminCommand::Disconnect(..) => "/admin/peers/disconnect".to_string(),
+                    PeerAdminCommand::AddBootstrapPeer(..) => "/admin/peers/bootstrap".to_string(),
+                    PeerAdminCommand::BanCIDR(..) => "/admin/cidrs/ban".to_string(),
+                    PeerAdminCommand::UnbanCIDR(..) => "/admin/cidrs/unban".to_string(),
+                }
+            },
             HttpRequestType::ClientError(_md, e) => {
                 match e {
                     ClientError::NotFound(path) => path.to_string(),
@@ -1630,6 +1833,20 @@ impl HttpRequestType {
                 HttpRequestPreamble::new_serialized(fd, &md.version, "POST", &self.request_path(), &md.peer, md.keep_alive, Some(request_body_bytes.len() as u32), Some(&HttpContentType::JSON), empty_headers)?;
                 fd.write_all(&request_body_bytes).map_err(net_error::WriteError)?;
             }
+            HttpRequestType::AdminGetNeighbors(md, token_opt) => {
+                HttpRequestPreamble::new_serialized(fd, &md.version, "GET", &self.request_path(), &md.peer, md.keep_alive, None, None,
+                                                    |ref mut fd| bearer_token_headers(fd, token_opt))?;
+            },
+            HttpRequestType::AdminCommand(md, token_opt, command) => {
+                let request_body = HttpRequestType::make_admin_body(command);
+
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| net_error::SerializeError(format!("Failed to serialize admin command to JSON: {:?}", &e)))?;
+
+                HttpRequestPreamble::new_serialized(fd, &md.version, "POST", &self.request_path(), &md.peer, md.keep_alive, Some(request_body_bytes.len() as u32), Some(&HttpContentType::JSON),
+                                                    |ref mut fd| bearer_token_headers(fd, token_opt))?;
+                fd.write_all(&request_body_bytes).map_err(net_error::WriteError)?;
+            },
             other_type => {
                 let md = other_type.metadata();
                 let request_path = other_type.request_path();
@@ -1810,6 +2027,13 @@ impl HttpResponseType {
             (&PATH_GET_CONTRACT_ABI, &HttpResponseType::parse_get_contract_abi),
             (&PATH_POST_CALL_READ_ONLY, &HttpResponseType::parse_call_read_only),
             (&PATH_GET_MAP_ENTRY, &HttpResponseType::parse_get_map_entry),
+            (&PATH_ADMIN_GETNEIGHBORS, &HttpResponseType::parse_admin_neighbors),
+            (&PATH_ADMIN_BAN_PEER, &HttpResponseType::parse_admin_command_accepted),
+            (&PATH_ADMIN_UNBAN_PEER, &HttpResponseType::parse_admin_command_accepted),
+            (&PATH_ADMIN_DISCONNECT_PEER, &HttpResponseType::parse_admin_command_accepted),
+            (&PATH_ADMIN_ADD_BOOTSTRAP_PEER, &HttpResponseType::parse_admin_command_accepted),
+            (&PATH_ADMIN_BAN_CIDR, &HttpResponseType::parse_admin_command_accepted),
+            (&PATH_ADMIN_UNBAN_CIDR, &HttpResponseType::parse_admin_command_accepted),
         ];
         
         // use url::Url to parse path and query string
@@ -1851,6 +2075,19 @@ impl HttpResponseType {
         Ok(HttpResponseType::Neighbors(HttpResponseMetadata::from_preamble(request_version, preamble), neighbors_data))
     }
 
+    fn parse_admin_neighbors<R: Read>(_protocol: &mut StacksHttp, request_version: HttpVersion, preamble: &HttpResponsePreamble, fd: &mut R, len_hint: Option<usize>) -> Result<HttpResponseType, net_error> {
+        let neighbors_data = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::AdminNeighbors(HttpResponseMetadata::from_preamble(request_version, preamble), neighbors_data))
+    }
+
+    fn parse_admin_command_accepted<R: Read>(_protocol: &mut StacksHttp, request_version: HttpVersion, preamble: &HttpResponsePreamble, fd: &mut R, len_hint: Option<usize>) -> Result<HttpResponseType, net_error> {
+        let accepted : bool = HttpResponseType::parse_json(preamble, fd, len_hint, 8)?;
+        if !accepted {
+            return Err(net_error::DeserializeError("Invalid admin command response".to_string()));
+        }
+        Ok(HttpResponseType::AdminCommandAccepted(HttpResponseMetadata::from_preamble(request_version, preamble)))
+    }
+
     fn parse_block<R: Read>(_protocol: &mut StacksHttp, request_version: HttpVersion, preamble: &HttpResponsePreamble, fd: &mut R, len_hint: Option<usize>) -> Result<HttpResponseType, net_error> {
         let block : StacksBlock = HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
         Ok(HttpResponseType::Block(HttpResponseMetadata::from_preamble(request_version, preamble), block))
@@ -1974,6 +2211,8 @@ impl HttpResponseType {
             HttpResponseType::GetContractSrc(ref md, _) => md,
             HttpResponseType::CallReadOnlyFunction(ref md, _) => md,
             HttpResponseType::OptionsPreflight(ref md) => md,
+            HttpResponseType::AdminNeighbors(ref md, _) => md,
+            HttpResponseType::AdminCommandAccepted(ref md) => md,
             // errors
             HttpResponseType::BadRequestJSON(ref md, _) => md,
             HttpResponseType::BadRequest(ref md, _) => md,
@@ -2098,6 +2337,14 @@ impl HttpResponseType {
                 HttpResponsePreamble::new_serialized(fd, 200, "OK", None, &HttpContentType::Text, md.request_id, |ref mut fd| keep_alive_headers(fd, md))?;
                 HttpResponseType::send_text(protocol, md, fd, "".as_bytes())?;
             },
+            HttpResponseType::AdminNeighbors(ref md, ref neighbor_data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, neighbor_data)?;
+            },
+            HttpResponseType::AdminCommandAccepted(ref md) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, &true)?;
+            },
             HttpResponseType::BadRequestJSON(ref md, ref data) => {
                 HttpResponsePreamble::new_serialized(fd, 400, HttpResponseType::error_reason(400), md.content_length.clone(), &HttpContentType::JSON, md.request_id, |ref mut fd| keep_alive_headers(fd, md))?;
                 HttpResponseType::send_json(protocol, md, fd, data)?;
@@ -2179,6 +2426,8 @@ impl MessageSequence for StacksHttpMessage {
                 HttpRequestType::GetContractSrc(..) => "HTTP(GetContractSrc)",
                 HttpRequestType::CallReadOnlyFunction(..) => "HTTP(CallReadOnlyFunction)",
                 HttpRequestType::OptionsPreflight(..) => "HTTP(OptionsPreflight)",
+                HttpRequestType::AdminGetNeighbors(..) => "HTTP(AdminGetNeighbors)",
+                HttpRequestType::AdminCommand(..) => "HTTP(AdminCommand)",
                 HttpRequestType::ClientError(..) => "HTTP(ClientError)",
             },
             StacksHttpMessage::Response(ref res) => match res {
@@ -2197,6 +2446,8 @@ impl MessageSequence for StacksHttpMessage {
                 HttpResponseType::TransactionID(_, _) => "HTTP(Transaction)",
                 HttpResponseType::MicroblockHash(_, _) => "HTTP(Microblock)",
                 HttpResponseType::OptionsPreflight(_) => "HTTP(OptionsPreflight)",
+                HttpResponseType::AdminNeighbors(_, _) => "HTTP(AdminNeighbors)",
+                HttpResponseType::AdminCommandAccepted(_) => "HTTP(AdminCommandAccepted)",
                 HttpResponseType::BadRequestJSON(..) | HttpResponseType::BadRequest(..) => "HTTP(400)",
                 HttpResponseType::Unauthorized(_, _) => "HTTP(401)",
                 HttpResponseType::PaymentRequired(_, _) => "HTTP(402)",
@@ -2626,6 +2877,8 @@ mod test {
     use net::codec::test::check_codec_and_corruption;
     use net::RPCNeighbor;
     use net::RPCNeighborsInfo;
+    use net::RPCAdminNeighbor;
+    use net::RPCAdminNeighborsInfo;
 
     use burnchains::Txid;
     use chainstate::stacks::test::make_codec_test_block;
@@ -3395,6 +3648,65 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_http_admin_request_codec() {
+        let md = HttpRequestMetadata {
+            version: HttpVersion::Http11,
+            peer: PeerHost::DNS("www.foo.com".to_string(), 80),
+            keep_alive: true,
+        };
+        let token = Some("secret-token".to_string());
+        let pubkey = Secp256k1PublicKey::from_hex("02fa66b66f8971a8cd4d20ffded09674e030f0f33883f337f34b95ad4935bac0e3").unwrap();
+
+        let tests = vec![
+            HttpRequestType::AdminGetNeighbors(md.clone(), token.clone()),
+            HttpRequestType::AdminGetNeighbors(md.clone(), None),
+            HttpRequestType::AdminCommand(md.clone(), token.clone(), PeerAdminCommand::BanPeer(PeerAddress::from_ipv4(1, 2, 3, 4), 20444, Some(3600))),
+            HttpRequestType::AdminCommand(md.clone(), token.clone(), PeerAdminCommand::BanPeer(PeerAddress([0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15]), 20444, None)),
+            HttpRequestType::AdminCommand(md.clone(), token.clone(), PeerAdminCommand::UnbanPeer(PeerAddress::from_ipv4(1, 2, 3, 4), 20444)),
+            HttpRequestType::AdminCommand(md.clone(), None, PeerAdminCommand::Disconnect(PeerAddress::from_ipv4(1, 2, 3, 4), 20444)),
+            HttpRequestType::AdminCommand(md.clone(), token.clone(), PeerAdminCommand::BanCIDR(PeerAddress::from_ipv4(1, 2, 0, 0), 112)),
+            HttpRequestType::AdminCommand(md.clone(), token.clone(), PeerAdminCommand::UnbanCIDR(PeerAddress([0,1,2,3,4,5,6,7,0,0,0,0,0,0,0,0]), 64)),
+            HttpRequestType::AdminCommand(md.clone(), token.clone(), PeerAdminCommand::AddBootstrapPeer(PeerAddress::from_ipv4(5, 6, 7, 8), 20444, pubkey.clone())),
+        ];
+
+        for test in tests.iter() {
+            let mut bytes = vec![];
+            let mut http = StacksHttp::new();
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(test.clone())).unwrap();
+
+            test_debug!("Admin request:\n{}", str::from_utf8(&bytes).unwrap());
+
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            assert_eq!(message, StacksHttpMessage::Request(test.clone()));
+        }
+
+        let bad_requests = vec![
+            // no body
+            ("POST /admin/peers/ban HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 0\r\nContent-Type: application/json\r\n\r\n", "invalid body length"),
+            // wrong content type
+            ("POST /admin/peers/ban HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 2\r\nContent-Type: text/plain\r\n\r\n{}", "Invalid content-type"),
+            // missing address
+            ("POST /admin/peers/ban HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 2\r\nContent-Type: application/json\r\n\r\n{}", "Missing peer address"),
+            // bad address
+            ("POST /admin/peers/disconnect HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 18\r\nContent-Type: application/json\r\n\r\n{\"addr\":\"1.2.3.4\"}", "Failed to parse peer address"),
+            // bad masks
+            ("POST /admin/cidrs/ban HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 21\r\nContent-Type: application/json\r\n\r\n{\"cidr\":\"1.2.0.0/33\"}", "Invalid IPv4 CIDR prefix mask"),
+            ("POST /admin/cidrs/unban HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 16\r\nContent-Type: application/json\r\n\r\n{\"cidr\":\"::1/0\"}", "Invalid IPv6 CIDR prefix mask"),
+            // bad public key
+            ("POST /admin/peers/bootstrap HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 44\r\nContent-Type: application/json\r\n\r\n{\"addr\":\"1.2.3.4:20444\",\"public_key\":\"0011\"}", "Failed to parse public key"),
+        ];
+        for (bad_request, expected_error) in bad_requests {
+            let mut http = StacksHttp::new();
+            let (preamble, offset) = http.read_preamble(bad_request.as_bytes()).unwrap();
+            let e = http.read_payload(&preamble, &bad_request.as_bytes()[offset..]);
+            let estr = format!("{:?}", &e);
+            assert!(e.is_err(), estr);
+            assert!(e.unwrap_err().to_string().find(expected_error).is_some(), estr);
+        }
+    }
+
     #[test]
     fn test_http_response_type_codec() {
         let test_neighbors_info = RPCNeighborsInfo {
@@ -3406,6 +3718,7 @@ mod test {
                     port: 12345,
                     public_key_hash: Hash160::from_bytes(&hex_bytes("1111111111111111111111111111111111111111").unwrap()).unwrap(),
                     authenticated: true,
+                    encrypted: true,
                 },
                 RPCNeighbor {
                     network_id: 3,
@@ -3414,12 +3727,34 @@ mod test {
                     port: 23456,
                     public_key_hash: Hash160::from_bytes(&hex_bytes("2222222222222222222222222222222222222222").unwrap()).unwrap(),
                     authenticated: false,
+                    encrypted: false,
                 },
             ],
             inbound: vec![],
             outbound: vec![]
         };
 
+        let test_admin_neighbors_info = RPCAdminNeighborsInfo {
+            neighbors: vec![
+                RPCAdminNeighbor {
+                    neighbor: test_neighbors_info.sample[0].clone(),
+                    outbound: true,
+                    first_contact_time: 1,
+                    last_contact_time: 2,
+                    last_send_time: 3,
+                    last_recv_time: 4,
+                    last_handshake_time: 5,
+                    bytes_tx: 6,
+                    bytes_rx: 7,
+                    msgs_tx: 8,
+                    msgs_rx: 9,
+                    msgs_rx_unsolicited: 10,
+                    msgs_err: 11,
+                    health_score: 0.5,
+                },
+            ]
+        };
+
         let privk = StacksPrivateKey::from_hex("6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001").unwrap();
         let test_block_info = make_codec_test_block(5);
         let test_microblock_info = make_sample_microblock_stream(&privk, &test_block_info.block_hash());
@@ -3436,6 +3771,8 @@ mod test {
             (HttpResponseType::Block(HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(test_block_info_bytes.len() as u32), true), test_block_info.clone()), format!("/v2/blocks/{}", test_block_info.block_hash().to_hex())),
             (HttpResponseType::Microblocks(HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(test_microblock_info_bytes.len() as u32), true), test_microblock_info.clone()), format!("/v2/microblocks/{}", test_microblock_info[0].block_hash().to_hex())),
             (HttpResponseType::TransactionID(HttpResponseMetadata::new(HttpVersion::Http11, 123, Some((Txid([0x1; 32]).to_hex().len() + 2) as u32), true), Txid([0x1; 32])), "/v2/transactions".to_string()),
+            (HttpResponseType::AdminNeighbors(HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(serde_json::to_string(&test_admin_neighbors_info).unwrap().len() as u32), true), test_admin_neighbors_info.clone()), "/admin/neighbors".to_string()),
+            (HttpResponseType::AdminCommandAccepted(HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(4), true)), "/admin/peers/ban".to_string()),
             
             // length is unknown
             (HttpResponseType::Neighbors(HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true), test_neighbors_info.clone()), "/v2/neighbors".to_string()),
@@ -3470,6 +3807,8 @@ mod test {
             HttpResponsePreamble::new(200, "OK".to_string(), Some(test_block_info_bytes.len() as u32), HttpContentType::Bytes, true, 123),
             HttpResponsePreamble::new(200, "OK".to_string(), Some(test_microblock_info_bytes.len() as u32), HttpContentType::Bytes, true, 123),
             HttpResponsePreamble::new(200, "OK".to_string(), Some((Txid([0x1; 32]).to_hex().len() + 2) as u32), HttpContentType::JSON, true, 123),
+            HttpResponsePreamble::new(200, "OK".to_string(), Some(serde_json::to_string(&test_admin_neighbors_info).unwrap().len() as u32), HttpContentType::JSON, true, 123),
+            HttpResponsePreamble::new(200, "OK".to_string(), Some(4), HttpContentType::JSON, true, 123),
             
             // length is unknown
             HttpResponsePreamble::new(200, "OK".to_string(), None, HttpContentType::JSON, true, 123),
@@ -3507,6 +3846,8 @@ mod test {
             test_block_info_bytes.clone(),
             test_microblock_info_bytes.clone(),
             Txid([0x1; 32]).to_hex().as_bytes().to_vec(),
+            serde_json::to_string(&test_admin_neighbors_info).unwrap().as_bytes().to_vec(),
+            "true".as_bytes().to_vec(),
             
             // with transfer-encoding: chunked
             serde_json::to_string(&test_neighbors_info).unwrap().as_bytes().to_vec(),
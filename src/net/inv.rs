@@ -287,7 +287,7 @@ impl NeighborBlockStats {
 
         let mut ret = vec![];
         let ic = sortdb.index_conn();
-        let canonical_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic)?;
+        let canonical_tip = SortitionDB::get_canonical_burn_chain_tip(&ic)?;
         for height in sortition_height_start..sortition_height_end {
             if !self.inv.has_ith_block(height) {
                 // of the edge of the bitmap
@@ -541,8 +541,7 @@ impl InvState {
     /// Returns the optional block sortition height at which the block or confirmed microblock stream resides in the blockchain (returns
     /// None if its bit was already set).
     fn set_data_available(&mut self, neighbor_key: &NeighborKey, sortdb: &SortitionDB, consensus_hash: &ConsensusHash, burn_header_hash: &BurnchainHeaderHash, microblocks: bool) -> Result<Option<u64>, net_error> {
-        let sortid = SortitionId::stubbed(burn_header_hash);
-        let sn = match SortitionDB::get_block_snapshot(&sortdb.conn, &sortid)? {
+        let sn = match SortitionDB::get_block_snapshot_by_burn_header_hash(&sortdb.conn, burn_header_hash)? {
             Some(sn) => sn,
             None => {
                 // we don't know about this block
@@ -866,7 +865,7 @@ impl PeerNetwork {
         assert!(num_blocks <= BLOCKS_INV_DATA_MAX_BITLEN as u64);
 
         let ic = sortdb.index_conn();
-        let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic)?;
+        let tip = SortitionDB::get_canonical_burn_chain_tip(&ic)?;
         match SortitionDB::get_ancestor_snapshot(&ic, highest_block_height, &tip.sortition_id)? {
             Some(sn) => {
                 let ch = sn.consensus_hash;
@@ -1538,7 +1537,7 @@ mod test {
 
         let num_blocks = 5;
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -1551,7 +1550,7 @@ mod test {
         }
 
         let (tip, num_burn_blocks) = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             let num_burn_blocks = sn.block_height - peer_1.config.burnchain.first_block_height;
             (sn, num_burn_blocks)
         };
@@ -1680,7 +1679,7 @@ mod test {
 
         let num_blocks = (BLOCKS_INV_DATA_MAX_BITLEN * 2) as u64;
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -1694,7 +1693,7 @@ mod test {
         }
 
         let num_burn_blocks = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height - 1
         };
         
@@ -1802,7 +1801,7 @@ mod test {
         assert!(num_blocks > peer_1.config.burnchain.consensus_hash_lifetime as u64);      // required to test that this peer will be considered stale
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -1815,7 +1814,7 @@ mod test {
         }
 
         let num_burn_blocks = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height - 1
         };
         
@@ -1901,7 +1900,7 @@ mod test {
         assert!(num_blocks > peer_1.config.burnchain.consensus_hash_lifetime as u64);      // required to test that this peer will be considered stale
 
         let first_stacks_block_height = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height
         };
 
@@ -1925,7 +1924,7 @@ mod test {
         }
 
         let num_burn_blocks = {
-            let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+            let sn = SortitionDB::get_canonical_burn_chain_tip(peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
             sn.block_height - 1
         };
         
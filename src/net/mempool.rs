@@ -0,0 +1,737 @@
+/*
+ copyright: (c) 2013-2020 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use net::NeighborKey;
+use net::Error as net_error;
+
+use net::*;
+
+use net::connection::ReplyHandleP2P;
+
+use net::p2p::PeerNetwork;
+
+use burnchains::Txid;
+
+use chainstate::stacks::StacksTransaction;
+
+use core::mempool::MemPoolDB;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use util::log;
+use util::get_epoch_time_secs;
+
+/// This module is responsible for synchronizing mempools with other peers.
+/// We page through each outbound neighbor's txids in ascending byte order, and ask for the
+/// transactions behind the txids we don't have.
+#[cfg(not(test))] pub const MEMPOOL_SYNC_INTERVAL : u64 = 120;
+#[cfg(test)] pub const MEMPOOL_SYNC_INTERVAL : u64 = 10;
+
+/// Maximum number of transaction bytes we'll fetch in one pass over our neighbors' mempools
+pub const MEMPOOL_SYNC_MAX_BYTES : u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum MempoolSyncWorkState {
+    GetTxidsBegin,
+    GetTxidsFinish,
+    GetTxsBegin,
+    GetTxsFinish,
+    Done
+}
+
+pub struct MempoolSyncState {
+    /// What state is this state-machine in?
+    state: MempoolSyncWorkState,
+
+    /// In-flight requests for txid pages
+    getmempooltxids_requests: HashMap<NeighborKey, ReplyHandleP2P>,
+    /// In-flight requests for transactions, and the txids we asked each peer for
+    getmempooltxs_requests: HashMap<NeighborKey, ReplyHandleP2P>,
+    getmempooltxs_txids: HashMap<NeighborKey, HashSet<Txid>>,
+
+    /// The last txid we got from each peer's mempool in this pass
+    page_cursors: HashMap<NeighborKey, Txid>,
+    /// Peers whose mempools we've paged through completely in this pass
+    exhausted_peers: HashSet<NeighborKey>,
+    /// Txids each peer advertised that we don't have and haven't asked for yet
+    missing_txids: HashMap<NeighborKey, VecDeque<Txid>>,
+    /// Txids we've asked for in this pass, from any peer
+    requested_txids: HashSet<Txid>,
+
+    /// Transactions fetched in this pass
+    pub synced_txs: Vec<StacksTransaction>,
+    /// Number of transaction bytes fetched in this pass
+    pub num_bytes_synced: u64,
+
+    /// Peers that sent protocol-violating data
+    pub broken_peers: HashSet<NeighborKey>,
+    /// Peers that we couldn't contact
+    pub dead_peers: HashSet<NeighborKey>,
+
+    /// How long is a request allowed to take?
+    request_timeout: u64,
+    /// How often to re-sync
+    sync_interval: u64,
+    /// How many transaction bytes can we fetch in one pass?
+    max_bytes: u64,
+    /// Last time we finished a pass
+    pub last_synced_at: u64,
+}
+
+impl MempoolSyncState {
+    pub fn new(request_timeout: u64, sync_interval: u64, max_bytes: u64) -> MempoolSyncState {
+        MempoolSyncState {
+            state: MempoolSyncWorkState::GetTxidsBegin,
+
+            getmempooltxids_requests: HashMap::new(),
+            getmempooltxs_requests: HashMap::new(),
+            getmempooltxs_txids: HashMap::new(),
+
+            page_cursors: HashMap::new(),
+            exhausted_peers: HashSet::new(),
+            missing_txids: HashMap::new(),
+            requested_txids: HashSet::new(),
+
+            synced_txs: vec![],
+            num_bytes_synced: 0,
+
+            broken_peers: HashSet::new(),
+            dead_peers: HashSet::new(),
+
+            request_timeout: request_timeout,
+            sync_interval: sync_interval,
+            max_bytes: max_bytes,
+            last_synced_at: 0,
+        }
+    }
+
+    pub fn reset(&mut self) -> () {
+        self.state = MempoolSyncWorkState::GetTxidsBegin;
+
+        self.getmempooltxids_requests.clear();
+        self.getmempooltxs_requests.clear();
+        self.getmempooltxs_txids.clear();
+
+        self.page_cursors.clear();
+        self.exhausted_peers.clear();
+        self.missing_txids.clear();
+        self.requested_txids.clear();
+
+        self.synced_txs.clear();
+        self.num_bytes_synced = 0;
+
+        self.broken_peers.clear();
+        self.dead_peers.clear();
+    }
+
+    /// Have we fetched as many bytes as we're allowed to in this pass?
+    fn is_budget_exhausted(&self) -> bool {
+        self.num_bytes_synced >= self.max_bytes
+    }
+
+    /// Do we still know of txids to fetch?
+    fn has_missing_txids(&self) -> bool {
+        self.missing_txids.values().any(|txids| txids.len() > 0)
+    }
+
+    /// Stop talking to dead and broken peers for the rest of this pass
+    fn cull_unusable_peers(&mut self) -> () {
+        for nk in self.dead_peers.iter().chain(self.broken_peers.iter()) {
+            self.missing_txids.remove(nk);
+            self.exhausted_peers.insert(nk.clone());
+        }
+    }
+
+    /// Record a page of txids from a peer.
+    /// Advance the peer's cursor, and remember which txids we'll need to ask it for.
+    /// The page must be no longer than we asked for, and its txids must come after the peer's
+    /// cursor in strictly increasing order.  Otherwise, the peer is broken -- it could keep us
+    /// paging through its mempool forever.
+    fn add_txid_page(&mut self, mempool: &MemPoolDB, nk: NeighborKey, txids: Vec<Txid>) -> () {
+        if (txids.len() as u32) > MEMPOOL_TXIDS_PAGE_MAX_LEN {
+            debug!("Remote neighbor {:?} sent {} txids, but we asked for at most {}", &nk, txids.len(), MEMPOOL_TXIDS_PAGE_MAX_LEN);
+            self.broken_peers.insert(nk);
+            return;
+        }
+
+        let mut prev_txid = self.page_cursors.get(&nk).cloned().unwrap_or(Txid([0u8; 32]));
+        for txid in txids.iter() {
+            if txid.as_bytes() <= prev_txid.as_bytes() {
+                debug!("Remote neighbor {:?} sent txid {} after {}, out of order", &nk, txid, &prev_txid);
+                self.broken_peers.insert(nk);
+                return;
+            }
+            prev_txid = txid.clone();
+        }
+
+        if (txids.len() as u32) < MEMPOOL_TXIDS_PAGE_MAX_LEN {
+            // last page
+            self.exhausted_peers.insert(nk.clone());
+        }
+
+        if let Some(last_txid) = txids.last() {
+            self.page_cursors.insert(nk.clone(), last_txid.clone());
+        }
+
+        let mut missing = VecDeque::new();
+        for txid in txids.into_iter() {
+            if self.requested_txids.contains(&txid) || mempool.has_tx(&txid) {
+                continue;
+            }
+            missing.push_back(txid);
+        }
+
+        test_debug!("Neighbor {:?} has {} transactions we don't", &nk, missing.len());
+        self.missing_txids.entry(nk).or_insert(VecDeque::new()).append(&mut missing);
+    }
+
+    /// Try to finish getting all MempoolTxids replies.
+    /// Return true if all requests have been handled.
+    pub fn getmempooltxids_try_finish(&mut self, network: &mut PeerNetwork, mempool: &MemPoolDB) -> Result<bool, net_error> {
+        assert_eq!(self.state, MempoolSyncWorkState::GetTxidsFinish);
+
+        let mut pending_getmempooltxids_requests = HashMap::new();
+        for (nk, mut rh) in self.getmempooltxids_requests.drain().collect::<Vec<_>>().into_iter() {
+            if let Err(_e) = network.saturate_p2p_socket(rh.get_event_id(), &mut rh) {
+                self.dead_peers.insert(nk);
+                continue;
+            }
+            match rh.try_send_recv() {
+                Ok(message) => {
+                    match message.payload {
+                        StacksMessageType::MempoolTxids(txids_data) => {
+                            debug!("Got {} mempool txids from {:?}", txids_data.txids.len(), &nk);
+                            self.add_txid_page(mempool, nk, txids_data.txids);
+                        },
+                        StacksMessageType::Nack(nack_data) => {
+                            // don't ask this peer again in this pass
+                            debug!("Remote neighbor {:?} nack'ed our GetMempoolTxids: NACK code {}", &nk, nack_data.error_code);
+                            self.exhausted_peers.insert(nk);
+                        },
+                        _ => {
+                            // unexpected reply
+                            debug!("Remote neighbor {:?} sent an unexpected reply of '{}'", &nk, message.get_message_name());
+                            self.broken_peers.insert(nk);
+                        }
+                    }
+                },
+                Err(Ok(same_req)) => {
+                    // try again
+                    pending_getmempooltxids_requests.insert(nk, same_req);
+                },
+                Err(Err(_e)) => {
+                    // connection broken.
+                    debug!("Failed to get mempool txids from {:?}: {:?}", &nk, &_e);
+                    self.dead_peers.insert(nk);
+                }
+            }
+        }
+
+        debug!("Still waiting for {} mempool txid replies", pending_getmempooltxids_requests.len());
+
+        if pending_getmempooltxids_requests.len() == 0 {
+            self.cull_unusable_peers();
+            self.state = MempoolSyncWorkState::GetTxsBegin;
+            return Ok(true);
+        }
+
+        self.getmempooltxids_requests = pending_getmempooltxids_requests;
+        Ok(false)
+    }
+
+    /// Try to finish getting all MempoolTxs replies.
+    /// Return true if all requests have been handled.
+    pub fn getmempooltxs_try_finish(&mut self, network: &mut PeerNetwork) -> Result<bool, net_error> {
+        assert_eq!(self.state, MempoolSyncWorkState::GetTxsFinish);
+
+        let mut pending_getmempooltxs_requests = HashMap::new();
+        for (nk, mut rh) in self.getmempooltxs_requests.drain().collect::<Vec<_>>().into_iter() {
+            if let Err(_e) = network.saturate_p2p_socket(rh.get_event_id(), &mut rh) {
+                self.dead_peers.insert(nk);
+                continue;
+            }
+            match rh.try_send_recv() {
+                Ok(message) => {
+                    match message.payload {
+                        StacksMessageType::MempoolTxs(txs_data) => {
+                            let requested = self.getmempooltxs_txids.remove(&nk).unwrap_or(HashSet::new());
+                            let mut txs = Vec::with_capacity(txs_data.txs.len());
+                            let mut num_bytes = 0;
+                            let mut unrequested = false;
+                            for tx in txs_data.txs.into_iter() {
+                                if !requested.contains(&tx.txid()) {
+                                    unrequested = true;
+                                    break;
+                                }
+                                num_bytes += tx.serialize_to_vec().len() as u64;
+                                txs.push(tx);
+                            }
+
+                            if unrequested {
+                                // peer sent us something we didn't ask for
+                                debug!("Remote neighbor {:?} sent a transaction we did not request", &nk);
+                                self.broken_peers.insert(nk);
+                            }
+                            else {
+                                debug!("Got {} mempool transactions ({} bytes) from {:?}", txs.len(), num_bytes, &nk);
+                                self.num_bytes_synced += num_bytes;
+                                self.synced_txs.append(&mut txs);
+                            }
+                        },
+                        StacksMessageType::Nack(nack_data) => {
+                            // peer is throttling us, or can't serve us; stop asking it
+                            debug!("Remote neighbor {:?} nack'ed our GetMempoolTxs: NACK code {}", &nk, nack_data.error_code);
+                            self.exhausted_peers.insert(nk.clone());
+                            self.missing_txids.remove(&nk);
+                        },
+                        _ => {
+                            // unexpected reply
+                            debug!("Remote neighbor {:?} sent an unexpected reply of '{}'", &nk, message.get_message_name());
+                            self.broken_peers.insert(nk);
+                        }
+                    }
+                },
+                Err(Ok(same_req)) => {
+                    // try again
+                    pending_getmempooltxs_requests.insert(nk, same_req);
+                },
+                Err(Err(_e)) => {
+                    // connection broken.
+                    debug!("Failed to get mempool transactions from {:?}: {:?}", &nk, &_e);
+                    self.dead_peers.insert(nk);
+                }
+            }
+        }
+
+        debug!("Still waiting for {} mempool transaction replies", pending_getmempooltxs_requests.len());
+
+        if pending_getmempooltxs_requests.len() == 0 {
+            self.getmempooltxs_txids.clear();
+            self.cull_unusable_peers();
+
+            self.state =
+                if self.is_budget_exhausted() {
+                    debug!("Fetched {} mempool bytes in this pass; stopping", self.num_bytes_synced);
+                    MempoolSyncWorkState::Done
+                }
+                else if self.has_missing_txids() {
+                    MempoolSyncWorkState::GetTxsBegin
+                }
+                else {
+                    MempoolSyncWorkState::GetTxidsBegin
+                };
+            return Ok(true);
+        }
+
+        self.getmempooltxs_requests = pending_getmempooltxs_requests;
+        Ok(false)
+    }
+}
+
+impl PeerNetwork {
+    pub fn with_mempool_sync_state<F, R>(network: &mut PeerNetwork, handler: F) -> Result<R, net_error>
+    where
+        F: FnOnce(&mut PeerNetwork, &mut MempoolSyncState) -> Result<R, net_error>
+    {
+        let mut mempool_sync = network.mempool_sync.take();
+        let res = match mempool_sync {
+            None => {
+                test_debug!("{:?}: mempool sync state not connected", &network.local_peer);
+                Err(net_error::NotConnected)
+            },
+            Some(ref mut ms) => handler(network, ms)
+        };
+        network.mempool_sync = mempool_sync;
+        res
+    }
+
+    /// Get the outbound, authenticated neighbors we can sync mempools with
+    fn get_mempool_sync_peers(&self) -> Vec<NeighborKey> {
+        let mut peers = vec![];
+        for (nk, event_id) in self.events.iter() {
+            if let Some(convo) = self.peers.get(event_id) {
+                if convo.is_outbound() && convo.is_authenticated() {
+                    peers.push(nk.clone());
+                }
+            }
+        }
+        peers
+    }
+
+    /// Start requesting the next page of txids from each neighbor we haven't finished paging
+    /// through.  If there are no such neighbors, then this pass is done.
+    pub fn mempool_getmempooltxids_begin(&mut self) -> Result<(), net_error> {
+        test_debug!("{:?}: getmempooltxids_begin", &self.local_peer);
+        PeerNetwork::with_mempool_sync_state(self, |ref mut network, ref mut sync_state| {
+            let mut requests = HashMap::new();
+            for nk in network.get_mempool_sync_peers().into_iter() {
+                if sync_state.exhausted_peers.contains(&nk) {
+                    continue;
+                }
+
+                let last_txid = sync_state.page_cursors.get(&nk).cloned().unwrap_or(Txid([0u8; 32]));
+                let request = GetMempoolTxidsData {
+                    last_txid: last_txid,
+                    max_txids: MEMPOOL_TXIDS_PAGE_MAX_LEN as u16
+                };
+
+                debug!("{:?}: send getmempooltxids request {:?} to {:?}", &network.local_peer, &request, &nk);
+
+                let message = network.sign_for_peer(&nk, StacksMessageType::GetMempoolTxids(request))?;
+                let rh = match network.send_message(&nk, message, sync_state.request_timeout) {
+                    Ok(rh) => rh,
+                    Err(e) => {
+                        debug!("Failed to send GetMempoolTxids to {:?}: {:?}", &nk, &e);
+                        sync_state.exhausted_peers.insert(nk);
+                        continue;
+                    }
+                };
+                requests.insert(nk, rh);
+            }
+
+            if requests.len() == 0 {
+                debug!("{:?}: no more mempool txids to fetch in this pass", &network.local_peer);
+                sync_state.state = MempoolSyncWorkState::Done;
+            }
+            else {
+                sync_state.getmempooltxids_requests = requests;
+                sync_state.state = MempoolSyncWorkState::GetTxidsFinish;
+            }
+            Ok(())
+        })
+    }
+
+    /// Finish requesting pages of txids
+    pub fn mempool_getmempooltxids_finish(&mut self, mempool: &MemPoolDB) -> Result<bool, net_error> {
+        test_debug!("{:?}: getmempooltxids_try_finish", &self.local_peer);
+        PeerNetwork::with_mempool_sync_state(self, |ref mut network, ref mut sync_state| {
+            sync_state.getmempooltxids_try_finish(network, mempool)
+        })
+    }
+
+    /// Start requesting the transactions we're missing, in batches of at most
+    /// MEMPOOL_TXS_REQUEST_MAX_LEN per neighbor.
+    pub fn mempool_getmempooltxs_begin(&mut self) -> Result<(), net_error> {
+        test_debug!("{:?}: getmempooltxs_begin", &self.local_peer);
+        PeerNetwork::with_mempool_sync_state(self, |ref mut network, ref mut sync_state| {
+            let mut requests = HashMap::new();
+            let mut requested_txids = HashMap::new();
+            let nks : Vec<NeighborKey> = sync_state.missing_txids.keys().cloned().collect();
+            for nk in nks.into_iter() {
+                let mut txids = vec![];
+                {
+                    let missing = sync_state.missing_txids.get_mut(&nk).expect("BUG: no missing txids");
+                    while txids.len() < (MEMPOOL_TXS_REQUEST_MAX_LEN as usize) {
+                        let txid = match missing.pop_front() {
+                            Some(txid) => txid,
+                            None => {
+                                break;
+                            }
+                        };
+                        if sync_state.requested_txids.contains(&txid) {
+                            // another peer is serving it
+                            continue;
+                        }
+                        sync_state.requested_txids.insert(txid.clone());
+                        txids.push(txid);
+                    }
+                }
+                if txids.len() == 0 {
+                    continue;
+                }
+
+                debug!("{:?}: send getmempooltxs request for {} txs to {:?}", &network.local_peer, txids.len(), &nk);
+
+                let txid_set : HashSet<Txid> = txids.iter().cloned().collect();
+                let message = network.sign_for_peer(&nk, StacksMessageType::GetMempoolTxs(GetMempoolTxsData { txids: txids }))?;
+                let rh = match network.send_message(&nk, message, sync_state.request_timeout) {
+                    Ok(rh) => rh,
+                    Err(e) => {
+                        debug!("Failed to send GetMempoolTxs to {:?}: {:?}", &nk, &e);
+                        sync_state.missing_txids.remove(&nk);
+                        sync_state.exhausted_peers.insert(nk);
+                        continue;
+                    }
+                };
+                requests.insert(nk.clone(), rh);
+                requested_txids.insert(nk, txid_set);
+            }
+
+            sync_state.missing_txids.retain(|_, txids| txids.len() > 0);
+
+            if requests.len() == 0 {
+                // nothing to fetch from the pages we have; get more pages
+                sync_state.state = MempoolSyncWorkState::GetTxidsBegin;
+            }
+            else {
+                sync_state.getmempooltxs_requests = requests;
+                sync_state.getmempooltxs_txids = requested_txids;
+                sync_state.state = MempoolSyncWorkState::GetTxsFinish;
+            }
+            Ok(())
+        })
+    }
+
+    /// Finish requesting transactions
+    pub fn mempool_getmempooltxs_finish(&mut self) -> Result<bool, net_error> {
+        test_debug!("{:?}: getmempooltxs_try_finish", &self.local_peer);
+        PeerNetwork::with_mempool_sync_state(self, |ref mut network, ref mut sync_state| {
+            sync_state.getmempooltxs_try_finish(network)
+        })
+    }
+
+    /// Initialize mempool sync state
+    pub fn init_mempool_sync(&mut self) -> () {
+        debug!("{:?}: Initializing mempool sync state", &self.local_peer);
+        self.mempool_sync = Some(MempoolSyncState::new(self.connection_opts.timeout, self.connection_opts.mempool_sync_interval, self.connection_opts.max_mempool_sync_bytes));
+    }
+
+    /// Drive fetching transactions from our neighbors' mempools.
+    /// Returns whether or not we're done with this pass, the transactions we fetched, and the
+    /// list of dead and broken peers that we should disconnect from.
+    pub fn sync_peer_mempools(&mut self, mempool: &MemPoolDB) -> Result<(bool, Vec<StacksTransaction>, Vec<NeighborKey>, Vec<NeighborKey>), net_error> {
+        if self.mempool_sync.is_none() {
+            self.init_mempool_sync();
+        }
+
+        match self.mempool_sync {
+            Some(ref sync_state) => {
+                if sync_state.state == MempoolSyncWorkState::GetTxidsBegin && sync_state.last_synced_at + sync_state.sync_interval >= get_epoch_time_secs() {
+                    // not time to sync again yet
+                    test_debug!("{:?}: Throttle mempool sync until {}s", &self.local_peer, sync_state.last_synced_at + sync_state.sync_interval);
+                    return Ok((true, vec![], vec![], vec![]));
+                }
+            },
+            None => {
+                unreachable!();
+            }
+        }
+
+        let res = loop {
+            let state = self.mempool_sync.as_ref().unwrap().state;
+
+            debug!("{:?}: mempool-sync state is {:?}", &self.local_peer, state);
+            let done_res = match state {
+                MempoolSyncWorkState::GetTxidsBegin => {
+                    self.mempool_getmempooltxids_begin()
+                        .and_then(|_| Ok(false))
+                },
+                MempoolSyncWorkState::GetTxidsFinish => {
+                    self.mempool_getmempooltxids_finish(mempool)
+                        .and_then(|_| Ok(false))
+                },
+                MempoolSyncWorkState::GetTxsBegin => {
+                    self.mempool_getmempooltxs_begin()
+                        .and_then(|_| Ok(false))
+                },
+                MempoolSyncWorkState::GetTxsFinish => {
+                    self.mempool_getmempooltxs_finish()
+                        .and_then(|_| Ok(false))
+                },
+                MempoolSyncWorkState::Done => {
+                    Ok(true)
+                }
+            };
+
+            if done_res.is_err() {
+                break done_res;
+            }
+            if let Ok(true) = done_res {
+                break done_res;
+            }
+
+            let new_state = self.mempool_sync.as_ref().unwrap().state;
+            if new_state == state {
+                break done_res;
+            }
+        };
+
+        let done = match res {
+            Ok(d) => d,
+            Err(e) => {
+                warn!("Failed to execute mempool synchronization: {:?}", &e);
+                if let Some(ref mut sync_state) = self.mempool_sync {
+                    sync_state.reset();
+                }
+                return Err(e);
+            }
+        };
+
+        if !done {
+            return Ok((false, vec![], vec![], vec![]));
+        }
+
+        match self.mempool_sync {
+            Some(ref mut sync_state) => {
+                let txs = sync_state.synced_txs.split_off(0);
+                let dead = sync_state.dead_peers.drain().collect();
+                let broken = sync_state.broken_peers.drain().collect();
+
+                debug!("{:?}: Mempool sync finished; fetched {} transactions ({} bytes)", &self.local_peer, txs.len(), sync_state.num_bytes_synced);
+
+                sync_state.reset();
+                sync_state.last_synced_at = get_epoch_time_secs();
+                Ok((true, txs, dead, broken))
+            },
+            None => {
+                Ok((true, vec![], vec![], vec![]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use net::*;
+    use net::test::*;
+    use chainstate::stacks::*;
+    use chainstate::stacks::miner::test::make_user_stacks_transfer;
+    use burnchains::Address;
+    use core::{FIRST_BURNCHAIN_BLOCK_HASH, FIRST_STACKS_BLOCK_HASH};
+    use chainstate::stacks::db::test::{instantiate_chainstate, chainstate_path};
+    use util::hash::Sha512Trunc256Sum;
+
+    fn make_txids(num_txids: u32) -> Vec<Txid> {
+        let mut txids : Vec<Txid> = (0..num_txids)
+            .map(|i| Txid(Sha512Trunc256Sum::from_data(&i.to_be_bytes()).0))
+            .collect();
+        txids.sort_by(|t1, t2| t1.as_bytes().cmp(t2.as_bytes()));
+        txids
+    }
+
+    fn make_neighbor_key(port: u16) -> NeighborKey {
+        NeighborKey {
+            peer_version: 0x12345678,
+            network_id: 0x80000000,
+            addrbytes: PeerAddress([0u8; 16]),
+            port: port,
+        }
+    }
+
+    #[test]
+    fn test_mempool_sync_rejects_bad_txid_pages() {
+        let _chainstate = instantiate_chainstate(false, 0x80000000, "test_mempool_sync_rejects_bad_txid_pages");
+        let mempool = MemPoolDB::open(false, 0x80000000, &chainstate_path("test_mempool_sync_rejects_bad_txid_pages")).unwrap();
+
+        let txids = make_txids(MEMPOOL_TXIDS_PAGE_MAX_LEN * 3);
+        let page_len = MEMPOOL_TXIDS_PAGE_MAX_LEN as usize;
+        let mut sync_state = MempoolSyncState::new(30, 10, MEMPOOL_SYNC_MAX_BYTES);
+
+        // well-behaved
+        let good = make_neighbor_key(1);
+        sync_state.add_txid_page(&mempool, good.clone(), txids[0..page_len].to_vec());
+        sync_state.add_txid_page(&mempool, good.clone(), txids[page_len..2*page_len].to_vec());
+        assert_eq!(sync_state.page_cursors.get(&good), Some(&txids[2*page_len - 1]));
+        assert_eq!(sync_state.missing_txids.get(&good).unwrap().len(), 2*page_len);
+        assert!(!sync_state.exhausted_peers.contains(&good));
+
+        // sends the same page over and over
+        let repeating = make_neighbor_key(2);
+        sync_state.add_txid_page(&mempool, repeating.clone(), txids[0..page_len].to_vec());
+        assert!(!sync_state.broken_peers.contains(&repeating));
+        sync_state.add_txid_page(&mempool, repeating.clone(), txids[0..page_len].to_vec());
+        assert!(sync_state.broken_peers.contains(&repeating));
+
+        // goes back to before its cursor
+        let rewinding = make_neighbor_key(3);
+        sync_state.add_txid_page(&mempool, rewinding.clone(), txids[page_len..2*page_len].to_vec());
+        sync_state.add_txid_page(&mempool, rewinding.clone(), txids[page_len - 1..2*page_len - 1].to_vec());
+        assert!(sync_state.broken_peers.contains(&rewinding));
+
+        // a page out of order, or with a txid repeated
+        let unordered = make_neighbor_key(4);
+        let mut unordered_page = txids[0..page_len].to_vec();
+        unordered_page.swap(0, 1);
+        sync_state.add_txid_page(&mempool, unordered, unordered_page);
+
+        let duplicated = make_neighbor_key(5);
+        let mut duplicated_page = txids[0..page_len].to_vec();
+        duplicated_page[1] = duplicated_page[0].clone();
+        sync_state.add_txid_page(&mempool, duplicated, duplicated_page);
+
+        // a page longer than we asked for
+        let overlong = make_neighbor_key(6);
+        sync_state.add_txid_page(&mempool, overlong.clone(), txids[0..page_len + 1].to_vec());
+        assert!(sync_state.broken_peers.contains(&overlong));
+        assert!(sync_state.missing_txids.get(&overlong).is_none());
+
+        assert_eq!(sync_state.broken_peers.len(), 5);
+        assert!(!sync_state.broken_peers.contains(&good));
+
+        // the broken peers are done for this pass
+        sync_state.cull_unusable_peers();
+        for nk in sync_state.broken_peers.iter() {
+            assert!(sync_state.exhausted_peers.contains(nk));
+            assert!(sync_state.missing_txids.get(nk).is_none());
+        }
+        assert!(!sync_state.exhausted_peers.contains(&good));
+        assert_eq!(sync_state.missing_txids.get(&good).unwrap().len(), 2*page_len);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_mempool_sync_2_peers() {
+        let mut peer_1_config = TestPeerConfig::new("test_mempool_sync_2_peers", 32800, 42800);
+        let mut peer_2_config = TestPeerConfig::new("test_mempool_sync_2_peers", 32801, 42801);
+
+        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
+        peer_2_config.add_neighbor(&peer_1_config.to_neighbor());
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        // more transactions than fit into one page of txids, or one request for transactions
+        let recipient = StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV").unwrap().to_account_principal();
+        let privk = StacksPrivateKey::new();
+        let num_txs = (MEMPOOL_TXIDS_PAGE_MAX_LEN * 2 + 1) as u64;
+        let mut expected_txids = HashSet::new();
+        for nonce in 0..num_txs {
+            let tx = make_user_stacks_transfer(&privk, nonce, 100, &recipient, 1);
+            expected_txids.insert(tx.txid());
+            peer_2.mempool.as_mut().unwrap().submit_raw(&FIRST_BURNCHAIN_BLOCK_HASH, &FIRST_STACKS_BLOCK_HASH, tx.serialize_to_vec()).unwrap();
+        }
+
+        let mut synced_txids = HashSet::new();
+        let mut round = 0;
+        while synced_txids.len() < expected_txids.len() {
+            if let Ok(mut result) = peer_1.step() {
+                for tx in result.synced_transactions.drain(..) {
+                    // only ask for each transaction once per pass
+                    assert!(!synced_txids.contains(&tx.txid()));
+                    synced_txids.insert(tx.txid());
+                }
+            }
+            let _ = peer_2.step();
+
+            // nothing should break
+            if let Some(ref sync_state) = peer_1.network.mempool_sync {
+                assert_eq!(sync_state.broken_peers.len(), 0);
+                assert_eq!(sync_state.dead_peers.len(), 0);
+            }
+
+            round += 1;
+            info!("Mempool sync round {}: {} of {} transactions", round, synced_txids.len(), expected_txids.len());
+        }
+
+        assert_eq!(synced_txids, expected_txids);
+    }
+}
@@ -20,12 +20,14 @@
 pub mod asn;
 pub mod chat;
 pub mod codec;
+pub mod compact;
 pub mod connection;
 pub mod db;
 pub mod dns;
 pub mod download;
 pub mod http;
 pub mod inv;
+pub mod mempool;
 pub mod neighbors;
 pub mod p2p;
 pub mod poll;
@@ -33,6 +35,7 @@ pub mod prune;
 pub mod rpc;
 pub mod relay;
 pub mod server;
+pub mod session;
 
 use std::fmt;
 use std::hash::Hash;
@@ -76,6 +79,7 @@ use chainstate::burn::BlockHeaderHash;
 use chainstate::stacks::{
     StacksAddress,
     StacksBlock,
+    StacksBlockHeader,
     StacksMicroblock,
     StacksTransaction,
     StacksPublicKey,
@@ -603,6 +607,9 @@ pub struct HttpResponsePreamble {
 pub const HTTP_PREAMBLE_MAX_ENCODED_SIZE : u32 = 4096;
 pub const HTTP_PREAMBLE_MAX_NUM_HEADERS : usize = 64;
 
+/// Maximum size of the body of an admin RPC request
+pub const HTTP_ADMIN_REQUEST_MAX_LEN : u32 = 4096;
+
 /// P2P message preamble -- included in all p2p network messages
 #[derive(Debug, Clone, PartialEq)]
 pub struct Preamble {
@@ -613,7 +620,7 @@ pub struct Preamble {
     pub burn_consensus_hash: ConsensusHash,         // consensus hash at block_height
     pub burn_stable_block_height: u64,              // latest stable block height (e.g. chain tip minus 7)
     pub burn_stable_consensus_hash: ConsensusHash,  // consensus hash for burn_stable_block_height
-    pub additional_data: u32,                       // RESERVED; pointer to additional data (should be all 0's if not used)
+    pub additional_data: u32,                       // RESERVED; pointer to additional data (should be all 0's if not used).  In a HandshakeAccept, the responder's encrypted session nonce.
     pub signature: MessageSignature,                // signature from the peer that sent this
     pub payload_len: u32                            // length of the following payload, including relayers vector
 }
@@ -733,6 +740,8 @@ pub struct HandshakeData {
 pub enum ServiceFlags {
     RELAY = 0x01,
     RPC = 0x02,
+    ENCRYPTION = 0x04,
+    COMPACT_BLOCKS = 0x08,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -749,6 +758,7 @@ pub mod NackErrorCodes {
     pub const HandshakeRequired : u32 = 1;
     pub const NoSuchBurnchainBlock : u32 = 2;
     pub const Throttled : u32 = 3;
+    pub const NoSuchBlock : u32 = 4;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -768,6 +778,84 @@ pub struct NatPunchData {
     pub nonce: u32,
 }
 
+/// Request for a page of the txids in a peer's mempool.  Txids are served in ascending byte order,
+/// so the requester can walk the remote mempool by passing back the last txid it was sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetMempoolTxidsData {
+    pub last_txid: Txid,                // only send txids strictly greater than this one (all 0's to start from the beginning)
+    pub max_txids: u16                  // maximum number of txids to send (not to exceed MEMPOOL_TXIDS_PAGE_MAX_LEN)
+}
+
+/// A page of txids from a peer's mempool, in ascending byte order.  Sent in reply to a GetMempoolTxids.
+/// A page with fewer txids than were asked for is the last page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MempoolTxidsData {
+    pub txids: Vec<Txid>
+}
+
+/// Request for specific transactions from a peer's mempool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetMempoolTxsData {
+    pub txids: Vec<Txid>                // not to exceed MEMPOOL_TXS_REQUEST_MAX_LEN
+}
+
+/// Transactions from a peer's mempool.  Sent in reply to a GetMempoolTxs.  Transactions the peer
+/// no longer has are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MempoolTxsData {
+    pub txs: Vec<StacksTransaction>
+}
+
+/// A message payload sealed under the session key of an encrypted session (see net::session).
+/// Only sent to peers that negotiated a session in their handshake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SealedData {
+    pub nonce: u64,                     // sender's message counter; never reused under the same key
+    pub ciphertext: Vec<u8>             // encrypted StacksMessageType, followed by its authentication tag
+}
+
+/// Short transaction ID used to identify a transaction within a compact block.  This is the first
+/// 8 bytes of SHA512/256(block hash || txid) (see net::compact).
+pub struct ShortTxid(pub [u8; 8]);
+impl_array_newtype!(ShortTxid, u8, 8);
+impl_array_hexstring_fmt!(ShortTxid);
+impl_byte_array_newtype!(ShortTxid, u8, 8);
+
+/// A transaction sent in full as part of a compact block, because the receiver probably doesn't
+/// have it in its mempool (e.g. the coinbase).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefilledTransaction {
+    pub index: u16,                     // position of this transaction in the block
+    pub tx: StacksTransaction
+}
+
+/// An anchored block pushed as its header and the short IDs of its transactions.  The receiver
+/// reassembles the block from its mempool, and asks for whichever transactions it's missing with
+/// a GetBlockTxs.  Only sent to peers that advertise ServiceFlags::COMPACT_BLOCKS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactBlockData {
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub header: StacksBlockHeader,
+    pub short_txids: Vec<ShortTxid>,                // short IDs of the transactions that are not prefilled, in block order
+    pub prefilled_txs: Vec<PrefilledTransaction>    // transactions sent in full, in ascending index order
+}
+
+/// Request for the transactions at the given positions in a block we were sent as a compact block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetBlockTxsData {
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub block_hash: BlockHeaderHash,
+    pub indexes: Vec<u16>               // in ascending order (not to exceed COMPACT_BLOCK_MAX_TXS)
+}
+
+/// Transactions from a block.  Sent in reply to a GetBlockTxs, in the order they were requested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockTxsData {
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub block_hash: BlockHeaderHash,
+    pub txs: Vec<StacksTransaction>
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RelayData {
     pub peer: NeighborAddress,
@@ -796,6 +884,14 @@ pub enum StacksMessageType {
     Pong(PongData),
     NatPunchRequest(u32),
     NatPunchReply(NatPunchData),
+    GetMempoolTxids(GetMempoolTxidsData),
+    MempoolTxids(MempoolTxidsData),
+    GetMempoolTxs(GetMempoolTxsData),
+    MempoolTxs(MempoolTxsData),
+    Sealed(SealedData),
+    CompactBlock(CompactBlockData),
+    GetBlockTxs(GetBlockTxsData),
+    BlockTxs(BlockTxsData),
 }
 
 /// Peer address variants
@@ -1012,18 +1108,21 @@ pub struct RPCNeighbor {
     pub addrbytes: PeerAddress,
     pub port: u16,
     pub public_key_hash: Hash160,
-    pub authenticated: bool
+    pub authenticated: bool,
+    #[serde(default)]
+    pub encrypted: bool
 }
 
 impl RPCNeighbor {
-    pub fn from_neighbor_key_and_pubkh(nk: NeighborKey, pkh: Hash160, auth: bool) -> RPCNeighbor {
+    pub fn from_neighbor_key_and_pubkh(nk: NeighborKey, pkh: Hash160, auth: bool, encrypted: bool) -> RPCNeighbor {
         RPCNeighbor {
             network_id: nk.network_id,
             peer_version: nk.peer_version,
             addrbytes: nk.addrbytes,
             port: nk.port,
             public_key_hash: pkh,
-            authenticated: auth
+            authenticated: auth,
+            encrypted: encrypted
         }
     }
 }
@@ -1036,6 +1135,66 @@ pub struct RPCNeighborsInfo {
     pub outbound: Vec<RPCNeighbor>,
 }
 
+/// Items in the admin NeighborsInfo -- a connected neighbor and its connection statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCAdminNeighbor {
+    pub neighbor: RPCNeighbor,
+    pub outbound: bool,
+    pub first_contact_time: u64,
+    pub last_contact_time: u64,
+    pub last_send_time: u64,
+    pub last_recv_time: u64,
+    pub last_handshake_time: u64,
+    pub bytes_tx: u64,
+    pub bytes_rx: u64,
+    pub msgs_tx: u64,
+    pub msgs_rx: u64,
+    pub msgs_rx_unsolicited: u64,
+    pub msgs_err: u64,
+    pub health_score: f64,
+}
+
+/// Struct given back from a call to `/admin/neighbors`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCAdminNeighborsInfo {
+    pub neighbors: Vec<RPCAdminNeighbor>,
+}
+
+/// Body of a POST to one of the admin peer-management endpoints.  Which fields are required
+/// depends on the endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct RPCAdminRequestBody {
+    /// peer address as "ip:port"
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addr: Option<String>,
+    /// address range as "ip/mask"
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cidr: Option<String>,
+    /// hex-encoded public key of a bootstrap peer
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// how long to ban a peer, in seconds.  Bans last until lifted if not given.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+}
+
+/// Peer management actions requested through the admin RPC endpoints.  These are carried out by
+/// the PeerNetwork, since it owns the peer DB and the peer connections.
+/// CIDR masks are over the 128-bit peer address (so an IPv4 /24 has a mask of 120).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PeerAdminCommand {
+    BanPeer(PeerAddress, u16, Option<u64>),     // address, port, ban duration
+    UnbanPeer(PeerAddress, u16),
+    BanCIDR(PeerAddress, u32),                  // prefix, mask
+    UnbanCIDR(PeerAddress, u32),
+    Disconnect(PeerAddress, u16),
+    AddBootstrapPeer(PeerAddress, u16, Secp256k1PublicKey),
+}
+
 /// All HTTP request paths we support, and the arguments they carry in their paths
 #[derive(Debug, Clone, PartialEq)]
 pub enum HttpRequestType {
@@ -1055,6 +1214,9 @@ pub enum HttpRequestType {
     GetContractSrc(HttpRequestMetadata, StacksAddress, ContractName, Option<StacksBlockId>, bool),
     GetContractABI(HttpRequestMetadata, StacksAddress, ContractName, Option<StacksBlockId>),
     OptionsPreflight(HttpRequestMetadata, String),
+    /// admin requests carry the bearer token they were sent with, if any
+    AdminGetNeighbors(HttpRequestMetadata, Option<String>),
+    AdminCommand(HttpRequestMetadata, Option<String>, PeerAdminCommand),
     /// catch-all for any errors we should surface from parsing
     ClientError(HttpRequestMetadata, ClientError),
 }
@@ -1131,6 +1293,8 @@ pub enum HttpResponseType {
     GetContractABI(HttpResponseMetadata, ContractInterface),
     GetContractSrc(HttpResponseMetadata, ContractSrcResponse),
     OptionsPreflight(HttpResponseMetadata),
+    AdminNeighbors(HttpResponseMetadata, RPCAdminNeighborsInfo),
+    AdminCommandAccepted(HttpResponseMetadata),
     // peer-given error responses
     BadRequest(HttpResponseMetadata, String),
     BadRequestJSON(HttpResponseMetadata, serde_json::Value),
@@ -1169,6 +1333,14 @@ pub enum StacksMessageID {
     Pong = 14,
     NatPunchRequest = 15,
     NatPunchReply = 16,
+    GetMempoolTxids = 17,
+    MempoolTxids = 18,
+    GetMempoolTxs = 19,
+    MempoolTxs = 20,
+    Sealed = 21,
+    CompactBlock = 22,
+    GetBlockTxs = 23,
+    BlockTxs = 24,
     Reserved = 255
 }
 
@@ -1272,6 +1444,17 @@ pub const BLOCKS_AVAILABLE_MAX_LEN : u32 = 32;
 // message.
 pub const BLOCKS_PUSHED_MAX : u32 = 32;
 
+// maximum number of txids in a page of a peer's mempool
+#[cfg(test)] pub const MEMPOOL_TXIDS_PAGE_MAX_LEN : u32 = 8;
+#[cfg(not(test))] pub const MEMPOOL_TXIDS_PAGE_MAX_LEN : u32 = 1024;
+
+// maximum number of transactions that can be requested from a peer's mempool at once
+#[cfg(test)] pub const MEMPOOL_TXS_REQUEST_MAX_LEN : u32 = 4;
+#[cfg(not(test))] pub const MEMPOOL_TXS_REQUEST_MAX_LEN : u32 = 64;
+
+// maximum number of transactions in a compact block (transactions are indexed by u16s)
+pub const COMPACT_BLOCK_MAX_TXS : u32 = 65536;
+
 macro_rules! impl_byte_array_message_codec {
     ($thing:ident, $len:expr) => {
         impl ::net::StacksMessageCodec for $thing {
@@ -1295,6 +1478,8 @@ impl_byte_array_message_codec!(StacksBlockId, 32);
 impl_byte_array_message_codec!(MessageSignature, 65);
 impl_byte_array_message_codec!(PeerAddress, 16);
 impl_byte_array_message_codec!(StacksPublicKeyBuffer, 33);
+impl_byte_array_message_codec!(Txid, 32);
+impl_byte_array_message_codec!(ShortTxid, 8);
 
 impl_byte_array_serde!(ConsensusHash);
 
@@ -1410,6 +1595,7 @@ pub struct NetworkResult {
     pub pushed_microblocks: HashMap<NeighborKey, Vec<(Vec<RelayData>, MicroblocksData)>>,                      // all microblocks pushed to us, and the relay hints from the message
     pub uploaded_transactions: Vec<StacksTransaction>,                                                         // transactions sent to us by the http server
     pub uploaded_microblocks: Vec<MicroblocksData>,                                                            // microblocks sent to us by the http server
+    pub synced_transactions: Vec<StacksTransaction>,                                                           // transactions we fetched from our neighbors' mempools
 }
 
 impl NetworkResult {
@@ -1423,6 +1609,7 @@ impl NetworkResult {
             pushed_microblocks: HashMap::new(),
             uploaded_transactions: vec![],
             uploaded_microblocks: vec![],
+            synced_transactions: vec![],
         }
     }
 
@@ -1438,14 +1625,20 @@ impl NetworkResult {
         self.pushed_transactions.len() > 0 || self.uploaded_transactions.len() > 0
     }
 
+    /// Transactions pushed or uploaded to us.  Does not include `synced_transactions`, most of
+    /// which we usually have already; the relayer reports the ones that were new to us.
     pub fn transactions(&self) -> Vec<StacksTransaction> {
         self.pushed_transactions.values()
             .flat_map(|pushed_txs| pushed_txs.iter().map(|(_, tx)| tx.clone()))
             .chain(self.uploaded_transactions.iter().map(|x| x.clone())).collect()
     }
 
+    pub fn has_synced_transactions(&self) -> bool {
+        self.synced_transactions.len() > 0
+    }
+
     pub fn has_data_to_store(&self) -> bool {
-        self.has_blocks() || self.has_microblocks() || self.has_transactions()
+        self.has_blocks() || self.has_microblocks() || self.has_transactions() || self.has_synced_transactions()
     }
 
     pub fn consume_unsolicited(&mut self, mut unhandled_messages: HashMap<NeighborKey, Vec<StacksMessage>>) -> () {
@@ -1748,6 +1941,7 @@ pub mod test {
         pub private_key_expire: u64,
         pub initial_neighbors: Vec<Neighbor>,
         pub asn4_entries: Vec<ASEntry4>,
+        pub asn6_entries: Vec<ASEntry6>,
         pub burnchain: Burnchain,
         pub connection_opts: ConnectionOptions,
         pub server_port: u16,
@@ -1777,6 +1971,7 @@ pub mod test {
                 private_key_expire: start_block + conn_opts.private_key_lifetime,
                 initial_neighbors: vec![],
                 asn4_entries: vec![],
+                asn6_entries: vec![],
                 burnchain: burnchain,
                 connection_opts: conn_opts,
                 server_port: 32000,
@@ -1889,7 +2084,7 @@ pub mod test {
 
             let mut peerdb = PeerDB::connect(&peerdb_path, true, config.network_id, config.burnchain.network_id, None, config.private_key_expire, 
                                              PeerAddress::from_ipv4(127,0,0,1), NETWORK_P2P_PORT, config.data_url.clone(), 
-                                             &config.asn4_entries, Some(&config.initial_neighbors)).unwrap();
+                                             &config.asn4_entries, &config.asn6_entries, Some(&config.initial_neighbors)).unwrap();
 
             let mut sortdb = SortitionDB::connect(&sortdb_path, config.burnchain.first_block_height, &config.burnchain.first_block_hash, get_epoch_time_secs(), true).unwrap();
             let chainstate = StacksChainState::open_and_exec(false, config.network_id, &chainstate_path, Some(config.initial_balances.clone()), |_| {}, ExecutionCost::max_value()).unwrap();
@@ -1928,7 +2123,7 @@ pub mod test {
             let local_peer = PeerDB::get_local_peer(peerdb.conn()).unwrap();
             let burnchain_view = {
                 let ic = sortdb.index_conn();
-                let chaintip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic).unwrap();
+                let chaintip = SortitionDB::get_canonical_burn_chain_tip(&ic).unwrap();
                 ic.get_burnchain_view(&config.burnchain, &chaintip).unwrap()
             };
             let mut peer_network = PeerNetwork::new(peerdb, local_peer, config.peer_version, config.burnchain.clone(), burnchain_view, config.connection_opts.clone());
@@ -1954,7 +2149,7 @@ pub mod test {
             let chain_view = match self.sortdb {
                 Some(ref mut sortdb) => {
                     let ic = sortdb.index_conn();
-                    let chaintip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic).unwrap();
+                    let chaintip = SortitionDB::get_canonical_burn_chain_tip(&ic).unwrap();
                     ic.get_burnchain_view(&self.config.burnchain, &chaintip).unwrap()
                 }
                 None => panic!("Misconfigured peer: no sortdb")
@@ -2035,7 +2230,7 @@ pub mod test {
         fn make_empty_burnchain_block(&mut self) -> BurnchainBlock {
             let empty_block = {
                 let sortdb = self.sortdb.take().unwrap();
-                let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(sortdb.conn()).unwrap();
+                let sn = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
                 let empty_block = self.empty_burnchain_block(sn.block_height);
                 self.sortdb = Some(sortdb);
                 empty_block
@@ -2046,7 +2241,7 @@ pub mod test {
         pub fn next_burnchain_block(&mut self, mut blockstack_ops: Vec<BlockstackOperationType>) -> (u64, BurnchainHeaderHash) {
             let mut sortdb = self.sortdb.take().unwrap();
             let (block_height, block_hash) = {
-                let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&sortdb.conn()).unwrap();
+                let tip = SortitionDB::get_canonical_burn_chain_tip(&sortdb.conn()).unwrap();
                 let block_header_hash = BurnchainHeaderHash::from_test_data(tip.block_height + 1, &TrieHash([0u8; 32]), 12345);
                 let block_header = BurnchainBlockHeader::from_parent_snapshot(&tip, block_header_hash.clone(), blockstack_ops.len() as u64);
                 let mut tx = SortitionHandleTx::begin(&mut sortdb, &tip.sortition_id).unwrap();
@@ -2079,7 +2274,7 @@ pub mod test {
             let mut node = self.stacks_node.take().unwrap();
             let res = {
                 let ic = sortdb.index_conn();
-                let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic).unwrap();
+                let tip = SortitionDB::get_canonical_burn_chain_tip(&ic).unwrap();
                 let sn_opt = SortitionDB::get_block_snapshot_for_winning_stacks_block(&ic, &tip.sortition_id, &block.block_hash()).unwrap();
                 if sn_opt.is_none() {
                     return Err(format!("No such block in canonical burn fork: {}", &block.block_hash()));
@@ -2099,7 +2294,7 @@ pub mod test {
             let mut node = self.stacks_node.take().unwrap();
             let res = {
                 let ic = sortdb.index_conn();
-                let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic).unwrap();
+                let tip = SortitionDB::get_canonical_burn_chain_tip(&ic).unwrap();
                 let anchor_block_hash = microblocks[0].header.prev_block.clone();
                 let sn_opt = SortitionDB::get_block_snapshot_for_winning_stacks_block(&ic, &tip.sortition_id, &anchor_block_hash).unwrap();
                 if sn_opt.is_none() {
@@ -2128,7 +2323,7 @@ pub mod test {
             let mut node = self.stacks_node.take().unwrap();
             {
                 let ic = sortdb.index_conn();
-                let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic).unwrap();
+                let tip = SortitionDB::get_canonical_burn_chain_tip(&ic).unwrap();
                 node.chainstate.preprocess_stacks_epoch(&ic, &tip, block, microblocks).unwrap();
             }
     
@@ -2230,11 +2425,11 @@ pub mod test {
         {
             let mut sortdb = self.sortdb.take().unwrap();
             let mut burn_block = {
-                let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(sortdb.conn()).unwrap();
+                let sn = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
                 TestBurnchainBlock::new(&sn, 0)
             };
 
-            let last_sortition_block = SortitionDB::get_canonical_burn_chain_tip_stubbed(sortdb.conn()).unwrap();        // no forks here
+            let last_sortition_block = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();        // no forks here
           
             let mut stacks_node = self.stacks_node.take().unwrap();
 
@@ -2263,7 +2458,7 @@ pub mod test {
         pub fn make_default_tenure(&mut self) -> (Vec<BlockstackOperationType>, StacksBlock, Vec<StacksMicroblock>) {
             let mut sortdb = self.sortdb.take().unwrap();
             let mut burn_block = {
-                let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(sortdb.conn()).unwrap();
+                let sn = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
                 TestBurnchainBlock::new(&sn, 0)
             };
           
@@ -2315,7 +2510,7 @@ pub mod test {
             let sortdb = self.sortdb.take().unwrap();
             let view_res = {
                 let ic = sortdb.index_conn();
-                let chaintip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic).unwrap();
+                let chaintip = SortitionDB::get_canonical_burn_chain_tip(&ic).unwrap();
                 ic.get_burnchain_view(&self.config.burnchain, &chaintip)
             };
             self.sortdb = Some(sortdb);
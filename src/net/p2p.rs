@@ -39,6 +39,10 @@ use net::relay::RelayerStats;
 
 use net::download::BlockDownloader;
 
+use net::mempool::MempoolSyncState;
+
+use net::compact::CompactBlockState;
+
 use net::poll::NetworkState;
 use net::poll::NetworkPollState;
 
@@ -261,6 +265,7 @@ pub enum PeerNetworkWorkState {
     ConfirmPublicIP,
     BlockInvSync,
     BlockDownload,
+    MempoolSync,
     Prune
 }
 
@@ -318,6 +323,12 @@ pub struct PeerNetwork {
     // peer block download state
     pub block_downloader: Option<BlockDownloader>,
 
+    // peer mempool sync state
+    pub mempool_sync: Option<MempoolSyncState>,
+
+    // compact block reassembly state
+    pub compact_blocks: Option<CompactBlockState>,
+
     // do we need to do a prune at the end of the work state cycle?
     pub do_prune: bool,
 
@@ -352,6 +363,14 @@ impl PeerNetwork {
         let pub_ip = connection_opts.public_ip_address.clone();
         let pub_ip_learned = pub_ip.is_none();
         local_peer.public_ip_address = pub_ip.clone();
+        if connection_opts.encrypt_p2p {
+            // advertise that we'll set up encrypted sessions
+            local_peer.services |= ServiceFlags::ENCRYPTION as u16;
+        }
+        if connection_opts.compact_blocks {
+            // advertise that we can reassemble compact blocks
+            local_peer.services |= ServiceFlags::COMPACT_BLOCKS as u16;
+        }
         PeerNetwork {
             local_peer: local_peer,
             peer_version: peer_version,
@@ -389,6 +408,8 @@ impl PeerNetwork {
             
             inv_state: None,
             block_downloader: None,
+            mempool_sync: None,
+            compact_blocks: None,
 
             do_prune: false,
 
@@ -582,22 +603,42 @@ impl PeerNetwork {
         Ok(())
     }
 
-    /// Broadcast a message to a list of neighbors
+    /// Broadcast a message to a list of neighbors.
+    /// Blocks are sent as compact blocks to neighbors that can reassemble them.
     pub fn broadcast_message(&mut self, mut neighbor_keys: Vec<NeighborKey>, relay_hints: Vec<RelayData>, message_payload: StacksMessageType) -> () {
         debug!("{:?}: Will broadcast '{}' to up to {} neighbors", &self.local_peer, message_payload.get_message_name(), neighbor_keys.len());
+        let compact_payloads =
+            if self.connection_opts.compact_blocks {
+                CompactBlockData::from_blocks_payload(&message_payload)
+            }
+            else {
+                None
+            };
+
         for nk in neighbor_keys.drain(..) {
             if let Some(event_id) = self.events.get(&nk) {
                 let event_id = *event_id;
                 if let Some(convo) = self.peers.get_mut(&event_id) {
-                    match convo.sign_and_forward(&self.local_peer, &self.chain_view, relay_hints.clone(), message_payload.clone()) {
-                        Ok(rh) => {
-                            debug!("{:?}: Broadcasted '{}' to {:?}", &self.local_peer, message_payload.get_message_name(), &nk);
-                            self.add_relay_handle(event_id, rh);
-                        },
-                        Err(e) => {
-                            warn!("{:?}: Failed to broadcast message to {:?}: {:?}", &self.local_peer, nk, &e);
+                    let payloads = match compact_payloads {
+                        Some(ref compact_payloads) if convo.supports_compact_blocks(&self.local_peer) => compact_payloads.clone(),
+                        _ => vec![message_payload.clone()]
+                    };
+                    let mut relay_handles = vec![];
+                    for payload in payloads.into_iter() {
+                        let payload_name = payload.get_message_name();
+                        match convo.sign_and_forward(&self.local_peer, &self.chain_view, relay_hints.clone(), payload) {
+                            Ok(rh) => {
+                                debug!("{:?}: Broadcasted '{}' to {:?}", &self.local_peer, payload_name, &nk);
+                                relay_handles.push(rh);
+                            },
+                            Err(e) => {
+                                warn!("{:?}: Failed to broadcast message to {:?}: {:?}", &self.local_peer, nk, &e);
+                            }
                         }
                     }
+                    for rh in relay_handles.into_iter() {
+                        self.add_relay_handle(event_id, rh);
+                    }
                 }
             }
         }
@@ -945,6 +986,100 @@ impl PeerNetwork {
         Ok(disconnect)
     }
 
+    /// Find the event IDs of all conversations with the given peer address and port
+    fn find_peer_events(&self, addrbytes: &PeerAddress, port: u16) -> Vec<usize> {
+        let mut ret = vec![];
+        for (event_id, convo) in self.peers.iter() {
+            let nk = convo.to_neighbor_key();
+            if nk.addrbytes == *addrbytes && nk.port == port {
+                ret.push(*event_id);
+            }
+        }
+        ret
+    }
+
+    /// Carry out peer management commands from the admin RPC endpoints.
+    /// Bans and CIDR changes are written to the peer DB, and any conversations they cover are dropped.
+    fn process_admin_commands(&mut self, commands: Vec<PeerAdminCommand>) -> Result<(), net_error> {
+        for command in commands.into_iter() {
+            debug!("{:?}: Admin command {:?}", &self.local_peer, &command);
+            let mut disconnect = vec![];
+            match command {
+                PeerAdminCommand::BanPeer(addrbytes, port, duration_opt) => {
+                    let deny_deadline = match duration_opt {
+                        Some(duration) => {
+                            let deadline = get_epoch_time_secs().saturating_add(duration);
+                            if deadline > (i64::max_value() as u64) { i64::max_value() as u64 } else { deadline }
+                        },
+                        None => i64::max_value() as u64
+                    };
+
+                    let mut tx = self.peerdb.tx_begin()?;
+                    PeerDB::set_deny_peer(&mut tx, self.local_peer.network_id, &addrbytes, port, deny_deadline)?;
+                    tx.commit()?;
+
+                    disconnect = self.find_peer_events(&addrbytes, port);
+                },
+                PeerAdminCommand::UnbanPeer(addrbytes, port) => {
+                    let mut tx = self.peerdb.tx_begin()?;
+                    PeerDB::set_deny_peer(&mut tx, self.local_peer.network_id, &addrbytes, port, 0)?;
+                    tx.commit()?;
+                },
+                PeerAdminCommand::BanCIDR(prefix, mask) => {
+                    let mut tx = self.peerdb.tx_begin()?;
+                    PeerDB::add_deny_cidr(&mut tx, &prefix, mask)?;
+                    tx.commit()?;
+
+                    for (event_id, convo) in self.peers.iter() {
+                        if PeerDB::is_address_denied(self.peerdb.conn(), &convo.to_neighbor_key().addrbytes)? {
+                            disconnect.push(*event_id);
+                        }
+                    }
+                },
+                PeerAdminCommand::UnbanCIDR(prefix, mask) => {
+                    let mut tx = self.peerdb.tx_begin()?;
+                    PeerDB::remove_deny_cidr(&mut tx, &prefix, mask)?;
+                    tx.commit()?;
+                },
+                PeerAdminCommand::Disconnect(addrbytes, port) => {
+                    disconnect = self.find_peer_events(&addrbytes, port);
+                },
+                PeerAdminCommand::AddBootstrapPeer(addrbytes, port, public_key) => {
+                    let neighbor_key = NeighborKey {
+                        peer_version: self.peer_version,
+                        network_id: self.local_peer.network_id,
+                        addrbytes: addrbytes,
+                        port: port
+                    };
+
+                    // the real key expiry is learned on handshake
+                    let neighbor = Neighbor::empty(&neighbor_key, &public_key, i64::max_value() as u64);
+
+                    let mut tx = self.peerdb.tx_begin()?;
+                    if !PeerDB::try_insert_peer(&mut tx, &neighbor)? {
+                        debug!("{:?}: Bootstrap peer {:?} is already known, or has no free slot", &self.local_peer, &neighbor_key);
+                    }
+                    tx.commit()?;
+
+                    match self.connect_peer(&neighbor_key) {
+                        Ok(event_id) => {
+                            debug!("{:?}: Connecting to bootstrap peer {:?} as event {}", &self.local_peer, &neighbor_key, event_id);
+                        },
+                        Err(e) => {
+                            warn!("{:?}: Failed to connect to bootstrap peer {:?}: {:?}", &self.local_peer, &neighbor_key, &e);
+                        }
+                    }
+                }
+            }
+
+            for event_id in disconnect.into_iter() {
+                debug!("{:?}: Admin disconnect event {}", &self.local_peer, event_id);
+                self.deregister_peer(event_id);
+            }
+        }
+        Ok(())
+    }
+
     /// Get the neighbor if we know of it and it's public key is unexpired.
     fn lookup_peer(&self, cur_block_height: u64, peer_addr: &SocketAddr) -> Result<Option<Neighbor>, net_error> {
         let conn = self.peerdb.conn();
@@ -1256,7 +1391,7 @@ impl PeerNetwork {
 
     /// Process network traffic on a p2p conversation.
     /// Returns list of unhandled messages, and whether or not the convo is still alive.
-    fn process_p2p_conversation(local_peer: &LocalPeer, peerdb: &mut PeerDB, sortdb: &SortitionDB, chainstate: &mut StacksChainState, chain_view: &BurnchainView, 
+    fn process_p2p_conversation(local_peer: &LocalPeer, peerdb: &mut PeerDB, sortdb: &SortitionDB, chainstate: &mut StacksChainState, mempool: &MemPoolDB, chain_view: &BurnchainView, 
                                 event_id: usize, client_sock: &mut mio_net::TcpStream, convo: &mut ConversationP2P) -> Result<(Vec<StacksMessage>, bool), net_error> {
         // get incoming bytes and update the state of this conversation.
         let mut convo_dead = false;
@@ -1280,7 +1415,7 @@ impl PeerNetwork {
         // react to inbound messages -- do we need to send something out, or fulfill requests
         // to other threads?  Try to chat even if the recv() failed, since we'll want to at
         // least drain the conversation inbox.
-        let chat_res = convo.chat(local_peer, peerdb, sortdb, chainstate, chain_view);
+        let chat_res = convo.chat(local_peer, peerdb, sortdb, chainstate, mempool, chain_view);
         let unhandled = match chat_res {
             Err(e) => {
                 debug!("Failed to converse on event {} (socket {:?}): {:?}", event_id, &client_sock, &e);
@@ -1325,7 +1460,7 @@ impl PeerNetwork {
     /// Advance the state of all such conversations with remote peers.
     /// Return the list of events that correspond to failed conversations, as well as the set of
     /// unhandled messages grouped by event_id.
-    fn process_ready_sockets(&mut self, sortdb: &SortitionDB, chainstate: &mut StacksChainState, poll_state: &mut NetworkPollState) -> (Vec<usize>, HashMap<usize, Vec<StacksMessage>>) {
+    fn process_ready_sockets(&mut self, sortdb: &SortitionDB, chainstate: &mut StacksChainState, mempool: &MemPoolDB, poll_state: &mut NetworkPollState) -> (Vec<usize>, HashMap<usize, Vec<StacksMessage>>) {
         let mut to_remove = vec![];
         let mut unhandled : HashMap<usize, Vec<StacksMessage>> = HashMap::new();
 
@@ -1348,7 +1483,7 @@ impl PeerNetwork {
                 Some(ref mut convo) => {
                     // activity on a p2p socket
                     debug!("{:?}: process p2p data from {:?}", &self.local_peer, convo);
-                    let mut convo_unhandled = match PeerNetwork::process_p2p_conversation(&self.local_peer, &mut self.peerdb, sortdb, chainstate, &self.chain_view, *event_id, client_sock, convo) {
+                    let mut convo_unhandled = match PeerNetwork::process_p2p_conversation(&self.local_peer, &mut self.peerdb, sortdb, chainstate, mempool, &self.chain_view, *event_id, client_sock, convo) {
                         Ok((convo_unhandled, alive)) => {
                             if !alive {
                                 to_remove.push(*event_id);
@@ -2153,11 +2288,58 @@ impl PeerNetwork {
         Ok(done)
     }
 
+    /// Fetch transactions from our neighbors' mempools, and add them to our network result.
+    fn do_network_mempool_sync(&mut self, mempool: &MemPoolDB, network_result: &mut NetworkResult) -> Result<bool, net_error> {
+        if cfg!(test) && self.connection_opts.disable_mempool_sync {
+            test_debug!("{:?}: mempool sync is disabled", &self.local_peer);
+            return Ok(true);
+        }
+
+        let (done, mut txs, dead_neighbors, broken_neighbors) = self.sync_peer_mempools(mempool)?;
+
+        network_result.synced_transactions.append(&mut txs);
+
+        // disconnect and ban broken peers
+        for broken in broken_neighbors.into_iter() {
+            self.deregister_and_ban_neighbor(&broken);
+        }
+
+        // disconnect from dead connections
+        for dead in dead_neighbors.into_iter() {
+            self.deregister_neighbor(&dead);
+        }
+
+        Ok(done)
+    }
+
+    /// Fetch the transactions we're missing from compact blocks pushed to us, and add the blocks
+    /// we finish reassembling to our network result.
+    fn do_network_compact_blocks(&mut self, network_result: &mut NetworkResult) -> Result<(), net_error> {
+        let (blocks, dead_neighbors, broken_neighbors) = self.sync_compact_blocks()?;
+
+        for (neighbor_key, burn_header_hash, block) in blocks.into_iter() {
+            network_result.pushed_blocks.entry(neighbor_key).or_insert(vec![]).push(BlocksData { blocks: vec![(burn_header_hash, block)] });
+        }
+
+        // disconnect and ban broken peers
+        for broken in broken_neighbors.into_iter() {
+            self.deregister_and_ban_neighbor(&broken);
+        }
+
+        // disconnect from dead connections
+        for dead in dead_neighbors.into_iter() {
+            self.deregister_neighbor(&dead);
+        }
+
+        Ok(())
+    }
+
     /// Do the actual work in the state machine.
     /// Return true if we need to prune connections.
     fn do_network_work(&mut self, 
                        sortdb: &SortitionDB, 
                        chainstate: &mut StacksChainState, 
+                       mempool: &MemPoolDB,
                        mut dns_client_opt: Option<&mut DNSClient>,
                        download_backpressure: bool,
                        network_result: &mut NetworkResult) -> Result<bool, net_error> {
@@ -2217,7 +2399,7 @@ impl PeerNetwork {
                         }
                         else {
                             // skip downloads for now
-                            self.work_state = PeerNetworkWorkState::Prune;
+                            self.work_state = PeerNetworkWorkState::MempoolSync;
                         }
 
                         // pass along hints
@@ -2237,16 +2419,22 @@ impl PeerNetwork {
                         Some(ref mut dns_client) => {
                             if self.do_network_block_download(sortdb, chainstate, *dns_client, network_result)? {
                                 // advance work state
-                                self.work_state = PeerNetworkWorkState::Prune;
+                                self.work_state = PeerNetworkWorkState::MempoolSync;
                             }
                         },
                         None => {
                             // skip this step -- no DNS client available
                             test_debug!("{:?}: no DNS client provided; skipping block download", &self.local_peer);
-                            self.work_state = PeerNetworkWorkState::Prune;
+                            self.work_state = PeerNetworkWorkState::MempoolSync;
                         }
                     }
                 },
+                PeerNetworkWorkState::MempoolSync => {
+                    // fetch transactions our neighbors have that we don't
+                    if self.do_network_mempool_sync(mempool, network_result)? {
+                        self.work_state = PeerNetworkWorkState::Prune;
+                    }
+                },
                 PeerNetworkWorkState::Prune => {
                     // did one pass
                     did_cycle = true;
@@ -2470,8 +2658,7 @@ impl PeerNetwork {
         test_debug!("{:?}: Process BlocksData from {:?} with {} entries", &self.local_peer, outbound_neighbor_key, new_blocks.blocks.len());
 
         for (burn_header_hash, block) in new_blocks.blocks.iter() {
-            let sortid = SortitionId::stubbed(burn_header_hash);
-            let sn = match SortitionDB::get_block_snapshot(&sortdb.conn, &sortid) {
+            let sn = match SortitionDB::get_block_snapshot_by_burn_header_hash(&sortdb.conn, burn_header_hash) {
                 Ok(Some(sn)) => sn,
                 Ok(None) => {
                     // ignore
@@ -2492,9 +2679,58 @@ impl PeerNetwork {
         }
     }
     
+    /// Handle an unsolicited CompactBlock.
+    /// Update our inv for the peer that sent it, and start reassembling it from our mempool
+    /// unless we already have it.
+    /// Mask errors.
+    fn handle_unsolicited_CompactBlock(&mut self, sortdb: &SortitionDB, chainstate: &StacksChainState, mempool: &MemPoolDB, event_id: usize, neighbor_key: &NeighborKey, compact: &CompactBlockData) -> () {
+        let block_hash = compact.header.block_hash();
+        let sn = match SortitionDB::get_block_snapshot_by_burn_header_hash(&sortdb.conn, &compact.burn_header_hash) {
+            Ok(Some(sn)) => sn,
+            Ok(None) => {
+                // ignore
+                return;
+            },
+            Err(e) => {
+                warn!("Failed to query block snapshot for {}: {:?}", &compact.burn_header_hash, &e);
+                return;
+            }
+        };
+
+        if sn.winning_stacks_block_hash != block_hash {
+            info!("Ignoring compact block {} -- winning block was {} (sortition: {})", &block_hash, sn.winning_stacks_block_hash, sn.sortition);
+            return;
+        }
+
+        test_debug!("{:?}: Process CompactBlock {}/{} from {:?}", &self.local_peer, &compact.burn_header_hash, &block_hash, neighbor_key);
+
+        if let Some(outbound_neighbor_key) = self.find_outbound_neighbor(event_id) {
+            self.handle_unsolicited_inv_update(sortdb, event_id, &outbound_neighbor_key, &sn.consensus_hash, &compact.burn_header_hash, false);
+        }
+
+        let index_block_hash = compact.header.index_block_hash(&compact.burn_header_hash);
+        let have_block = StacksChainState::has_staging_block(&chainstate.blocks_db, &compact.burn_header_hash, &block_hash)
+            .and_then(|staging| if staging { Ok(true) } else { StacksChainState::has_block_indexed(&chainstate.blocks_path, &index_block_hash) });
+        match have_block {
+            Ok(true) => {
+                debug!("{:?}: Already have block {}/{}; ignoring compact block from {:?}", &self.local_peer, &compact.burn_header_hash, &block_hash, neighbor_key);
+                return;
+            },
+            Ok(false) => {},
+            Err(e) => {
+                warn!("Failed to check for block {}/{}: {:?}", &compact.burn_header_hash, &block_hash, &e);
+                return;
+            }
+        }
+
+        if let Err(e) = self.add_compact_block(mempool, neighbor_key, compact) {
+            warn!("{:?}: Failed to reassemble compact block {}/{}: {:?}", &self.local_peer, &compact.burn_header_hash, &block_hash, &e);
+        }
+    }
+    
     /// Handle unsolicited messages propagated up to us from our ongoing ConversationP2Ps.
     /// Return messages that we couldn't handle here, but key them by neighbor, not event.
-    fn handle_unsolicited_messages(&mut self, sortdb: &SortitionDB, mut unsolicited: HashMap<usize, Vec<StacksMessage>>) -> Result<HashMap<NeighborKey, Vec<StacksMessage>>, net_error> {
+    fn handle_unsolicited_messages(&mut self, sortdb: &SortitionDB, chainstate: &StacksChainState, mempool: &MemPoolDB, mut unsolicited: HashMap<usize, Vec<StacksMessage>>) -> Result<HashMap<NeighborKey, Vec<StacksMessage>>, net_error> {
         let mut unhandled : HashMap<NeighborKey, Vec<StacksMessage>> = HashMap::new();
         for (event_id, messages) in unsolicited.drain() {
             let neighbor_key = match self.peers.get(&event_id) {
@@ -2530,6 +2766,11 @@ impl PeerNetwork {
                             unhandled.insert(neighbor_key.clone(), vec![message]);
                         }
                     },
+                    StacksMessageType::CompactBlock(ref compact) => {
+                        // update inv state for this peer, and reassemble the block.  The relayer
+                        // gets it once we have all of its transactions.
+                        self.handle_unsolicited_CompactBlock(sortdb, chainstate, mempool, event_id, &neighbor_key, compact);
+                    },
                     _ => {
                         if let Some(msgs) = unhandled.get_mut(&neighbor_key) {
                             msgs.push(message);
@@ -2637,10 +2878,17 @@ impl PeerNetwork {
         }
     }
 
-    /// Get the local peer from the peer DB, but also preserve the public IP address
+    /// Get the local peer from the peer DB, but also preserve the public IP address and the
+    /// services we enable at runtime
     pub fn load_local_peer(&self) -> Result<LocalPeer, net_error> {
         let mut lp = PeerDB::get_local_peer(&self.peerdb.conn())?;
         lp.public_ip_address = self.local_peer.public_ip_address.clone();
+        if self.connection_opts.encrypt_p2p {
+            lp.services |= ServiceFlags::ENCRYPTION as u16;
+        }
+        if self.connection_opts.compact_blocks {
+            lp.services |= ServiceFlags::COMPACT_BLOCKS as u16;
+        }
         Ok(lp)
     }
    
@@ -2653,6 +2901,7 @@ impl PeerNetwork {
                         network_result: &mut NetworkResult,
                         sortdb: &SortitionDB, 
                         chainstate: &mut StacksChainState, 
+                        mempool: &MemPoolDB,
                         dns_client_opt: Option<&mut DNSClient>,
                         download_backpressure: bool,
                         mut poll_state: NetworkPollState) -> Result<(), net_error> {
@@ -2663,7 +2912,7 @@ impl PeerNetwork {
         }
 
         // update burnchain snapshot if we need to (careful -- it's expensive)
-        let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&sortdb.conn)?;
+        let sn = SortitionDB::get_canonical_burn_chain_tip(&sortdb.conn)?;
         if sn.block_height > self.chain_view.burn_block_height {
             debug!("{:?}: load chain view for burn block {}", &self.local_peer, sn.block_height);
             let new_chain_view = {
@@ -2690,21 +2939,24 @@ impl PeerNetwork {
         let unauthenticated_inbounds = self.find_unauthenticated_inbound_convos();
 
         // run existing conversations, clear out broken ones, and get back messages forwarded to us
-        let (error_events, unsolicited_messages) = self.process_ready_sockets(sortdb, chainstate, &mut poll_state);
+        let (error_events, unsolicited_messages) = self.process_ready_sockets(sortdb, chainstate, mempool, &mut poll_state);
         for error_event in error_events {
             debug!("{:?}: Failed connection on event {}", &self.local_peer, error_event);
             self.deregister_peer(error_event);
         }
-        let unhandled_messages = self.handle_unsolicited_messages(sortdb, unsolicited_messages)?;
+        let unhandled_messages = self.handle_unsolicited_messages(sortdb, chainstate, mempool, unsolicited_messages)?;
         network_result.consume_unsolicited(unhandled_messages);
 
+        // finish reassembling compact blocks
+        self.do_network_compact_blocks(network_result)?;
+
         // schedule now-authenticated inbound convos for pingback
         self.schedule_network_pingbacks(unauthenticated_inbounds)?;
 
         // do some Actual Work(tm)
         // do this _after_ processing new sockets, so the act of opening a socket doesn't trample
         // an already-used network ID.
-        let do_prune = self.do_network_work(sortdb, chainstate, dns_client_opt, download_backpressure, network_result)?;
+        let do_prune = self.do_network_work(sortdb, chainstate, mempool, dns_client_opt, download_backpressure, network_result)?;
         if do_prune {
             // prune back our connections if it's been a while
             // (only do this if we're done with all other tasks).
@@ -2793,8 +3045,13 @@ impl PeerNetwork {
             result.consume_http_uploads(http_stacks_msgs);
             Ok(())
         })?;
-        
-        self.dispatch_network(&mut result, sortdb, chainstate, dns_client_opt, download_backpressure, p2p_poll_state)?;
+
+        let admin_commands = self.http.take_admin_commands();
+        if let Err(e) = self.process_admin_commands(admin_commands) {
+            warn!("{:?}: Failed to process admin commands: {:?}", &self.local_peer, &e);
+        }
+
+        self.dispatch_network(&mut result, sortdb, chainstate, mempool, dns_client_opt, download_backpressure, p2p_poll_state)?;
 
         debug!("<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<< End Network Dispatch <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
         Ok(result)
@@ -2808,6 +3065,7 @@ mod test {
     use net::*;
     use net::db::*;
     use net::codec::*;
+    use net::test::*;
     use std::thread;
     use std::time;
     use util::log;
@@ -2863,6 +3121,7 @@ mod test {
             stable_confirmations: 7,
             first_block_height: 50,
             first_block_hash: first_burn_hash.clone(),
+            pox_constants: PoxConstants::mainnet_default(),
         };
 
         let mut burnchain_view = BurnchainView {
@@ -2874,7 +3133,7 @@ mod test {
         };
         burnchain_view.make_test_data();
 
-        let db = PeerDB::connect_memory(0x9abcdef0, 0, 23456, "http://test-p2p.com".into(), &vec![], initial_neighbors).unwrap();
+        let db = PeerDB::connect_memory(0x9abcdef0, 0, 23456, "http://test-p2p.com".into(), &vec![], &vec![], initial_neighbors).unwrap();
         let local_peer = PeerDB::get_local_peer(db.conn()).unwrap();
         let p2p = PeerNetwork::new(db, local_peer, 0x12345678, burnchain, burnchain_view, conn_opts);
         p2p
@@ -3081,6 +3340,112 @@ mod test {
         test_debug!("fake endpoint thread joined");
     }
 
+    #[test]
+    #[ignore]
+    fn test_encrypted_sessions() {
+        let mut peer_1_config = TestPeerConfig::from_port(34000);
+        let mut peer_2_config = TestPeerConfig::from_port(34002);
+        let peer_3_config = TestPeerConfig::from_port(34004);
+
+        // peers 1 and 2 encrypt their sessions; peer 3 doesn't know how
+        peer_1_config.connection_opts.encrypt_p2p = true;
+        peer_2_config.connection_opts.encrypt_p2p = true;
+
+        // peer 1 crawls peers 2 and 3
+        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
+        peer_1_config.add_neighbor(&peer_3_config.to_neighbor());
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+        let mut peer_3 = TestPeer::new(peer_3_config);
+
+        let neighbor_2 = peer_2.to_neighbor();
+        let neighbor_3 = peer_3.to_neighbor();
+
+        let mut i = 0;
+        loop {
+            let _ = peer_1.step();
+            let _ = peer_2.step();
+            let _ = peer_3.step();
+
+            let convo_2_ready = match peer_1.network.get_convo(&neighbor_2.addr) {
+                Some(convo) => convo.is_authenticated() && convo.stats.msgs_rx > 1,
+                None => false
+            };
+            let convo_3_ready = match peer_1.network.get_convo(&neighbor_3.addr) {
+                Some(convo) => convo.is_authenticated() && convo.stats.msgs_rx > 1,
+                None => false
+            };
+
+            if convo_2_ready && convo_3_ready {
+                break;
+            }
+
+            i += 1;
+            assert!(i < 1000, "peers never finished talking to each other");
+        }
+
+        debug!("Completed walk round {} step(s)", i);
+
+        // peers 1 and 2 encrypt their conversation, and it still works
+        let convo_2 = peer_1.network.get_convo(&neighbor_2.addr).unwrap();
+        assert!(convo_2.is_encrypted());
+        assert!(convo_2.stats.msgs_err == 0);
+
+        let mut encrypted_2 = false;
+        for (_, convo) in peer_2.network.peers.iter() {
+            if convo.is_authenticated() {
+                assert!(convo.is_encrypted());
+                encrypted_2 = true;
+            }
+        }
+        assert!(encrypted_2);
+
+        // peers 1 and 3 fall back to plaintext
+        let convo_3 = peer_1.network.get_convo(&neighbor_3.addr).unwrap();
+        assert!(!convo_3.is_encrypted());
+        assert!(convo_3.stats.msgs_err == 0);
+
+        for (_, convo) in peer_3.network.peers.iter() {
+            assert!(!convo.is_encrypted());
+        }
+    }
+
+    #[test]
+    fn test_process_admin_commands() {
+        let mut p2p = make_test_p2p_network(&vec![]);
+        let network_id = p2p.local_peer.network_id;
+
+        let addr_1 = PeerAddress::from_ipv4(1, 2, 3, 4);
+        let addr_2 = PeerAddress::from_ipv4(1, 2, 4, 5);
+        let addr_3 = PeerAddress::from_ipv4(5, 6, 7, 8);
+        let pubkey = Secp256k1PublicKey::from_hex("02fa66b66f8971a8cd4d20ffded09674e030f0f33883f337f34b95ad4935bac0e3").unwrap();
+
+        // ban and unban a single peer
+        p2p.process_admin_commands(vec![PeerAdminCommand::BanPeer(addr_1.clone(), 20444, None)]).unwrap();
+        assert!(PeerDB::is_peer_denied(p2p.peerdb.conn(), network_id, &addr_1, 20444).unwrap());
+        assert!(!PeerDB::is_peer_denied(p2p.peerdb.conn(), network_id, &addr_1, 20445).unwrap());
+
+        p2p.process_admin_commands(vec![PeerAdminCommand::UnbanPeer(addr_1.clone(), 20444)]).unwrap();
+        assert!(!PeerDB::is_peer_denied(p2p.peerdb.conn(), network_id, &addr_1, 20444).unwrap());
+
+        // ban and unban a /16
+        p2p.process_admin_commands(vec![PeerAdminCommand::BanCIDR(PeerAddress::from_ipv4(1, 2, 0, 0), 112)]).unwrap();
+        assert!(PeerDB::is_address_denied(p2p.peerdb.conn(), &addr_1).unwrap());
+        assert!(PeerDB::is_address_denied(p2p.peerdb.conn(), &addr_2).unwrap());
+        assert!(!PeerDB::is_address_denied(p2p.peerdb.conn(), &addr_3).unwrap());
+
+        p2p.process_admin_commands(vec![PeerAdminCommand::UnbanCIDR(PeerAddress::from_ipv4(1, 2, 0, 0), 112)]).unwrap();
+        assert!(!PeerDB::is_address_denied(p2p.peerdb.conn(), &addr_1).unwrap());
+        assert!(!PeerDB::is_address_denied(p2p.peerdb.conn(), &addr_2).unwrap());
+
+        // bootstrap peers are remembered even if we can't reach them yet
+        p2p.process_admin_commands(vec![PeerAdminCommand::AddBootstrapPeer(addr_3.clone(), 20444, pubkey.clone())]).unwrap();
+        let neighbor = PeerDB::get_peer(p2p.peerdb.conn(), network_id, &addr_3, 20444).unwrap().unwrap();
+        assert_eq!(neighbor.public_key, pubkey);
+        assert_eq!(neighbor.denied, 0);
+    }
+
     /*
     #[test]
     fn test_neighbors_connect() {
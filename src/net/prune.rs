@@ -83,7 +83,18 @@ impl PeerNetwork {
                         },
                         Some(peer) => {
                             let stats = convo.stats.clone();
-                            let org = peer.org;
+                            let org =
+                                if peer.org == 0 {
+                                    // not classified when it was stored (e.g. an IPv6 peer from
+                                    // before we had IPv6 prefixes) -- try again now
+                                    match PeerDB::asn_lookup(peer_dbconn, &nk.addrbytes).map_err(net_error::DBError)? {
+                                        Some(asn) => asn,
+                                        None => 0
+                                    }
+                                }
+                                else {
+                                    peer.org
+                                };
                             if org_neighbor.contains_key(&org) {
                                 org_neighbor.get_mut(&org).unwrap().push((nk, stats));
                             }
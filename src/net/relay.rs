@@ -46,6 +46,7 @@ use chainstate::burn::db::sortdb::{
 };
 
 use burnchains::Burnchain;
+use burnchains::Txid;
 use burnchains::BurnchainView;
 
 use util::hash::Sha512Trunc256Sum;
@@ -87,7 +88,8 @@ pub struct RelayerStats {
 
 pub struct ProcessedNetReceipts {
     pub blocks_processed: Vec<StacksEpochReceipt>,
-    pub mempool_txs_added: Vec<StacksTransaction>
+    pub mempool_txs_added: Vec<StacksTransaction>,
+    pub mempool_txs_dropped: Vec<(Txid, MemPoolDropReason)>
 }
 
 /// Private trait for keeping track of messages that can be relayed, so we can identify the peers
@@ -445,11 +447,7 @@ impl Relayer {
             let block_hash = block.block_hash();
 
             // is this the right Stacks block for this sortition?
-            // PoX TODO: this function will need to be able to figure out the
-            //   current PoX fork -- either by getting a reference to the chain controller
-            //   or by querying the PoX database
-            let sortition_id = SortitionId::stubbed(burn_header_hash);
-            let sn = match SortitionDB::get_block_snapshot(conn, &sortition_id)? {
+            let sn = match SortitionDB::get_block_snapshot_by_burn_header_hash(conn, burn_header_hash)? {
                 Some(sn) => sn,
                 None => {
                     // we don't know about this burn block (yet)
@@ -471,15 +469,14 @@ impl Relayer {
 
     /// Insert a staging block
     pub fn process_new_anchored_block(sort_ic: &SortitionDBConn, chainstate: &mut StacksChainState, burn_header_hash: &BurnchainHeaderHash, block: &StacksBlock) -> Result<bool, chainstate_error> {
-        let db_handle = SortitionHandleConn::open_reader_stubbed(sort_ic, burn_header_hash)?;
-
-        let sn = match db_handle.get_block_snapshot(burn_header_hash)? {
+        let sn = match SortitionDB::get_block_snapshot_by_burn_header_hash(sort_ic, burn_header_hash)? {
             Some(sn) => sn,
             None => {
                 debug!("Received unknown block {}/{}", burn_header_hash, block.block_hash());
                 return Ok(false);
             }
         };
+        let db_handle = SortitionHandleConn::open_reader(sort_ic, &sn.sortition_id)?;
 
         // find the snapshot of the parent of this block
         let parent_block_snapshot = match db_handle.get_block_snapshot_of_parent_stacks_block(burn_header_hash, &block.block_hash())? {
@@ -738,7 +735,7 @@ impl Relayer {
 
     /// Set up the unconfirmed chain state off of the canonical chain tip
     pub fn setup_unconfirmed_state(chainstate: &mut StacksChainState, sortdb: &SortitionDB, block_receipts: &Vec<StacksEpochReceipt>) -> Result<(), Error> {
-        let (canonical_burn_hash, canonical_block_hash) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(sortdb.conn())?;
+        let (canonical_burn_hash, canonical_block_hash) = SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())?;
         let canonical_tip = StacksBlockHeader::make_index_block_hash(&canonical_burn_hash, &canonical_block_hash);
         for receipt in block_receipts.iter() {
             if receipt.header.anchored_header.block_hash() == canonical_block_hash && receipt.header.burn_header_hash == canonical_burn_hash {
@@ -816,8 +813,7 @@ impl Relayer {
     pub fn load_blocks_available_data(sortdb: &SortitionDB, burn_header_hashes: Vec<BurnchainHeaderHash>) -> Result<BlocksAvailableMap, net_error> {
         let mut ret = BlocksAvailableMap::new();
         for bhh in burn_header_hashes.into_iter() {
-            let sortid = SortitionId::stubbed(&bhh);
-            let sn = match SortitionDB::get_block_snapshot(&sortdb.conn, &sortid)? {
+            let sn = match SortitionDB::get_block_snapshot_by_burn_header_hash(&sortdb.conn, &bhh)? {
                 Some(sn) => sn,
                 None => {
                     continue;
@@ -887,6 +883,34 @@ impl Relayer {
         Ok(ret)
     }
 
+    /// Store all transactions we fetched from our neighbors' mempools, and return the ones we
+    /// didn't already have.  These aren't forwarded, since our neighbors will sync them too.
+    fn process_synced_transactions(network_result: &mut NetworkResult, sortdb: &SortitionDB, chainstate: &StacksChainState, mempool: &mut MemPoolDB) -> Result<Vec<StacksTransaction>, net_error> {
+        if network_result.synced_transactions.len() == 0 {
+            return Ok(vec![]);
+        }
+
+        let (burn_header_hash, block_hash) = match chainstate.get_stacks_chain_tip(sortdb)? {
+            Some(tip) => (tip.burn_header_hash, tip.anchored_block_hash),
+            None => {
+                debug!("No Stacks chain tip; dropping {} synced transaction(s)", network_result.synced_transactions.len());
+                return Ok(vec![]);
+            }
+        };
+
+        let mut ret = vec![];
+        for tx in network_result.synced_transactions.iter() {
+            if mempool.has_tx(&tx.txid()) {
+                continue;
+            }
+            if Relayer::store_transaction(mempool, &burn_header_hash, &block_hash, tx.clone()) {
+                ret.push(tx.clone());
+            }
+        }
+
+        Ok(ret)
+    }
+
     pub fn advertize_blocks(&mut self, available: BlocksAvailableMap) -> Result<(), net_error> {
         self.p2p.advertize_blocks(available)
     }
@@ -915,6 +939,7 @@ impl Relayer {
     /// * Forward along unconfirmed microblocks that we didn't already have
     /// * Add all transactions to the mempool.
     /// * Forward transactions we didn't already have.
+    /// * Add transactions fetched from our neighbors' mempools to the mempool.
     /// Mask errors from invalid data -- all errors due to invalid blocks and invalid data should be captured, and
     /// turned into peer bans.
     pub fn process_network_result(&mut self, _local_peer: &LocalPeer, network_result: &mut NetworkResult,
@@ -986,9 +1011,19 @@ impl Relayer {
             }
         }
 
+        // store transactions we fetched from our neighbors' mempools
+        test_debug!("{:?}: Process {} synced transaction(s)", &_local_peer, network_result.synced_transactions.len());
+        let mut synced_txs = Relayer::process_synced_transactions(network_result, sortdb, chainstate, mempool)?;
+        mempool_txs_added.append(&mut synced_txs);
+
+        // collect transactions evicted from the mempool since we last looked, including those
+        // dropped by RPC submissions
+        let mempool_txs_dropped = mempool.drain_dropped_txs()?;
+
         let receipts = ProcessedNetReceipts {
             blocks_processed,
-            mempool_txs_added
+            mempool_txs_added,
+            mempool_txs_dropped
         };
 
         Ok(receipts)
@@ -1514,7 +1549,7 @@ mod test {
             out_degree: 0
         };
 
-        let peerdb = PeerDB::connect_memory(0x80000000, 0, 4032, UrlString::try_from("http://foo.com").unwrap(), &vec![asn1, asn2], &vec![n1.clone(), n2.clone(), n3.clone()]).unwrap();
+        let peerdb = PeerDB::connect_memory(0x80000000, 0, 4032, UrlString::try_from("http://foo.com").unwrap(), &vec![asn1, asn2], &vec![], &vec![n1.clone(), n2.clone(), n3.clone()]).unwrap();
 
         let asn_count = RelayerStats::count_ASNs(peerdb.conn(), &vec![nk_1.clone(), nk_2.clone(), nk_3.clone()]).unwrap();
         assert_eq!(asn_count.len(), 3);
@@ -1582,7 +1617,7 @@ mod test {
 
                                                peers[0].process_stacks_epoch_at_tip(&stacks_block, &microblocks);
 
-                                               let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peers[0].sortdb.as_ref().unwrap().conn()).unwrap();
+                                               let sn = SortitionDB::get_canonical_burn_chain_tip(&peers[0].sortdb.as_ref().unwrap().conn()).unwrap();
                                                block_data.push((sn.burn_header_hash.clone(), Some(stacks_block), Some(microblocks)));
                                            }
                                            block_data
@@ -1732,7 +1767,7 @@ mod test {
 
                                                peers[0].process_stacks_epoch_at_tip(&stacks_block, &microblocks);
 
-                                               let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peers[0].sortdb.as_ref().unwrap().conn()).unwrap();
+                                               let sn = SortitionDB::get_canonical_burn_chain_tip(&peers[0].sortdb.as_ref().unwrap().conn()).unwrap();
                                                block_data.push((sn.burn_header_hash.clone(), Some(stacks_block), Some(microblocks)));
                                            }
                                            let saved_copy : Vec<(BurnchainHeaderHash, StacksBlock, Vec<StacksMicroblock>)> = block_data.clone().drain(..).map(|(bhh, blk_opt, mblocks_opt)| (bhh, blk_opt.unwrap(), mblocks_opt.unwrap())).collect();
@@ -1932,7 +1967,7 @@ mod test {
 
                                                peers[0].process_stacks_epoch_at_tip(&stacks_block, &microblocks);
 
-                                               let sn = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peers[0].sortdb.as_ref().unwrap().conn()).unwrap();
+                                               let sn = SortitionDB::get_canonical_burn_chain_tip(&peers[0].sortdb.as_ref().unwrap().conn()).unwrap();
                                                block_data.push((sn.burn_header_hash.clone(), Some(stacks_block), Some(microblocks)));
                                            }
                                            *blocks_and_microblocks.borrow_mut() = block_data.clone().drain(..).map(|(bhh, blk_opt, mblocks_opt)| (bhh, blk_opt.unwrap(), mblocks_opt.unwrap())).collect();
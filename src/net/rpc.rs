@@ -52,6 +52,7 @@ use net::connection::ConnectionOptions;
 use net::db::PeerDB;
 use net::p2p::PeerNetwork;
 use net::{ RPCNeighbor, RPCNeighborsInfo };
+use net::{ RPCAdminNeighbor, RPCAdminNeighborsInfo, PeerAdminCommand };
 use net::{ MapEntryResponse, AccountEntryResponse, CallReadOnlyResponse, ContractSrcResponse };
 use net::p2p::PeerMap;
 use core::mempool::*;
@@ -130,6 +131,9 @@ pub struct ConversationHttp {
     pending_request: Option<ReplyHandleHttp>,
     pending_response: Option<HttpResponseType>,
     pending_error_response: Option<HttpResponseType>,
+
+    // peer management commands from the admin endpoints, to be carried out by the peer network
+    pending_admin_commands: Vec<PeerAdminCommand>,
 }
 
 impl fmt::Display for ConversationHttp {
@@ -146,7 +150,7 @@ impl fmt::Debug for ConversationHttp {
 
 impl RPCPeerInfoData {
     pub fn from_db(burnchain: &Burnchain, sortdb: &SortitionDB, chainstate: &StacksChainState, peerdb: &PeerDB, exit_at_block_height: &Option<&u64>) -> Result<RPCPeerInfoData, net_error> {
-        let burnchain_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&sortdb.conn)?;
+        let burnchain_tip = SortitionDB::get_canonical_burn_chain_tip(&sortdb.conn)?;
         let local_peer = PeerDB::get_local_peer(peerdb.conn())?;
         let stable_burnchain_tip = {
             let ic = sortdb.index_conn();
@@ -198,7 +202,7 @@ impl RPCNeighborsInfo {
 
         let sample : Vec<RPCNeighbor> = neighbor_sample
             .into_iter()
-            .map(|n| RPCNeighbor::from_neighbor_key_and_pubkh(n.addr.clone(), Hash160::from_data(&n.public_key.to_bytes()), true))
+            .map(|n| RPCNeighbor::from_neighbor_key_and_pubkh(n.addr.clone(), Hash160::from_data(&n.public_key.to_bytes()), true, false))
             .collect();
 
         let mut inbound = vec![];
@@ -207,10 +211,10 @@ impl RPCNeighborsInfo {
             let nk = convo.to_neighbor_key();
             let naddr = convo.to_neighbor_address();
             if convo.is_outbound() {
-                outbound.push(RPCNeighbor::from_neighbor_key_and_pubkh(nk, naddr.public_key_hash, convo.is_authenticated()));
+                outbound.push(RPCNeighbor::from_neighbor_key_and_pubkh(nk, naddr.public_key_hash, convo.is_authenticated(), convo.is_encrypted()));
             }
             else {
-                inbound.push(RPCNeighbor::from_neighbor_key_and_pubkh(nk, naddr.public_key_hash, convo.is_authenticated()));
+                inbound.push(RPCNeighbor::from_neighbor_key_and_pubkh(nk, naddr.public_key_hash, convo.is_authenticated(), convo.is_encrypted()));
             }
         }
 
@@ -222,6 +226,37 @@ impl RPCNeighborsInfo {
     }
 }
 
+impl RPCAdminNeighborsInfo {
+    /// Load connection statistics for each neighbor we're talking to
+    pub fn from_p2p(peers: &PeerMap) -> RPCAdminNeighborsInfo {
+        let mut neighbors = vec![];
+        for (_, convo) in peers.iter() {
+            let nk = convo.to_neighbor_key();
+            let naddr = convo.to_neighbor_address();
+            neighbors.push(RPCAdminNeighbor {
+                neighbor: RPCNeighbor::from_neighbor_key_and_pubkh(nk, naddr.public_key_hash, convo.is_authenticated(), convo.is_encrypted()),
+                outbound: convo.stats.outbound,
+                first_contact_time: convo.stats.first_contact_time,
+                last_contact_time: convo.stats.last_contact_time,
+                last_send_time: convo.stats.last_send_time,
+                last_recv_time: convo.stats.last_recv_time,
+                last_handshake_time: convo.stats.last_handshake_time,
+                bytes_tx: convo.stats.bytes_tx,
+                bytes_rx: convo.stats.bytes_rx,
+                msgs_tx: convo.stats.msgs_tx,
+                msgs_rx: convo.stats.msgs_rx,
+                msgs_rx_unsolicited: convo.stats.msgs_rx_unsolicited,
+                msgs_err: convo.stats.msgs_err,
+                health_score: convo.stats.get_health_score()
+            });
+        }
+
+        RPCAdminNeighborsInfo {
+            neighbors: neighbors
+        }
+    }
+}
+
 impl ConversationHttp {
     pub fn new(network_id: u32, burnchain: &Burnchain, peer_addr: SocketAddr, outbound_url: Option<UrlString>, peer_host: PeerHost, conn_opts: &ConnectionOptions, conn_id: usize) -> ConversationHttp {
         let mut stacks_http = StacksHttp::new();
@@ -239,6 +274,7 @@ impl ConversationHttp {
             pending_request: None,
             pending_response: None,
             pending_error_response: None,
+            pending_admin_commands: vec![],
             keep_alive: true,
             total_request_count: 0,
             total_reply_count: 0,
@@ -268,6 +304,11 @@ impl ConversationHttp {
         self.pending_request.is_some()
     }
     
+    /// Take the peer management commands that came in on the admin endpoints
+    pub fn take_admin_commands(&mut self) -> Vec<PeerAdminCommand> {
+        self.pending_admin_commands.split_off(0)
+    }
+
     /// Start a HTTP request from this peer, and expect a response.
     /// Returns the request handle; does not set the handle into this connection.
     fn start_request(&mut self, req: HttpRequestType) -> Result<ReplyHandleHttp, net_error> {
@@ -346,6 +387,56 @@ impl ConversationHttp {
         response.send(http, fd)
     }
 
+    /// Check the bearer token on an admin request.
+    /// The admin endpoints don't exist unless we have a token configured.
+    /// Returns true if the caller may proceed; otherwise, sends back an error response.
+    fn handle_admin_auth<W: Write>(http: &mut StacksHttp, fd: &mut W, req: &HttpRequestType, options: &ConnectionOptions, token_opt: Option<&String>) -> Result<bool, net_error> {
+        let response_metadata = HttpResponseMetadata::from(req);
+        let admin_token = match options.admin_rpc_token {
+            Some(ref admin_token) => admin_token,
+            None => {
+                let response = HttpResponseType::NotFound(response_metadata, req.request_path());
+                return response.send(http, fd).map(|_| false);
+            }
+        };
+
+        let authorized = match token_opt {
+            Some(token) => {
+                // don't leak how much of the token matched
+                token.len() == admin_token.len() &&
+                    token.bytes().zip(admin_token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+            },
+            None => false
+        };
+
+        if !authorized {
+            debug!("Unauthorized admin request {}", req.request_path());
+            let response = HttpResponseType::Unauthorized(response_metadata, "Missing or invalid bearer token".to_string());
+            return response.send(http, fd).map(|_| false);
+        }
+
+        Ok(true)
+    }
+
+    /// Handle a GET of the admin neighbors list.
+    /// The response will be synchronously written to the given fd (so use a fd that can buffer!)
+    fn handle_admin_getneighbors<W: Write>(http: &mut StacksHttp, fd: &mut W, req: &HttpRequestType, peers: &PeerMap) -> Result<(), net_error> {
+        let response_metadata = HttpResponseMetadata::from(req);
+        let neighbor_data = RPCAdminNeighborsInfo::from_p2p(peers);
+        let response = HttpResponseType::AdminNeighbors(response_metadata, neighbor_data);
+        response.send(http, fd)
+    }
+
+    /// Handle a peer management command.  The peer network carries it out once this conversation
+    /// hands it off, so all we can tell the client is that it was queued -- not whether it
+    /// succeeded.  The admin endpoints are documented as fire-and-forget for this reason.
+    fn handle_admin_command<W: Write>(http: &mut StacksHttp, fd: &mut W, req: &HttpRequestType, command: &PeerAdminCommand) -> Result<(), net_error> {
+        debug!("Accepted admin command {:?}", command);
+        let response_metadata = HttpResponseMetadata::from(req);
+        let response = HttpResponseType::AdminCommandAccepted(response_metadata);
+        response.send(http, fd)
+    }
+
     /// Handle a GET block.  Start streaming the reply.
     /// The response's preamble (but not the block data) will be synchronously written to the fd
     /// (so use a fd that can buffer!)
@@ -860,6 +951,19 @@ impl ConversationHttp {
                 response.send(&mut self.connection.protocol, &mut reply).map(|_| ())?;
                 None
             },
+            HttpRequestType::AdminGetNeighbors(ref _md, ref token_opt) => {
+                if ConversationHttp::handle_admin_auth(&mut self.connection.protocol, &mut reply, &req, &self.connection.options, token_opt.as_ref())? {
+                    ConversationHttp::handle_admin_getneighbors(&mut self.connection.protocol, &mut reply, &req, peers)?;
+                }
+                None
+            },
+            HttpRequestType::AdminCommand(ref _md, ref token_opt, ref command) => {
+                if ConversationHttp::handle_admin_auth(&mut self.connection.protocol, &mut reply, &req, &self.connection.options, token_opt.as_ref())? {
+                    ConversationHttp::handle_admin_command(&mut self.connection.protocol, &mut reply, &req, command)?;
+                    self.pending_admin_commands.push(command.clone());
+                }
+                None
+            },
             HttpRequestType::ClientError(ref _md, ref err) => {
                 let response_metadata = HttpResponseMetadata::from(&req);
                 let response = match err {
@@ -1249,6 +1353,16 @@ impl ConversationHttp {
     pub fn new_callreadonlyfunction(&self, contract_addr: StacksAddress, contract_name: ContractName, sender: PrincipalData, function_name: ClarityName, function_args: Vec<Value>, tip_opt: Option<StacksBlockId>) -> HttpRequestType {
         HttpRequestType::CallReadOnlyFunction(HttpRequestMetadata::from_host(self.peer_host.clone()), contract_addr, contract_name, sender, function_name, function_args, tip_opt)
     }
+
+    /// Make a new request for the admin neighbors list
+    pub fn new_admin_getneighbors(&self, token: Option<String>) -> HttpRequestType {
+        HttpRequestType::AdminGetNeighbors(HttpRequestMetadata::from_host(self.peer_host.clone()), token)
+    }
+
+    /// Make a new request to carry out a peer management command
+    pub fn new_admin_command(&self, token: Option<String>, command: PeerAdminCommand) -> HttpRequestType {
+        HttpRequestType::AdminCommand(HttpRequestMetadata::from_host(self.peer_host.clone()), token, command)
+    }
 }
 
 #[cfg(test)]
@@ -1410,7 +1524,7 @@ mod test {
         tx_signer.sign_origin(&privk1).unwrap();
         let tx_unconfirmed_contract_signed = tx_signer.get_tx().unwrap();
             
-        let tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
+        let tip = SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn()).unwrap();
         let mut anchor_cost = ExecutionCost::zero();
         let mut anchor_size = 0;
 
@@ -1429,7 +1543,7 @@ mod test {
                 }
             };
 
-            let block_builder = StacksBlockBuilder::make_block_builder(&parent_tip, vrf_proof, tip.total_burn, microblock_pubkeyhash).unwrap();
+            let block_builder = StacksBlockBuilder::make_block_builder(&parent_tip, vrf_proof, tip.total_burn, microblock_pubkeyhash, (tip.block_height + 1) as u32).unwrap();
             let (anchored_block, anchored_block_size, anchored_block_cost) = StacksBlockBuilder::make_anchored_block_from_txs(block_builder, chainstate, vec![tx_coinbase_signed.clone(), tx_contract_signed.clone()]).unwrap();
 
             anchor_size = anchored_block_size;
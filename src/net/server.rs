@@ -71,6 +71,9 @@ pub struct HttpPeer {
 
     // connection options
     pub connection_opts: ConnectionOptions,
+
+    // peer management commands received on the admin endpoints, for the peer network to carry out
+    admin_commands: Vec<PeerAdminCommand>,
 }
 
 impl HttpPeer {
@@ -85,7 +88,8 @@ impl HttpPeer {
             http_server_handle: server_handle,
 
             burnchain: burnchain,
-            connection_opts: conn_opts
+            connection_opts: conn_opts,
+            admin_commands: vec![]
         }
     }
 
@@ -93,6 +97,11 @@ impl HttpPeer {
         self.http_server_handle = h;
     }
 
+    /// Take the peer management commands received since the last call
+    pub fn take_admin_commands(&mut self) -> Vec<PeerAdminCommand> {
+        self.admin_commands.split_off(0)
+    }
+
     /// Is there a HTTP conversation open to this data_url that is not in progress?
     pub fn find_free_conversation(&self, data_url: &UrlString) -> Option<usize> {
         for (event_id, convo) in self.peers.iter() {
@@ -458,6 +467,7 @@ impl HttpPeer {
                                 to_remove.push(*event_id);
                             }
                             msgs.append(&mut new_msgs);
+                            self.admin_commands.append(&mut convo.take_admin_commands());
                         },
                         Err(_e) => {
                             to_remove.push(*event_id);
@@ -0,0 +1,316 @@
+/*
+ copyright: (c) 2013-2020 by Blockstack PBC, a public benefit corporation.
+
+ This file is part of Blockstack.
+
+ Blockstack is free software. You may redistribute or modify
+ it under the terms of the GNU General Public License as published by
+ the Free Software Foundation, either version 3 of the License or
+ (at your option) any later version.
+
+ Blockstack is distributed in the hope that it will be useful,
+ but WITHOUT ANY WARRANTY, including without the implied warranty of
+ MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ GNU General Public License for more details.
+
+ You should have received a copy of the GNU General Public License
+ along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+use std::io::Cursor;
+
+use net::Error as net_error;
+use net::StacksMessageType;
+use net::SealedData;
+use net::StacksMessageCodec;
+use net::codec::*;
+
+use util::secp256k1::Secp256k1PrivateKey;
+use util::secp256k1::Secp256k1PublicKey;
+
+use sha2::Sha512Trunc256;
+use sha2::Digest;
+
+use ring::aead::{
+    Aad,
+    LessSafeKey,
+    Nonce,
+    UnboundKey,
+    CHACHA20_POLY1305,
+    NONCE_LEN,
+};
+
+/// Domain separators for the two directions of an encrypted session
+const INITIATOR_KEY_LABEL : &'static [u8] = b"stacks-p2p-session-initiator";
+const RESPONDER_KEY_LABEL : &'static [u8] = b"stacks-p2p-session-responder";
+
+/// Encrypted session state for a conversation with a single peer.
+///
+/// Both peers compute the same ECDH shared secret from their long-lived node keys, and bind it to
+/// this particular handshake via the initiator's handshake sequence number and a nonce chosen by
+/// the responder.  Each direction gets its own key, so sealed messages can't be reflected back to
+/// their sender.  Payloads are sealed with ChaCha20-Poly1305; the per-message AEAD nonce is an
+/// explicit counter carried in the `SealedData`.  The counter must strictly increase on the
+/// receiving side too, so sealed messages can't be replayed or reordered.
+#[derive(Clone)]
+pub struct P2PSession {
+    seal_key: [u8; 32],
+    open_key: [u8; 32],
+    next_nonce: u64,
+    last_open_nonce: Option<u64>
+}
+
+impl fmt::Debug for P2PSession {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // never log key material
+        write!(f, "P2PSession(next_nonce={}, last_open_nonce={:?})", self.next_nonce, self.last_open_nonce)
+    }
+}
+
+impl P2PSession {
+    /// Derive the session keys.  `handshake_seq` is the sequence number of the initiator's
+    /// Handshake, and `session_nonce` is the value the responder put in its HandshakeAccept.
+    pub fn new(local_private_key: &Secp256k1PrivateKey, remote_public_key: &Secp256k1PublicKey, handshake_seq: u32, session_nonce: u32, initiator: bool) -> P2PSession {
+        let shared_secret = local_private_key.shared_secret(remote_public_key);
+        let initiator_key = P2PSession::derive_key(INITIATOR_KEY_LABEL, &shared_secret, handshake_seq, session_nonce);
+        let responder_key = P2PSession::derive_key(RESPONDER_KEY_LABEL, &shared_secret, handshake_seq, session_nonce);
+
+        let (seal_key, open_key) =
+            if initiator {
+                (initiator_key, responder_key)
+            }
+            else {
+                (responder_key, initiator_key)
+            };
+
+        P2PSession {
+            seal_key,
+            open_key,
+            next_nonce: 0,
+            last_open_nonce: None
+        }
+    }
+
+    fn derive_key(label: &[u8], shared_secret: &[u8; 32], handshake_seq: u32, session_nonce: u32) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        let mut sha2 = Sha512Trunc256::new();
+        sha2.input(label);
+        sha2.input(&shared_secret[..]);
+        sha2.input(handshake_seq.to_be_bytes());
+        sha2.input(session_nonce.to_be_bytes());
+        key.copy_from_slice(sha2.result().as_slice());
+        key
+    }
+
+    fn aead_nonce(nonce: u64) -> Nonce {
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        nonce_bytes[NONCE_LEN - 8..].copy_from_slice(&nonce.to_be_bytes());
+        Nonce::assume_unique_for_key(nonce_bytes)
+    }
+
+    fn aead_key(key: &[u8; 32]) -> Result<LessSafeKey, ()> {
+        let unbound_key = UnboundKey::new(&CHACHA20_POLY1305, &key[..]).map_err(|_e| ())?;
+        Ok(LessSafeKey::new(unbound_key))
+    }
+
+    /// Can this payload be sent through an encrypted session?  Handshake messages are always
+    /// sent in the clear, since they're what set up (or tear down) the session.
+    pub fn can_seal(payload: &StacksMessageType) -> bool {
+        match *payload {
+            StacksMessageType::Handshake(_) |
+            StacksMessageType::HandshakeAccept(_) |
+            StacksMessageType::HandshakeReject |
+            StacksMessageType::Sealed(_) => false,
+            _ => true
+        }
+    }
+
+    /// Encrypt a message payload
+    pub fn seal(&mut self, payload: &StacksMessageType) -> Result<SealedData, net_error> {
+        if !P2PSession::can_seal(payload) {
+            return Err(net_error::SigningError(format!("Cannot seal a {} message", payload.get_message_name())));
+        }
+
+        let nonce = self.next_nonce;
+        self.next_nonce = self.next_nonce.checked_add(1)
+            .ok_or(net_error::SigningError("Session nonce overflow".to_string()))?;
+
+        let mut ciphertext = vec![];
+        payload.consensus_serialize(&mut ciphertext)?;
+
+        let key = P2PSession::aead_key(&self.seal_key)
+            .map_err(|_e| net_error::SigningError("Invalid session key".to_string()))?;
+
+        key.seal_in_place_append_tag(P2PSession::aead_nonce(nonce), Aad::empty(), &mut ciphertext)
+            .map_err(|_e| net_error::SigningError("Failed to seal payload".to_string()))?;
+
+        Ok(SealedData {
+            nonce,
+            ciphertext
+        })
+    }
+
+    /// Decrypt and authenticate a sealed message payload.  Its nonce must be higher than that of
+    /// every payload opened before it.
+    pub fn open(&mut self, sealed: &SealedData) -> Result<StacksMessageType, net_error> {
+        if let Some(last_open_nonce) = self.last_open_nonce {
+            if sealed.nonce <= last_open_nonce {
+                return Err(net_error::VerifyingError(format!("Sealed payload nonce {} is not after {}", sealed.nonce, last_open_nonce)));
+            }
+        }
+
+        let key = P2PSession::aead_key(&self.open_key)
+            .map_err(|_e| net_error::VerifyingError("Invalid session key".to_string()))?;
+
+        let mut buf = sealed.ciphertext.clone();
+        let plaintext_len = key.open_in_place(P2PSession::aead_nonce(sealed.nonce), Aad::empty(), &mut buf)
+            .map_err(|_e| net_error::VerifyingError("Failed to open sealed payload".to_string()))?
+            .len();
+
+        let mut cursor = Cursor::new(&buf[0..plaintext_len]);
+        let payload : StacksMessageType = read_next(&mut cursor)?;
+        if (cursor.position() as usize) != plaintext_len {
+            return Err(net_error::DeserializeError("Sealed payload has trailing bytes".to_string()));
+        }
+
+        if !P2PSession::can_seal(&payload) {
+            return Err(net_error::VerifyingError(format!("Sealed payload contains a {} message", payload.get_message_name())));
+        }
+
+        // only authentic payloads advance the counter
+        self.last_open_nonce = Some(sealed.nonce);
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use net::*;
+
+    fn make_session_pair() -> (P2PSession, P2PSession) {
+        let initiator_key = Secp256k1PrivateKey::new();
+        let responder_key = Secp256k1PrivateKey::new();
+
+        let initiator = P2PSession::new(&initiator_key, &Secp256k1PublicKey::from_private(&responder_key), 123, 456, true);
+        let responder = P2PSession::new(&responder_key, &Secp256k1PublicKey::from_private(&initiator_key), 123, 456, false);
+        (initiator, responder)
+    }
+
+    #[test]
+    fn session_seal_open() {
+        let (mut initiator, mut responder) = make_session_pair();
+
+        let ping = StacksMessageType::Ping(PingData { nonce: 0x01020304 });
+        let pong = StacksMessageType::Pong(PongData { nonce: 0x01020304 });
+
+        let sealed_ping = initiator.seal(&ping).unwrap();
+        assert_eq!(sealed_ping.nonce, 0);
+        assert_eq!(responder.open(&sealed_ping).unwrap(), ping);
+
+        // a direction's key can't open its own messages
+        assert!(initiator.open(&sealed_ping).is_err());
+
+        let sealed_pong = responder.seal(&pong).unwrap();
+        assert_eq!(sealed_pong.nonce, 0);
+        assert_eq!(initiator.open(&sealed_pong).unwrap(), pong);
+
+        // nonces advance, so identical payloads seal differently
+        let sealed_ping_2 = initiator.seal(&ping).unwrap();
+        assert_eq!(sealed_ping_2.nonce, 1);
+        assert!(sealed_ping_2.ciphertext != sealed_ping.ciphertext);
+        assert_eq!(responder.open(&sealed_ping_2).unwrap(), ping);
+
+        // handshakes are never sealed
+        assert!(initiator.seal(&StacksMessageType::HandshakeReject).is_err());
+        assert!(initiator.seal(&StacksMessageType::Sealed(sealed_ping.clone())).is_err());
+    }
+
+    #[test]
+    fn session_open_tampered() {
+        let (mut initiator, mut responder) = make_session_pair();
+        let ping = StacksMessageType::Ping(PingData { nonce: 0x01020304 });
+
+        let sealed = initiator.seal(&ping).unwrap();
+
+        let mut bad_ciphertext = sealed.clone();
+        bad_ciphertext.ciphertext[0] ^= 0x01;
+        assert!(responder.open(&bad_ciphertext).is_err());
+
+        let mut bad_tag = sealed.clone();
+        let last = bad_tag.ciphertext.len() - 1;
+        bad_tag.ciphertext[last] ^= 0x01;
+        assert!(responder.open(&bad_tag).is_err());
+
+        let mut bad_nonce = sealed.clone();
+        bad_nonce.nonce += 1;
+        assert!(responder.open(&bad_nonce).is_err());
+
+        let mut truncated = sealed.clone();
+        truncated.ciphertext.truncate(8);
+        assert!(responder.open(&truncated).is_err());
+
+        assert_eq!(responder.open(&sealed).unwrap(), ping);
+    }
+
+    #[test]
+    fn session_open_wrong_keys() {
+        let initiator_key = Secp256k1PrivateKey::new();
+        let responder_key = Secp256k1PrivateKey::new();
+        let other_key = Secp256k1PrivateKey::new();
+        let ping = StacksMessageType::Ping(PingData { nonce: 0x01020304 });
+
+        let mut initiator = P2PSession::new(&initiator_key, &Secp256k1PublicKey::from_private(&responder_key), 123, 456, true);
+        let sealed = initiator.seal(&ping).unwrap();
+
+        // different peer
+        let mut imposter = P2PSession::new(&other_key, &Secp256k1PublicKey::from_private(&initiator_key), 123, 456, false);
+        assert!(imposter.open(&sealed).is_err());
+
+        // different handshake
+        let mut stale_seq = P2PSession::new(&responder_key, &Secp256k1PublicKey::from_private(&initiator_key), 124, 456, false);
+        assert!(stale_seq.open(&sealed).is_err());
+
+        let mut stale_nonce = P2PSession::new(&responder_key, &Secp256k1PublicKey::from_private(&initiator_key), 123, 457, false);
+        assert!(stale_nonce.open(&sealed).is_err());
+
+        let mut responder = P2PSession::new(&responder_key, &Secp256k1PublicKey::from_private(&initiator_key), 123, 456, false);
+        assert_eq!(responder.open(&sealed).unwrap(), ping);
+    }
+
+    #[test]
+    fn session_open_replayed() {
+        let (mut initiator, mut responder) = make_session_pair();
+        let ping = StacksMessageType::Ping(PingData { nonce: 0x01020304 });
+
+        let sealed = initiator.seal(&ping).unwrap();
+        assert_eq!(responder.open(&sealed).unwrap(), ping);
+        assert!(responder.open(&sealed).is_err());
+
+        // the session still works
+        let sealed_2 = initiator.seal(&ping).unwrap();
+        assert_eq!(responder.open(&sealed_2).unwrap(), ping);
+        assert!(responder.open(&sealed).is_err());
+        assert!(responder.open(&sealed_2).is_err());
+    }
+
+    #[test]
+    fn session_open_reordered() {
+        let (mut initiator, mut responder) = make_session_pair();
+        let pings : Vec<_> = (0..4).map(|i| StacksMessageType::Ping(PingData { nonce: i })).collect();
+        let sealed : Vec<_> = pings.iter().map(|ping| initiator.seal(ping).unwrap()).collect();
+
+        // messages may be skipped, but not delivered out of order
+        assert_eq!(responder.open(&sealed[0]).unwrap(), pings[0]);
+        assert_eq!(responder.open(&sealed[2]).unwrap(), pings[2]);
+        assert!(responder.open(&sealed[1]).is_err());
+
+        // a forged message with a far-off nonce doesn't advance the counter
+        let mut forged = sealed[3].clone();
+        forged.nonce = u64::max_value();
+        assert!(responder.open(&forged).is_err());
+
+        assert_eq!(responder.open(&sealed[3]).unwrap(), pings[3]);
+    }
+}
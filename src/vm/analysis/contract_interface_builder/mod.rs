@@ -1,7 +1,7 @@
 use vm::analysis::types::ContractAnalysis;
 use std::collections::{BTreeMap, BTreeSet};
 use vm::{ClarityName};
-use vm::types::{TypeSignature, FunctionArg, TupleTypeSignature, FunctionType, FixedFunction};
+use vm::types::{TypeSignature, FunctionArg, TupleTypeSignature, FunctionType, FixedFunction, StringSubtype};
 
 pub fn build_contract_interface(contract_analysis: &ContractAnalysis) -> ContractInterface {
     let mut contract_interface = ContractInterface::new();
@@ -83,6 +83,10 @@ pub enum ContractInterfaceAtomType {
     bool,
     principal,
     buffer { length: u32 },
+    #[serde(rename = "string-ascii")]
+    string_ascii { length: u32 },
+    #[serde(rename = "string-utf8")]
+    string_utf8 { length: u32 },
     tuple(Vec<ContractInterfaceTupleEntryType>),
     optional(Box<ContractInterfaceAtomType>),
     response { ok: Box<ContractInterfaceAtomType>, error: Box<ContractInterfaceAtomType> },
@@ -133,6 +137,8 @@ impl ContractInterfaceAtomType {
             PrincipalType => ContractInterfaceAtomType::principal,
             TraitReferenceType(_) => ContractInterfaceAtomType::trait_reference,
             BufferType(len) => ContractInterfaceAtomType::buffer { length: len.into() },
+            StringType(StringSubtype::ASCII(len)) => ContractInterfaceAtomType::string_ascii { length: len.into() },
+            StringType(StringSubtype::UTF8(len)) => ContractInterfaceAtomType::string_utf8 { length: len.into() },
             TupleType(sig) => Self::from_tuple_type(sig),
             ListType(list_data) => {
                 let (type_f, length) = list_data.clone().destruct();
@@ -151,6 +157,42 @@ impl ContractInterfaceAtomType {
             }
         }
     }
+
+    /// The type signature this atom type was built from.  Returns None for trait references,
+    ///   which don't record their trait, and for types that are no longer valid.
+    pub fn to_type_signature(&self) -> Option<TypeSignature> {
+        use std::convert::TryFrom;
+        use vm::types::{BufferLength, StringUTF8Length};
+        let sig = match self {
+            ContractInterfaceAtomType::none => TypeSignature::NoType,
+            ContractInterfaceAtomType::int128 => TypeSignature::IntType,
+            ContractInterfaceAtomType::uint128 => TypeSignature::UIntType,
+            ContractInterfaceAtomType::bool => TypeSignature::BoolType,
+            ContractInterfaceAtomType::principal => TypeSignature::PrincipalType,
+            ContractInterfaceAtomType::trait_reference => return None,
+            ContractInterfaceAtomType::buffer { length } =>
+                TypeSignature::BufferType(BufferLength::try_from(*length).ok()?),
+            ContractInterfaceAtomType::string_ascii { length } =>
+                TypeSignature::StringType(StringSubtype::ASCII(BufferLength::try_from(*length).ok()?)),
+            ContractInterfaceAtomType::string_utf8 { length } =>
+                TypeSignature::StringType(StringSubtype::UTF8(StringUTF8Length::try_from(*length).ok()?)),
+            ContractInterfaceAtomType::tuple(entries) => {
+                let mut type_data = vec![];
+                for entry in entries.iter() {
+                    let name = ClarityName::try_from(entry.name.clone()).ok()?;
+                    type_data.push((name, entry.type_f.to_type_signature()?));
+                }
+                TypeSignature::TupleType(TupleTypeSignature::try_from(type_data).ok()?)
+            },
+            ContractInterfaceAtomType::optional(inner) =>
+                TypeSignature::new_option(inner.to_type_signature()?).ok()?,
+            ContractInterfaceAtomType::response { ok, error } =>
+                TypeSignature::new_response(ok.to_type_signature()?, error.to_type_signature()?).ok()?,
+            ContractInterfaceAtomType::list { type_f, length } =>
+                TypeSignature::list_of(type_f.to_type_signature()?, *length).ok()?,
+        };
+        Some(sig)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
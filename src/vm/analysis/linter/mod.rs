@@ -0,0 +1,305 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use vm::analysis::types::ContractAnalysis;
+use vm::coverage::expr_line;
+use vm::functions::define::DefineFunctionsParsed;
+use vm::is_reserved;
+use vm::representations::{SymbolicExpression, ClarityName};
+use vm::representations::SymbolicExpressionType::{Atom, List};
+use vm::types::TypeSignature;
+
+define_named_enum!(LintKind {
+    UnusedBinding("unused-binding"),
+    UnusedPrivateFunction("unused-private-function"),
+    UncheckedResponse("unchecked-response"),
+    ShadowedName("shadowed-name"),
+    UnwrapPanicInPublic("unwrap-panic-in-public"),
+});
+
+/// A likely mistake in a contract.  Unlike the errors raised by the other analysis passes,
+///   lint warnings never stop a contract from being published.
+#[derive(Debug)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    /// 0 if this build does not track source spans
+    pub line: u32,
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: warning[{}]: {}", self.line, self.kind.get_name(), self.message)
+    }
+}
+
+struct Binding {
+    name: ClarityName,
+    line: u32,
+    used: bool,
+}
+
+/// Looks for likely mistakes in a contract that has passed type checking: bindings and
+///   private functions that are never used, responses that are discarded without being
+///   checked, bindings that shadow other names, and `unwrap-panic` in public functions.
+pub struct Linter <'a> {
+    contract_analysis: &'a ContractAnalysis,
+    /// every name defined by the contract's top-level `define-*` forms
+    top_level_names: HashSet<ClarityName>,
+    /// the `let`, `match` and argument bindings in scope, innermost last
+    scopes: Vec<Vec<Binding>>,
+    /// names referenced somewhere other than a local binding
+    referenced: HashSet<ClarityName>,
+    /// the public function being linted, if any
+    public_function: Option<ClarityName>,
+    warnings: Vec<LintWarning>,
+}
+
+impl <'a> Linter <'a> {
+    pub fn run(contract_analysis: &'a ContractAnalysis) -> Vec<LintWarning> {
+        let mut linter = Linter {
+            contract_analysis,
+            top_level_names: HashSet::new(),
+            scopes: vec![],
+            referenced: HashSet::new(),
+            public_function: None,
+            warnings: vec![],
+        };
+        linter.lint_contract();
+        linter.warnings.sort_by_key(|warning| warning.line);
+        linter.warnings
+    }
+
+    fn warn(&mut self, kind: LintKind, line: u32, message: String) {
+        self.warnings.push(LintWarning { kind, line, message });
+    }
+
+    fn lint_contract(&mut self) {
+        let contract_analysis = self.contract_analysis;
+        let expressions = &contract_analysis.expressions;
+
+        // (name, line) of every private function
+        let mut private_functions = vec![];
+        for expression in expressions.iter() {
+            let parsed = match DefineFunctionsParsed::try_parse(expression) {
+                Ok(Some(parsed)) => parsed,
+                _ => continue
+            };
+            match parsed {
+                DefineFunctionsParsed::PrivateFunction { signature, .. } => {
+                    if let Some(name) = signature.get(0).and_then(|name| name.match_atom()) {
+                        private_functions.push((name.clone(), expr_line(expression)));
+                        self.top_level_names.insert(name.clone());
+                    }
+                },
+                DefineFunctionsParsed::PublicFunction { signature, .. } |
+                DefineFunctionsParsed::ReadOnlyFunction { signature, .. } => {
+                    if let Some(name) = signature.get(0).and_then(|name| name.match_atom()) {
+                        self.top_level_names.insert(name.clone());
+                    }
+                },
+                DefineFunctionsParsed::Constant { name, .. } |
+                DefineFunctionsParsed::NonFungibleToken { name, .. } |
+                DefineFunctionsParsed::BoundedFungibleToken { name, .. } |
+                DefineFunctionsParsed::UnboundedFungibleToken { name } |
+                DefineFunctionsParsed::Map { name, .. } |
+                DefineFunctionsParsed::PersistedVariable { name, .. } |
+                DefineFunctionsParsed::Trait { name, .. } |
+                DefineFunctionsParsed::UseTrait { name, .. } => {
+                    self.top_level_names.insert(name.clone());
+                },
+                DefineFunctionsParsed::ImplTrait { .. } => {}
+            }
+        }
+
+        for expression in expressions.iter() {
+            match DefineFunctionsParsed::try_parse(expression) {
+                Ok(Some(DefineFunctionsParsed::PrivateFunction { signature, body })) |
+                Ok(Some(DefineFunctionsParsed::ReadOnlyFunction { signature, body })) => {
+                    self.lint_function(signature, body);
+                },
+                Ok(Some(DefineFunctionsParsed::PublicFunction { signature, body })) => {
+                    self.public_function = signature.get(0).and_then(|name| name.match_atom()).cloned();
+                    self.lint_function(signature, body);
+                    self.public_function = None;
+                },
+                Ok(Some(DefineFunctionsParsed::Constant { value, .. })) => self.lint_expression(value),
+                Ok(Some(DefineFunctionsParsed::PersistedVariable { initial, .. })) => self.lint_expression(initial),
+                Ok(Some(DefineFunctionsParsed::BoundedFungibleToken { max_supply, .. })) => self.lint_expression(max_supply),
+                Ok(Some(_)) => {},
+                _ => self.lint_expression(expression)
+            }
+        }
+
+        for (name, line) in private_functions.into_iter() {
+            if !self.referenced.contains(&name) {
+                self.warn(LintKind::UnusedPrivateFunction, line,
+                          format!("private function `{}` is never called", name.as_str()));
+            }
+        }
+    }
+
+    fn lint_function(&mut self, signature: &[SymbolicExpression], body: &SymbolicExpression) {
+        let arguments = signature.iter().skip(1)
+            .filter_map(|argument| {
+                let name = argument.match_list()?.get(0)?.match_atom()?;
+                Some((name.clone(), expr_line(argument)))
+            })
+            .collect();
+        self.push_scope(arguments);
+        self.lint_expression(body);
+        // unused arguments are not reported: a public function's signature is part of its interface.
+        self.scopes.pop();
+    }
+
+    /// Bring bindings into scope, reporting any that shadow a name already in use
+    fn push_scope(&mut self, names: Vec<(ClarityName, u32)>) {
+        let mut scope = vec![];
+        for (name, line) in names.into_iter() {
+            let shadows_local = self.scopes.iter()
+                .any(|scope| scope.iter().any(|binding| binding.name == name));
+            if shadows_local || self.top_level_names.contains(&name) {
+                self.warn(LintKind::ShadowedName, line,
+                          format!("`{}` shadows another definition of the same name", name.as_str()));
+            } else if is_reserved(&name) {
+                self.warn(LintKind::ShadowedName, line,
+                          format!("`{}` shadows a built-in function or keyword", name.as_str()));
+            }
+            scope.push(Binding { name, line, used: false });
+        }
+        self.scopes.push(scope);
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().expect("BUG: linter popped an empty scope stack");
+        for binding in scope.into_iter() {
+            if !binding.used {
+                self.warn(LintKind::UnusedBinding, binding.line,
+                          format!("`{}` is bound but never used", binding.name.as_str()));
+            }
+        }
+    }
+
+    fn reference(&mut self, name: &ClarityName) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.iter_mut().rev().find(|binding| &binding.name == name) {
+                binding.used = true;
+                return;
+            }
+        }
+        self.referenced.insert(name.clone());
+    }
+
+    fn lint_expressions(&mut self, expressions: &[SymbolicExpression]) {
+        for expression in expressions.iter() {
+            self.lint_expression(expression);
+        }
+    }
+
+    /// Lint a sequence of statements whose results, except for the last, are thrown away
+    fn lint_statements(&mut self, statements: &[SymbolicExpression]) {
+        if let Some((_last, discarded)) = statements.split_last() {
+            for statement in discarded.iter() {
+                self.check_response_used(statement);
+            }
+        }
+        self.lint_expressions(statements);
+    }
+
+    fn check_response_used(&mut self, statement: &SymbolicExpression) {
+        let statement_type = self.contract_analysis.type_map.as_ref()
+            .and_then(|type_map| type_map.get_type(statement));
+        if let Some(TypeSignature::ResponseType(_)) = statement_type {
+            let described = match statement.match_list().and_then(|list| list.get(0)).and_then(|head| head.match_atom()) {
+                Some(function_name) => format!("the response from `{}`", function_name.as_str()),
+                None => "a response".to_string()
+            };
+            self.warn(LintKind::UncheckedResponse, expr_line(statement),
+                      format!("{} is discarded without being checked", described));
+        }
+    }
+
+    fn lint_expression(&mut self, expression: &SymbolicExpression) {
+        let list = match expression.expr {
+            Atom(ref name) => return self.reference(name),
+            List(ref list) => list,
+            _ => return
+        };
+        let (function_name, args) = match list.split_first() {
+            Some((head, args)) => match head.match_atom() {
+                Some(function_name) => (function_name, args),
+                None => return self.lint_expressions(list)
+            },
+            None => return
+        };
+
+        match function_name.as_str() {
+            "let" if args.len() >= 2 => {
+                let mut names = vec![];
+                for binding in args[0].match_list().unwrap_or(&[]).iter() {
+                    if let Some([name, value]) = binding.match_list() {
+                        self.lint_expression(value);
+                        if let Some(name) = name.match_atom() {
+                            names.push((name.clone(), expr_line(binding)));
+                        }
+                    }
+                }
+                self.push_scope(names);
+                self.lint_statements(&args[1..]);
+                self.pop_scope();
+            },
+            "match" if args.len() == 4 || args.len() == 5 => {
+                self.lint_expression(&args[0]);
+                let branches: Vec<_> = if args.len() == 4 {
+                    // (match option some-name some-branch none-branch)
+                    vec![(Some(&args[1]), &args[2]), (None, &args[3])]
+                } else {
+                    // (match response ok-name ok-branch err-name err-branch)
+                    vec![(Some(&args[1]), &args[2]), (Some(&args[3]), &args[4])]
+                };
+                for (name, branch) in branches.into_iter() {
+                    match name.and_then(|name| name.match_atom()) {
+                        Some(bound_name) => {
+                            let line = name.map(expr_line).unwrap_or(0);
+                            self.push_scope(vec![(bound_name.clone(), line)]);
+                            self.lint_expression(branch);
+                            self.pop_scope();
+                        },
+                        None => self.lint_expression(branch)
+                    }
+                }
+            },
+            "begin" => self.lint_statements(args),
+            "unwrap-panic" | "unwrap-err-panic" => {
+                if let Some(public_function) = self.public_function.clone() {
+                    self.warn(LintKind::UnwrapPanicInPublic, expr_line(expression),
+                              format!("`{}` in public function `{}` aborts the transaction without an error code; consider `{}` with an error",
+                                      function_name.as_str(), public_function.as_str(),
+                                      function_name.trim_end_matches("-panic").to_string() + "!"));
+                }
+                self.lint_expressions(args);
+            },
+            // field names are not references
+            "get" if args.len() == 2 => self.lint_expression(&args[1]),
+            "tuple" => {
+                for entry in args.iter() {
+                    if let Some([_key, value]) = entry.match_list() {
+                        self.lint_expression(value);
+                    }
+                }
+            },
+            // the function called is in another contract
+            "contract-call?" if args.len() >= 2 => {
+                self.lint_expression(&args[0]);
+                self.lint_expressions(&args[2..]);
+            },
+            _ => {
+                self.reference(function_name);
+                self.lint_expressions(args);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,72 @@
+use vm::analysis::mem_type_check;
+use vm::analysis::linter::{Linter, LintKind};
+
+fn lint(contract: &str) -> Vec<(String, u32)> {
+    let (_, contract_analysis) = mem_type_check(contract).unwrap();
+    Linter::run(&contract_analysis).iter()
+        .map(|warning| (warning.kind.get_name(), warning.line))
+        .collect()
+}
+
+#[test]
+fn test_clean_contract() {
+    let contract = "(define-data-var total int 0)
+         (define-private (double (x int)) (* x 2))
+         (define-private (add-to-total (x int))
+           (begin (var-set total (+ (var-get total) x))
+                  (if (> x 0) (ok (var-get total)) (err u1))))
+         (define-public (add-all (items (list 10 int)))
+           (let ((doubled (map double items)))
+             (match (add-to-total (fold + doubled 0))
+               new-total (ok new-total)
+               error (err error))))
+         (define-read-only (get-entry (amount int))
+           (get amount {amount: amount}))";
+
+    assert_eq!(lint(contract), vec![]);
+}
+
+#[test]
+fn test_lints() {
+    let contract = "(define-data-var total int 0)
+         (define-private (never-called) (ok 1))
+         (define-private (set-total (x int))
+           (begin (var-set total x)
+                  (ok x)))
+         (define-public (bump (by int))
+           (let ((unused 1)
+                 (next (+ (var-get total) by)))
+             (set-total next)
+             (ok (unwrap-panic (set-total next)))))
+         (define-read-only (get-or-zero (value (optional int)))
+           (match value
+             inner 0
+             0))
+         (define-read-only (get-value (value int))
+           (let ((filter value)) filter))";
+
+    assert_eq!(lint(contract), vec![
+        ("unused-private-function".to_string(), 2),
+        ("unused-binding".to_string(), 7),
+        ("unchecked-response".to_string(), 9),
+        ("unwrap-panic-in-public".to_string(), 10),
+        ("unused-binding".to_string(), 13),
+        ("shadowed-name".to_string(), 16),
+    ]);
+
+    // unwrap-panic is fine outside of public functions
+    let contract = "(define-read-only (get (value (optional int))) (unwrap-panic value))";
+    assert_eq!(lint(contract), vec![]);
+}
+
+#[test]
+fn test_lint_warning_display() {
+    let (_, contract_analysis) = mem_type_check("(define-private (foo) 1)").unwrap();
+    let warnings = Linter::run(&contract_analysis);
+    assert_eq!(warnings.len(), 1);
+    match warnings[0].kind {
+        LintKind::UnusedPrivateFunction => {},
+        _ => panic!("Expected an unused-private-function warning")
+    }
+    assert_eq!(warnings[0].to_string(), "1: warning[unused-private-function]: private function `foo` is never called");
+}
@@ -3,14 +3,17 @@ pub mod errors;
 pub mod trait_checker;
 pub mod type_checker;
 pub mod read_only_checker;
+pub mod linter;
 pub mod analysis_db;
 pub mod contract_interface_builder;
 
 pub use self::types::{ContractAnalysis, AnalysisPass};
+use std::collections::BTreeMap;
 use vm::representations::{SymbolicExpression};
 use vm::types::{TypeSignature, QualifiedContractIdentifier};
 use vm::costs::LimitedCostTracker;
 use vm::database::STORE_CONTRACT_SRC_INTERFACE;
+use vm::diagnostic::Diagnostic;
 
 pub use self::errors::{CheckResult, CheckError, CheckErrors};
 pub use self::analysis_db::{AnalysisDatabase};
@@ -70,6 +73,44 @@ pub fn run_analysis(contract_identifier: &QualifiedContractIdentifier,
     }
 }
 
+/// Runs the same passes as `run_analysis`, but keeps going past top-level expressions which fail
+///   to check, and returns the diagnostics for all the independent errors found, in source order.
+///   The analysis is never saved.
+/// This is for developer tooling: contract deployment must use `run_analysis`, which stops at
+///   the first error.
+pub fn run_analysis_all_errors(contract_identifier: &QualifiedContractIdentifier,
+                               expressions: &[SymbolicExpression],
+                               analysis_db: &mut AnalysisDatabase,
+                               cost_tracker: LimitedCostTracker) -> Result<ContractAnalysis, Vec<Diagnostic>> {
+    let mut contract_analysis = ContractAnalysis::new(contract_identifier.clone(), expressions.to_vec(), cost_tracker);
+    let result = analysis_db.execute(|db| {
+        // at most one error is reported per top-level expression, and the read-only checker
+        //   runs first in `run_analysis`, so its errors take precedence.
+        let mut errors = BTreeMap::new();
+        for (index, error) in ReadOnlyChecker::run_pass_collecting_errors(&mut contract_analysis, db) {
+            errors.entry(index).or_insert(error);
+        }
+        for (index, error) in TypeChecker::run_pass_collecting_errors(&mut contract_analysis, db) {
+            errors.entry(index).or_insert(error);
+        }
+        if errors.is_empty() {
+            TraitChecker::run_pass(&mut contract_analysis, db)
+                .map_err(|e| vec![e.diagnostic])?;
+        } else {
+            return Err(errors.into_values().map(|e| e.diagnostic).collect());
+        }
+        if STORE_CONTRACT_SRC_INTERFACE {
+            let interface = build_contract_interface(&contract_analysis);
+            contract_analysis.contract_interface = Some(interface);
+        }
+        Ok(())
+    });
+    match result {
+        Ok(_) => Ok(contract_analysis),
+        Err(diagnostics) => Err(diagnostics)
+    }
+}
+
 #[cfg(test)]
 mod tests;
 
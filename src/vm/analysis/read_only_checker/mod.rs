@@ -30,6 +30,13 @@ impl <'a, 'b> AnalysisPass for ReadOnlyChecker <'a, 'b> {
     }
 }
 
+impl <'a, 'b> ReadOnlyChecker <'a, 'b> {
+    pub fn run_pass_collecting_errors(contract_analysis: &mut ContractAnalysis, analysis_db: &mut AnalysisDatabase) -> Vec<(usize, CheckError)> {
+        let mut command = ReadOnlyChecker::new(analysis_db);
+        command.run_collecting_errors(contract_analysis)
+    }
+}
+
 impl <'a, 'b> ReadOnlyChecker <'a, 'b> {
 
     fn new(db: &'a mut AnalysisDatabase<'b>) -> ReadOnlyChecker<'a, 'b> {
@@ -42,16 +49,40 @@ impl <'a, 'b> ReadOnlyChecker <'a, 'b> {
     pub fn run(& mut self, contract_analysis: &mut ContractAnalysis) -> CheckResult<()> {
 
         for exp in contract_analysis.expressions.iter() {
-            let mut result = self.check_reads_only_valid(&exp);
-            if let Err(ref mut error) = result {
-                if !error.has_expression() {
-                    error.set_expression(&exp);
+            self.check_top_level(exp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Like `run`, but keeps going after a top-level expression fails the check.  A function whose
+    ///   definition failed is treated as read-only wherever it is called.  Returns each error with
+    ///   the index of the top-level expression it came from.
+    pub fn run_collecting_errors(&mut self, contract_analysis: &ContractAnalysis) -> Vec<(usize, CheckError)> {
+        let mut errors = vec![];
+        for (index, exp) in contract_analysis.expressions.iter().enumerate() {
+            if let Err(error) = self.check_top_level(exp) {
+                if let Ok(Some(DefineFunctionsParsed::PrivateFunction { signature, .. })) |
+                       Ok(Some(DefineFunctionsParsed::PublicFunction { signature, .. })) |
+                       Ok(Some(DefineFunctionsParsed::ReadOnlyFunction { signature, .. })) = DefineFunctionsParsed::try_parse(exp) {
+                    if let Some(function_name) = signature.first().and_then(|name| name.match_atom()) {
+                        self.defined_functions.entry(function_name.clone()).or_insert(true);
+                    }
                 }
+                errors.push((index, error));
             }
-            result?
         }
+        errors
+    }
 
-        Ok(())
+    fn check_top_level(&mut self, exp: &SymbolicExpression) -> CheckResult<()> {
+        let mut result = self.check_reads_only_valid(exp);
+        if let Err(ref mut error) = result {
+            if !error.has_expression() {
+                error.set_expression(exp);
+            }
+        }
+        result
     }
 
     fn check_define_function(&mut self, signature: &[SymbolicExpression], body: &SymbolicExpression) -> CheckResult<(ClarityName, bool)> {
@@ -160,12 +191,12 @@ impl <'a, 'b> ReadOnlyChecker <'a, 'b> {
         match function {
             Add | Subtract | Divide | Multiply | CmpGeq | CmpLeq | CmpLess | CmpGreater |
             Modulo | Power | BitwiseXOR | And | Or | Not | Hash160 | Sha256 | Keccak256 | Equals | If |
-            Sha512 | Sha512Trunc256 |
+            Sha512 | Sha512Trunc256 | Secp256k1Recover | Secp256k1Verify | PrincipalOf |
             ConsSome | ConsOkay | ConsError | DefaultTo | UnwrapRet | UnwrapErrRet | IsOkay | IsNone | Asserts |
             Unwrap | UnwrapErr | Match | IsErr | IsSome | TryRet |
             ToUInt | ToInt | Append | Concat | AsMaxLen |
             ContractOf |
-            ListCons | GetBlockInfo | TupleGet | Len | Print | AsContract | Begin | FetchVar | GetStxBalance | GetTokenBalance | GetAssetOwner => {
+            ListCons | GetBlockInfo | TupleGet | Len | ElementAt | IndexOf | Slice | Print | AsContract | Begin | FetchVar | GetStxBalance | GetTokenBalance | GetTokenSupply | GetAssetOwner => {
                 self.check_all_read_only(args)
             },
             AtBlock => {
@@ -192,7 +223,8 @@ impl <'a, 'b> ReadOnlyChecker <'a, 'b> {
                 res
             },
             StxTransfer | StxBurn |
-            SetEntry | DeleteEntry | InsertEntry | SetVar | MintAsset | MintToken | TransferAsset | TransferToken => {
+            SetEntry | DeleteEntry | InsertEntry | SetVar | MintAsset | MintToken | TransferAsset | TransferToken |
+            BurnAsset | BurnToken => {
                 Ok(false)
             },
             Let => {
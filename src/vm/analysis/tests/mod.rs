@@ -208,7 +208,7 @@ fn test_non_function_application() {
 fn test_expected_list_or_buff() {
     let snippet = "(filter not 4)";
     let err = mem_type_check(snippet).unwrap_err();
-    assert!(format!("{}", err.diagnostic).contains("expecting expression of type 'list' or 'buff'"));
+    assert!(format!("{}", err.diagnostic).contains("expecting expression of type 'list', 'buff', 'string-ascii' or 'string-utf8'"));
 }
 
 #[test]
@@ -267,3 +267,50 @@ fn test_write_attempt_in_readonly() {
     let err = mem_type_check(snippet).unwrap_err();
     assert!(format!("{}", err.diagnostic).contains("expecting read-only statements, detected a writing operation"));
 }
+
+#[test]
+fn test_run_analysis_all_errors() {
+    use vm::analysis::{run_analysis, run_analysis_all_errors};
+    use vm::costs::LimitedCostTracker;
+    use vm::database::MemoryBackingStore;
+    use vm::types::QualifiedContractIdentifier;
+
+    let contract = "(define-constant bad-constant (+ 1 u1))
+         (define-data-var counter uint u0)
+         (define-read-only (writes) (begin (var-set counter u1) u1))
+         (define-private (broken (x int)) (+ x true))
+         (define-private (uses-broken) (+ (broken 1) 1))
+         (define-private (uses-constant) (- bad-constant 1))
+         (define-public (unknown) (ok (no-such-function)))
+         (define-read-only (reads) (ok (writes)))
+         (define-public (fine) (ok (var-get counter)))
+         (define-public (not-a-response) u1)";
+
+    let contract_identifier = QualifiedContractIdentifier::transient();
+    let mut expressions = parse(&contract_identifier, contract).unwrap();
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_analysis_db();
+
+    // errors that follow from using `bad-constant`, `broken` or `writes` are not reported
+    let diagnostics = run_analysis_all_errors(&contract_identifier, &expressions, &mut db,
+                                              LimitedCostTracker::new_max_limit()).unwrap_err();
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, vec![
+        "expecting expression of type 'int', found 'uint'",
+        "expecting read-only statements, detected a writing operation",
+        "expecting expression of type 'int', found 'bool'",
+        "use of unresolved function 'no-such-function'",
+        "public functions must return an expression of type 'response', found 'uint'"]);
+
+    // the consensus path still stops at the first error (from the read-only checker, which runs first)
+    let err = run_analysis(&contract_identifier, &mut expressions, &mut db, false,
+                           LimitedCostTracker::new_max_limit()).map_err(|(e, _)| e).unwrap_err();
+    assert_eq!(err.diagnostic, diagnostics[1]);
+
+    let contract = "(define-data-var counter uint u0)
+         (define-public (fine) (ok (var-get counter)))";
+    let expressions = parse(&contract_identifier, contract).unwrap();
+    let analysis = run_analysis_all_errors(&contract_identifier, &expressions, &mut db,
+                                           LimitedCostTracker::new_max_limit()).unwrap();
+    assert!(analysis.public_function_types.contains_key("fine"));
+}
@@ -3,7 +3,7 @@ pub mod contexts;
 pub mod natives;
 
 use std::convert::TryInto;
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use vm::representations::{SymbolicExpression, ClarityName, depth_traverse};
 use vm::representations::SymbolicExpressionType::{AtomValue, Atom, List, LiteralValue, TraitReference, Field};
 use vm::types::{TypeSignature, TupleTypeSignature, FunctionArg,
@@ -51,6 +51,10 @@ pub struct TypeChecker <'a, 'b> {
     function_return_tracker: Option<Option<TypeSignature>>,
     db: &'a mut AnalysisDatabase<'b>,
     pub cost_track: LimitedCostTracker,
+    // only used by run_collecting_errors: the names of top-level definitions that failed to check,
+    //   and whether the current top-level expression has looked any of them up.
+    failed_definitions: HashSet<ClarityName>,
+    used_failed_definition: bool,
 }
 
 impl CostTracker for TypeChecker<'_, '_> {
@@ -89,6 +93,23 @@ impl AnalysisPass for TypeChecker <'_, '_> {
     }
 }
 
+impl TypeChecker <'_, '_> {
+    /// Like `run_pass`, but runs the checker with `run_collecting_errors`.  The contract analysis
+    ///   is only filled in if no errors were found.
+    pub fn run_pass_collecting_errors(contract_analysis: &mut ContractAnalysis, analysis_db: &mut AnalysisDatabase) -> Vec<(usize, CheckError)> {
+        let cost_track = contract_analysis.take_contract_cost_tracker();
+        let mut command = TypeChecker::new(analysis_db, cost_track);
+        let errors = command.run_collecting_errors(contract_analysis);
+        let cost_track = if errors.is_empty() {
+            command.into_contract_analysis(contract_analysis)
+        } else {
+            command.cost_track
+        };
+        contract_analysis.replace_contract_cost_tracker(cost_track);
+        errors
+    }
+}
+
 pub type TypeResult = CheckResult<TypeSignature>;
 
 impl FunctionType {
@@ -201,6 +222,21 @@ pub fn no_type() -> TypeSignature {
     TypeSignature::NoType
 }
 
+/// The error-typed placeholder bound to a function whose definition failed to check: it keeps
+///   the declared arguments, but returns NoType.
+fn placeholder_function_type(signature: &[SymbolicExpression]) -> FunctionType {
+    let args = parse_name_type_pairs::<()>(&signature[1..], &mut ())
+        .unwrap_or_default();
+    FunctionType::Fixed(FixedFunction {
+        args: args.into_iter().map(|(arg_name, arg_type)| FunctionArg::new(arg_type, arg_name)).collect(),
+        returns: no_type()
+    })
+}
+
+fn is_cost_error(error: &CheckErrors) -> bool {
+    matches!(error, CheckErrors::CostOverflow | CheckErrors::CostBalanceExceeded(_, _) | CheckErrors::MemoryBalanceExceeded(_, _))
+}
+
 impl <'a, 'b> TypeChecker <'a, 'b> {
     fn new(db: &'a mut AnalysisDatabase<'b>, cost_track: LimitedCostTracker) -> TypeChecker<'a, 'b> {
         Self {
@@ -208,6 +244,8 @@ impl <'a, 'b> TypeChecker <'a, 'b> {
             contract_context: ContractContext::new(),
             function_return_tracker: None,
             type_map: TypeMap::new(),
+            failed_definitions: HashSet::new(),
+            used_failed_definition: false,
         }
     }
 
@@ -242,6 +280,131 @@ impl <'a, 'b> TypeChecker <'a, 'b> {
     }
 
     pub fn run(&mut self, contract_analysis: &mut ContractAnalysis) -> CheckResult<()> {
+        self.charge_analysis_storage(contract_analysis)?;
+
+        let mut local_context = TypingContext::new();
+
+        for exp in contract_analysis.expressions.iter() {
+            self.type_check_top_level(exp, &mut local_context)?;
+        }
+        Ok(())
+    }
+
+    /// Like `run`, but keeps going after a top-level expression fails to check, so that every
+    ///   independent error in the contract is found.  The name of a failed definition is bound to
+    ///   a placeholder (an error type for functions and constants, the declared type otherwise),
+    ///   and errors in expressions which used a failed definition are not reported, since they may
+    ///   only be a consequence of it.  Returns each error with the index of the top-level
+    ///   expression it came from.
+    /// Contract deployment must use `run`: this is for reporting errors to developers.
+    pub fn run_collecting_errors(&mut self, contract_analysis: &ContractAnalysis) -> Vec<(usize, CheckError)> {
+        if let Err(e) = self.charge_analysis_storage(contract_analysis) {
+            return vec![(0, e)];
+        }
+
+        let mut errors = vec![];
+        let mut local_context = TypingContext::new();
+
+        for (index, exp) in contract_analysis.expressions.iter().enumerate() {
+            self.used_failed_definition = false;
+            if let Err(error) = self.type_check_top_level(exp, &mut local_context) {
+                // a function define which failed part-way may not have reset its return tracker.
+                self.function_return_tracker = None;
+
+                if is_cost_error(&error.err) {
+                    errors.push((index, error));
+                    break;
+                }
+
+                let is_consequence = self.used_failed_definition || self.references_failed_definition(&error);
+                if let Some(name) = self.bind_placeholder(exp) {
+                    self.failed_definitions.insert(name);
+                }
+                if !is_consequence {
+                    errors.push((index, error));
+                }
+            }
+        }
+        errors
+    }
+
+    fn references_failed_definition(&self, error: &CheckError) -> bool {
+        let mut found = false;
+        if let Some(ref expressions) = error.expressions {
+            for expression in expressions.iter() {
+                let _ = depth_traverse::<_, _, ()>(expression, |x| {
+                    match x.expr {
+                        Atom(ref name) | TraitReference(ref name, _) if self.failed_definitions.contains(name) => {
+                            found = true;
+                        },
+                        _ => {}
+                    }
+                    Ok(())
+                });
+            }
+        }
+        found
+    }
+
+    /// Binds a placeholder for a top-level definition which failed to check, returning the name
+    ///   it defines.  If no placeholder can be bound (say, its type signature is invalid), uses
+    ///   of the name will fail to check, which is reported as a consequence of the failure.
+    fn bind_placeholder(&mut self, expression: &SymbolicExpression) -> Option<ClarityName> {
+        use vm::functions::define::DefineFunctionsParsed::*;
+
+        let define_type = DefineFunctionsParsed::try_parse(expression).ok()??;
+        let name = match define_type {
+            Constant { name, .. } | Map { name, .. } | PersistedVariable { name, .. } |
+            BoundedFungibleToken { name, .. } | UnboundedFungibleToken { name } |
+            NonFungibleToken { name, .. } | Trait { name, .. } | UseTrait { name, .. } => name.clone(),
+            PrivateFunction { signature, .. } | PublicFunction { signature, .. } | ReadOnlyFunction { signature, .. } => {
+                signature.first()?.match_atom()?.clone()
+            },
+            ImplTrait { .. } => return None
+        };
+
+        if self.contract_context.check_name_used(&name).is_err() {
+            // already bound, i.e., the definition failed because its name was taken.
+            return None;
+        }
+
+        // if binding fails, that is handled as if the type signature were invalid.
+        let _ = match define_type {
+            Constant { .. } => self.contract_context.add_variable_type(name.clone(), no_type()),
+            PrivateFunction { signature, .. } =>
+                self.contract_context.add_private_function_type(name.clone(), placeholder_function_type(signature)),
+            PublicFunction { signature, .. } =>
+                self.contract_context.add_public_function_type(name.clone(), placeholder_function_type(signature)),
+            ReadOnlyFunction { signature, .. } =>
+                self.contract_context.add_read_only_function_type(name.clone(), placeholder_function_type(signature)),
+            Map { key_type, value_type, .. } => {
+                match (TupleTypeSignature::parse_name_type_pair_list::<()>(key_type, &mut ()),
+                       TupleTypeSignature::parse_name_type_pair_list::<()>(value_type, &mut ())) {
+                    (Ok(key_type), Ok(value_type)) =>
+                        self.contract_context.add_map_type(name.clone(), (key_type.into(), value_type.into())),
+                    _ => Ok(())
+                }
+            },
+            PersistedVariable { data_type, .. } => {
+                match TypeSignature::parse_type_repr::<()>(data_type, &mut ()) {
+                    Ok(data_type) => self.contract_context.add_persisted_variable_type(name.clone(), data_type),
+                    Err(_) => Ok(())
+                }
+            },
+            BoundedFungibleToken { .. } | UnboundedFungibleToken { .. } => self.contract_context.add_ft(name.clone()),
+            NonFungibleToken { nft_type, .. } => {
+                match TypeSignature::parse_type_repr::<()>(nft_type, &mut ()) {
+                    Ok(nft_type) => self.contract_context.add_nft(name.clone(), nft_type),
+                    Err(_) => Ok(())
+                }
+            },
+            Trait { .. } | UseTrait { .. } | ImplTrait { .. } => Ok(())
+        };
+
+        Some(name)
+    }
+
+    fn charge_analysis_storage(&mut self, contract_analysis: &ContractAnalysis) -> CheckResult<()> {
         // charge for the eventual storage cost of the analysis --
         //  it is linear in the size of the AST.
         let mut size: u64 = 0;
@@ -258,22 +421,21 @@ impl <'a, 'b> TypeChecker <'a, 'b> {
         }
 
         runtime_cost!(cost_functions::ANALYSIS_STORAGE, self, size)?;
+        Ok(())
+    }
 
-        let mut local_context = TypingContext::new();
-
-        for exp in contract_analysis.expressions.iter() {
-            let mut result_res = self.try_type_check_define(&exp, &mut local_context);
-            if let Err(ref mut error) = result_res {
-                if !error.has_expression() {
-                    error.set_expression(&exp);
-                }
-            }
-            let result = result_res?;
-            if result.is_none() {
-                // was _not_ a define statement, so handle like a normal statement.
-                self.type_check(&exp, &local_context)?;
+    fn type_check_top_level(&mut self, exp: &SymbolicExpression, local_context: &mut TypingContext) -> CheckResult<()> {
+        let mut result_res = self.try_type_check_define(exp, local_context);
+        if let Err(ref mut error) = result_res {
+            if !error.has_expression() {
+                error.set_expression(exp);
             }
         }
+        let result = result_res?;
+        if result.is_none() {
+            // was _not_ a define statement, so handle like a normal statement.
+            self.type_check(exp, local_context)?;
+        }
         Ok(())
     }
 
@@ -437,6 +599,9 @@ impl <'a, 'b> TypeChecker <'a, 'b> {
         if let Some(type_result) = self.try_native_function_check(function_name, args, context) {
             type_result
         } else {
+            if self.failed_definitions.contains(function_name) {
+                self.used_failed_definition = true;
+            }
             let function = match self.get_function_type(function_name) {
                 Some(FunctionType::Fixed(function)) => Ok(function),
                 _ => Err(CheckErrors::UnknownFunction(function_name.to_string()))
@@ -456,6 +621,9 @@ impl <'a, 'b> TypeChecker <'a, 'b> {
         if let Some(type_result) = type_reserved_variable(name) {
             Ok(type_result)
         } else if let Some(type_result) = self.contract_context.get_variable_type(name) {
+            if self.failed_definitions.contains(name) {
+                self.used_failed_definition = true;
+            }
             Ok(type_result.clone())
         } else if let Some(type_result) = context.lookup_trait_reference_type(name) {
             Ok(TypeSignature::TraitReferenceType(type_result.clone()))
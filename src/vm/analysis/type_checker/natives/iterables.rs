@@ -2,7 +2,7 @@ use vm::functions::NativeFunctions;
 use vm::representations::{SymbolicExpression, SymbolicExpressionType};
 use vm::types::{ TypeSignature, FunctionType };
 use vm::types::{Value, MAX_VALUE_SIZE};
-pub use vm::types::signatures::{ListTypeData, BufferLength};
+pub use vm::types::signatures::{ListTypeData, BufferLength, StringSubtype};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 
@@ -50,6 +50,11 @@ pub fn check_special_map(checker: &mut TypeChecker, args: &[SymbolicExpression],
             TypeSignature::list_of(mapped_type, buffer_data.into())
                 .map_err(|_| CheckErrors::ConstructedListTooLarge.into())
         },
+        TypeSignature::StringType(string_data) => {
+            let mapped_type = function_type.check_args(checker, &[TypeSignature::min_string(&string_data)])?;
+            TypeSignature::list_of(mapped_type, string_data.get_max_len())
+                .map_err(|_| CheckErrors::ConstructedListTooLarge.into())
+        },
         _ => Err(CheckErrors::ExpectedListOrBuffer(argument_type).into())
     }
 }
@@ -70,6 +75,7 @@ pub fn check_special_filter(checker: &mut TypeChecker, args: &[SymbolicExpressio
         let input_type = match argument_type {
             TypeSignature::ListType(ref list_data) => Ok(list_data.clone().destruct().0),
             TypeSignature::BufferType(_) => Ok(TypeSignature::min_buffer()),
+            TypeSignature::StringType(ref string_data) => Ok(TypeSignature::min_string(string_data)),
             _ => Err(CheckErrors::ExpectedListOrBuffer(argument_type.clone()))
         }?;
     
@@ -98,6 +104,7 @@ pub fn check_special_fold(checker: &mut TypeChecker, args: &[SymbolicExpression]
     let input_type = match argument_type {
         TypeSignature::ListType(list_data) => Ok(list_data.destruct().0),
         TypeSignature::BufferType(_) => Ok(TypeSignature::min_buffer()),
+        TypeSignature::StringType(ref string_data) => Ok(TypeSignature::min_string(string_data)),
         _ => Err(CheckErrors::ExpectedListOrBuffer(argument_type))
     }?;
 
@@ -151,6 +158,17 @@ pub fn check_special_concat(checker: &mut TypeChecker, args: &[SymbolicExpressio
                 return Err(CheckErrors::TypeError(rhs_type.clone(), TypeSignature::max_buffer()).into());
             }
         },
+        TypeSignature::StringType(lhs_string) => {
+            match rhs_type {
+                TypeSignature::StringType(ref rhs_string) if lhs_string.is_same_kind(rhs_string) => {
+                    let size = lhs_string.get_max_len().checked_add(rhs_string.get_max_len())
+                        .ok_or(CheckErrors::MaxLengthOverflow)?;
+                    let return_type = TypeSignature::StringType(lhs_string.with_max_len(size)?);
+                    Ok(return_type)
+                },
+                _ => Err(CheckErrors::TypeError(TypeSignature::StringType(lhs_string), rhs_type.clone()).into())
+            }
+        },
         _ => Err(CheckErrors::ExpectedListOrBuffer(lhs_type.clone()).into())
     }
 }
@@ -174,6 +192,20 @@ pub fn check_special_append(checker: &mut TypeChecker, args: &[SymbolicExpressio
             let return_type = TypeSignature::list_of(list_entry_type, new_len)?;
             return Ok(return_type);
         },
+        TypeSignature::StringType(lhs_string) => {
+            let rhs_type = checker.type_check(&args[1], context)?;
+            let char_type = TypeSignature::min_string(&lhs_string);
+
+            analysis_typecheck_cost(checker, &char_type, &rhs_type)?;
+
+            // strings are appended with a single character of the same kind.
+            if !char_type.admits_type(&rhs_type) {
+                return Err(CheckErrors::TypeError(char_type, rhs_type).into());
+            }
+            let new_len = lhs_string.get_max_len().checked_add(1)
+                .ok_or(CheckErrors::MaxLengthOverflow)?;
+            Ok(TypeSignature::StringType(lhs_string.with_max_len(new_len)?))
+        },
         _ => Err(CheckErrors::ExpectedListApplication.into())
     }
 }
@@ -206,6 +238,10 @@ pub fn check_special_as_max_len(checker: &mut TypeChecker, args: &[SymbolicExpre
         TypeSignature::BufferType(_) => {
             Ok(TypeSignature::OptionalType(Box::new(TypeSignature::BufferType(BufferLength::try_from(expected_len).unwrap()))))
         },
+        TypeSignature::StringType(string_data) => {
+            let resized_string = string_data.with_max_len(expected_len)?;
+            Ok(TypeSignature::OptionalType(Box::new(TypeSignature::StringType(resized_string))))
+        },
         _ => Err(CheckErrors::ExpectedListOrBuffer(iterable).into())
     }
 }
@@ -217,9 +253,70 @@ pub fn check_special_len(checker: &mut TypeChecker, args: &[SymbolicExpression],
     runtime_cost!(cost_functions::ANALYSIS_ITERABLE_FUNC, checker, 1)?;
 
     match collection_type {
-        TypeSignature::ListType(_) | TypeSignature::BufferType(_) => Ok(()),
+        TypeSignature::ListType(_) | TypeSignature::BufferType(_) | TypeSignature::StringType(_) => Ok(()),
         _ => Err(CheckErrors::ExpectedListOrBuffer(collection_type.clone()))
     }?;
 
     Ok(TypeSignature::UIntType)
 }
+
+/// The type of a single element of a list, buffer or string.
+fn get_iterable_item_type(iterable: &TypeSignature) -> CheckResult<TypeSignature> {
+    match iterable {
+        TypeSignature::ListType(ref list_data) => Ok(list_data.clone().destruct().0),
+        TypeSignature::BufferType(_) => Ok(TypeSignature::min_buffer()),
+        TypeSignature::StringType(ref string_data) => Ok(TypeSignature::min_string(string_data)),
+        _ => Err(CheckErrors::ExpectedListOrBuffer(iterable.clone()).into())
+    }
+}
+
+pub fn check_special_element_at(checker: &mut TypeChecker, args: &[SymbolicExpression], context: &TypingContext) -> TypeResult {
+    check_argument_count(2, args)?;
+
+    let iterable = checker.type_check(&args[0], context)?;
+    checker.type_check_expects(&args[1], context, &TypeSignature::UIntType)?;
+
+    runtime_cost!(cost_functions::ANALYSIS_ITERABLE_FUNC, checker, 1)?;
+
+    let item_type = get_iterable_item_type(&iterable)?;
+    TypeSignature::new_option(item_type)
+        .map_err(|e| e.into())
+}
+
+pub fn check_special_index_of(checker: &mut TypeChecker, args: &[SymbolicExpression], context: &TypingContext) -> TypeResult {
+    check_argument_count(2, args)?;
+
+    let iterable = checker.type_check(&args[0], context)?;
+    let item_type = get_iterable_item_type(&iterable)?;
+    let searched_type = checker.type_check(&args[1], context)?;
+
+    runtime_cost!(cost_functions::ANALYSIS_ITERABLE_FUNC, checker, 1)?;
+    analysis_typecheck_cost(checker, &item_type, &searched_type)?;
+
+    // the searched item must be comparable with the iterable's items,
+    //   in the same sense as `is-eq`.
+    TypeSignature::least_supertype(&item_type, &searched_type)
+        .map_err(|_| CheckErrors::TypeError(item_type, searched_type))?;
+
+    Ok(TypeSignature::OptionalType(Box::new(TypeSignature::UIntType)))
+}
+
+pub fn check_special_slice(checker: &mut TypeChecker, args: &[SymbolicExpression], context: &TypingContext) -> TypeResult {
+    check_argument_count(3, args)?;
+
+    let iterable = checker.type_check(&args[0], context)?;
+    checker.type_check_expects(&args[1], context, &TypeSignature::UIntType)?;
+    checker.type_check_expects(&args[2], context, &TypeSignature::UIntType)?;
+
+    runtime_cost!(cost_functions::ANALYSIS_ITERABLE_FUNC, checker, 1)?;
+
+    // a slice is bounded by the max length of the sliced iterable,
+    //   so its type is unchanged.
+    match iterable {
+        TypeSignature::ListType(_) | TypeSignature::BufferType(_) | TypeSignature::StringType(_) => {
+            TypeSignature::new_option(iterable)
+                .map_err(|e| e.into())
+        },
+        _ => Err(CheckErrors::ExpectedListOrBuffer(iterable).into())
+    }
+}
@@ -1,7 +1,7 @@
 use vm::errors::{Error as InterpError, RuntimeErrorType};
 use vm::functions::{NativeFunctions, handle_binding_list};
 use vm::{ClarityName, SymbolicExpression, SymbolicExpressionType};
-use vm::types::{BUFF_32, BUFF_20, BUFF_64, TypeSignature, TupleTypeSignature,
+use vm::types::{BUFF_65, BUFF_33, BUFF_32, BUFF_20, BUFF_64, TypeSignature, TupleTypeSignature,
                 BlockInfoProperty, Value, PrincipalData, MAX_VALUE_SIZE, FunctionArg,
                 FunctionType, FixedFunction, FunctionSignature};
 use super::{TypeChecker, TypingContext, TypeResult, no_type, check_argument_count,
@@ -371,6 +371,39 @@ impl TypedNativeFunction {
                          TypeSignature::UIntType,
                          TypeSignature::IntType],
                     BUFF_32.clone()))),
+            Secp256k1Recover =>
+                Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
+                    args: vec![
+                        FunctionArg::new(BUFF_32.clone(),
+                                         ClarityName::try_from("message-hash".to_owned())
+                                         .expect("FAIL: ClarityName failed to accept default arg name")),
+                        FunctionArg::new(BUFF_65.clone(),
+                                         ClarityName::try_from("signature".to_owned())
+                                         .expect("FAIL: ClarityName failed to accept default arg name")),
+                    ],
+                    returns: TypeSignature::new_response(BUFF_33.clone(), TypeSignature::UIntType).unwrap() }))),
+            Secp256k1Verify =>
+                Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
+                    args: vec![
+                        FunctionArg::new(BUFF_32.clone(),
+                                         ClarityName::try_from("message-hash".to_owned())
+                                         .expect("FAIL: ClarityName failed to accept default arg name")),
+                        FunctionArg::new(BUFF_65.clone(),
+                                         ClarityName::try_from("signature".to_owned())
+                                         .expect("FAIL: ClarityName failed to accept default arg name")),
+                        FunctionArg::new(BUFF_33.clone(),
+                                         ClarityName::try_from("public-key".to_owned())
+                                         .expect("FAIL: ClarityName failed to accept default arg name")),
+                    ],
+                    returns: TypeSignature::BoolType }))),
+            PrincipalOf =>
+                Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
+                    args: vec![
+                        FunctionArg::new(BUFF_33.clone(),
+                                         ClarityName::try_from("public-key".to_owned())
+                                         .expect("FAIL: ClarityName failed to accept default arg name")),
+                    ],
+                    returns: TypeSignature::new_response(TypeSignature::PrincipalType, TypeSignature::UIntType).unwrap() }))),
             GetStxBalance =>
                 Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
                     args: vec![
@@ -410,6 +443,9 @@ impl TypedNativeFunction {
             TransferAsset => Special(SpecialNativeFunction(&assets::check_special_transfer_asset)),
             MintAsset => Special(SpecialNativeFunction(&assets::check_special_mint_asset)),
             MintToken => Special(SpecialNativeFunction(&assets::check_special_mint_token)),
+            BurnAsset => Special(SpecialNativeFunction(&assets::check_special_burn_asset)),
+            BurnToken => Special(SpecialNativeFunction(&assets::check_special_burn_token)),
+            GetTokenSupply => Special(SpecialNativeFunction(&assets::check_special_get_token_supply)),
             Equals => Special(SpecialNativeFunction(&check_special_equals)),
             If => Special(SpecialNativeFunction(&check_special_if)),
             Let => Special(SpecialNativeFunction(&check_special_let)),
@@ -422,6 +458,9 @@ impl TypedNativeFunction {
             Concat => Special(SpecialNativeFunction(&iterables::check_special_concat)),
             AsMaxLen => Special(SpecialNativeFunction(&iterables::check_special_as_max_len)),
             Len => Special(SpecialNativeFunction(&iterables::check_special_len)),
+            ElementAt => Special(SpecialNativeFunction(&iterables::check_special_element_at)),
+            IndexOf => Special(SpecialNativeFunction(&iterables::check_special_index_of)),
+            Slice => Special(SpecialNativeFunction(&iterables::check_special_slice)),
             ListCons => Special(SpecialNativeFunction(&check_special_list_cons)),
             FetchEntry => Special(SpecialNativeFunction(&maps::check_special_fetch_entry)),
             SetEntry => Special(SpecialNativeFunction(&maps::check_special_set_entry)),
@@ -14,8 +14,14 @@ const FIRST_CLASS_TOKENS: &str = "(define-fungible-token stackaroos)
          (nft-get-owner? stacka-nfts \"1234567890\" )
          (define-read-only (my-ft-get-balance (account principal))
             (ft-get-balance stackaroos account))
+         (define-read-only (my-ft-get-supply)
+            (ft-get-supply stackaroos))
          (define-public (my-token-transfer (to principal) (amount uint))
             (ft-transfer? stackaroos amount tx-sender to))
+         (define-public (my-token-burn (amount uint))
+            (ft-burn? stackaroos amount tx-sender))
+         (define-public (my-nft-burn (id (buff 10)))
+            (nft-burn? stacka-nfts id tx-sender))
          (define-public (faucet)
            (let ((original-sender tx-sender))
              (as-contract (ft-transfer? stackaroos u1 tx-sender original-sender))))
@@ -130,6 +136,14 @@ fn test_bad_asset_usage() {
                        "(define-non-fungible-token stackaroos integer)",
                        "(ft-mint? stackaroos 100 tx-sender)",
                        "(ft-transfer? stackaroos 1 tx-sender tx-sender)",
+                       "(ft-burn? stackoos u1 tx-sender)",
+                       "(ft-burn? stackaroos 1 tx-sender)",
+                       "(ft-burn? stackaroos u1 u100)",
+                       "(nft-burn? stackoos \"abc\" tx-sender)",
+                       "(nft-burn? stacka-nfts u1234 tx-sender)",
+                       "(nft-burn? stacka-nfts \"abc\" u2)",
+                       "(ft-get-supply stackoos)",
+                       "(ft-get-supply u1234)",
     ];
 
     let expected = [
@@ -183,6 +197,18 @@ fn test_bad_asset_usage() {
                                TypeSignature::IntType),
         CheckErrors::TypeError(TypeSignature::UIntType,
                                TypeSignature::IntType),
+        CheckErrors::NoSuchFT("stackoos".to_string()),
+        CheckErrors::TypeError(TypeSignature::UIntType,
+                               TypeSignature::IntType),
+        CheckErrors::TypeError(TypeSignature::PrincipalType,
+                               TypeSignature::UIntType),
+        CheckErrors::NoSuchNFT("stackoos".to_string()),
+        CheckErrors::TypeError(buff_type(10),
+                               TypeSignature::UIntType),
+        CheckErrors::TypeError(TypeSignature::PrincipalType,
+                               TypeSignature::UIntType),
+        CheckErrors::NoSuchFT("stackoos".to_string()),
+        CheckErrors::BadTokenName,
     ];
 
     for (script, expected_err) in bad_scripts.iter().zip(expected.iter()) {
@@ -3,11 +3,11 @@ use serde_json;
 
 use vm::ast::parse;
 use vm::analysis::errors::CheckErrors;
-use vm::analysis::{AnalysisDatabase, contract_interface_builder::build_contract_interface};
+use vm::analysis::{AnalysisDatabase, contract_interface_builder::{build_contract_interface, ContractInterfaceAtomType}};
 use vm::database::MemoryBackingStore;
 use vm::analysis::mem_type_check;
 use vm::analysis::type_check;
-use vm::types::QualifiedContractIdentifier;
+use vm::types::{QualifiedContractIdentifier, FunctionType};
 
 const SIMPLE_TOKENS: &str =
         "(define-map tokens ((account principal)) ((balance uint)))
@@ -354,6 +354,59 @@ fn test_names_tokens_contracts_interface() {
 }
 
 
+#[test]
+fn test_string_contracts_interface() {
+    const STRING_TEST_CONTRACT: &str = "
+        (define-data-var name (string-ascii 10) a\"blockstack\")
+        (define-read-only (greet (who (string-utf8 20))) (concat u\"hi \" who))";
+
+    let contract_analysis = mem_type_check(STRING_TEST_CONTRACT).unwrap().1;
+    let test_contract_json_str = build_contract_interface(&contract_analysis).serialize();
+    let test_contract_json = serde_json::from_str(&test_contract_json_str).unwrap();
+
+    let test_contract_json_expected = serde_json::from_str(r#"{
+        "functions": [
+            { "name": "greet",
+                "access": "read_only",
+                "args": [
+                    { "name": "who", "type": { "string-utf8": { "length": 20 } } }
+                ],
+                "outputs": { "type": { "string-utf8": { "length": 23 } } }
+            }
+        ],
+        "maps": [],
+        "variables": [
+            { "name": "name", "access": "variable", "type": { "string-ascii": { "length": 10 } } }
+        ],
+        "fungible_tokens": [],
+        "non_fungible_tokens": []
+    }"#).unwrap();
+
+    assert_json_eq!(test_contract_json, test_contract_json_expected);
+}
+
+#[test]
+fn test_contract_interface_to_type_signature() {
+    const SIGNATURES_TEST_CONTRACT: &str = "
+        (define-private (f00 (a1 int) (a2 uint) (a3 bool) (a4 principal)) true)
+        (define-private (f01 (a1 (buff 5)) (a2 (string-ascii 10)) (a3 (string-utf8 20))) true)
+        (define-private (f02 (a1 (optional int)) (a2 (response uint bool)) (a3 (list 5 (buff 3)))) true)
+        (define-private (f03 (a1 (tuple (n1 int) (n2 (list 2 (optional principal)))))) true)";
+
+    let contract_analysis = mem_type_check(SIGNATURES_TEST_CONTRACT).unwrap().1;
+
+    // the interface's types convert back to the signatures they came from
+    for function_type in contract_analysis.private_function_types.values() {
+        if let FunctionType::Fixed(function) = function_type {
+            for arg in function.args.iter() {
+                assert_eq!(ContractInterfaceAtomType::from_type_signature(&arg.signature).to_type_signature(),
+                           Some(arg.signature.clone()));
+            }
+        }
+    }
+    assert_eq!(ContractInterfaceAtomType::trait_reference.to_type_signature(), None);
+}
+
 #[test]
 fn test_names_tokens_contracts() {
     let tokens_contract_id = QualifiedContractIdentifier::local("tokens").unwrap();
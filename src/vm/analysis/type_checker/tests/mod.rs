@@ -404,6 +404,37 @@ fn test_simple_hash_checks() {
     }
 }
 
+#[test]
+fn test_secp256k1_checks() {
+    let good = ["(secp256k1-recover? (sha256 u1) 0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e)",
+                "(secp256k1-verify (sha256 u1) 0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
+                "(principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
+                "(principal-of? (unwrap-panic (secp256k1-recover? (sha256 u1) 0x01)))"];
+    let expected = ["(response (buff 33) uint)", "bool", "(response principal uint)", "(response principal uint)"];
+
+    let bad = ["(secp256k1-recover? (sha512 u1) 0x01)",
+               "(secp256k1-recover? (sha256 u1))",
+               "(secp256k1-verify (sha256 u1) 0x01 u1)",
+               "(secp256k1-verify (sha256 u1) 0x01)",
+               "(principal-of? (sha512 u1))",
+               "(principal-of? 'ST1AW6EKPGT61SQ9FNVDS17RKNWT8ZP582VF9HSCP)"];
+    let bad_expected = [
+        CheckErrors::TypeError(BUFF_32.clone(), BUFF_64.clone()),
+        CheckErrors::IncorrectArgumentCount(2, 1),
+        CheckErrors::TypeError(buff_type(33), UIntType),
+        CheckErrors::IncorrectArgumentCount(3, 2),
+        CheckErrors::TypeError(buff_type(33), BUFF_64.clone()),
+        CheckErrors::TypeError(buff_type(33), PrincipalType)];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(expected, &format!("{}", type_check_helper(&good_test).unwrap()));
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_simple_ifs() {
     let good = ["(if (> 1 2) (+ 1 2 3) (- 1 2))",
@@ -683,6 +714,61 @@ fn test_buff_as_max_len() {
     }
 }
 
+#[test]
+fn test_element_at_index_of_slice() {
+    let good = [
+        "(element-at (list 1 2 3) u1)",
+        "(element-at 0x010203 u1)",
+        "(element-at a\"abc\" u1)",
+        "(index-of (list 1 2 3) 2)",
+        "(index-of 0x010203 0x02)",
+        "(index-of u\"abc\" u\"b\")",
+        "(index-of (list) 1)",
+        "(slice? (list 1 2 3) u1 u2)",
+        "(slice? 0x010203 u1 u2)",
+        "(slice? a\"abc\" u1 u2)"];
+    let expected = [
+        "(optional int)",
+        "(optional (buff 1))",
+        "(optional (string-ascii 1))",
+        "(optional uint)",
+        "(optional uint)",
+        "(optional uint)",
+        "(optional uint)",
+        "(optional (list 3 int))",
+        "(optional (buff 3))",
+        "(optional (string-ascii 3))"];
+
+    let bad = [
+        "(element-at (list 1 2 3) 1)",
+        "(element-at 1 u1)",
+        "(element-at (list 1 2 3))",
+        "(index-of (list 1 2 3) u1)",
+        "(index-of 0x010203 a\"a\")",
+        "(index-of 1 1)",
+        "(slice? (list 1 2 3) u1 2)",
+        "(slice? 1 u1 u2)",
+        "(slice? (list 1 2 3) u1)"];
+    let bad_expected = [
+        CheckErrors::TypeError(UIntType, IntType),
+        CheckErrors::ExpectedListOrBuffer(IntType),
+        CheckErrors::IncorrectArgumentCount(2, 1),
+        CheckErrors::TypeError(IntType, UIntType),
+        CheckErrors::TypeError(buff_type(1), TypeSignature::from("(string-ascii 1)")),
+        CheckErrors::ExpectedListOrBuffer(IntType),
+        CheckErrors::TypeError(UIntType, IntType),
+        CheckErrors::ExpectedListOrBuffer(IntType),
+        CheckErrors::IncorrectArgumentCount(3, 2)];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(expected, &format!("{}", type_check_helper(&good_test).unwrap()));
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_native_append() {
     let good = [
@@ -777,6 +863,55 @@ fn test_buff_filter() {
     }
 }
 
+#[test]
+fn test_strings() {
+    let good = [
+        "(concat a\"123\" a\"58\")",
+        "(concat u\"123\" u\"\\u{1F600}\")",
+        "(append a\"123\" a\"5\")",
+        "(len u\"blockstack\")",
+        "(as-max-len? a\"12345\" u8)",
+        "(as-max-len? u\"12345\" u4)",
+        "(if true a\"abcdef\" a\"abc\")",
+        "(if true u\"a\" u\"abcdef\")",
+        "(define-private (f (e (string-ascii 1))) (is-eq e a\"1\"))
+        (filter f a\"101010\")",
+        "(define-private (f (e (string-utf8 1))) (is-eq e u\"1\"))
+        (map f u\"101010\")",
+        "(define-private (get-len (x (string-utf8 1)) (acc uint)) (+ acc u1))
+        (fold get-len u\"101010\" u0)"];
+    let expected = [
+        "(string-ascii 5)",
+        "(string-utf8 4)",
+        "(string-ascii 4)",
+        "uint",
+        "(optional (string-ascii 8))",
+        "(optional (string-utf8 4))",
+        "(string-ascii 6)",
+        "(string-utf8 6)",
+        "(string-ascii 6)",
+        "(list 6 bool)",
+        "uint"];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        let type_sig = mem_type_check(good_test).unwrap().0.unwrap();
+        assert_eq!(expected, &type_sig.to_string());
+    }
+
+    let bad = [
+        "(concat a\"123\" u\"58\")",
+        "(concat a\"123\" 0x3538)",
+        "(append a\"123\" a\"58\")",
+        "(append a\"123\" u\"5\")",
+        "(if true a\"a\" u\"a\")",
+        "(define-data-var name (string-ascii 3) a\"abcd\")",
+        "(define-data-var name (string-utf8 3) a\"abc\")"];
+
+    for bad_test in bad.iter() {
+        mem_type_check(bad_test).unwrap_err();
+    }
+}
+
 #[test]
 fn test_lists_in_defines() {
     let good = "
@@ -24,6 +24,7 @@ pub enum ParseErrors {
     UnknownQuotedValue(String),
     FailedParsingIntValue(String),
     FailedParsingBuffer(String),
+    FailedParsingString(String),
     FailedParsingHexValue(String, String),
     FailedParsingPrincipal(String),
     FailedParsingField(String),
@@ -136,6 +137,7 @@ impl DiagnosableError for ParseErrors {
             ParseErrors::FailedParsingHexValue(value, x) => format!("Invalid hex-string literal {}: {}", value, x),
             ParseErrors::FailedParsingPrincipal(value) => format!("Invalid principal literal: {}", value),
             ParseErrors::FailedParsingBuffer(value) => format!("Invalid buffer literal: {}", value),
+            ParseErrors::FailedParsingString(value) => format!("Invalid string literal: {}", value),
             ParseErrors::FailedParsingField(value) => format!("Invalid field literal: {}", value),
             ParseErrors::FailedParsingRemainder(remainder) => format!("Failed to lex input remainder: '{}'", remainder),
             ParseErrors::ClosingParenthesisUnexpected => format!("Tried to close list which isn't open."),
@@ -0,0 +1,471 @@
+use vm::ast::errors::ParseResult;
+use vm::ast::parser::{self, LexItem};
+
+/// Forms are broken over multiple lines when they would not fit in this width
+pub const MAX_LINE_WIDTH: usize = 80;
+
+const INDENT: usize = 2;
+
+enum Node {
+    Atom(String),
+    List(Vec<Node>),
+    Tuple(Vec<Node>),
+    /// a comment on its own line
+    Comment(String),
+    /// a comment on the same line as the code before it
+    TrailingComment(String),
+    /// one or more blank lines between two items
+    BlankLine,
+}
+
+/// Lists whose items are data rather than a function application: a function signature, or
+///   a `let` binding list.
+#[derive(Clone, Copy, PartialEq)]
+enum ListKind {
+    Application,
+    Data,
+}
+
+fn build_nodes(input: &str) -> ParseResult<Vec<Node>> {
+    // run the real parser first: everything below can then assume the source is well-formed.
+    parser::parse(input)?;
+    let lexed = parser::lex_with_source(input)?;
+
+    let mut stack: Vec<Vec<Node>> = vec![vec![]];
+    // line numbers are counted here rather than taken from the lexer, which only
+    //   advances its line count by one per item.
+    let mut line = 1;
+    // the line of the last item that was not whitespace
+    let mut last_line = 0;
+
+    for (item, source, _line, _column) in lexed.into_iter() {
+        if let LexItem::Whitespace = item {
+            line += source.matches('\n').count();
+            continue;
+        }
+
+        {
+            let current = stack.last_mut().expect("BUG: formatter stack empty");
+            if last_line > 0 && line > last_line + 1 && !current.is_empty() {
+                current.push(Node::BlankLine);
+            }
+        }
+
+        match item {
+            LexItem::LeftParen | LexItem::LeftCurly => stack.push(vec![]),
+            LexItem::RightParen | LexItem::RightCurly => {
+                let items = stack.pop().expect("BUG: formatter stack empty");
+                let node = match item {
+                    LexItem::RightParen => Node::List(items),
+                    _ => Node::Tuple(items)
+                };
+                stack.last_mut().expect("BUG: formatter stack empty").push(node);
+            },
+            LexItem::ColonSeparator | LexItem::CommaSeparator => {},
+            LexItem::Comment(text) => {
+                let node = if line == last_line {
+                    Node::TrailingComment(text)
+                } else {
+                    Node::Comment(text)
+                };
+                stack.last_mut().expect("BUG: formatter stack empty").push(node);
+            },
+            _ => {
+                stack.last_mut().expect("BUG: formatter stack empty").push(Node::Atom(source.to_string()));
+            }
+        }
+
+        last_line = line;
+    }
+
+    Ok(stack.pop().expect("BUG: formatter stack empty"))
+}
+
+fn is_expression(node: &Node) -> bool {
+    match node {
+        Node::Atom(_) | Node::List(_) | Node::Tuple(_) => true,
+        _ => false
+    }
+}
+
+fn list_head(items: &[Node]) -> Option<&str> {
+    match items.iter().find(|item| is_expression(item)) {
+        Some(Node::Atom(name)) => Some(name),
+        _ => None
+    }
+}
+
+fn is_function_definition(items: &[Node]) -> bool {
+    match list_head(items) {
+        Some("define-public") | Some("define-private") | Some("define-read-only") => true,
+        _ => false
+    }
+}
+
+/// The form on a single line, or None if it contains comments
+fn flat(node: &Node) -> Option<String> {
+    match node {
+        Node::Atom(text) => Some(text.clone()),
+        Node::List(items) => {
+            let mut parts = vec![];
+            for item in items.iter() {
+                match item {
+                    Node::BlankLine => {},
+                    Node::Comment(_) | Node::TrailingComment(_) => return None,
+                    _ => parts.push(flat(item)?)
+                }
+            }
+            Some(format!("({})", parts.join(" ")))
+        },
+        Node::Tuple(items) => {
+            let mut parts = vec![];
+            for item in items.iter() {
+                match item {
+                    Node::BlankLine => {},
+                    Node::Comment(_) | Node::TrailingComment(_) => return None,
+                    _ => parts.push(flat(item)?)
+                }
+            }
+            let entries: Vec<_> = parts.chunks(2)
+                .map(|entry| entry.join(": "))
+                .collect();
+            Some(format!("{{{}}}", entries.join(", ")))
+        },
+        Node::Comment(_) | Node::TrailingComment(_) | Node::BlankLine => None
+    }
+}
+
+/// The column just past the end of `out`, which was written starting at `start_column`
+fn end_column(out: &str, start_column: usize) -> usize {
+    match out.rfind('\n') {
+        Some(ix) => out.len() - ix - 1,
+        None => start_column + out.len()
+    }
+}
+
+fn new_line(out: &mut String, column: usize, blank: bool) {
+    if blank {
+        out.push('\n');
+    }
+    out.push('\n');
+    out.push_str(&" ".repeat(column));
+}
+
+/// Render `node`, which starts at `column`.  Lines after the first are fully indented.
+fn render(node: &Node, column: usize, kind: ListKind) -> String {
+    let always_break = match node {
+        Node::List(items) => kind == ListKind::Application && is_function_definition(items),
+        _ => false
+    };
+    if !always_break {
+        if let Some(line) = flat(node) {
+            if column + line.len() <= MAX_LINE_WIDTH {
+                return line;
+            }
+        }
+    }
+
+    match node {
+        Node::List(items) => render_list(items, column, kind),
+        Node::Tuple(items) => render_tuple(items, column),
+        Node::Atom(text) | Node::Comment(text) | Node::TrailingComment(text) => text.clone(),
+        Node::BlankLine => String::new()
+    }
+}
+
+fn render_list(items: &[Node], column: usize, kind: ListKind) -> String {
+    let head = list_head(items);
+
+    // how many expressions go on the opening line, and where the rest are indented to
+    let (opening_count, body_column) = match (head, kind) {
+        (Some(head), ListKind::Data) => (2, column + 2 + head.len()),
+        (Some("begin"), _) => (1, column + INDENT),
+        (Some(_), _) => (2, column + INDENT),
+        (None, _) => (1, column + 1)
+    };
+
+    let argument_kind = |index: usize| {
+        match (head, index) {
+            (Some("let"), 1) => ListKind::Data,
+            (Some(_), 1) if is_function_definition(items) => ListKind::Data,
+            _ => ListKind::Application
+        }
+    };
+
+    // `match` binding names share a line with the branch they bind for:
+    //   (match value some-name some-branch none-branch), or
+    //   (match value ok-name ok-branch err-name err-branch)
+    let expression_count = items.iter().filter(|item| is_expression(item)).count();
+    let continues_line = |index: usize| {
+        match (head, expression_count, index) {
+            (Some("match"), 5, 3) | (Some("match"), 6, 3) | (Some("match"), 6, 5) => true,
+            _ => false
+        }
+    };
+
+    let mut out = String::from("(");
+    let mut placed = 0;
+    let mut on_opening_line = true;
+    let mut in_body = false;
+    let mut pending_blank = false;
+    let mut last_was_comment = false;
+
+    for item in items.iter() {
+        match item {
+            Node::BlankLine => {
+                if in_body {
+                    pending_blank = true;
+                }
+            },
+            Node::TrailingComment(text) => {
+                if !out.ends_with('(') {
+                    out.push(' ');
+                }
+                out.push_str(text);
+                on_opening_line = false;
+                last_was_comment = true;
+            },
+            Node::Comment(text) => {
+                new_line(&mut out, body_column, pending_blank);
+                out.push_str(text);
+                on_opening_line = false;
+                in_body = true;
+                pending_blank = false;
+                last_was_comment = true;
+            },
+            _ => {
+                if on_opening_line && placed < opening_count {
+                    if placed > 0 {
+                        out.push(' ');
+                    }
+                } else if continues_line(placed) && !last_was_comment {
+                    out.push(' ');
+                } else {
+                    new_line(&mut out, body_column, pending_blank);
+                    on_opening_line = false;
+                    in_body = true;
+                }
+                let start = end_column(&out, column);
+                out.push_str(&render(item, start, argument_kind(placed)));
+                placed += 1;
+                pending_blank = false;
+                last_was_comment = false;
+            }
+        }
+    }
+
+    if last_was_comment {
+        new_line(&mut out, column, false);
+    }
+    out.push(')');
+    out
+}
+
+fn render_tuple(items: &[Node], column: usize) -> String {
+    let entry_column = column + 1;
+    let entry_count = items.iter().filter(|item| is_expression(item)).count() / 2;
+
+    let mut out = String::from("{");
+    let mut placed = 0;
+    let mut pending_blank = false;
+    let mut last_was_comment = false;
+
+    for item in items.iter() {
+        match item {
+            Node::BlankLine => {
+                if placed > 0 {
+                    pending_blank = true;
+                }
+            },
+            Node::TrailingComment(text) => {
+                if !out.ends_with('{') {
+                    out.push(' ');
+                }
+                out.push_str(text);
+                last_was_comment = true;
+            },
+            Node::Comment(text) => {
+                new_line(&mut out, entry_column, pending_blank);
+                out.push_str(text);
+                pending_blank = false;
+                last_was_comment = true;
+            },
+            _ => {
+                let is_key = placed % 2 == 0;
+                if is_key {
+                    if placed > 0 || last_was_comment {
+                        new_line(&mut out, entry_column, pending_blank);
+                    }
+                } else if last_was_comment {
+                    new_line(&mut out, entry_column + INDENT, false);
+                } else {
+                    out.push(' ');
+                }
+                let start = end_column(&out, column);
+                out.push_str(&render(item, start, ListKind::Application));
+                if is_key {
+                    out.push(':');
+                } else if placed / 2 + 1 < entry_count {
+                    out.push(',');
+                }
+                placed += 1;
+                pending_blank = false;
+                last_was_comment = false;
+            }
+        }
+    }
+
+    if last_was_comment {
+        new_line(&mut out, column, false);
+    }
+    out.push('}');
+    out
+}
+
+/// Reformat Clarity source into its canonical layout.  Fails if the source does not parse.
+///   The formatter works from the lexer's output rather than from the AST, so that comments
+///   and the exact spelling of literals survive.
+///
+/// Forms are kept on one line whenever they fit within `MAX_LINE_WIDTH`.  Forms that do not
+///   fit are broken Lisp-style: the function name and its first argument stay on the opening
+///   line, the remaining arguments go one per line indented by two, and closing parens stack
+///   at the end of the last line.  A few forms get their own layouts:
+///
+///   - `define-public`, `define-private` and `define-read-only` always put their body on its
+///     own line, and a signature that does not fit aligns its arguments under the first one.
+///   - `let` aligns its bindings under the first binding.
+///   - `match` puts each binding name on the same line as the branch it binds for.
+///   - `begin` puts every statement on its own line.
+///   - tuples that do not fit put each `key: value` entry on its own line.
+///
+/// Blank lines between forms are kept (collapsed to one), as are comments.
+pub fn format_source(input: &str) -> ParseResult<String> {
+    let nodes = build_nodes(input)?;
+
+    let mut out = String::new();
+    let mut pending_blank = false;
+    for node in nodes.iter() {
+        match node {
+            Node::BlankLine => {
+                pending_blank = !out.is_empty();
+            },
+            Node::TrailingComment(text) => {
+                out.push(' ');
+                out.push_str(text);
+            },
+            _ => {
+                if !out.is_empty() {
+                    new_line(&mut out, 0, pending_blank);
+                }
+                out.push_str(&render(node, 0, ListKind::Application));
+                pending_blank = false;
+            }
+        }
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use vm::ast::parse;
+    use vm::types::QualifiedContractIdentifier;
+
+    fn parsed(input: &str) -> Vec<String> {
+        parse(&QualifiedContractIdentifier::transient(), input).unwrap()
+            .iter().map(|e| e.to_string()).collect()
+    }
+
+    fn assert_formats_to(input: &str, expected: &str) {
+        let formatted = format_source(input).unwrap();
+        assert_eq!(formatted, expected);
+        // formatting is idempotent
+        assert_eq!(format_source(&formatted).unwrap(), expected);
+        // and only ever changes whitespace
+        assert_eq!(parsed(input), parsed(&formatted));
+    }
+
+    #[test]
+    fn test_format_short_forms() {
+        assert_formats_to(
+            "(define-constant   owner    tx-sender)\n\n\n\n(define-map balances\n ((owner principal))\n ((balance uint)))\n",
+            "(define-constant owner tx-sender)\n\n(define-map balances ((owner principal)) ((balance uint)))\n");
+        assert_formats_to(
+            "(define-private (add (a int) (b int)) (+ a b))",
+            "(define-private (add (a int) (b int))\n  (+ a b))\n");
+        assert_formats_to(
+            "(map-get? balances {  owner:  tx-sender ,id : u1 })  ",
+            "(map-get? balances {owner: tx-sender, id: u1})\n");
+        assert_formats_to(
+            "(print \"a  \\\"quoted\\\"  string\") (print u\"\\u{2764}\") (print 0x0102)",
+            "(print \"a  \\\"quoted\\\"  string\")\n(print u\"\\u{2764}\")\n(print 0x0102)\n");
+    }
+
+    #[test]
+    fn test_format_long_forms() {
+        let input = "(define-public (transfer-with-a-long-name (recipient principal) (amount uint) (memo (buff 34)))
+(let ((sender tx-sender) (sender-balance (get-balance-of sender)) (recipient-balance (get-balance-of recipient)))
+(asserts! (>= sender-balance amount) (err u1))
+(match (ft-transfer? token amount sender recipient) transferred (ok {sender: sender, recipient: recipient, amount: amount, memo: memo}) error (err error))))";
+        let expected = "(define-public (transfer-with-a-long-name (recipient principal)
+                                          (amount uint)
+                                          (memo (buff 34)))
+  (let ((sender tx-sender)
+        (sender-balance (get-balance-of sender))
+        (recipient-balance (get-balance-of recipient)))
+    (asserts! (>= sender-balance amount) (err u1))
+    (match (ft-transfer? token amount sender recipient)
+      transferred (ok {sender: sender,
+                       recipient: recipient,
+                       amount: amount,
+                       memo: memo})
+      error (err error))))
+";
+        assert_formats_to(input, expected);
+
+        assert_formats_to(
+            "(begin (print \"the first statement in this block is quite long\") (print \"and so is the second\"))",
+            "(begin\n  (print \"the first statement in this block is quite long\")\n  (print \"and so is the second\"))\n");
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let input = ";; a token
+;;   with comments
+
+(define-data-var total uint u0) ;; running total
+(define-public (bump)
+  ;; bump the total
+  (begin
+
+    (var-set total (+ (var-get total) u1)) ;; add one
+    (ok {total: (var-get total), ;; the new total
+         by: u1})
+    ;; done
+    ))";
+        let expected = ";; a token
+;;   with comments
+
+(define-data-var total uint u0) ;; running total
+(define-public (bump)
+  ;; bump the total
+  (begin
+    (var-set total (+ (var-get total) u1)) ;; add one
+    (ok {total: (var-get total), ;; the new total
+         by: u1})
+    ;; done
+  ))
+";
+        assert_formats_to(input, expected);
+    }
+
+    #[test]
+    fn test_format_invalid_source() {
+        assert!(format_source("(define-constant a (+ 1 2)").is_err());
+        assert!(format_source("(list 1 2))").is_err());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod parser;
+pub mod formatter;
 pub mod expression_identifier;
 pub mod definition_sorter;
 pub mod traits_resolver;
@@ -24,15 +24,16 @@ pub enum LexItem {
     Variable(String),
     CommaSeparator,
     ColonSeparator,
-    Whitespace
+    Whitespace,
+    Comment(String)
 }
 
 #[derive(Debug)]
 enum TokenType {
-    Whitespace, Comma, Colon,
+    Whitespace, Comment, Comma, Colon,
     LParens, RParens,
     LCurly, RCurly,
-    StringLiteral, HexStringLiteral,
+    StringLiteral, StringASCIILiteral, StringUTF8Literal, HexStringLiteral,
     UIntLiteral, IntLiteral,
     Variable, TraitReferenceLiteral, PrincipalLiteral,
     SugaredContractIdentifierLiteral,
@@ -72,6 +73,59 @@ fn get_value_or_err(input: &str, captures: Captures) -> ParseResult<String> {
     Ok(input[matched.start()..matched.end()].to_string())
 }
 
+/// Unescapes the body of an `a"..."` or `u"..."` literal: `\"`, `\\`, `\n`, `\t` and `\r`,
+///   plus, in `u"..."` literals, `\u{XXXX}` for any unicode scalar value given in hex.
+fn unescape_string_literal(input: &str, allow_unicode_escapes: bool) -> Option<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('"') => {
+                output.push('"');
+                chars.next();
+            },
+            Some('\\') => {
+                output.push('\\');
+                chars.next();
+            },
+            Some('n') => {
+                output.push('\n');
+                chars.next();
+            },
+            Some('t') => {
+                output.push('\t');
+                chars.next();
+            },
+            Some('r') => {
+                output.push('\r');
+                chars.next();
+            },
+            Some('u') if allow_unicode_escapes => {
+                chars.next();
+                if chars.next() != Some('{') {
+                    return None;
+                }
+                let mut hex_digits = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(d) if d.is_ascii_hexdigit() && hex_digits.len() < 6 => hex_digits.push(d),
+                        _ => return None
+                    }
+                }
+                let scalar = u32::from_str_radix(&hex_digits, 16).ok()?;
+                output.push(std::char::from_u32(scalar)?);
+            },
+            _ => output.push(c)
+        }
+    }
+    Some(output)
+}
+
 fn get_lines_at(input: &str) -> Vec<usize> {
     let mut out: Vec<_> = input.match_indices("\n")
         .map(|(ix, _)| ix)
@@ -91,13 +145,24 @@ lazy_static! {
 }
 
 pub fn lex(input: &str) -> ParseResult<Vec<(LexItem, u32, u32)>> {
+    let lexed = lex_with_source(input)?;
+    Ok(lexed.into_iter()
+       .map(|(item, _source, line, column)| (item, line, column))
+       .collect())
+}
+
+/// Like `lex`, but also returns the source text each item was lexed from, which
+///   tools that rewrite source (i.e., the formatter) need to reproduce literals exactly.
+pub fn lex_with_source(input: &str) -> ParseResult<Vec<(LexItem, &str, u32, u32)>> {
     // Aaron: I'd like these to be static, but that'd require using
     //    lazy_static (or just hand implementing that), and I'm not convinced
     //    it's worth either (1) an extern macro, or (2) the complexity of hand implementing.
 
     let lex_matchers: &[LexMatcher] = &[
+        LexMatcher::new(r##"u"(?P<value>((\\")|([[ -~]&&[^"]]))*)""##, TokenType::StringUTF8Literal),
+        LexMatcher::new(r##"a"(?P<value>((\\")|([[ -~]&&[^"]]))*)""##, TokenType::StringASCIILiteral),
         LexMatcher::new(r##""(?P<value>((\\")|([[ -~]&&[^"]]))*)""##, TokenType::StringLiteral),
-        LexMatcher::new(";;[ -~]*", TokenType::Whitespace), // ;; comments.
+        LexMatcher::new(";;[ -~]*", TokenType::Comment), // ;; comments.
         LexMatcher::new("[\n]+", TokenType::Whitespace),
         LexMatcher::new("[ \t]+", TokenType::Whitespace),
         LexMatcher::new("[,]", TokenType::Comma),
@@ -158,6 +223,7 @@ pub fn lex(input: &str) -> ParseResult<Vec<(LexItem, u32, u32)>> {
                             TokenType::RParens => Ok(()),
                             TokenType::RCurly => Ok(()),
                             TokenType::Whitespace => Ok(()),
+                            TokenType::Comment => Ok(()),
                             TokenType::Comma => Ok(()),
                             TokenType::Colon => Ok(()),
                             _ => Err(ParseError::new(ParseErrors::SeparatorExpected(current_slice[..whole_match.end()].to_string())))
@@ -169,6 +235,7 @@ pub fn lex(input: &str) -> ParseResult<Vec<(LexItem, u32, u32)>> {
                             TokenType::RParens => Ok(()),
                             TokenType::RCurly => Ok(()),
                             TokenType::Whitespace => Ok(()),
+                            TokenType::Comment => Ok(()),
                             TokenType::Comma => Ok(()),
                             TokenType::Colon => Ok(()),
                             _ => Err(ParseError::new(ParseErrors::SeparatorExpectedAfterColon(
@@ -193,6 +260,10 @@ pub fn lex(input: &str) -> ParseResult<Vec<(LexItem, u32, u32)>> {
                         context = LexContext::ExpectNothing;
                         Ok(LexItem::Whitespace)
                     },
+                    TokenType::Comment => {
+                        context = LexContext::ExpectNothing;
+                        Ok(LexItem::Comment(current_slice[..whole_match.end()].to_string()))
+                    },
                     TokenType::Comma => {
                         context = LexContext::ExpectNothing;
                         Ok(LexItem::CommaSeparator)
@@ -301,9 +372,27 @@ pub fn lex(input: &str) -> ParseResult<Vec<(LexItem, u32, u32)>> {
                         }?;
                         Ok(LexItem::LiteralValue(str_value.len(), value))
                     },
+                    TokenType::StringASCIILiteral => {
+                        let str_value = get_value_or_err(current_slice, captures)?;
+                        let value = match unescape_string_literal(&str_value, false)
+                            .map(|unescaped| Value::string_ascii_from_bytes(unescaped.into_bytes())) {
+                            Some(Ok(parsed)) => Ok(parsed),
+                            _ => Err(ParseError::new(ParseErrors::FailedParsingString(str_value.clone())))
+                        }?;
+                        Ok(LexItem::LiteralValue(str_value.len(), value))
+                    },
+                    TokenType::StringUTF8Literal => {
+                        let str_value = get_value_or_err(current_slice, captures)?;
+                        let value = match unescape_string_literal(&str_value, true)
+                            .map(Value::string_utf8_from_string_utf8_literal) {
+                            Some(Ok(parsed)) => Ok(parsed),
+                            _ => Err(ParseError::new(ParseErrors::FailedParsingString(str_value.clone())))
+                        }?;
+                        Ok(LexItem::LiteralValue(str_value.len(), value))
+                    },
                 }?;
 
-                result.push((token, current_line, column_pos));
+                result.push((token, &current_slice[..whole_match.end()], current_line, column_pos));
                 column_pos += whole_match.end() as u32;
                 did_match = true;
                 break;
@@ -495,7 +584,7 @@ pub fn parse_lexed(mut input: Vec<(LexItem, u32, u32)>) -> ParseResult<Vec<PreSy
                     }
                 };
             },
-            LexItem::Whitespace => ()
+            LexItem::Whitespace | LexItem::Comment(_) => ()
         };
     }
 
@@ -612,6 +701,29 @@ r#"z (let ((x 1) (y 2))
       assert_eq!(Ok(program), parsed, "Should match expected tuple literal");
     }
 
+    #[test]
+    fn test_parse_string_literals() {
+        let input = r#""a\"b" a"a\"b\n\tc" u"caf\u{e9}\n""#;
+        let parsed: Vec<_> = ast::parser::parse(&input).unwrap().into_iter()
+            .map(|expr| expr.match_atom_value().unwrap().clone())
+            .collect();
+
+        assert_eq!(parsed, vec![
+            Value::buff_from(b"a\"b".to_vec()).unwrap(),
+            Value::string_ascii_from_bytes(b"a\"b\n\tc".to_vec()).unwrap(),
+            Value::string_utf8_from_string_utf8_literal("caf\u{e9}\n".to_string()).unwrap()]);
+
+        // strings print as literals that parse back to the same value
+        for value in parsed[1..].iter() {
+            let printed = format!("{}", value);
+            assert_eq!(ast::parser::parse(&printed).unwrap()[0].match_atom_value(), Some(value));
+        }
+        assert_eq!(format!("{}", parsed[1]), r#"a"a\"b\n\tc""#);
+
+        // a bare 0x is not an empty buffer
+        assert!(ast::parser::parse("0x").is_err());
+    }
+
     #[test]
     fn test_parse_contract_principals() {
         let input = "'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR.contract-a";
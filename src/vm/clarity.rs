@@ -9,6 +9,7 @@ use vm::analysis::{ContractAnalysis, errors::CheckError, errors::CheckErrors};
 use vm::ast;
 use vm::analysis;
 use vm::costs::{LimitedCostTracker, ExecutionCost, CostTracker};
+use vm::costs::profile::CostProfile;
 
 use chainstate::burn::BlockHeaderHash;
 use chainstate::stacks::StacksBlockId;
@@ -35,6 +36,8 @@ use std::fmt;
 pub struct ClarityInstance {
     datastore: Option<MarfedKV>,
     block_limit: ExecutionCost,
+    cost_profiling: bool,
+    mainnet: bool,
 }
 
 ///
@@ -44,7 +47,8 @@ pub struct ClarityBlockConnection<'a> {
     datastore: MarfedKV,
     parent: &'a mut ClarityInstance,
     header_db: &'a dyn HeadersDB,
-    cost_track: Option<LimitedCostTracker>
+    cost_track: Option<LimitedCostTracker>,
+    burn_block_height: Option<u32>
 }
 
 ///
@@ -56,13 +60,16 @@ pub struct ClarityTransactionConnection<'a> {
     log: Option<RollbackWrapperPersistedLog>,
     store: &'a mut MarfedKV,
     header_db: &'a dyn HeadersDB,
-    cost_track: &'a mut Option<LimitedCostTracker>
+    cost_track: &'a mut Option<LimitedCostTracker>,
+    burn_block_height: Option<u32>,
+    mainnet: bool
 }
 
 pub struct ClarityReadOnlyConnection<'a> {
     datastore: MarfedKV,
     parent: &'a mut ClarityInstance,
     header_db: &'a dyn HeadersDB,
+    burn_block_height: Option<u32>
 }
 
 #[derive(Debug)]
@@ -169,7 +176,25 @@ impl ClarityBlockConnection<'_> {
 
 impl ClarityInstance {
     pub fn new(datastore: MarfedKV, block_limit: ExecutionCost) -> ClarityInstance {
-        ClarityInstance { datastore: Some(datastore), block_limit }
+        ClarityInstance { datastore: Some(datastore), block_limit, cost_profiling: false, mainnet: false }
+    }
+
+    /// Evaluate code as running on mainnet (rather than testnet)
+    pub fn set_mainnet(&mut self, mainnet: bool) {
+        self.mainnet = mainnet;
+    }
+
+    /// Profile the costs of each transaction in the blocks processed from now on
+    pub fn set_cost_profiling(&mut self, enabled: bool) {
+        self.cost_profiling = enabled;
+    }
+
+    fn new_block_cost_tracker(&self) -> LimitedCostTracker {
+        let mut cost_track = LimitedCostTracker::new(self.block_limit.clone());
+        if self.cost_profiling {
+            cost_track.begin_profiling();
+        }
+        cost_track
     }
 
     pub fn with_marf<F, R> (&mut self, f: F) -> R
@@ -190,13 +215,14 @@ impl ClarityInstance {
 
         datastore.begin(current, next);
 
-        let cost_track = Some(LimitedCostTracker::new(self.block_limit.clone()));
+        let cost_track = Some(self.new_block_cost_tracker());
 
         ClarityBlockConnection {
             datastore,
             header_db,
             parent: self,
-            cost_track
+            cost_track,
+            burn_block_height: None
         }
     }
     
@@ -208,13 +234,17 @@ impl ClarityInstance {
 
         datastore.begin_unconfirmed(current);
 
-        let cost_track = Some(LimitedCostTracker::new(self.block_limit.clone()));
+        let cost_track = Some(self.new_block_cost_tracker());
+
+        // unconfirmed state is evaluated as of the burnchain block its anchored block was mined in
+        let burn_block_height = header_db.get_burn_block_height_for_block(current);
 
         ClarityBlockConnection {
             datastore,
             header_db,
             parent: self,
-            cost_track
+            cost_track,
+            burn_block_height
         }
     }
 
@@ -227,10 +257,14 @@ impl ClarityInstance {
         datastore
             .set_chain_tip(at_block);
 
+        // read-only evaluation sees the burnchain block height of the chain tip it reads from
+        let burn_block_height = header_db.get_burn_block_height_for_block(at_block);
+
         ClarityReadOnlyConnection {
             datastore,
             header_db,
-            parent: self
+            parent: self,
+            burn_block_height
         }
     }
 
@@ -238,9 +272,11 @@ impl ClarityInstance {
                           contract: &QualifiedContractIdentifier, program: &str) -> Result<Value, Error> {
         self.datastore.as_mut().unwrap()
             .set_chain_tip(at_block);
-        let clarity_db = self.datastore.as_mut().unwrap()
+        let mut clarity_db = self.datastore.as_mut().unwrap()
             .as_clarity_db(header_db);
+        clarity_db.set_burn_block_height(header_db.get_burn_block_height_for_block(at_block));
         let mut env = OwnedEnvironment::new(clarity_db);
+        env.set_mainnet(self.mainnet);
         env.eval_read_only(contract, program)
             .map(|(x, _, _)| x)
             .map_err(Error::from)
@@ -260,6 +296,8 @@ pub trait ClarityConnection {
     where F: FnOnce(ClarityDatabase) -> (R, ClarityDatabase);
     fn with_analysis_db_readonly<F, R>(&mut self, to_do: F) -> R
     where F: FnOnce(&mut AnalysisDatabase) -> R;
+    /// Is this connection evaluating code on mainnet?
+    fn is_mainnet(&self) -> bool;
 
     fn with_clarity_db_readonly<F, R>(&mut self, to_do: F) -> R
     where F: FnOnce(&mut ClarityDatabase) -> R {
@@ -270,8 +308,10 @@ pub trait ClarityConnection {
 
     fn with_readonly_clarity_env<F, R>(&mut self, sender: PrincipalData, cost_track: LimitedCostTracker, to_do: F) -> Result<R, InterpreterError>
     where F: FnOnce(&mut Environment) -> Result<R, InterpreterError> {
+        let mainnet = self.is_mainnet();
         self.with_clarity_db_readonly_owned(|clarity_db| {
             let mut vm_env = OwnedEnvironment::new_cost_limited(clarity_db, cost_track);
+            vm_env.set_mainnet(mainnet);
             let result = vm_env.execute_in_env(sender.into(), to_do)
                 .map(|(result, _, _)| result);
             let (db, _) = vm_env.destruct()
@@ -286,6 +326,7 @@ impl ClarityConnection for ClarityBlockConnection <'_> {
     fn with_clarity_db_readonly_owned<F, R>(&mut self, to_do: F) -> R
     where F: FnOnce(ClarityDatabase) -> (R, ClarityDatabase) {
         let mut db = ClarityDatabase::new(&mut self.datastore, &self.header_db);
+        db.set_burn_block_height(self.burn_block_height);
         db.begin();
         let (result, mut db) = to_do(db);
         db.roll_back();
@@ -300,6 +341,10 @@ impl ClarityConnection for ClarityBlockConnection <'_> {
         db.roll_back();
         result
     }
+
+    fn is_mainnet(&self) -> bool {
+        self.parent.mainnet
+    }
 }
 
 impl ClarityConnection for ClarityReadOnlyConnection <'_> {
@@ -307,6 +352,7 @@ impl ClarityConnection for ClarityReadOnlyConnection <'_> {
     fn with_clarity_db_readonly_owned<F, R>(&mut self, to_do: F) -> R
     where F: FnOnce(ClarityDatabase) -> (R, ClarityDatabase) {
         let mut db = ClarityDatabase::new(&mut self.datastore, &self.header_db);
+        db.set_burn_block_height(self.burn_block_height);
         db.begin();
         let (result, mut db) = to_do(db);
         db.roll_back();
@@ -321,6 +367,10 @@ impl ClarityConnection for ClarityReadOnlyConnection <'_> {
         db.roll_back();
         result
     }
+
+    fn is_mainnet(&self) -> bool {
+        self.parent.mainnet
+    }
 }
 
 impl <'a> ClarityReadOnlyConnection <'a> {
@@ -330,6 +380,12 @@ impl <'a> ClarityReadOnlyConnection <'a> {
 }
 
 impl <'a> ClarityBlockConnection <'a> {
+    /// Set the height of the burnchain block this block was mined in, which
+    ///   `burn-block-height` and STX lock-ups are evaluated against.
+    pub fn set_burn_block_height(&mut self, burn_block_height: u32) {
+        self.burn_block_height = Some(burn_block_height);
+    }
+
     /// Rolls back all changes in the current block by
     /// (1) dropping all writes from the current MARF tip,
     /// (2) rolling back side-storage
@@ -415,10 +471,12 @@ impl <'a> ClarityBlockConnection <'a> {
         let store = &mut self.datastore;
         let cost_track = &mut self.cost_track;
         let header_db = &self.header_db;
+        let burn_block_height = self.burn_block_height;
+        let mainnet = self.parent.mainnet;
         let mut log = RollbackWrapperPersistedLog::new();
         log.nest();
         ClarityTransactionConnection {
-            store, cost_track, header_db, log: Some(log)
+            store, cost_track, header_db, log: Some(log), burn_block_height, mainnet
         }
     }
 
@@ -448,6 +506,7 @@ impl ClarityConnection for ClarityTransactionConnection <'_> {
         using!(self.log, "log", |log| {
             let rollback_wrapper = RollbackWrapper::from_persisted_log(self.store, log);
             let mut db = ClarityDatabase::new_with_rollback_wrapper(rollback_wrapper, &self.header_db);
+            db.set_burn_block_height(self.burn_block_height);
             db.begin();
             let (r, mut db) = to_do(db);
             db.roll_back();
@@ -464,6 +523,10 @@ impl ClarityConnection for ClarityTransactionConnection <'_> {
             result
         })
     }
+
+    fn is_mainnet(&self) -> bool {
+        self.mainnet
+    }
 }
 
 impl <'a> Drop for ClarityTransactionConnection<'a> {
@@ -491,6 +554,7 @@ impl <'a> ClarityTransactionConnection <'a> {
         using!(self.log, "log", |log| {
             let rollback_wrapper = RollbackWrapper::from_persisted_log(self.store, log);
             let mut db = ClarityDatabase::new_with_rollback_wrapper(rollback_wrapper, &self.header_db);
+            db.set_burn_block_height(self.burn_block_height);
 
             db.begin();
             let result = to_do(&mut db);
@@ -512,6 +576,12 @@ impl <'a> ClarityTransactionConnection <'a> {
         }
     }
 
+    /// Get the profile of the costs charged since the last call, if profiling.
+    pub fn take_cost_profile(&mut self) -> Option<CostProfile> {
+        self.cost_track.as_mut()
+            .and_then(|track| track.take_profile())
+    }
+
     /// Analyze a provided smart contract, but do not write the analysis to the AnalysisDatabase
     pub fn analyze_smart_contract(&mut self, identifier: &QualifiedContractIdentifier, contract_content: &str)
                                   -> Result<(ContractAST, ContractAnalysis), Error> {
@@ -550,11 +620,13 @@ impl <'a> ClarityTransactionConnection <'a> {
             using!(self.cost_track, "cost tracker", |cost_track| {
                 let rollback_wrapper = RollbackWrapper::from_persisted_log(self.store, log);
                 let mut db = ClarityDatabase::new_with_rollback_wrapper(rollback_wrapper, &self.header_db);
+                db.set_burn_block_height(self.burn_block_height);
 
                 // wrap the whole contract-call in a claritydb transaction,
                 //   so we can abort on call_back's boolean retun
                 db.begin();
                 let mut vm_env = OwnedEnvironment::new_cost_limited(db, cost_track);
+                vm_env.set_mainnet(self.mainnet);
                 let result = to_do(&mut vm_env);
                 let (mut db, cost_track) = vm_env.destruct()
                     .expect("Failed to recover database reference after executing transaction");
@@ -11,6 +11,9 @@ use vm::representations::{SymbolicExpression, ClarityName, ContractName};
 use vm::contracts::Contract;
 use vm::ast::ContractAST;
 use vm::costs::{CostTracker, ExecutionCost, LimitedCostTracker, cost_functions, CostErrors};
+use vm::costs::profile::CostProfile;
+use vm::coverage::CoverageReporter;
+use vm::debug::Debugger;
 use vm::ast;
 use vm::{eval, is_reserved};
 
@@ -77,6 +80,10 @@ pub struct GlobalContext<'a> {
     pub database: ClarityDatabase<'a>,
     read_only: Vec<bool>,
     pub cost_track: LimitedCostTracker,
+    pub coverage_reporting: Option<CoverageReporter>,
+    pub debugger: Option<Debugger>,
+    // which network's address versions to use for principals made by the VM
+    pub mainnet: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -445,9 +452,8 @@ impl <'a> OwnedEnvironment <'a> {
     pub fn stx_faucet(&mut self, recipient: &PrincipalData, amount: u128) {
         self.execute_in_env(recipient.clone().into(),
                             |env| {
-                                let bal = env.global_context.database.get_account_stx_balance(recipient);
-                                env.global_context.database.set_account_stx_balance(recipient, bal + amount);
-                                Ok(())
+                                let bal = env.global_context.database.get_account_stx_balance(recipient)?;
+                                env.global_context.database.set_account_stx_balance(recipient, bal + amount)
                             }).unwrap();
     }
 
@@ -466,6 +472,44 @@ impl <'a> OwnedEnvironment <'a> {
         self.context.begin();
     }
 
+    /// Record which expressions get evaluated from now on, in the given reporter
+    pub fn set_coverage_reporter(&mut self, reporter: CoverageReporter) {
+        self.context.coverage_reporting = Some(reporter);
+    }
+
+    /// Stop recording coverage, and get what was recorded
+    pub fn take_coverage_reporter(&mut self) -> Option<CoverageReporter> {
+        self.context.coverage_reporting.take()
+    }
+
+    /// Make principals with mainnet (rather than testnet) address versions
+    pub fn set_mainnet(&mut self, mainnet: bool) {
+        self.context.mainnet = mainnet;
+    }
+
+    /// Pause evaluation in the given debugger at breakpoints and while stepping
+    pub fn set_debugger(&mut self, debugger: Debugger) {
+        self.context.debugger = Some(debugger);
+    }
+
+    pub fn take_debugger(&mut self) -> Option<Debugger> {
+        self.context.debugger.take()
+    }
+
+    /// Attribute costs to the functions that incur them from now on
+    pub fn begin_cost_profiling(&mut self) {
+        self.context.cost_track.begin_profiling();
+    }
+
+    /// Get the cost profile so far, if profiling
+    pub fn take_cost_profile(&mut self) -> Option<CostProfile> {
+        self.context.cost_track.take_profile()
+    }
+
+    pub fn get_cost_total(&self) -> ExecutionCost {
+        self.context.cost_track.get_total()
+    }
+
     pub fn commit(&mut self) -> Result<(AssetMap, EventBatch)> {
         let (asset_map, event_batch) = self.context.commit()?;
         let asset_map = asset_map.ok_or(InterpreterError::FailedToConstructAssetTable)?;
@@ -629,7 +673,9 @@ impl <'a,'b> Environment <'a,'b> {
                 return Err(CheckErrors::CircularReference(vec![func_identifier.to_string()]).into())
             }
             self.call_stack.insert(&func_identifier, true);
+            self.global_context.cost_track.profile_enter(&func_identifier);
             let res = self.execute_function_as_transaction(&func, &args, Some(&contract.contract_context));
+            self.global_context.cost_track.profile_exit();
             self.call_stack.remove(&func_identifier, true)?;
             res
         })
@@ -791,6 +837,19 @@ impl <'a,'b> Environment <'a,'b> {
         Ok(())
     }
 
+    pub fn register_nft_burn_event(&mut self, sender: PrincipalData, value: Value, asset_identifier: AssetIdentifier) -> Result<()> {
+        let event_data = NFTBurnEventData {
+            sender,
+            asset_identifier,
+            value
+        };
+
+        if let Some(batch) = self.global_context.event_batches.last_mut() {
+            batch.events.push(StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(event_data)));
+        }
+        Ok(())
+    }
+
     pub fn register_ft_transfer_event(&mut self, sender: PrincipalData, recipient: PrincipalData, amount: u128, asset_identifier: AssetIdentifier) -> Result<()> {
         let event_data = FTTransferEventData {
             sender,
@@ -817,6 +876,19 @@ impl <'a,'b> Environment <'a,'b> {
         }
         Ok(())
     }
+
+    pub fn register_ft_burn_event(&mut self, sender: PrincipalData, amount: u128, asset_identifier: AssetIdentifier) -> Result<()> {
+        let event_data = FTBurnEventData {
+            sender,
+            asset_identifier,
+            amount
+        };
+
+        if let Some(batch) = self.global_context.event_batches.last_mut() {
+            batch.events.push(StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(event_data)));
+        }
+        Ok(())
+    }
 }
 
 impl <'a> GlobalContext<'a> {
@@ -828,6 +900,9 @@ impl <'a> GlobalContext<'a> {
             read_only: Vec::new(),
             asset_maps: Vec::new(),
             event_batches: Vec::new(),
+            coverage_reporting: None,
+            debugger: None,
+            mainnet: false,
         }
     }
 
@@ -854,6 +929,16 @@ impl <'a> GlobalContext<'a> {
             .add_token_transfer(sender, asset_identifier, transfered)
     }
 
+    // burned tokens leave the sender just like transferred ones, so they are
+    //   tracked in the same table, and post-conditions treat them as sent.
+    pub fn log_asset_burn(&mut self, sender: &PrincipalData, contract_identifier: &QualifiedContractIdentifier, asset_name: &ClarityName, burned: Value) {
+        self.log_asset_transfer(sender, contract_identifier, asset_name, burned)
+    }
+
+    pub fn log_token_burn(&mut self, sender: &PrincipalData, contract_identifier: &QualifiedContractIdentifier, asset_name: &ClarityName, burned: u128) -> Result<()> {
+        self.log_token_transfer(sender, contract_identifier, asset_name, burned)
+    }
+
     pub fn log_stx_transfer(&mut self, sender: &PrincipalData, transfered: u128) -> Result<()> {
         self.get_asset_map()
             .add_stx_transfer(sender, transfered)
@@ -112,6 +112,9 @@ def_runtime_cost!(SHA256 { Constant(1) });
 def_runtime_cost!(SHA512 { Constant(1) });
 def_runtime_cost!(SHA512T256 { Constant(1) });
 def_runtime_cost!(KECCAK256 { Constant(1) });
+def_runtime_cost!(SECP256K1RECOVER { Constant(1) });
+def_runtime_cost!(SECP256K1VERIFY { Constant(1) });
+def_runtime_cost!(PRINCIPAL_OF { Constant(1) });
 def_runtime_cost!(PRINT { Linear(1, 1) });
 def_runtime_cost!(SOME_CONS { Constant(1) });
 def_runtime_cost!(OK_CONS { Constant(1) });
@@ -133,6 +136,9 @@ def_runtime_cost!(AND { Linear(1, 1) });
 def_runtime_cost!(APPEND { Linear(1, 1) });
 def_runtime_cost!(CONCAT { Linear(1, 1) });
 def_runtime_cost!(AS_MAX_LEN { Constant(1) });
+def_runtime_cost!(ELEMENT_AT { Constant(1) });
+def_runtime_cost!(INDEX_OF { Linear(1, 1) });
+def_runtime_cost!(SLICE { Linear(1, 1) });
 
 def_runtime_cost!(CONTRACT_CALL { Constant(1) });
 def_runtime_cost!(CONTRACT_OF { Constant(1) });
@@ -259,6 +265,20 @@ pub const FT_TRANSFER: SimpleCostSpecification = SimpleCostSpecification {
     read_count: Constant(2),
     read_length: Constant(1) };
 
+pub const FT_BURN: SimpleCostSpecification = SimpleCostSpecification {
+    write_length: Constant(1),
+    write_count: Constant(2),
+    runtime: Constant(1),
+    read_count: Constant(2),
+    read_length: Constant(1) };
+
+pub const FT_SUPPLY: SimpleCostSpecification = SimpleCostSpecification {
+    write_length: Constant(0),
+    write_count: Constant(0),
+    runtime: Constant(1),
+    read_count: Constant(1),
+    read_length: Constant(1) };
+
 pub const FT_BALANCE: SimpleCostSpecification = SimpleCostSpecification {
     write_length: Constant(0),
     write_count: Constant(0),
@@ -280,6 +300,13 @@ pub const NFT_TRANSFER: SimpleCostSpecification = SimpleCostSpecification {
     read_count: Constant(1),
     read_length: Constant(1) };
 
+pub const NFT_BURN: SimpleCostSpecification = SimpleCostSpecification {
+    write_length: Constant(1),
+    write_count: Constant(1),
+    runtime: Linear(1, 1),
+    read_count: Constant(1),
+    read_length: Constant(1) };
+
 pub const NFT_OWNER: SimpleCostSpecification = SimpleCostSpecification {
     write_length: Constant(0),
     write_count: Constant(0),
@@ -1,9 +1,12 @@
 pub mod cost_functions;
 pub mod constants;
+pub mod profile;
 
 use std::{fmt, cmp};
 use vm::types::TypeSignature;
 use vm::Value;
+use vm::callables::FunctionIdentifier;
+use vm::costs::profile::CostProfile;
 use std::convert::TryFrom;
 
 type Result<T> = std::result::Result<T, CostErrors>;
@@ -83,7 +86,8 @@ pub struct LimitedCostTracker {
     total: ExecutionCost,
     limit: ExecutionCost,
     memory: u64,
-    memory_limit: u64
+    memory_limit: u64,
+    profile: Option<CostProfile>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -96,11 +100,11 @@ pub enum CostErrors {
 impl LimitedCostTracker {
     pub fn new(limit: ExecutionCost) -> LimitedCostTracker {
         LimitedCostTracker { limit, memory_limit: CLARITY_MEMORY_LIMIT,
-                             total: ExecutionCost::zero(), memory: 0 }
+                             total: ExecutionCost::zero(), memory: 0, profile: None }
     }
     pub fn new_max_limit() -> LimitedCostTracker {
         LimitedCostTracker { limit: ExecutionCost::max_value(), total: ExecutionCost::zero(),
-                             memory: 0, memory_limit: CLARITY_MEMORY_LIMIT }
+                             memory: 0, memory_limit: CLARITY_MEMORY_LIMIT, profile: None }
     }
     pub fn get_total(&self) -> ExecutionCost {
         self.total.clone()
@@ -109,10 +113,35 @@ impl LimitedCostTracker {
         // used by the miner to "undo" the cost of a transaction when trying to pack a block.
         self.total = total;
     }
+    /// Start attributing costs to the functions they are incurred in
+    pub fn begin_profiling(&mut self) {
+        if self.profile.is_none() {
+            self.profile = Some(CostProfile::new());
+        }
+    }
+    /// Get the profile of the costs charged since profiling began (or since the last call), and
+    /// start a new one.  Returns None if profiling is off.
+    pub fn take_profile(&mut self) -> Option<CostProfile> {
+        self.profile.as_mut()
+            .map(|profile| std::mem::replace(profile, CostProfile::new()))
+    }
+    pub fn profile_enter(&mut self, function: &FunctionIdentifier) {
+        if let Some(ref mut profile) = self.profile {
+            profile.enter(function);
+        }
+    }
+    pub fn profile_exit(&mut self) {
+        if let Some(ref mut profile) = self.profile {
+            profile.exit();
+        }
+    }
 }
 
 fn add_cost(s: &mut LimitedCostTracker, cost: ExecutionCost) -> std::result::Result<(), CostErrors> {
     s.total.add(&cost)?;
+    if let Some(ref mut profile) = s.profile {
+        profile.add_cost(&cost);
+    }
     if s.total.exceeds(&s.limit) {
         Err(CostErrors::CostBalanceExceeded(s.total.clone(), s.limit.clone()))
     } else {
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use vm::callables::FunctionIdentifier;
+use vm::costs::ExecutionCost;
+use vm::functions::{NativeFunctions, lookup_reserved_functions};
+
+lazy_static! {
+    // natives are identified in the call stack by their internal names (i.e. `native_add`), but
+    //  should show up in profiles by their Clarity names (i.e. `+`).
+    static ref NATIVE_FUNCTION_NAMES: HashMap<String, &'static str> = {
+        let mut names = HashMap::new();
+        for name in NativeFunctions::ALL_NAMES.iter() {
+            if let Some(callable) = lookup_reserved_functions(name) {
+                names.insert(callable.get_identifier().to_string(), *name);
+            }
+        }
+        names
+    };
+}
+
+/// Name of the profile node for costs incurred outside of any function call
+pub const PROFILE_ROOT_NAME: &str = "[top-level]";
+
+define_named_enum!(CostMetric {
+    Runtime("runtime"),
+    ReadCount("read_count"),
+    ReadLength("read_length"),
+    WriteCount("write_count"),
+    WriteLength("write_length"),
+});
+
+impl CostMetric {
+    pub fn get(&self, cost: &ExecutionCost) -> u64 {
+        match self {
+            CostMetric::Runtime => cost.runtime,
+            CostMetric::ReadCount => cost.read_count,
+            CostMetric::ReadLength => cost.read_length,
+            CostMetric::WriteCount => cost.write_count,
+            CostMetric::WriteLength => cost.write_length,
+        }
+    }
+}
+
+/// The costs of one function, as called from one call path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostProfileNode {
+    /// `contract:function` for user-defined functions, and the Clarity name for natives
+    pub name: String,
+    pub calls: u64,
+    /// costs incurred by this function itself
+    pub self_cost: ExecutionCost,
+    /// costs incurred by this function and everything it called
+    pub total_cost: ExecutionCost,
+    pub children: Vec<CostProfileNode>,
+}
+
+/// Execution costs attributed to the functions that incurred them, as a call tree.  A
+/// `LimitedCostTracker` with profiling turned on feeds this as it charges costs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostProfile {
+    pub root: CostProfileNode,
+    /// path of child indexes from the root to the function being evaluated
+    #[serde(skip)]
+    stack: Vec<usize>,
+}
+
+fn add_saturating(total: &mut ExecutionCost, cost: &ExecutionCost) {
+    total.runtime = total.runtime.saturating_add(cost.runtime);
+    total.read_count = total.read_count.saturating_add(cost.read_count);
+    total.read_length = total.read_length.saturating_add(cost.read_length);
+    total.write_count = total.write_count.saturating_add(cost.write_count);
+    total.write_length = total.write_length.saturating_add(cost.write_length);
+}
+
+impl CostProfileNode {
+    fn new(name: String) -> CostProfileNode {
+        CostProfileNode {
+            name,
+            calls: 0,
+            self_cost: ExecutionCost::zero(),
+            total_cost: ExecutionCost::zero(),
+            children: vec![]
+        }
+    }
+
+    fn write_folded(&self, path: &str, metric: &CostMetric, out: &mut String) {
+        let value = metric.get(&self.self_cost);
+        if value > 0 {
+            out.push_str(&format!("{} {}\n", path, value));
+        }
+        for child in self.children.iter() {
+            child.write_folded(&format!("{};{}", path, child.name), metric, out);
+        }
+    }
+}
+
+impl CostProfile {
+    pub fn new() -> CostProfile {
+        CostProfile {
+            root: CostProfileNode::new(PROFILE_ROOT_NAME.to_string()),
+            stack: vec![]
+        }
+    }
+
+    fn current_node(&mut self) -> &mut CostProfileNode {
+        let mut node = &mut self.root;
+        for ix in self.stack.iter() {
+            node = &mut node.children[*ix];
+        }
+        node
+    }
+
+    /// Called when the evaluator calls a function
+    pub fn enter(&mut self, function: &FunctionIdentifier) {
+        let identifier = function.to_string();
+        let name = match NATIVE_FUNCTION_NAMES.get(&identifier) {
+            Some(name) => name.to_string(),
+            None => identifier
+        };
+
+        let node = self.current_node();
+        let ix = match node.children.iter().position(|child| child.name == name) {
+            Some(ix) => ix,
+            None => {
+                node.children.push(CostProfileNode::new(name));
+                node.children.len() - 1
+            }
+        };
+        node.children[ix].calls += 1;
+        self.stack.push(ix);
+    }
+
+    /// Called when the function last entered returns
+    pub fn exit(&mut self) {
+        self.stack.pop();
+    }
+
+    /// Charge a cost to the function being evaluated, and to each of its callers
+    pub fn add_cost(&mut self, cost: &ExecutionCost) {
+        let mut node = &mut self.root;
+        add_saturating(&mut node.total_cost, cost);
+        for ix in self.stack.iter() {
+            node = &mut node.children[*ix];
+            add_saturating(&mut node.total_cost, cost);
+        }
+        add_saturating(&mut node.self_cost, cost);
+    }
+
+    /// Write the profile out in the "folded stacks" format that flame graph tools read: one
+    /// line per call path, with the cost incurred by the path's last function.
+    pub fn to_folded_stacks(&self, metric: &CostMetric) -> String {
+        let mut out = String::new();
+        self.root.write_folded(&self.root.name, metric, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm::contexts::OwnedEnvironment;
+    use vm::costs::LimitedCostTracker;
+    use vm::database::MemoryBackingStore;
+    use vm::representations::SymbolicExpression;
+    use vm::types::{PrincipalData, QualifiedContractIdentifier};
+    use vm::Value;
+
+    const COUNTER: &str = "(define-data-var count int 0)
+(define-public (bump (by int))
+  (begin
+    (var-set count (+ (var-get count) by))
+    (ok (var-get count))))
+";
+
+    const CALLER: &str = "(define-private (double (x int)) (* x 2))
+(define-public (bump-twice (by int))
+  (contract-call? .counter bump (double by)))
+";
+
+    fn find<'a>(node: &'a CostProfileNode, path: &[&str]) -> &'a CostProfileNode {
+        match path.split_first() {
+            Some((name, rest)) => {
+                let child = node.children.iter().find(|child| child.name == *name)
+                    .expect(&format!("no {} under {}", name, node.name));
+                find(child, rest)
+            },
+            None => node
+        }
+    }
+
+    #[test]
+    fn test_cost_profile() {
+        let sender = Value::from(PrincipalData::parse_standard_principal("S1G2081040G2081040G2081040G208105NK8PE5").unwrap());
+        let caller = QualifiedContractIdentifier::local("caller").unwrap();
+
+        let mut marf = MemoryBackingStore::new();
+        let mut env = OwnedEnvironment::new_cost_limited(marf.as_clarity_db(), LimitedCostTracker::new_max_limit());
+        env.initialize_contract(QualifiedContractIdentifier::local("counter").unwrap(), COUNTER).unwrap();
+        env.initialize_contract(caller.clone(), CALLER).unwrap();
+
+        let cost_before = env.get_cost_total();
+        env.begin_cost_profiling();
+        for _ in 0..2 {
+            env.execute_transaction(sender.clone(), caller.clone(), "bump-twice",
+                                    &[SymbolicExpression::atom_value(Value::Int(3))]).unwrap();
+        }
+        let mut total = env.get_cost_total();
+        total.sub(&cost_before).unwrap();
+        let profile = env.take_cost_profile().unwrap();
+
+        // everything charged while profiling is in the profile
+        assert_eq!(profile.root.total_cost, total);
+
+        let bump_twice = find(&profile.root, &["S1G2081040G2081040G2081040G208105NK8PE5.caller:bump-twice"]);
+        assert_eq!(bump_twice.calls, 2);
+
+        let contract_call = find(bump_twice, &["contract-call?"]);
+        let double = find(contract_call, &["S1G2081040G2081040G2081040G208105NK8PE5.caller:double"]);
+        assert_eq!(double.calls, 2);
+        assert_eq!(find(double, &["*"]).calls, 2);
+
+        let bump = find(contract_call, &["S1G2081040G2081040G2081040G208105NK8PE5.counter:bump"]);
+        assert_eq!(bump.calls, 2);
+        let var_set = find(bump, &["begin", "var-set"]);
+        assert!(var_set.self_cost.write_count > 0);
+        assert!(bump.total_cost.write_count >= var_set.total_cost.write_count);
+        assert_eq!(bump.self_cost.write_count, 0);
+
+        let folded = profile.to_folded_stacks(&CostMetric::Runtime);
+        assert!(folded.contains("[top-level];S1G2081040G2081040G2081040G208105NK8PE5.caller:bump-twice;contract-call?;S1G2081040G2081040G2081040G208105NK8PE5.counter:bump;begin;var-set "));
+        let folded_total: u64 = folded.lines()
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(folded_total, total.runtime);
+
+        // profiling starts over once the profile is taken
+        let profile = env.take_cost_profile().unwrap();
+        assert_eq!(profile.root.total_cost, ExecutionCost::zero());
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::{HashMap, BTreeMap};
+
+use vm::errors::{Error, InterpreterResult as Result};
+use vm::representations::{SymbolicExpression, SymbolicExpressionType, Span};
+use vm::types::QualifiedContractIdentifier;
+use vm::Value;
+
+/// What happened to one expression over all of its evaluations
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionCoverage {
+    pub span: Span,
+    /// number of times the expression was evaluated
+    pub hits: u64,
+    /// number of times it evaluated to a value
+    pub successes: u64,
+    /// number of times it returned early (i.e. through `unwrap!`, `asserts!`, `try!` and friends)
+    pub short_returns: u64,
+}
+
+/// Records how many times each expression of each contract was evaluated.  The evaluator feeds
+/// this when it is installed in a `GlobalContext`, and the result can be written out as LCOV.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageReporter {
+    contracts: HashMap<QualifiedContractIdentifier, HashMap<u64, ExpressionCoverage>>,
+}
+
+// how many times one branch of a branching expression was taken.  If the branching expression
+// itself was never evaluated, LCOV reports its branches as "-" rather than 0.
+enum BranchCount {
+    NotEvaluated,
+    Taken(u64),
+}
+
+impl CoverageReporter {
+    pub fn new() -> CoverageReporter {
+        CoverageReporter {
+            contracts: HashMap::new()
+        }
+    }
+
+    fn get_entry(&mut self, contract_identifier: &QualifiedContractIdentifier, expr: &SymbolicExpression) -> &mut ExpressionCoverage {
+        self.contracts.entry(contract_identifier.clone()).or_insert_with(HashMap::new)
+            .entry(expr.id).or_insert_with(|| {
+                #[cfg(feature = "developer-mode")]
+                let span = expr.span.clone();
+                #[cfg(not(feature = "developer-mode"))]
+                let span = Span::zero();
+
+                ExpressionCoverage { span, hits: 0, successes: 0, short_returns: 0 }
+            })
+    }
+
+    /// Called by the evaluator before it evaluates an expression
+    pub fn report_eval(&mut self, contract_identifier: &QualifiedContractIdentifier, expr: &SymbolicExpression) {
+        self.get_entry(contract_identifier, expr).hits += 1;
+    }
+
+    /// Called by the evaluator once it has evaluated an expression
+    pub fn report_result(&mut self, contract_identifier: &QualifiedContractIdentifier, expr: &SymbolicExpression, result: &Result<Value>) {
+        let entry = self.get_entry(contract_identifier, expr);
+        match result {
+            Ok(_) => entry.successes += 1,
+            Err(Error::ShortReturn(_)) => entry.short_returns += 1,
+            Err(_) => {}
+        }
+    }
+
+    /// Add another reporter's counts to this one's
+    #[cfg(test)]
+    pub fn merge(&mut self, other: CoverageReporter) {
+        for (contract_identifier, expressions) in other.contracts.into_iter() {
+            let our_expressions = self.contracts.entry(contract_identifier).or_insert_with(HashMap::new);
+            for (id, coverage) in expressions.into_iter() {
+                match our_expressions.get_mut(&id) {
+                    Some(ours) => {
+                        ours.hits += coverage.hits;
+                        ours.successes += coverage.successes;
+                        ours.short_returns += coverage.short_returns;
+                    },
+                    None => {
+                        our_expressions.insert(id, coverage);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_coverage(&self, contract_identifier: &QualifiedContractIdentifier, expr_id: u64) -> Option<&ExpressionCoverage> {
+        self.contracts.get(contract_identifier).and_then(|expressions| expressions.get(&expr_id))
+    }
+
+    fn get_hits(&self, contract_identifier: &QualifiedContractIdentifier, expr: &SymbolicExpression) -> u64 {
+        self.get_coverage(contract_identifier, expr.id).map(|c| c.hits).unwrap_or(0)
+    }
+
+    fn get_short_returns(&self, contract_identifier: &QualifiedContractIdentifier, expr: &SymbolicExpression) -> u64 {
+        self.get_coverage(contract_identifier, expr.id).map(|c| c.short_returns).unwrap_or(0)
+    }
+
+    /// Write an LCOV tracefile record for a contract, given the source file it was deployed from
+    /// and its parsed expressions.  Line numbers come from the expressions' spans, so they are
+    /// only available when built with the `developer-mode` feature.
+    pub fn to_lcov(&self, contract_identifier: &QualifiedContractIdentifier, source_path: &str, expressions: &[SymbolicExpression]) -> String {
+        let mut statements = vec![];
+        let mut functions = vec![];
+        for expr in expressions.iter() {
+            match top_level_executable(expr) {
+                TopLevel::Function(name, body) => {
+                    functions.push((name, expr_line(expr), body));
+                    for body_expr in body.iter() {
+                        collect_statements(body_expr, &mut statements);
+                    }
+                },
+                TopLevel::Executable(executable) => {
+                    for exec_expr in executable.iter() {
+                        collect_statements(exec_expr, &mut statements);
+                    }
+                },
+            }
+        }
+
+        let mut out = format!("TN:\nSF:{}\n", source_path);
+
+        let mut functions_hit = 0;
+        for (name, line, _) in functions.iter() {
+            out.push_str(&format!("FN:{},{}\n", line, name));
+        }
+        for (name, _, body) in functions.iter() {
+            let hits = body.first().map(|expr| self.get_hits(contract_identifier, expr)).unwrap_or(0);
+            if hits > 0 {
+                functions_hit += 1;
+            }
+            out.push_str(&format!("FNDA:{},{}\n", hits, name));
+        }
+        out.push_str(&format!("FNF:{}\nFNH:{}\n", functions.len(), functions_hit));
+
+        let mut branches_found = 0;
+        let mut branches_hit = 0;
+        for expr in statements.iter() {
+            for (branch_num, count) in self.get_branch_counts(contract_identifier, expr).into_iter().enumerate() {
+                branches_found += 1;
+                let taken = match count {
+                    BranchCount::NotEvaluated => "-".to_string(),
+                    BranchCount::Taken(n) => {
+                        if n > 0 {
+                            branches_hit += 1;
+                        }
+                        n.to_string()
+                    }
+                };
+                // branch points are numbered by their expression id, so that two different branch
+                // points on the same line get different LCOV block numbers.
+                out.push_str(&format!("BRDA:{},{},{},{}\n", expr_line(expr), expr.id, branch_num, taken));
+            }
+        }
+        out.push_str(&format!("BRF:{}\nBRH:{}\n", branches_found, branches_hit));
+
+        // a line is as covered as its most-evaluated statement
+        let mut lines = BTreeMap::new();
+        for expr in statements.iter() {
+            let hits = self.get_hits(contract_identifier, expr);
+            let line_hits = lines.entry(expr_line(expr)).or_insert(0);
+            if hits > *line_hits {
+                *line_hits = hits;
+            }
+        }
+        for (line, hits) in lines.iter() {
+            out.push_str(&format!("DA:{},{}\n", line, hits));
+        }
+        out.push_str(&format!("LF:{}\nLH:{}\n", lines.len(), lines.values().filter(|hits| **hits > 0).count()));
+        out.push_str("end_of_record\n");
+        out
+    }
+
+    /// How many times each branch of a branching expression was taken
+    fn get_branch_counts(&self, contract_identifier: &QualifiedContractIdentifier, expr: &SymbolicExpression) -> Vec<BranchCount> {
+        let (name, args) = match application(expr) {
+            Some(x) => x,
+            None => return vec![]
+        };
+        let coverage = match self.get_coverage(contract_identifier, expr.id) {
+            Some(coverage) => coverage,
+            None => {
+                let num_branches = match name {
+                    "if" | "match" | "asserts!" | "unwrap!" | "unwrap-err!" | "try!" => 2,
+                    _ => 0
+                };
+                return (0..num_branches).map(|_| BranchCount::NotEvaluated).collect();
+            }
+        };
+
+        // the early return out of `unwrap!` and friends is only theirs if none of their arguments
+        // returned early first
+        let own_short_returns = || {
+            let args_short_returns: u64 = args.iter().map(|arg| self.get_short_returns(contract_identifier, arg)).sum();
+            coverage.short_returns.saturating_sub(args_short_returns)
+        };
+
+        match name {
+            "if" if args.len() == 3 => vec![
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[1])),
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[2]))],
+            // (match response ok-name ok-branch err-name err-branch)
+            "match" if args.len() == 5 => vec![
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[2])),
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[4]))],
+            // (match option some-name some-branch none-branch)
+            "match" if args.len() == 4 => vec![
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[2])),
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[3]))],
+            "asserts!" if args.len() == 2 => vec![
+                BranchCount::Taken(coverage.successes),
+                BranchCount::Taken(self.get_hits(contract_identifier, &args[1]))],
+            "unwrap!" | "unwrap-err!" | "try!" => vec![
+                BranchCount::Taken(coverage.successes),
+                BranchCount::Taken(own_short_returns())],
+            _ => vec![]
+        }
+    }
+}
+
+/// The line an expression starts on, or 0 if this build does not track source spans
+pub fn expr_line(expr: &SymbolicExpression) -> u32 {
+    #[cfg(feature = "developer-mode")]
+    let line = expr.span.start_line;
+    #[cfg(not(feature = "developer-mode"))]
+    let line = 0;
+
+    line
+}
+
+/// If this expression is a function application, get the function's name and arguments
+fn application(expr: &SymbolicExpression) -> Option<(&str, &[SymbolicExpression])> {
+    expr.match_list()
+        .and_then(|list| list.split_first())
+        .and_then(|(head, args)| head.match_atom().map(|name| (name.as_str(), args)))
+}
+
+enum TopLevel<'a> {
+    Function(String, &'a [SymbolicExpression]),
+    Executable(&'a [SymbolicExpression]),
+}
+
+/// Find the parts of a top-level expression that get evaluated.  Definitions are not evaluated
+/// themselves, and neither are the type signatures in them.
+fn top_level_executable(expr: &SymbolicExpression) -> TopLevel {
+    match application(expr) {
+        Some((name, args)) => match name {
+            "define-public" | "define-private" | "define-read-only" if args.len() >= 2 => {
+                let function_name = args[0].match_list()
+                    .and_then(|signature| signature.first())
+                    .and_then(|name| name.match_atom())
+                    .map(|name| name.to_string())
+                    .unwrap_or_default();
+                TopLevel::Function(function_name, &args[1..])
+            },
+            "define-constant" if args.len() == 2 => TopLevel::Executable(&args[1..]),
+            "define-data-var" if args.len() == 3 => TopLevel::Executable(&args[2..]),
+            "define-fungible-token" if args.len() == 2 => TopLevel::Executable(&args[1..]),
+            "define-public" | "define-private" | "define-read-only" | "define-constant" | "define-data-var" |
+            "define-fungible-token" | "define-non-fungible-token" | "define-map" | "define-trait" |
+            "use-trait" | "impl-trait" => TopLevel::Executable(&[]),
+            _ => TopLevel::Executable(std::slice::from_ref(expr)),
+        },
+        None => TopLevel::Executable(std::slice::from_ref(expr)),
+    }
+}
+
+/// Collect every function application that can be evaluated in this expression.  Names that are
+/// bound or looked up, like `let` variables, tuple keys and `match` bindings, are not statements.
+fn collect_statements<'a>(expr: &'a SymbolicExpression, statements: &mut Vec<&'a SymbolicExpression>) {
+    if let SymbolicExpressionType::List(ref children) = expr.expr {
+        match application(expr) {
+            Some((name, args)) => {
+                statements.push(expr);
+                let evaluated: Vec<&SymbolicExpression> = match name {
+                    "let" if args.len() >= 1 => {
+                        collect_bindings(&args[0], statements);
+                        args[1..].iter().collect()
+                    },
+                    "tuple" => {
+                        for pair in args.iter() {
+                            collect_bindings_pair(pair, statements);
+                        }
+                        vec![]
+                    },
+                    "match" if args.len() == 5 => vec![&args[0], &args[2], &args[4]],
+                    "match" if args.len() == 4 => vec![&args[0], &args[2], &args[3]],
+                    "get" if args.len() == 2 => vec![&args[1]],
+                    "contract-call?" if args.len() >= 2 => args[2..].iter().collect(),
+                    _ => args.iter().collect()
+                };
+                for child in evaluated.into_iter() {
+                    collect_statements(child, statements);
+                }
+            },
+            None => {
+                // an implicit tuple, i.e. a list of (name value) pairs
+                for pair in children.iter() {
+                    collect_bindings_pair(pair, statements);
+                }
+            }
+        }
+    }
+}
+
+fn collect_bindings<'a>(bindings: &'a SymbolicExpression, statements: &mut Vec<&'a SymbolicExpression>) {
+    if let Some(pairs) = bindings.match_list() {
+        for pair in pairs.iter() {
+            collect_bindings_pair(pair, statements);
+        }
+    }
+}
+
+fn collect_bindings_pair<'a>(pair: &'a SymbolicExpression, statements: &mut Vec<&'a SymbolicExpression>) {
+    if let Some(pair) = pair.match_list() {
+        for value in pair.iter().skip(1) {
+            collect_statements(value, statements);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm::ast::build_ast;
+    use vm::contexts::OwnedEnvironment;
+    use vm::database::MemoryBackingStore;
+    use vm::types::{PrincipalData, QualifiedContractIdentifier};
+
+    const CONTRACT: &str = "(define-constant limit (+ 5 5))
+(define-map balances ((owner principal)) ((amount uint)))
+(define-private (check (x int))
+  (if (> x limit)
+      (ok x)
+      (err u1)))
+(define-public (run (x int))
+  (begin
+    (asserts! (> x 0)
+              (err u2))
+    (let ((checked (unwrap! (check x)
+                            (err u3))))
+      (ok checked))))
+(define-read-only (never) (ok 1))
+";
+
+    #[test]
+    fn test_lcov_report() {
+        let contract_identifier = QualifiedContractIdentifier::local("cov").unwrap();
+        let sender = Value::from(PrincipalData::parse_standard_principal("S1G2081040G2081040G2081040G208105NK8PE5").unwrap());
+
+        let mut marf = MemoryBackingStore::new();
+        let mut env = OwnedEnvironment::new(marf.as_clarity_db());
+        env.set_coverage_reporter(CoverageReporter::new());
+
+        env.initialize_contract(contract_identifier.clone(), CONTRACT).unwrap();
+        for x in [20, 5, -1].iter() {
+            env.execute_transaction(sender.clone(), contract_identifier.clone(), "run",
+                                    &[SymbolicExpression::atom_value(Value::Int(*x))]).unwrap();
+        }
+
+        let reporter = env.take_coverage_reporter().unwrap();
+        let ast = build_ast(&contract_identifier, CONTRACT, &mut ()).unwrap();
+        let lcov = reporter.to_lcov(&contract_identifier, "cov.clar", &ast.expressions);
+
+        let expected = vec![
+            "TN:", "SF:cov.clar",
+            "FN:3,check", "FN:7,run", "FN:14,never",
+            "FNDA:2,check", "FNDA:3,run", "FNDA:0,never",
+            "FNF:3", "FNH:2",
+            // (if (> x 10) ...): taken once each way
+            "BRDA:4,", ",0,1", ",1,1",
+            // (asserts! (> x 0) (err u2)): passed twice, failed once
+            "BRDA:9,", ",0,2", ",1,1",
+            // (unwrap! (check x) (err u3)): unwrapped once, returned early once.  Its arguments
+            // are always evaluated, so `(err u3)` on line 12 is hit twice.
+            "BRDA:11,", ",0,1", ",1,1",
+            "BRF:6", "BRH:6",
+            "DA:1,1", "DA:4,2", "DA:5,1", "DA:6,1", "DA:8,3", "DA:9,3", "DA:10,1", "DA:11,2", "DA:12,2", "DA:13,1", "DA:14,0",
+            "LF:11", "LH:10",
+            "end_of_record"];
+        for line in expected.iter() {
+            assert!(lcov.contains(line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let contract_identifier = QualifiedContractIdentifier::local("cov").unwrap();
+        let ast = build_ast(&contract_identifier, "(+ 1 2)", &mut ()).unwrap();
+        let expr = &ast.expressions[0];
+
+        let mut first = CoverageReporter::new();
+        first.report_eval(&contract_identifier, expr);
+        first.report_result(&contract_identifier, expr, &Ok(Value::Int(3)));
+
+        let mut second = first.clone();
+        second.report_eval(&contract_identifier, expr);
+
+        first.merge(second);
+        let coverage = first.get_coverage(&contract_identifier, expr.id).unwrap();
+        assert_eq!(coverage.hits, 3);
+        assert_eq!(coverage.successes, 2);
+        assert_eq!(coverage.short_returns, 0);
+    }
+}
@@ -19,7 +19,7 @@ use vm::database::{MarfedKV, ClarityBackingStore};
 use vm::database::structures::{
     FungibleTokenMetadata, NonFungibleTokenMetadata, ContractMetadata,
     DataMapMetadata, DataVariableMetadata, ClaritySerializable, SimmedBlock,
-    ClarityDeserializable
+    ClarityDeserializable, STXBalance
 };
 use vm::database::RollbackWrapper;
 use util::db::{DBConn, FromRow};
@@ -50,6 +50,7 @@ pub enum StoreType {
 pub struct ClarityDatabase<'a> {
     pub store: RollbackWrapper<'a>,
     headers_db: &'a dyn HeadersDB,
+    burn_block_height: Option<u32>,
 }
 
 pub trait HeadersDB {
@@ -57,6 +58,7 @@ pub trait HeadersDB {
     fn get_burn_header_hash_for_block(&self, id_bhh: &StacksBlockId) -> Option<BurnchainHeaderHash>;
     fn get_vrf_seed_for_block(&self, id_bhh: &StacksBlockId) -> Option<VRFSeed>;
     fn get_burn_block_time_for_block(&self, id_bhh: &StacksBlockId) -> Option<u64>;
+    fn get_burn_block_height_for_block(&self, id_bhh: &StacksBlockId) -> Option<u32>;
     fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress>;
 }
 
@@ -92,6 +94,11 @@ impl HeadersDB for DBConn {
             .map(|x| x.burn_header_timestamp)
     }
 
+    fn get_burn_block_height_for_block(&self, id_bhh: &StacksBlockId) -> Option<u32> {
+        get_stacks_header_info(self, id_bhh)
+            .map(|x| x.burn_header_height)
+    }
+
     fn get_vrf_seed_for_block(&self, id_bhh: &StacksBlockId) -> Option<VRFSeed> {
         get_stacks_header_info(self, id_bhh)
             .map(|x| VRFSeed::from_proof(&x.anchored_header.proof))
@@ -116,6 +123,9 @@ impl HeadersDB for &dyn HeadersDB {
     fn get_burn_block_time_for_block(&self, bhh: &StacksBlockId) -> Option<u64> {
         (*self).get_burn_block_time_for_block(bhh)
     }
+    fn get_burn_block_height_for_block(&self, bhh: &StacksBlockId) -> Option<u32> {
+        (*self).get_burn_block_height_for_block(bhh)
+    }
     fn get_miner_address(&self, bhh: &StacksBlockId)  -> Option<StacksAddress> {
         (*self).get_miner_address(bhh)
     }
@@ -138,6 +148,9 @@ impl HeadersDB for NullHeadersDB {
     fn get_burn_block_time_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u64> {
         None
     }
+    fn get_burn_block_height_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u32> {
+        None
+    }
     fn get_miner_address(&self, _id_bhh: &StacksBlockId)  -> Option<StacksAddress> {
         None
     }
@@ -147,12 +160,18 @@ impl <'a> ClarityDatabase <'a> {
     pub fn new(store: &'a mut dyn ClarityBackingStore, headers_db: &'a dyn HeadersDB) -> ClarityDatabase<'a> {
         ClarityDatabase {
             store: RollbackWrapper::new(store),
-            headers_db
+            headers_db,
+            burn_block_height: None
         }
     }
 
     pub fn new_with_rollback_wrapper(store: RollbackWrapper<'a>, headers_db: &'a dyn HeadersDB) -> ClarityDatabase<'a> {
-        ClarityDatabase { store, headers_db }
+        ClarityDatabase { store, headers_db, burn_block_height: None }
+    }
+
+    /// Set the burnchain block height of the block being evaluated, if it's known.
+    pub fn set_burn_block_height(&mut self, burn_block_height: Option<u32>) {
+        self.burn_block_height = burn_block_height;
     }
 
     pub fn initialize(&mut self) {
@@ -309,6 +328,13 @@ impl <'a> ClarityDatabase <'a> {
             .expect("Failed to get block data.")
     }
 
+    /// The burnchain block height of the block being evaluated.  This is given to the
+    /// database by its connection; it is an error to ask for it when it isn't known.
+    pub fn get_current_burnchain_block_height(&mut self) -> Result<u32> {
+        self.burn_block_height
+            .ok_or(RuntimeErrorType::UnknownBurnBlockHeight.into())
+    }
+
     pub fn get_burnchain_block_header_hash(&mut self, block_height: u32) -> BurnchainHeaderHash {
         let id_bhh = self.get_index_block_header_hash(block_height);
         self.headers_db.get_burn_header_hash_for_block(&id_bhh)
@@ -483,7 +509,7 @@ impl <'a> ClarityDatabase <'a> {
 
 impl <'a> ClarityDatabase <'a> {
     pub fn create_fungible_token(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str, total_supply: &Option<u128>) {
-        let data = FungibleTokenMetadata { total_supply: total_supply.clone() };
+        let data = FungibleTokenMetadata { total_supply: total_supply.clone(), tracks_supply: true };
 
         let key = ClarityDatabase::make_metadata_key(StoreType::FungibleTokenMeta, token_name);
         self.insert_metadata(contract_identifier, &key, &data);
@@ -495,6 +521,19 @@ impl <'a> ClarityDatabase <'a> {
         }
     }
 
+    /// Get a token's circulating supply.  Tokens without a total supply don't get a supply entry
+    ///   until they are first minted, so a missing entry means nothing is in circulation.
+    ///   Tokens without a total supply that were defined before circulating supply was tracked
+    ///   have an unknown supply.
+    fn get_circulating_supply(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str, descriptor: &FungibleTokenMetadata) -> Result<u128> {
+        if !descriptor.has_known_supply() {
+            return Err(RuntimeErrorType::SupplyUnknown.into())
+        }
+
+        let key = ClarityDatabase::make_key_for_trip(contract_identifier, StoreType::CirculatingSupply, token_name);
+        Ok(self.get(&key).unwrap_or(0))
+    }
+
     fn load_ft(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str) -> Result<FungibleTokenMetadata> {
         let key = ClarityDatabase::make_metadata_key(StoreType::FungibleTokenMeta, token_name);
 
@@ -517,26 +556,51 @@ impl <'a> ClarityDatabase <'a> {
             .ok_or(CheckErrors::NoSuchNFT(token_name.to_string()).into())
     }
 
+    /// Count newly-minted tokens towards a token's circulating supply.  Mints of tokens with an
+    ///   unknown supply aren't counted.
     pub fn checked_increase_token_supply(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str, amount: u128) -> Result<()> {
         let descriptor = self.load_ft(contract_identifier, token_name)?;
+        if !descriptor.has_known_supply() {
+            return Ok(())
+        }
 
-        if let Some(total_supply) = descriptor.total_supply {
-            let key = ClarityDatabase::make_key_for_trip(contract_identifier, StoreType::CirculatingSupply, token_name);
-            let current_supply: u128 = self.get(&key)
-                .expect("ERROR: Clarity VM failed to track token supply.");
- 
-            let new_supply = current_supply.checked_add(amount)
-                .ok_or(RuntimeErrorType::ArithmeticOverflow)?;
+        let key = ClarityDatabase::make_key_for_trip(contract_identifier, StoreType::CirculatingSupply, token_name);
+        let current_supply = self.get_circulating_supply(contract_identifier, token_name, &descriptor)?;
+
+        let new_supply = current_supply.checked_add(amount)
+            .ok_or(RuntimeErrorType::ArithmeticOverflow)?;
 
+        if let Some(total_supply) = descriptor.total_supply {
             if new_supply > total_supply {
-                Err(RuntimeErrorType::SupplyOverflow(new_supply, total_supply).into())
-            } else {
-                self.put(&key, &new_supply);
-                Ok(())
+                return Err(RuntimeErrorType::SupplyOverflow(new_supply, total_supply).into())
             }
-        } else {
-            Ok(())
         }
+
+        self.put(&key, &new_supply);
+        Ok(())
+    }
+
+    /// Remove burnt tokens from a token's circulating supply.  Burns of tokens with an unknown
+    ///   supply aren't counted.
+    pub fn checked_decrease_token_supply(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str, amount: u128) -> Result<()> {
+        let descriptor = self.load_ft(contract_identifier, token_name)?;
+        if !descriptor.has_known_supply() {
+            return Ok(())
+        }
+
+        let key = ClarityDatabase::make_key_for_trip(contract_identifier, StoreType::CirculatingSupply, token_name);
+        let current_supply = self.get_circulating_supply(contract_identifier, token_name, &descriptor)?;
+
+        let new_supply = current_supply.checked_sub(amount)
+            .ok_or(RuntimeErrorType::SupplyUnderflow(current_supply, amount))?;
+
+        self.put(&key, &new_supply);
+        Ok(())
+    }
+
+    pub fn get_ft_supply(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str) -> Result<u128> {
+        let descriptor = self.load_ft(contract_identifier, token_name)?;
+        self.get_circulating_supply(contract_identifier, token_name, &descriptor)
     }
 
     pub fn get_ft_balance(&mut self, contract_identifier: &QualifiedContractIdentifier, token_name: &str, principal: &PrincipalData) -> Result<u128> {
@@ -566,8 +630,14 @@ impl <'a> ClarityDatabase <'a> {
 
         let key = ClarityDatabase::make_key_for_quad(contract_identifier, StoreType::NonFungibleToken, asset_name, asset.serialize());
 
-        let result = self.get(&key);
-        result.ok_or(RuntimeErrorType::NoSuchToken.into())
+        // burned assets read the same as ones that were never minted.
+        let owner = match self.get::<String>(&key) {
+            Some(serialized) => serde_json::from_str::<Option<PrincipalData>>(&serialized)
+                .map_err(|_| InterpreterError::InterpreterError("Clarity VM stored an unreadable NFT owner".into()))?,
+            None => None
+        };
+
+        owner.ok_or(RuntimeErrorType::NoSuchToken.into())
     }
 
     pub fn get_nft_key_type(&mut self, contract_identifier: &QualifiedContractIdentifier, asset_name: &str) -> Result<TypeSignature> {
@@ -583,7 +653,20 @@ impl <'a> ClarityDatabase <'a> {
 
         let key = ClarityDatabase::make_key_for_quad(contract_identifier, StoreType::NonFungibleToken, asset_name, asset.serialize());
 
-        self.put(&key, principal);
+        self.put(&key, &Some(principal.clone()));
+
+        Ok(())
+    }
+
+    pub fn burn_nft(&mut self, contract_identifier: &QualifiedContractIdentifier, asset_name: &str, asset: &Value) -> Result<()> {
+        let descriptor = self.load_nft(contract_identifier, asset_name)?;
+        if !descriptor.key_type.admits(asset) {
+            return Err(CheckErrors::TypeValueError(descriptor.key_type, (*asset).clone()).into())
+        }
+
+        let key = ClarityDatabase::make_key_for_quad(contract_identifier, StoreType::NonFungibleToken, asset_name, asset.serialize());
+
+        self.put(&key, &(None as Option<PrincipalData>));
 
         Ok(())
     }
@@ -603,18 +686,52 @@ impl<'a> ClarityDatabase<'a> {
         ClarityDatabase::make_key_for_account(principal, StoreType::Nonce)
     }
 
-    pub fn get_account_stx_balance(&mut self, principal: &PrincipalData) -> u128 {
+    /// Get the full STX balance record of an account, including any locked tokens.
+    pub fn get_account_stx_balance_info(&mut self, principal: &PrincipalData) -> STXBalance {
         let key = ClarityDatabase::make_key_for_account_balance(principal);
         let result = self.get(&key);
         match result {
-            None => 0,
+            None => STXBalance::zero(),
             Some(balance) => balance
         }
     }
 
-    pub fn set_account_stx_balance(&mut self, principal: &PrincipalData, balance: u128) {
+    /// Get the number of uSTX this account can spend in the block being evaluated.
+    /// Tokens whose lock-up has expired are spendable.
+    pub fn get_account_stx_balance(&mut self, principal: &PrincipalData) -> Result<u128> {
+        let balance = self.get_account_stx_balance_info(principal);
+        if balance.amount_locked == 0 {
+            return Ok(balance.amount_unlocked);
+        }
+        let burn_block_height = self.get_current_burnchain_block_height()?;
+        Ok(balance.get_available_balance_at_burn_block(burn_block_height as u64))
+    }
+
+    /// Set the number of uSTX this account can spend.  Tokens that are still locked are left
+    /// alone; tokens whose lock-up has expired are considered part of the spendable balance.
+    pub fn set_account_stx_balance(&mut self, principal: &PrincipalData, balance: u128) -> Result<()> {
+        let mut balance_info = self.get_account_stx_balance_info(principal);
+        if balance_info.amount_locked > 0 {
+            let burn_block_height = self.get_current_burnchain_block_height()?;
+            balance_info.unlock_available_tokens_if_any(burn_block_height as u64);
+        }
+        balance_info.amount_unlocked = balance;
+        self.set_account_stx_balance_info(principal, &balance_info);
+        Ok(())
+    }
+
+    fn set_account_stx_balance_info(&mut self, principal: &PrincipalData, balance: &STXBalance) {
         let key = ClarityDatabase::make_key_for_account_balance(principal);
-        self.put(&key, &balance);
+        self.put(&key, balance);
+    }
+
+    /// Lock up some of an account's spendable uSTX until the burnchain reaches `unlock_height`.
+    pub fn lock_account_stx(&mut self, principal: &PrincipalData, amount: u128, unlock_height: u64) -> Result<()> {
+        let burn_block_height = self.get_current_burnchain_block_height()?;
+        let mut balance_info = self.get_account_stx_balance_info(principal);
+        balance_info.lock_tokens(amount, unlock_height, burn_block_height as u64)?;
+        self.set_account_stx_balance_info(principal, &balance_info);
+        Ok(())
     }
 
     pub fn get_account_nonce(&mut self, principal: &PrincipalData) -> u64 {
@@ -42,7 +42,17 @@ macro_rules! clarity_serializable {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FungibleTokenMetadata {
-    pub total_supply: Option<u128>
+    pub total_supply: Option<u128>,
+    // tokens without a total supply that were defined before their circulating supply was
+    //   tracked were minted without counting it.
+    #[serde(default)]
+    pub tracks_supply: bool,
+}
+
+impl FungibleTokenMetadata {
+    pub fn has_known_supply(&self) -> bool {
+        self.total_supply.is_some() || self.tracks_supply
+    }
 }
 
 clarity_serializable!(FungibleTokenMetadata);
@@ -77,6 +87,98 @@ pub struct ContractMetadata {
 clarity_serializable!(ContractMetadata);
 
 
+/// An account's STX balance.  Some of it may be locked up by the PoX contract until the
+/// burnchain reaches `unlock_height`, after which it is spendable again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct STXBalance {
+    pub amount_unlocked: u128,
+    pub amount_locked: u128,
+    pub unlock_height: u64
+}
+
+impl ClaritySerializable for STXBalance {
+    fn serialize(&self) -> String {
+        serde_json::to_string(self)
+            .expect("Failed to serialize STX balance")
+    }
+}
+
+impl ClarityDeserializable<STXBalance> for STXBalance {
+    /// Balances stored before tokens could be locked are a bare uSTX amount.
+    fn deserialize(json: &str) -> Self {
+        match serde_json::from_str(json) {
+            Ok(balance) => balance,
+            Err(_) => {
+                let amount_unlocked: u128 = serde_json::from_str(json)
+                    .expect("Failed to deserialize STX balance");
+                STXBalance::initial(amount_unlocked)
+            }
+        }
+    }
+}
+
+impl STXBalance {
+    pub fn zero() -> STXBalance {
+        STXBalance::initial(0)
+    }
+
+    pub fn initial(amount_unlocked: u128) -> STXBalance {
+        STXBalance {
+            amount_unlocked,
+            amount_locked: 0,
+            unlock_height: 0
+        }
+    }
+
+    /// Are any tokens still locked at the given burnchain block height?
+    pub fn has_locked_tokens_at_burn_block(&self, burn_block_height: u64) -> bool {
+        self.amount_locked > 0 && self.unlock_height > burn_block_height
+    }
+
+    /// How many uSTX can be spent at the given burnchain block height?
+    pub fn get_available_balance_at_burn_block(&self, burn_block_height: u64) -> u128 {
+        if self.has_locked_tokens_at_burn_block(burn_block_height) {
+            self.amount_unlocked
+        } else {
+            self.get_total_balance()
+        }
+    }
+
+    /// How many uSTX does this account hold, locked or not?
+    pub fn get_total_balance(&self) -> u128 {
+        self.amount_unlocked.checked_add(self.amount_locked)
+            .expect("FATAL: STX balance overflow")
+    }
+
+    /// Release the locked tokens if their lock-up has expired by the given burnchain block height.
+    pub fn unlock_available_tokens_if_any(&mut self, burn_block_height: u64) {
+        if self.amount_locked > 0 && !self.has_locked_tokens_at_burn_block(burn_block_height) {
+            self.amount_unlocked = self.get_total_balance();
+            self.amount_locked = 0;
+            self.unlock_height = 0;
+        }
+    }
+
+    /// Lock up `amount` of the spendable balance until the given burnchain block height.
+    /// Fails if there isn't enough to lock, if tokens are already locked, or if the unlock
+    /// height has already passed.
+    pub fn lock_tokens(&mut self, amount: u128, unlock_height: u64, burn_block_height: u64) -> Result<()> {
+        self.unlock_available_tokens_if_any(burn_block_height);
+        if self.amount_locked > 0 {
+            return Err(RuntimeErrorType::StxAlreadyLocked.into());
+        }
+        if unlock_height <= burn_block_height {
+            return Err(RuntimeErrorType::BadStxUnlockHeight(unlock_height, burn_block_height).into());
+        }
+
+        self.amount_unlocked = self.amount_unlocked.checked_sub(amount)
+            .ok_or(RuntimeErrorType::BadStxLockAmount(amount, self.amount_unlocked))?;
+        self.amount_locked = amount;
+        self.unlock_height = unlock_height;
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SimmedBlock {
     pub block_height: u64,
@@ -89,6 +191,22 @@ pub struct SimmedBlock {
 clarity_serializable!(SimmedBlock);
 
 clarity_serializable!(PrincipalData);
+
+// NFT owners.  A burned asset has no owner, and an owner serializes the same as a bare
+//   `PrincipalData`, so owners stored before assets could be burned still read back.
+impl ClaritySerializable for Option<PrincipalData> {
+    fn serialize(&self) -> String {
+        serde_json::to_string(self)
+            .expect("Failed to serialize vm.Value")
+    }
+}
+
+impl ClarityDeserializable<Option<PrincipalData>> for Option<PrincipalData> {
+    fn deserialize(json: &str) -> Self {
+        serde_json::from_str(json)
+            .expect("Failed to serialize vm.Value")
+    }
+}
 clarity_serializable!(i128);
 clarity_serializable!(u128);
 clarity_serializable!(u64);
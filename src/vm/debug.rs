@@ -0,0 +1,418 @@
+use std::io::Write;
+
+use vm::contexts::{Environment, LocalContext};
+use vm::coverage::expr_line;
+use vm::errors::InterpreterResult as Result;
+use vm::representations::{SymbolicExpression, SymbolicExpressionType};
+use vm::types::QualifiedContractIdentifier;
+use vm::{execute, Value};
+
+const HELP: &str = "Commands:
+  break [contract:]line   stop when evaluation reaches a line (b)
+  delete [contract:]line  remove a breakpoint (d)
+  breakpoints             list the breakpoints
+  step                    stop at the next expression, entering function calls (s)
+  next                    stop at the next expression, without entering this one (n)
+  finish                  stop once the current function returns (f)
+  continue                run until the next breakpoint (c)
+  locals                  print the local variable bindings (l)
+  var NAME                print a data var of the current contract (v)
+  map NAME KEY            print a map entry of the current contract (m)
+  cost                    print the execution cost so far
+  backtrace               print the function call stack (bt)
+  help                    print this message (h)";
+
+// expressions are printed on one line, and cut short if they're long
+const MAX_EXPR_DISPLAY_LEN: usize = 80;
+
+/// A source line of a contract to stop at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub contract: QualifiedContractIdentifier,
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum StepMode {
+    /// only stop at breakpoints
+    Continue,
+    /// stop at the next expression
+    StepIn,
+    /// stop at the next expression that isn't nested deeper than the given frame
+    StepOver(usize),
+    /// stop at the next expression outside of a function call stack of the given depth
+    StepOut(usize),
+}
+
+/// An interactive debugger for the evaluator.  When installed in a `GlobalContext`, the evaluator
+/// hands it every expression before evaluating it, and the debugger stops at breakpoints and while
+/// stepping, reading commands from its command source until told to resume.
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    mode: StepMode,
+    /// (contract, line) of each expression currently being evaluated, innermost last
+    frames: Vec<(QualifiedContractIdentifier, u32)>,
+    /// the frame the debugger last stopped at, whose result gets printed
+    stopped_frame: Option<usize>,
+    commands: Box<dyn Iterator<Item=String>>,
+    output: Box<dyn Write>,
+}
+
+impl Debugger {
+    /// Make a debugger that stops at the first expression evaluated.  Once `commands` runs dry,
+    /// the debugger lets evaluation run to completion.
+    pub fn new(commands: Box<dyn Iterator<Item=String>>, output: Box<dyn Write>) -> Debugger {
+        Debugger {
+            breakpoints: Vec::new(),
+            mode: StepMode::StepIn,
+            frames: Vec::new(),
+            stopped_frame: None,
+            commands,
+            output
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, contract: QualifiedContractIdentifier, line: u32) {
+        let breakpoint = Breakpoint { contract, line };
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn get_breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Stop again at the next expression evaluated
+    pub fn pause(&mut self) {
+        self.mode = StepMode::StepIn;
+    }
+
+    /// Called by the evaluator before evaluating `expr`.  Stops and takes commands if a breakpoint
+    /// was hit or a step finished.
+    pub fn begin_eval(&mut self, env: &mut Environment, context: &LocalContext, expr: &SymbolicExpression) {
+        let contract = env.contract_context.contract_identifier.clone();
+        let line = expr_line(expr);
+
+        // a breakpoint is hit by the outermost expression that starts on its line, so that it
+        //  stops once per evaluation of that line.
+        let hit_breakpoint = self.frames.last() != Some(&(contract.clone(), line)) &&
+            self.breakpoints.iter().any(|b| b.contract == contract && b.line == line);
+
+        self.frames.push((contract, line));
+
+        // only function applications are worth stopping at
+        if let SymbolicExpressionType::List(_) = expr.expr {
+        } else {
+            return;
+        }
+
+        let step_done = match self.mode {
+            StepMode::Continue => false,
+            StepMode::StepIn => true,
+            StepMode::StepOver(depth) => self.frames.len() <= depth,
+            StepMode::StepOut(call_depth) => env.call_stack.depth() < call_depth,
+        };
+
+        if hit_breakpoint || step_done {
+            self.stop(env, context, expr);
+        }
+    }
+
+    /// Called by the evaluator after evaluating `expr`
+    pub fn finish_eval(&mut self, _expr: &SymbolicExpression, result: &Result<Value>) {
+        if self.stopped_frame == Some(self.frames.len()) {
+            self.stopped_frame = None;
+            match result {
+                Ok(value) => self.say(&format!("=> {}", value)),
+                Err(error) => self.say(&format!("=> error: {}", error)),
+            }
+        }
+        self.frames.pop();
+    }
+
+    fn say(&mut self, message: &str) {
+        // the debugger's output is best-effort: failing to write it shouldn't fail evaluation
+        let _ = writeln!(self.output, "{}", message);
+    }
+
+    fn stop(&mut self, env: &mut Environment, context: &LocalContext, expr: &SymbolicExpression) {
+        self.stopped_frame = Some(self.frames.len());
+
+        let mut expr_str = expr.to_string();
+        if expr_str.len() > MAX_EXPR_DISPLAY_LEN {
+            let cut = (0..MAX_EXPR_DISPLAY_LEN).rev().find(|i| expr_str.is_char_boundary(*i)).unwrap_or(0);
+            expr_str.truncate(cut);
+            expr_str.push_str("...");
+        }
+        let location = format!("{}:{}: {}", env.contract_context.contract_identifier, expr_line(expr), expr_str);
+        self.say(&location);
+
+        loop {
+            let _ = write!(self.output, "(debug) ");
+            let _ = self.output.flush();
+
+            let command = match self.commands.next() {
+                Some(command) => command,
+                None => {
+                    // nothing more to read: detach, and let evaluation finish
+                    self.say("");
+                    self.breakpoints.clear();
+                    self.mode = StepMode::Continue;
+                    return;
+                }
+            };
+
+            let mut words = command.trim().splitn(2, char::is_whitespace);
+            let name = words.next().unwrap_or("");
+            let rest = words.next().unwrap_or("").trim();
+
+            match name {
+                "" => {},
+                "s" | "step" => {
+                    self.mode = StepMode::StepIn;
+                    return;
+                },
+                "n" | "next" => {
+                    self.mode = StepMode::StepOver(self.frames.len());
+                    return;
+                },
+                "f" | "finish" => {
+                    self.mode = StepMode::StepOut(env.call_stack.depth());
+                    return;
+                },
+                "c" | "continue" => {
+                    self.mode = StepMode::Continue;
+                    return;
+                },
+                "b" | "break" => {
+                    match parse_location(rest, &env.contract_context.contract_identifier) {
+                        Some((contract, line)) => {
+                            self.say(&format!("Breakpoint set at {}:{}", contract, line));
+                            self.add_breakpoint(contract, line);
+                        },
+                        None => self.say("Usage: break [contract:]line")
+                    }
+                },
+                "d" | "delete" => {
+                    match parse_location(rest, &env.contract_context.contract_identifier) {
+                        Some((contract, line)) => {
+                            let breakpoint = Breakpoint { contract, line };
+                            let count = self.breakpoints.len();
+                            self.breakpoints.retain(|b| *b != breakpoint);
+                            if self.breakpoints.len() < count {
+                                self.say(&format!("Breakpoint deleted at {}:{}", breakpoint.contract, breakpoint.line));
+                            } else {
+                                self.say(&format!("No breakpoint at {}:{}", breakpoint.contract, breakpoint.line));
+                            }
+                        },
+                        None => self.say("Usage: delete [contract:]line")
+                    }
+                },
+                "breakpoints" => {
+                    let listing: Vec<_> = self.breakpoints.iter()
+                        .map(|b| format!("{}:{}", b.contract, b.line))
+                        .collect();
+                    for entry in listing {
+                        self.say(&entry);
+                    }
+                },
+                "l" | "locals" => {
+                    for (name, value) in local_bindings(context) {
+                        self.say(&format!("{} = {}", name, value));
+                    }
+                },
+                "v" | "var" => {
+                    let message = match env.global_context.database.lookup_variable(&env.contract_context.contract_identifier, rest) {
+                        Ok(value) => format!("{} = {}", rest, value),
+                        Err(error) => format!("Failed to read data var {}: {}", rest, error)
+                    };
+                    self.say(&message);
+                },
+                "m" | "map" => {
+                    let mut words = rest.splitn(2, char::is_whitespace);
+                    let map_name = words.next().unwrap_or("");
+                    let key_str = words.next().unwrap_or("").trim();
+                    if map_name.is_empty() || key_str.is_empty() {
+                        self.say("Usage: map NAME KEY");
+                        continue;
+                    }
+
+                    let message = match execute(key_str) {
+                        Ok(Some(key)) => {
+                            match env.global_context.database.fetch_entry(&env.contract_context.contract_identifier, map_name, &key) {
+                                Ok(value) => format!("{}[{}] = {}", map_name, key, value),
+                                Err(error) => format!("Failed to read map {}: {}", map_name, error)
+                            }
+                        },
+                        Ok(None) => format!("Not a value: {}", key_str),
+                        Err(error) => format!("Failed to evaluate {}: {}", key_str, error)
+                    };
+                    self.say(&message);
+                },
+                "cost" => {
+                    let message = format!("{}", env.global_context.cost_track.get_total());
+                    self.say(&message);
+                },
+                "bt" | "backtrace" => {
+                    for function in env.call_stack.make_stack_trace().iter().rev() {
+                        self.say(&format!("{}", function));
+                    }
+                },
+                "h" | "help" => self.say(HELP),
+                _ => self.say(&format!("Unknown command: {}.  Try 'help'.", name))
+            }
+        }
+    }
+}
+
+/// Parse `contract:line` or `line`, where a bare line refers to `current_contract`
+fn parse_location(location: &str, current_contract: &QualifiedContractIdentifier) -> Option<(QualifiedContractIdentifier, u32)> {
+    match location.rfind(':') {
+        Some(split) => {
+            let contract = QualifiedContractIdentifier::parse(&location[..split]).ok()?;
+            let line = location[split+1..].parse().ok()?;
+            Some((contract, line))
+        },
+        None => {
+            let line = location.parse().ok()?;
+            Some((current_contract.clone(), line))
+        }
+    }
+}
+
+/// The variables bound in a local context, innermost first.  Shadowed bindings are left out.
+fn local_bindings(context: &LocalContext) -> Vec<(String, Value)> {
+    let mut bindings: Vec<(String, Value)> = vec![];
+    let mut current = Some(context);
+    while let Some(context) = current {
+        let mut names: Vec<_> = context.variables.keys().collect();
+        names.sort();
+        for name in names {
+            if !bindings.iter().any(|(bound, _)| bound.as_str() == name.as_str()) {
+                bindings.push((name.to_string(), context.variables[name].clone()));
+            }
+        }
+        current = context.parent;
+    }
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+    use vm::contexts::OwnedEnvironment;
+    use vm::database::MemoryBackingStore;
+    use vm::types::PrincipalData;
+
+    const COUNTER: &str = "(define-data-var count int 0)
+(define-map seen ((who principal)) ((times int)))
+(define-public (bump (by int))
+  (begin
+    (var-set count (+ (var-get count) by))
+    (map-set seen ((who tx-sender)) ((times by)))
+    (ok (var-get count))))
+";
+
+    const CALLER: &str = "(define-public (bump-twice (by int))
+  (let ((doubled (* by 2)))
+    (contract-call? .counter bump doubled)))
+";
+
+    #[derive(Clone)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Call caller.bump-twice under a debugger fed `commands`, and return the debugger's output
+    fn debug_session(commands: &[&str]) -> String {
+        let sender = Value::from(PrincipalData::parse_standard_principal("S1G2081040G2081040G2081040G208105NK8PE5").unwrap());
+        let counter = QualifiedContractIdentifier::local("counter").unwrap();
+        let caller = QualifiedContractIdentifier::local("caller").unwrap();
+
+        let mut marf = MemoryBackingStore::new();
+        let mut env = OwnedEnvironment::new(marf.as_clarity_db());
+        env.initialize_contract(counter, COUNTER).unwrap();
+        env.initialize_contract(caller.clone(), CALLER).unwrap();
+
+        let output = SharedBuffer(Rc::new(RefCell::new(vec![])));
+        let commands: Vec<String> = commands.iter().map(|c| c.to_string()).collect();
+        env.set_debugger(Debugger::new(Box::new(commands.into_iter()), Box::new(output.clone())));
+
+        let (result, _, _) = env.execute_transaction(sender, caller, "bump-twice",
+                                                     &[SymbolicExpression::atom_value(Value::Int(3))]).unwrap();
+        assert_eq!(result, Value::okay(Value::Int(6)).unwrap());
+        assert!(env.take_debugger().is_some());
+
+        let transcript = String::from_utf8(output.0.borrow().clone()).unwrap();
+        transcript
+    }
+
+    #[test]
+    fn test_breakpoints_across_contract_call() {
+        let transcript = debug_session(&["break S1G2081040G2081040G2081040G208105NK8PE5.counter:6", "continue",
+                                         "locals", "var count", "map seen {who: 'S1G2081040G2081040G2081040G208105NK8PE5}",
+                                         "bt", "continue"]);
+        let expected = vec![
+            // stopped at the start of bump-twice's body
+            "S1G2081040G2081040G2081040G208105NK8PE5.caller:2: ( let",
+            "Breakpoint set at S1G2081040G2081040G2081040G208105NK8PE5.counter:6",
+            // and then inside counter's bump, after the var-set ran but before the map-set
+            "S1G2081040G2081040G2081040G208105NK8PE5.counter:6: ( map-set",
+            "by = 6",
+            "count = 6",
+            "seen[(tuple (who S1G2081040G2081040G2081040G208105NK8PE5))] = none",
+            // the call stack, innermost first
+            "S1G2081040G2081040G2081040G208105NK8PE5.counter:bump\n",
+            "_native_:special_contract-call\n",
+            "S1G2081040G2081040G2081040G208105NK8PE5.caller:bump-twice\n",
+        ];
+        let mut rest = transcript.as_str();
+        for line in expected.iter() {
+            let found = rest.find(line).expect(&format!("missing {} in:\n{}", line, transcript));
+            rest = &rest[found + line.len()..];
+        }
+    }
+
+    #[test]
+    fn test_stepping() {
+        let transcript = debug_session(&["step", "next", "step", "locals", "finish", "cost"]);
+        let stops: Vec<_> = transcript.lines()
+            .map(|line| line.trim_start_matches("(debug) "))
+            .filter(|line| line.starts_with("S1G2081040G2081040G2081040G208105NK8PE5."))
+            .collect();
+
+        assert_eq!(stops, vec![
+            "S1G2081040G2081040G2081040G208105NK8PE5.caller:2: ( let ( ( doubled ( * by 2 ) ) ) ( contract-call? S1G2081040G2081040G2081040G20...",
+            "S1G2081040G2081040G2081040G208105NK8PE5.caller:2: ( * by 2 )",
+            "S1G2081040G2081040G2081040G208105NK8PE5.caller:3: ( contract-call? S1G2081040G2081040G2081040G208105NK8PE5.counter bump doubled )",
+            // stepped into the called contract
+            "S1G2081040G2081040G2081040G208105NK8PE5.counter:4: ( begin ( var-set count ( + ( var-get count ) by ) ) ( map-set seen ( ( who tx-...",
+        ]);
+        assert!(transcript.contains("by = 6\n"));
+        // finishing bump runs the rest of the transaction, since nothing is left to stop at, so
+        // the debugger never prompts for the last command
+        assert!(transcript.ends_with("=> (ok 6)\n"), "{}", transcript);
+        assert_eq!(transcript.matches("(debug) ").count(), 5, "{}", transcript);
+    }
+
+    #[test]
+    fn test_parse_location() {
+        let current = QualifiedContractIdentifier::local("current").unwrap();
+        assert_eq!(parse_location("12", &current), Some((current.clone(), 12)));
+        assert_eq!(parse_location("S1G2081040G2081040G2081040G208105NK8PE5.other:3", &current),
+                   Some((QualifiedContractIdentifier::local("other").unwrap(), 3)));
+        assert_eq!(parse_location("nope", &current), None);
+        assert_eq!(parse_location("bad-contract:3", &current), None);
+    }
+}
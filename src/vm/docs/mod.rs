@@ -6,27 +6,27 @@ use vm::analysis::type_checker::{TypedNativeFunction};
 use vm::analysis::type_checker::natives::SimpleNativeFunction;
 
 #[derive(Serialize)]
-struct ReferenceAPIs {
-    functions: Vec<FunctionAPI>,
-    keywords: Vec<KeywordAPI>
+pub struct ReferenceAPIs {
+    pub functions: Vec<FunctionAPI>,
+    pub keywords: Vec<KeywordAPI>
 }
 
 #[derive(Serialize, Clone)]
-struct KeywordAPI {
-    name: &'static str,
-    output_type: &'static str,
-    description: &'static str,
-    example: &'static str
+pub struct KeywordAPI {
+    pub name: &'static str,
+    pub output_type: &'static str,
+    pub description: &'static str,
+    pub example: &'static str
 }
 
 #[derive(Serialize)]
-struct FunctionAPI {
-    name: String,
-    input_type: String,
-    output_type: String,
-    signature: String,
-    description: String,
-    example: String
+pub struct FunctionAPI {
+    pub name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub signature: String,
+    pub description: String,
+    pub example: String
 }
 
 struct SimpleFunctionAPI {
@@ -59,6 +59,13 @@ const BLOCK_HEIGHT: KeywordAPI = KeywordAPI {
     example: "(> block-height 1000) ;; returns true if the current block-height has passed 1000 blocks."
 };
 
+const BURN_BLOCK_HEIGHT: KeywordAPI = KeywordAPI {
+    name: "burn-block-height",
+    output_type: "uint",
+    description: "Returns the current block height of the underlying burn blockchain as a uint",
+    example: "(> burn-block-height 1000) ;; returns true if the current height of the underlying burn blockchain has passed 1000 blocks."
+};
+
 const CONTRACT_CALLER_KEYWORD: KeywordAPI = KeywordAPI {
     name: "contract-caller",
     output_type: "principal",
@@ -357,25 +364,28 @@ inputted value.",
 };
 
 const MAP_API: SpecialAPI = SpecialAPI {
-    input_type: "Function(A) -> B, (list A)",
+    input_type: "Function(A) -> B, (list A)|buff|string-ascii|string-utf8",
     output_type: "(list B)",
     signature: "(map func list)",
     description: "The `map` function applies the input function `func` to each element of the
-input list, and outputs a list containing the _outputs_ from those function applications.",
+input list, and outputs a list containing the _outputs_ from those function applications.
+Buffers and strings may be used in place of the list, in which case `func` is applied to each
+element (a buffer or string of length 1).",
     example: "(map not (list true false true false)) ;; Returns (false true false true)"
 };
 
 const FILTER_API: SpecialAPI = SpecialAPI {
-    input_type: "Function(A) -> bool, (list A)",
-    output_type: "(list A)",
+    input_type: "Function(A) -> bool, (list A)|buff|string-ascii|string-utf8",
+    output_type: "(list A)|buff|string-ascii|string-utf8",
     signature: "(filter func list)",
     description: "The `filter` function applies the input function `func` to each element of the
-input list, and returns the same list with any elements removed for which the `func` returned `false`.",
+input list, buffer or string, and returns the same sequence with any elements removed for which
+the `func` returned `false`.",
     example: "(filter not (list true false true false)) ;; Returns (false false)"
 };
 
 const FOLD_API: SpecialAPI = SpecialAPI {
-    input_type: "Function(A, B) -> B, (list A), B",
+    input_type: "Function(A, B) -> B, (list A)|buff|string-ascii|string-utf8, B",
     output_type: "B",
     signature: "(fold func list initial-value)",
     description: "The `fold` special form applies the input function `func` to each element of the
@@ -394,13 +404,14 @@ has to be a literal function name.",
 };
 
 const CONCAT_API: SpecialAPI = SpecialAPI {
-    input_type: "(buff, buff)|(list, list)",
-    output_type: "buff|list",
+    input_type: "(buff, buff)|(string-ascii, string-ascii)|(string-utf8, string-utf8)|(list, list)",
+    output_type: "buff|string-ascii|string-utf8|list",
     signature: "(concat buff-a buff-b)",
-    description: "The `concat` function takes two buffers or two lists with the same entry type,
-and returns a concatenated buffer or list of the same entry type, with max_len = max_len_a + max_len_b.",
+    description: "The `concat` function takes two buffers, two strings of the same kind or two lists with the same entry type,
+and returns a concatenated buffer, string or list of the same entry type, with max_len = max_len_a + max_len_b.",
     example: "(concat \"hello \" \"world\") ;; Returns 0x68656c6c6f20776f726c64
-                                            ;; hex form of \"hello world\""
+                                            ;; hex form of \"hello world\"
+(concat a\"hello \" a\"world\") ;; Returns a\"hello world\""
 };
 
 const APPEND_API: SpecialAPI = SpecialAPI {
@@ -408,16 +419,16 @@ const APPEND_API: SpecialAPI = SpecialAPI {
     output_type: "list",
     signature: "(append (list 1 2 3 4) 5)",
     description: "The `append` function takes a list and another value with the same entry type,
-or a buffer and another buffer of length 1 and outputs a buffer or a list of the same type with max_len += 1.",
+or a buffer or string and another buffer or string of the same kind of length 1, and outputs a buffer, string or list of the same type with max_len += 1.",
     example: "(append (list 1 2 3 4) 5) ;; Returns (1 2 3 4 5)"
 };
 
 const ASSERTS_MAX_LEN_API: SpecialAPI = SpecialAPI {
-    input_type: "buff|list, uint",
-    output_type: "(optional buff|list)",
+    input_type: "buff|string-ascii|string-utf8|list, uint",
+    output_type: "(optional buff|string-ascii|string-utf8|list)",
     signature: "(as-max-len? buffer u10)",
-    description: "The `as-max-len?` function takes a length N (must be a literal) and a buffer or list argument, which must be typed as a list
-or buffer of length M and outputs that same list or buffer, but typed with max length N.
+    description: "The `as-max-len?` function takes a length N (must be a literal) and a buffer, string or list argument, which must be typed as a list,
+string or buffer of length M and outputs that same list, string or buffer, but typed with max length N.
 
 This function returns an optional type with the resulting iterable. If the input iterable is less than
 or equal to the supplied max-len, it returns `(some <iterable>)`, otherwise it returns `none`.",
@@ -426,15 +437,57 @@ or equal to the supplied max-len, it returns `(some <iterable>)`, otherwise it r
 };
 
 const LEN_API: SpecialAPI = SpecialAPI {
-    input_type: "buff|list",
+    input_type: "buff|string-ascii|string-utf8|list",
     output_type: "uint",
     signature: "(len buffer)",
-    description: "The `len` function returns the length of a given buffer or list.",
+    description: "The `len` function returns the length of a given buffer, string or list. For `string-utf8`
+values, the length is the number of unicode characters, not the number of bytes.",
     example: "(len \"blockstack\") ;; Returns u10
+(len u\"caf\\u{e9}\") ;; Returns u4
 (len (list 1 2 3 4 5)) ;; Returns u5
 "
 };
 
+const ELEMENT_AT_API: SpecialAPI = SpecialAPI {
+    input_type: "buff|string-ascii|string-utf8|list A, uint",
+    output_type: "(optional buff|string-ascii|string-utf8|A)",
+    signature: "(element-at sequence index)",
+    description: "The `element-at` function returns the element at position `index` (starting at `u0`) of the given
+buffer, string or list. For buffers and strings, the element is returned as a buffer or string of length 1.
+If `index` is out of bounds, the function returns `none`, otherwise it returns `(some element)`.",
+    example: "(element-at (list 4 5 6) u1) ;; Returns (some 5)
+(element-at a\"blockstack\" u5) ;; Returns (some a\"s\")
+(element-at 0x0102 u2) ;; Returns none
+"
+};
+
+const INDEX_OF_API: SpecialAPI = SpecialAPI {
+    input_type: "buff|string-ascii|string-utf8|list A, buff|string-ascii|string-utf8|A",
+    output_type: "(optional uint)",
+    signature: "(index-of sequence item)",
+    description: "The `index-of` function returns the position of the first occurrence of `item` in the given
+buffer, string or list. For buffers and strings, `item` must be a buffer or string of length 1.
+If `item` is not found, the function returns `none`, otherwise it returns `(some index)`.",
+    example: "(index-of (list 4 5 6) 5) ;; Returns (some u1)
+(index-of a\"blockstack\" a\"k\") ;; Returns (some u4)
+(index-of 0x0102 0x03) ;; Returns none
+"
+};
+
+const SLICE_API: SpecialAPI = SpecialAPI {
+    input_type: "buff|string-ascii|string-utf8|list, uint, uint",
+    output_type: "(optional buff|string-ascii|string-utf8|list)",
+    signature: "(slice? sequence left right)",
+    description: "The `slice?` function returns the elements of the given buffer, string or list from position `left`
+(inclusive) up to position `right` (exclusive). The result is typed with the same max length as the input sequence.
+If `left` is greater than `right`, or `right` is greater than the length of the sequence, the function
+returns `none`, otherwise it returns `(some sub-sequence)`.",
+    example: "(slice? (list 1 2 3 4 5) u1 u3) ;; Returns (some (2 3))
+(slice? a\"blockstack\" u5 u10) ;; Returns (some a\"stack\")
+(slice? 0x010203 u2 u1) ;; Returns none
+"
+};
+
 const LIST_API: SpecialAPI = SpecialAPI {
     input_type: "A, ...",
     output_type: "(list A)",
@@ -603,6 +656,42 @@ is supplied the hash is computed over the little-endian representation of the in
     example: "(keccak256 0) ;; Returns 0xf490de2920c8a35fabeb13208852aa28c76f9be9b03a4dd2b3c075f7a26923b4"
 };
 
+const SECP256K1RECOVER_API: SpecialAPI = SpecialAPI {
+    input_type: "(buff 32), (buff 65)",
+    output_type: "(response (buff 33) uint)",
+    signature: "(secp256k1-recover? message-hash signature)",
+    description: "The `secp256k1-recover?` function recovers the public key used to sign the message whose
+`sha256` hash is `message-hash` with the provided `signature`. The signature is 65 bytes long: the recovery id
+followed by the 64-byte compact signature. If the signature does not match, it returns the error code `(err u1)`.
+Otherwise, it returns `(ok public-key)`, where `public-key` is the 33-byte compressed public key.",
+    example: "(secp256k1-recover? 0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+ 0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e) ;; Returns (ok 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)"
+};
+
+const SECP256K1VERIFY_API: SpecialAPI = SpecialAPI {
+    input_type: "(buff 32), (buff 65), (buff 33)",
+    output_type: "bool",
+    signature: "(secp256k1-verify message-hash signature public-key)",
+    description: "The `secp256k1-verify` function verifies that the provided `signature` of the message whose
+`sha256` hash is `message-hash` was produced by the private key matching `public-key`. The signature is 65 bytes
+long: the recovery id followed by the 64-byte compact signature. Malformed and high-S signatures do not verify.",
+    example: "(secp256k1-verify 0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+ 0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e
+ 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110) ;; Returns true"
+};
+
+const PRINCIPAL_OF_API: SpecialAPI = SpecialAPI {
+    input_type: "(buff 33)",
+    output_type: "(response principal uint)",
+    signature: "(principal-of? public-key)",
+    description: "The `principal-of?` function returns the standard principal derived from the provided 33-byte
+compressed public key, using the single-signature (`p2pkh`) hash mode. If the public key is invalid, it returns
+the error code `(err u1)`.
+
+Note: the derived principal always uses the testnet address version.",
+    example: "(principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110) ;; Returns (ok ST1AW6EKPGT61SQ9FNVDS17RKNWT8ZP582VF9HSCP)"
+};
+
 const CONTRACT_CALL_API: SpecialAPI = SpecialAPI {
     input_type: "ContractName, PublicFunctionName, Arg0, ...",
     output_type: "(response A B)",
@@ -1174,6 +1263,66 @@ Otherwise, on successfuly mint, it returns `(ok true)`.
 "
 };
 
+const BURN_TOKEN: SpecialAPI = SpecialAPI {
+    input_type: "TokenName, uint, principal",
+    output_type: "(response bool uint)",
+    signature: "(ft-burn? token-name amount sender)",
+    description: "`ft-burn?` is used to decrease the token balance for the `sender` principal for a token
+type defined using `define-fungible-token`. The decreased token balance is _not_ transfered to another principal, but
+rather destroyed, reducing the circulating supply.
+
+This function returns (ok true) if the burn is successful. In the event of an unsuccessful burn it
+returns one of the following error codes:
+
+`(err u1)` -- `sender` does not have enough balance to burn this amount
+`(err u3)` -- non-positive amount provided to burn
+",
+    example: "
+(define-fungible-token stackaroo)
+(ft-mint? stackaroo u100 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF) ;; Returns (ok true)
+(ft-burn? stackaroo u50 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF) ;; Returns (ok true)
+(ft-burn? stackaroo u60 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF) ;; Returns (err u1)
+"
+};
+
+const BURN_ASSET: SpecialAPI = SpecialAPI {
+    input_type: "AssetName, A, principal",
+    output_type: "(response bool uint)",
+    signature: "(nft-burn? asset-class asset-identifier sender)",
+    description: "`nft-burn?` is used to destroy an asset currently owned by the `sender` principal.
+The asset must have been defined using `define-non-fungible-token`, and the supplied `asset-identifier` must be of the same type specified in
+that definition. Once burned, the asset no longer has an owner, and may be minted again.
+
+This function returns (ok true) if the burn is successful. In the event of an unsuccessful burn it
+returns one of the following error codes:
+
+`(err u1)` -- `sender` does not own the asset
+`(err u3)` -- asset identified by asset-identifier does not exist
+",
+    example: "
+(define-non-fungible-token stackaroo (buff 40))
+(nft-mint? stackaroo \"Roo\" 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF) ;; Returns (ok true)
+(nft-burn? stackaroo \"Roo\" 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF) ;; Returns (ok true)
+(nft-get-owner? stackaroo \"Roo\") ;; Returns none
+"
+};
+
+const GET_SUPPLY: SpecialAPI = SpecialAPI {
+    input_type: "TokenName",
+    output_type: "uint",
+    signature: "(ft-get-supply token-name)",
+    description: "`ft-get-supply` returns the circulating supply of a token type defined using
+`define-fungible-token`: the amount minted so far, less any amount that has been burned.
+Tokens without a total supply that were defined before circulating supplies were tracked have
+no known supply, and this function throws a runtime error for them.",
+    example: "
+(define-fungible-token stackaroo)
+(ft-mint? stackaroo u100 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF)
+(ft-burn? stackaroo u20 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF)
+(ft-get-supply stackaroo) ;; Returns u80
+"
+};
+
 const GET_OWNER: SpecialAPI = SpecialAPI {
     input_type: "AssetName, A",
     output_type: "(optional principal)",
@@ -1338,6 +1487,9 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         Concat => make_for_special(&CONCAT_API, name),
         AsMaxLen => make_for_special(&ASSERTS_MAX_LEN_API, name),
         Len => make_for_special(&LEN_API, name),
+        ElementAt => make_for_special(&ELEMENT_AT_API, name),
+        IndexOf => make_for_special(&INDEX_OF_API, name),
+        Slice => make_for_special(&SLICE_API, name),
         ListCons => make_for_special(&LIST_API, name),
         FetchEntry => make_for_special(&FETCH_ENTRY_API, name),
         SetEntry => make_for_special(&SET_ENTRY_API, name),
@@ -1351,6 +1503,9 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         Sha512 => make_for_special(&SHA512_API, name),
         Sha512Trunc256 => make_for_special(&SHA512T256_API, name),
         Keccak256 => make_for_special(&KECCAK256_API, name),
+        Secp256k1Recover => make_for_special(&SECP256K1RECOVER_API, name),
+        Secp256k1Verify => make_for_special(&SECP256K1VERIFY_API, name),
+        PrincipalOf => make_for_special(&PRINCIPAL_OF_API, name),
         Print => make_for_special(&PRINT_API, name),
         ContractCall => make_for_special(&CONTRACT_CALL_API, name),
         ContractOf => make_for_special(&CONTRACT_OF_API, name),
@@ -1374,6 +1529,9 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         MintAsset => make_for_special(&MINT_ASSET, name),
         MintToken => make_for_special(&MINT_TOKEN, name),
         GetTokenBalance => make_for_special(&GET_BALANCE, name),
+        GetTokenSupply => make_for_special(&GET_SUPPLY, name),
+        BurnToken => make_for_special(&BURN_TOKEN, name),
+        BurnAsset => make_for_special(&BURN_ASSET, name),
         GetAssetOwner => make_for_special(&GET_OWNER, name),
         TransferToken => make_for_special(&TOKEN_TRANSFER, name),
         TransferAsset => make_for_special(&ASSET_TRANSFER, name),
@@ -1392,7 +1550,7 @@ fn make_keyword_reference(variable: &NativeVariables) -> Option<KeywordAPI> {
         NativeVariables::NativeTrue => Some(TRUE_KEYWORD.clone()),
         NativeVariables::NativeFalse => Some(FALSE_KEYWORD.clone()),
         NativeVariables::BlockHeight => Some(BLOCK_HEIGHT.clone()),
-        NativeVariables::BurnBlockHeight => None,
+        NativeVariables::BurnBlockHeight => Some(BURN_BLOCK_HEIGHT.clone()),
     }
 }
 
@@ -1436,7 +1594,7 @@ fn make_define_reference(define_type: &DefineFunctions) -> FunctionAPI {
     }
 }
 
-fn make_all_api_reference() -> ReferenceAPIs {
+pub fn make_all_api_reference() -> ReferenceAPIs {
     let mut functions: Vec<_> = NativeFunctions::ALL.iter()
         .map(|x| make_api_reference(x))
         .collect();
@@ -1490,6 +1648,9 @@ mod test {
         fn get_burn_block_time_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u64> {
             Some(1557860301)
         }
+        fn get_burn_block_height_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u32> {
+            Some(567890)
+        }
         fn get_miner_address(&self, _id_bhh: &StacksBlockId)  -> Option<StacksAddress> {
             None
         }
@@ -1575,8 +1736,7 @@ mod test {
                                |e| {
                                    e.global_context.database.set_account_stx_balance(
                                        &QualifiedContractIdentifier::local("docs-test").unwrap().into(),
-                                       10000);
-                                   Ok(())
+                                       10000)
                                }).unwrap();
             env.initialize_contract(contract_id, 
                                     &std::fs::read_to_string("sample-contracts/tokens.clar", ).unwrap()).unwrap();
@@ -54,6 +54,8 @@ pub enum RuntimeErrorType {
     ArithmeticOverflow,
     ArithmeticUnderflow,
     SupplyOverflow(u128, u128),
+    SupplyUnderflow(u128, u128),
+    SupplyUnknown,
     DivisionByZero,
     // error in parsing types
     ParseError(String),
@@ -76,6 +78,11 @@ pub enum RuntimeErrorType {
     UnknownBlockHeaderHash(BlockHeaderHash),
     BadBlockHash(Vec<u8>),
     UnwrapFailure,
+    UnknownBurnBlockHeight,
+    // error in locking up STX: (amount, available)
+    BadStxLockAmount(u128, u128),
+    StxAlreadyLocked,
+    BadStxUnlockHeight(u64, u64),
 }
 
 #[derive(Debug, PartialEq)]
@@ -0,0 +1,106 @@
+use vm::errors::{CheckErrors, InterpreterResult as Result, check_argument_count};
+use vm::types::{Value, BuffData, TypeSignature, PrincipalData, BUFF_32, BUFF_33, BUFF_65};
+use vm::representations::SymbolicExpression;
+use vm::costs::cost_functions;
+use vm::{LocalContext, Environment, eval};
+
+use address::AddressHashMode;
+use burnchains::PublicKey;
+use chainstate::stacks::{StacksAddress, StacksPublicKey};
+use util::secp256k1::{MessageSignature, Secp256k1PublicKey};
+
+enum Secp256k1ErrorCodes { INVALID_SIGNATURE = 1 }
+enum PrincipalOfErrorCodes { INVALID_PUBLIC_KEY = 1 }
+
+macro_rules! clarity_ecode {
+    ($thing:expr) => {
+        Ok(Value::err_uint($thing as u128))
+    }
+}
+
+fn expect_buffer(expected_type: TypeSignature, input: Value) -> Result<BuffData> {
+    match input {
+        Value::Buffer(data) => Ok(data),
+        _ => Err(CheckErrors::TypeValueError(expected_type, input).into())
+    }
+}
+
+/// Signatures are 65-byte recoverable signatures, laid out as
+///   the recovery id followed by the 64-byte compact signature.
+fn to_message_signature(signature: &BuffData) -> Option<MessageSignature> {
+    if signature.data.len() != 65 {
+        return None
+    }
+    let mut bytes = [0u8; 65];
+    bytes.copy_from_slice(&signature.data);
+    Some(MessageSignature(bytes))
+}
+
+pub fn native_secp256k1_recover(message_hash: Value, signature: Value) -> Result<Value> {
+    let message_hash = expect_buffer(BUFF_32, message_hash)?;
+    let signature = expect_buffer(BUFF_65, signature)?;
+
+    let signature = match to_message_signature(&signature) {
+        Some(signature) => signature,
+        None => return clarity_ecode!(Secp256k1ErrorCodes::INVALID_SIGNATURE)
+    };
+
+    match Secp256k1PublicKey::recover_to_pubkey(&message_hash.data, &signature) {
+        Ok(public_key) => Value::okay(Value::buff_from(public_key.to_bytes_compressed())?),
+        Err(_) => clarity_ecode!(Secp256k1ErrorCodes::INVALID_SIGNATURE)
+    }
+}
+
+pub fn native_secp256k1_verify(mut args: Vec<Value>) -> Result<Value> {
+    check_argument_count(3, &args)?;
+    let public_key = args.pop().unwrap();
+    let signature = args.pop().unwrap();
+    let message_hash = args.pop().unwrap();
+
+    let message_hash = expect_buffer(BUFF_32, message_hash)?;
+    let signature = expect_buffer(BUFF_65, signature)?;
+    let public_key = expect_buffer(BUFF_33, public_key)?;
+
+    let signature = match to_message_signature(&signature) {
+        Some(signature) => signature,
+        None => return Ok(Value::Bool(false))
+    };
+    let public_key = match Secp256k1PublicKey::from_slice(&public_key.data) {
+        Ok(public_key) => public_key,
+        Err(_) => return Ok(Value::Bool(false))
+    };
+
+    // malformed hashes, unrecoverable and high-S signatures
+    //   are all treated as failed verifications.
+    let verified = public_key.verify(&message_hash.data, &signature)
+        .unwrap_or(false);
+    Ok(Value::Bool(verified))
+}
+
+/// The derived principal uses the single-sig address version of
+///   the network the VM is running on.
+pub fn special_principal_of(args: &[SymbolicExpression], env: &mut Environment, context: &LocalContext) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    runtime_cost!(cost_functions::PRINCIPAL_OF, env, 0)?;
+
+    let public_key = eval(&args[0], env, context)?;
+    let public_key = expect_buffer(BUFF_33, public_key)?;
+
+    let public_key = match StacksPublicKey::from_slice(&public_key.data) {
+        Ok(public_key) => public_key,
+        Err(_) => return clarity_ecode!(PrincipalOfErrorCodes::INVALID_PUBLIC_KEY)
+    };
+
+    let hash_mode = AddressHashMode::SerializeP2PKH;
+    let version = if env.global_context.mainnet {
+        hash_mode.to_version_mainnet()
+    } else {
+        hash_mode.to_version_testnet()
+    };
+
+    match StacksAddress::from_public_keys(version, &hash_mode, 1, &vec![public_key]) {
+        Some(address) => Value::okay(Value::Principal(PrincipalData::from(address))),
+        None => clarity_ecode!(PrincipalOfErrorCodes::INVALID_PUBLIC_KEY)
+    }
+}
@@ -1,9 +1,9 @@
 use vm::costs::{cost_functions, CostOverflowingMath};
 use vm::errors::{CheckErrors, RuntimeErrorType, InterpreterResult as Result, check_argument_count};
-use vm::types::{Value, ListData, signatures::ListTypeData, TypeSignature::BoolType, TypeSignature};
+use vm::types::{Value, ListData, StringData, signatures::ListTypeData, TypeSignature::BoolType, TypeSignature};
 use vm::representations::{SymbolicExpression, SymbolicExpressionType};
 use vm::{LocalContext, Environment, eval, apply, lookup_function};
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::cmp;
 
 pub fn list_cons(args: &[SymbolicExpression], env: &mut Environment, context: &LocalContext) -> Result<Value> {
@@ -64,6 +64,26 @@ pub fn special_filter(args: &[SymbolicExpression], env: &mut Environment, contex
             }
             Value::buff_from(filtered_vec)
         },
+        Value::String(string) => {
+            let mut filtered = match string {
+                StringData::ASCII(_) => StringData::ASCII(Default::default()),
+                StringData::UTF8(_) => StringData::UTF8(Default::default()),
+            };
+            for x in string.into_chars() {
+                let argument = [ SymbolicExpression::atom_value(x.clone()) ];
+                let filter_eval = apply(&function, &argument, env, context)?;
+                if let Value::Bool(include) = filter_eval {
+                    if include {
+                        if let Value::String(c) = x {
+                            filtered.append(c)?;
+                        }
+                    } // else, filter out.
+                } else {
+                    return Err(CheckErrors::TypeValueError(BoolType, filter_eval).into())
+                }
+            }
+            Ok(Value::String(filtered))
+        },
         _ => Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
     }
 }
@@ -91,6 +111,11 @@ pub fn special_fold(args: &[SymbolicExpression], env: &mut Environment, context:
                 SymbolicExpression::atom_value(Value::buff_from_byte(x))
             }).collect()
         },
+        Value::String(string) => {
+            string.into_chars().drain(..).map(|x| {
+                SymbolicExpression::atom_value(x)
+            }).collect()
+        },
         _ => return Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
     };
     mapped_args.iter().try_fold(initial, |acc, x| {
@@ -119,6 +144,11 @@ pub fn special_map(args: &[SymbolicExpression], env: &mut Environment, context:
                 vec![SymbolicExpression::atom_value(Value::buff_from_byte(x))]
             }).collect()
         },
+        Value::String(string) => {
+            string.into_chars().drain(..).map(|x| {
+                vec![SymbolicExpression::atom_value(x)]
+            }).collect()
+        },
         _ => return Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
     };
     let mapped_vec: Result<Vec<_>> =
@@ -152,6 +182,18 @@ pub fn special_append(args: &[SymbolicExpression], env: &mut Environment, contex
                 Err(CheckErrors::TypeValueError(entry_type, element).into())
             }
         },
+        Value::String(mut string) => {
+            let element =  eval(&args[1], env, context)?;
+            runtime_cost!(cost_functions::APPEND, env, u64::from(element.size()))?;
+            match element {
+                // strings may only be appended with a single character of the same kind
+                Value::String(c) if c.len() == 1 => {
+                    string.append(c)?;
+                    Ok(Value::String(string))
+                },
+                _ => Err(CheckErrors::TypeValueError(TypeSignature::type_of(&Value::String(string)), element).into())
+            }
+        },
         _ => Err(CheckErrors::ExpectedListApplication.into())
     }
 }
@@ -177,6 +219,10 @@ pub fn special_concat(args: &[SymbolicExpression], env: &mut Environment, contex
             data.append(&mut rhs_data.data);
             Value::buff_from(data)
         },
+        (Value::String(mut lhs_data), Value::String(rhs_data)) => {
+            lhs_data.append(rhs_data)?;
+            Ok(Value::String(lhs_data))
+        },
         (_, _) => {
             Err(RuntimeErrorType::BadTypeConstruction.into())
         }
@@ -194,6 +240,7 @@ pub fn special_as_max_len(args: &[SymbolicExpression], env: &mut Environment, co
         let iterable_len = match iterable {
             Value::List(ref list) => list.data.len(),
             Value::Buffer(ref buff) => buff.data.len(),
+            Value::String(ref string) => string.len() as usize,
             _ => return Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
         };
         if iterable_len as u128 > *expected_len {
@@ -214,6 +261,110 @@ pub fn native_len(iterable: Value) -> Result<Value> {
     match iterable {
         Value::List(list) => Ok(Value::UInt(list.data.len() as u128)),
         Value::Buffer(buff) => Ok(Value::UInt(buff.data.len() as u128)),
+        Value::String(string) => Ok(Value::UInt(string.len() as u128)),
         _ => Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
     }
 }
+
+pub fn native_element_at(iterable: Value, index: Value) -> Result<Value> {
+    let index = match index {
+        Value::UInt(index) => index,
+        _ => return Err(CheckErrors::TypeValueError(TypeSignature::UIntType, index).into())
+    };
+
+    let element = match usize::try_from(index) {
+        Ok(index) => match iterable {
+            Value::List(mut list) => {
+                if index < list.data.len() {
+                    Some(list.data.swap_remove(index))
+                } else {
+                    None
+                }
+            },
+            Value::Buffer(buff) => buff.data.get(index).map(|x| Value::buff_from_byte(*x)),
+            Value::String(string) => string.element_at(index),
+            _ => return Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
+        },
+        // an index this large cannot be in bounds.
+        Err(_) => None
+    };
+
+    match element {
+        Some(element) => Value::some(element),
+        None => Ok(Value::none())
+    }
+}
+
+pub fn special_index_of(args: &[SymbolicExpression], env: &mut Environment, context: &LocalContext) -> Result<Value> {
+    check_argument_count(2, args)?;
+
+    let iterable = eval(&args[0], env, context)?;
+    let item = eval(&args[1], env, context)?;
+
+    // the scan is linear in the sequence, so that's what gets charged.
+    runtime_cost!(cost_functions::INDEX_OF, env, iterable.size())?;
+
+    let position = match iterable {
+        Value::List(list) => list.data.iter().position(|x| *x == item),
+        Value::Buffer(buff) => match item {
+            Value::Buffer(ref item_buff) if item_buff.data.len() == 1 =>
+                buff.data.iter().position(|x| *x == item_buff.data[0]),
+            _ => None
+        },
+        Value::String(string) => {
+            string.into_chars().iter().position(|x| *x == item)
+        },
+        _ => return Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
+    };
+
+    match position {
+        Some(position) => Value::some(Value::UInt(position as u128)),
+        None => Ok(Value::none())
+    }
+}
+
+pub fn special_slice(args: &[SymbolicExpression], env: &mut Environment, context: &LocalContext) -> Result<Value> {
+    check_argument_count(3, args)?;
+
+    let iterable = eval(&args[0], env, context)?;
+    let left = eval(&args[1], env, context)?;
+    let right = eval(&args[2], env, context)?;
+
+    runtime_cost!(cost_functions::SLICE, env, iterable.size())?;
+
+    let (left, right) = match (left, right) {
+        (Value::UInt(left), Value::UInt(right)) => (left, right),
+        (Value::UInt(_), right) => return Err(CheckErrors::TypeValueError(TypeSignature::UIntType, right).into()),
+        (left, _) => return Err(CheckErrors::TypeValueError(TypeSignature::UIntType, left).into()),
+    };
+
+    // out-of-bounds or inverted ranges yield none, rather than a runtime error.
+    let bounds = |len: usize| {
+        if left <= right && right <= len as u128 {
+            Some((left as usize, right as usize))
+        } else {
+            None
+        }
+    };
+
+    let sliced = match iterable {
+        Value::List(mut list) => match bounds(list.data.len()) {
+            Some((left, right)) => {
+                let data = list.data.drain(left..right).collect();
+                Value::list_with_type(data, list.type_signature)?
+            },
+            None => return Ok(Value::none())
+        },
+        Value::Buffer(buff) => match bounds(buff.data.len()) {
+            Some((left, right)) => Value::buff_from(buff.data[left..right].to_vec())?,
+            None => return Ok(Value::none())
+        },
+        Value::String(string) => match bounds(string.len() as usize) {
+            Some((left, right)) => Value::String(string.slice(left, right)),
+            None => return Ok(Value::none())
+        },
+        _ => return Err(CheckErrors::ExpectedListOrBuffer(TypeSignature::type_of(&iterable)).into())
+    };
+
+    Value::some(sliced)
+}
@@ -6,6 +6,7 @@ mod boolean;
 mod database;
 mod options;
 mod assets;
+mod crypto;
 
 use vm::errors::{Error, CheckErrors, RuntimeErrorType, ShortReturnType, InterpreterResult as Result, check_argument_count, check_arguments_at_least};
 use vm::types::{Value, PrincipalData, ResponseData, TypeSignature};
@@ -42,6 +43,9 @@ define_named_enum!(NativeFunctions {
     Concat("concat"),
     AsMaxLen("as-max-len?"),
     Len("len"),
+    ElementAt("element-at"),
+    IndexOf("index-of"),
+    Slice("slice?"),
     ListCons("list"),
     FetchVar("var-get"),
     SetVar("var-set"),
@@ -57,6 +61,9 @@ define_named_enum!(NativeFunctions {
     Sha512("sha512"),
     Sha512Trunc256("sha512/256"),
     Keccak256("keccak256"),
+    Secp256k1Recover("secp256k1-recover?"),
+    Secp256k1Verify("secp256k1-verify"),
+    PrincipalOf("principal-of?"),
     Print("print"),
     ContractCall("contract-call?"),
     AsContract("as-contract"),
@@ -85,6 +92,9 @@ define_named_enum!(NativeFunctions {
     TransferAsset("nft-transfer?"),
     MintAsset("nft-mint?"),
     MintToken("ft-mint?"),
+    GetTokenSupply("ft-get-supply"),
+    BurnToken("ft-burn?"),
+    BurnAsset("nft-burn?"),
     GetStxBalance("stx-get-balance"),
     StxTransfer("stx-transfer?"),
     StxBurn("stx-burn?"),
@@ -123,6 +133,9 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
             AsMaxLen => SpecialFunction("special_as_max_len", &iterables::special_as_max_len),
             Append => SpecialFunction("special_append", &iterables::special_append),
             Len => NativeFunction("native_len", NativeHandle::SingleArg(&iterables::native_len), cost_functions::LEN),
+            ElementAt => NativeFunction("native_element_at", NativeHandle::DoubleArg(&iterables::native_element_at), cost_functions::ELEMENT_AT),
+            IndexOf => SpecialFunction("special_index_of", &iterables::special_index_of),
+            Slice => SpecialFunction("special_slice", &iterables::special_slice),
             ListCons => SpecialFunction("special_list_cons", &iterables::list_cons),
             FetchEntry => SpecialFunction("special_map-get?", &database::special_fetch_entry),
             SetEntry => SpecialFunction("special_set-entry", &database::special_set_entry),
@@ -136,6 +149,9 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
             Sha512 => NativeFunction("native_sha512", NativeHandle::SingleArg(&native_sha512), cost_functions::SHA512),
             Sha512Trunc256 => NativeFunction("native_sha512trunc256", NativeHandle::SingleArg(&native_sha512trunc256), cost_functions::SHA512T256),
             Keccak256 => NativeFunction("native_keccak256", NativeHandle::SingleArg(&native_keccak256), cost_functions::KECCAK256),
+            Secp256k1Recover => NativeFunction("native_secp256k1_recover", NativeHandle::DoubleArg(&crypto::native_secp256k1_recover), cost_functions::SECP256K1RECOVER),
+            Secp256k1Verify => NativeFunction("native_secp256k1_verify", NativeHandle::MoreArg(&crypto::native_secp256k1_verify), cost_functions::SECP256K1VERIFY),
+            PrincipalOf => SpecialFunction("special_principal_of", &crypto::special_principal_of),
             Print => SpecialFunction("special_print", &special_print),
             ContractCall => SpecialFunction("special_contract-call", &database::special_contract_call),
             AsContract => SpecialFunction("special_as-contract", &special_as_contract),
@@ -160,7 +176,10 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
             MintToken => SpecialFunction("special_mint_token", &assets::special_mint_token),
             TransferAsset => SpecialFunction("special_transfer_asset", &assets::special_transfer_asset),
             TransferToken => SpecialFunction("special_transfer_token", &assets::special_transfer_token),
+            BurnAsset => SpecialFunction("special_burn_asset", &assets::special_burn_asset),
+            BurnToken => SpecialFunction("special_burn_token", &assets::special_burn_token),
             GetTokenBalance => SpecialFunction("special_get_balance", &assets::special_get_balance),
+            GetTokenSupply => SpecialFunction("special_get_supply", &assets::special_get_supply),
             GetAssetOwner => SpecialFunction("special_get_owner", &assets::special_get_owner),
             AtBlock => SpecialFunction("special_at_block", &database::special_at_block),
             GetStxBalance => SpecialFunction("special_stx_balance", &assets::special_stx_balance),
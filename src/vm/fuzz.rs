@@ -0,0 +1,653 @@
+use std::cmp;
+use std::fmt;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use vm::analysis::contract_interface_builder::{ContractInterface, ContractInterfaceFunctionAccess};
+use vm::contexts::OwnedEnvironment;
+use vm::database::{ClarityBackingStore, ClarityDatabase, MemoryBackingStore, NULL_HEADER_DB};
+use vm::representations::SymbolicExpression;
+use vm::types::{QualifiedContractIdentifier, PrincipalData, StandardPrincipalData, TypeSignature,
+                StringSubtype, TupleData, UTF8Data, StringData};
+use vm::Value;
+
+/// Read-only functions named with this prefix, which take no arguments, are a contract's
+/// invariants: each must return `true` after the contract is deployed, and after every call.
+pub const INVARIANT_PREFIX: &str = "invariant-";
+
+// generated lists, buffers and strings are never longer than this, whatever their declared
+//   maximum length, so that each call stays cheap.
+const MAX_GENERATED_LENGTH: u32 = 16;
+
+// the shrinker gives up on finding a smaller reproduction after running this many sequences.
+const MAX_SHRINK_RUNS: u32 = 2000;
+
+const UTF8_CHARS: &[char] = &['a', 'Z', '0', ' ', '"', '\u{e9}', '\u{20ac}', '\u{8a9e}', '\u{1f600}'];
+
+/// A public function, and the types of its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzFunction {
+    pub name: String,
+    pub args: Vec<TypeSignature>,
+}
+
+/// What to fuzz in a contract: which of its public functions to call, and which invariants to check
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzTarget {
+    pub contract_identifier: QualifiedContractIdentifier,
+    pub functions: Vec<FuzzFunction>,
+    pub invariants: Vec<String>,
+    /// public functions which can't be called with generated arguments (i.e., they take a trait)
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzConfig {
+    /// number of call sequences to try
+    pub runs: u32,
+    /// number of calls in each sequence
+    pub calls_per_run: usize,
+    pub seed: u64,
+    /// the principals calls are sent from, and which principal arguments are drawn from
+    pub senders: Vec<PrincipalData>,
+}
+
+/// One call to a public function of the target contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub function: String,
+    pub sender: PrincipalData,
+    pub args: Vec<Value>,
+}
+
+/// A sequence of calls after which an invariant did not hold
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzFailure {
+    pub contract_identifier: QualifiedContractIdentifier,
+    /// which of the runs found the failure
+    pub run: u32,
+    pub calls: Vec<Call>,
+    pub invariant: String,
+    /// what the invariant returned, or why it failed to evaluate
+    pub result: String,
+}
+
+impl FuzzTarget {
+    /// Fuzz every public function of the contract which can be called with generated arguments.
+    pub fn from_interface(contract_identifier: QualifiedContractIdentifier, interface: &ContractInterface) -> FuzzTarget {
+        let mut functions = vec![];
+        let mut invariants = vec![];
+        let mut skipped = vec![];
+        for function in interface.functions.iter() {
+            match function.access {
+                ContractInterfaceFunctionAccess::public => {
+                    let args: Option<Vec<_>> = function.args.iter()
+                        .map(|arg| arg.type_f.to_type_signature().filter(can_generate))
+                        .collect();
+                    match args {
+                        Some(args) => functions.push(FuzzFunction { name: function.name.clone(), args }),
+                        None => skipped.push(function.name.clone())
+                    }
+                },
+                ContractInterfaceFunctionAccess::read_only => {
+                    if function.name.starts_with(INVARIANT_PREFIX) && function.args.is_empty() {
+                        invariants.push(function.name.clone());
+                    }
+                },
+                ContractInterfaceFunctionAccess::private => {}
+            }
+        }
+        FuzzTarget { contract_identifier, functions, invariants, skipped }
+    }
+
+    fn get_function(&self, name: &str) -> Option<&FuzzFunction> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl FuzzConfig {
+    pub fn new(senders: Vec<PrincipalData>) -> FuzzConfig {
+        FuzzConfig { runs: 100, calls_per_run: 20, seed: 0, senders }
+    }
+}
+
+/// A few standard principals to send calls from, for callers with none in particular in mind
+pub fn default_senders() -> Vec<PrincipalData> {
+    (1..4).map(|i| PrincipalData::from(StandardPrincipalData(26, [i; 20]))).collect()
+}
+
+impl Call {
+    /// This call as Clarity source
+    pub fn to_clarity(&self, contract_identifier: &QualifiedContractIdentifier) -> String {
+        let mut out = format!("(contract-call? '{} {}", contract_identifier, self.function);
+        for arg in self.args.iter() {
+            out.push(' ');
+            out.push_str(&to_clarity_literal(arg));
+        }
+        out.push(')');
+        out
+    }
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} failed after {} call(s), returning {}:", self.invariant, self.calls.len(), self.result)?;
+        for call in self.calls.iter() {
+            writeln!(f, "  {} ;; sent by '{}", call.to_clarity(&self.contract_identifier), call.sender)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a value as a Clarity expression which evaluates to it
+pub fn to_clarity_literal(value: &Value) -> String {
+    match value {
+        Value::Principal(principal) => format!("'{}", principal),
+        Value::List(list) => {
+            let mut out = "(list".to_string();
+            for item in list.data.iter() {
+                out.push(' ');
+                out.push_str(&to_clarity_literal(item));
+            }
+            out.push(')');
+            out
+        },
+        Value::Tuple(tuple) => {
+            let mut out = "(tuple".to_string();
+            for (name, item) in tuple.data_map.iter() {
+                out.push_str(&format!(" ({} {})", name.as_str(), to_clarity_literal(item)));
+            }
+            out.push(')');
+            out
+        },
+        Value::Optional(optional) => match optional.data {
+            Some(ref item) => format!("(some {})", to_clarity_literal(item)),
+            None => "none".to_string()
+        },
+        Value::Response(response) => {
+            let constructor = if response.committed { "ok" } else { "err" };
+            format!("({} {})", constructor, to_clarity_literal(&response.data))
+        },
+        _ => value.to_string()
+    }
+}
+
+// values can be generated for every type a public function may take, save traits.
+fn can_generate(sig: &TypeSignature) -> bool {
+    match sig {
+        TypeSignature::NoType | TypeSignature::TraitReferenceType(_) => false,
+        TypeSignature::ListType(list_type) => can_generate(list_type.get_list_item_type()),
+        TypeSignature::TupleType(tuple_type) => tuple_type.get_type_map().values().all(can_generate),
+        TypeSignature::OptionalType(inner) => can_generate(inner),
+        TypeSignature::ResponseType(inner) => can_generate(&inner.0) && can_generate(&inner.1),
+        _ => true
+    }
+}
+
+fn random_length<R: Rng>(rng: &mut R, max_len: u32) -> usize {
+    let max_len = cmp::min(max_len, MAX_GENERATED_LENGTH);
+    if rng.gen_bool(0.2) {
+        max_len as usize
+    } else {
+        rng.gen_range(0, max_len + 1) as usize
+    }
+}
+
+fn random_uint<R: Rng>(rng: &mut R) -> u128 {
+    match rng.gen_range(0, 4) {
+        0 => *[0, 1, u128::MAX].choose(rng).unwrap(),
+        1 | 2 => rng.gen_range(0, 1000),
+        _ => rng.gen()
+    }
+}
+
+fn random_int<R: Rng>(rng: &mut R) -> i128 {
+    match rng.gen_range(0, 4) {
+        0 => *[0, 1, -1, i128::MAX, i128::MIN].choose(rng).unwrap(),
+        1 | 2 => rng.gen_range(-1000, 1000),
+        _ => rng.gen()
+    }
+}
+
+/// Generate a random value of the given type.  Principals are drawn from `principals`.
+pub fn random_value<R: Rng>(rng: &mut R, sig: &TypeSignature, principals: &[PrincipalData]) -> Option<Value> {
+    let value = match sig {
+        TypeSignature::NoType | TypeSignature::TraitReferenceType(_) => return None,
+        TypeSignature::IntType => Value::Int(random_int(rng)),
+        TypeSignature::UIntType => Value::UInt(random_uint(rng)),
+        TypeSignature::BoolType => Value::Bool(rng.gen()),
+        TypeSignature::PrincipalType => Value::Principal(principals.choose(rng)?.clone()),
+        TypeSignature::BufferType(max_len) => {
+            let len = random_length(rng, max_len.into());
+            Value::buff_from((0..len).map(|_| rng.gen()).collect()).ok()?
+        },
+        TypeSignature::StringType(StringSubtype::ASCII(max_len)) => {
+            let len = random_length(rng, max_len.into());
+            Value::string_ascii_from_bytes((0..len).map(|_| rng.gen_range(0x20, 0x7f)).collect()).ok()?
+        },
+        TypeSignature::StringType(StringSubtype::UTF8(max_len)) => {
+            let len = random_length(rng, max_len.into());
+            let string = (0..len).map(|_| *UTF8_CHARS.choose(rng).unwrap()).collect();
+            Value::string_utf8_from_string_utf8_literal(string).ok()?
+        },
+        TypeSignature::ListType(list_type) => {
+            let len = random_length(rng, list_type.get_max_len());
+            let items = (0..len).map(|_| random_value(rng, list_type.get_list_item_type(), principals))
+                .collect::<Option<Vec<_>>>()?;
+            Value::list_with_type(items, list_type.clone()).ok()?
+        },
+        TypeSignature::TupleType(tuple_type) => {
+            let items = tuple_type.get_type_map().iter()
+                .map(|(name, item_type)| random_value(rng, item_type, principals).map(|item| (name.clone(), item)))
+                .collect::<Option<Vec<_>>>()?;
+            Value::Tuple(TupleData::from_data(items).ok()?)
+        },
+        TypeSignature::OptionalType(inner) => {
+            if rng.gen_bool(0.25) {
+                Value::none()
+            } else {
+                Value::some(random_value(rng, inner, principals)?).ok()?
+            }
+        },
+        TypeSignature::ResponseType(inner) => {
+            if rng.gen() {
+                Value::okay(random_value(rng, &inner.0, principals)?).ok()?
+            } else {
+                Value::error(random_value(rng, &inner.1, principals)?).ok()?
+            }
+        }
+    };
+    Some(value)
+}
+
+fn random_call<R: Rng>(rng: &mut R, target: &FuzzTarget, config: &FuzzConfig, principals: &[PrincipalData]) -> Option<Call> {
+    let function = target.functions.choose(rng)?;
+    let sender = config.senders.choose(rng)?.clone();
+    let args = function.args.iter()
+        .map(|arg_type| random_value(rng, arg_type, principals))
+        .collect::<Option<Vec<_>>>()?;
+    Some(Call { function: function.name.clone(), sender, args })
+}
+
+/// Smaller values of the same type, which a failing call might still fail with.  `simplest` is
+/// the principal that principals simplify to.
+fn simpler_values(value: &Value, sig: &TypeSignature, simplest: &PrincipalData) -> Vec<Value> {
+    let mut simpler = match (value, sig) {
+        (Value::Int(int), _) => vec![Value::Int(0), Value::Int(int / 2)],
+        (Value::UInt(int), _) => vec![Value::UInt(0), Value::UInt(int / 2)],
+        (Value::Bool(_), _) => vec![Value::Bool(false)],
+        (Value::Principal(_), _) => vec![Value::Principal(simplest.clone())],
+        (Value::Buffer(buff), _) => {
+            let len = buff.data.len();
+            vec![Value::buff_from(vec![]), Value::buff_from(buff.data[..len / 2].to_vec())]
+                .into_iter().filter_map(Result::ok).collect()
+        },
+        (Value::String(StringData::ASCII(string)), _) => {
+            let len = string.data.len();
+            vec![Value::string_ascii_from_bytes(vec![]), Value::string_ascii_from_bytes(string.data[..len / 2].to_vec())]
+                .into_iter().filter_map(Result::ok).collect()
+        },
+        (Value::String(StringData::UTF8(string)), _) => {
+            let len = string.data.len();
+            vec![Value::String(StringData::UTF8(UTF8Data { data: vec![] })),
+                 Value::String(StringData::UTF8(UTF8Data { data: string.data[..len / 2].to_vec() }))]
+        },
+        (Value::List(list), TypeSignature::ListType(list_type)) => {
+            let len = list.data.len();
+            let mut candidates = vec![vec![], list.data[..len / 2].to_vec()];
+            for (index, item) in list.data.iter().enumerate() {
+                for simpler_item in simpler_values(item, list_type.get_list_item_type(), simplest) {
+                    let mut items = list.data.clone();
+                    items[index] = simpler_item;
+                    candidates.push(items);
+                }
+            }
+            candidates.into_iter()
+                .filter_map(|items| Value::list_with_type(items, list_type.clone()).ok())
+                .collect()
+        },
+        (Value::Tuple(tuple), TypeSignature::TupleType(tuple_type)) => {
+            let mut candidates = vec![];
+            for (name, item_type) in tuple_type.get_type_map().iter() {
+                let item = match tuple.data_map.get(name) {
+                    Some(item) => item,
+                    None => continue
+                };
+                for simpler_item in simpler_values(item, item_type, simplest) {
+                    let mut items = tuple.data_map.clone();
+                    items.insert(name.clone(), simpler_item);
+                    if let Ok(data) = TupleData::from_data(items.into_iter().collect()) {
+                        candidates.push(Value::Tuple(data));
+                    }
+                }
+            }
+            candidates
+        },
+        (Value::Optional(optional), TypeSignature::OptionalType(inner)) => match optional.data {
+            Some(ref item) => {
+                let mut candidates = vec![Value::none()];
+                candidates.extend(simpler_values(item, inner, simplest).into_iter()
+                                  .filter_map(|item| Value::some(item).ok()));
+                candidates
+            },
+            None => vec![]
+        },
+        (Value::Response(response), TypeSignature::ResponseType(inner)) => {
+            if response.committed {
+                simpler_values(&response.data, &inner.0, simplest).into_iter()
+                    .filter_map(|item| Value::okay(item).ok()).collect()
+            } else {
+                simpler_values(&response.data, &inner.1, simplest).into_iter()
+                    .filter_map(|item| Value::error(item).ok()).collect()
+            }
+        },
+        _ => vec![]
+    };
+    simpler.retain(|candidate| candidate != value);
+    simpler.dedup();
+    simpler
+}
+
+/// Deploy the target into a fresh store with `deploy`, then make each call in turn, checking the
+/// invariants once deployed and after every call.  Calls which error are aborted, as they would
+/// be in a block.  Returns the number of calls made and the invariant which failed, along with
+/// its result, if any failed.
+pub fn run_sequence<F>(target: &FuzzTarget, calls: &[Call], deploy: &mut F) -> Result<Option<(usize, String, String)>, String>
+where F: FnMut(&mut dyn ClarityBackingStore) -> Result<(), String> {
+    let mut store = MemoryBackingStore::new();
+    deploy(&mut store)?;
+
+    let db = ClarityDatabase::new(&mut store, &NULL_HEADER_DB);
+    let mut vm_env = OwnedEnvironment::new(db);
+    if let Some((invariant, result)) = check_invariants(target, &mut vm_env) {
+        return Ok(Some((0, invariant, result)));
+    }
+    for (index, call) in calls.iter().enumerate() {
+        let args: Vec<_> = call.args.iter().cloned().map(SymbolicExpression::atom_value).collect();
+        // errors abort the call, and leave nothing else to check
+        let _ = vm_env.execute_transaction(Value::Principal(call.sender.clone()), target.contract_identifier.clone(),
+                                           &call.function, &args);
+        if let Some((invariant, result)) = check_invariants(target, &mut vm_env) {
+            return Ok(Some((index + 1, invariant, result)));
+        }
+    }
+    Ok(None)
+}
+
+fn check_invariants(target: &FuzzTarget, vm_env: &mut OwnedEnvironment) -> Option<(String, String)> {
+    for invariant in target.invariants.iter() {
+        let result = match vm_env.eval_read_only(&target.contract_identifier, &format!("({})", invariant)) {
+            Ok((Value::Bool(true), _, _)) => continue,
+            Ok((value, _, _)) => to_clarity_literal(&value),
+            Err(e) => e.to_string()
+        };
+        return Some((invariant.clone(), result));
+    }
+    None
+}
+
+/// Make random sequences of calls to the target's public functions, from random senders and
+/// with random arguments, until one breaks an invariant.  Each sequence runs against a fresh
+/// store, set up by `deploy`.  A failing sequence is shrunk before it is returned: calls are
+/// dropped, and arguments and senders simplified, for as long as the same invariant still fails.
+pub fn fuzz<F>(target: &FuzzTarget, config: &FuzzConfig, mut deploy: F) -> Result<Option<FuzzFailure>, String>
+where F: FnMut(&mut dyn ClarityBackingStore) -> Result<(), String> {
+    if config.senders.is_empty() {
+        return Err("Fuzzing needs at least one sender".to_string());
+    }
+
+    let mut principals = config.senders.clone();
+    principals.push(PrincipalData::Contract(target.contract_identifier.clone()));
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    for run in 0..config.runs {
+        let calls: Vec<_> = (0..config.calls_per_run)
+            .filter_map(|_| random_call(&mut rng, target, config, &principals))
+            .collect();
+        if let Some((made, invariant, result)) = run_sequence(target, &calls, &mut deploy)? {
+            let mut shrinker = Shrinker { target, simplest: &config.senders[0], deploy: &mut deploy,
+                                          invariant, result, runs: 0 };
+            let calls = shrinker.shrink(calls[..made].to_vec())?;
+            let Shrinker { invariant, result, .. } = shrinker;
+            return Ok(Some(FuzzFailure {
+                contract_identifier: target.contract_identifier.clone(),
+                run, calls, invariant, result
+            }));
+        }
+    }
+    Ok(None)
+}
+
+struct Shrinker<'a, F> {
+    target: &'a FuzzTarget,
+    simplest: &'a PrincipalData,
+    deploy: &'a mut F,
+    invariant: String,
+    result: String,
+    runs: u32,
+}
+
+impl <'a, F> Shrinker<'a, F> where F: FnMut(&mut dyn ClarityBackingStore) -> Result<(), String> {
+    // if the calls still break the same invariant, the calls up to the point that it breaks
+    fn still_fails(&mut self, calls: &[Call]) -> Result<Option<Vec<Call>>, String> {
+        if self.runs >= MAX_SHRINK_RUNS {
+            return Ok(None);
+        }
+        self.runs += 1;
+        match run_sequence(self.target, calls, self.deploy)? {
+            Some((made, invariant, result)) if invariant == self.invariant => {
+                self.result = result;
+                Ok(Some(calls[..made].to_vec()))
+            },
+            _ => Ok(None)
+        }
+    }
+
+    fn shrink(&mut self, mut calls: Vec<Call>) -> Result<Vec<Call>, String> {
+        loop {
+            let mut progress = false;
+
+            let mut index = 0;
+            while index < calls.len() {
+                let mut candidate = calls.clone();
+                candidate.remove(index);
+                match self.still_fails(&candidate)? {
+                    Some(shrunk) => {
+                        calls = shrunk;
+                        progress = true;
+                    },
+                    None => index += 1
+                }
+            }
+
+            let mut index = 0;
+            while index < calls.len() {
+                if &calls[index].sender != self.simplest {
+                    let mut candidate = calls.clone();
+                    candidate[index].sender = self.simplest.clone();
+                    if let Some(shrunk) = self.still_fails(&candidate)? {
+                        calls = shrunk;
+                        progress = true;
+                    }
+                }
+
+                let arg_types = match self.target.get_function(&calls[index].function) {
+                    Some(function) => function.args.clone(),
+                    None => vec![]
+                };
+                for (arg_index, arg_type) in arg_types.iter().enumerate() {
+                    // keep simplifying this argument until none of its simpler values fail
+                    let mut simplified = true;
+                    while simplified && index < calls.len() {
+                        simplified = false;
+                        for simpler in simpler_values(&calls[index].args[arg_index], arg_type, self.simplest) {
+                            let mut candidate = calls.clone();
+                            candidate[index].args[arg_index] = simpler;
+                            if let Some(shrunk) = self.still_fails(&candidate)? {
+                                calls = shrunk;
+                                progress = true;
+                                simplified = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+                index += 1;
+            }
+
+            if !progress || self.runs >= MAX_SHRINK_RUNS {
+                return Ok(calls);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use vm::ClarityName;
+    use vm::analysis::mem_type_check;
+    use vm::analysis::contract_interface_builder::build_contract_interface;
+    use vm::types::ListTypeData;
+
+    const COUNTER: &str = "
+        (define-trait adder ((add (uint) (response bool uint))))
+        (define-data-var counter uint u0)
+        (define-data-var last-sender (optional principal) none)
+        (define-public (add (amount uint))
+          (begin
+            (var-set counter (+ (var-get counter) amount))
+            (var-set last-sender (some tx-sender))
+            (ok (var-get counter))))
+        (define-public (reset (memo (list 3 (buff 2))) (labels (tuple (name (string-ascii 4)) (tag (optional (string-utf8 2))))))
+          (begin
+            (var-set counter u0)
+            (ok (len memo))))
+        (define-public (delegate (target <adder>)) (ok true))
+        (define-read-only (get-counter) (var-get counter))
+        (define-read-only (invariant-below-limit) (< (var-get counter) u1000))
+        (define-read-only (invariant-not-checked (x uint)) false)";
+
+    fn counter_target() -> FuzzTarget {
+        let analysis = mem_type_check(COUNTER).unwrap().1;
+        FuzzTarget::from_interface(QualifiedContractIdentifier::transient(), &build_contract_interface(&analysis))
+    }
+
+    fn deploy_counter(store: &mut dyn ClarityBackingStore) -> Result<(), String> {
+        let db = ClarityDatabase::new(store, &NULL_HEADER_DB);
+        let mut vm_env = OwnedEnvironment::new(db);
+        vm_env.initialize_contract(QualifiedContractIdentifier::transient(), COUNTER)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_fuzz_target() {
+        let target = counter_target();
+        let names: Vec<_> = target.functions.iter().map(|function| function.name.as_str()).collect();
+        assert_eq!(names, vec!["add", "reset"]);
+        assert_eq!(target.functions[0].args, vec![TypeSignature::UIntType]);
+        assert_eq!(target.invariants, vec!["invariant-below-limit".to_string()]);
+        assert_eq!(target.skipped, vec!["delegate".to_string()]);
+    }
+
+    #[test]
+    fn test_random_values() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let principals = default_senders();
+        let target = counter_target();
+        let reset = target.get_function("reset").unwrap();
+        let list_type = ListTypeData::new_list(TypeSignature::UIntType, 300).unwrap();
+
+        let mut lengths = vec![];
+        for _ in 0..200 {
+            for arg_type in reset.args.iter() {
+                let value = random_value(&mut rng, arg_type, &principals).unwrap();
+                assert!(arg_type.admits(&value));
+                if let Value::List(ref list) = value {
+                    lengths.push(list.data.len());
+                }
+            }
+            // long lists are capped
+            match random_value(&mut rng, &TypeSignature::ListType(list_type.clone()), &principals).unwrap() {
+                Value::List(list) => assert!(list.data.len() <= MAX_GENERATED_LENGTH as usize),
+                _ => panic!("Expected a list")
+            }
+            match random_value(&mut rng, &TypeSignature::PrincipalType, &principals).unwrap() {
+                Value::Principal(principal) => assert!(principals.contains(&principal)),
+                _ => panic!("Expected a principal")
+            }
+        }
+        assert!(lengths.iter().all(|len| *len <= 3));
+        assert!(lengths.contains(&0) && lengths.contains(&3));
+
+        assert_eq!(random_value(&mut rng, &TypeSignature::NoType, &principals), None);
+    }
+
+    #[test]
+    fn test_to_clarity() {
+        let call = Call {
+            function: "reset".to_string(),
+            sender: default_senders()[0].clone(),
+            args: vec![
+                Value::list_from(vec![Value::buff_from(vec![1, 2]).unwrap()]).unwrap(),
+                Value::Tuple(TupleData::from_data(vec![
+                    (ClarityName::try_from("name".to_string()).unwrap(), Value::string_ascii_from_bytes(b"a\"b".to_vec()).unwrap()),
+                    (ClarityName::try_from("tag".to_string()).unwrap(), Value::some(Value::Principal(default_senders()[1].clone())).unwrap())]).unwrap()),
+                Value::error(Value::Int(-1)).unwrap(),
+            ]
+        };
+        assert_eq!(call.to_clarity(&QualifiedContractIdentifier::local("counter").unwrap()),
+                   format!("(contract-call? 'S1G2081040G2081040G2081040G208105NK8PE5.counter reset (list 0x0102) \
+                            (tuple (name a\"a\\\"b\") (tag (some '{}))) (err -1))", default_senders()[1]));
+    }
+
+    #[test]
+    fn test_fuzz_shrinks() {
+        let target = counter_target();
+        let mut config = FuzzConfig::new(default_senders());
+        config.seed = 7;
+        config.calls_per_run = 10;
+
+        let failure = fuzz(&target, &config, deploy_counter).unwrap().unwrap();
+        assert_eq!(failure.invariant, "invariant-below-limit");
+        assert_eq!(failure.result, "false");
+
+        // a single call that puts the counter over the limit
+        assert_eq!(failure.calls.len(), 1);
+        assert_eq!(failure.calls[0].function, "add");
+        assert_eq!(failure.calls[0].sender, config.senders[0]);
+        match failure.calls[0].args[0] {
+            Value::UInt(amount) => assert!(amount >= 1000 && amount < 2000),
+            _ => panic!("Expected a uint")
+        }
+        assert!(format!("{}", failure).starts_with("invariant-below-limit failed after 1 call(s), returning false:\n  (contract-call? '"));
+
+        // the same seed finds the same failure
+        assert_eq!(fuzz(&target, &config, deploy_counter).unwrap(), Some(failure));
+    }
+
+    #[test]
+    fn test_fuzz_passes() {
+        let mut target = counter_target();
+        target.invariants = vec!["get-counter".to_string()];
+        let mut config = FuzzConfig::new(default_senders());
+        config.runs = 10;
+
+        // invariants must return exactly true
+        let failure = fuzz(&target, &config, deploy_counter).unwrap().unwrap();
+        assert_eq!(failure.calls, vec![]);
+        assert_eq!(failure.result, "u0");
+
+        // the counter is reset before it can pass the limit
+        target.invariants = vec!["invariant-below-limit".to_string()];
+        target.functions.retain(|function| function.name == "reset");
+        assert_eq!(fuzz(&target, &config, deploy_counter).unwrap(), None);
+
+        // deployment failures are passed on
+        assert_eq!(fuzz(&target, &config, |_: &mut dyn ClarityBackingStore| Err("no".to_string())), Err("no".to_string()));
+    }
+}
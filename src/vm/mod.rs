@@ -16,12 +16,15 @@ pub mod contexts;
 pub mod database;
 pub mod clarity;
 
-mod functions;
+pub mod functions;
 mod variables;
 mod callables;
 
 pub mod docs;
 pub mod analysis;
+pub mod coverage;
+pub mod debug;
+pub mod fuzz;
 
 #[cfg(test)]
 pub mod tests;
@@ -107,12 +110,15 @@ pub fn apply(function: &CallableType, args: &[SymbolicExpression],
 
     if let CallableType::SpecialFunction(_, function) = function {
         env.call_stack.insert(&identifier, track_recursion);
+        env.global_context.cost_track.profile_enter(&identifier);
         let mut resp = function(args, env, context);
         add_stack_trace(&mut resp, env);
+        env.global_context.cost_track.profile_exit();
         env.call_stack.remove(&identifier, track_recursion)?;
         resp
     } else {
         env.call_stack.insert(&identifier, track_recursion);
+        env.global_context.cost_track.profile_enter(&identifier);
 
         let mut used_memory = 0;
         let mut evaluated_args = vec![];
@@ -121,6 +127,7 @@ pub fn apply(function: &CallableType, args: &[SymbolicExpression],
                 Ok(x) => x,
                 Err(e) => {
                     env.drop_memory(used_memory);
+                    env.global_context.cost_track.profile_exit();
                     env.call_stack.remove(&identifier, track_recursion)?;
                     return Err(e)
                 }
@@ -130,6 +137,7 @@ pub fn apply(function: &CallableType, args: &[SymbolicExpression],
                 Ok(_x) => {},
                 Err(e) => {
                     env.drop_memory(used_memory);
+                    env.global_context.cost_track.profile_exit();
                     env.call_stack.remove(&identifier, track_recursion)?;
                     return Err(Error::from(e))
                 }
@@ -148,6 +156,7 @@ pub fn apply(function: &CallableType, args: &[SymbolicExpression],
         };
         add_stack_trace(&mut resp, env);
         env.drop_memory(used_memory);
+        env.global_context.cost_track.profile_exit();
         env.call_stack.remove(&identifier, track_recursion)?;
         resp
     }
@@ -156,7 +165,17 @@ pub fn apply(function: &CallableType, args: &[SymbolicExpression],
 pub fn eval <'a> (exp: &SymbolicExpression, env: &'a mut Environment, context: &LocalContext) -> Result<Value> {
     use vm::representations::SymbolicExpressionType::{AtomValue, Atom, List, LiteralValue, TraitReference, Field};
 
-    match exp.expr {
+    if let Some(ref mut coverage_tracker) = env.global_context.coverage_reporting {
+        coverage_tracker.report_eval(&env.contract_context.contract_identifier, exp);
+    }
+
+    // the debugger is taken out of the context while it runs, so that it can inspect the environment
+    if let Some(mut debugger) = env.global_context.debugger.take() {
+        debugger.begin_eval(env, context, exp);
+        env.global_context.debugger = Some(debugger);
+    }
+
+    let res = match exp.expr {
         AtomValue(ref value) | LiteralValue(ref value) => Ok(value.clone()),
         Atom(ref value) => lookup_variable(&value, context, env),
         List(ref children) => {
@@ -168,7 +187,17 @@ pub fn eval <'a> (exp: &SymbolicExpression, env: &'a mut Environment, context: &
             apply(&f, &rest, env, context)
         },
         TraitReference(_, _) | Field(_) => unreachable!("can't be evaluated"),
+    };
+
+    if let Some(ref mut coverage_tracker) = env.global_context.coverage_reporting {
+        coverage_tracker.report_result(&env.contract_context.contract_identifier, exp, &res);
+    }
+
+    if let Some(ref mut debugger) = env.global_context.debugger {
+        debugger.finish_eval(exp, &res);
     }
+
+    res
 }
 
 
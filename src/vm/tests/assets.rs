@@ -525,6 +525,133 @@ fn total_supply(owned_env: &mut OwnedEnvironment) {
     });
 }
 
+fn test_token_burn_and_supply(owned_env: &mut OwnedEnvironment) {
+    let contract = "(define-fungible-token stackaroos u100)
+         (define-read-only (get-supply)
+            (ft-get-supply stackaroos))
+         (define-read-only (get-balance (account principal))
+            (ft-get-balance stackaroos account))
+         (define-public (burn (amount uint))
+            (ft-burn? stackaroos amount tx-sender))
+         (define-public (faucet)
+            (ft-mint? stackaroos u60 tx-sender))";
+
+    let p1 = execute("'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR");
+
+    let p1_principal = match p1 {
+        Value::Principal(PrincipalData::Standard(ref data)) => data.clone(),
+        _ => panic!()
+    };
+
+    let token_contract_id = QualifiedContractIdentifier::new(p1_principal.clone(), "tokens".into());
+    let token_identifier = AssetIdentifier { contract_identifier: token_contract_id.clone(),
+                                             asset_name: "stackaroos".into() };
+
+    owned_env.initialize_contract(token_contract_id.clone(), contract).unwrap();
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "get-supply", &vec![]).unwrap();
+    assert_eq!(result, Value::UInt(0));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "faucet", &vec![]).unwrap();
+    assert!(is_committed(&result));
+
+    let (result, asset_map, events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "burn", &symbols_from_values(vec![Value::UInt(50)])).unwrap();
+    assert!(is_committed(&result));
+    assert_eq!(events.len(), 1);
+
+    // burned tokens are tracked as sent by the burning principal
+    let asset_map = asset_map.to_table();
+    assert_eq!(asset_map[&PrincipalData::Standard(p1_principal.clone())][&token_identifier], AssetMapEntry::Token(50));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "get-supply", &vec![]).unwrap();
+    assert_eq!(result, Value::UInt(10));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "get-balance", &symbols_from_values(vec![p1.clone()])).unwrap();
+    assert_eq!(result, Value::UInt(10));
+
+    let (result, asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "burn", &symbols_from_values(vec![Value::UInt(11)])).unwrap();
+    assert!(is_err_code(&result, 1));
+    assert_eq!(asset_map.to_table().len(), 0);
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "burn", &symbols_from_values(vec![Value::UInt(0)])).unwrap();
+    assert!(is_err_code(&result, 3));
+
+    // burning frees up room under the total supply cap
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "faucet", &vec![]).unwrap();
+    assert!(is_committed(&result));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &token_contract_id, "get-supply", &vec![]).unwrap();
+    assert_eq!(result, Value::UInt(70));
+}
+
+fn test_asset_burn(owned_env: &mut OwnedEnvironment) {
+    let contract = "(define-non-fungible-token names int)
+         (define-public (mint (name int))
+            (nft-mint? names name tx-sender))
+         (define-public (burn (name int))
+            (nft-burn? names name tx-sender))
+         (define-read-only (owner (name int))
+            (nft-get-owner? names name))";
+
+    let p1 = execute("'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR");
+    let p2 = execute("'SM2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQVX8X0G");
+
+    let p1_principal = match p1 {
+        Value::Principal(PrincipalData::Standard(ref data)) => data.clone(),
+        _ => panic!()
+    };
+
+    let names_contract_id = QualifiedContractIdentifier::new(p1_principal.clone(), "names".into());
+    let names_identifier = AssetIdentifier { contract_identifier: names_contract_id.clone(),
+                                             asset_name: "names".into() };
+
+    owned_env.initialize_contract(names_contract_id.clone(), contract).unwrap();
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &names_contract_id, "burn", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert!(is_err_code(&result, 3));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &names_contract_id, "mint", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert!(is_committed(&result));
+
+    let (result, asset_map, _events) = execute_transaction(owned_env,
+        p2.clone(), &names_contract_id, "burn", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert!(is_err_code(&result, 1));
+    assert_eq!(asset_map.to_table().len(), 0);
+
+    let (result, asset_map, events) = execute_transaction(owned_env,
+        p1.clone(), &names_contract_id, "burn", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert!(is_committed(&result));
+    assert_eq!(events.len(), 1);
+
+    let asset_map = asset_map.to_table();
+    assert_eq!(asset_map[&PrincipalData::Standard(p1_principal.clone())][&names_identifier],
+               AssetMapEntry::Asset(vec![Value::Int(1)]));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &names_contract_id, "owner", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert_eq!(result, Value::none());
+
+    // a burned asset may be minted again
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p2.clone(), &names_contract_id, "mint", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert!(is_committed(&result));
+
+    let (result, _asset_map, _events) = execute_transaction(owned_env,
+        p1.clone(), &names_contract_id, "owner", &symbols_from_values(vec![Value::Int(1)])).unwrap();
+    assert_eq!(result, Value::some(p2.clone()).unwrap());
+}
+
 fn test_overlapping_nfts(owned_env: &mut OwnedEnvironment) {
     let tokens_contract = FIRST_CLASS_TOKENS;
     let names_contract = ASSET_NAMES;
@@ -731,9 +858,113 @@ fn test_simple_naming_system(owned_env: &mut OwnedEnvironment) {
 #[test]
 fn test_all() {
     let to_test = [test_overlapping_nfts, test_simple_token_system,
-                   test_simple_naming_system, total_supply, test_native_stx_ops];
+                   test_simple_naming_system, total_supply, test_native_stx_ops,
+                   test_token_burn_and_supply, test_asset_burn];
     for test in to_test.iter() {
         with_memory_environment(test, true);
         with_marfed_environment(test, true);
     }
 }
+
+#[test]
+fn test_stored_asset_formats() {
+    use vm::database::{MemoryBackingStore, StoreType, ClarityDatabase, ClaritySerializable};
+    use vm::types::TypeSignature;
+
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_clarity_db();
+    db.begin();
+
+    let contract_id = QualifiedContractIdentifier::local("tokens").unwrap();
+    let owner = PrincipalData::parse("SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR").unwrap();
+    db.create_non_fungible_token(&contract_id, "names", &TypeSignature::IntType);
+
+    // owners written before nft-burn? existed are bare principals
+    let legacy_key = ClarityDatabase::make_key_for_quad(&contract_id, StoreType::NonFungibleToken, "names", Value::Int(1).serialize());
+    db.put(&legacy_key, &owner);
+    assert_eq!(db.get_nft_owner(&contract_id, "names", &Value::Int(1)).unwrap(), owner);
+
+    // and are stored the same way now
+    db.set_nft_owner(&contract_id, "names", &Value::Int(2), &owner).unwrap();
+    assert_eq!(db.get::<String>(&legacy_key), db.get::<String>(&ClarityDatabase::make_key_for_quad(&contract_id, StoreType::NonFungibleToken, "names", Value::Int(2).serialize())));
+
+    db.burn_nft(&contract_id, "names", &Value::Int(1)).unwrap();
+    assert_eq!(db.get::<Option<PrincipalData>>(&legacy_key), Some(None));
+    assert_eq!(db.get_nft_owner(&contract_id, "names", &Value::Int(1)).unwrap_err(), RuntimeErrorType::NoSuchToken.into());
+    assert_eq!(db.get_nft_owner(&contract_id, "names", &Value::Int(3)).unwrap_err(), RuntimeErrorType::NoSuchToken.into());
+
+    // a corrupt owner is an error, not a crash
+    let bad_key = ClarityDatabase::make_key_for_quad(&contract_id, StoreType::NonFungibleToken, "names", Value::Int(4).serialize());
+    db.put(&bad_key, &Value::Int(4));
+    match db.get_nft_owner(&contract_id, "names", &Value::Int(4)).unwrap_err() {
+        Error::Interpreter(_) => {},
+        e => panic!("Unexpected error {:?}", e)
+    }
+
+    // tokens without a total supply have no supply entry until they're minted
+    db.create_fungible_token(&contract_id, "stackaroos", &None);
+    let supply_key = ClarityDatabase::make_key_for_trip(&contract_id, StoreType::CirculatingSupply, "stackaroos");
+    assert_eq!(db.get::<u128>(&supply_key), None);
+    assert_eq!(db.get_ft_supply(&contract_id, "stackaroos").unwrap(), 0);
+
+    db.checked_increase_token_supply(&contract_id, "stackaroos", 10).unwrap();
+    assert_eq!(db.get_ft_supply(&contract_id, "stackaroos").unwrap(), 10);
+
+    // burning more than is in circulation is an error
+    assert_eq!(db.checked_decrease_token_supply(&contract_id, "stackaroos", 11).unwrap_err(),
+               RuntimeErrorType::SupplyUnderflow(10, 11).into());
+    assert_eq!(db.get_ft_supply(&contract_id, "stackaroos").unwrap(), 10);
+    db.checked_decrease_token_supply(&contract_id, "stackaroos", 10).unwrap();
+    assert_eq!(db.get_ft_supply(&contract_id, "stackaroos").unwrap(), 0);
+
+    // tokens without a total supply defined before supply was tracked have an unknown supply,
+    //   and their mints and burns aren't counted
+    let legacy_meta_key = ClarityDatabase::make_metadata_key(StoreType::FungibleTokenMeta, "legacy-roos");
+    db.store.insert_metadata(&contract_id, &legacy_meta_key, "{\"total_supply\":null}");
+    db.checked_increase_token_supply(&contract_id, "legacy-roos", 10).unwrap();
+    db.checked_decrease_token_supply(&contract_id, "legacy-roos", 20).unwrap();
+    let legacy_supply_key = ClarityDatabase::make_key_for_trip(&contract_id, StoreType::CirculatingSupply, "legacy-roos");
+    assert_eq!(db.get::<u128>(&legacy_supply_key), None);
+    assert_eq!(db.get_ft_supply(&contract_id, "legacy-roos").unwrap_err(), RuntimeErrorType::SupplyUnknown.into());
+
+    db.roll_back();
+}
+
+#[test]
+fn test_stored_stx_balance_formats() {
+    use vm::database::{MemoryBackingStore, ClarityDatabase, STXBalance};
+
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_clarity_db();
+    db.begin();
+
+    // balances written before tokens could be locked are bare amounts
+    let owner = PrincipalData::parse("SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR").unwrap();
+    db.put(&ClarityDatabase::make_key_for_account_balance(&owner), &1000u128);
+    assert_eq!(db.get_account_stx_balance_info(&owner), STXBalance::initial(1000));
+    assert_eq!(db.get_account_stx_balance(&owner).unwrap(), 1000);
+
+    db.set_account_stx_balance(&owner, 900).unwrap();
+    assert_eq!(db.get_account_stx_balance_info(&owner), STXBalance::initial(900));
+
+    db.roll_back();
+
+    // locked tokens can't be spent until the unlock height, and can be from then on
+    let mut balance = STXBalance::initial(900);
+    balance.lock_tokens(600, 15, 1).unwrap();
+    assert_eq!(balance.get_available_balance_at_burn_block(14), 300);
+    assert_eq!(balance.get_available_balance_at_burn_block(15), 900);
+    assert_eq!(balance.get_total_balance(), 900);
+
+    balance.unlock_available_tokens_if_any(14);
+    assert_eq!(balance.amount_locked, 600);
+    balance.unlock_available_tokens_if_any(15);
+    assert_eq!(balance, STXBalance::initial(900));
+
+    // tokens can't be over-locked, locked twice, or locked until a height that has passed
+    assert!(balance.lock_tokens(901, 20, 15).is_err());
+    assert!(balance.lock_tokens(100, 15, 15).is_err());
+    balance.lock_tokens(100, 20, 15).unwrap();
+    assert!(balance.lock_tokens(100, 20, 15).is_err());
+    assert_eq!(balance.amount_unlocked, 800);
+}
@@ -50,6 +50,9 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         Concat => "(concat list-bar list-bar)",
         AsMaxLen => "(as-max-len? list-bar u3)",
         Len => "(len list-bar)",
+        ElementAt => "(element-at list-bar u0)",
+        IndexOf => "(index-of list-bar 1)",
+        Slice => "(slice? list-bar u0 u1)",
         ListCons => "(list 1 2 3 4)",
         FetchEntry => "(map-get? map-foo {a: 1})",
         SetEntry => "(map-set map-foo {a: 1} {b: 2})",
@@ -63,6 +66,9 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         Sha512 => "(sha512 1)",
         Sha512Trunc256 => "(sha512/256 1)",
         Keccak256 => "(keccak256 1)",
+        Secp256k1Recover => "(secp256k1-recover? 0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855 0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e)",
+        Secp256k1Verify => "(secp256k1-verify 0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855 0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
+        PrincipalOf => "(principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
         Print => "(print 1)",
         ContractCall => "(contract-call? .contract-other foo-exec 1)",
         ContractOf => "(contract-of contract)",
@@ -87,6 +93,9 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         MintToken => "(nft-mint? nft-foo 1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         GetTokenBalance => "(ft-get-balance ft-foo 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         GetAssetOwner => "(nft-get-owner? nft-foo 1)",
+        GetTokenSupply => "(ft-get-supply ft-foo)",
+        BurnToken => "(ft-burn? ft-foo u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
+        BurnAsset => "(nft-burn? nft-foo 1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         TransferToken => "(ft-transfer? ft-foo u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         TransferAsset => "(nft-transfer? nft-foo 1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         AtBlock => "(at-block 0x0000000000000000000000000000000000000000000000000000000000000000 1)",
@@ -171,3 +180,17 @@ fn test_all() {
         assert!(cost.exceeds(&baseline));
     }
 }
+
+#[test]
+fn test_index_of_cost_scales() {
+    // index-of scans the whole sequence, so it must cost more on a longer one.
+    //   element-at on the same list cancels out the cost of building the list.
+    let index_of_runtime = |len: usize| {
+        let list = format!("(list {})", vec!["1"; len].join(" "));
+        let scan = test_tracked_costs(&format!("(index-of {} 0)", &list));
+        let lookup = test_tracked_costs(&format!("(element-at {} u0)", &list));
+        scan.runtime - lookup.runtime
+    };
+
+    assert!(index_of_runtime(100) > index_of_runtime(1));
+}
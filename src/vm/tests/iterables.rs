@@ -162,6 +162,27 @@ fn test_simple_buff_concat() {
         RuntimeErrorType::BadTypeConstruction.into());
 }
 
+#[test]
+fn test_simple_string_concat() {
+    let tests = [
+        "(concat a\"012\" a\"34\")",
+        "(concat a\"\" a\"\")",
+        "(concat u\"caf\" u\"\\u{e9}\")"];
+
+    let expected = [
+        Value::string_ascii_from_bytes("01234".as_bytes().to_vec()).unwrap(),
+        Value::string_ascii_from_bytes(vec![]).unwrap(),
+        Value::string_utf8_from_string_utf8_literal("café".to_string()).unwrap()];
+
+    for (test, expected) in tests.iter().zip(expected.iter()) {
+        assert_eq!(expected.clone(), execute(test).unwrap().unwrap());
+    }
+
+    assert_eq!(
+        execute("(concat a\"1\" u\"1\")").unwrap_err(),
+        RuntimeErrorType::BadTypeConstruction.into());
+}
+
 #[test]
 fn test_simple_buff_assert_max_len() {
     let tests = [
@@ -195,6 +216,23 @@ fn test_simple_buff_assert_max_len() {
         CheckErrors::TypeError(UIntType, BufferType(1_u32.try_into().unwrap())).into());
 }
 
+#[test]
+fn test_simple_string_assert_max_len() {
+    let tests = [
+        "(as-max-len? a\"123\" u3)",
+        "(as-max-len? a\"123\" u2)",
+        "(as-max-len? u\"12\\u{2603}\" u3)"];
+
+    let expected = [
+        Value::some(Value::string_ascii_from_bytes(vec![49, 50, 51]).unwrap()).unwrap(),
+        Value::none(),
+        Value::some(Value::string_utf8_from_string_utf8_literal("12☃".to_string()).unwrap()).unwrap()];
+
+    for (test, expected) in tests.iter().zip(expected.iter()) {
+        assert_eq!(expected.clone(), execute(test).unwrap().unwrap());
+    }
+}
+
 #[test]
 fn test_simple_list_assert_max_len() {
     let tests = [
@@ -260,6 +298,21 @@ fn test_simple_filter_buffer() {
     assert_eq!(expected, execute(test1).unwrap().unwrap());
 }
 
+#[test]
+fn test_simple_filter_string() {
+    let test1 = "(define-private (test (x (string-ascii 1))) (not (is-eq x a\"0\")))
+                 (filter test a\"000123\")";
+
+    let expected = Value::string_ascii_from_bytes(vec![49, 50, 51]).unwrap();
+    assert_eq!(expected, execute(test1).unwrap().unwrap());
+
+    let test2 = "(define-private (test (x (string-utf8 1))) (not (is-eq x u\"a\")))
+                 (filter test u\"a\\u{1F600}a\")";
+
+    let expected = Value::string_utf8_from_string_utf8_literal("😀".to_string()).unwrap();
+    assert_eq!(expected, execute(test2).unwrap().unwrap());
+}
+
 #[test]
 fn test_list_tuple_admission() {
     let test = 
@@ -341,6 +394,157 @@ fn test_buff_len() {
     assert_eq!(expected, execute(test1).unwrap().unwrap());
 }
 
+#[test]
+fn test_string_len() {
+    let tests = [
+        "(len a\"blockstack\")",
+        "(len u\"block\\u{1F600}stack\")"];
+    let expected = [
+        Value::UInt(10),
+        Value::UInt(11)];
+
+    for (test, expected) in tests.iter().zip(expected.iter()) {
+        assert_eq!(expected.clone(), execute(test).unwrap().unwrap());
+    }
+}
+
+#[test]
+fn test_string_map_and_append() {
+    let test1 =
+        "(define-private (double (x (string-ascii 1))) (concat x x))
+         (map double a\"abc\")";
+
+    let expected = Value::list_from(vec![
+        Value::string_ascii_from_bytes("aa".as_bytes().to_vec()).unwrap(),
+        Value::string_ascii_from_bytes("bb".as_bytes().to_vec()).unwrap(),
+        Value::string_ascii_from_bytes("cc".as_bytes().to_vec()).unwrap()]).unwrap();
+    assert_eq!(expected, execute(test1).unwrap().unwrap());
+
+    let test2 = "(append a\"ab\" a\"c\")";
+    assert_eq!(Value::string_ascii_from_bytes("abc".as_bytes().to_vec()).unwrap(),
+               execute(test2).unwrap().unwrap());
+
+    execute("(append a\"ab\" a\"cd\")").unwrap_err();
+    execute("(append a\"ab\" a\"\")").unwrap_err();
+    execute("(append a\"ab\" u\"c\")").unwrap_err();
+}
+
+#[test]
+fn test_element_at() {
+    let tests = [
+        "(element-at (list 1 2 3) u0)",
+        "(element-at (list 1 2 3) u2)",
+        "(element-at (list 1 2 3) u3)",
+        "(element-at (list) u0)",
+        "(element-at 0x010203 u1)",
+        "(element-at 0x010203 u3)",
+        "(element-at a\"blockstack\" u5)",
+        "(element-at u\"caf\\u{e9}\" u3)"];
+
+    let expected = [
+        Value::some(Value::Int(1)).unwrap(),
+        Value::some(Value::Int(3)).unwrap(),
+        Value::none(),
+        Value::none(),
+        Value::some(Value::buff_from_byte(2)).unwrap(),
+        Value::none(),
+        Value::some(Value::string_ascii_from_bytes("s".as_bytes().to_vec()).unwrap()).unwrap(),
+        Value::some(Value::string_utf8_from_string_utf8_literal("é".to_string()).unwrap()).unwrap()];
+
+    for (test, expected) in tests.iter().zip(expected.iter()) {
+        assert_eq!(expected.clone(), execute(test).unwrap().unwrap());
+    }
+
+    assert_eq!(
+        execute("(element-at (list 1 2 3) 1)").unwrap_err(),
+        CheckErrors::TypeValueError(UIntType, Value::Int(1)).into());
+
+    assert_eq!(
+        execute("(element-at 1 u1)").unwrap_err(),
+        CheckErrors::ExpectedListOrBuffer(IntType).into());
+
+    assert_eq!(
+        execute("(element-at (list 1 2 3))").unwrap_err(),
+        CheckErrors::IncorrectArgumentCount(2, 1).into());
+}
+
+#[test]
+fn test_index_of() {
+    let tests = [
+        "(index-of (list 1 2 3 2) 2)",
+        "(index-of (list 1 2 3) 4)",
+        "(index-of (list) 1)",
+        "(index-of 0x010203 0x03)",
+        "(index-of 0x010203 0x0203)",
+        "(index-of \"blockstack\" \"s\")",
+        "(index-of u\"caf\\u{e9}\" u\"\\u{e9}\")",
+        "(index-of (list (some 1) none) none)"];
+
+    let expected = [
+        Value::some(Value::UInt(1)).unwrap(),
+        Value::none(),
+        Value::none(),
+        Value::some(Value::UInt(2)).unwrap(),
+        Value::none(),
+        Value::some(Value::UInt(5)).unwrap(),
+        Value::some(Value::UInt(3)).unwrap(),
+        Value::some(Value::UInt(1)).unwrap()];
+
+    for (test, expected) in tests.iter().zip(expected.iter()) {
+        assert_eq!(expected.clone(), execute(test).unwrap().unwrap());
+    }
+
+    assert_eq!(
+        execute("(index-of 1 1)").unwrap_err(),
+        CheckErrors::ExpectedListOrBuffer(IntType).into());
+}
+
+#[test]
+fn test_slice() {
+    let tests = [
+        "(slice? (list 1 2 3 4) u1 u3)",
+        "(slice? (list 1 2 3 4) u0 u4)",
+        "(slice? (list 1 2 3 4) u2 u2)",
+        "(slice? (list 1 2 3 4) u3 u1)",
+        "(slice? (list 1 2 3 4) u1 u5)",
+        "(slice? 0x01020304 u1 u3)",
+        "(slice? a\"blockstack\" u0 u5)",
+        "(slice? u\"caf\\u{e9}s\" u2 u4)"];
+
+    let expected = [
+        Value::some(Value::list_from(vec![Value::Int(2), Value::Int(3)]).unwrap()).unwrap(),
+        Value::some(Value::list_from(vec![Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4)]).unwrap()).unwrap(),
+        Value::some(Value::list_with_type(vec![], ListTypeData::new_list(IntType, 4).unwrap()).unwrap()).unwrap(),
+        Value::none(),
+        Value::none(),
+        Value::some(Value::buff_from(vec![2, 3]).unwrap()).unwrap(),
+        Value::some(Value::string_ascii_from_bytes("block".as_bytes().to_vec()).unwrap()).unwrap(),
+        Value::some(Value::string_utf8_from_string_utf8_literal("fé".to_string()).unwrap()).unwrap()];
+
+    for (test, expected) in tests.iter().zip(expected.iter()) {
+        assert_eq!(expected.clone(), execute(test).unwrap().unwrap());
+    }
+
+    // the slice keeps the max length of the sliced list
+    let test1 =
+        "(define-private (first-two (l (list 4 int))) (slice? l u0 u2))
+         (unwrap-panic (as-max-len? (unwrap-panic (first-two (list 1 2 3))) u4))";
+    assert_eq!(Value::list_from(vec![Value::Int(1), Value::Int(2)]).unwrap(),
+               execute(test1).unwrap().unwrap());
+
+    assert_eq!(
+        execute("(slice? (list 1 2 3) u0 2)").unwrap_err(),
+        CheckErrors::TypeValueError(UIntType, Value::Int(2)).into());
+
+    assert_eq!(
+        execute("(slice? 1 u0 u1)").unwrap_err(),
+        CheckErrors::ExpectedListOrBuffer(IntType).into());
+
+    assert_eq!(
+        execute("(slice? (list 1 2 3) u0)").unwrap_err(),
+        CheckErrors::IncorrectArgumentCount(3, 2).into());
+}
+
 
 #[test]
 fn test_construct_bad_list() {
@@ -4,7 +4,7 @@ use vm::errors::{CheckErrors, ShortReturnType, RuntimeErrorType, Error};
 use vm::{Value, LocalContext, ContractContext, GlobalContext, Environment, CallStack};
 use vm::contexts::{OwnedEnvironment};
 use vm::callables::DefinedFunction;
-use vm::types::{TypeSignature, BuffData, QualifiedContractIdentifier};
+use vm::types::{TypeSignature, BuffData, PrincipalData, QualifiedContractIdentifier, BUFF_32, BUFF_33};
 use vm::ast::parse;
 use vm::costs::LimitedCostTracker;
 use util::hash::{hex_bytes, to_hex};
@@ -146,6 +146,93 @@ fn test_keccak256() {
         .for_each(|(program, expectation)| assert_eq!(to_buffer(expectation), execute(program)));
 }
 
+#[test]
+fn test_secp256k1() {
+    let message_hash = "0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    let other_hash = "0xde5b9eb9e7c5592930eb2e30a01369c36586d872082ed8181ee83d2a0ec20f04";
+    let signature = "0x01cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e";
+    let bad_recovery_id = "0x04cdda8ee3793b123f5e6a88094a67dd8a0a940f8eaff8880298edea342c22f11d36362cf1636d2cd1f7d8f26125740ced3e9062aa23711f3af77326a0755f990e";
+    let public_key = "0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110";
+    let other_public_key = "0x02adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110";
+    let bad_public_key = "0x05adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110";
+
+    let tests = [
+        format!("(secp256k1-recover? {} {})", message_hash, signature),
+        format!("(secp256k1-recover? {} {})", message_hash, bad_recovery_id),
+        format!("(secp256k1-recover? {} 0x01cdda)", message_hash),
+        format!("(secp256k1-recover? 0x01 {})", signature),
+        format!("(secp256k1-verify {} {} {})", message_hash, signature, public_key),
+        format!("(secp256k1-verify {} {} {})", other_hash, signature, public_key),
+        format!("(secp256k1-verify {} {} {})", message_hash, signature, other_public_key),
+        format!("(secp256k1-verify {} {} {})", message_hash, bad_recovery_id, public_key),
+        format!("(secp256k1-verify {} {} {})", message_hash, signature, bad_public_key),
+        format!("(secp256k1-verify {} 0x01cdda {})", message_hash, public_key),
+    ];
+
+    let expectations = [
+        Value::okay(Value::buff_from(hex_bytes(&public_key[2..]).unwrap()).unwrap()).unwrap(),
+        Value::err_uint(1),
+        Value::err_uint(1),
+        Value::err_uint(1),
+        Value::Bool(true),
+        Value::Bool(false),
+        Value::Bool(false),
+        Value::Bool(false),
+        Value::Bool(false),
+        Value::Bool(false),
+    ];
+
+    tests.iter().zip(expectations.iter())
+        .for_each(|(program, expectation)| assert_eq!(expectation.clone(), execute(program)));
+
+    let errors = [
+        format!("(secp256k1-recover? {})", message_hash),
+        format!("(secp256k1-verify {} {})", message_hash, signature),
+        format!("(secp256k1-verify 1 {} {})", signature, public_key),
+    ];
+
+    let error_expectations: &[Error] = &[
+        CheckErrors::IncorrectArgumentCount(2, 1).into(),
+        CheckErrors::IncorrectArgumentCount(3, 2).into(),
+        CheckErrors::TypeValueError(BUFF_32, Value::Int(1)).into(),
+    ];
+
+    for (program, expectation) in errors.iter().zip(error_expectations.iter()) {
+        assert_eq!(*expectation, vm_execute(program).unwrap_err());
+    }
+}
+
+#[test]
+fn test_principal_of() {
+    let tests = [
+        "(principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
+        "(is-eq (principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)
+                (ok 'ST1AW6EKPGT61SQ9FNVDS17RKNWT8ZP582VF9HSCP))",
+        "(principal-of? 0x05adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
+        "(principal-of? 0x03adb8)"];
+
+    let expectations = [
+        Value::okay(Value::Principal(PrincipalData::parse_standard_principal("ST1AW6EKPGT61SQ9FNVDS17RKNWT8ZP582VF9HSCP").unwrap().into())).unwrap(),
+        Value::Bool(true),
+        Value::err_uint(1),
+        Value::err_uint(1)];
+
+    tests.iter().zip(expectations.iter())
+        .for_each(|(program, expectation)| assert_eq!(expectation.clone(), execute(program)));
+
+    assert_eq!(vm_execute("(principal-of? 1)").unwrap_err(),
+               CheckErrors::TypeValueError(BUFF_33, Value::Int(1)).into());
+
+    // on mainnet, the same key derives a mainnet principal
+    let mut marf = MemoryBackingStore::new();
+    let mut env = OwnedEnvironment::new(marf.as_clarity_db());
+    env.set_mainnet(true);
+    let (result, _, _) = env.eval_raw(
+        "(principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)").unwrap();
+    assert_eq!(result,
+               Value::okay(Value::Principal(PrincipalData::parse_standard_principal("SP1AW6EKPGT61SQ9FNVDS17RKNWT8ZP582RS0JSRJ").unwrap().into())).unwrap());
+}
+
 #[test]
 fn test_buffer_equality() {
     let tests = [
@@ -13,7 +13,7 @@ use util::hash;
 pub use vm::types::signatures::{
     TupleTypeSignature, AssetIdentifier, FixedFunction, FunctionSignature,
     TypeSignature, FunctionType, ListTypeData, FunctionArg, parse_name_type_pairs,
-    BUFF_64, BUFF_32, BUFF_20, BufferLength
+    BUFF_65, BUFF_64, BUFF_33, BUFF_32, BUFF_20, BufferLength, StringSubtype, StringUTF8Length
 };
 
 pub const MAX_VALUE_SIZE: u32 = 1024 * 1024; // 1MB
@@ -36,6 +36,23 @@ pub struct BuffData {
     pub data: Vec<u8>,
 }
 
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ASCIIData {
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct UTF8Data {
+    // each entry holds the UTF-8 encoding of a single unicode scalar value
+    pub data: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StringData {
+    ASCII(ASCIIData),
+    UTF8(UTF8Data),
+}
+
 #[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 pub struct ListData {
     pub data: Vec<Value>,
@@ -175,6 +192,7 @@ pub enum Value {
     UInt(u128),
     Bool(bool),
     Buffer(BuffData),
+    String(StringData),
     List(ListData),
     Principal(PrincipalData),
     Tuple(TupleData),
@@ -343,6 +361,120 @@ impl Value {
     pub fn buff_from_byte(byte: u8) -> Value {
         Value::Buffer(BuffData { data: vec![byte] })
     }
+
+    pub fn string_ascii_from_bytes(bytes: Vec<u8>) -> Result<Value> {
+        // check the string length
+        BufferLength::try_from(bytes.len())?;
+
+        for b in bytes.iter() {
+            if !b.is_ascii_alphanumeric() && !b.is_ascii_punctuation() && !b.is_ascii_whitespace() {
+                return Err(CheckErrors::InvalidCharactersDetected.into());
+            }
+        }
+        Ok(Value::String(StringData::ASCII(ASCIIData { data: bytes })))
+    }
+
+    pub fn string_utf8_from_string_utf8_literal(tokenized_str: String) -> Result<Value> {
+        let mut data = vec![];
+        for c in tokenized_str.chars() {
+            let mut encoded_char = vec![0; c.len_utf8()];
+            c.encode_utf8(&mut encoded_char[..]);
+            data.push(encoded_char);
+        }
+        // check the string length
+        StringUTF8Length::try_from(data.len())?;
+
+        Ok(Value::String(StringData::UTF8(UTF8Data { data })))
+    }
+
+    pub fn string_utf8_from_bytes(bytes: Vec<u8>) -> Result<Value> {
+        let validated_utf8_str = match std::str::from_utf8(&bytes) {
+            Ok(string) => string,
+            _ => return Err(CheckErrors::InvalidUTF8Encoding.into())
+        };
+        Value::string_utf8_from_string_utf8_literal(validated_utf8_str.to_string())
+    }
+}
+
+impl ASCIIData {
+    pub fn len(&self) -> BufferLength {
+        self.data.len().try_into().unwrap()
+    }
+}
+
+impl UTF8Data {
+    pub fn len(&self) -> StringUTF8Length {
+        self.data.len().try_into().unwrap()
+    }
+
+    /// The concatenated UTF-8 encoding of this string.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.data.iter().flatten().cloned().collect()
+    }
+}
+
+impl StringData {
+    /// The number of characters in this string.
+    pub fn len(&self) -> u32 {
+        match self {
+            StringData::ASCII(data) => data.len().into(),
+            StringData::UTF8(data) => data.len().into(),
+        }
+    }
+
+    pub fn type_signature(&self) -> TypeSignature {
+        match self {
+            StringData::ASCII(data) => TypeSignature::StringType(StringSubtype::ASCII(data.len())),
+            StringData::UTF8(data) => TypeSignature::StringType(StringSubtype::UTF8(data.len())),
+        }
+    }
+
+    /// Splits this string into single-character strings of the same kind.
+    pub fn into_chars(self) -> Vec<Value> {
+        match self {
+            StringData::ASCII(data) => data.data.into_iter()
+                .map(|c| Value::String(StringData::ASCII(ASCIIData { data: vec![c] })))
+                .collect(),
+            StringData::UTF8(data) => data.data.into_iter()
+                .map(|c| Value::String(StringData::UTF8(UTF8Data { data: vec![c] })))
+                .collect(),
+        }
+    }
+
+    /// The single-character string at `index`, if any.
+    pub fn element_at(&self, index: usize) -> Option<Value> {
+        match self {
+            StringData::ASCII(data) => data.data.get(index)
+                .map(|c| Value::String(StringData::ASCII(ASCIIData { data: vec![*c] }))),
+            StringData::UTF8(data) => data.data.get(index)
+                .map(|c| Value::String(StringData::UTF8(UTF8Data { data: vec![c.clone()] }))),
+        }
+    }
+
+    /// The characters in `[left, right)`, as a string of the same kind.
+    ///  Callers must ensure that `left <= right <= self.len()`.
+    pub fn slice(&self, left: usize, right: usize) -> StringData {
+        match self {
+            StringData::ASCII(data) => StringData::ASCII(ASCIIData { data: data.data[left..right].to_vec() }),
+            StringData::UTF8(data) => StringData::UTF8(UTF8Data { data: data.data[left..right].to_vec() }),
+        }
+    }
+
+    /// Appends `other` to this string. Both strings must be of the same kind.
+    pub fn append(&mut self, other: StringData) -> Result<()> {
+        match (self, other) {
+            (StringData::ASCII(lhs), StringData::ASCII(mut rhs)) => {
+                lhs.data.append(&mut rhs.data);
+                BufferLength::try_from(lhs.data.len())?;
+            },
+            (StringData::UTF8(lhs), StringData::UTF8(mut rhs)) => {
+                lhs.data.append(&mut rhs.data);
+                StringUTF8Length::try_from(lhs.data.len())?;
+            },
+            (_, _) => return Err(RuntimeErrorType::BadTypeConstruction.into())
+        }
+        Ok(())
+    }
 }
 
 impl BuffData {
@@ -387,6 +519,71 @@ impl fmt::Debug for BuffData {
     }
 }
 
+impl fmt::Display for ASCIIData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a\"")?;
+        for c in self.data.iter() {
+            match *c {
+                b'"' => write!(f, "\\\"")?,
+                b'\\' => write!(f, "\\\\")?,
+                b'\n' => write!(f, "\\n")?,
+                b'\t' => write!(f, "\\t")?,
+                b'\r' => write!(f, "\\r")?,
+                c => write!(f, "{}", c as char)?
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl fmt::Debug for ASCIIData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for UTF8Data {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "u\"")?;
+        for c in self.data.iter() {
+            if c.len() > 1 {
+                // non-ascii characters are rendered as unicode escapes,
+                //   matching the literal syntax accepted by the lexer.
+                let scalar = std::str::from_utf8(c)
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                    .ok_or(fmt::Error)?;
+                write!(f, "\\u{{{:x}}}", scalar as u32)?;
+            } else {
+                match c[0] {
+                    b'"' => write!(f, "\\\"")?,
+                    b'\\' => write!(f, "\\\\")?,
+                    b'\n' => write!(f, "\\n")?,
+                    b'\t' => write!(f, "\\t")?,
+                    b'\r' => write!(f, "\\r")?,
+                    c => write!(f, "{}", c as char)?
+                }
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl fmt::Debug for UTF8Data {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for StringData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StringData::ASCII(data) => write!(f, "{}", data),
+            StringData::UTF8(data) => write!(f, "{}", data),
+        }
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -394,6 +591,7 @@ impl fmt::Display for Value {
             Value::UInt(int) => write!(f, "u{}", int),
             Value::Bool(boolean) => write!(f, "{}", boolean),
             Value::Buffer(vec_bytes) => write!(f, "0x{}", &vec_bytes),
+            Value::String(string_data) => write!(f, "{}", string_data),
             Value::Tuple(data) => write!(f, "{}", data),
             Value::Principal(principal_data) => write!(f, "{}", principal_data),
             Value::Optional(opt_data) => write!(f, "{}", opt_data),
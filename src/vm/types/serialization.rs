@@ -1,7 +1,7 @@
 use vm::errors::{RuntimeErrorType, InterpreterResult, InterpreterError, 
                  IncomparableError, Error as ClarityError, CheckErrors};
 use vm::types::{Value, StandardPrincipalData, OptionalData, PrincipalData, BufferLength, MAX_VALUE_SIZE,
-                BOUND_VALUE_SERIALIZATION_BYTES,
+                BOUND_VALUE_SERIALIZATION_BYTES, StringData, StringSubtype,
                 TypeSignature, TupleData, QualifiedContractIdentifier, ResponseData};
 use vm::database::{ClaritySerializable, ClarityDeserializable};
 use vm::representations::{ClarityName, ContractName, MAX_STRING_LEN};
@@ -88,7 +88,9 @@ define_u8_enum!(TypePrefix {
     OptionalNone = 9,
     OptionalSome = 10,
     List = 11,
-    Tuple = 12
+    Tuple = 12,
+    StringASCII = 13,
+    StringUTF8 = 14
 });
 
 impl From<&PrincipalData> for TypePrefix {
@@ -109,6 +111,8 @@ impl From<&Value> for TypePrefix {
             Int(_) => TypePrefix::Int,
             UInt(_) => TypePrefix::UInt,
             Buffer(_) => TypePrefix::Buffer,
+            String(StringData::ASCII(_)) => TypePrefix::StringASCII,
+            String(StringData::UTF8(_)) => TypePrefix::StringUTF8,
             Bool(value) => {
                 if *value {
                     TypePrefix::BoolTrue
@@ -303,6 +307,61 @@ impl Value {
                 // can safely unwrap, because the buffer length was _already_ checked.
                 Ok(Value::buff_from(data).unwrap())
             },
+            TypePrefix::StringASCII => {
+                let mut buffer_len = [0; 4];
+                r.read_exact(&mut buffer_len)?;
+                let buffer_len = BufferLength::try_from(
+                    u32::from_be_bytes(buffer_len))?;
+
+                if let Some(x) = expected_type {
+                    let passed_test = match x {
+                        TypeSignature::StringType(StringSubtype::ASCII(expected_len)) => {
+                            u32::from(&buffer_len) <= u32::from(expected_len)
+                        },
+                        _ => false
+                    };
+                    if !passed_test {
+                        return Err(SerializationError::DeserializeExpected(x.clone()))
+                    }
+                }
+
+                let mut data = vec![0; u32::from(buffer_len) as usize];
+
+                r.read_exact(&mut data[..])?;
+
+                Value::string_ascii_from_bytes(data)
+                    .map_err(|_| "Illegal string_ascii type".into())
+            },
+            TypePrefix::StringUTF8 => {
+                // the serialized length is the number of bytes in the encoding,
+                //   which bounds the number of characters.
+                let mut total_len = [0; 4];
+                r.read_exact(&mut total_len)?;
+                let total_len = BufferLength::try_from(
+                    u32::from_be_bytes(total_len))?;
+
+                let mut data: Vec<u8> = vec![0; u32::from(total_len) as usize];
+
+                r.read_exact(&mut data[..])?;
+
+                let value = Value::string_utf8_from_bytes(data)
+                    .map_err(|_| "Illegal string_utf8 type")?;
+
+                if let Some(x) = expected_type {
+                    let passed_test = match (x, &value) {
+                        (TypeSignature::StringType(StringSubtype::UTF8(expected_len)),
+                         Value::String(StringData::UTF8(utf8_data))) => {
+                            u32::from(utf8_data.len()) <= u32::from(expected_len)
+                        },
+                        _ => false
+                    };
+                    if !passed_test {
+                        return Err(SerializationError::DeserializeExpected(x.clone()))
+                    }
+                }
+
+                Ok(value)
+            },
             TypePrefix::BoolTrue => {
                 check_match!(expected_type, TypeSignature::BoolType)?;
                 Ok(Bool(true))
@@ -463,7 +522,16 @@ impl Value {
             Buffer(value) => {
                 w.write_all(&(u32::from(value.len()).to_be_bytes()))?;
                 w.write_all(&value.data)?
-            }
+            },
+            String(StringData::ASCII(value)) => {
+                w.write_all(&(u32::from(value.len()).to_be_bytes()))?;
+                w.write_all(&value.data)?
+            },
+            String(StringData::UTF8(value)) => {
+                let encoded = value.to_bytes();
+                w.write_all(&(u32::try_from(encoded.len()).unwrap().to_be_bytes()))?;
+                w.write_all(&encoded)?
+            },
             Principal(Standard(data)) => {
                 data.serialize_write(w)?
             },
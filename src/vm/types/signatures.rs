@@ -44,6 +44,15 @@ pub struct TupleTypeSignature {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BufferLength (u32);
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StringUTF8Length (u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StringSubtype {
+    ASCII(BufferLength),
+    UTF8(StringUTF8Length),
+}
+
 // INVARIANTS enforced by the Type Signatures.
 //   1. A TypeSignature constructor will always fail rather than construct a
 //        type signature for a too large or invalid type. This is why any variable length
@@ -58,6 +67,7 @@ pub enum TypeSignature {
     UIntType,
     BoolType,
     BufferType(BufferLength),
+    StringType(StringSubtype),
     PrincipalType,
     ListType(ListTypeData),
     TupleType(TupleTypeSignature),
@@ -72,6 +82,7 @@ use self::TypeSignature::{
     UIntType, 
     BoolType, 
     BufferType,
+    StringType,
     PrincipalType, 
     ListType, 
     TupleType, 
@@ -80,7 +91,9 @@ use self::TypeSignature::{
     TraitReferenceType
 };
 
+pub const BUFF_65: TypeSignature = BufferType(BufferLength(65));
 pub const BUFF_64: TypeSignature = BufferType(BufferLength(64));
+pub const BUFF_33: TypeSignature = BufferType(BufferLength(33));
 pub const BUFF_32: TypeSignature = BufferType(BufferLength(32));
 pub const BUFF_20: TypeSignature = BufferType(BufferLength(20));
 
@@ -191,6 +204,85 @@ impl TryFrom<i128> for BufferLength {
     }
 }
 
+impl From<&StringUTF8Length> for u32 {
+    fn from(v: &StringUTF8Length) -> u32 {
+        v.0
+    }
+}
+
+impl From<StringUTF8Length> for u32 {
+    fn from(v: StringUTF8Length) -> u32 {
+        v.0
+    }
+}
+
+// UTF-8 strings are sized by their character count: each character
+//   is charged the maximum encoded width of 4 bytes.
+impl TryFrom<u32> for StringUTF8Length {
+    type Error = CheckErrors;
+    fn try_from(data: u32) -> Result<StringUTF8Length> {
+        let len = data.checked_mul(4)
+            .ok_or_else(|| CheckErrors::ValueTooLarge)?;
+        if len > MAX_VALUE_SIZE {
+            Err(CheckErrors::ValueTooLarge)
+        } else {
+            Ok(StringUTF8Length(data))
+        }
+    }
+}
+
+impl TryFrom<usize> for StringUTF8Length {
+    type Error = CheckErrors;
+    fn try_from(data: usize) -> Result<StringUTF8Length> {
+        let len = data.checked_mul(4)
+            .ok_or_else(|| CheckErrors::ValueTooLarge)?;
+        if len > (MAX_VALUE_SIZE as usize) {
+            Err(CheckErrors::ValueTooLarge)
+        } else {
+            Ok(StringUTF8Length(data as u32))
+        }
+    }
+}
+
+impl TryFrom<i128> for StringUTF8Length {
+    type Error = CheckErrors;
+    fn try_from(data: i128) -> Result<StringUTF8Length> {
+        let len = data.checked_mul(4)
+            .ok_or_else(|| CheckErrors::ValueTooLarge)?;
+        if len > (MAX_VALUE_SIZE as i128) {
+            Err(CheckErrors::ValueTooLarge)
+        } else {
+            Ok(StringUTF8Length(data as u32))
+        }
+    }
+}
+
+impl StringSubtype {
+    /// The maximum number of characters admitted by this string type.
+    pub fn get_max_len(&self) -> u32 {
+        match self {
+            StringSubtype::ASCII(len) => len.into(),
+            StringSubtype::UTF8(len) => len.into(),
+        }
+    }
+
+    /// Constructs a string type of the same kind as this one, but with `max_len` characters.
+    pub fn with_max_len(&self, max_len: u32) -> Result<StringSubtype> {
+        match self {
+            StringSubtype::ASCII(_) => Ok(StringSubtype::ASCII(BufferLength::try_from(max_len)?)),
+            StringSubtype::UTF8(_) => Ok(StringSubtype::UTF8(StringUTF8Length::try_from(max_len)?)),
+        }
+    }
+
+    pub fn is_same_kind(&self, other: &StringSubtype) -> bool {
+        match (self, other) {
+            (StringSubtype::ASCII(_), StringSubtype::ASCII(_)) |
+            (StringSubtype::UTF8(_), StringSubtype::UTF8(_)) => true,
+            _ => false
+        }
+    }
+}
+
 impl ListTypeData {
     pub fn new_list(entry_type: TypeSignature, max_len: u32) -> Result<ListTypeData> {
         let would_be_depth = 1 + entry_type.depth();
@@ -330,6 +422,14 @@ impl TypeSignature {
                     false
                 }
             },
+            StringType(ref my_string) => {
+                if let StringType(ref other_string) = other {
+                    my_string.is_same_kind(other_string) &&
+                        my_string.get_max_len() >= other_string.get_max_len()
+                } else {
+                    false
+                }
+            },
             TupleType(ref tuple_sig) => {
                 if let TupleType(ref other_tuple_sig) = other {
                     tuple_sig.admits(other_tuple_sig)
@@ -486,6 +586,12 @@ impl TypeSignature {
         BufferType(1_u32.try_into().unwrap())
     }
 
+    /// The type of a single character of the given string type.
+    pub fn min_string(string_type: &StringSubtype) -> TypeSignature {
+        StringType(string_type.with_max_len(1)
+                   .expect("FAIL: single character string types must be constructible"))
+    }
+
     pub fn max_buffer() -> TypeSignature {
         BufferType(BufferLength(u32::try_from(MAX_VALUE_SIZE)
                                 .expect("FAIL: Max Clarity Value Size is no longer realizable in Buffer Type")))
@@ -520,6 +626,8 @@ impl TypeSignature {
     ///        if some_a | some_b is NoType, use the other type's entry.
     ///  For buffers:
     ///      least_supertype(A, B) := (buff len: max(len A, len B))
+    ///  For strings of the same kind (ascii or utf8):
+    ///      least_supertype(A, B) := (string len: max(len A, len B))
     ///  For ints, uints, principals, bools:
     ///      least_supertype(A, B) := if A != B, error, else A
     ///
@@ -569,6 +677,17 @@ impl TypeSignature {
                 }.clone();
                 Ok(BufferType(buff_len))
             },
+            (StringType(string_a), StringType(string_b)) => {
+                if !string_a.is_same_kind(string_b) {
+                    return Err(CheckErrors::TypeError(a.clone(), b.clone()))
+                }
+                let string_type = if string_a.get_max_len() > string_b.get_max_len() {
+                    string_a
+                } else {
+                    string_b
+                }.clone();
+                Ok(StringType(string_type))
+            },
             (NoType, x) | (x, NoType) => {
                 Ok(x.clone())
             },
@@ -604,6 +723,7 @@ impl TypeSignature {
                     .expect("ERROR: Too large of a buffer successfully constructed.");
                 BufferType(buff_length)
             },
+            Value::String(string_data) => string_data.type_signature(),
             Value::Tuple(v) => TupleType(
                 v.type_signature.clone()),
             Value::List(list_data) => ListType(list_data.type_signature.clone()),
@@ -686,6 +806,27 @@ impl TypeSignature {
         }
     }
 
+    // Parses type signatures of the form:
+    // (string-ascii 10) or (string-utf8 10)
+    fn parse_string_type_repr(type_args: &[SymbolicExpression], utf8: bool) -> Result<TypeSignature> {
+        if type_args.len() != 1 {
+            return Err(CheckErrors::InvalidTypeDescription)
+        }
+        if let SymbolicExpressionType::LiteralValue(Value::Int(str_len)) = &type_args[0].expr {
+            if *str_len < 0 {
+                return Err(CheckErrors::InvalidTypeDescription)
+            }
+            let subtype = if utf8 {
+                StringSubtype::UTF8(StringUTF8Length::try_from(*str_len)?)
+            } else {
+                StringSubtype::ASCII(BufferLength::try_from(*str_len)?)
+            };
+            Ok(TypeSignature::StringType(subtype))
+        } else {
+            Err(CheckErrors::InvalidTypeDescription)
+        }
+    }
+
     fn parse_optional_type_repr<A: CostTracker>(type_args: &[SymbolicExpression], accounting: &mut A) -> Result<TypeSignature> {
         if type_args.len() != 1 {
             return Err(CheckErrors::InvalidTypeDescription)
@@ -719,6 +860,8 @@ impl TypeSignature {
                     match compound_type.as_ref() {
                         "list" => TypeSignature::parse_list_type_repr(rest, accounting),
                         "buff" => TypeSignature::parse_buff_type_repr(rest),
+                        "string-ascii" => TypeSignature::parse_string_type_repr(rest, false),
+                        "string-utf8" => TypeSignature::parse_string_type_repr(rest, true),
                         "tuple" => TypeSignature::parse_tuple_type_repr(rest, accounting),
                         "optional" => TypeSignature::parse_optional_type_repr(rest, accounting),
                         "response" => TypeSignature::parse_response_type_repr(rest, accounting),
@@ -791,7 +934,7 @@ impl TypeSignature {
         match self {
             // NoType's may be asked for their size at runtime --
             //  legal constructions like `(ok 1)` have NoType parts (if they have unknown error variant types).
-            TraitReferenceType(_) | NoType | IntType | UIntType | BoolType | PrincipalType | BufferType(_) => 1,
+            TraitReferenceType(_) | NoType | IntType | UIntType | BoolType | PrincipalType | BufferType(_) | StringType(_) => 1,
             TupleType(tuple_sig) => {
                 1 + tuple_sig.max_depth()
             },
@@ -818,6 +961,9 @@ impl TypeSignature {
             BoolType => Some(1),
             PrincipalType => Some(148), // 20+128
             BufferType(len) => Some(4 + u32::from(len)),
+            StringType(StringSubtype::ASCII(len)) => Some(4 + u32::from(len)),
+            // each utf8 character may take up to 4 bytes
+            StringType(StringSubtype::UTF8(len)) => Some(4 + 4 * u32::from(len)),
             TupleType(tuple_sig) => tuple_sig.inner_size(),
             ListType(list_type) => list_type.inner_size(),
             OptionalType(t) => t.size().checked_add(WRAPPER_VALUE_SIZE),
@@ -847,7 +993,7 @@ impl TypeSignature {
             // These types all only use ~1 byte for their type enum
             NoType | IntType | UIntType | BoolType | PrincipalType => Some(1),
             // u32 length + type enum
-            BufferType(_) => Some(1 + 4),
+            BufferType(_) | StringType(_) => Some(1 + 4),
             TupleType(tuple_sig) => tuple_sig.type_size(),
             ListType(list_type) => list_type.type_size(),
             OptionalType(t) => {
@@ -1011,6 +1157,8 @@ impl fmt::Display for TypeSignature {
             UIntType => write!(f, "uint"),
             BoolType => write!(f, "bool"),
             BufferType(len) => write!(f, "(buff {})", len),
+            StringType(StringSubtype::ASCII(len)) => write!(f, "(string-ascii {})", len),
+            StringType(StringSubtype::UTF8(len)) => write!(f, "(string-utf8 {})", len),
             OptionalType(t) => write!(f, "(optional {})", t),
             ResponseType(v) => write!(f, "(response {} {})", v.0, v.1),
             TupleType(t) => write!(f, "{}", t),
@@ -1027,6 +1175,12 @@ impl fmt::Display for BufferLength {
     }
 }
 
+impl fmt::Display for StringUTF8Length {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl fmt::Display for FunctionArg {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.signature)
@@ -1053,6 +1207,19 @@ mod test {
         assert_eq!(TypeSignature::type_of(&value), type_descr);
     }
 
+    #[test]
+    fn type_of_list_of_strings() {
+        let value = execute("(list a\"abc\" a\"abcde\")").unwrap().unwrap();
+        let type_descr = "(list 2 (string-ascii 5))".into();
+        assert_eq!(TypeSignature::type_of(&value), type_descr);
+
+        let value = execute("(list u\"abc\" u\"\\u{1F600}\")").unwrap().unwrap();
+        let type_descr = "(list 2 (string-utf8 3))".into();
+        assert_eq!(TypeSignature::type_of(&value), type_descr);
+
+        execute("(list a\"abc\" u\"abc\")").unwrap_err();
+    }
+
     #[test]
     fn type_signature_way_too_big() {
         // first_tuple.type_size ~= 131
@@ -1094,6 +1261,11 @@ mod test {
             ("(list 4294967295 (buff 2))", ValueTooLarge),
             ("(list 2147483647 (buff 2))", ValueTooLarge),
             ("(tuple (l (buff 1048576)))", ValueTooLarge),
+            ("(string-ascii)", InvalidTypeDescription),
+            ("(string-ascii -1)", InvalidTypeDescription),
+            ("(string-ascii 1048577)", ValueTooLarge),
+            ("(string-utf8 262145)", ValueTooLarge),
+            ("(string-utf8 u10)", InvalidTypeDescription),
         ];
 
         for (desc, expected) in bad_type_descriptions.iter() {
@@ -1107,6 +1279,9 @@ mod test {
             "(buff 1048576)",
             "(list 4400 bool)",
             "(tuple (l (buff 1048550)))",
+            "(string-ascii 1048576)",
+            "(string-utf8 262144)",
+            "(list 4 (string-utf8 10))",
         ];
 
         for desc in okay_types.iter() {
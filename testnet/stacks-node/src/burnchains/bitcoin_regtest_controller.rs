@@ -37,6 +37,9 @@ use stacks::deps::bitcoin::blockdata::script::{Script, Builder};
 use stacks::deps::bitcoin::network::encodable::ConsensusEncodable;
 use stacks::deps::bitcoin::network::serialize::RawEncoder;
 use stacks::deps::bitcoin::util::hash::Sha256dHash;
+use stacks::chainstate::stacks::{
+    StacksAddress, C32_ADDRESS_VERSION_MAINNET_SINGLESIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG
+};
 use stacks::net::StacksMessageCodec;
 use stacks::util::hash::{Hash160, hex_bytes};
 use stacks::util::secp256k1::Secp256k1PublicKey;
@@ -353,23 +356,30 @@ impl BitcoinRegtestController {
                 .into_script(),
         };
 
-        let burn_address_hash = Hash160([0u8; 20]).as_bytes();
-        let burn_output = TxOut {
-            value: payload.burn_fee,
-            script_pubkey: Builder::new()
-                .push_opcode(opcodes::All::OP_DUP)
-                .push_opcode(opcodes::All::OP_HASH160)
-                .push_slice(burn_address_hash)
-                .push_opcode(opcodes::All::OP_EQUALVERIFY)
-                .push_opcode(opcodes::All::OP_CHECKSIG)
-                .into_script()
-        };
+        tx.output = vec![consensus_output];
 
-        tx.output = vec![consensus_output, burn_output];
+        let total_spent = if payload.commit_outs.len() > 0 {
+            // pay the PoX reward addresses equal shares of the burn fee, and give whatever
+            // doesn't divide evenly to the first one
+            let amount = payload.burn_fee / (payload.commit_outs.len() as u64);
+            let remainder = payload.burn_fee % (payload.commit_outs.len() as u64);
+            for (i, address) in payload.commit_outs.iter().enumerate() {
+                let value = if i == 0 { amount + remainder } else { amount };
+                tx.output.push(BitcoinRegtestController::make_address_output(address, value));
+            }
+            payload.burn_fee
+        } else {
+            let burn_address = StacksAddress {
+                version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                bytes: Hash160([0u8; 20])
+            };
+            tx.output.push(BitcoinRegtestController::make_address_output(&burn_address, payload.burn_fee));
+            payload.burn_fee
+        };
 
         self.finalize_tx(
             &mut tx,
-            payload.burn_fee,
+            total_spent,
             utxos,
             signer)?;
 
@@ -380,6 +390,34 @@ impl BitcoinRegtestController {
         Some(tx)
     }
 
+    /// Make a transaction output that pays the given address -- p2pkh for single-sig addresses,
+    /// and p2sh otherwise.
+    fn make_address_output(address: &StacksAddress, value: u64) -> TxOut {
+        let script_pubkey = match address.version {
+            C32_ADDRESS_VERSION_MAINNET_SINGLESIG | C32_ADDRESS_VERSION_TESTNET_SINGLESIG => {
+                Builder::new()
+                    .push_opcode(opcodes::All::OP_DUP)
+                    .push_opcode(opcodes::All::OP_HASH160)
+                    .push_slice(address.bytes.as_bytes())
+                    .push_opcode(opcodes::All::OP_EQUALVERIFY)
+                    .push_opcode(opcodes::All::OP_CHECKSIG)
+                    .into_script()
+            },
+            _ => {
+                Builder::new()
+                    .push_opcode(opcodes::All::OP_HASH160)
+                    .push_slice(address.bytes.as_bytes())
+                    .push_opcode(opcodes::All::OP_EQUAL)
+                    .into_script()
+            }
+        };
+
+        TxOut {
+            value,
+            script_pubkey
+        }
+    }
+
     fn prepare_tx(&self, public_key: &Secp256k1PublicKey, ops_fee: u64) -> Option<(Transaction, Vec<UTXO>)> {
         
         let tx_fee = self.config.burnchain.burnchain_op_tx_fee;
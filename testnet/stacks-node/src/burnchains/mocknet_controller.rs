@@ -94,7 +94,7 @@ impl BurnchainController for MocknetController {
             Ok(db) => db,
             Err(_) => panic!("Error while connecting to burnchain db")
         };
-        let block_snapshot = SortitionDB::get_canonical_burn_chain_tip_stubbed(db.conn())
+        let block_snapshot = SortitionDB::get_canonical_burn_chain_tip(db.conn())
             .expect("FATAL: failed to get canonical chain tip");
 
         self.db = Some(db);
@@ -152,6 +152,7 @@ impl BurnchainController for MocknetController {
                         memo: payload.memo,
                         burn_fee: payload.burn_fee,
                         input: payload.input,
+                        commit_outs: vec![],
                         txid,
                         vtxindex: vtxindex,
                         block_height: next_block_header.block_height,
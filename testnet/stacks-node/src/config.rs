@@ -14,6 +14,11 @@ use stacks::util::secp256k1::Secp256k1PublicKey;
 use stacks::util::hash::{to_hex, hex_bytes};
 use stacks::vm::types::{PrincipalData, QualifiedContractIdentifier, AssetIdentifier} ;
 use stacks::vm::costs::ExecutionCost;
+use stacks::core::mempool::{
+    MemPoolReplacePolicy, MemPoolLimits,
+    MEMPOOL_MIN_REPLACE_FEE_BUMP, MEMPOOL_MIN_REPLACE_FEE_BUMP_PERCENT,
+    MEMPOOL_MAX_TXS, MEMPOOL_MAX_BYTES
+};
 
 use super::node::TESTNET_CHAIN_ID;
 use super::neon_node::TESTNET_PEER_VERSION;
@@ -263,6 +268,11 @@ impl Config {
                     miner: node.miner.unwrap_or(default_node_config.miner),
                     mine_microblocks: node.mine_microblocks.unwrap_or(default_node_config.mine_microblocks),
                     wait_time_for_microblocks: node.wait_time_for_microblocks.unwrap_or(default_node_config.wait_time_for_microblocks),
+                    mempool_min_fee_bump: node.mempool_min_fee_bump.unwrap_or(default_node_config.mempool_min_fee_bump),
+                    mempool_min_fee_bump_percent: node.mempool_min_fee_bump_percent.unwrap_or(default_node_config.mempool_min_fee_bump_percent),
+                    mempool_max_txs: node.mempool_max_txs.unwrap_or(default_node_config.mempool_max_txs),
+                    mempool_max_bytes: node.mempool_max_bytes.unwrap_or(default_node_config.mempool_max_bytes),
+                    cost_profiling: node.cost_profiling.unwrap_or(default_node_config.cost_profiling),
                     prometheus_bind: node.prometheus_bind,
                 };
                 node_config.set_bootstrap_node(node.bootstrap_node);
@@ -387,6 +397,9 @@ impl Config {
                     dns_timeout: opts.dns_timeout.unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.dns_timeout.clone()),
                     max_inflight_blocks: opts.max_inflight_blocks.unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.max_inflight_blocks.clone()),
                     maximum_call_argument_size: opts.maximum_call_argument_size.unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.maximum_call_argument_size.clone()),
+                    encrypt_p2p: opts.encrypt_p2p.unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.encrypt_p2p.clone()),
+                    compact_blocks: opts.compact_blocks.unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.compact_blocks.clone()),
+                    admin_rpc_token: opts.admin_rpc_token.or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.admin_rpc_token.clone()),
                     ..ConnectionOptions::default() 
                 }
             },
@@ -564,6 +577,11 @@ pub struct NodeConfig {
     pub miner: bool,
     pub mine_microblocks: bool,
     pub wait_time_for_microblocks: u64,
+    pub mempool_min_fee_bump: u64,
+    pub mempool_min_fee_bump_percent: u64,
+    pub mempool_max_txs: u64,
+    pub mempool_max_bytes: u64,
+    pub cost_profiling: bool,
     pub prometheus_bind: Option<String>,
 }
 
@@ -598,10 +616,29 @@ impl NodeConfig {
             miner: false,
             mine_microblocks: false,
             wait_time_for_microblocks: 0,
+            mempool_min_fee_bump: MEMPOOL_MIN_REPLACE_FEE_BUMP,
+            mempool_min_fee_bump_percent: MEMPOOL_MIN_REPLACE_FEE_BUMP_PERCENT,
+            mempool_max_txs: MEMPOOL_MAX_TXS,
+            mempool_max_bytes: MEMPOOL_MAX_BYTES,
+            cost_profiling: false,
             prometheus_bind: None,
         }
     }
 
+    pub fn get_mempool_replace_policy(&self) -> MemPoolReplacePolicy {
+        MemPoolReplacePolicy {
+            min_fee_bump: self.mempool_min_fee_bump,
+            min_fee_bump_percent: self.mempool_min_fee_bump_percent,
+        }
+    }
+
+    pub fn get_mempool_limits(&self) -> MemPoolLimits {
+        MemPoolLimits {
+            max_txs: self.mempool_max_txs,
+            max_bytes: self.mempool_max_bytes,
+        }
+    }
+
     pub fn get_burnchain_path(&self) -> String {
         format!("{}/burnchain", self.working_dir)
     }
@@ -669,6 +706,9 @@ pub struct ConnectionOptionsFile {
     pub read_only_call_limit_read_count: Option<u64>,
     pub read_only_call_limit_runtime: Option<u64>,
     pub maximum_call_argument_size: Option<u32>,
+    pub encrypt_p2p: Option<bool>,
+    pub compact_blocks: Option<bool>,
+    pub admin_rpc_token: Option<String>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -695,6 +735,11 @@ pub struct NodeConfigFile {
     pub miner: Option<bool>,
     pub mine_microblocks: Option<bool>,
     pub wait_time_for_microblocks: Option<u64>,
+    pub mempool_min_fee_bump: Option<u64>,
+    pub mempool_min_fee_bump_percent: Option<u64>,
+    pub mempool_max_txs: Option<u64>,
+    pub mempool_max_bytes: Option<u64>,
+    pub cost_profiling: Option<bool>,
     pub prometheus_bind: Option<String>,
 }
 
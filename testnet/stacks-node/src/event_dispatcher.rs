@@ -1,5 +1,6 @@
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::time::Duration;
 use std::thread::sleep;
 
@@ -17,6 +18,8 @@ use stacks::vm::types::{Value, QualifiedContractIdentifier, AssetIdentifier};
 use stacks::vm::analysis::{contract_interface_builder::build_contract_interface};
 use stacks::util::hash::{bytes_to_hex};
 use stacks::chainstate::stacks::StacksBlockId;
+use stacks::chainstate::stacks::miner::TransactionDropReason;
+use stacks::core::mempool::MemPoolDropReason;
 
 use super::config::{EventObserverConfig, EventKeyType};
 use super::node::{ChainTip};
@@ -31,6 +34,8 @@ const STATUS_RESP_NOT_COMMITTED: &str = "abort_by_response";
 const STATUS_RESP_POST_CONDITION: &str  = "abort_by_post_condition";
 
 pub const PATH_MEMPOOL_TX_SUBMIT: &str = "new_mempool_tx";
+pub const PATH_MEMPOOL_TX_DROP: &str = "drop_mempool_tx";
+pub const PATH_MINED_TX_DROP: &str = "drop_mined_tx";
 pub const PATH_BLOCK_PROCESSED: &str = "new_block";
 
 impl EventObserver {
@@ -104,6 +109,17 @@ impl EventObserver {
         self.send_payload(payload, PATH_MEMPOOL_TX_SUBMIT);
     }
 
+    fn make_dropped_txs_payload(txids: Vec<Txid>, reason: &str) -> serde_json::Value {
+        let dropped_txids: Vec<_> = txids.into_iter().map(|txid| {
+            serde_json::Value::String(format!("0x{}", &txid))
+        }).collect();
+
+        json!({
+            "dropped_txids": serde_json::Value::Array(dropped_txids),
+            "reason": reason,
+        })
+    }
+
     fn send(&mut self, filtered_events: Vec<&(bool, Txid, &StacksTransactionEvent)>, chain_tip: &ChainTip,
             parent_index_hash: &StacksBlockId) {
         // Serialize events to JSON
@@ -156,6 +172,7 @@ impl EventObserver {
                 "raw_result": format!("0x{}", raw_result.join("")),
                 "raw_tx": format!("0x{}", raw_tx.join("")),
                 "contract_abi": contract_interface_json,
+                "cost_profile": receipt.cost_profile,
             });
             tx_index += 1;
             val
@@ -231,12 +248,18 @@ impl EventDispatcher {
                     StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(event_data)) => {
                         self.update_dispatch_matrix_if_observer_subscribed(&event_data.asset_identifier, i, &mut dispatch_matrix);
                     },
+                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(&event_data.asset_identifier, i, &mut dispatch_matrix);
+                    },
                     StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(event_data)) => {
                         self.update_dispatch_matrix_if_observer_subscribed(&event_data.asset_identifier, i, &mut dispatch_matrix);
                     },
                     StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(event_data)) => {
                         self.update_dispatch_matrix_if_observer_subscribed(&event_data.asset_identifier, i, &mut dispatch_matrix);
                     },
+                    StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(event_data)) => {
+                        self.update_dispatch_matrix_if_observer_subscribed(&event_data.asset_identifier, i, &mut dispatch_matrix);
+                    },
                 }
                 events.push((!receipt.post_condition_aborted, tx_hash, event));
                 for o_i in &self.any_event_observers_lookup {
@@ -273,6 +296,44 @@ impl EventDispatcher {
         }
     }
 
+    pub fn process_dropped_mempool_txs(&self, txs: Vec<(Txid, MemPoolDropReason)>) {
+        self.process_dropped_txs(txs, PATH_MEMPOOL_TX_DROP);
+    }
+
+    /// Announce the mempool transactions the miner left out of the block it just built.
+    pub fn process_dropped_mined_txs(&self, txs: Vec<(Txid, TransactionDropReason)>) {
+        self.process_dropped_txs(txs, PATH_MINED_TX_DROP);
+    }
+
+    fn process_dropped_txs<R: fmt::Display>(&self, txs: Vec<(Txid, R)>, path: &str) {
+        // lazily assemble payload only if we have observers
+        let interested_observers: Vec<_> = self.registered_observers.iter().enumerate().filter(
+            |(obs_id, _observer)| {
+                self.mempool_observers_lookup.contains(&(*obs_id as u16)) ||
+                    self.any_event_observers_lookup.contains(&(*obs_id as u16))
+            }).collect();
+        if interested_observers.len() < 1 {
+            return;
+        }
+
+        // one payload per drop reason, in the order the reasons were first seen
+        let mut grouped: Vec<(String, Vec<Txid>)> = vec![];
+        for (txid, reason) in txs.into_iter() {
+            let reason = reason.to_string();
+            match grouped.iter_mut().find(|(r, _)| *r == reason) {
+                Some((_, txids)) => txids.push(txid),
+                None => grouped.push((reason, vec![txid]))
+            }
+        }
+
+        for (reason, txids) in grouped.into_iter() {
+            let payload = EventObserver::make_dropped_txs_payload(txids, &reason);
+            for (_, observer) in interested_observers.iter() {
+                observer.send_payload(&payload, path);
+            }
+        }
+    }
+
     fn update_dispatch_matrix_if_observer_subscribed(&self, asset_identifier: &AssetIdentifier, event_index: usize, dispatch_matrix: &mut Vec<HashSet<usize>>) {
         if let Some(observer_indexes) = self.assets_observers_lookup.get(asset_identifier) {
             for o_i in observer_indexes {
@@ -207,6 +207,7 @@ fn inner_generate_block_commit_op(
     input: BurnchainSigner,
     block_header_hash: BlockHeaderHash,
     burn_fee: u64, 
+    commit_outs: Vec<StacksAddress>,
     key: &RegisteredKey,
     parent_burnchain_height: u32,
     parent_winning_vtx: u16,
@@ -219,6 +220,7 @@ fn inner_generate_block_commit_op(
         block_header_hash,
         burn_fee,
         input,
+        commit_outs,
         key_block_ptr: key.block_height as u32,
         key_vtxindex: key.op_vtxindex as u16,
         memo: vec![],
@@ -253,6 +255,8 @@ fn spawn_peer(mut this: PeerNetwork, p2p_sock: &SocketAddr, rpc_sock: &SocketAdd
     let mut mem_pool = MemPoolDB::open(
         false, TESTNET_CHAIN_ID, &stacks_chainstate_path)
         .map_err(NetError::DBError)?;
+    mem_pool.set_replace_policy(config.node.get_mempool_replace_policy());
+    mem_pool.set_limits(config.node.get_mempool_limits());
 
     // buffer up blocks to store without stalling the p2p thread
     let mut results_with_data = VecDeque::new();
@@ -275,7 +279,7 @@ fn spawn_peer(mut this: PeerNetwork, p2p_sock: &SocketAddr, rpc_sock: &SocketAdd
                 };
 
             // update p2p's read-only view of the unconfirmed state
-            let (canonical_burn_tip, canonical_block_tip) = SortitionDB::get_canonical_stacks_chain_tip_hash_stubbed(sortdb.conn())
+            let (canonical_burn_tip, canonical_block_tip) = SortitionDB::get_canonical_stacks_chain_tip_hash(sortdb.conn())
                 .expect("Failed to read canonical stacks chain tip");
             let canonical_tip = StacksBlockHeader::make_index_block_hash(&canonical_burn_tip, &canonical_block_tip);
             chainstate.refresh_unconfirmed_state_readonly(canonical_tip)
@@ -344,15 +348,23 @@ fn spawn_miner_relayer(mut relayer: Relayer, local_peer: LocalPeer,
     let mut chainstate = StacksChainState::open_with_block_limit(
         false, TESTNET_CHAIN_ID, &stacks_chainstate_path, config.block_limit.clone())
         .map_err(|e| NetError::ChainstateError(e.to_string()))?;
+    chainstate.set_cost_profiling(config.node.cost_profiling);
     
     let mut mem_pool = MemPoolDB::open(
         false, TESTNET_CHAIN_ID, &stacks_chainstate_path)
         .map_err(NetError::DBError)?;
+    mem_pool.set_replace_policy(config.node.get_mempool_replace_policy());
+    mem_pool.set_limits(config.node.get_mempool_limits());
 
     let mut last_mined_block: Option<AssembledAnchorBlock> = None;
     let burn_fee_cap = config.burnchain.burn_fee_cap;
     let mine_microblocks = config.node.mine_microblocks;
 
+    let burnchain = Burnchain::new(
+        &config.get_burn_db_path(),
+        &config.burnchain.chain,
+        "regtest").expect("Error while instantiating burnchain");
+
     let mut bitcoin_controller = BitcoinRegtestController::new_dummy(config);
 
     let blocks_path = chainstate.blocks_path.clone();
@@ -416,6 +428,10 @@ fn spawn_miner_relayer(mut relayer: Relayer, local_peer: LocalPeer,
                     if mempool_txs_added > 0 {
                         event_dispatcher.process_new_mempool_txs(net_receipts.mempool_txs_added);
                     }
+
+                    if net_receipts.mempool_txs_dropped.len() > 0 {
+                        event_dispatcher.process_dropped_mempool_txs(net_receipts.mempool_txs_dropped);
+                    }
                 },
                 RelayerDirective::ProcessTenure(burn_header_hash, parent_burn_header_hash, block_header_hash) => {
                     debug!("Relayer: Process tenure");
@@ -504,8 +520,8 @@ fn spawn_miner_relayer(mut relayer: Relayer, local_peer: LocalPeer,
                 RelayerDirective::RunTenure(registered_key, last_burn_block) => {
                     debug!("Relayer: Run tenure");
                     last_mined_block = InitializedNeonNode::relayer_run_tenure(
-                        registered_key, &mut chainstate, &sortdb, last_burn_block,
-                        &mut keychain, &mut mem_pool, burn_fee_cap, &mut bitcoin_controller);
+                        registered_key, &mut chainstate, &sortdb, &burnchain, last_burn_block,
+                        &mut keychain, &mut mem_pool, burn_fee_cap, &mut bitcoin_controller, &event_dispatcher);
                     bump_processed_counter(&blocks_processed);
                 },
                 RelayerDirective::RegisterKey(ref last_burn_block) => {
@@ -570,7 +586,7 @@ impl InitializedNeonNode {
 
         let view = {
             let ic = sortdb.index_conn();
-            let sortition_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic)
+            let sortition_tip = SortitionDB::get_canonical_burn_chain_tip(&ic)
                 .expect("Failed to get sortition tip");
             ic.get_burnchain_view(&burnchain, &sortition_tip).unwrap()
         };
@@ -612,6 +628,7 @@ impl InitializedNeonNode {
             p2p_sock.port(),
             data_url.clone(),
             &vec![], 
+            &vec![], 
             Some(&initial_neighbors)).unwrap();
 
         let local_peer = match PeerDB::get_local_peer(peerdb.conn()) {
@@ -742,11 +759,13 @@ impl InitializedNeonNode {
     fn relayer_run_tenure(registered_key: RegisteredKey,
                           chain_state: &mut StacksChainState,
                           burn_db: &SortitionDB,
+                          burnchain: &Burnchain,
                           burn_block: BlockSnapshot,
                           keychain: &mut Keychain,
                           mem_pool: &mut MemPoolDB,
                           burn_fee_cap: u64,
-                          bitcoin_controller: &mut BitcoinRegtestController) -> Option<AssembledAnchorBlock> {
+                          bitcoin_controller: &mut BitcoinRegtestController,
+                          event_dispatcher: &EventDispatcher) -> Option<AssembledAnchorBlock> {
         // Generates a proof out of the sortition hash provided in the params.
         let vrf_proof = keychain.generate_proof(
             &registered_key.vrf_public_key, 
@@ -776,7 +795,15 @@ impl InitializedNeonNode {
 
                 // the stacks block I'm mining off of's burn header hash and vtx index:
                 let parent_burn_hash = stacks_tip.burn_header_hash.clone();
-                let parent_sortition_id = SortitionId::stubbed(&parent_burn_hash);
+                let parent_sortition_id = match burn_db.get_sortition_id(&parent_burn_hash)
+                    .expect("SortitionDB failure.") {
+                        Some(x) => x,
+                        None => {
+                            warn!("Failed to find the sortition for the parent burnchain block {}",
+                                  &parent_burn_hash);
+                            return None
+                        }
+                    };
                 let parent_winning_vtxindex =
                     match SortitionDB::get_block_winning_vtxindex(burn_db.conn(), &parent_sortition_id)
                     .expect("SortitionDB failure.") {
@@ -821,9 +848,10 @@ impl InitializedNeonNode {
         
         let coinbase_tx = inner_generate_coinbase_tx(keychain, coinbase_nonce);
 
-        let (anchored_block, consumed_execution, bytes_so_far) = match StacksBlockBuilder::build_anchored_block(
+        let (anchored_block, consumed_execution, bytes_so_far, dropped_txs) = match StacksBlockBuilder::build_anchored_block(
             chain_state, mem_pool, &stacks_parent_header, parent_block_total_burn,
-            vrf_proof.clone(), mblock_pubkey_hash, &coinbase_tx, HELIUM_BLOCK_LIMIT.clone()) {
+            vrf_proof.clone(), mblock_pubkey_hash, &coinbase_tx, HELIUM_BLOCK_LIMIT.clone(),
+            (burn_block.block_height + 1) as u32) {
             Ok(block) => block,
             Err(e) => {
                 error!("Failure mining anchored block: {}", e);
@@ -835,11 +863,29 @@ impl InitializedNeonNode {
               if parent_block_total_burn == 0 { "Genesis" } else { "Stacks" },
               anchored_block.block_hash(), anchored_block.txs.len() );
 
+        for (txid, reason) in dropped_txs.iter() {
+            debug!("Did not mine tx {}: {:?}", txid, reason);
+        }
+        if dropped_txs.len() > 0 {
+            event_dispatcher.process_dropped_mined_txs(dropped_txs);
+        }
+
+        // during a reward phase, the commit pays the PoX reward addresses chosen for the next
+        // burnchain block instead of burning
+        let commit_outs = match burn_db.index_handle(&burn_block.sortition_id).get_pox_recipients(burnchain, burn_block.block_height + 1) {
+            Ok(recipients) => recipients.unwrap_or(vec![]),
+            Err(e) => {
+                error!("Failure determining PoX reward recipients: {:?}", e);
+                return None
+            }
+        };
+
         // let's commit
         let op = inner_generate_block_commit_op(
             keychain.get_burnchain_signer(),
             anchored_block.block_hash(),
             burn_fee_cap,
+            commit_outs,
             &registered_key,
             parent_block_burn_height.try_into()
                 .expect("Could not convert parent block height into u32"),
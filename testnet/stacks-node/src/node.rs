@@ -20,7 +20,7 @@ use stacks::chainstate::burn::operations::{
     LeaderKeyRegisterOp,
     BlockstackOperationType,
 };
-use stacks::core::mempool::MemPoolDB;
+use stacks::core::mempool::{MemPoolDB, MemPoolReplacePolicy, MemPoolLimits};
 use stacks::net::{
     p2p::PeerNetwork, Error as NetError, db::PeerDB, PeerAddress,
     rpc::RPCHandlerArgs
@@ -71,7 +71,8 @@ pub struct Node {
 
 fn spawn_peer(mut this: PeerNetwork, p2p_sock: &SocketAddr, rpc_sock: &SocketAddr,
               burn_db_path: String, stacks_chainstate_path: String, event_dispatcher: EventDispatcher,
-              exit_at_block_height: Option<u64>, poll_timeout: u64) -> Result<JoinHandle<()>, NetError> {
+              exit_at_block_height: Option<u64>, replace_policy: MemPoolReplacePolicy,
+              limits: MemPoolLimits, poll_timeout: u64) -> Result<JoinHandle<()>, NetError> {
     this.bind(p2p_sock, rpc_sock).unwrap();
     let server_thread = thread::spawn(move || {
         let handler_args = RPCHandlerArgs { exit_at_block_height: exit_at_block_height.as_ref(),
@@ -106,6 +107,8 @@ fn spawn_peer(mut this: PeerNetwork, p2p_sock: &SocketAddr, rpc_sock: &SocketAdd
                     continue;
                 }
             };
+            mem_pool.set_replace_policy(replace_policy.clone());
+            mem_pool.set_limits(limits.clone());
 
             let net_result = this.run(&sortdb, &mut chainstate, &mut mem_pool, None,
                                       false, poll_timeout, &handler_args)
@@ -113,6 +116,17 @@ fn spawn_peer(mut this: PeerNetwork, p2p_sock: &SocketAddr, rpc_sock: &SocketAdd
             if net_result.has_transactions() {
                 event_dispatcher.process_new_mempool_txs(net_result.transactions())
             }
+
+            match mem_pool.drain_dropped_txs() {
+                Ok(dropped_txs) => {
+                    if dropped_txs.len() > 0 {
+                        event_dispatcher.process_dropped_mempool_txs(dropped_txs);
+                    }
+                },
+                Err(e) => {
+                    warn!("Failed to load dropped mempool transactions: {}", e);
+                }
+            }
         }
     });
     Ok(server_thread)
@@ -172,7 +186,7 @@ impl Node {
         let chainstate_path = config.get_chainstate_path();
         let sortdb_path = config.get_burn_db_file_path();
 
-        let chain_state = match StacksChainState::open(
+        let mut chain_state = match StacksChainState::open(
             false, 
             TESTNET_CHAIN_ID, 
             &chainstate_path) {
@@ -181,6 +195,7 @@ impl Node {
                 panic!()
             },
         };
+        chain_state.set_cost_profiling(config.node.cost_profiling);
 
         let mut node = Node {
             active_registered_key: None,
@@ -228,7 +243,7 @@ impl Node {
 
         let view = {
             let ic = sortdb.index_conn();
-            let sortition_tip = SortitionDB::get_canonical_burn_chain_tip_stubbed(&ic)
+            let sortition_tip = SortitionDB::get_canonical_burn_chain_tip(&ic)
                 .expect("Failed to get sortition tip");
             ic.get_burnchain_view(&burnchain, &sortition_tip).unwrap()
         };
@@ -271,6 +286,7 @@ impl Node {
             p2p_sock.port(),
             data_url.clone(),
             &vec![], 
+            &vec![], 
             Some(&initial_neighbors)).unwrap();
 
         let local_peer = match PeerDB::get_local_peer(peerdb.conn()) {
@@ -290,6 +306,8 @@ impl Node {
             self.config.get_chainstate_path(),
             event_dispatcher,
             exit_at_block_height,
+            self.config.node.get_mempool_replace_policy(),
+            self.config.node.get_mempool_limits(),
             1000).unwrap();
 
         info!("Bound HTTP server on: {}", &self.config.node.rpc_bind);
@@ -428,7 +446,8 @@ impl Node {
             microblock_secret_key, 
             block_to_build_upon,
             vrf_proof,
-            burn_fee_cap);
+            burn_fee_cap,
+            self.event_dispatcher.clone());
 
         Some(tenure)
     }
@@ -594,6 +613,7 @@ impl Node {
             block_header_hash,
             burn_fee,
             input: self.keychain.get_burnchain_signer(),
+            commit_outs: vec![],
             key_block_ptr: key.block_height as u32,
             key_vtxindex: key.op_vtxindex as u16,
             memo: vec![],
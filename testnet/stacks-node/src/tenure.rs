@@ -1,4 +1,4 @@
-use super::{Config, BurnchainTip};
+use super::{Config, BurnchainTip, EventDispatcher};
 use super::node::{TESTNET_CHAIN_ID, ChainTip};
 
 use std::time::{Instant, Duration};
@@ -30,7 +30,8 @@ pub struct Tenure {
     burn_fee_cap: u64,
     vrf_proof: VRFProof,
     microblock_pubkeyhash: Hash160,
-    parent_block_total_burn: u64
+    parent_block_total_burn: u64,
+    event_dispatcher: EventDispatcher
 }
 
 impl <'a> Tenure {
@@ -42,7 +43,8 @@ impl <'a> Tenure {
                microblock_secret_key: StacksPrivateKey,  
                burnchain_tip: BurnchainTip,
                vrf_proof: VRFProof,
-               burn_fee_cap: u64) -> Tenure {
+               burn_fee_cap: u64,
+               event_dispatcher: EventDispatcher) -> Tenure {
 
         let mut microblock_pubkey = StacksPublicKey::from_private(&microblock_secret_key);
         microblock_pubkey.set_compressed(true);
@@ -60,7 +62,8 @@ impl <'a> Tenure {
             vrf_proof,
             burn_fee_cap,
             microblock_pubkeyhash,
-            parent_block_total_burn
+            parent_block_total_burn,
+            event_dispatcher
         }
     }
 
@@ -81,13 +84,18 @@ impl <'a> Tenure {
             &self.config.get_chainstate_path(),
             self.config.block_limit.clone()).unwrap();
 
-        let (anchored_block, _, _) = StacksBlockBuilder::build_anchored_block(
+        let (anchored_block, _, _, dropped_txs) = StacksBlockBuilder::build_anchored_block(
             &mut chain_state, &mut self.mem_pool, &self.parent_block.metadata,
             self.parent_block_total_burn, self.vrf_proof.clone(), self.microblock_pubkeyhash.clone(),
-            &self.coinbase_tx, self.config.block_limit.clone()).unwrap();
+            &self.coinbase_tx, self.config.block_limit.clone(),
+            (self.burnchain_tip.block_snapshot.block_height + 1) as u32).unwrap();
 
         info!("Finish tenure: {}", anchored_block.block_hash());
 
+        if dropped_txs.len() > 0 {
+            self.event_dispatcher.process_dropped_mined_txs(dropped_txs);
+        }
+
         let artifact = TenureArtifacts {
             anchored_block,
             microblocks: vec![],